@@ -83,20 +83,24 @@ pub use ockam_node::{
 #[cfg(feature = "ockam_transport_tcp")]
 /// TCP transport
 pub mod tcp {
+    #[cfg(unix)]
+    pub use ockam_transport_tcp::UnixInlet;
     pub use ockam_transport_tcp::{
-        TcpConnection, TcpConnectionMode, TcpConnectionOptions, TcpInletOptions, TcpListener,
-        TcpListenerInfo, TcpListenerOptions, TcpOutletOptions, TcpSenderInfo, TcpTransport,
-        TcpTransportExtension, MAX_MESSAGE_SIZE, TCP,
+        TcpConnection, TcpConnectionMode, TcpConnectionOptions, TcpInletConnectionGuard,
+        TcpInletOptions, TcpInletRouteSelector, TcpListener, TcpListenerInfo, TcpListenerOptions,
+        TcpOutletAllowlist, TcpOutletDestinationAuthorizer, TcpOutletOptions, TcpSenderInfo,
+        TcpTransport, TcpTransportExtension, MAX_MESSAGE_SIZE, TCP,
     };
 }
 #[cfg(feature = "ockam_transport_udp")]
 /// UDP transport
 pub mod udp {
     pub use ockam_transport_udp::{
-        RendezvousClient, RendezvousService, UdpBind, UdpBindArguments, UdpBindOptions,
-        UdpPuncture, UdpPunctureNegotiation, UdpPunctureNegotiationListener,
+        RendezvousClient, RendezvousService, UdpBind, UdpBindArguments, UdpBindOptions, UdpInlet,
+        UdpInletOptions, UdpOutlet, UdpOutletOptions, UdpPortalMessage, UdpPuncture,
+        UdpPunctureNegotiation, UdpPunctureNegotiationListener,
         UdpPunctureNegotiationListenerOptions, UdpTransport, UdpTransportExtension,
-        MAX_MESSAGE_SIZE, UDP,
+        DEFAULT_UDP_INLET_MAX_FLOWS, DEFAULT_UDP_PORTAL_IDLE_TIMEOUT, MAX_MESSAGE_SIZE, UDP,
     };
 }
 pub use relay_service::{RelayService, RelayServiceOptions};
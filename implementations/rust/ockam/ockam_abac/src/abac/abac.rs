@@ -56,6 +56,13 @@ impl Abac {
             environment,
         }
     }
+
+    /// Return a copy of this AccessControl where the environment is extended with
+    /// other attributes, for example attributes of the resource known at evaluation time
+    pub fn with_environment(mut self, environment: Env) -> Self {
+        self.environment.merge_right(environment);
+        self
+    }
 }
 
 impl Abac {
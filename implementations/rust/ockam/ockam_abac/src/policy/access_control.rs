@@ -56,6 +56,15 @@ impl PolicyAccessControl {
         }
     }
 
+    /// Return a copy of this access control where the environment used to evaluate the policy
+    /// is extended with other attributes
+    pub fn with_environment(&self, env: Env) -> Self {
+        Self {
+            abac: self.abac.clone().with_environment(env),
+            ..self.clone()
+        }
+    }
+
     pub fn create_incoming(&self) -> IncomingPolicyAccessControl {
         IncomingPolicyAccessControl {
             policy_access_control: self.clone(),
@@ -1,4 +1,4 @@
-use std::net::{SocketAddr, TcpListener};
+use std::net::{SocketAddr, TcpListener, UdpSocket};
 use std::str::FromStr;
 
 use ockam_core::Result;
@@ -58,3 +58,7 @@ pub fn get_free_address() -> Result<SocketAddr, ApiError> {
 pub fn get_free_address_for(ip: &str) -> Result<SocketAddr, ApiError> {
     Ok(TcpListener::bind(format!("{ip}:0"))?.local_addr()?)
 }
+
+pub fn get_free_udp_address_for(ip: &str) -> Result<SocketAddr, ApiError> {
+    Ok(UdpSocket::bind(format!("{ip}:0"))?.local_addr()?)
+}
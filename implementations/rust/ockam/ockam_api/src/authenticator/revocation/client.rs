@@ -0,0 +1,65 @@
+use miette::IntoDiagnostic;
+
+use ockam::identity::models::{CredentialHash, RevocationListAndPurposeKey};
+use ockam::identity::Identifier;
+use ockam_core::api::Request;
+use ockam_core::async_trait;
+use ockam_node::Context;
+
+use crate::nodes::service::default_address::DefaultAddress;
+use crate::orchestrator::{AuthorityNodeClient, HasSecureClient};
+
+#[async_trait]
+pub trait Revocations {
+    async fn revoke_credential(
+        &self,
+        ctx: &Context,
+        credential_hash: CredentialHash,
+    ) -> miette::Result<()>;
+
+    async fn revoke_identifier(&self, ctx: &Context, identifier: Identifier) -> miette::Result<()>;
+
+    async fn get_revocation_list(
+        &self,
+        ctx: &Context,
+    ) -> miette::Result<RevocationListAndPurposeKey>;
+}
+
+#[async_trait]
+impl Revocations for AuthorityNodeClient {
+    async fn revoke_credential(
+        &self,
+        ctx: &Context,
+        credential_hash: CredentialHash,
+    ) -> miette::Result<()> {
+        let req = Request::post("/credentials").body(credential_hash);
+        self.get_secure_client()
+            .tell(ctx, DefaultAddress::REVOCATION_LIST, req)
+            .await
+            .into_diagnostic()?
+            .success()
+            .into_diagnostic()
+    }
+
+    async fn revoke_identifier(&self, ctx: &Context, identifier: Identifier) -> miette::Result<()> {
+        let req = Request::post("/identifiers").body(identifier);
+        self.get_secure_client()
+            .tell(ctx, DefaultAddress::REVOCATION_LIST, req)
+            .await
+            .into_diagnostic()?
+            .success()
+            .into_diagnostic()
+    }
+
+    async fn get_revocation_list(
+        &self,
+        ctx: &Context,
+    ) -> miette::Result<RevocationListAndPurposeKey> {
+        self.get_secure_client()
+            .ask(ctx, DefaultAddress::REVOCATION_LIST, Request::get("/"))
+            .await
+            .into_diagnostic()?
+            .success()
+            .into_diagnostic()
+    }
+}
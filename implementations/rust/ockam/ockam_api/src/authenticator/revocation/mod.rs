@@ -0,0 +1,7 @@
+mod client;
+mod revocation_list_issuer;
+mod revocation_list_issuer_worker;
+
+pub use client::*;
+pub use revocation_list_issuer::*;
+pub use revocation_list_issuer_worker::*;
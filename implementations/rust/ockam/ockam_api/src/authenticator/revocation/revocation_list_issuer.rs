@@ -0,0 +1,124 @@
+use either::Either;
+
+use ockam::identity::models::{CredentialHash, RevocationListAndPurposeKey};
+use ockam::identity::utils::now;
+use ockam::identity::{Credentials, Identifier, IdentitiesAttributes};
+use ockam_core::compat::sync::Arc;
+use ockam_core::Result;
+
+use crate::authenticator::common::EnrollerAccessControlChecks;
+use crate::authenticator::direct::{
+    AccountAuthorityInfo, DirectAuthenticator, DirectAuthenticatorError, DirectAuthenticatorResult,
+};
+use crate::authenticator::{AuthorityMembersRepository, AuthorityRevocationRepository};
+
+/// This struct maintains the revocation list of an Authority:
+///   - enrollers can revoke a credential or all the credentials of an identifier
+///   - anyone can retrieve the list, signed by the Authority
+pub struct RevocationListIssuer {
+    authority: Identifier,
+    revocations: Arc<dyn AuthorityRevocationRepository>,
+    members: Arc<dyn AuthorityMembersRepository>,
+    identities_attributes: Arc<IdentitiesAttributes>,
+    credentials: Arc<Credentials>,
+    account_authority: Option<AccountAuthorityInfo>,
+}
+
+impl RevocationListIssuer {
+    pub fn new(
+        authority: &Identifier,
+        revocations: Arc<dyn AuthorityRevocationRepository>,
+        members: Arc<dyn AuthorityMembersRepository>,
+        identities_attributes: Arc<IdentitiesAttributes>,
+        credentials: Arc<Credentials>,
+        account_authority: Option<AccountAuthorityInfo>,
+    ) -> Self {
+        Self {
+            authority: authority.clone(),
+            revocations,
+            members,
+            identities_attributes,
+            credentials,
+            account_authority,
+        }
+    }
+
+    /// Return the current revocation list, signed by the Authority
+    pub async fn issue_revocation_list(&self) -> Result<RevocationListAndPurposeKey> {
+        let revocations = self.revocations.get_revocations(&self.authority).await?;
+        self.credentials
+            .credentials_creation()
+            .issue_revocation_list(
+                &self.authority,
+                revocations.version(),
+                revocations.credentials,
+                revocations.identifiers,
+            )
+            .await
+    }
+
+    #[instrument(skip_all, fields(enroller = %enroller, credential_hash = %credential_hash))]
+    pub async fn revoke_credential(
+        &self,
+        enroller: &Identifier,
+        credential_hash: &CredentialHash,
+    ) -> Result<DirectAuthenticatorResult<()>> {
+        let check = EnrollerAccessControlChecks::check_identifier(
+            &self.authority,
+            self.members.clone(),
+            self.identities_attributes.clone(),
+            enroller,
+            &self.account_authority,
+        )
+        .await?;
+
+        if !check.is_enroller {
+            warn!(
+                "Non-enroller {} is trying to revoke the credential {}",
+                enroller, credential_hash
+            );
+            return Ok(Either::Right(DirectAuthenticatorError(
+                "Non-enroller is trying to revoke a credential".to_string(),
+            )));
+        }
+
+        self.revocations
+            .revoke_credential(&self.authority, credential_hash, enroller, now()?)
+            .await?;
+
+        info!("Successfully revoked the credential {}", credential_hash);
+
+        Ok(Either::Left(()))
+    }
+
+    /// Revoke all the credentials of an identifier and remove it from the members,
+    /// so that it can't get new credentials
+    #[instrument(skip_all, fields(enroller = %enroller, identifier = %identifier))]
+    pub async fn revoke_identifier(
+        &self,
+        enroller: &Identifier,
+        identifier: &Identifier,
+    ) -> Result<DirectAuthenticatorResult<()>> {
+        // the same rules as deleting a member apply
+        let res = DirectAuthenticator::new(
+            &self.authority,
+            self.members.clone(),
+            self.identities_attributes.clone(),
+            self.account_authority.clone(),
+        )
+        .delete_member(enroller, identifier)
+        .await?;
+
+        if res.is_right() {
+            return Ok(res);
+        }
+
+        self.revocations
+            .revoke_identifier(&self.authority, identifier, enroller, now()?)
+            .await?;
+
+        info!("Successfully revoked the credentials of {}", identifier);
+
+        Ok(Either::Left(()))
+    }
+}
@@ -0,0 +1,102 @@
+use either::Either;
+use minicbor::Decoder;
+use tracing::trace;
+
+use ockam::identity::models::CredentialHash;
+use ockam::identity::{Credentials, Identifier, IdentitiesAttributes};
+use ockam_core::api::{Method, RequestHeader, Response};
+use ockam_core::compat::sync::Arc;
+use ockam_core::{Result, Routed, SecureChannelLocalInfo, Worker};
+use ockam_node::Context;
+
+use crate::authenticator::direct::AccountAuthorityInfo;
+use crate::authenticator::revocation::RevocationListIssuer;
+use crate::authenticator::{AuthorityMembersRepository, AuthorityRevocationRepository};
+
+/// This struct runs as a Worker to serve the revocation list of an Authority
+/// and to let enrollers revoke credentials and identifiers
+pub struct RevocationListIssuerWorker {
+    issuer: RevocationListIssuer,
+}
+
+impl RevocationListIssuerWorker {
+    pub fn new(
+        authority: &Identifier,
+        revocations: Arc<dyn AuthorityRevocationRepository>,
+        members: Arc<dyn AuthorityMembersRepository>,
+        identities_attributes: Arc<IdentitiesAttributes>,
+        credentials: Arc<Credentials>,
+        account_authority: Option<AccountAuthorityInfo>,
+    ) -> Self {
+        Self {
+            issuer: RevocationListIssuer::new(
+                authority,
+                revocations,
+                members,
+                identities_attributes,
+                credentials,
+                account_authority,
+            ),
+        }
+    }
+}
+
+#[ockam_core::worker]
+impl Worker for RevocationListIssuerWorker {
+    type Message = Vec<u8>;
+    type Context = Context;
+
+    async fn handle_message(&mut self, c: &mut Context, m: Routed<Self::Message>) -> Result<()> {
+        let secure_channel_info = match SecureChannelLocalInfo::find_info(m.local_message()) {
+            Ok(secure_channel_info) => secure_channel_info,
+            Err(_e) => {
+                let resp = Response::bad_request_no_request("secure channel required").to_vec()?;
+                c.send(m.return_route().clone(), resp).await?;
+                return Ok(());
+            }
+        };
+
+        let from = Identifier::from(secure_channel_info.their_identifier());
+        let return_route = m.return_route().clone();
+        let body = m.into_body()?;
+        let mut dec = Decoder::new(&body);
+        let req: RequestHeader = dec.decode()?;
+        trace! {
+            target: "revocation_list",
+            from   = %from,
+            id     = %req.id(),
+            method = ?req.method(),
+            path   = %req.path(),
+            body   = %req.has_body(),
+            "request"
+        }
+        let path_segments = req.path_segments::<5>();
+        let res = match (req.method(), path_segments.as_slice()) {
+            (Some(Method::Get), [""]) => match self.issuer.issue_revocation_list().await {
+                Ok(list) => Response::ok().with_headers(&req).body(list).to_vec()?,
+                Err(error) => Response::internal_error(&req, &error.to_string()).to_vec()?,
+            },
+            (Some(Method::Post), ["credentials"]) => {
+                let credential_hash: CredentialHash = dec.decode()?;
+                match self
+                    .issuer
+                    .revoke_credential(&from, &credential_hash)
+                    .await?
+                {
+                    Either::Left(_) => Response::ok().with_headers(&req).to_vec()?,
+                    Either::Right(error) => Response::forbidden(&req, &error.0).to_vec()?,
+                }
+            }
+            (Some(Method::Post), ["identifiers"]) => {
+                let identifier: Identifier = dec.decode()?;
+                match self.issuer.revoke_identifier(&from, &identifier).await? {
+                    Either::Left(_) => Response::ok().with_headers(&req).to_vec()?,
+                    Either::Right(error) => Response::forbidden(&req, &error.0).to_vec()?,
+                }
+            }
+            _ => Response::unknown_path(&req).to_vec()?,
+        };
+
+        c.send(return_route, res).await
+    }
+}
@@ -0,0 +1,80 @@
+use ockam::identity::models::CredentialHash;
+use ockam::identity::{Identifier, TimestampInSeconds};
+use ockam_core::async_trait;
+use ockam_core::compat::boxed::Box;
+use ockam_core::Result;
+use ockam_node::database::AutoRetry;
+use ockam_node::retry;
+
+/// This repository stores the credentials and identifiers revoked by an Authority
+#[async_trait]
+pub trait AuthorityRevocationRepository: Send + Sync + 'static {
+    /// Revoke a credential. Revoking the same credential twice has no effect
+    async fn revoke_credential(
+        &self,
+        authority: &Identifier,
+        credential_hash: &CredentialHash,
+        revoked_by: &Identifier,
+        revoked_at: TimestampInSeconds,
+    ) -> Result<()>;
+
+    /// Revoke all the credentials of an identifier. Revoking the same identifier twice has no effect
+    async fn revoke_identifier(
+        &self,
+        authority: &Identifier,
+        identifier: &Identifier,
+        revoked_by: &Identifier,
+        revoked_at: TimestampInSeconds,
+    ) -> Result<()>;
+
+    /// Return everything revoked by an Authority
+    async fn get_revocations(&self, authority: &Identifier) -> Result<AuthorityRevocations>;
+}
+
+/// Credentials and identifiers revoked by an Authority
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AuthorityRevocations {
+    /// Hashes of the revoked credentials
+    pub credentials: Vec<CredentialHash>,
+    /// Revoked identifiers
+    pub identifiers: Vec<Identifier>,
+}
+
+impl AuthorityRevocations {
+    /// Version of the revocation list. Since revocations are never removed, the number
+    /// of revoked items is incremented each time the list changes
+    pub fn version(&self) -> u64 {
+        (self.credentials.len() + self.identifiers.len()) as u64
+    }
+}
+
+#[async_trait]
+impl<T: AuthorityRevocationRepository> AuthorityRevocationRepository for AutoRetry<T> {
+    async fn revoke_credential(
+        &self,
+        authority: &Identifier,
+        credential_hash: &CredentialHash,
+        revoked_by: &Identifier,
+        revoked_at: TimestampInSeconds,
+    ) -> Result<()> {
+        retry!(self
+            .wrapped
+            .revoke_credential(authority, credential_hash, revoked_by, revoked_at))
+    }
+
+    async fn revoke_identifier(
+        &self,
+        authority: &Identifier,
+        identifier: &Identifier,
+        revoked_by: &Identifier,
+        revoked_at: TimestampInSeconds,
+    ) -> Result<()> {
+        retry!(self
+            .wrapped
+            .revoke_identifier(authority, identifier, revoked_by, revoked_at))
+    }
+
+    async fn get_revocations(&self, authority: &Identifier) -> Result<AuthorityRevocations> {
+        retry!(self.wrapped.get_revocations(authority))
+    }
+}
@@ -0,0 +1,183 @@
+use core::str::FromStr;
+use sqlx::*;
+use std::sync::Arc;
+use tracing::debug;
+
+use crate::authenticator::{AuthorityRevocationRepository, AuthorityRevocations};
+use ockam::identity::models::CredentialHash;
+use ockam::identity::{Identifier, TimestampInSeconds};
+use ockam_core::async_trait;
+use ockam_core::Result;
+use ockam_node::database::AutoRetry;
+use ockam_node::database::{FromSqlxError, SqlxDatabase, ToVoid};
+
+const CREDENTIAL_KIND: &str = "credential";
+const IDENTIFIER_KIND: &str = "identifier";
+
+/// Implementation of [`AuthorityRevocationRepository`] trait based on an underlying database
+/// using sqlx as its API
+#[derive(Clone)]
+pub struct AuthorityRevocationSqlxDatabase {
+    database: SqlxDatabase,
+}
+
+impl AuthorityRevocationSqlxDatabase {
+    /// Create a new database
+    pub fn new(database: SqlxDatabase) -> Self {
+        debug!("create a repository for authority revocations");
+        Self { database }
+    }
+
+    /// Create a repository
+    pub fn make_repository(database: SqlxDatabase) -> Arc<dyn AuthorityRevocationRepository> {
+        if database.needs_retry() {
+            Arc::new(AutoRetry::new(Self::new(database)))
+        } else {
+            Arc::new(Self::new(database))
+        }
+    }
+
+    /// Create a new in-memory database
+    pub async fn create() -> Result<Self> {
+        Ok(Self::new(
+            SqlxDatabase::in_memory("authority revocations").await?,
+        ))
+    }
+
+    async fn revoke(
+        &self,
+        authority: &Identifier,
+        kind: &str,
+        value: String,
+        revoked_by: &Identifier,
+        revoked_at: TimestampInSeconds,
+    ) -> Result<()> {
+        let query = query(
+            r#"
+            INSERT INTO authority_revocation (authority_id, kind, value, revoked_by, revoked_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (authority_id, kind, value)
+            DO NOTHING"#,
+        )
+        .bind(authority)
+        .bind(kind)
+        .bind(value)
+        .bind(revoked_by)
+        .bind(revoked_at);
+        query.execute(&*self.database.pool).await.void()
+    }
+}
+
+#[async_trait]
+impl AuthorityRevocationRepository for AuthorityRevocationSqlxDatabase {
+    async fn revoke_credential(
+        &self,
+        authority: &Identifier,
+        credential_hash: &CredentialHash,
+        revoked_by: &Identifier,
+        revoked_at: TimestampInSeconds,
+    ) -> Result<()> {
+        self.revoke(
+            authority,
+            CREDENTIAL_KIND,
+            credential_hash.to_string(),
+            revoked_by,
+            revoked_at,
+        )
+        .await
+    }
+
+    async fn revoke_identifier(
+        &self,
+        authority: &Identifier,
+        identifier: &Identifier,
+        revoked_by: &Identifier,
+        revoked_at: TimestampInSeconds,
+    ) -> Result<()> {
+        self.revoke(
+            authority,
+            IDENTIFIER_KIND,
+            identifier.to_string(),
+            revoked_by,
+            revoked_at,
+        )
+        .await
+    }
+
+    async fn get_revocations(&self, authority: &Identifier) -> Result<AuthorityRevocations> {
+        let query = query_as(
+            "SELECT kind, value FROM authority_revocation WHERE authority_id = $1 ORDER BY revoked_at, value",
+        )
+        .bind(authority);
+        let rows: Vec<AuthorityRevocationRow> =
+            query.fetch_all(&*self.database.pool).await.into_core()?;
+
+        let mut revocations = AuthorityRevocations::default();
+        for row in rows {
+            match row.kind.as_str() {
+                CREDENTIAL_KIND => revocations
+                    .credentials
+                    .push(CredentialHash::from_str(&row.value)?),
+                _ => revocations
+                    .identifiers
+                    .push(Identifier::from_str(&row.value)?),
+            }
+        }
+        Ok(revocations)
+    }
+}
+
+// Low-level representation of a table row
+#[derive(FromRow)]
+struct AuthorityRevocationRow {
+    kind: String,
+    value: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::identity::models::IDENTIFIER_LEN;
+    use ockam::identity::utils::now;
+    use ockam_node::database::with_dbs;
+
+    #[tokio::test]
+    async fn test_authority_revocation_repository() -> Result<()> {
+        with_dbs(|db| async move {
+            let repository: Arc<dyn AuthorityRevocationRepository> =
+                Arc::new(AuthorityRevocationSqlxDatabase::new(db));
+
+            let authority = Identifier([1u8; IDENTIFIER_LEN]);
+            let other_authority = Identifier([2u8; IDENTIFIER_LEN]);
+            let enroller = Identifier([3u8; IDENTIFIER_LEN]);
+            let member = Identifier([4u8; IDENTIFIER_LEN]);
+            let credential_hash = CredentialHash([5u8; 32]);
+            let now = now()?;
+
+            let revocations = repository.get_revocations(&authority).await?;
+            assert_eq!(revocations.version(), 0);
+
+            repository
+                .revoke_credential(&authority, &credential_hash, &enroller, now)
+                .await?;
+            repository
+                .revoke_identifier(&authority, &member, &enroller, now)
+                .await?;
+            // revoking twice doesn't change the list
+            repository
+                .revoke_identifier(&authority, &member, &enroller, now)
+                .await?;
+
+            let revocations = repository.get_revocations(&authority).await?;
+            assert_eq!(revocations.version(), 2);
+            assert_eq!(revocations.credentials, vec![credential_hash]);
+            assert_eq!(revocations.identifiers, vec![member]);
+
+            let revocations = repository.get_revocations(&other_authority).await?;
+            assert_eq!(revocations, AuthorityRevocations::default());
+
+            Ok(())
+        })
+        .await
+    }
+}
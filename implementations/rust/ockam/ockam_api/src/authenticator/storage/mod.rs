@@ -3,6 +3,8 @@ mod authority_enrollment_token_repository_sql;
 mod authority_member;
 mod authority_members_repository;
 mod authority_members_repository_sql;
+mod authority_revocation_repository;
+mod authority_revocation_repository_sql;
 mod enrollment_token;
 
 pub use authority_enrollment_token_repository::*;
@@ -10,4 +12,6 @@ pub use authority_enrollment_token_repository_sql::*;
 pub use authority_member::*;
 pub use authority_members_repository::*;
 pub use authority_members_repository_sql::*;
+pub use authority_revocation_repository::*;
+pub use authority_revocation_repository_sql::*;
 pub use enrollment_token::*;
@@ -6,9 +6,11 @@ use crate::authenticator::direct::{AccountAuthorityInfo, DirectAuthenticatorWork
 use crate::authenticator::enrollment_tokens::{
     EnrollmentTokenAcceptorWorker, EnrollmentTokenIssuerWorker,
 };
+use crate::authenticator::revocation::RevocationListIssuerWorker;
 use crate::authenticator::{
     AuthorityEnrollmentTokenRepository, AuthorityEnrollmentTokenSqlxDatabase, AuthorityMember,
-    AuthorityMembersRepository, AuthorityMembersSqlxDatabase,
+    AuthorityMembersRepository, AuthorityMembersSqlxDatabase, AuthorityRevocationRepository,
+    AuthorityRevocationSqlxDatabase,
 };
 use ockam::identity::utils::now;
 use ockam::identity::{
@@ -35,12 +37,14 @@ use crate::nodes::service::default_address::DefaultAddress;
 //   - a credential issuer: return the attributes of a member as a time-limited credential.
 //   - an enrollment token issuer: create a token attributed allowing an identity to acquire some specific attributes.
 //   - an enrollment token acceptor: create or update a member, given a token.
+//   - a revocation list service: revoke credentials and identifiers, and return the signed list of revocations.
 #[derive(Clone)]
 pub struct Authority {
     identifier: Identifier,
     secure_channels: Arc<SecureChannels>,
     members: Arc<dyn AuthorityMembersRepository>,
     tokens: Arc<dyn AuthorityEnrollmentTokenRepository>,
+    revocations: Arc<dyn AuthorityRevocationRepository>,
     account_authority: Option<AccountAuthorityInfo>,
 }
 
@@ -77,6 +81,7 @@ impl Authority {
 
         let members = AuthorityMembersSqlxDatabase::make_repository(database.clone());
         let tokens = AuthorityEnrollmentTokenSqlxDatabase::make_repository(database.clone());
+        let revocations = AuthorityRevocationSqlxDatabase::make_repository(database.clone());
         let secure_channel_repository =
             SecureChannelSqlxDatabase::make_repository(database.clone());
 
@@ -109,6 +114,7 @@ impl Authority {
             secure_channels,
             members,
             tokens,
+            revocations,
             account_authority,
         })
     }
@@ -270,6 +276,32 @@ impl Authority {
         Ok(())
     }
 
+    /// Start the revocation list service, to revoke credentials and distribute the list
+    /// of revoked credentials to the verifiers
+    pub fn start_revocation_list_service(
+        &self,
+        ctx: &Context,
+        secure_channel_flow_control_id: &FlowControlId,
+    ) -> Result<()> {
+        let revocation_list = RevocationListIssuerWorker::new(
+            &self.identifier,
+            self.revocations.clone(),
+            self.members.clone(),
+            self.secure_channels.identities().identities_attributes(),
+            self.secure_channels.identities().credentials(),
+            self.account_authority.clone(),
+        );
+
+        let address = DefaultAddress::REVOCATION_LIST.to_string();
+        ctx.flow_controls()
+            .add_consumer(&address.clone().into(), secure_channel_flow_control_id);
+
+        ctx.start_worker(address.clone(), revocation_list)?;
+
+        info!("started a revocation list service at '{address}'");
+        Ok(())
+    }
+
     /// Start the Okta service to retrieve attributes authenticated by Okta
     pub fn start_okta(
         &self,
@@ -28,6 +28,9 @@ pub async fn start_node(
     authority.start_credential_issuer(ctx, &secure_channel_flow_control_id, configuration)?;
     debug!("credential issuer started");
 
+    authority.start_revocation_list_service(ctx, &secure_channel_flow_control_id)?;
+    debug!("revocation list service started");
+
     // start the Okta service (if the optional configuration has been provided)
     authority.start_okta(ctx, &secure_channel_flow_control_id, configuration)?;
     debug!("okta service started");
@@ -7,7 +7,7 @@ use ockam_core::env::get_env_with_default;
 use ockam_node::database::{DatabaseConfiguration, DatabaseType};
 
 use crate::cli_state::error::Result;
-use crate::cli_state::CliStateError;
+use crate::cli_state::{CliStateError, VaultPassphrases};
 use crate::logs::ExportingEnabled;
 use crate::terminal::notification::Notification;
 
@@ -47,6 +47,8 @@ pub struct CliState {
     exporting_enabled: ExportingEnabled,
     /// Broadcast channel to be notified of major events during a process supported by the CliState API
     notifications: Sender<Notification>,
+    /// Passphrases of the encrypted vaults used by this process
+    vault_passphrases: VaultPassphrases,
 }
 
 impl CliState {
@@ -65,6 +67,10 @@ impl CliState {
         &self.database
     }
 
+    pub(super) fn vault_passphrases(&self) -> &VaultPassphrases {
+        &self.vault_passphrases
+    }
+
     pub fn database_configuration(&self) -> Result<DatabaseConfiguration> {
         Self::make_database_configuration(&self.mode)
     }
@@ -252,6 +258,7 @@ impl CliState {
             // is eventually used to trace user journeys.
             exporting_enabled: ExportingEnabled::Off,
             notifications,
+            vault_passphrases: VaultPassphrases::default(),
         };
 
         Ok(state)
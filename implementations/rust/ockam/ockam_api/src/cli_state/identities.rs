@@ -1,9 +1,9 @@
 use colorful::Colorful;
 use ockam::identity::models::ChangeHistory;
-use ockam::identity::{Identifier, Identity};
+use ockam::identity::{Identifier, Identity, RecoveryKey};
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::Error;
-use ockam_vault::{HandleToSecret, SigningSecretKeyHandle};
+use ockam_vault::{HandleToSecret, SigningSecretKeyHandle, VerifyingPublicKey};
 
 use crate::cli_state::{random_name, CliState, Result};
 use crate::colors::color_primary;
@@ -109,6 +109,64 @@ impl CliState {
         self.store_named_identity(&identifier, name, vault_name)
             .await
     }
+
+    /// Create an identity committing the hash of an offline recovery key.
+    /// That recovery key can later be used to recover the identity if its vault is lost
+    #[instrument(skip_all, fields(name = %name, vault_name = %vault_name))]
+    pub async fn create_identity_with_recovery_key(
+        &self,
+        name: &str,
+        vault_name: &str,
+        recovery_public_key: VerifyingPublicKey,
+    ) -> Result<NamedIdentity> {
+        let vault = self.get_named_vault(vault_name).await?;
+        let identities = self.make_identities(self.make_vault(vault).await?).await?;
+        let identifier = identities
+            .identities_creation()
+            .identity_builder()
+            .with_recovery_public_key(recovery_public_key)
+            .build()
+            .await?;
+
+        self.store_named_identity(&identifier, name, vault_name)
+            .await
+    }
+
+    /// Recover an identity from its change history and the recovery key committed in its last change.
+    /// A new key is generated in the vault and the identity is rotated with a change signed by the
+    /// recovery key, so that the identifier stays the same.
+    /// A new recovery key can be committed in that change
+    #[instrument(skip_all, fields(name = %name, vault_name = %vault_name))]
+    pub async fn recover_identity(
+        &self,
+        name: &str,
+        vault_name: &str,
+        change_history: ChangeHistory,
+        recovery_key: RecoveryKey,
+        new_recovery_public_key: Option<VerifyingPublicKey>,
+    ) -> Result<NamedIdentity> {
+        let vault = self.get_named_vault(vault_name).await?;
+        let identities = self.make_identities(self.make_vault(vault).await?).await?;
+        let identifier = identities
+            .identities_verification()
+            .import_from_change_history(None, change_history)
+            .await?;
+
+        let identities_creation = identities.identities_creation();
+        let mut builder = identities_creation
+            .identity_builder()
+            .with_recovery_key(recovery_key);
+        if let Some(new_recovery_public_key) = new_recovery_public_key {
+            builder = builder.with_recovery_public_key(new_recovery_public_key);
+        }
+        let options = builder.build_options().await?;
+        identities_creation
+            .rotate_identity_with_options(&identifier, options)
+            .await?;
+
+        self.store_named_identity(&identifier, name, vault_name)
+            .await
+    }
 }
 
 /// The methods below allow to query identities:
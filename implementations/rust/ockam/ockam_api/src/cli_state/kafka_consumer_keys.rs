@@ -0,0 +1,277 @@
+use super::Result;
+use crate::cli_state::KafkaConsumerKey;
+use crate::CliState;
+use ockam::identity::utils::now;
+use ockam::identity::{Identifier, TimestampInSeconds};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::Address;
+use ockam_vault::storage::SecretsRepository;
+use ockam_vault::{AeadSecret, AeadSecretKeyHandle, HandleToSecret, AEAD_SECRET_LENGTH};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Key of a Kafka consumer, exported with its secret to be imported by another consumer
+/// node of the same consumer group
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedKafkaConsumerKey {
+    pub decryptor_address: String,
+    pub producer_identifier: Identifier,
+    #[serde(with = "hex")]
+    pub secret: Vec<u8>,
+    pub last_used_at: u64,
+}
+
+impl CliState {
+    /// Keep a copy of the key of a key exchange only secure channel used to decrypt Kafka records,
+    /// so that those records can still be decrypted once the secure channel is closed.
+    /// If the key is already stored, only its last usage time is updated
+    #[instrument(skip_all, fields(node_name = node_name, decryptor_address = %decryptor_address))]
+    pub async fn store_kafka_consumer_key(
+        &self,
+        node_name: &str,
+        decryptor_address: &Address,
+        producer_identifier: &Identifier,
+        secure_channel_key: &AeadSecretKeyHandle,
+    ) -> Result<()> {
+        let repository = self.kafka_consumer_keys_repository();
+        let now = now()?;
+        let key = match repository
+            .get_kafka_consumer_key(node_name, decryptor_address)
+            .await?
+        {
+            Some(key) => key.with_last_used_at(now),
+            None => {
+                let secrets = self.node_secrets_repository(node_name).await?;
+                let secret = secrets
+                    .get_aead_secret(secure_channel_key)
+                    .await?
+                    .ok_or_else(|| {
+                        ockam_core::Error::new(
+                            Origin::Api,
+                            Kind::NotFound,
+                            format!("no key found for the secure channel {decryptor_address}"),
+                        )
+                    })?;
+                let key_handle = Self::make_kafka_consumer_key_handle();
+                secrets.store_aead_secret(&key_handle, secret).await?;
+                KafkaConsumerKey::new(
+                    decryptor_address.clone(),
+                    producer_identifier.clone(),
+                    key_handle,
+                    now,
+                )
+            }
+        };
+        Ok(repository.store_kafka_consumer_key(node_name, &key).await?)
+    }
+
+    /// Return the key of a Kafka consumer for a given decryptor address
+    #[instrument(skip_all, fields(node_name = node_name, decryptor_address = %decryptor_address))]
+    pub async fn get_kafka_consumer_key(
+        &self,
+        node_name: &str,
+        decryptor_address: &Address,
+    ) -> Result<Option<KafkaConsumerKey>> {
+        Ok(self
+            .kafka_consumer_keys_repository()
+            .get_kafka_consumer_key(node_name, decryptor_address)
+            .await?)
+    }
+
+    /// Delete the keys of a Kafka consumer which were not used during the retention period.
+    /// The records encrypted with those keys have already been deleted from their topics.
+    /// Return the number of deleted keys
+    #[instrument(skip_all, fields(node_name = node_name))]
+    pub async fn delete_expired_kafka_consumer_keys(
+        &self,
+        node_name: &str,
+        retention: Duration,
+    ) -> Result<usize> {
+        let repository = self.kafka_consumer_keys_repository();
+        let expiration = now()?.0.saturating_sub(retention.as_secs());
+        let expired: Vec<KafkaConsumerKey> = repository
+            .get_kafka_consumer_keys(node_name)
+            .await?
+            .into_iter()
+            .filter(|k| k.last_used_at().0 < expiration)
+            .collect();
+        if expired.is_empty() {
+            return Ok(0);
+        }
+
+        let secrets = self.node_secrets_repository(node_name).await?;
+        for key in expired.iter() {
+            repository
+                .delete_kafka_consumer_key(node_name, key.decryptor_address())
+                .await?;
+            secrets.delete_aead_secret(key.key_handle()).await?;
+        }
+        Ok(expired.len())
+    }
+
+    /// Export the keys of a Kafka consumer, with their secrets
+    #[instrument(skip_all, fields(node_name = node_name))]
+    pub async fn export_kafka_consumer_keys(
+        &self,
+        node_name: &str,
+    ) -> Result<Vec<ExportedKafkaConsumerKey>> {
+        let keys = self
+            .kafka_consumer_keys_repository()
+            .get_kafka_consumer_keys(node_name)
+            .await?;
+        let secrets = self.node_secrets_repository(node_name).await?;
+
+        let mut exported = vec![];
+        for key in keys {
+            match secrets.get_aead_secret(key.key_handle()).await? {
+                Some(secret) => exported.push(ExportedKafkaConsumerKey {
+                    decryptor_address: key.decryptor_address().to_string(),
+                    producer_identifier: key.producer_identifier().clone(),
+                    secret: secret.0.to_vec(),
+                    last_used_at: key.last_used_at().0,
+                }),
+                None => warn!(
+                    "the secret of the Kafka consumer key {} is missing",
+                    key.decryptor_address()
+                ),
+            }
+        }
+        Ok(exported)
+    }
+
+    /// Import the keys exported by another Kafka consumer node.
+    /// The keys which are already known by the node are skipped.
+    /// Return the number of imported keys
+    #[instrument(skip_all, fields(node_name = node_name))]
+    pub async fn import_kafka_consumer_keys(
+        &self,
+        node_name: &str,
+        keys: Vec<ExportedKafkaConsumerKey>,
+    ) -> Result<usize> {
+        let repository = self.kafka_consumer_keys_repository();
+        let secrets = self.node_secrets_repository(node_name).await?;
+
+        let mut imported = 0;
+        for key in keys {
+            let decryptor_address = Address::from_string(&key.decryptor_address);
+            if repository
+                .get_kafka_consumer_key(node_name, &decryptor_address)
+                .await?
+                .is_some()
+            {
+                continue;
+            }
+            let secret: [u8; AEAD_SECRET_LENGTH] = key.secret.try_into().map_err(|_| {
+                ockam_core::Error::new(
+                    Origin::Api,
+                    Kind::Invalid,
+                    format!("invalid secret for the Kafka consumer key {decryptor_address}"),
+                )
+            })?;
+
+            let key_handle = Self::make_kafka_consumer_key_handle();
+            secrets
+                .store_aead_secret(&key_handle, AeadSecret(secret))
+                .await?;
+            repository
+                .store_kafka_consumer_key(
+                    node_name,
+                    &KafkaConsumerKey::new(
+                        decryptor_address,
+                        key.producer_identifier,
+                        key_handle,
+                        TimestampInSeconds(key.last_used_at),
+                    ),
+                )
+                .await?;
+            imported += 1;
+        }
+        Ok(imported)
+    }
+
+    /// Return the secrets of the vault used by a node
+    async fn node_secrets_repository(&self, node_name: &str) -> Result<Arc<dyn SecretsRepository>> {
+        let named_vault = self.get_node_vault(node_name).await?;
+        Ok(self
+            .make_secrets_database(&named_vault)
+            .await?
+            .into_repository())
+    }
+
+    fn make_kafka_consumer_key_handle() -> AeadSecretKeyHandle {
+        AeadSecretKeyHandle::new(HandleToSecret::new(rand::random::<[u8; 16]>().to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_export_and_import_kafka_consumer_keys() -> Result<()> {
+        let cli = CliState::test().await?;
+        let producer = cli
+            .create_identity_with_name("producer")
+            .await?
+            .identifier();
+        cli.create_node("consumer1").await?;
+        cli.create_node("consumer2").await?;
+
+        // create a secure channel key in the vault of the first node
+        let vault = cli
+            .make_vault(cli.get_node_vault("consumer1").await?)
+            .await?
+            .secure_channel_vault;
+        let buffer = vault
+            .import_secret_buffer(vec![7; AEAD_SECRET_LENGTH])
+            .await?;
+        let channel_key = vault.convert_secret_buffer_to_aead_key(buffer).await?;
+        vault.persist_aead_key(&channel_key).await?;
+
+        let decryptor_address = Address::from_string("decryptor");
+        cli.store_kafka_consumer_key("consumer1", &decryptor_address, &producer, &channel_key)
+            .await?;
+
+        // the key is kept when the secure channel key is deleted
+        vault.delete_persisted_aead_key(&channel_key).await?;
+        let key = cli
+            .get_kafka_consumer_key("consumer1", &decryptor_address)
+            .await?
+            .unwrap();
+        assert_eq!(key.producer_identifier(), &producer);
+
+        let exported = cli.export_kafka_consumer_keys("consumer1").await?;
+        assert_eq!(exported.len(), 1);
+        assert_eq!(exported[0].secret, vec![7; AEAD_SECRET_LENGTH]);
+
+        assert_eq!(
+            cli.import_kafka_consumer_keys("consumer2", exported.clone())
+                .await?,
+            1
+        );
+        // keys are only imported once
+        assert_eq!(
+            cli.import_kafka_consumer_keys("consumer2", exported.clone())
+                .await?,
+            0
+        );
+        assert_eq!(cli.export_kafka_consumer_keys("consumer2").await?, exported);
+
+        // the keys which were not used during the retention period are deleted
+        let old_key = ExportedKafkaConsumerKey {
+            decryptor_address: "old_decryptor".to_string(),
+            last_used_at: 1,
+            ..exported[0].clone()
+        };
+        cli.import_kafka_consumer_keys("consumer2", vec![old_key])
+            .await?;
+        assert_eq!(
+            cli.delete_expired_kafka_consumer_keys("consumer2", Duration::from_secs(3600))
+                .await?,
+            1
+        );
+        assert_eq!(cli.export_kafka_consumer_keys("consumer2").await?, exported);
+        Ok(())
+    }
+}
@@ -2,6 +2,7 @@ pub use cli_state::*;
 pub use enrollments::*;
 pub use error::*;
 pub use identities::*;
+pub use kafka_consumer_keys::*;
 pub use nodes::*;
 pub use storage::*;
 pub use vaults::*;
@@ -13,6 +14,7 @@ pub mod error;
 pub mod identities;
 mod identities_attributes;
 pub mod journeys;
+pub mod kafka_consumer_keys;
 pub mod nodes;
 pub mod policies;
 pub mod projects;
@@ -48,6 +48,10 @@ impl CliState {
         TcpPortalsSqlxDatabase::make_repository(self.database())
     }
 
+    pub(super) fn kafka_consumer_keys_repository(&self) -> Arc<dyn KafkaConsumerKeysRepository> {
+        KafkaConsumerKeysSqlxDatabase::make_repository(self.database())
+    }
+
     pub(super) fn projects_repository(&self) -> Arc<dyn ProjectsRepository> {
         ProjectsSqlxDatabase::make_repository(self.database())
     }
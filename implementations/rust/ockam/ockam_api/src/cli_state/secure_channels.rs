@@ -15,7 +15,7 @@ impl CliState {
             .build();
         Ok(SecureChannels::from_identities(
             identities,
-            SecureChannelSqlxDatabase::make_repository(self.database()),
+            SecureChannelSqlxDatabase::make_repository_for_node(self.database(), node_name),
         ))
     }
 }
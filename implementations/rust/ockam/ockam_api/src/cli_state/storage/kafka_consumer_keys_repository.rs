@@ -0,0 +1,119 @@
+use ockam::identity::{Identifier, TimestampInSeconds};
+use ockam_core::Result;
+use ockam_core::{async_trait, Address};
+use ockam_node::database::AutoRetry;
+use ockam_node::retry;
+use ockam_vault::AeadSecretKeyHandle;
+
+/// The KafkaConsumerKeysRepository stores the keys used by Kafka consumers to decrypt records.
+/// Those keys are kept after the secure channels used to exchange them are closed
+#[async_trait]
+pub trait KafkaConsumerKeysRepository: Send + Sync + 'static {
+    /// Store a key for a given node name.
+    /// If a key is already stored for the same decryptor address, only its last usage time is updated
+    async fn store_kafka_consumer_key(&self, node_name: &str, key: &KafkaConsumerKey)
+        -> Result<()>;
+
+    /// Return the key associated to a decryptor address for a given node name
+    async fn get_kafka_consumer_key(
+        &self,
+        node_name: &str,
+        decryptor_address: &Address,
+    ) -> Result<Option<KafkaConsumerKey>>;
+
+    /// Return all the keys of a given node name
+    async fn get_kafka_consumer_keys(&self, node_name: &str) -> Result<Vec<KafkaConsumerKey>>;
+
+    /// Delete the key associated to a decryptor address for a given node name
+    async fn delete_kafka_consumer_key(
+        &self,
+        node_name: &str,
+        decryptor_address: &Address,
+    ) -> Result<()>;
+}
+
+#[async_trait]
+impl<T: KafkaConsumerKeysRepository> KafkaConsumerKeysRepository for AutoRetry<T> {
+    async fn store_kafka_consumer_key(
+        &self,
+        node_name: &str,
+        key: &KafkaConsumerKey,
+    ) -> Result<()> {
+        retry!(self.wrapped.store_kafka_consumer_key(node_name, key))
+    }
+
+    async fn get_kafka_consumer_key(
+        &self,
+        node_name: &str,
+        decryptor_address: &Address,
+    ) -> Result<Option<KafkaConsumerKey>> {
+        retry!(self
+            .wrapped
+            .get_kafka_consumer_key(node_name, decryptor_address))
+    }
+
+    async fn get_kafka_consumer_keys(&self, node_name: &str) -> Result<Vec<KafkaConsumerKey>> {
+        retry!(self.wrapped.get_kafka_consumer_keys(node_name))
+    }
+
+    async fn delete_kafka_consumer_key(
+        &self,
+        node_name: &str,
+        decryptor_address: &Address,
+    ) -> Result<()> {
+        retry!(self
+            .wrapped
+            .delete_kafka_consumer_key(node_name, decryptor_address))
+    }
+}
+
+/// Key used by a Kafka consumer to decrypt the records encrypted by a producer.
+/// The secret itself is stored in the vault of the node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KafkaConsumerKey {
+    decryptor_address: Address,
+    producer_identifier: Identifier,
+    key_handle: AeadSecretKeyHandle,
+    last_used_at: TimestampInSeconds,
+}
+
+impl KafkaConsumerKey {
+    pub fn new(
+        decryptor_address: Address,
+        producer_identifier: Identifier,
+        key_handle: AeadSecretKeyHandle,
+        last_used_at: TimestampInSeconds,
+    ) -> Self {
+        Self {
+            decryptor_address,
+            producer_identifier,
+            key_handle,
+            last_used_at,
+        }
+    }
+
+    /// Decryptor address sent by the producer with each encrypted record
+    pub fn decryptor_address(&self) -> &Address {
+        &self.decryptor_address
+    }
+
+    /// Identifier of the producer which encrypted the records
+    pub fn producer_identifier(&self) -> &Identifier {
+        &self.producer_identifier
+    }
+
+    /// Handle of the key in the vault of the node
+    pub fn key_handle(&self) -> &AeadSecretKeyHandle {
+        &self.key_handle
+    }
+
+    /// Last time the key was used
+    pub fn last_used_at(&self) -> TimestampInSeconds {
+        self.last_used_at
+    }
+
+    pub fn with_last_used_at(mut self, last_used_at: TimestampInSeconds) -> Self {
+        self.last_used_at = last_used_at;
+        self
+    }
+}
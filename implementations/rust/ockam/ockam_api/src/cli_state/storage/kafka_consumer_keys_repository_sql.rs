@@ -0,0 +1,185 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use sqlx::*;
+use tracing::debug;
+
+use crate::cli_state::storage::kafka_consumer_keys_repository::{
+    KafkaConsumerKey, KafkaConsumerKeysRepository,
+};
+use ockam::identity::{Identifier, TimestampInSeconds};
+use ockam::{FromSqlxError, SqlxDatabase, ToVoid};
+use ockam_core::Result;
+use ockam_core::{async_trait, Address};
+use ockam_node::database::AutoRetry;
+use ockam_vault::{AeadSecretKeyHandle, HandleToSecret};
+
+#[derive(Clone)]
+pub struct KafkaConsumerKeysSqlxDatabase {
+    database: SqlxDatabase,
+}
+
+impl KafkaConsumerKeysSqlxDatabase {
+    /// Create a new database
+    pub fn new(database: SqlxDatabase) -> Self {
+        debug!("create a repository for kafka consumer keys");
+        Self { database }
+    }
+
+    /// Create a repository
+    pub fn make_repository(database: SqlxDatabase) -> Arc<dyn KafkaConsumerKeysRepository> {
+        if database.needs_retry() {
+            Arc::new(AutoRetry::new(Self::new(database)))
+        } else {
+            Arc::new(Self::new(database))
+        }
+    }
+
+    /// Create a new in-memory database
+    #[allow(unused)]
+    pub async fn create() -> Result<Arc<Self>> {
+        Ok(Arc::new(Self::new(
+            SqlxDatabase::in_memory("kafka consumer keys").await?,
+        )))
+    }
+}
+
+#[async_trait]
+impl KafkaConsumerKeysRepository for KafkaConsumerKeysSqlxDatabase {
+    async fn store_kafka_consumer_key(
+        &self,
+        node_name: &str,
+        key: &KafkaConsumerKey,
+    ) -> Result<()> {
+        let query = query(
+            r#"
+            INSERT INTO kafka_consumer_key (node_name, decryptor_address, producer_identifier, key_handle, last_used_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (node_name, decryptor_address)
+            DO UPDATE SET last_used_at = $5"#,
+        )
+        .bind(node_name)
+        .bind(key.decryptor_address().to_string())
+        .bind(key.producer_identifier())
+        .bind(key.key_handle())
+        .bind(key.last_used_at());
+        query.execute(&*self.database.pool).await.void()
+    }
+
+    async fn get_kafka_consumer_key(
+        &self,
+        node_name: &str,
+        decryptor_address: &Address,
+    ) -> Result<Option<KafkaConsumerKey>> {
+        let query = query_as(
+            "SELECT decryptor_address, producer_identifier, key_handle, last_used_at FROM kafka_consumer_key WHERE node_name = $1 AND decryptor_address = $2",
+        )
+        .bind(node_name)
+        .bind(decryptor_address.to_string());
+        let row: Option<KafkaConsumerKeyRow> = query
+            .fetch_optional(&*self.database.pool)
+            .await
+            .into_core()?;
+        row.map(|r| r.kafka_consumer_key()).transpose()
+    }
+
+    async fn get_kafka_consumer_keys(&self, node_name: &str) -> Result<Vec<KafkaConsumerKey>> {
+        let query = query_as(
+            "SELECT decryptor_address, producer_identifier, key_handle, last_used_at FROM kafka_consumer_key WHERE node_name = $1 ORDER BY last_used_at",
+        )
+        .bind(node_name);
+        let rows: Vec<KafkaConsumerKeyRow> =
+            query.fetch_all(&*self.database.pool).await.into_core()?;
+        rows.iter().map(|r| r.kafka_consumer_key()).collect()
+    }
+
+    async fn delete_kafka_consumer_key(
+        &self,
+        node_name: &str,
+        decryptor_address: &Address,
+    ) -> Result<()> {
+        let query =
+            query("DELETE FROM kafka_consumer_key WHERE node_name = $1 AND decryptor_address = $2")
+                .bind(node_name)
+                .bind(decryptor_address.to_string());
+        query.execute(&*self.database.pool).await.void()
+    }
+}
+
+// Database serialization / deserialization
+
+/// Low-level representation of a row in the kafka_consumer_key table
+#[derive(sqlx::FromRow)]
+struct KafkaConsumerKeyRow {
+    decryptor_address: String,
+    producer_identifier: String,
+    key_handle: Vec<u8>,
+    last_used_at: i64,
+}
+
+impl KafkaConsumerKeyRow {
+    fn kafka_consumer_key(&self) -> Result<KafkaConsumerKey> {
+        Ok(KafkaConsumerKey::new(
+            Address::from_string(&self.decryptor_address),
+            Identifier::from_str(&self.producer_identifier)?,
+            AeadSecretKeyHandle::new(HandleToSecret::new(self.key_handle.clone())),
+            TimestampInSeconds(self.last_used_at as u64),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::identity::models::IDENTIFIER_LEN;
+    use ockam_node::database::with_dbs;
+
+    #[tokio::test]
+    async fn test_repository() -> Result<()> {
+        with_dbs(|db| async move {
+            let repository: Arc<dyn KafkaConsumerKeysRepository> =
+                Arc::new(KafkaConsumerKeysSqlxDatabase::new(db));
+
+            let decryptor_address = Address::from_string("decryptor");
+            let key = KafkaConsumerKey::new(
+                decryptor_address.clone(),
+                Identifier([1; IDENTIFIER_LEN]),
+                AeadSecretKeyHandle::new(HandleToSecret::new(vec![1, 2, 3])),
+                TimestampInSeconds(10),
+            );
+            repository.store_kafka_consumer_key("node", &key).await?;
+            let actual = repository
+                .get_kafka_consumer_key("node", &decryptor_address)
+                .await?;
+            assert_eq!(actual, Some(key.clone()));
+
+            // storing the key again only updates its last usage time
+            let updated = KafkaConsumerKey::new(
+                decryptor_address.clone(),
+                Identifier([1; IDENTIFIER_LEN]),
+                AeadSecretKeyHandle::new(HandleToSecret::new(vec![4, 5, 6])),
+                TimestampInSeconds(20),
+            );
+            repository
+                .store_kafka_consumer_key("node", &updated)
+                .await?;
+            let actual = repository.get_kafka_consumer_keys("node").await?;
+            assert_eq!(actual, vec![key.with_last_used_at(TimestampInSeconds(20))]);
+
+            // keys are stored per node
+            let actual = repository.get_kafka_consumer_keys("other_node").await?;
+            assert!(actual.is_empty());
+
+            repository
+                .delete_kafka_consumer_key("node", &decryptor_address)
+                .await?;
+            let actual = repository
+                .get_kafka_consumer_key("node", &decryptor_address)
+                .await?;
+            assert_eq!(actual, None);
+
+            Ok(())
+        })
+        .await
+    }
+}
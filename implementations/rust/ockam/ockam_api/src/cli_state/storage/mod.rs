@@ -4,6 +4,8 @@ pub use identities_repository::*;
 pub use identities_repository_sql::*;
 pub use journeys_repository::*;
 pub use journeys_repository_sql::*;
+pub use kafka_consumer_keys_repository::*;
+pub use kafka_consumer_keys_repository_sql::*;
 pub use nodes_repository::*;
 pub use nodes_repository_sql::*;
 pub use projects_repository::*;
@@ -23,6 +25,8 @@ mod identities_repository;
 mod identities_repository_sql;
 mod journeys_repository;
 mod journeys_repository_sql;
+mod kafka_consumer_keys_repository;
+mod kafka_consumer_keys_repository_sql;
 mod nodes_repository;
 mod nodes_repository_sql;
 mod projects_repository;
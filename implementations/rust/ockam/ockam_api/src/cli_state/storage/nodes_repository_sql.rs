@@ -161,6 +161,10 @@ impl NodesRepository for NodesSqlxDatabase {
             sqlx::query("DELETE FROM tcp_outlet_status WHERE node_name = $1").bind(node_name);
         query.execute(&mut *transaction).await.void()?;
 
+        let query =
+            sqlx::query("DELETE FROM kafka_consumer_key WHERE node_name = $1").bind(node_name);
+        query.execute(&mut *transaction).await.void()?;
+
         transaction.commit().await.void()
     }
 
@@ -195,6 +195,8 @@ impl TcpOutletStatusRow {
             worker_addr,
             payload: self.payload.clone(),
             privileged: self.privileged.to_bool(),
+            unix_socket_path: None,
+            allowed_destinations: None,
         })
     }
 }
@@ -48,15 +48,21 @@ impl VaultsRepository for VaultsSqlxDatabase {
         let query = query(
             r#"
         INSERT INTO
-            vault (name, path, is_default, is_kms)
-            VALUES ($1, $2, $3, $4)
+            vault (name, path, is_default, is_kms, pkcs11_module, pkcs11_token_label)
+            VALUES ($1, $2, $3, $4, $5, $6)
             ON CONFLICT (name)
-            DO UPDATE SET path = $2, is_default = $3, is_kms = $4"#,
+            DO UPDATE SET path = $2, is_default = $3, is_kms = $4, pkcs11_module = $5, pkcs11_token_label = $6"#,
         )
         .bind(name)
         .bind(vault_type.path().map(|p| p.to_string_lossy().to_string()))
         .bind(!default_exists)
-        .bind(vault_type.use_aws_kms());
+        .bind(vault_type.use_aws_kms())
+        .bind(
+            vault_type
+                .pkcs11_module()
+                .map(|p| p.to_string_lossy().to_string()),
+        )
+        .bind(vault_type.pkcs11_token_label());
         query.execute(&mut *transaction).await.void()?;
 
         transaction.commit().await.void()?;
@@ -64,10 +70,18 @@ impl VaultsRepository for VaultsSqlxDatabase {
     }
 
     async fn update_vault(&self, name: &str, vault_type: VaultType) -> Result<()> {
-        let query = query("UPDATE vault SET path = $1, is_kms = $2 WHERE name = $3")
-            .bind(vault_type.path().map(|p| p.to_string_lossy().to_string()))
-            .bind(vault_type.use_aws_kms())
-            .bind(name);
+        let query = query(
+            "UPDATE vault SET path = $1, is_kms = $2, pkcs11_module = $3, pkcs11_token_label = $4 WHERE name = $5",
+        )
+        .bind(vault_type.path().map(|p| p.to_string_lossy().to_string()))
+        .bind(vault_type.use_aws_kms())
+        .bind(
+            vault_type
+                .pkcs11_module()
+                .map(|p| p.to_string_lossy().to_string()),
+        )
+        .bind(vault_type.pkcs11_token_label())
+        .bind(name);
         query.execute(&*self.database.pool).await.void()
     }
 
@@ -77,7 +91,9 @@ impl VaultsRepository for VaultsSqlxDatabase {
     }
 
     async fn get_database_vault(&self) -> Result<Option<NamedVault>> {
-        let query = query_as("SELECT name, path, is_default, is_kms FROM vault WHERE path is NULL");
+        let query = query_as(
+            "SELECT name, path, is_default, is_kms, pkcs11_module, pkcs11_token_label FROM vault WHERE path is NULL AND pkcs11_module is NULL",
+        );
         let row: Option<VaultRow> = query
             .fetch_optional(&*self.database.pool)
             .await
@@ -87,7 +103,7 @@ impl VaultsRepository for VaultsSqlxDatabase {
 
     async fn get_named_vault(&self, name: &str) -> Result<Option<NamedVault>> {
         let query =
-            query_as("SELECT name, path, is_default, is_kms FROM vault WHERE name = $1").bind(name);
+            query_as("SELECT name, path, is_default, is_kms, pkcs11_module, pkcs11_token_label FROM vault WHERE name = $1").bind(name);
         let row: Option<VaultRow> = query
             .fetch_optional(&*self.database.pool)
             .await
@@ -96,7 +112,9 @@ impl VaultsRepository for VaultsSqlxDatabase {
     }
 
     async fn get_named_vaults(&self) -> Result<Vec<NamedVault>> {
-        let query = query_as("SELECT name, path, is_default, is_kms FROM vault");
+        let query = query_as(
+            "SELECT name, path, is_default, is_kms, pkcs11_module, pkcs11_token_label FROM vault",
+        );
         let rows: Vec<VaultRow> = query.fetch_all(&*self.database.pool).await.into_core()?;
         rows.iter().map(|r| r.named_vault()).collect()
     }
@@ -110,6 +128,8 @@ pub(crate) struct VaultRow {
     path: Nullable<String>,
     is_default: Boolean,
     is_kms: Boolean,
+    pkcs11_module: Nullable<String>,
+    pkcs11_token_label: Nullable<String>,
 }
 
 impl VaultRow {
@@ -122,6 +142,9 @@ impl VaultRow {
     }
 
     pub(crate) fn vault_type(&self) -> VaultType {
+        if let Some(module) = self.pkcs11_module.to_option() {
+            return VaultType::pkcs11(module, self.pkcs11_token_label.to_option());
+        }
         match self.path.to_option() {
             None => VaultType::database(UseAwsKms::from(self.is_kms.to_bool())),
             Some(p) => VaultType::local_file(
@@ -196,4 +219,26 @@ mod test {
         })
         .await
     }
+
+    #[tokio::test]
+    async fn test_store_pkcs11_vault() -> Result<()> {
+        with_dbs(|db| async move {
+            let repository: Arc<dyn VaultsRepository> = Arc::new(VaultsSqlxDatabase::new(db));
+
+            // It is possible to create a vault storing its keys on a PKCS#11 token
+            let vault_type =
+                VaultType::pkcs11("/usr/lib/softhsm/libsofthsm2.so", Some("ockam".into()));
+            let pkcs11 = repository.store_vault("pkcs11", vault_type.clone()).await?;
+            let expected = NamedVault::new("pkcs11", vault_type, true);
+            assert_eq!(pkcs11, expected);
+
+            let result = repository.get_named_vault("pkcs11").await?;
+            assert_eq!(result, Some(pkcs11));
+
+            // A PKCS#11 vault is not the database vault
+            assert_eq!(repository.get_database_vault().await?, None);
+            Ok(())
+        })
+        .await
+    }
 }
@@ -4,20 +4,79 @@ use crate::output::Output;
 use crate::{fmt_log, fmt_ok, fmt_warn};
 use colorful::Colorful;
 use ockam::identity::{Identities, Vault};
+use ockam_core::env::get_env;
 use ockam_core::errcode::{Kind, Origin};
 use ockam_node::database::SqlxDatabase;
+use ockam_vault::storage::{KeyDerivationParameters, SecretsSqlxDatabase};
 use ockam_vault_aws::AwsSigningVault;
+use ockam_vault_pkcs11::{
+    Pkcs11Client, Pkcs11Config, Pkcs11VaultForSecureChannels, Pkcs11VaultForSigning,
+};
+use std::collections::BTreeMap;
 use std::fmt::Write;
 use std::fmt::{Debug, Display, Formatter};
 use std::fs::OpenOptions;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 static DEFAULT_VAULT_NAME: &str = "default";
 
+/// Environment variable used to provide the user PIN of a PKCS#11 token
+pub const OCKAM_PKCS11_PIN: &str = "OCKAM_PKCS11_PIN";
+
+/// Environment variable used to provide the passphrase of encrypted vaults
+pub const OCKAM_VAULT_PASSPHRASE: &str = "OCKAM_VAULT_PASSPHRASE";
+
+/// Environment variable used to provide the path of a file containing the key of encrypted vaults.
+/// It is used when OCKAM_VAULT_PASSPHRASE is not set
+pub const OCKAM_VAULT_KEY_FILE: &str = "OCKAM_VAULT_KEY_FILE";
+
+/// Prefix of the environment variables used to pass the hex-encoded passphrase of a specific
+/// vault to the nodes started by a command
+pub const OCKAM_VAULT_PASSPHRASE_PREFIX: &str = "OCKAM_VAULT_PASSPHRASE_";
+
+/// Function used to ask for the passphrase of an encrypted vault, given the vault name
+pub type VaultPassphrasePrompt = Arc<dyn Fn(&str) -> Option<Vec<u8>> + Send + Sync>;
+
+/// Passphrases of the encrypted vaults, indexed by vault name.
+///
+/// They are shared by all the clones of a CliState. When the passphrase of an encrypted vault
+/// is not known, it is asked once with the configured prompt, if any
+#[derive(Clone, Default)]
+pub struct VaultPassphrases {
+    passphrases: Arc<Mutex<BTreeMap<String, Vec<u8>>>>,
+    prompt: Arc<Mutex<Option<VaultPassphrasePrompt>>>,
+}
+
+impl Debug for VaultPassphrases {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let vault_names: Vec<String> = self.passphrases.lock().unwrap().keys().cloned().collect();
+        f.debug_struct("VaultPassphrases")
+            .field("vault_names", &vault_names)
+            .finish()
+    }
+}
+
+impl VaultPassphrases {
+    fn get(&self, vault_name: &str) -> Option<Vec<u8>> {
+        self.passphrases.lock().unwrap().get(vault_name).cloned()
+    }
+
+    fn set(&self, vault_name: &str, passphrase: Vec<u8>) {
+        self.passphrases
+            .lock()
+            .unwrap()
+            .insert(vault_name.to_string(), passphrase);
+    }
+
+    fn prompt(&self) -> Option<VaultPassphrasePrompt> {
+        self.prompt.lock().unwrap().clone()
+    }
+}
+
 /// The methods below support the creation and update of local vaults
 ///
-///  - by default private keys are stored locally but they can also be stored in a KMS
+///  - by default private keys are stored locally but they can also be stored in a KMS or on a PKCS#11 token
 ///  - keys stored locally are stored with other application data in the local database if the default vault is used
 ///  - any additional vault stores its keys in a separate file
 ///
@@ -73,6 +132,41 @@ impl CliState {
         }
     }
 
+    /// Create a vault storing its signing keys and static X25519 keys on a PKCS#11 token.
+    /// The other secrets are stored in the main database.
+    ///
+    /// The token is opened once to check that the module can be loaded and that the
+    /// user PIN, read from the OCKAM_PKCS11_PIN environment variable, is correct.
+    #[instrument(skip_all, fields(vault_name = vault_name.clone(), module = %module.display()))]
+    pub async fn create_pkcs11_vault(
+        &self,
+        vault_name: Option<String>,
+        module: PathBuf,
+        token_label: Option<String>,
+    ) -> Result<NamedVault> {
+        let vaults_repository = self.vaults_repository();
+        let vault_name = match vault_name {
+            Some(vault_name) => vault_name.clone(),
+            None => self.make_vault_name().await?,
+        };
+        if vaults_repository
+            .get_named_vault(&vault_name)
+            .await?
+            .is_some()
+        {
+            return Err(CliStateError::AlreadyExists {
+                resource: "vault".to_string(),
+                name: vault_name.to_string(),
+            });
+        }
+
+        let vault_type = VaultType::pkcs11(module, token_label);
+        Self::make_pkcs11_client(&vault_type).await?;
+        Ok(vaults_repository
+            .store_vault(&vault_name, vault_type)
+            .await?)
+    }
+
     /// Delete an existing vault
     #[instrument(skip_all, fields(vault_name = vault_name))]
     pub async fn delete_named_vault(&self, vault_name: &str) -> Result<()> {
@@ -109,6 +203,8 @@ impl CliState {
                 VaultType::LocalFileVault { path, .. } => {
                     let _ = std::fs::remove_file(path);
                 }
+                // the keys stored on the token are not deleted
+                VaultType::Pkcs11Vault { .. } => {}
             }
         }
         Ok(())
@@ -287,6 +383,14 @@ impl CliState {
                 // remove the old file
                 std::fs::remove_file(old_path)?;
             }
+            VaultType::Pkcs11Vault { .. } => Err(ockam_core::Error::new(
+                Origin::Api,
+                Kind::Invalid,
+                format!(
+                    "The vault {} cannot be moved to {path:?} because its keys are stored on a PKCS#11 token",
+                    vault.name()
+                ),
+            ))?,
         }
         Ok(())
     }
@@ -294,24 +398,109 @@ impl CliState {
     /// Make a concrete vault based on the NamedVault metadata
     #[instrument(skip_all, fields(vault_name = named_vault.name))]
     pub async fn make_vault(&self, named_vault: NamedVault) -> Result<Vault> {
-        let db = match named_vault.vault_type {
-            VaultType::DatabaseVault { .. } => self.database(),
-            VaultType::LocalFileVault { ref path, .. } =>
-            // TODO: Avoid creating multiple dbs with the same file
-            {
-                SqlxDatabase::create_sqlite(path.as_path()).await?
-            }
-        };
+        let secrets = self.make_secrets_database(&named_vault).await?;
+        let make_vault = || Vault::create_with_secrets_repository(secrets.into_repository());
 
         if named_vault.vault_type.use_aws_kms() {
-            let mut vault = Vault::create_with_database(db);
+            let mut vault = make_vault();
             let aws_vault = Arc::new(AwsSigningVault::create().await?);
             vault.identity_vault = aws_vault.clone();
             vault.credential_vault = aws_vault;
             Ok(vault)
+        } else if let VaultType::Pkcs11Vault { .. } = named_vault.vault_type {
+            let mut vault = make_vault();
+            let client = Self::make_pkcs11_client(&named_vault.vault_type).await?;
+            let signing_vault = Arc::new(Pkcs11VaultForSigning::new(client.clone()));
+            vault.identity_vault = signing_vault.clone();
+            vault.credential_vault = signing_vault;
+            vault.secure_channel_vault = Arc::new(Pkcs11VaultForSecureChannels::new(
+                client,
+                vault.secure_channel_vault.clone(),
+            ));
+            Ok(vault)
         } else {
-            Ok(Vault::create_with_database(db))
+            Ok(make_vault())
+        }
+    }
+
+    /// Return true if the secrets of a vault are encrypted with a passphrase
+    #[instrument(skip_all, fields(vault_name = vault_name))]
+    pub async fn is_vault_encrypted(&self, vault_name: &str) -> Result<bool> {
+        let named_vault = self.get_named_vault(vault_name).await?;
+        Ok(
+            SecretsSqlxDatabase::new(self.vault_database(&named_vault).await?)
+                .is_encrypted()
+                .await?,
+        )
+    }
+
+    /// Encrypt the secrets of a vault with a key derived from a passphrase.
+    /// The secrets already stored in the vault are encrypted as well
+    #[instrument(skip_all, fields(vault_name = vault_name))]
+    pub async fn encrypt_vault(&self, vault_name: &str, passphrase: &[u8]) -> Result<()> {
+        let named_vault = self.get_named_vault(vault_name).await?;
+        SecretsSqlxDatabase::new(self.vault_database(&named_vault).await?)
+            .encrypt(passphrase, KeyDerivationParameters::default())
+            .await?;
+        Ok(())
+    }
+
+    /// Unlock all the encrypted vaults, so that their passphrases are known by this process
+    #[instrument(skip_all)]
+    pub async fn unlock_encrypted_vaults(&self) -> Result<()> {
+        for named_vault in self.get_named_vaults().await? {
+            self.make_secrets_database(&named_vault).await?;
         }
+        Ok(())
+    }
+
+    /// Set the passphrase of an encrypted vault for this process
+    pub fn set_vault_passphrase(&self, vault_name: &str, passphrase: Vec<u8>) {
+        self.vault_passphrases().set(vault_name, passphrase)
+    }
+
+    /// Set the function used to ask for the passphrase of an encrypted vault when it is not
+    /// provided by the environment
+    pub fn set_vault_passphrase_prompt(&self, prompt: VaultPassphrasePrompt) {
+        *self.vault_passphrases().prompt.lock().unwrap() = Some(prompt);
+    }
+
+    /// Return the environment variables passing the known vault passphrases to a child process
+    pub fn vault_passphrases_env(&self) -> Vec<(String, String)> {
+        self.vault_passphrases()
+            .passphrases
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(vault_name, passphrase)| {
+                (
+                    vault_passphrase_env_name(vault_name),
+                    hex::encode(passphrase),
+                )
+            })
+            .collect()
+    }
+
+    /// Change the passphrase of an encrypted vault
+    #[instrument(skip_all, fields(vault_name = vault_name))]
+    pub async fn change_vault_passphrase(
+        &self,
+        vault_name: &str,
+        passphrase: &[u8],
+        new_passphrase: &[u8],
+    ) -> Result<()> {
+        let named_vault = self.get_named_vault(vault_name).await?;
+        SecretsSqlxDatabase::new(self.vault_database(&named_vault).await?)
+            .change_passphrase(
+                passphrase,
+                new_passphrase,
+                KeyDerivationParameters::default(),
+            )
+            .await?;
+        if self.vault_passphrases().get(vault_name).is_some() {
+            self.set_vault_passphrase(vault_name, new_passphrase.to_vec());
+        }
+        Ok(())
     }
 }
 
@@ -378,6 +567,96 @@ impl CliState {
             .await?)
     }
 
+    /// Return the database storing the secrets of a vault
+    async fn vault_database(&self, named_vault: &NamedVault) -> Result<SqlxDatabase> {
+        Ok(match named_vault.vault_type {
+            VaultType::DatabaseVault { .. } | VaultType::Pkcs11Vault { .. } => self.database(),
+            VaultType::LocalFileVault { ref path, .. } =>
+            // TODO: Avoid creating multiple dbs with the same file
+            {
+                SqlxDatabase::create_sqlite(path.as_path()).await?
+            }
+        })
+    }
+
+    /// Return the secrets database of a vault.
+    /// If the secrets are encrypted, they are unlocked with the passphrase of the vault
+    pub(super) async fn make_secrets_database(
+        &self,
+        named_vault: &NamedVault,
+    ) -> Result<SecretsSqlxDatabase> {
+        let secrets = SecretsSqlxDatabase::new(self.vault_database(named_vault).await?);
+        if !secrets.is_encrypted().await? {
+            return Ok(secrets);
+        }
+        let passphrase = self.get_vault_passphrase(&named_vault.name())?;
+        let unlocked = secrets.unlock(&passphrase).await?;
+        self.set_vault_passphrase(&named_vault.name(), passphrase);
+        Ok(unlocked)
+    }
+
+    /// Return the passphrase of an encrypted vault. It is either:
+    ///
+    ///  - already known by this process
+    ///  - provided by the environment for this vault specifically, or for all vaults
+    ///  - asked with the configured prompt
+    ///
+    fn get_vault_passphrase(&self, vault_name: &str) -> Result<Vec<u8>> {
+        if let Some(passphrase) = self.vault_passphrases().get(vault_name) {
+            return Ok(passphrase);
+        }
+        if let Some(passphrase) = get_env::<String>(&vault_passphrase_env_name(vault_name))? {
+            return hex::decode(passphrase).map_err(|e| {
+                ockam_core::Error::new(
+                    Origin::Api,
+                    Kind::Invalid,
+                    format!("The passphrase of the vault {vault_name} is not hex-encoded: {e}"),
+                )
+                .into()
+            });
+        }
+        if let Some(passphrase) = vault_passphrase_from_env()? {
+            return Ok(passphrase);
+        }
+        if let Some(passphrase) = self
+            .vault_passphrases()
+            .prompt()
+            .and_then(|p| p(vault_name))
+        {
+            return Ok(passphrase);
+        }
+        Err(ockam_core::Error::new(
+            Origin::Api,
+            Kind::Misuse,
+            format!(
+                "The vault {vault_name} is encrypted. Its passphrase must be provided with the {OCKAM_VAULT_PASSPHRASE} or {OCKAM_VAULT_KEY_FILE} environment variables"
+            ),
+        ))?
+    }
+
+    /// Open a session on the token of a PKCS#11 vault
+    async fn make_pkcs11_client(vault_type: &VaultType) -> Result<Arc<Pkcs11Client>> {
+        let VaultType::Pkcs11Vault {
+            module,
+            token_label,
+        } = vault_type
+        else {
+            return Err(ockam_core::Error::new(
+                Origin::Api,
+                Kind::Misuse,
+                "this vault is not a PKCS#11 vault",
+            ))?;
+        };
+        let mut config = Pkcs11Config::new(module);
+        if let Some(token_label) = token_label {
+            config = config.with_token_label(token_label);
+        }
+        if let Some(pin) = get_env::<String>(OCKAM_PKCS11_PIN)? {
+            config = config.with_pin(pin);
+        }
+        Ok(Pkcs11Client::create(config).await?)
+    }
+
     /// Return the vault name to use for a vault:
     ///
     ///  - if a user has specified a name, use it
@@ -400,6 +679,33 @@ impl CliState {
     }
 }
 
+/// Return the passphrase of encrypted vaults if it is provided by the environment, either
+/// directly with OCKAM_VAULT_PASSPHRASE or with a key file referenced by OCKAM_VAULT_KEY_FILE
+pub fn vault_passphrase_from_env() -> Result<Option<Vec<u8>>> {
+    if let Some(passphrase) = get_env::<String>(OCKAM_VAULT_PASSPHRASE)? {
+        return Ok(Some(passphrase.into_bytes()));
+    }
+    match get_env::<String>(OCKAM_VAULT_KEY_FILE)? {
+        Some(path) => Ok(Some(std::fs::read(path)?)),
+        None => Ok(None),
+    }
+}
+
+/// Return the name of the environment variable used to pass the passphrase of a specific vault
+pub fn vault_passphrase_env_name(vault_name: &str) -> String {
+    let vault_name: String = vault_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{OCKAM_VAULT_PASSPHRASE_PREFIX}{vault_name}")
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
 pub struct NamedVault {
     name: String,
@@ -416,6 +722,10 @@ pub enum VaultType {
         path: PathBuf,
         use_aws_kms: UseAwsKms,
     },
+    Pkcs11Vault {
+        module: PathBuf,
+        token_label: Option<String>,
+    },
 }
 
 impl Display for VaultType {
@@ -426,11 +736,15 @@ impl Display for VaultType {
             match &self {
                 VaultType::DatabaseVault { .. } => "INTERNAL",
                 VaultType::LocalFileVault { .. } => "EXTERNAL",
+                VaultType::Pkcs11Vault { .. } => "PKCS11",
             }
         )?;
         if self.use_aws_kms() {
             writeln!(f, "Uses AWS KMS: true",)?;
         }
+        if let Some(module) = self.pkcs11_module() {
+            writeln!(f, "PKCS#11 module: {}", module.display())?;
+        }
         Ok(())
     }
 }
@@ -463,13 +777,34 @@ impl VaultType {
         }
     }
 
+    pub fn pkcs11(module: impl Into<PathBuf>, token_label: Option<String>) -> Self {
+        VaultType::Pkcs11Vault {
+            module: module.into(),
+            token_label,
+        }
+    }
+
     pub fn path(&self) -> Option<&Path> {
         match self {
-            VaultType::DatabaseVault { .. } => None,
+            VaultType::DatabaseVault { .. } | VaultType::Pkcs11Vault { .. } => None,
             VaultType::LocalFileVault { path, .. } => Some(path.as_path()),
         }
     }
 
+    pub fn pkcs11_module(&self) -> Option<&Path> {
+        match self {
+            VaultType::Pkcs11Vault { module, .. } => Some(module.as_path()),
+            _ => None,
+        }
+    }
+
+    pub fn pkcs11_token_label(&self) -> Option<&str> {
+        match self {
+            VaultType::Pkcs11Vault { token_label, .. } => token_label.as_deref(),
+            _ => None,
+        }
+    }
+
     pub fn use_aws_kms(&self) -> bool {
         match self {
             VaultType::DatabaseVault { use_aws_kms } => use_aws_kms == &UseAwsKms::Yes,
@@ -477,6 +812,7 @@ impl VaultType {
                 path: _,
                 use_aws_kms,
             } => use_aws_kms == &UseAwsKms::Yes,
+            VaultType::Pkcs11Vault { .. } => false,
         }
     }
 }
@@ -542,11 +878,15 @@ impl Output for NamedVault {
             match &self.vault_type {
                 VaultType::DatabaseVault { .. } => "INTERNAL",
                 VaultType::LocalFileVault { .. } => "EXTERNAL",
+                VaultType::Pkcs11Vault { .. } => "PKCS11",
             }
         )?;
         if self.vault_type.use_aws_kms() {
             writeln!(output, "Uses AWS KMS: true",)?;
         }
+        if let Some(module) = self.vault_type.pkcs11_module() {
+            writeln!(output, "PKCS#11 module: {}", module.display())?;
+        }
         Ok(output)
     }
 }
@@ -559,7 +899,8 @@ mod tests {
     use ockam_node::database::skip_if_postgres;
     use ockam_vault::{
         ECDSASHA256CurveP256SecretKey, ECDSASHA256CurveP256Signature, HandleToSecret,
-        SigningSecret, SigningSecretKeyHandle, X25519SecretKey, X25519SecretKeyHandle,
+        SigningKeyType, SigningSecret, SigningSecretKeyHandle, X25519SecretKey,
+        X25519SecretKeyHandle,
     };
 
     #[tokio::test]
@@ -772,6 +1113,67 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_encrypt_vault() -> Result<()> {
+        let cli = CliState::test().await?;
+        let vault = cli
+            .create_named_vault(Some("vault".to_string()), None, UseAwsKms::No)
+            .await?;
+        let handle = cli
+            .make_vault(vault.clone())
+            .await?
+            .identity_vault
+            .generate_signing_secret_key(SigningKeyType::EdDSACurve25519)
+            .await?;
+
+        // the existing secrets are encrypted
+        assert!(!cli.is_vault_encrypted("vault").await?);
+        cli.encrypt_vault("vault", b"passphrase").await?;
+        assert!(cli.is_vault_encrypted("vault").await?);
+
+        // a passphrase is necessary to use the vault
+        assert!(cli.make_vault(vault.clone()).await.is_err());
+
+        // it can be asked once, and is then known by all the clones of the state
+        let asked = Arc::new(Mutex::new(0));
+        let asked_clone = asked.clone();
+        cli.clone()
+            .set_vault_passphrase_prompt(Arc::new(move |vault_name: &str| {
+                assert_eq!(vault_name, "vault");
+                *asked_clone.lock().unwrap() += 1;
+                Some(b"passphrase".to_vec())
+            }));
+        assert!(cli.make_vault(vault.clone()).await.is_ok());
+        let public_key = cli
+            .make_vault(vault.clone())
+            .await?
+            .identity_vault
+            .get_verifying_public_key(&handle)
+            .await;
+        assert!(public_key.is_ok());
+        assert_eq!(*asked.lock().unwrap(), 1);
+
+        // the passphrase can be passed to another process
+        assert_eq!(
+            cli.vault_passphrases_env(),
+            vec![(
+                "OCKAM_VAULT_PASSPHRASE_VAULT".to_string(),
+                hex::encode(b"passphrase")
+            )]
+        );
+
+        // the passphrase can be changed
+        cli.change_vault_passphrase("vault", b"passphrase", b"new passphrase")
+            .await?;
+        let other = CliState::create(cli.mode.clone()).await?;
+        assert!(other.make_vault(vault.clone()).await.is_err());
+        other.set_vault_passphrase("vault", b"new passphrase".to_vec());
+        assert!(other.make_vault(vault.clone()).await.is_ok());
+        assert!(cli.make_vault(vault).await.is_ok());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_delete_vault() -> Result<()> {
         let cli = CliState::test().await?;
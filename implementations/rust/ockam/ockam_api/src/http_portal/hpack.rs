@@ -0,0 +1,401 @@
+use crate::http_portal::huffman::HUFFMAN_CODES;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use once_cell::sync::Lazy;
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+/// Header field of an HTTP/2 header block
+pub type HeaderField = (Vec<u8>, Vec<u8>);
+
+/// Size of the dynamic table when no size update is sent, from the HTTP/2 settings
+pub const DEFAULT_HEADER_TABLE_SIZE: usize = 4096;
+
+/// Size added to each entry of the dynamic table, RFC 7541 section 4.1
+const ENTRY_OVERHEAD: usize = 32;
+
+/// Static table of RFC 7541 appendix A
+const STATIC_TABLE: [(&str, &str); 61] = [
+    (":authority", ""),
+    (":method", "GET"),
+    (":method", "POST"),
+    (":path", "/"),
+    (":path", "/index.html"),
+    (":scheme", "http"),
+    (":scheme", "https"),
+    (":status", "200"),
+    (":status", "204"),
+    (":status", "206"),
+    (":status", "304"),
+    (":status", "400"),
+    (":status", "404"),
+    (":status", "500"),
+    ("accept-charset", ""),
+    ("accept-encoding", "gzip, deflate"),
+    ("accept-language", ""),
+    ("accept-ranges", ""),
+    ("accept", ""),
+    ("access-control-allow-origin", ""),
+    ("age", ""),
+    ("allow", ""),
+    ("authorization", ""),
+    ("cache-control", ""),
+    ("content-disposition", ""),
+    ("content-encoding", ""),
+    ("content-language", ""),
+    ("content-length", ""),
+    ("content-location", ""),
+    ("content-range", ""),
+    ("content-type", ""),
+    ("cookie", ""),
+    ("date", ""),
+    ("etag", ""),
+    ("expect", ""),
+    ("expires", ""),
+    ("from", ""),
+    ("host", ""),
+    ("if-match", ""),
+    ("if-modified-since", ""),
+    ("if-none-match", ""),
+    ("if-range", ""),
+    ("if-unmodified-since", ""),
+    ("last-modified", ""),
+    ("link", ""),
+    ("location", ""),
+    ("max-forwards", ""),
+    ("proxy-authenticate", ""),
+    ("proxy-authorization", ""),
+    ("range", ""),
+    ("referer", ""),
+    ("refresh", ""),
+    ("retry-after", ""),
+    ("server", ""),
+    ("set-cookie", ""),
+    ("strict-transport-security", ""),
+    ("transfer-encoding", ""),
+    ("user-agent", ""),
+    ("vary", ""),
+    ("via", ""),
+    ("www-authenticate", ""),
+];
+
+/// Decoder of the header blocks sent by one peer of an HTTP/2 connection.
+/// It keeps the dynamic table of the peer encoder, so every header block sent on the
+/// connection must be decoded, in order
+pub struct HpackDecoder {
+    dynamic_table: VecDeque<HeaderField>,
+    size: usize,
+    max_size: usize,
+    max_allowed_size: usize,
+}
+
+impl HpackDecoder {
+    /// The peer can resize its dynamic table up to the maximum allowed size
+    pub fn new(max_allowed_size: usize) -> Self {
+        Self {
+            dynamic_table: VecDeque::new(),
+            size: 0,
+            max_size: DEFAULT_HEADER_TABLE_SIZE.min(max_allowed_size),
+            max_allowed_size,
+        }
+    }
+
+    /// Decode a complete header block
+    pub fn decode(&mut self, block: &[u8]) -> Result<Vec<HeaderField>> {
+        let mut fields = vec![];
+        let mut position = 0;
+        while position < block.len() {
+            let first = block[position];
+            if first & 0x80 != 0 {
+                // indexed header field
+                let index = decode_integer(block, &mut position, 7)?;
+                fields.push(self.entry(index)?);
+            } else if first & 0x40 != 0 {
+                // literal header field with incremental indexing
+                let field = self.decode_literal(block, &mut position, 6)?;
+                self.insert(field.clone());
+                fields.push(field);
+            } else if first & 0x20 != 0 {
+                // dynamic table size update
+                let size = decode_integer(block, &mut position, 5)?;
+                if size > self.max_allowed_size {
+                    return Err(invalid("the HPACK dynamic table size is too large"));
+                }
+                self.max_size = size;
+                self.evict(0);
+            } else {
+                // literal header field without indexing, or never indexed
+                fields.push(self.decode_literal(block, &mut position, 4)?);
+            }
+        }
+        Ok(fields)
+    }
+
+    fn decode_literal(
+        &self,
+        block: &[u8],
+        position: &mut usize,
+        prefix_bits: u8,
+    ) -> Result<HeaderField> {
+        let index = decode_integer(block, position, prefix_bits)?;
+        let name = if index == 0 {
+            decode_string(block, position)?
+        } else {
+            self.entry(index)?.0
+        };
+        let value = decode_string(block, position)?;
+        Ok((name, value))
+    }
+
+    fn entry(&self, index: usize) -> Result<HeaderField> {
+        if index == 0 {
+            return Err(invalid("invalid HPACK index"));
+        }
+        if let Some((name, value)) = STATIC_TABLE.get(index - 1) {
+            return Ok((name.as_bytes().to_vec(), value.as_bytes().to_vec()));
+        }
+        self.dynamic_table
+            .get(index - STATIC_TABLE.len() - 1)
+            .cloned()
+            .ok_or_else(|| invalid("invalid HPACK index"))
+    }
+
+    fn insert(&mut self, field: HeaderField) {
+        let size = field.0.len() + field.1.len() + ENTRY_OVERHEAD;
+        self.evict(size);
+        // an entry bigger than the table empties the table, without being added
+        if size <= self.max_size {
+            self.size += size;
+            self.dynamic_table.push_front(field);
+        }
+    }
+
+    /// Evict the oldest entries until the new size fits in the table
+    fn evict(&mut self, new_size: usize) {
+        while self.size + new_size > self.max_size {
+            match self.dynamic_table.pop_back() {
+                Some((name, value)) => self.size -= name.len() + value.len() + ENTRY_OVERHEAD,
+                None => break,
+            }
+        }
+    }
+}
+
+/// Encode header fields as literals without indexing, and without Huffman encoding,
+/// so that the dynamic table of the peer decoder is not modified
+pub fn encode_without_indexing(fields: &[HeaderField], buffer: &mut Vec<u8>) {
+    for (name, value) in fields {
+        buffer.push(0);
+        encode_integer(name.len(), 7, 0, buffer);
+        buffer.extend_from_slice(name);
+        encode_integer(value.len(), 7, 0, buffer);
+        buffer.extend_from_slice(value);
+    }
+}
+
+fn decode_integer(block: &[u8], position: &mut usize, prefix_bits: u8) -> Result<usize> {
+    let mask = (1u8 << prefix_bits) - 1;
+    let first = *block
+        .get(*position)
+        .ok_or_else(|| invalid("truncated HPACK integer"))?;
+    *position += 1;
+    let mut value = (first & mask) as usize;
+    if value < mask as usize {
+        return Ok(value);
+    }
+
+    let mut shift = 0;
+    loop {
+        let byte = *block
+            .get(*position)
+            .ok_or_else(|| invalid("truncated HPACK integer"))?;
+        *position += 1;
+        if shift > 28 {
+            return Err(invalid("HPACK integer overflow"));
+        }
+        value += ((byte & 0x7f) as usize) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+}
+
+fn encode_integer(value: usize, prefix_bits: u8, flags: u8, buffer: &mut Vec<u8>) {
+    let mask = (1usize << prefix_bits) - 1;
+    if value < mask {
+        buffer.push(flags | value as u8);
+        return;
+    }
+    buffer.push(flags | mask as u8);
+    let mut value = value - mask;
+    while value >= 0x80 {
+        buffer.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn decode_string(block: &[u8], position: &mut usize) -> Result<Vec<u8>> {
+    let huffman = block.get(*position).map_or(false, |b| b & 0x80 != 0);
+    let length = decode_integer(block, position, 7)?;
+    let end = position
+        .checked_add(length)
+        .filter(|end| *end <= block.len())
+        .ok_or_else(|| invalid("truncated HPACK string"))?;
+    let data = &block[*position..end];
+    *position = end;
+    if huffman {
+        huffman_decode(data)
+    } else {
+        Ok(data.to_vec())
+    }
+}
+
+/// Node of the Huffman decoding tree: the next node for a 0 bit and for a 1 bit.
+/// The leaves are stored as the bitwise complement of their symbol
+type HuffmanNode = [i32; 2];
+
+static HUFFMAN_TREE: Lazy<Vec<HuffmanNode>> = Lazy::new(|| {
+    let mut tree: Vec<HuffmanNode> = vec![[0, 0]];
+    for (symbol, (code, length)) in HUFFMAN_CODES.iter().enumerate() {
+        let mut node = 0;
+        for bit in (0..*length).rev() {
+            let branch = ((code >> bit) & 1) as usize;
+            if bit == 0 {
+                tree[node][branch] = !(symbol as i32);
+            } else {
+                if tree[node][branch] == 0 {
+                    tree.push([0, 0]);
+                    tree[node][branch] = (tree.len() - 1) as i32;
+                }
+                node = tree[node][branch] as usize;
+            }
+        }
+    }
+    tree
+});
+
+/// Symbol terminating a Huffman-encoded string, which must not be decoded
+const END_OF_STRING: i32 = 256;
+
+fn huffman_decode(data: &[u8]) -> Result<Vec<u8>> {
+    let tree = &*HUFFMAN_TREE;
+    let mut decoded = Vec::with_capacity(data.len() * 8 / 5);
+    let mut node = 0;
+    // the padding is made of the most significant bits of the end of string symbol: only ones
+    let mut padding_bits = 0;
+    let mut padding_ones = true;
+    for byte in data {
+        for bit in (0..8).rev() {
+            let branch = ((byte >> bit) & 1) as usize;
+            let next = tree[node][branch];
+            match next.cmp(&0) {
+                Ordering::Less => {
+                    let symbol = !next;
+                    if symbol == END_OF_STRING {
+                        return Err(invalid(
+                            "the HPACK string contains the end of string symbol",
+                        ));
+                    }
+                    decoded.push(symbol as u8);
+                    node = 0;
+                    padding_bits = 0;
+                    padding_ones = true;
+                }
+                Ordering::Equal => return Err(invalid("invalid HPACK Huffman code")),
+                Ordering::Greater => {
+                    node = next as usize;
+                    padding_bits += 1;
+                    padding_ones &= branch == 1;
+                }
+            }
+        }
+    }
+    if padding_bits > 7 || !padding_ones {
+        return Err(invalid("invalid HPACK Huffman padding"));
+    }
+    Ok(decoded)
+}
+
+fn invalid(message: &'static str) -> Error {
+    Error::new(Origin::Transport, Kind::Protocol, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(fields: &[(&str, &str)]) -> Vec<HeaderField> {
+        fields
+            .iter()
+            .map(|(n, v)| (n.as_bytes().to_vec(), v.as_bytes().to_vec()))
+            .collect()
+    }
+
+    /// Requests of the appendix C.4 of RFC 7541, with Huffman encoding
+    #[test]
+    fn decode_requests_with_huffman_encoding() {
+        let mut decoder = HpackDecoder::new(DEFAULT_HEADER_TABLE_SIZE);
+        let first = hex::decode("828684418cf1e3c2e5f23a6ba0ab90f4ff").unwrap();
+        assert_eq!(
+            decoder.decode(&first).unwrap(),
+            fields(&[
+                (":method", "GET"),
+                (":scheme", "http"),
+                (":path", "/"),
+                (":authority", "www.example.com"),
+            ])
+        );
+
+        let second = hex::decode("828684be5886a8eb10649cbf").unwrap();
+        assert_eq!(
+            decoder.decode(&second).unwrap(),
+            fields(&[
+                (":method", "GET"),
+                (":scheme", "http"),
+                (":path", "/"),
+                (":authority", "www.example.com"),
+                ("cache-control", "no-cache"),
+            ])
+        );
+
+        let third = hex::decode("828785bf408825a849e95ba97d7f8925a849e95bb8e8b4bf").unwrap();
+        assert_eq!(
+            decoder.decode(&third).unwrap(),
+            fields(&[
+                (":method", "GET"),
+                (":scheme", "https"),
+                (":path", "/index.html"),
+                (":authority", "www.example.com"),
+                ("custom-key", "custom-value"),
+            ])
+        );
+        assert_eq!(decoder.dynamic_table.len(), 3);
+        assert_eq!(decoder.size, 164);
+    }
+
+    #[test]
+    fn encoded_fields_are_not_indexed() {
+        let headers = fields(&[(":method", "GET"), ("x-ockam-identifier", &"I".repeat(200))]);
+        let mut block = vec![];
+        encode_without_indexing(&headers, &mut block);
+
+        let mut decoder = HpackDecoder::new(DEFAULT_HEADER_TABLE_SIZE);
+        assert_eq!(decoder.decode(&block).unwrap(), headers);
+        assert!(decoder.dynamic_table.is_empty());
+    }
+
+    #[test]
+    fn invalid_blocks_are_rejected() {
+        let mut decoder = HpackDecoder::new(DEFAULT_HEADER_TABLE_SIZE);
+        // index beyond the tables
+        assert!(decoder.decode(&[0xbe]).is_err());
+        // truncated string
+        assert!(decoder.decode(&[0x40, 0x05, b'a']).is_err());
+        // table size update larger than allowed
+        assert!(decoder.decode(&[0x3f, 0xe1, 0x7f]).is_err());
+        // padding which is not made of ones
+        assert!(huffman_decode(&[0x00]).is_err());
+    }
+}
@@ -0,0 +1,731 @@
+use crate::http_portal::hpack::{encode_without_indexing, HeaderField, HpackDecoder};
+use crate::http_portal::route_policy::{is_ockam_header, request_path, HttpAuthorization};
+use crate::protocol_interceptor::http::*;
+use crate::protocol_interceptor::{Connection, PendingRequests, ProtocolInterceptor};
+use ockam::identity::Identifier;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, Error, Result};
+use ockam_node::Context;
+use ockam_transport_tcp::Direction;
+use std::collections::BTreeSet;
+
+/// Maximum size of the head of an HTTP/1.1 message, or of an HTTP/2 header block
+pub const MAX_HTTP_HEAD_SIZE: usize = 64 * 1024;
+/// Largest frame size which can be negotiated by HTTP/2 peers
+const MAX_HTTP2_FRAME_SIZE: usize = (1 << 24) - 1;
+/// Frame size supported by all HTTP/2 peers, used to send the header blocks
+const DEFAULT_HTTP2_FRAME_SIZE: usize = 16_384;
+/// Largest HPACK dynamic table accepted from the clients
+const MAX_HEADER_TABLE_SIZE: usize = 64 * 1024;
+/// Number of denied HTTP/2 streams whose frames are dropped
+const MAX_DENIED_STREAMS: usize = 1024;
+/// Number of HTTP/2 streams which can be forwarded and still open at the same time
+const MAX_OPEN_STREAMS: usize = 1024;
+/// HTTP/2 error code sent when a frame is received on a closed stream
+const HTTP2_STREAM_CLOSED: u32 = 0x5;
+/// HTTP/2 error code sent when a stream is refused before being processed
+const HTTP2_REFUSED_STREAM: u32 = 0x7;
+
+/// Check the HTTP requests on the outlet side of a portal, against the identity of the inlet.
+///
+/// The requests are authorized with the policy of their route, and are sent to the server
+/// with headers describing the identity of the inlet: `X-Ockam-Identifier` and
+/// `X-Ockam-Attribute-<name>`. The `X-Ockam-*` headers sent by the clients are removed.
+/// A denied request is answered with a `403 Forbidden` response, and is not sent to the
+/// server.
+///
+/// HTTP/1.1 and HTTP/2 without TLS (h2c, with prior knowledge) are supported. The upgrades
+/// to HTTP/2 are declined, and the connections upgraded to another protocol, like WebSockets,
+/// are passed through after their initial request was authorized
+pub struct HttpPortalInterceptor {
+    authorization: HttpAuthorization,
+}
+
+impl HttpPortalInterceptor {
+    pub(crate) fn new(authorization: HttpAuthorization) -> Self {
+        Self { authorization }
+    }
+
+    /// Return the headers describing the identity if the request is authorized,
+    /// or the status of the response sent in place of the request
+    async fn authorize(
+        &self,
+        identifier: Option<&Identifier>,
+        method: &str,
+        target: &str,
+    ) -> Result<Decision> {
+        let Some(identifier) = identifier else {
+            warn!("the HTTP request was not received from a secure channel");
+            return Ok(Decision::Denied(403));
+        };
+        // the requests sent through a tunnel could not be checked
+        if method.eq_ignore_ascii_case("CONNECT") {
+            return Ok(Decision::Denied(405));
+        }
+        let Some(path) = request_path(target) else {
+            debug!(%identifier, %target, "rejected an ambiguous HTTP request target");
+            return Ok(Decision::Denied(400));
+        };
+        if !self
+            .authorization
+            .is_authorized(identifier, method, &path)
+            .await?
+        {
+            debug!(%identifier, %method, %path, "denied an HTTP request");
+            return Ok(Decision::Denied(403));
+        }
+        Ok(Decision::Allowed(
+            self.authorization.identity_headers(identifier).await?,
+        ))
+    }
+
+    async fn intercept_http1_request(
+        &self,
+        connection: &mut Connection<Self>,
+        mut head: HttpHead,
+        has_body: bool,
+    ) -> Result<Vec<HttpStreamingMessage>> {
+        let decision = self
+            .authorize(connection.identifier(), head.method(), head.target())
+            .await?;
+        let state = &mut connection.state;
+        match decision {
+            Decision::Allowed(identity_headers) => {
+                // the client could continue with HTTP/2 frames, which would not be checked
+                if head.has_token("upgrade", "h2c") {
+                    head.headers.retain(|(name, _)| {
+                        !name.eq_ignore_ascii_case("upgrade")
+                            && !name.eq_ignore_ascii_case("http2-settings")
+                    });
+                }
+                head.headers
+                    .retain(|(name, _)| !is_ockam_header(name.as_bytes()));
+                head.headers.extend(identity_headers);
+
+                let kind = if head.method().eq_ignore_ascii_case("HEAD") {
+                    RequestKind::Head
+                } else {
+                    RequestKind::Regular
+                };
+                state.shared.request_forwarded(kind)?;
+                state.pending.push(None)?;
+                state.dropping_body = false;
+                Ok(vec![HttpStreamingMessage::Head { head, has_body }])
+            }
+            Decision::Denied(status) => {
+                state.dropping_body = has_body;
+                let response = HttpStreamingMessage::Head {
+                    head: HttpHead::empty_response(status, reason(status)),
+                    has_body: false,
+                };
+                // the response is sent after the responses of the preceding requests
+                if state.pending.is_empty() {
+                    connection.reply(response);
+                } else {
+                    state.pending.push(Some(response))?;
+                }
+                Ok(vec![])
+            }
+        }
+    }
+
+    async fn intercept_http2_request(
+        &self,
+        connection: &mut Connection<Self>,
+        frames: Vec<Http2Frame>,
+    ) -> Result<Vec<HttpStreamingMessage>> {
+        let Some(http2) = connection.state.http2.as_mut() else {
+            return Err(invalid(
+                "HTTP/2 frame received before the connection preface",
+            ));
+        };
+        let frame = &frames[0];
+        let stream_id = frame.stream_id;
+        let denied = http2.denied_streams.contains(&stream_id);
+        let end_stream = frame.has_flag(HTTP2_END_STREAM);
+
+        if frame.frame_type != HTTP2_HEADERS {
+            let open = http2.open_streams.contains(&stream_id);
+            if end_stream || frame.frame_type == HTTP2_RST_STREAM {
+                http2.open_streams.remove(&stream_id);
+                http2.denied_streams.remove(&stream_id);
+            }
+            // the data sent on a stream which was not forwarded is dropped
+            if !denied && (open || frame.frame_type != HTTP2_DATA) {
+                return Ok(vec![HttpStreamingMessage::Frames(frames)]);
+            }
+            // the flow control window used by the dropped data is given back to the client
+            if frame.frame_type == HTTP2_DATA && !frame.payload.is_empty() {
+                connection.reply(HttpStreamingMessage::Frames(vec![Http2Frame {
+                    frame_type: HTTP2_WINDOW_UPDATE,
+                    flags: 0,
+                    stream_id: 0,
+                    payload: (frame.payload.len() as u32).to_be_bytes().to_vec(),
+                }]));
+            }
+            return Ok(vec![]);
+        }
+
+        // every header block is decoded to keep the decoder synchronized with the client
+        let mut fields = http2.decoder.decode(&header_block_fragments(&frames)?)?;
+        if denied {
+            if end_stream {
+                http2.denied_streams.remove(&stream_id);
+            }
+            return Ok(vec![]);
+        }
+        fields.retain(|(name, _)| !is_ockam_header(name));
+
+        // trailers can only be sent on a stream which was forwarded and is still open.
+        // Any other header block on a previous stream could be seen as a new request by
+        // the server, without having been authorized
+        if stream_id <= http2.last_stream_id {
+            if !http2.open_streams.contains(&stream_id) {
+                debug!(%stream_id, "rejected an HTTP/2 header block sent on a closed stream");
+                connection.reply(rst_stream(stream_id, HTTP2_STREAM_CLOSED));
+                return Ok(vec![]);
+            }
+            if end_stream {
+                http2.open_streams.remove(&stream_id);
+            }
+            return Ok(vec![header_block(stream_id, end_stream, &fields)]);
+        }
+        http2.last_stream_id = stream_id;
+        if !end_stream && http2.open_streams.len() >= MAX_OPEN_STREAMS {
+            debug!(%stream_id, "refused an HTTP/2 stream, too many streams are open");
+            connection.reply(rst_stream(stream_id, HTTP2_REFUSED_STREAM));
+            return Ok(vec![]);
+        }
+
+        let method = pseudo_header(&fields, b":method");
+        let path = pseudo_header(&fields, b":path");
+        match self
+            .authorize(connection.identifier(), &method, &path)
+            .await?
+        {
+            Decision::Allowed(identity_headers) => {
+                fields.extend(
+                    identity_headers
+                        .into_iter()
+                        .map(|(name, value)| (name.to_ascii_lowercase().into_bytes(), value)),
+                );
+                if !end_stream {
+                    if let Some(http2) = connection.state.http2.as_mut() {
+                        http2.open_streams.insert(stream_id);
+                    }
+                }
+                Ok(vec![header_block(stream_id, end_stream, &fields)])
+            }
+            Decision::Denied(status) => {
+                let response = [
+                    (b":status".to_vec(), status.to_string().into_bytes()),
+                    (b"content-length".to_vec(), b"0".to_vec()),
+                ];
+                connection.reply(header_block(stream_id, true, &response));
+                if !end_stream {
+                    if let Some(http2) = connection.state.http2.as_mut() {
+                        if http2.denied_streams.len() >= MAX_DENIED_STREAMS {
+                            http2.denied_streams.pop_first();
+                        }
+                        http2.denied_streams.insert(stream_id);
+                    }
+                }
+                Ok(vec![])
+            }
+        }
+    }
+}
+
+enum Decision {
+    Allowed(Vec<(String, Vec<u8>)>),
+    Denied(u16),
+}
+
+/// State of an HTTP connection
+pub struct HttpConnection {
+    shared: HttpConnectionShared,
+    /// Requests waiting for a response, with the response of the denied requests,
+    /// which is sent after the responses of the preceding requests
+    pending: PendingRequests<Option<HttpStreamingMessage>>,
+    /// True while the body of a denied HTTP/1.1 request is received
+    dropping_body: bool,
+    http2: Option<Http2Connection>,
+}
+
+struct Http2Connection {
+    decoder: HpackDecoder,
+    last_stream_id: u32,
+    /// Streams which were forwarded to the server, and can still be used by the client
+    open_streams: BTreeSet<u32>,
+    denied_streams: BTreeSet<u32>,
+}
+
+impl HttpConnection {
+    /// Return the responses of the denied requests which followed the request of a complete
+    /// response
+    fn response_completed(&mut self) -> Vec<HttpStreamingMessage> {
+        self.pending.pop();
+        let mut responses = vec![];
+        while let Some(Some(_)) = self.pending.peek() {
+            if let Some(Some(response)) = self.pending.pop() {
+                responses.push(response);
+            }
+        }
+        responses
+    }
+}
+
+#[async_trait]
+impl ProtocolInterceptor for HttpPortalInterceptor {
+    type Framing = HttpStreamingFraming;
+    type State = HttpConnection;
+
+    fn connection_state(&self) -> HttpConnection {
+        HttpConnection {
+            shared: HttpConnectionShared::default(),
+            pending: PendingRequests::default(),
+            dropping_body: false,
+            http2: None,
+        }
+    }
+
+    fn framing(&self, direction: Direction, state: &HttpConnection) -> HttpStreamingFraming {
+        HttpStreamingFraming::new(
+            direction,
+            state.shared.clone(),
+            MAX_HTTP_HEAD_SIZE,
+            MAX_HTTP2_FRAME_SIZE,
+        )
+    }
+
+    async fn intercept_request(
+        &self,
+        _context: &mut Context,
+        connection: &mut Connection<Self>,
+        request: HttpStreamingMessage,
+    ) -> Result<Vec<HttpStreamingMessage>> {
+        match request {
+            HttpStreamingMessage::Head { head, has_body } => {
+                self.intercept_http1_request(connection, head, has_body)
+                    .await
+            }
+            HttpStreamingMessage::Body { last, .. } if connection.state.dropping_body => {
+                connection.state.dropping_body = !last;
+                Ok(vec![])
+            }
+            HttpStreamingMessage::Preface => {
+                connection.state.http2 = Some(Http2Connection {
+                    decoder: HpackDecoder::new(MAX_HEADER_TABLE_SIZE),
+                    last_stream_id: 0,
+                    open_streams: BTreeSet::new(),
+                    denied_streams: BTreeSet::new(),
+                });
+                Ok(vec![request])
+            }
+            HttpStreamingMessage::Frames(frames) => {
+                self.intercept_http2_request(connection, frames).await
+            }
+            request => Ok(vec![request]),
+        }
+    }
+
+    async fn intercept_response(
+        &self,
+        _context: &mut Context,
+        connection: &mut Connection<Self>,
+        response: HttpStreamingMessage,
+    ) -> Result<Vec<HttpStreamingMessage>> {
+        let completed = match &response {
+            HttpStreamingMessage::Head { head, has_body } => {
+                let status = head.status().unwrap_or_default();
+                !has_body && (status == 101 || status >= 200)
+            }
+            HttpStreamingMessage::Body { last, .. } => *last,
+            // a stream reset by the server can't be used anymore by the client
+            HttpStreamingMessage::Frames(frames) => {
+                if let Some(http2) = connection.state.http2.as_mut() {
+                    for frame in frames.iter().filter(|f| f.frame_type == HTTP2_RST_STREAM) {
+                        http2.open_streams.remove(&frame.stream_id);
+                    }
+                }
+                false
+            }
+            _ => false,
+        };
+        let mut responses = vec![response];
+        if completed {
+            responses.extend(connection.state.response_completed());
+        }
+        Ok(responses)
+    }
+}
+
+/// Concatenate the fragments of a header block, without the padding and the priority
+/// of the HEADERS frame
+fn header_block_fragments(frames: &[Http2Frame]) -> Result<Vec<u8>> {
+    let mut block = vec![];
+    for frame in frames {
+        let mut fragment = &frame.payload[..];
+        if frame.frame_type == HTTP2_HEADERS {
+            if frame.has_flag(HTTP2_PADDED) {
+                let (padding, rest) = fragment
+                    .split_first()
+                    .ok_or_else(|| invalid("invalid HTTP/2 padding"))?;
+                let length = rest
+                    .len()
+                    .checked_sub(*padding as usize)
+                    .ok_or_else(|| invalid("invalid HTTP/2 padding"))?;
+                fragment = &rest[..length];
+            }
+            if frame.has_flag(HTTP2_PRIORITY) {
+                fragment = fragment
+                    .get(5..)
+                    .ok_or_else(|| invalid("invalid HTTP/2 priority"))?;
+            }
+        }
+        block.extend_from_slice(fragment);
+    }
+    Ok(block)
+}
+
+/// Encode header fields in a HEADERS frame, followed by CONTINUATION frames if necessary
+fn header_block(stream_id: u32, end_stream: bool, fields: &[HeaderField]) -> HttpStreamingMessage {
+    let mut block = vec![];
+    encode_without_indexing(fields, &mut block);
+
+    let fragments: Vec<&[u8]> = if block.is_empty() {
+        vec![&[]]
+    } else {
+        block.chunks(DEFAULT_HTTP2_FRAME_SIZE).collect()
+    };
+    let count = fragments.len();
+    let frames = fragments
+        .into_iter()
+        .enumerate()
+        .map(|(index, fragment)| {
+            let mut flags = 0;
+            if index == 0 && end_stream {
+                flags |= HTTP2_END_STREAM;
+            }
+            if index == count - 1 {
+                flags |= HTTP2_END_HEADERS;
+            }
+            Http2Frame {
+                frame_type: if index == 0 {
+                    HTTP2_HEADERS
+                } else {
+                    HTTP2_CONTINUATION
+                },
+                flags,
+                stream_id,
+                payload: fragment.to_vec(),
+            }
+        })
+        .collect();
+    HttpStreamingMessage::Frames(frames)
+}
+
+/// Reset a stream with an error code
+fn rst_stream(stream_id: u32, error_code: u32) -> HttpStreamingMessage {
+    HttpStreamingMessage::Frames(vec![Http2Frame {
+        frame_type: HTTP2_RST_STREAM,
+        flags: 0,
+        stream_id,
+        payload: error_code.to_be_bytes().to_vec(),
+    }])
+}
+
+fn pseudo_header(fields: &[HeaderField], name: &[u8]) -> String {
+    fields
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, v)| String::from_utf8_lossy(v).to_string())
+        .unwrap_or_default()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        400 => "Bad Request",
+        403 => "Forbidden",
+        405 => "Method Not Allowed",
+        _ => "",
+    }
+}
+
+fn invalid(message: &'static str) -> Error {
+    Error::new(Origin::Transport, Kind::Protocol, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_portal::route_policy::HttpRoutePolicies;
+    use crate::protocol_interceptor::testing::{InterceptorHarness, PassThrough};
+    use crate::protocol_interceptor::{Framing, ProtocolInterceptorFactory};
+    use ockam_abac::expr::{eq, str};
+    use ockam_abac::{subject_identifier_attribute, PolicyExpression};
+    use ockam_transport_tcp::PortalInterceptorFactory;
+
+    async fn factory(allowed: &Identifier) -> Result<impl PortalInterceptorFactory> {
+        let identities = ockam::identity::identities().await?;
+        let expression = eq([subject_identifier_attribute(), str(allowed.to_string())]);
+        let authorization = HttpAuthorization::new(
+            HttpRoutePolicies::default().with_expression(
+                None,
+                "/admin",
+                PolicyExpression::FullExpression(expression),
+            ),
+            vec![],
+            identities.identities_attributes(),
+            None,
+        );
+        Ok(ProtocolInterceptorFactory::new(HttpPortalInterceptor::new(
+            authorization,
+        )))
+    }
+
+    async fn identifiers() -> Result<(Identifier, Identifier)> {
+        let identities = ockam::identity::identities().await?;
+        Ok((
+            identities.identities_creation().create_identity().await?,
+            identities.identities_creation().create_identity().await?,
+        ))
+    }
+
+    #[ockam_macros::test]
+    async fn http1_requests_are_checked(context: &mut Context) -> Result<()> {
+        let (allowed, denied) = identifiers().await?;
+        let outlet = factory(&allowed).await?;
+
+        let requests = b"GET /public HTTP/1.1\r\nX-Ockam-Identifier: spoofed\r\n\r\n\
+            POST /admin/users HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nuser\r\n0\r\n\r\n\
+            GET /public/../admin HTTP/1.1\r\n\r\n";
+        let response = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+        let replayed = InterceptorHarness::new(&PassThrough, &outlet)
+            .with_chunk_size(7)
+            .with_inlet_identifier(denied.clone())
+            .replay(
+                context,
+                vec![
+                    (Direction::FromInletToOutlet, requests.to_vec()),
+                    (Direction::FromOutletToInlet, response.to_vec()),
+                ],
+            )
+            .await?;
+
+        // only the first request is sent, with the identifier of the inlet
+        assert_eq!(
+            String::from_utf8_lossy(&replayed.received_by_server),
+            format!("GET /public HTTP/1.1\r\nX-Ockam-Identifier: {denied}\r\n\r\n")
+        );
+        // the denied requests are answered after the response of the first request
+        assert_eq!(
+            String::from_utf8_lossy(&replayed.received_by_client),
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok\
+            HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n\
+            HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n"
+        );
+
+        let request = b"POST /admin/users HTTP/1.1\r\nContent-Length: 4\r\n\r\nuser";
+        let replayed = InterceptorHarness::new(&PassThrough, &outlet)
+            .with_inlet_identifier(allowed.clone())
+            .replay(
+                context,
+                vec![(Direction::FromInletToOutlet, request.to_vec())],
+            )
+            .await?;
+        assert_eq!(
+            String::from_utf8_lossy(&replayed.received_by_server),
+            format!("POST /admin/users HTTP/1.1\r\nContent-Length: 4\r\nX-Ockam-Identifier: {allowed}\r\n\r\nuser")
+        );
+        Ok(())
+    }
+
+    #[ockam_macros::test]
+    async fn upgraded_connections_are_passed_through(context: &mut Context) -> Result<()> {
+        let (allowed, _) = identifiers().await?;
+        let outlet = factory(&allowed).await?;
+
+        let request = b"GET /chat HTTP/1.1\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\r\n";
+        let response =
+            b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\r\n\x81\x02hi";
+        let replayed = InterceptorHarness::new(&PassThrough, &outlet)
+            .with_inlet_identifier(allowed.clone())
+            .replay(
+                context,
+                vec![
+                    (Direction::FromInletToOutlet, request.to_vec()),
+                    (Direction::FromOutletToInlet, response.to_vec()),
+                    (
+                        Direction::FromInletToOutlet,
+                        b"\x81\x82GET /admin HTTP/1.1\r\n\r\n".to_vec(),
+                    ),
+                ],
+            )
+            .await?;
+
+        assert!(replayed
+            .received_by_server
+            .ends_with(b"\r\n\r\n\x81\x82GET /admin HTTP/1.1\r\n\r\n"));
+        assert_eq!(replayed.received_by_client, response);
+        Ok(())
+    }
+
+    #[ockam_macros::test]
+    async fn http2_requests_are_checked(context: &mut Context) -> Result<()> {
+        let (allowed, denied) = identifiers().await?;
+        let outlet = factory(&allowed).await?;
+
+        let fields = |path: &str| -> Vec<HeaderField> {
+            vec![
+                (b":method".to_vec(), b"POST".to_vec()),
+                (b":scheme".to_vec(), b"http".to_vec()),
+                (b":path".to_vec(), path.as_bytes().to_vec()),
+                (b"x-ockam-identifier".to_vec(), b"spoofed".to_vec()),
+            ]
+        };
+        let encode = |messages: Vec<HttpStreamingMessage>| -> Vec<u8> {
+            let mut framing = HttpStreamingFraming::new(
+                Direction::FromInletToOutlet,
+                HttpConnectionShared::default(),
+                MAX_HTTP_HEAD_SIZE,
+                MAX_HTTP2_FRAME_SIZE,
+            );
+            let mut buffer = vec![];
+            for message in messages {
+                framing.encode(message, &mut buffer).unwrap();
+            }
+            buffer
+        };
+        let data = |stream_id: u32| {
+            HttpStreamingMessage::Frames(vec![Http2Frame {
+                frame_type: HTTP2_DATA,
+                flags: HTTP2_END_STREAM,
+                stream_id,
+                payload: b"body".to_vec(),
+            }])
+        };
+        let settings = HttpStreamingMessage::Frames(vec![Http2Frame {
+            frame_type: 0x4,
+            flags: 0,
+            stream_id: 0,
+            payload: vec![],
+        }]);
+
+        let requests = encode(vec![
+            HttpStreamingMessage::Preface,
+            settings.clone(),
+            header_block(1, false, &fields("/public")),
+            data(1),
+            header_block(3, false, &fields("/admin")),
+            data(3),
+        ]);
+        let replayed = InterceptorHarness::new(&PassThrough, &outlet)
+            .with_chunk_size(5)
+            .with_inlet_identifier(denied.clone())
+            .replay(context, vec![(Direction::FromInletToOutlet, requests)])
+            .await?;
+
+        let mut allowed_fields = fields("/public");
+        allowed_fields[3] = (
+            b"x-ockam-identifier".to_vec(),
+            denied.to_string().into_bytes(),
+        );
+        assert_eq!(
+            replayed.received_by_server,
+            encode(vec![
+                HttpStreamingMessage::Preface,
+                settings,
+                header_block(1, false, &allowed_fields),
+                data(1),
+            ])
+        );
+
+        let forbidden = [
+            (b":status".to_vec(), b"403".to_vec()),
+            (b"content-length".to_vec(), b"0".to_vec()),
+        ];
+        let window_update = HttpStreamingMessage::Frames(vec![Http2Frame {
+            frame_type: HTTP2_WINDOW_UPDATE,
+            flags: 0,
+            stream_id: 0,
+            payload: 4u32.to_be_bytes().to_vec(),
+        }]);
+        assert_eq!(
+            replayed.received_by_client,
+            encode(vec![header_block(3, true, &forbidden), window_update])
+        );
+        Ok(())
+    }
+
+    #[ockam_macros::test]
+    async fn http2_headers_on_closed_streams_are_rejected(context: &mut Context) -> Result<()> {
+        let (allowed, denied) = identifiers().await?;
+        let outlet = factory(&allowed).await?;
+
+        let fields = |path: &str| -> Vec<HeaderField> {
+            vec![
+                (b":method".to_vec(), b"GET".to_vec()),
+                (b":scheme".to_vec(), b"http".to_vec()),
+                (b":path".to_vec(), path.as_bytes().to_vec()),
+            ]
+        };
+        let trailers = vec![(b"grpc-status".to_vec(), b"0".to_vec())];
+        let encode = |messages: Vec<HttpStreamingMessage>| -> Vec<u8> {
+            let mut framing = HttpStreamingFraming::new(
+                Direction::FromInletToOutlet,
+                HttpConnectionShared::default(),
+                MAX_HTTP_HEAD_SIZE,
+                MAX_HTTP2_FRAME_SIZE,
+            );
+            let mut buffer = vec![];
+            for message in messages {
+                framing.encode(message, &mut buffer).unwrap();
+            }
+            buffer
+        };
+
+        // a denied request is sent again on the same stream, and trailers are sent
+        // on an allowed stream, which is still open
+        let requests = encode(vec![
+            HttpStreamingMessage::Preface,
+            header_block(1, true, &fields("/admin")),
+            header_block(1, true, &fields("/admin")),
+            header_block(3, false, &fields("/public")),
+            header_block(3, true, &trailers),
+            header_block(3, true, &trailers),
+        ]);
+        let replayed = InterceptorHarness::new(&PassThrough, &outlet)
+            .with_inlet_identifier(denied.clone())
+            .replay(context, vec![(Direction::FromInletToOutlet, requests)])
+            .await?;
+
+        let mut allowed_fields = fields("/public");
+        allowed_fields.push((
+            b"x-ockam-identifier".to_vec(),
+            denied.to_string().into_bytes(),
+        ));
+        assert_eq!(
+            replayed.received_by_server,
+            encode(vec![
+                HttpStreamingMessage::Preface,
+                header_block(3, false, &allowed_fields),
+                header_block(3, true, &trailers),
+            ])
+        );
+
+        let forbidden = [
+            (b":status".to_vec(), b"403".to_vec()),
+            (b"content-length".to_vec(), b"0".to_vec()),
+        ];
+        assert_eq!(
+            replayed.received_by_client,
+            encode(vec![
+                header_block(1, true, &forbidden),
+                rst_stream(1, HTTP2_STREAM_CLOSED),
+                rst_stream(3, HTTP2_STREAM_CLOSED),
+            ])
+        );
+        Ok(())
+    }
+}
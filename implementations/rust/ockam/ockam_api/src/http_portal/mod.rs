@@ -0,0 +1,23 @@
+//! HTTP-aware portals.
+//!
+//! The outlet checks each HTTP request against the identity of the inlet node: a policy
+//! expression can be set for the requests matching a method and a path prefix, and the
+//! authorized requests are sent to the server with headers describing the identity, so that
+//! internal web services don't need to authenticate their users.
+//! HTTP/1.1 and HTTP/2 without TLS (h2c) are supported, the connections are delimited with the
+//! [`HttpStreamingFraming`](crate::protocol_interceptor::HttpStreamingFraming) of the
+//! protocol interceptor framework.
+
+mod hpack;
+mod huffman;
+mod interceptor;
+pub mod portal;
+mod route_policy;
+
+pub use hpack::{encode_without_indexing, HeaderField, HpackDecoder};
+pub use interceptor::{HttpConnection, HttpPortalInterceptor, MAX_HTTP_HEAD_SIZE};
+pub use portal::{CreateHttpOutlet, HttpPortals};
+pub use route_policy::{
+    is_valid_attribute_name, parse_http_route_policy, request_path, HttpRoutePolicies,
+    HttpRoutePolicy, ATTRIBUTE_HEADER_PREFIX, IDENTIFIER_HEADER, OCKAM_HEADER_PREFIX,
+};
@@ -0,0 +1,209 @@
+use crate::http_portal::interceptor::HttpPortalInterceptor;
+use crate::http_portal::route_policy::{HttpAuthorization, HttpRoutePolicies};
+use crate::nodes::models::portal::{CreateOutlet, OutletAccessControl, OutletStatus};
+use crate::nodes::{BackgroundNodeClient, NodeManagerWorker};
+use crate::protocol_interceptor::ProtocolInterceptorFactory;
+use crate::{ApiError, DefaultAddress};
+use minicbor::{CborLen, Decode, Encode};
+use ockam::flow_control::FlowControls;
+use ockam::{Address, Context, Result};
+use ockam_abac::PolicyExpression;
+use ockam_abac::{Action, Resource, ResourceType};
+use ockam_core::api::{Error, Request, Response};
+use ockam_core::async_trait;
+use ockam_transport_core::HostnamePort;
+use ockam_transport_tcp::{read_portal_payload_length, PortalOutletInterceptor};
+use std::sync::Arc;
+
+impl NodeManagerWorker {
+    pub(crate) async fn start_http_outlet_service(
+        &self,
+        ctx: &Context,
+        body: CreateHttpOutlet,
+    ) -> Result<Response<OutletStatus>, Response<Error>> {
+        debug!("Starting HTTP Outlet service");
+        let CreateOutlet {
+            hostname_port,
+            worker_addr,
+            reachable_from_default_secure_channel,
+            policy_expression,
+            privileged,
+            tls,
+            unix_socket_path,
+            allowed_destinations,
+            destination_policy_expression: _,
+        } = body.tcp_outlet;
+        if unix_socket_path.is_some() {
+            return Err(Response::bad_request_no_request(
+                "HTTP outlets can't connect to a Unix domain socket",
+            ));
+        }
+        if allowed_destinations.is_some() {
+            return Err(Response::bad_request_no_request(
+                "HTTP outlets can't forward to dynamic destinations",
+            ));
+        }
+        let address = self
+            .node_manager
+            .registry
+            .outlets
+            .generate_worker_addr(worker_addr);
+        let outlet_address: Address = format!("{}_outlet", address.address()).into();
+
+        let authorization = HttpAuthorization::new(
+            body.route_policies,
+            body.injected_attributes,
+            self.node_manager
+                .cli_state
+                .identities_attributes(&self.node_manager.node_name()),
+            self.node_manager.project_authority(),
+        );
+
+        // Start the interceptor
+        self.create_http_portal_outlet_interceptor(
+            ctx,
+            address,
+            outlet_address.clone(),
+            policy_expression.clone(),
+            authorization,
+        )
+        .await
+        .map_err(|e| Response::bad_request_no_request(&format!("{e:?}")))?;
+
+        // Start the outlet
+        match self
+            .node_manager
+            .create_outlet(
+                ctx,
+                hostname_port,
+                tls,
+                Some(outlet_address),
+                reachable_from_default_secure_channel,
+                OutletAccessControl::WithPolicyExpression(policy_expression),
+                privileged,
+            )
+            .await
+        {
+            Ok(outlet_status) => Ok(Response::ok().body(outlet_status)),
+            Err(e) => Err(Response::bad_request_no_request(&format!("{e:?}"))),
+        }
+    }
+
+    async fn create_http_portal_outlet_interceptor(
+        &self,
+        ctx: &Context,
+        interceptor_address: Address,
+        outlet_address: Address,
+        outlet_policy_expression: Option<PolicyExpression>,
+        authorization: HttpAuthorization,
+    ) -> Result<(), Error> {
+        debug!(%interceptor_address, %outlet_address, ?outlet_policy_expression, "Creating http portal outlet interceptor");
+        let default_secure_channel_listener_flow_control_id = ctx
+            .flow_controls()
+            .get_flow_control_with_spawner(&DefaultAddress::SECURE_CHANNEL_LISTENER.into())
+            .ok_or_else(|| {
+                ApiError::core("Unable to get flow control for secure channel listener")
+            })?;
+
+        let policy_access_control = self
+            .node_manager
+            .policy_access_control(
+                self.node_manager.project_authority().clone(),
+                Resource::new(outlet_address.to_string(), ResourceType::TcpOutlet),
+                Action::HandleMessage,
+                outlet_policy_expression,
+            )
+            .await?;
+
+        let spawner_flow_control_id = FlowControls::generate_flow_control_id();
+
+        let interceptor_factory = Arc::new(ProtocolInterceptorFactory::new(
+            HttpPortalInterceptor::new(authorization),
+        ));
+
+        PortalOutletInterceptor::create(
+            ctx,
+            interceptor_address.clone(),
+            Some(spawner_flow_control_id.clone()),
+            interceptor_factory,
+            Arc::new(policy_access_control.create_outgoing(ctx)?),
+            Arc::new(policy_access_control.create_incoming()),
+            read_portal_payload_length(),
+        )?;
+
+        // every secure channel can reach this service
+        let flow_controls = ctx.flow_controls();
+        flow_controls.add_consumer(
+            &interceptor_address,
+            &default_secure_channel_listener_flow_control_id,
+        );
+
+        // this spawner flow control id is used to control communication with dynamically created
+        // outlets
+        flow_controls.add_spawner(&interceptor_address, &spawner_flow_control_id);
+
+        // allow communication with the tcp outlet
+        flow_controls.add_consumer(&outlet_address, &spawner_flow_control_id);
+        Ok(())
+    }
+}
+
+#[async_trait]
+pub trait HttpPortals {
+    async fn create_http_outlet(
+        &self,
+        ctx: &Context,
+        to: HostnamePort,
+        from: Option<&Address>,
+        policy_expression: Option<PolicyExpression>,
+        route_policies: HttpRoutePolicies,
+        injected_attributes: Vec<String>,
+    ) -> miette::Result<OutletStatus>;
+}
+
+#[async_trait]
+impl HttpPortals for BackgroundNodeClient {
+    #[instrument(skip(self, ctx))]
+    async fn create_http_outlet(
+        &self,
+        ctx: &Context,
+        to: HostnamePort,
+        from: Option<&Address>,
+        policy_expression: Option<PolicyExpression>,
+        route_policies: HttpRoutePolicies,
+        injected_attributes: Vec<String>,
+    ) -> miette::Result<OutletStatus> {
+        let mut outlet_payload = CreateOutlet::new(to, false, from.cloned(), true, false);
+        if let Some(policy_expression) = policy_expression {
+            outlet_payload.set_policy_expression(policy_expression);
+        }
+        let payload = CreateHttpOutlet::new(outlet_payload, route_policies, injected_attributes);
+        let req = Request::post("/node/http_outlet").body(payload);
+        self.ask(ctx, req).await
+    }
+}
+
+/// Request body to create an HTTP outlet
+#[derive(Clone, Debug, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct CreateHttpOutlet {
+    #[n(1)] pub(crate) tcp_outlet: CreateOutlet,
+    #[n(2)] pub(crate) route_policies: HttpRoutePolicies,
+    /// Attributes of the inlet identity sent to the server in headers
+    #[n(3)] pub(crate) injected_attributes: Vec<String>,
+}
+
+impl CreateHttpOutlet {
+    pub fn new(
+        tcp_outlet: CreateOutlet,
+        route_policies: HttpRoutePolicies,
+        injected_attributes: Vec<String>,
+    ) -> Self {
+        Self {
+            tcp_outlet,
+            route_policies,
+            injected_attributes,
+        }
+    }
+}
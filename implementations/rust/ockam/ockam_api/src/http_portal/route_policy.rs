@@ -0,0 +1,469 @@
+use minicbor::{CborLen, Decode, Encode};
+use ockam::identity::{Identifier, IdentitiesAttributes};
+use ockam_abac::expr::str;
+use ockam_abac::{Abac, Env, PolicyExpression};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Header containing the identifier of the inlet node, added to the requests
+pub const IDENTIFIER_HEADER: &str = "X-Ockam-Identifier";
+/// Prefix of the headers containing the attributes of the inlet node, added to the requests
+pub const ATTRIBUTE_HEADER_PREFIX: &str = "X-Ockam-Attribute-";
+/// Prefix of the headers set by the outlet. They are removed from the requests of the clients
+pub const OCKAM_HEADER_PREFIX: &str = "X-Ockam-";
+
+/// Policy expression checked for the requests matching a method and a path prefix
+#[derive(Debug, Clone, PartialEq, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct HttpRoutePolicy {
+    /// Method of the requests, all the methods when not set
+    #[n(1)] pub method: Option<String>,
+    /// Prefix of the paths, matching whole segments: `/api` matches `/api` and `/api/users`,
+    /// but not `/apis`
+    #[n(2)] pub path_prefix: String,
+    #[n(3)] pub expression: PolicyExpression,
+}
+
+/// Per-route policies of an HTTP outlet, checked against the identity of the inlet.
+/// The policy of a request is the policy with the longest prefix matching its path.
+/// For the same prefix, a policy for the method of the request is preferred
+#[derive(Debug, Clone, Default, PartialEq, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+#[cbor(transparent)]
+pub struct HttpRoutePolicies(#[n(0)] Vec<HttpRoutePolicy>);
+
+impl HttpRoutePolicies {
+    /// Set the policy expression of the requests matching a method and a path prefix
+    pub fn with_expression(
+        mut self,
+        method: Option<String>,
+        path_prefix: impl Into<String>,
+        expression: PolicyExpression,
+    ) -> Self {
+        let method = method.map(|m| m.to_ascii_uppercase());
+        let path_prefix = normalize_prefix(&path_prefix.into());
+        match self
+            .0
+            .iter_mut()
+            .find(|p| p.method == method && p.path_prefix == path_prefix)
+        {
+            Some(policy) => policy.expression = expression,
+            None => self.0.push(HttpRoutePolicy {
+                method,
+                path_prefix,
+                expression,
+            }),
+        }
+        self
+    }
+
+    /// Return true if no route is configured
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Return the policy expression of a request, if one was configured for its route.
+    /// The path must be normalized with [`request_path`]
+    pub fn expression(&self, method: &str, path: &str) -> Option<&PolicyExpression> {
+        self.0
+            .iter()
+            .filter(|p| matches_prefix(&p.path_prefix, path))
+            .filter(|p| {
+                p.method
+                    .as_ref()
+                    .map_or(true, |m| m.eq_ignore_ascii_case(method))
+            })
+            .max_by_key(|p| (p.path_prefix.len(), p.method.is_some()))
+            .map(|p| &p.expression)
+    }
+}
+
+/// Remove the trailing slash of a prefix, since the prefixes match whole segments
+fn normalize_prefix(prefix: &str) -> String {
+    let prefix = prefix.trim_end_matches('/');
+    if prefix.is_empty() {
+        "/".to_string()
+    } else {
+        prefix.to_string()
+    }
+}
+
+fn matches_prefix(prefix: &str, path: &str) -> bool {
+    prefix == "/"
+        || path
+            .strip_prefix(prefix)
+            .map_or(false, |rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Return the path of a request target, as interpreted by the server, to match it with
+/// the route prefixes.
+///
+/// The query is removed, the percent-encoded characters are decoded, and the path parameters
+/// of each segment (`;name=value`) are ignored.
+/// Since servers resolve paths differently, the paths which could be resolved to a different
+/// route are rejected: paths with `.` or `..` segments, empty segments, backslashes,
+/// or encoded slashes
+pub fn request_path(target: &str) -> Option<String> {
+    if target == "*" {
+        return Some("/".to_string());
+    }
+    let path = match target.find("://") {
+        // absolute form, `http://host/path`
+        Some(scheme_end) => {
+            let authority = &target[scheme_end + 3..];
+            match authority.find(['/', '?', '#']) {
+                Some(start) if authority[start..].starts_with('/') => &authority[start..],
+                _ => "/",
+            }
+        }
+        None if target.starts_with('/') => target,
+        None => return None,
+    };
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+
+    let decoded = percent_decode(path)?;
+    let mut normalized = String::with_capacity(decoded.len());
+    let segments: Vec<&str> = decoded.split('/').skip(1).collect();
+    for (index, segment) in segments.iter().enumerate() {
+        let segment = segment.split(';').next().unwrap_or_default();
+        let is_last = index == segments.len() - 1;
+        if (segment.is_empty() && !is_last) || segment == "." || segment == ".." {
+            return None;
+        }
+        normalized.push('/');
+        normalized.push_str(segment);
+    }
+    Some(normalized)
+}
+
+fn percent_decode(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let byte = match bytes[index] {
+            b'%' => {
+                let hex = path.get(index + 1..index + 3)?;
+                index += 3;
+                match u8::from_str_radix(hex, 16).ok()? {
+                    // encoded separators are interpreted differently by servers
+                    b'/' | b'\\' | 0 => return None,
+                    byte => byte,
+                }
+            }
+            b'\\' => return None,
+            byte => {
+                index += 1;
+                byte
+            }
+        };
+        decoded.push(byte);
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Parse the policy of a route, as `[METHOD ]PATH_PREFIX=EXPRESSION`,
+/// for example `GET /reports=(= subject.role "analyst")`
+pub fn parse_http_route_policy(
+    value: &str,
+) -> Result<(Option<String>, String, PolicyExpression), String> {
+    let Some((route, expression)) = value.split_once('=') else {
+        return Err(format!(
+            "invalid route policy '{value}', expected [METHOD ]<PATH_PREFIX>=<EXPRESSION>"
+        ));
+    };
+    let (method, path_prefix) = match route.trim().split_once(char::is_whitespace) {
+        Some((method, path_prefix)) => (Some(method.trim()), path_prefix.trim()),
+        None => (None, route.trim()),
+    };
+    if let Some(method) = method {
+        if method.is_empty() || !method.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(format!("invalid HTTP method '{method}'"));
+        }
+    }
+    if !path_prefix.starts_with('/') {
+        return Err(format!(
+            "invalid path prefix '{path_prefix}', it must start with '/'"
+        ));
+    }
+    let expression = PolicyExpression::from_str(expression.trim())
+        .map_err(|e| format!("invalid policy expression for '{}': {e}", route.trim()))?;
+    Ok((
+        method.map(|m| m.to_ascii_uppercase()),
+        path_prefix.to_string(),
+        expression,
+    ))
+}
+
+/// Return true if an attribute name can be sent as part of a header name
+pub fn is_valid_attribute_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+/// Check the route policies against the identity of an HTTP inlet, and provide the headers
+/// describing this identity to the server.
+/// The expressions can use the `resource.id` attribute, set to the path of the request,
+/// and the `action.id` attribute, set to its method
+#[derive(Clone)]
+pub(crate) struct HttpAuthorization {
+    policies: HttpRoutePolicies,
+    injected_attributes: Vec<String>,
+    identities_attributes: Arc<IdentitiesAttributes>,
+    authority: Option<Identifier>,
+}
+
+impl HttpAuthorization {
+    pub(crate) fn new(
+        policies: HttpRoutePolicies,
+        injected_attributes: Vec<String>,
+        identities_attributes: Arc<IdentitiesAttributes>,
+        authority: Option<Identifier>,
+    ) -> Self {
+        Self {
+            policies,
+            injected_attributes,
+            identities_attributes,
+            authority,
+        }
+    }
+
+    /// Return true if the identity can send the request.
+    /// Routes without a policy expression are only protected by the policy of the outlet
+    pub(crate) async fn is_authorized(
+        &self,
+        identifier: &Identifier,
+        method: &str,
+        path: &str,
+    ) -> ockam_core::Result<bool> {
+        let expression = match self.policies.expression(method, path) {
+            Some(expression) => expression.to_expression(),
+            None => return Ok(true),
+        };
+
+        let mut environment = Env::new();
+        environment.put("resource.id", str(path.to_string()));
+        environment.put("action.id", str(method.to_ascii_uppercase()));
+        Abac::is_identity_authorized_static(
+            self.identities_attributes.clone(),
+            &environment,
+            self.authority.as_ref(),
+            identifier,
+            &expression,
+        )
+        .await
+    }
+
+    /// Return the headers added to the requests of an identity: its identifier, and the
+    /// selected attributes attested by the authority.
+    /// The attribute values which can't be sent in a header are skipped
+    pub(crate) async fn identity_headers(
+        &self,
+        identifier: &Identifier,
+    ) -> ockam_core::Result<Vec<(String, Vec<u8>)>> {
+        let mut headers = vec![(
+            IDENTIFIER_HEADER.to_string(),
+            identifier.to_string().into_bytes(),
+        )];
+        if self.injected_attributes.is_empty() {
+            return Ok(headers);
+        }
+        let Some(authority) = &self.authority else {
+            return Ok(headers);
+        };
+        let Some(entry) = self
+            .identities_attributes
+            .get_attributes(identifier, authority)
+            .await?
+        else {
+            return Ok(headers);
+        };
+
+        for name in &self.injected_attributes {
+            let Some(value) = entry.attrs().get(name.as_bytes()) else {
+                continue;
+            };
+            if value
+                .iter()
+                .all(|b| *b == b'\t' || (b' '..=b'~').contains(b))
+            {
+                headers.push((format!("{ATTRIBUTE_HEADER_PREFIX}{name}"), value.clone()));
+            } else {
+                warn!(%identifier, attribute = %name, "the attribute value can't be sent in a header");
+            }
+        }
+        Ok(headers)
+    }
+}
+
+/// Return true if a header is reserved to the outlet
+pub(crate) fn is_ockam_header(name: &[u8]) -> bool {
+    name.len() >= OCKAM_HEADER_PREFIX.len()
+        && name[..OCKAM_HEADER_PREFIX.len()].eq_ignore_ascii_case(OCKAM_HEADER_PREFIX.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::identity::utils::now;
+    use ockam::identity::AttributesEntry;
+    use ockam_abac::expr::eq;
+    use ockam_abac::{subject_identifier_attribute, Expr};
+    use ockam_core::compat::collections::BTreeMap;
+    use ockam_node::Context;
+
+    fn constant(value: bool) -> PolicyExpression {
+        PolicyExpression::FullExpression(if value {
+            Expr::CONST_TRUE
+        } else {
+            Expr::CONST_FALSE
+        })
+    }
+
+    #[test]
+    fn longest_prefix_is_used() {
+        let policies = HttpRoutePolicies::default()
+            .with_expression(None, "/", constant(true))
+            .with_expression(None, "/api/", constant(false))
+            .with_expression(Some("get".to_string()), "/api", constant(true))
+            .with_expression(None, "/api/admin", constant(false));
+
+        assert_eq!(policies.expression("GET", "/"), Some(&constant(true)));
+        assert_eq!(policies.expression("GET", "/apis"), Some(&constant(true)));
+        assert_eq!(policies.expression("GET", "/api"), Some(&constant(true)));
+        assert_eq!(
+            policies.expression("get", "/api/users"),
+            Some(&constant(true))
+        );
+        assert_eq!(
+            policies.expression("POST", "/api/users"),
+            Some(&constant(false))
+        );
+        assert_eq!(
+            policies.expression("GET", "/api/admin/users"),
+            Some(&constant(false))
+        );
+        assert!(HttpRoutePolicies::default()
+            .with_expression(None, "/api", constant(true))
+            .expression("GET", "/other")
+            .is_none());
+    }
+
+    #[test]
+    fn request_paths_are_normalized() {
+        assert_eq!(request_path("/api/users?id=1").unwrap(), "/api/users");
+        assert_eq!(request_path("/api/").unwrap(), "/api/");
+        assert_eq!(request_path("/%61pi/users").unwrap(), "/api/users");
+        assert_eq!(request_path("/api;v=1/users").unwrap(), "/api/users");
+        assert_eq!(request_path("http://host:8080/api?x").unwrap(), "/api");
+        assert_eq!(request_path("http://host?x").unwrap(), "/");
+        assert_eq!(request_path("*").unwrap(), "/");
+
+        for rejected in [
+            "/public/../admin",
+            "/public/%2e%2e/admin",
+            "/public/..;/admin",
+            "/public%2fadmin",
+            "/public\\admin",
+            "//admin",
+            "/./admin",
+            "/%zz",
+            "host:443",
+        ] {
+            assert!(request_path(rejected).is_none(), "{rejected}");
+        }
+    }
+
+    #[test]
+    fn parse_route_policies() {
+        let (method, prefix, expression) =
+            parse_http_route_policy(r#"get /reports=(= subject.role "analyst")"#).unwrap();
+        assert_eq!(method.as_deref(), Some("GET"));
+        assert_eq!(prefix, "/reports");
+        assert_eq!(
+            expression,
+            PolicyExpression::from_str(r#"(= subject.role "analyst")"#).unwrap()
+        );
+
+        let (method, prefix, _) = parse_http_route_policy("/=true").unwrap();
+        assert_eq!(method, None);
+        assert_eq!(prefix, "/");
+
+        assert!(parse_http_route_policy("/reports").is_err());
+        assert!(parse_http_route_policy("reports=true").is_err());
+        assert!(parse_http_route_policy("G3T /reports=true").is_err());
+    }
+
+    #[test]
+    fn ockam_headers_are_recognized() {
+        assert!(is_ockam_header(b"x-ockam-identifier"));
+        assert!(is_ockam_header(b"X-OCKAM-Attribute-role"));
+        assert!(!is_ockam_header(b"x-ockam"));
+        assert!(!is_ockam_header(b"x-request-id"));
+    }
+
+    #[ockam_macros::test]
+    async fn check_route_policies(_ctx: &mut Context) -> ockam_core::Result<()> {
+        let identities = ockam::identity::identities().await?;
+        let authority = identities.identities_creation().create_identity().await?;
+        let allowed = identities.identities_creation().create_identity().await?;
+        let denied = identities.identities_creation().create_identity().await?;
+
+        let attributes = BTreeMap::from([
+            (b"role".to_vec(), b"analyst".to_vec()),
+            (b"team".to_vec(), b"line\nbreak".to_vec()),
+        ]);
+        identities
+            .identities_attributes()
+            .put_attributes(
+                &allowed,
+                AttributesEntry::new(attributes, now()?, None, Some(authority.clone())),
+            )
+            .await?;
+
+        let expression = eq([subject_identifier_attribute(), str(allowed.to_string())]);
+        let authorization = HttpAuthorization::new(
+            HttpRoutePolicies::default().with_expression(
+                Some("POST".to_string()),
+                "/reports",
+                PolicyExpression::FullExpression(expression),
+            ),
+            vec!["role".to_string(), "team".to_string()],
+            identities.identities_attributes(),
+            Some(authority),
+        );
+
+        assert!(
+            authorization
+                .is_authorized(&allowed, "POST", "/reports/daily")
+                .await?
+        );
+        assert!(
+            !authorization
+                .is_authorized(&denied, "POST", "/reports")
+                .await?
+        );
+        // routes without a policy are not restricted
+        assert!(
+            authorization
+                .is_authorized(&denied, "GET", "/reports")
+                .await?
+        );
+
+        let headers = authorization.identity_headers(&allowed).await?;
+        assert_eq!(
+            headers,
+            vec![
+                (
+                    IDENTIFIER_HEADER.to_string(),
+                    allowed.to_string().into_bytes()
+                ),
+                ("X-Ockam-Attribute-role".to_string(), b"analyst".to_vec()),
+            ]
+        );
+        Ok(())
+    }
+}
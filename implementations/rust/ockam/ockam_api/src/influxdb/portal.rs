@@ -1,4 +1,4 @@
-use crate::influxdb::gateway::interceptor::HttpAuthInterceptorFactory;
+use crate::influxdb::gateway::interceptor::HttpAuthInterceptor;
 use crate::influxdb::gateway::token_lease_refresher::TokenLeaseRefresher;
 use crate::influxdb::{LeaseUsage, StartInfluxDBLeaseIssuerRequest};
 use crate::nodes::models::portal::{
@@ -39,7 +39,20 @@ impl NodeManagerWorker {
             policy_expression,
             privileged,
             tls,
+            unix_socket_path,
+            allowed_destinations,
+            destination_policy_expression: _,
         } = body.tcp_outlet;
+        if unix_socket_path.is_some() {
+            return Err(Response::bad_request_no_request(
+                "InfluxDB outlets can't connect to a Unix domain socket",
+            ));
+        }
+        if allowed_destinations.is_some() {
+            return Err(Response::bad_request_no_request(
+                "InfluxDB outlets can't forward to dynamic destinations",
+            ));
+        }
         let address = self
             .node_manager
             .registry
@@ -121,7 +134,20 @@ impl NodeManagerWorker {
             disable_tcp_fallback,
             privileged,
             tls_certificate_provider,
+            unix_socket,
+            additional_outlet_addrs,
+            load_balancing: _,
         } = body.tcp_inlet.clone();
+        if unix_socket.is_some() {
+            return Err(Response::bad_request_no_request(
+                "InfluxDB inlets can't listen on a Unix domain socket",
+            ));
+        }
+        if additional_outlet_addrs.is_some() {
+            return Err(Response::bad_request_no_request(
+                "InfluxDB inlets can't balance connections across several outlets",
+            ));
+        }
 
         //TODO: should be an easier way to tweak the multiaddr
         let mut issuer_route = outlet_addr.clone();
@@ -226,7 +252,7 @@ impl NodeManagerWorker {
         let spawner_flow_control_id = FlowControls::generate_flow_control_id();
 
         let token_refresher = TokenLeaseRefresher::new_with_fixed_token(token_to_use);
-        let http_interceptor_factory = Arc::new(HttpAuthInterceptorFactory::new(token_refresher));
+        let http_interceptor_factory = Arc::new(HttpAuthInterceptor::factory(token_refresher));
 
         PortalOutletInterceptor::create(
             ctx,
@@ -274,7 +300,7 @@ impl NodeManagerWorker {
 
         let token_refresher =
             TokenLeaseRefresher::new(ctx, Arc::downgrade(&self.node_manager), lease_issuer_route)?;
-        let http_interceptor_factory = Arc::new(HttpAuthInterceptorFactory::new(token_refresher));
+        let http_interceptor_factory = Arc::new(HttpAuthInterceptor::factory(token_refresher));
 
         PortalInletInterceptor::create(
             ctx,
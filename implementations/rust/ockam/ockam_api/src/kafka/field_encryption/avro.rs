@@ -0,0 +1,550 @@
+use crate::kafka::field_encryption::path::{
+    is_selected, remaining_for_field, remaining_for_index, PathSegment,
+};
+use crate::kafka::protocol_aware::InterceptError;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Maximum nesting depth of a decoded value.
+/// Recursive schemas, like linked lists, can otherwise exhaust the stack
+const MAX_AVRO_DEPTH: usize = 64;
+
+/// Avro schema, as returned by a schema registry.
+/// It is only used to decode the binary encoding of a record, which doesn't contain any type
+/// information, into an [`AvroValue`]
+#[derive(Debug, Clone)]
+pub(crate) struct AvroSchema {
+    root: Value,
+    /// Named types (records, enums, fixed), by full name and by short name
+    names: HashMap<String, Value>,
+}
+
+/// Decoded Avro value.
+/// Each value retains enough information, like the branch of a union, to be encoded
+/// without its schema
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum AvroValue {
+    Null,
+    Boolean(bool),
+    Int(i32),
+    Long(i64),
+    Float([u8; 4]),
+    Double([u8; 8]),
+    Bytes(Vec<u8>),
+    String(String),
+    Fixed(Vec<u8>),
+    Enum(i32),
+    Array(Vec<AvroValue>),
+    Map(Vec<(String, AvroValue)>),
+    Record(Vec<(String, AvroValue)>),
+    Union(i64, Box<AvroValue>),
+    /// Value already encoded, like a decrypted value
+    Raw(Vec<u8>),
+}
+
+impl AvroSchema {
+    #[cfg(test)]
+    pub(crate) fn parse(schema: &str) -> Result<Self, InterceptError> {
+        Ok(Self::new(serde_json::from_str(schema)?))
+    }
+
+    pub(crate) fn new(root: Value) -> Self {
+        let mut names = HashMap::new();
+        Self::collect_names(&root, None, &mut names);
+        Self { root, names }
+    }
+
+    fn collect_names(schema: &Value, namespace: Option<&str>, names: &mut HashMap<String, Value>) {
+        match schema {
+            Value::Array(branches) => {
+                for branch in branches {
+                    Self::collect_names(branch, namespace, names);
+                }
+            }
+            Value::Object(object) => {
+                let namespace = object
+                    .get("namespace")
+                    .and_then(|n| n.as_str())
+                    .or(namespace);
+                if let Some(name) = object.get("name").and_then(|n| n.as_str()) {
+                    let full_name = match namespace {
+                        Some(namespace) if !name.contains('.') && !namespace.is_empty() => {
+                            format!("{namespace}.{name}")
+                        }
+                        _ => name.to_string(),
+                    };
+                    let short_name = full_name.rsplit('.').next().unwrap_or(name).to_string();
+                    names.insert(short_name, schema.clone());
+                    names.insert(full_name, schema.clone());
+                }
+                if let Some(fields) = object.get("fields").and_then(|f| f.as_array()) {
+                    for field in fields {
+                        if let Some(field_type) = field.get("type") {
+                            Self::collect_names(field_type, namespace, names);
+                        }
+                    }
+                }
+                for key in ["type", "items", "values"] {
+                    if let Some(nested) = object.get(key) {
+                        if nested.is_object() || nested.is_array() {
+                            Self::collect_names(nested, namespace, names);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Decode the binary encoding of a value
+    pub(crate) fn decode(&self, data: &[u8]) -> Result<AvroValue, InterceptError> {
+        let mut reader = data;
+        let value = self.decode_value(&self.root, &mut reader, 0)?;
+        if !reader.is_empty() {
+            return Err("Unexpected data after the Avro record".into());
+        }
+        Ok(value)
+    }
+
+    fn resolve<'a>(&'a self, name: &str) -> Result<&'a Value, InterceptError> {
+        self.names
+            .get(name)
+            .or_else(|| name.rsplit('.').next().and_then(|n| self.names.get(n)))
+            .ok_or(InterceptError::Generic("Unknown Avro type"))
+    }
+
+    fn decode_value(
+        &self,
+        schema: &Value,
+        reader: &mut &[u8],
+        depth: usize,
+    ) -> Result<AvroValue, InterceptError> {
+        if depth > MAX_AVRO_DEPTH {
+            return Err("Avro value nested too deeply".into());
+        }
+        match schema {
+            Value::String(type_name) => self.decode_named_type(type_name, schema, reader, depth),
+            Value::Array(branches) => {
+                let index = read_long(reader)?;
+                let branch = usize::try_from(index)
+                    .ok()
+                    .and_then(|i| branches.get(i))
+                    .ok_or(InterceptError::Generic("Invalid Avro union branch"))?;
+                Ok(AvroValue::Union(
+                    index,
+                    Box::new(self.decode_value(branch, reader, depth + 1)?),
+                ))
+            }
+            Value::Object(object) => match object.get("type") {
+                Some(Value::String(type_name)) => match type_name.as_str() {
+                    "record" | "error" => {
+                        let fields = object
+                            .get("fields")
+                            .and_then(|f| f.as_array())
+                            .ok_or(InterceptError::Generic("Invalid Avro record schema"))?;
+                        let mut values = Vec::with_capacity(fields.len());
+                        for field in fields {
+                            let name = field
+                                .get("name")
+                                .and_then(|n| n.as_str())
+                                .ok_or(InterceptError::Generic("Invalid Avro field schema"))?;
+                            let field_type = field
+                                .get("type")
+                                .ok_or(InterceptError::Generic("Invalid Avro field schema"))?;
+                            values.push((
+                                name.to_string(),
+                                self.decode_value(field_type, reader, depth + 1)?,
+                            ));
+                        }
+                        Ok(AvroValue::Record(values))
+                    }
+                    "enum" => Ok(AvroValue::Enum(read_int(reader)?)),
+                    "fixed" => {
+                        let size = object
+                            .get("size")
+                            .and_then(|s| s.as_u64())
+                            .ok_or(InterceptError::Generic("Invalid Avro fixed schema"))?;
+                        Ok(AvroValue::Fixed(
+                            read_bytes(reader, size as usize)?.to_vec(),
+                        ))
+                    }
+                    "array" => {
+                        let items = object
+                            .get("items")
+                            .ok_or(InterceptError::Generic("Invalid Avro array schema"))?;
+                        let mut values = vec![];
+                        while let Some(count) = read_block_count(reader)? {
+                            for _ in 0..count {
+                                values.push(self.decode_value(items, reader, depth + 1)?);
+                            }
+                        }
+                        Ok(AvroValue::Array(values))
+                    }
+                    "map" => {
+                        let items = object
+                            .get("values")
+                            .ok_or(InterceptError::Generic("Invalid Avro map schema"))?;
+                        let mut values = vec![];
+                        while let Some(count) = read_block_count(reader)? {
+                            for _ in 0..count {
+                                let key = read_string(reader)?;
+                                values.push((key, self.decode_value(items, reader, depth + 1)?));
+                            }
+                        }
+                        Ok(AvroValue::Map(values))
+                    }
+                    // a primitive type, possibly annotated with a logical type
+                    _ => self.decode_named_type(type_name, schema, reader, depth),
+                },
+                Some(nested) => self.decode_value(nested, reader, depth + 1),
+                None => Err("Invalid Avro schema".into()),
+            },
+            _ => Err("Invalid Avro schema".into()),
+        }
+    }
+
+    fn decode_named_type(
+        &self,
+        type_name: &str,
+        schema: &Value,
+        reader: &mut &[u8],
+        depth: usize,
+    ) -> Result<AvroValue, InterceptError> {
+        Ok(match type_name {
+            "null" => AvroValue::Null,
+            "boolean" => AvroValue::Boolean(read_bytes(reader, 1)?[0] != 0),
+            "int" => AvroValue::Int(read_int(reader)?),
+            "long" => AvroValue::Long(read_long(reader)?),
+            "float" => AvroValue::Float(read_bytes(reader, 4)?.try_into().unwrap()),
+            "double" => AvroValue::Double(read_bytes(reader, 8)?.try_into().unwrap()),
+            "bytes" => {
+                let length = read_length(reader)?;
+                AvroValue::Bytes(read_bytes(reader, length)?.to_vec())
+            }
+            "string" => AvroValue::String(read_string(reader)?),
+            name => {
+                let named = self.resolve(name)?;
+                if named == schema {
+                    return Err("Invalid Avro schema".into());
+                }
+                self.decode_value(named, reader, depth + 1)?
+            }
+        })
+    }
+}
+
+impl AvroValue {
+    /// Append the binary encoding of this value
+    pub(crate) fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            AvroValue::Null => {}
+            AvroValue::Boolean(b) => out.push(*b as u8),
+            AvroValue::Int(i) => write_long(out, *i as i64),
+            AvroValue::Long(l) => write_long(out, *l),
+            AvroValue::Float(f) => out.extend_from_slice(f),
+            AvroValue::Double(d) => out.extend_from_slice(d),
+            AvroValue::Bytes(bytes) => {
+                write_long(out, bytes.len() as i64);
+                out.extend_from_slice(bytes);
+            }
+            AvroValue::String(string) => {
+                write_long(out, string.len() as i64);
+                out.extend_from_slice(string.as_bytes());
+            }
+            AvroValue::Fixed(bytes) => out.extend_from_slice(bytes),
+            AvroValue::Enum(index) => write_long(out, *index as i64),
+            AvroValue::Array(values) => {
+                if !values.is_empty() {
+                    write_long(out, values.len() as i64);
+                    for value in values {
+                        value.encode(out);
+                    }
+                }
+                write_long(out, 0);
+            }
+            AvroValue::Map(values) => {
+                if !values.is_empty() {
+                    write_long(out, values.len() as i64);
+                    for (key, value) in values {
+                        write_long(out, key.len() as i64);
+                        out.extend_from_slice(key.as_bytes());
+                        value.encode(out);
+                    }
+                }
+                write_long(out, 0);
+            }
+            AvroValue::Record(fields) => {
+                for (_, value) in fields {
+                    value.encode(out);
+                }
+            }
+            AvroValue::Union(index, value) => {
+                write_long(out, *index);
+                value.encode(out);
+            }
+            AvroValue::Raw(bytes) => out.extend_from_slice(bytes),
+        }
+    }
+}
+
+/// Collect the Avro values selected by the paths.
+/// Unions are transparent, the selected value is the value of the union branch
+pub(crate) fn select_avro_values<'a>(
+    value: &'a mut AvroValue,
+    paths: &[&[PathSegment]],
+    selected: &mut Vec<&'a mut AvroValue>,
+) {
+    if paths.is_empty() {
+        return;
+    }
+    if let AvroValue::Union(_, value) = value {
+        return select_avro_values(value, paths, selected);
+    }
+    if is_selected(paths) {
+        selected.push(value);
+        return;
+    }
+
+    match value {
+        AvroValue::Record(fields) | AvroValue::Map(fields) => {
+            for (name, value) in fields.iter_mut() {
+                select_avro_values(value, &remaining_for_field(paths, name), selected);
+            }
+        }
+        AvroValue::Array(values) => {
+            for (index, value) in values.iter_mut().enumerate() {
+                select_avro_values(value, &remaining_for_index(paths, index), selected);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn read_bytes<'a>(reader: &mut &'a [u8], length: usize) -> Result<&'a [u8], InterceptError> {
+    if reader.len() < length {
+        return Err(InterceptError::InvalidData);
+    }
+    let (bytes, rest) = reader.split_at(length);
+    *reader = rest;
+    Ok(bytes)
+}
+
+pub(crate) fn read_varint(reader: &mut &[u8]) -> Result<u64, InterceptError> {
+    let mut result = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = read_bytes(reader, 1)?[0];
+        result |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+    }
+    Err(InterceptError::InvalidData)
+}
+
+pub(crate) fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Read a zig-zag encoded variable-length long
+pub(crate) fn read_long(reader: &mut &[u8]) -> Result<i64, InterceptError> {
+    let value = read_varint(reader)?;
+    Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+}
+
+/// Write a zig-zag encoded variable-length long
+pub(crate) fn write_long(out: &mut Vec<u8>, value: i64) {
+    write_varint(out, ((value << 1) ^ (value >> 63)) as u64)
+}
+
+fn read_int(reader: &mut &[u8]) -> Result<i32, InterceptError> {
+    i32::try_from(read_long(reader)?).map_err(|_| InterceptError::InvalidData)
+}
+
+fn read_length(reader: &mut &[u8]) -> Result<usize, InterceptError> {
+    usize::try_from(read_long(reader)?).map_err(|_| InterceptError::InvalidData)
+}
+
+fn read_string(reader: &mut &[u8]) -> Result<String, InterceptError> {
+    let length = read_length(reader)?;
+    String::from_utf8(read_bytes(reader, length)?.to_vec())
+        .map_err(|_| InterceptError::Generic("Invalid Avro string"))
+}
+
+/// Read the number of items of the next block of an array or a map,
+/// or return None for the last block.
+/// The count can't exceed the number of bytes left, so that a forged count can't make
+/// the decoder loop or allocate without consuming any data. Items which are encoded
+/// with zero bytes, like nulls, are only supported up to that count
+fn read_block_count(reader: &mut &[u8]) -> Result<Option<u64>, InterceptError> {
+    let count = read_long(reader)?;
+    if count == 0 {
+        return Ok(None);
+    }
+    if count < 0 {
+        // a negative count is followed by the size of the block, in bytes
+        let size = read_length(reader)?;
+        if size > reader.len() {
+            return Err(InterceptError::InvalidData);
+        }
+    }
+    let count = count.unsigned_abs();
+    if count > reader.len() as u64 {
+        return Err("Invalid Avro block count".into());
+    }
+    Ok(Some(count))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::kafka::field_encryption::path::FieldPath;
+
+    pub(crate) const SCHEMA: &str = r#"{
+        "type": "record",
+        "name": "Payment",
+        "namespace": "com.example",
+        "fields": [
+            {"name": "id", "type": "long"},
+            {"name": "customer", "type": {
+                "type": "record",
+                "name": "Customer",
+                "fields": [
+                    {"name": "name", "type": "string"},
+                    {"name": "email", "type": ["null", "string"]}
+                ]
+            }},
+            {"name": "card", "type": "bytes"},
+            {"name": "amount", "type": "double"},
+            {"name": "tags", "type": {"type": "array", "items": "string"}},
+            {"name": "attributes", "type": {"type": "map", "values": "string"}},
+            {"name": "previous", "type": ["null", "Customer"]},
+            {"name": "status", "type": {"type": "enum", "name": "Status", "symbols": ["OK", "KO"]}}
+        ]
+    }"#;
+
+    pub(crate) fn payment() -> AvroValue {
+        let customer = AvroValue::Record(vec![
+            ("name".into(), AvroValue::String("alice".into())),
+            (
+                "email".into(),
+                AvroValue::Union(1, Box::new(AvroValue::String("alice@example.com".into()))),
+            ),
+        ]);
+        AvroValue::Record(vec![
+            ("id".into(), AvroValue::Long(-42)),
+            ("customer".into(), customer.clone()),
+            ("card".into(), AvroValue::Bytes(b"1234".to_vec())),
+            ("amount".into(), AvroValue::Double(12.5f64.to_le_bytes())),
+            (
+                "tags".into(),
+                AvroValue::Array(vec![
+                    AvroValue::String("a".into()),
+                    AvroValue::String("b".into()),
+                ]),
+            ),
+            (
+                "attributes".into(),
+                AvroValue::Map(vec![("k".into(), AvroValue::String("v".into()))]),
+            ),
+            ("previous".into(), AvroValue::Union(1, Box::new(customer))),
+            ("status".into(), AvroValue::Enum(1)),
+        ])
+    }
+
+    #[test]
+    fn decode_encode_round_trip() {
+        let schema = AvroSchema::parse(SCHEMA).unwrap();
+        let mut encoded = vec![];
+        payment().encode(&mut encoded);
+
+        let decoded = schema.decode(&encoded).unwrap();
+        assert_eq!(decoded, payment());
+
+        // trailing data is rejected
+        encoded.push(0);
+        assert!(schema.decode(&encoded).is_err());
+    }
+
+    #[test]
+    fn forged_block_counts_are_rejected() {
+        let schema = AvroSchema::parse(r#"{"type": "array", "items": "null"}"#).unwrap();
+
+        // a count larger than the data left
+        let mut encoded = vec![];
+        write_long(&mut encoded, i64::MAX);
+        encoded.push(0);
+        assert!(schema.decode(&encoded).is_err());
+
+        // a block size larger than the data left
+        let mut encoded = vec![];
+        write_long(&mut encoded, -1);
+        write_long(&mut encoded, 1024);
+        encoded.push(0);
+        assert!(schema.decode(&encoded).is_err());
+
+        // a count bounded by the data left
+        let mut encoded = vec![];
+        write_long(&mut encoded, 1);
+        encoded.push(0);
+        assert_eq!(
+            schema.decode(&encoded).unwrap(),
+            AvroValue::Array(vec![AvroValue::Null])
+        );
+    }
+
+    #[test]
+    fn deeply_nested_values_are_rejected() {
+        let schema = AvroSchema::parse(
+            r#"{
+                "type": "record",
+                "name": "Node",
+                "fields": [{"name": "next", "type": ["null", "Node"]}]
+            }"#,
+        )
+        .unwrap();
+
+        let encode_list = |length: usize| {
+            let mut encoded = vec![];
+            for _ in 0..length {
+                write_long(&mut encoded, 1);
+            }
+            write_long(&mut encoded, 0);
+            encoded
+        };
+        assert!(schema.decode(&encode_list(10)).is_ok());
+        assert!(schema.decode(&encode_list(10_000)).is_err());
+
+        // a record which only contains itself doesn't consume any data
+        let schema = AvroSchema::parse(
+            r#"{"type": "record", "name": "Loop", "fields": [{"name": "self", "type": "Loop"}]}"#,
+        )
+        .unwrap();
+        assert!(schema.decode(&[]).is_err());
+    }
+
+    #[test]
+    fn select_values() {
+        let mut value = payment();
+        let paths = ["customer.email", "previous.name", "tags[1]", "attributes.k"]
+            .iter()
+            .map(|p| FieldPath::parse(p).unwrap())
+            .collect::<Vec<_>>();
+        let paths = paths.iter().map(|p| p.segments()).collect::<Vec<_>>();
+        let mut selected = vec![];
+        select_avro_values(&mut value, &paths, &mut selected);
+        let selected: Vec<AvroValue> = selected.into_iter().map(|v| v.clone()).collect();
+        assert_eq!(
+            selected,
+            vec![
+                AvroValue::String("alice@example.com".into()),
+                AvroValue::String("b".into()),
+                AvroValue::String("v".into()),
+                AvroValue::String("alice".into()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use crate::kafka::field_encryption::path::{
+    is_selected, remaining_for_field, remaining_for_index, PathSegment,
+};
+use serde_json::Value;
+
+/// Collect the JSON values selected by the paths.
+/// When a value is selected, the values it contains are not visited
+pub(crate) fn select_json_values<'a>(
+    value: &'a mut Value,
+    paths: &[&[PathSegment]],
+    selected: &mut Vec<&'a mut Value>,
+) {
+    if paths.is_empty() {
+        return;
+    }
+    if is_selected(paths) {
+        selected.push(value);
+        return;
+    }
+
+    match value {
+        Value::Object(map) => {
+            for (name, value) in map.iter_mut() {
+                select_json_values(value, &remaining_for_field(paths, name), selected);
+            }
+        }
+        Value::Array(values) => {
+            for (index, value) in values.iter_mut().enumerate() {
+                select_json_values(value, &remaining_for_index(paths, index), selected);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kafka::field_encryption::path::FieldPath;
+    use serde_json::json;
+
+    #[test]
+    fn select_nested_values() {
+        let mut value = json!({
+            "a": {"b": 1, "c": 2},
+            "list": [{"secret": "s1", "public": "p1"}, {"secret": "s2", "public": "p2"}],
+            "top": "t",
+        });
+        let paths = ["a.b", "list[*].secret", "top", "missing.field"]
+            .iter()
+            .map(|p| FieldPath::parse(p).unwrap())
+            .collect::<Vec<_>>();
+        let paths = paths.iter().map(|p| p.segments()).collect::<Vec<_>>();
+
+        let mut selected = vec![];
+        select_json_values(&mut value, &paths, &mut selected);
+        for value in selected {
+            *value = json!("encrypted");
+        }
+
+        assert_eq!(
+            value,
+            json!({
+                "a": {"b": "encrypted", "c": 2},
+                "list": [
+                    {"secret": "encrypted", "public": "p1"},
+                    {"secret": "encrypted", "public": "p2"}
+                ],
+                "top": "encrypted",
+            })
+        );
+    }
+}
@@ -0,0 +1,374 @@
+//! Encryption of specific fields of the records values.
+//!
+//! Record values are either JSON documents, or values serialized with a schema registry
+//! serializer (Confluent wire format): a magic byte, a 4 bytes schema id,
+//! message indexes for Protobuf, then the Avro, Protobuf or JSON payload.
+//!
+//! The encrypted fields keep a type compatible with the schema so that consumers
+//! using strict deserializers can still parse the records:
+//!  - JSON values and strings are replaced by the hex encoding of the encrypted content
+//!  - bytes are replaced by the encrypted content
+//!
+//! Avro fields of other types are replaced by strings too, which requires the schemas
+//! to be registered through the schema registry proxy of the Kafka inlet, so that they
+//! declare those fields as strings (see [`schema_rewrite`]).
+
+mod avro;
+mod json;
+mod path;
+mod protobuf;
+mod schema_registry;
+pub(crate) mod schema_rewrite;
+
+pub use path::{FieldPath, PathSegment};
+pub use schema_registry::SchemaRegistryClient;
+
+use crate::kafka::field_encryption::avro::{read_long, select_avro_values, AvroSchema, AvroValue};
+use crate::kafka::field_encryption::json::select_json_values;
+use crate::kafka::field_encryption::protobuf::{ProtoField, ProtoMessage, ProtoSchema};
+use crate::kafka::field_encryption::schema_registry::Schema;
+use crate::kafka::protocol_aware::InterceptError;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// First byte of a record serialized with a schema registry serializer
+const MAGIC_BYTE: u8 = 0;
+
+/// Length of the magic byte and the schema id
+const SCHEMA_ID_HEADER_LENGTH: usize = 5;
+
+/// Record value decoded to encrypt or decrypt some of its fields.
+/// The header contains the magic byte, schema id and message indexes, if any,
+/// and is written back unchanged
+pub(crate) enum FieldEncryptedRecord {
+    Json {
+        header: Vec<u8>,
+        value: Value,
+    },
+    Avro {
+        header: Vec<u8>,
+        value: AvroValue,
+        /// Schema of the encrypted record, used to check that the encrypted fields
+        /// are declared with a compatible type
+        encrypted_schema: Option<Arc<AvroSchema>>,
+    },
+    Protobuf {
+        header: Vec<u8>,
+        schema: Arc<ProtoSchema>,
+        message_name: String,
+        message: ProtoMessage,
+    },
+}
+
+/// A field of a record selected for encryption or decryption
+pub(crate) enum SelectedField<'a> {
+    Json(&'a mut Value),
+    Avro(&'a mut AvroValue),
+    Protobuf(ProtoField<'a>),
+}
+
+impl FieldEncryptedRecord {
+    /// Decode a record value, which fields are encrypted or not.
+    /// A schema registry is needed when the record value starts with a schema id
+    pub(crate) async fn decode(
+        data: &[u8],
+        schema_registry: Option<&SchemaRegistryClient>,
+        encrypted: bool,
+    ) -> Result<Self, InterceptError> {
+        if data.len() < SCHEMA_ID_HEADER_LENGTH || data[0] != MAGIC_BYTE {
+            return Ok(FieldEncryptedRecord::Json {
+                header: vec![],
+                value: serde_json::from_slice(data)?,
+            });
+        }
+
+        let schema_registry = schema_registry.ok_or_else(|| {
+            warn!("a schema registry must be configured to encrypt fields of records with a schema id");
+            InterceptError::Generic("No schema registry configured")
+        })?;
+        let schema_id = u32::from_be_bytes(data[1..SCHEMA_ID_HEADER_LENGTH].try_into().unwrap());
+        let payload = &data[SCHEMA_ID_HEADER_LENGTH..];
+
+        match schema_registry.schema(schema_id).await? {
+            Schema::Json => Ok(FieldEncryptedRecord::Json {
+                header: data[..SCHEMA_ID_HEADER_LENGTH].to_vec(),
+                value: serde_json::from_slice(payload)?,
+            }),
+            Schema::Avro {
+                original,
+                registered,
+            } => {
+                let (schema, encrypted_schema) = if encrypted {
+                    (registered, None)
+                } else {
+                    (original, Some(registered))
+                };
+                Ok(FieldEncryptedRecord::Avro {
+                    header: data[..SCHEMA_ID_HEADER_LENGTH].to_vec(),
+                    value: schema.decode(payload)?,
+                    encrypted_schema,
+                })
+            }
+            Schema::Protobuf(schema) => {
+                let mut reader = payload;
+                let indexes = read_message_indexes(&mut reader)?;
+                let header_length = data.len() - reader.len();
+                let message_name = schema.message_name(&indexes)?.to_string();
+                let message = schema.decode(&message_name, reader)?;
+                Ok(FieldEncryptedRecord::Protobuf {
+                    header: data[..header_length].to_vec(),
+                    schema,
+                    message_name,
+                    message,
+                })
+            }
+        }
+    }
+
+    /// Return the fields selected by the paths
+    pub(crate) fn select(
+        &mut self,
+        paths: &[FieldPath],
+    ) -> Result<Vec<SelectedField<'_>>, InterceptError> {
+        let paths: Vec<&[PathSegment]> = paths.iter().map(|p| p.segments()).collect();
+        Ok(match self {
+            FieldEncryptedRecord::Json { value, .. } => {
+                let mut selected = vec![];
+                select_json_values(value, &paths, &mut selected);
+                selected.into_iter().map(SelectedField::Json).collect()
+            }
+            FieldEncryptedRecord::Avro { value, .. } => {
+                let mut selected = vec![];
+                select_avro_values(value, &paths, &mut selected);
+                selected.into_iter().map(SelectedField::Avro).collect()
+            }
+            FieldEncryptedRecord::Protobuf {
+                schema,
+                message_name,
+                message,
+                ..
+            } => {
+                let mut selected = vec![];
+                schema.select_fields(message_name, message, &paths, &mut selected)?;
+                selected.into_iter().map(SelectedField::Protobuf).collect()
+            }
+        })
+    }
+
+    /// Encode the record value, with its original header
+    pub(crate) fn encode(&self) -> Result<Vec<u8>, InterceptError> {
+        Ok(match self {
+            FieldEncryptedRecord::Json { header, value } => {
+                let mut result = header.clone();
+                serde_json::to_writer(&mut result, value)?;
+                result
+            }
+            FieldEncryptedRecord::Avro {
+                header,
+                value,
+                encrypted_schema,
+            } => {
+                let mut result = header.clone();
+                value.encode(&mut result);
+                if let Some(encrypted_schema) = encrypted_schema {
+                    encrypted_schema
+                        .decode(&result[header.len()..])
+                        .map_err(|_| {
+                            warn!("the encrypted Avro fields must be declared as strings or bytes in the registered schema");
+                            InterceptError::Generic("The encrypted Avro record doesn't match its schema")
+                        })?;
+                }
+                result
+            }
+            FieldEncryptedRecord::Protobuf {
+                header, message, ..
+            } => {
+                let mut result = header.clone();
+                message.encode(&mut result);
+                result
+            }
+        })
+    }
+}
+
+impl SelectedField<'_> {
+    /// Return the content to encrypt, or None if the field has no value
+    pub(crate) fn plain_text(&self) -> Result<Option<Vec<u8>>, InterceptError> {
+        match self {
+            SelectedField::Json(value) => Ok(Some(serde_json::to_vec(value)?)),
+            // the binary encoding of the value is encrypted, so that it can be decrypted
+            // without knowing its type
+            SelectedField::Avro(AvroValue::Null) => Ok(None),
+            SelectedField::Avro(value) => {
+                let mut plain_text = vec![];
+                value.encode(&mut plain_text);
+                Ok(Some(plain_text))
+            }
+            SelectedField::Protobuf(field) => Ok(Some(field.value.clone())),
+        }
+    }
+
+    /// Replace the field value with its encrypted content
+    pub(crate) fn set_encrypted(&mut self, encrypted: Vec<u8>) {
+        match self {
+            SelectedField::Json(value) => **value = Value::String(hex::encode(encrypted)),
+            SelectedField::Avro(value) => match value {
+                AvroValue::Bytes(_) => **value = AvroValue::Bytes(encrypted),
+                _ => **value = AvroValue::String(hex::encode(encrypted)),
+            },
+            SelectedField::Protobuf(field) if field.is_string => {
+                *field.value = hex::encode(encrypted).into_bytes()
+            }
+            SelectedField::Protobuf(field) => *field.value = encrypted,
+        }
+    }
+
+    /// Return the encrypted content of the field, or None if the field has no value
+    pub(crate) fn encrypted(&self) -> Result<Option<Vec<u8>>, InterceptError> {
+        let hex_decode = |string: &[u8]| {
+            hex::decode(string)
+                .map(Some)
+                .map_err(|_| InterceptError::Generic("Encrypted is not a valid hex string"))
+        };
+        match self {
+            SelectedField::Json(Value::String(string)) => hex_decode(string.as_bytes()),
+            SelectedField::Json(_) => {
+                error!("encrypted field is not a hex string");
+                Err("The encrypted field is not a hex-encoded string".into())
+            }
+            SelectedField::Avro(AvroValue::Null) => Ok(None),
+            SelectedField::Avro(AvroValue::String(string)) => hex_decode(string.as_bytes()),
+            SelectedField::Avro(AvroValue::Bytes(bytes)) => Ok(Some(bytes.clone())),
+            SelectedField::Avro(_) => {
+                Err("The encrypted Avro field is not a string or bytes".into())
+            }
+            SelectedField::Protobuf(field) if field.is_string => hex_decode(field.value),
+            SelectedField::Protobuf(field) => Ok(Some(field.value.clone())),
+        }
+    }
+
+    /// Replace the field value with its decrypted content
+    pub(crate) fn set_decrypted(&mut self, decrypted: Vec<u8>) -> Result<(), InterceptError> {
+        match self {
+            SelectedField::Json(value) => **value = serde_json::from_slice(&decrypted)?,
+            SelectedField::Avro(value) => **value = AvroValue::Raw(decrypted),
+            SelectedField::Protobuf(field) => *field.value = decrypted,
+        }
+        Ok(())
+    }
+}
+
+/// Read the indexes of a Protobuf message in its schema.
+/// A single 0 means the first message of the schema, and is encoded as an empty list
+fn read_message_indexes(reader: &mut &[u8]) -> Result<Vec<u64>, InterceptError> {
+    let count = read_long(reader)?;
+    if count == 0 {
+        return Ok(vec![0]);
+    }
+    let mut indexes = vec![];
+    for _ in 0..count {
+        indexes.push(u64::try_from(read_long(reader)?).map_err(|_| InterceptError::InvalidData)?);
+    }
+    Ok(indexes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kafka::field_encryption::avro::write_long;
+    use crate::kafka::field_encryption::schema_rewrite::rewrite_schema;
+
+    fn paths(paths: &[&str]) -> Vec<FieldPath> {
+        paths.iter().map(|p| FieldPath::parse(p).unwrap()).collect()
+    }
+
+    /// Replace every selected field with its encrypted value, then decrypt them
+    async fn round_trip(
+        data: &[u8],
+        registry: &SchemaRegistryClient,
+        paths: &[FieldPath],
+    ) -> Vec<u8> {
+        let mut record = FieldEncryptedRecord::decode(data, Some(registry), false)
+            .await
+            .unwrap();
+        for mut field in record.select(paths).unwrap() {
+            let plain_text = field.plain_text().unwrap().unwrap();
+            field.set_encrypted([b"encrypted:".to_vec(), plain_text].concat());
+        }
+        let encrypted = record.encode().unwrap();
+        assert_ne!(encrypted, data);
+
+        let mut record = FieldEncryptedRecord::decode(&encrypted, Some(registry), true)
+            .await
+            .unwrap();
+        for mut field in record.select(paths).unwrap() {
+            let encrypted = field.encrypted().unwrap().unwrap();
+            let plain_text = encrypted.strip_prefix(b"encrypted:").unwrap().to_vec();
+            field.set_decrypted(plain_text).unwrap();
+        }
+        record.encode().unwrap()
+    }
+
+    #[tokio::test]
+    async fn avro_round_trip() {
+        let registry = SchemaRegistryClient::new("http://localhost").unwrap();
+        let schema = avro::tests::SCHEMA;
+        registry.add_schema(7, Schema::parse(None, schema).unwrap());
+        let mut data = vec![MAGIC_BYTE, 0, 0, 0, 7];
+        avro::tests::payment().encode(&mut data);
+
+        let paths = paths(&["customer.email", "card", "tags[*]"]);
+        assert_eq!(round_trip(&data, &registry, &paths).await, data);
+
+        // a number can only be encrypted if the registered schema declares it as a string
+        let paths = self::paths(&["id", "amount"]);
+        let mut record = FieldEncryptedRecord::decode(&data, Some(&registry), false)
+            .await
+            .unwrap();
+        for mut field in record.select(&paths).unwrap() {
+            let plain_text = field.plain_text().unwrap().unwrap();
+            field.set_encrypted(plain_text);
+        }
+        assert!(record.encode().is_err());
+
+        let rewritten = rewrite_schema(None, schema, &paths).unwrap().unwrap();
+        registry.add_schema(10, Schema::parse(None, &rewritten).unwrap());
+        data[4] = 10;
+        assert_eq!(round_trip(&data, &registry, &paths).await, data);
+    }
+
+    #[tokio::test]
+    async fn protobuf_round_trip() {
+        let registry = SchemaRegistryClient::new("http://localhost").unwrap();
+        registry.add_schema(
+            8,
+            Schema::parse(Some("PROTOBUF"), protobuf::tests::SCHEMA).unwrap(),
+        );
+
+        // the message indexes [0] are encoded as an empty list
+        let mut data = vec![MAGIC_BYTE, 0, 0, 0, 8];
+        write_long(&mut data, 0);
+        data.extend(protobuf::tests::payment());
+
+        let paths = paths(&["customer.name", "card", "attributes.k"]);
+        assert_eq!(round_trip(&data, &registry, &paths).await, data);
+    }
+
+    #[tokio::test]
+    async fn json_round_trip() {
+        let registry = SchemaRegistryClient::new("http://localhost").unwrap();
+        registry.add_schema(9, Schema::Json);
+
+        let json = br#"{"a":{"b":[1,{"c":"secret"}]},"d":true}"#;
+        let paths = paths(&["a.b[1].c", "d"]);
+        assert_eq!(round_trip(json, &registry, &paths).await, json);
+
+        let data = [&[MAGIC_BYTE, 0, 0, 0, 9], &json[..]].concat();
+        assert_eq!(round_trip(&data, &registry, &paths).await, data);
+
+        // a schema registry is needed for records with a schema id
+        assert!(FieldEncryptedRecord::decode(&data, None, false)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,238 @@
+use std::fmt::{Display, Formatter};
+
+/// A segment of a field path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    /// A named field of an object, record or message, or the key of a map
+    Field(String),
+    /// An element of an array or of a repeated field
+    Index(usize),
+    /// All the fields of an object or map, or all the elements of an array
+    Wildcard,
+}
+
+/// Path to one or several fields of a record, in a JSONPath-like syntax:
+///
+///  - `field`: top-level field
+///  - `$.a.b` or `a.b`: nested field
+///  - `a[0]`, `a[*]`, `a.*`: array elements, or all the values of an object
+///  - `['a.b']`: field with a name containing special characters
+///
+/// A name which can't be parsed as a path is used as a top-level field name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldPath(Vec<PathSegment>);
+
+impl FieldPath {
+    /// Path to a top-level field
+    pub fn field(name: impl Into<String>) -> Self {
+        Self(vec![PathSegment::Field(name.into())])
+    }
+
+    /// Parse a path, or return an error describing why the path is invalid
+    pub fn parse(path: &str) -> Result<Self, String> {
+        let mut segments = vec![];
+        let mut chars = path.chars().peekable();
+
+        if chars.peek() == Some(&'$') {
+            chars.next();
+            match chars.peek() {
+                None => return Err("the path must contain at least one field".to_string()),
+                Some('.') | Some('[') => {}
+                Some(c) => return Err(format!("unexpected character '{c}' after '$'")),
+            }
+            if chars.peek() == Some(&'.') {
+                chars.next();
+            }
+        }
+
+        let mut expect_name = true;
+        loop {
+            match chars.peek() {
+                None => {
+                    if expect_name {
+                        return Err(format!("the path '{path}' is incomplete"));
+                    }
+                    break;
+                }
+                Some('[') => {
+                    chars.next();
+                    let mut content = String::new();
+                    let mut closed = false;
+                    let mut quote = None;
+                    for c in chars.by_ref() {
+                        match (quote, c) {
+                            (None, '\'') | (None, '"') if content.is_empty() => quote = Some(c),
+                            (Some(q), c) if c == q => quote = None,
+                            (None, ']') => {
+                                closed = true;
+                                break;
+                            }
+                            _ => content.push(c),
+                        }
+                    }
+                    if !closed {
+                        return Err(format!("missing ']' in '{path}'"));
+                    }
+                    let raw = content.trim();
+                    let segment = if raw == "*" {
+                        PathSegment::Wildcard
+                    } else if let Ok(index) = raw.parse::<usize>() {
+                        PathSegment::Index(index)
+                    } else if !raw.is_empty() {
+                        PathSegment::Field(content)
+                    } else {
+                        return Err(format!("empty brackets in '{path}'"));
+                    };
+                    segments.push(segment);
+                    expect_name = false;
+                }
+                Some('.') => {
+                    if expect_name {
+                        return Err(format!("unexpected '.' in '{path}'"));
+                    }
+                    chars.next();
+                    expect_name = true;
+                }
+                Some(_) => {
+                    if !expect_name {
+                        return Err(format!("missing '.' in '{path}'"));
+                    }
+                    let mut name = String::new();
+                    while let Some(c) = chars.peek() {
+                        if *c == '.' || *c == '[' {
+                            break;
+                        }
+                        name.push(*c);
+                        chars.next();
+                    }
+                    if name == "*" {
+                        segments.push(PathSegment::Wildcard);
+                    } else {
+                        segments.push(PathSegment::Field(name));
+                    }
+                    expect_name = false;
+                }
+            }
+        }
+        Ok(Self(segments))
+    }
+
+    /// Parse a path, falling back to a top-level field with that name if the path is invalid
+    pub fn parse_or_field(path: &str) -> Self {
+        Self::parse(path).unwrap_or_else(|_| Self::field(path))
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+}
+
+impl Display for FieldPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "$")?;
+        for segment in &self.0 {
+            match segment {
+                PathSegment::Field(name) => write!(f, "['{name}']")?,
+                PathSegment::Index(index) => write!(f, "[{index}]")?,
+                PathSegment::Wildcard => write!(f, "[*]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Return the remaining segments of the paths which select a named field
+pub(crate) fn remaining_for_field<'a>(
+    paths: &[&'a [PathSegment]],
+    name: &str,
+) -> Vec<&'a [PathSegment]> {
+    paths
+        .iter()
+        .filter_map(|path| match path.first() {
+            Some(PathSegment::Field(n)) if n == name => Some(&path[1..]),
+            Some(PathSegment::Wildcard) => Some(&path[1..]),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Return the remaining segments of the paths which select an element of an array
+pub(crate) fn remaining_for_index<'a>(
+    paths: &[&'a [PathSegment]],
+    index: usize,
+) -> Vec<&'a [PathSegment]> {
+    paths
+        .iter()
+        .filter_map(|path| match path.first() {
+            Some(PathSegment::Index(i)) if *i == index => Some(&path[1..]),
+            Some(PathSegment::Wildcard) => Some(&path[1..]),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Return the remaining segments of the paths which select any element of an array.
+/// This is used to navigate a schema, where all the elements have the same type
+pub(crate) fn remaining_for_items<'a>(paths: &[&'a [PathSegment]]) -> Vec<&'a [PathSegment]> {
+    paths
+        .iter()
+        .filter_map(|path| match path.first() {
+            Some(PathSegment::Index(_)) | Some(PathSegment::Wildcard) => Some(&path[1..]),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Return the remaining segments of the paths which select any value of a map
+pub(crate) fn remaining_for_values<'a>(paths: &[&'a [PathSegment]]) -> Vec<&'a [PathSegment]> {
+    paths
+        .iter()
+        .filter_map(|path| match path.first() {
+            Some(PathSegment::Field(_)) | Some(PathSegment::Wildcard) => Some(&path[1..]),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Return true if one of the paths selects the current value
+pub(crate) fn is_selected(paths: &[&[PathSegment]]) -> bool {
+    paths.iter().any(|p| p.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_paths() {
+        use PathSegment::*;
+        assert_eq!(
+            FieldPath::parse("field").unwrap(),
+            FieldPath::field("field")
+        );
+        assert_eq!(
+            FieldPath::parse("$.a.b").unwrap().segments(),
+            &[Field("a".into()), Field("b".into())]
+        );
+        assert_eq!(
+            FieldPath::parse("a[0].b[*].*").unwrap().segments(),
+            &[
+                Field("a".into()),
+                Index(0),
+                Field("b".into()),
+                Wildcard,
+                Wildcard
+            ]
+        );
+        assert_eq!(
+            FieldPath::parse("$['a.b'][\"c\"]").unwrap().segments(),
+            &[Field("a.b".into()), Field("c".into())]
+        );
+
+        assert!(FieldPath::parse("a.").is_err());
+        assert!(FieldPath::parse("a..b").is_err());
+        assert!(FieldPath::parse("a[0").is_err());
+        assert!(FieldPath::parse("$").is_err());
+        assert_eq!(FieldPath::parse_or_field("a..b"), FieldPath::field("a..b"));
+    }
+}
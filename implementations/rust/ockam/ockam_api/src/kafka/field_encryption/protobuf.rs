@@ -0,0 +1,715 @@
+use crate::kafka::field_encryption::avro::{read_varint, write_varint};
+use crate::kafka::field_encryption::path::{
+    is_selected, remaining_for_field, remaining_for_index, PathSegment,
+};
+use crate::kafka::protocol_aware::InterceptError;
+use std::collections::{HashMap, HashSet};
+
+/// Protobuf schema, parsed from the `.proto` definition returned by a schema registry.
+/// Only the messages, their fields and the enum names are kept: this is enough to
+/// navigate the binary encoding of a message
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ProtoSchema {
+    /// Messages, by fully qualified name
+    messages: HashMap<String, MessageType>,
+    /// Fully qualified names of the enums
+    enums: HashSet<String>,
+    /// Top-level messages, in declaration order
+    top_level: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct MessageType {
+    fields: Vec<FieldDefinition>,
+    /// Nested messages, in declaration order
+    nested: Vec<String>,
+    /// True for the entries of a map field: the key is field 1, the value is field 2
+    map_entry: bool,
+}
+
+#[derive(Debug, Clone)]
+struct FieldDefinition {
+    name: String,
+    number: u64,
+    type_name: String,
+    repeated: bool,
+}
+
+/// Kind of value stored in a field
+#[derive(Clone)]
+enum FieldKind {
+    String,
+    Bytes,
+    Message(String),
+    Other,
+}
+
+/// Message decoded from its binary encoding.
+/// The fields are kept in their original order, including unknown fields
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ProtoMessage {
+    fields: Vec<(u64, WireValue)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum WireValue {
+    Varint(u64),
+    Fixed64([u8; 8]),
+    LengthDelimited(Vec<u8>),
+    Fixed32([u8; 4]),
+    Message(ProtoMessage),
+}
+
+/// A string or bytes field selected for encryption
+pub(crate) struct ProtoField<'a> {
+    pub(crate) value: &'a mut Vec<u8>,
+    pub(crate) is_string: bool,
+}
+
+impl ProtoSchema {
+    /// Parse a `.proto` definition
+    pub(crate) fn parse(definition: &str) -> Result<Self, InterceptError> {
+        let tokens = tokenize(definition)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            package: String::new(),
+            schema: ProtoSchema::default(),
+        };
+        parser.parse_file()?;
+        Ok(parser.schema)
+    }
+
+    /// Return the name of the message identified by the Confluent message indexes
+    pub(crate) fn message_name(&self, indexes: &[u64]) -> Result<&str, InterceptError> {
+        let mut names = &self.top_level;
+        let mut name = None;
+        for index in indexes {
+            let found = names
+                .get(*index as usize)
+                .ok_or(InterceptError::Generic("Unknown Protobuf message index"))?;
+            name = Some(found.as_str());
+            names = &self.messages[found].nested;
+        }
+        name.ok_or(InterceptError::Generic("Unknown Protobuf message index"))
+    }
+
+    /// Decode the binary encoding of a message
+    pub(crate) fn decode(
+        &self,
+        message_name: &str,
+        data: &[u8],
+    ) -> Result<ProtoMessage, InterceptError> {
+        let message_type = self
+            .messages
+            .get(message_name)
+            .ok_or(InterceptError::Generic("Unknown Protobuf message"))?;
+        let mut reader = data;
+        let mut fields = vec![];
+        while !reader.is_empty() {
+            let tag = read_varint(&mut reader)?;
+            let number = tag >> 3;
+            let value = match tag & 0x7 {
+                0 => WireValue::Varint(read_varint(&mut reader)?),
+                1 => WireValue::Fixed64(read_bytes(&mut reader, 8)?.try_into().unwrap()),
+                2 => {
+                    let length = usize::try_from(read_varint(&mut reader)?)
+                        .map_err(|_| InterceptError::InvalidData)?;
+                    let bytes = read_bytes(&mut reader, length)?;
+                    let field = message_type.fields.iter().find(|f| f.number == number);
+                    match field.map(|f| self.field_kind(message_name, &f.type_name)) {
+                        Some(FieldKind::Message(name)) => {
+                            WireValue::Message(self.decode(&name, bytes)?)
+                        }
+                        _ => WireValue::LengthDelimited(bytes.to_vec()),
+                    }
+                }
+                5 => WireValue::Fixed32(read_bytes(&mut reader, 4)?.try_into().unwrap()),
+                _ => return Err("Unsupported Protobuf wire type".into()),
+            };
+            fields.push((number, value));
+        }
+        Ok(ProtoMessage { fields })
+    }
+
+    /// Collect the string and bytes fields selected by the paths.
+    /// The elements of a repeated field are selected by index, and the values of
+    /// a map field are selected by key
+    pub(crate) fn select_fields<'a>(
+        &self,
+        message_name: &str,
+        message: &'a mut ProtoMessage,
+        paths: &[&[PathSegment]],
+        selected: &mut Vec<ProtoField<'a>>,
+    ) -> Result<(), InterceptError> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+        let message_type = self
+            .messages
+            .get(message_name)
+            .ok_or(InterceptError::Generic("Unknown Protobuf message"))?;
+
+        let mut occurrences: HashMap<u64, usize> = HashMap::new();
+        for (number, value) in message.fields.iter_mut() {
+            let field = match message_type.fields.iter().find(|f| f.number == *number) {
+                Some(field) => field,
+                None => continue,
+            };
+            let remaining = remaining_for_field(paths, &field.name);
+            if remaining.is_empty() {
+                continue;
+            }
+            let kind = self.field_kind(message_name, &field.type_name);
+
+            if !field.repeated {
+                self.select_value(kind, value, &remaining, selected)?;
+                continue;
+            }
+
+            let occurrence = occurrences.entry(*number).or_default();
+            let index = *occurrence;
+            *occurrence += 1;
+
+            let is_map_entry =
+                matches!(&kind, FieldKind::Message(name) if self.messages[name].map_entry);
+            match kind {
+                FieldKind::Message(entry_name) if is_map_entry => {
+                    let entry = match value {
+                        WireValue::Message(entry) => entry,
+                        _ => continue,
+                    };
+                    let entry_type = &self.messages[&entry_name];
+                    let key = entry.fields.iter().find_map(|(n, v)| match (n, v) {
+                        (1, WireValue::LengthDelimited(key)) => {
+                            Some(String::from_utf8_lossy(key).to_string())
+                        }
+                        (1, WireValue::Varint(key)) => Some(key.to_string()),
+                        _ => None,
+                    });
+                    let mut value_paths = remaining_for_index(&remaining, index);
+                    if let Some(key) = key {
+                        value_paths.extend(remaining_for_field(&remaining, &key));
+                    }
+                    let value_type = entry_type.fields.iter().find(|f| f.number == 2);
+                    if let Some(value_type) = value_type {
+                        let value_kind = self.field_kind(&entry_name, &value_type.type_name);
+                        for (_, value) in entry.fields.iter_mut().filter(|(n, _)| *n == 2) {
+                            self.select_value(value_kind.clone(), value, &value_paths, selected)?;
+                        }
+                    }
+                }
+                kind => {
+                    // selecting a repeated field selects all its elements
+                    let mut element_paths = remaining_for_index(&remaining, index);
+                    if is_selected(&remaining) {
+                        element_paths.push(&[]);
+                    }
+                    self.select_value(kind, value, &element_paths, selected)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn select_value<'a>(
+        &self,
+        kind: FieldKind,
+        value: &'a mut WireValue,
+        paths: &[&[PathSegment]],
+        selected: &mut Vec<ProtoField<'a>>,
+    ) -> Result<(), InterceptError> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+        if is_selected(paths) {
+            let is_string = match kind {
+                FieldKind::String => true,
+                FieldKind::Bytes => false,
+                _ => return Err("Only string and bytes Protobuf fields can be encrypted".into()),
+            };
+            if let WireValue::LengthDelimited(value) = value {
+                selected.push(ProtoField { value, is_string });
+            }
+        } else if let (FieldKind::Message(name), WireValue::Message(message)) = (kind, value) {
+            self.select_fields(&name, message, paths, selected)?;
+        }
+        Ok(())
+    }
+
+    /// Resolve the type of a field declared in a message, following the Protobuf scoping rules
+    fn field_kind(&self, scope: &str, type_name: &str) -> FieldKind {
+        match type_name {
+            "string" => return FieldKind::String,
+            "bytes" => return FieldKind::Bytes,
+            "double" | "float" | "int32" | "int64" | "uint32" | "uint64" | "sint32" | "sint64"
+            | "fixed32" | "fixed64" | "sfixed32" | "sfixed64" | "bool" => return FieldKind::Other,
+            _ => {}
+        }
+        if let Some(absolute) = type_name.strip_prefix('.') {
+            return self.kind_of(absolute);
+        }
+        let mut scope = Some(scope);
+        while let Some(current) = scope {
+            let candidate = if current.is_empty() {
+                type_name.to_string()
+            } else {
+                format!("{current}.{type_name}")
+            };
+            if self.messages.contains_key(&candidate) || self.enums.contains(&candidate) {
+                return self.kind_of(&candidate);
+            }
+            scope = match current.rsplit_once('.') {
+                Some((parent, _)) => Some(parent),
+                None if !current.is_empty() => Some(""),
+                None => None,
+            };
+        }
+        FieldKind::Other
+    }
+
+    fn kind_of(&self, full_name: &str) -> FieldKind {
+        if self.messages.contains_key(full_name) {
+            FieldKind::Message(full_name.to_string())
+        } else {
+            FieldKind::Other
+        }
+    }
+}
+
+impl ProtoMessage {
+    /// Append the binary encoding of this message
+    pub(crate) fn encode(&self, out: &mut Vec<u8>) {
+        for (number, value) in &self.fields {
+            match value {
+                WireValue::Varint(v) => {
+                    write_varint(out, number << 3);
+                    write_varint(out, *v);
+                }
+                WireValue::Fixed64(v) => {
+                    write_varint(out, (number << 3) | 1);
+                    out.extend_from_slice(v);
+                }
+                WireValue::LengthDelimited(v) => {
+                    write_varint(out, (number << 3) | 2);
+                    write_varint(out, v.len() as u64);
+                    out.extend_from_slice(v);
+                }
+                WireValue::Message(message) => {
+                    let mut encoded = vec![];
+                    message.encode(&mut encoded);
+                    write_varint(out, (number << 3) | 2);
+                    write_varint(out, encoded.len() as u64);
+                    out.extend_from_slice(&encoded);
+                }
+                WireValue::Fixed32(v) => {
+                    write_varint(out, (number << 3) | 5);
+                    out.extend_from_slice(v);
+                }
+            }
+        }
+    }
+}
+
+fn read_bytes<'a>(reader: &mut &'a [u8], length: usize) -> Result<&'a [u8], InterceptError> {
+    if reader.len() < length {
+        return Err(InterceptError::InvalidData);
+    }
+    let (bytes, rest) = reader.split_at(length);
+    *reader = rest;
+    Ok(bytes)
+}
+
+/// Split a `.proto` definition into identifiers, numbers, strings and symbols,
+/// skipping comments
+fn tokenize(definition: &str) -> Result<Vec<String>, InterceptError> {
+    let mut tokens = vec![];
+    let mut chars = definition.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if previous == '*' => break,
+                        Some(c) => previous = c,
+                        None => return Err("Unterminated comment in the Protobuf schema".into()),
+                    }
+                }
+            }
+            '"' | '\'' => {
+                let mut string = String::from(c);
+                loop {
+                    match chars.next() {
+                        Some('\\') => {
+                            string.push('\\');
+                            if let Some(c) = chars.next() {
+                                string.push(c);
+                            }
+                        }
+                        Some(q) if q == c => break,
+                        Some(c) => string.push(c),
+                        None => return Err("Unterminated string in the Protobuf schema".into()),
+                    }
+                }
+                tokens.push(string);
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' || c == '+' => {
+                let mut token = String::from(c);
+                while let Some(c) = chars.peek() {
+                    if c.is_alphanumeric() || *c == '_' || *c == '.' {
+                        token.push(*c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(token);
+            }
+            c => tokens.push(c.to_string()),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    position: usize,
+    package: String,
+    schema: ProtoSchema,
+}
+
+impl Parser {
+    fn next(&mut self) -> Result<String, InterceptError> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or(InterceptError::Generic(
+                "Unexpected end of the Protobuf schema",
+            ))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(|t| t.as_str())
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), InterceptError> {
+        if self.next()? == expected {
+            Ok(())
+        } else {
+            Err("Invalid Protobuf schema".into())
+        }
+    }
+
+    /// Skip a statement, up to its final `;`, or a block, up to its closing `}`
+    fn skip_statement(&mut self) -> Result<(), InterceptError> {
+        let mut depth = 0;
+        loop {
+            match self.next()?.as_str() {
+                ";" if depth == 0 => return Ok(()),
+                "{" => depth += 1,
+                "}" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn parse_file(&mut self) -> Result<(), InterceptError> {
+        while let Some(token) = self.peek() {
+            match token {
+                "package" => {
+                    self.next()?;
+                    self.package = self.next()?;
+                    self.expect(";")?;
+                }
+                "message" => {
+                    self.next()?;
+                    let scope = self.package.clone();
+                    let name = self.parse_message(&scope)?;
+                    self.schema.top_level.push(name);
+                }
+                "enum" => {
+                    self.next()?;
+                    let scope = self.package.clone();
+                    self.parse_enum(&scope)?;
+                }
+                ";" => {
+                    self.next()?;
+                }
+                // syntax, import, option, service, extend
+                _ => self.skip_statement()?,
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_enum(&mut self, scope: &str) -> Result<(), InterceptError> {
+        let name = qualified(scope, &self.next()?);
+        self.schema.enums.insert(name);
+        self.skip_statement()
+    }
+
+    /// Parse a message after the `message` keyword and return its fully qualified name
+    fn parse_message(&mut self, scope: &str) -> Result<String, InterceptError> {
+        let name = qualified(scope, &self.next()?);
+        self.expect("{")?;
+        let mut message = MessageType::default();
+        self.parse_message_body(&name, &mut message)?;
+        self.schema.messages.insert(name.clone(), message);
+        Ok(name)
+    }
+
+    fn parse_message_body(
+        &mut self,
+        name: &str,
+        message: &mut MessageType,
+    ) -> Result<(), InterceptError> {
+        loop {
+            let token = self.next()?;
+            match token.as_str() {
+                "}" => return Ok(()),
+                ";" => {}
+                "message" => {
+                    let nested = self.parse_message(name)?;
+                    message.nested.push(nested);
+                }
+                "enum" => self.parse_enum(name)?,
+                "oneof" => {
+                    self.next()?;
+                    self.expect("{")?;
+                    self.parse_message_body(name, message)?;
+                }
+                "option" | "reserved" | "extensions" | "extend" => self.skip_statement()?,
+                "group" => return Err("Protobuf groups are not supported".into()),
+                "map" => {
+                    self.expect("<")?;
+                    let key_type = self.next()?;
+                    self.expect(",")?;
+                    let value_type = self.next()?;
+                    self.expect(">")?;
+                    let (field_name, number) = self.parse_field_end()?;
+                    let entry_name = format!("{name}.{field_name}$Entry");
+                    self.schema.messages.insert(
+                        entry_name.clone(),
+                        MessageType {
+                            fields: vec![
+                                FieldDefinition {
+                                    name: "key".to_string(),
+                                    number: 1,
+                                    type_name: key_type,
+                                    repeated: false,
+                                },
+                                FieldDefinition {
+                                    name: "value".to_string(),
+                                    number: 2,
+                                    type_name: value_type,
+                                    repeated: false,
+                                },
+                            ],
+                            nested: vec![],
+                            map_entry: true,
+                        },
+                    );
+                    message.fields.push(FieldDefinition {
+                        name: field_name,
+                        number,
+                        type_name: format!(".{entry_name}"),
+                        repeated: true,
+                    });
+                }
+                _ => {
+                    let (repeated, type_name) = match token.as_str() {
+                        "repeated" => (true, self.next()?),
+                        "optional" | "required" => (false, self.next()?),
+                        _ => (false, token),
+                    };
+                    let (field_name, number) = self.parse_field_end()?;
+                    message.fields.push(FieldDefinition {
+                        name: field_name,
+                        number,
+                        type_name,
+                        repeated,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Parse `name = number [options];`
+    fn parse_field_end(&mut self) -> Result<(String, u64), InterceptError> {
+        let name = self.next()?;
+        self.expect("=")?;
+        let number = self
+            .next()?
+            .parse()
+            .map_err(|_| InterceptError::Generic("Invalid Protobuf field number"))?;
+        if self.peek() == Some("[") {
+            while self.next()? != "]" {}
+        }
+        self.expect(";")?;
+        Ok((name, number))
+    }
+}
+
+fn qualified(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{scope}.{name}")
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::kafka::field_encryption::path::FieldPath;
+
+    pub(crate) const SCHEMA: &str = r#"
+        syntax = "proto3";
+        package com.example;
+
+        import "google/protobuf/timestamp.proto";
+
+        /* a payment */
+        message Payment {
+            int64 id = 1;
+            Customer customer = 2;
+            bytes card = 3 [deprecated = true];
+            repeated string tags = 4;
+            map<string, string> attributes = 5;
+            oneof method {
+                string iban = 6;
+                Customer.Address address = 7;
+            }
+            Status status = 8;
+            google.protobuf.Timestamp created_at = 9;
+
+            enum Status {
+                OK = 0;
+                KO = 1;
+            }
+        }
+
+        message Customer {
+            // the name of the customer
+            string name = 1;
+            optional string email = 2;
+
+            message Address {
+                string street = 1;
+            }
+        }
+    "#;
+
+    fn tag(out: &mut Vec<u8>, number: u64, wire_type: u64) {
+        write_varint(out, (number << 3) | wire_type);
+    }
+
+    fn string_field(out: &mut Vec<u8>, number: u64, value: &[u8]) {
+        tag(out, number, 2);
+        write_varint(out, value.len() as u64);
+        out.extend_from_slice(value);
+    }
+
+    /// Binary encoding of a Payment message
+    pub(crate) fn payment() -> Vec<u8> {
+        let mut customer = vec![];
+        string_field(&mut customer, 1, b"alice");
+        string_field(&mut customer, 2, b"alice@example.com");
+
+        let mut entry = vec![];
+        string_field(&mut entry, 1, b"k");
+        string_field(&mut entry, 2, b"v");
+
+        let mut payment = vec![];
+        tag(&mut payment, 1, 0);
+        write_varint(&mut payment, 42);
+        string_field(&mut payment, 2, &customer);
+        string_field(&mut payment, 3, b"1234");
+        string_field(&mut payment, 4, b"a");
+        string_field(&mut payment, 4, b"b");
+        string_field(&mut payment, 5, &entry);
+        string_field(&mut payment, 6, b"FR76");
+        tag(&mut payment, 8, 0);
+        write_varint(&mut payment, 1);
+        // unknown field
+        tag(&mut payment, 100, 5);
+        payment.extend_from_slice(&[1, 2, 3, 4]);
+        payment
+    }
+
+    #[test]
+    fn decode_encode_round_trip() {
+        let schema = ProtoSchema::parse(SCHEMA).unwrap();
+        assert_eq!(schema.message_name(&[0]).unwrap(), "com.example.Payment");
+        assert_eq!(
+            schema.message_name(&[1, 0]).unwrap(),
+            "com.example.Customer.Address"
+        );
+        assert!(schema.message_name(&[2]).is_err());
+
+        let message = schema.decode("com.example.Payment", &payment()).unwrap();
+        let mut encoded = vec![];
+        message.encode(&mut encoded);
+        assert_eq!(encoded, payment());
+    }
+
+    #[test]
+    fn select_fields() {
+        let schema = ProtoSchema::parse(SCHEMA).unwrap();
+        let mut message = schema.decode("com.example.Payment", &payment()).unwrap();
+
+        let paths = ["customer.email", "card", "tags[1]", "attributes.k", "iban"]
+            .iter()
+            .map(|p| FieldPath::parse(p).unwrap())
+            .collect::<Vec<_>>();
+        let paths = paths.iter().map(|p| p.segments()).collect::<Vec<_>>();
+        let mut selected = vec![];
+        schema
+            .select_fields("com.example.Payment", &mut message, &paths, &mut selected)
+            .unwrap();
+        let selected: Vec<(Vec<u8>, bool)> = selected
+            .into_iter()
+            .map(|f| (f.value.clone(), f.is_string))
+            .collect();
+        assert_eq!(
+            selected,
+            vec![
+                (b"alice@example.com".to_vec(), true),
+                (b"1234".to_vec(), false),
+                (b"b".to_vec(), true),
+                (b"v".to_vec(), true),
+                (b"FR76".to_vec(), true),
+            ]
+        );
+
+        // only strings and bytes can be encrypted
+        let path = FieldPath::parse("id").unwrap();
+        let mut selected = vec![];
+        assert!(schema
+            .select_fields(
+                "com.example.Payment",
+                &mut message,
+                &[path.segments()],
+                &mut selected
+            )
+            .is_err());
+    }
+}
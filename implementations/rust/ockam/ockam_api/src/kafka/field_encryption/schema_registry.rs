@@ -0,0 +1,109 @@
+use crate::kafka::field_encryption::avro::AvroSchema;
+use crate::kafka::field_encryption::protobuf::ProtoSchema;
+use crate::kafka::field_encryption::schema_rewrite::{to_original, to_registered};
+use crate::kafka::protocol_aware::InterceptError;
+use crate::ApiError;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Schema used to serialize a record value
+#[derive(Debug, Clone)]
+pub(crate) enum Schema {
+    /// Avro schema, with the original types of the encrypted fields, used by the producers,
+    /// and with the types of the encrypted values, used to encode the encrypted records
+    Avro {
+        original: Arc<AvroSchema>,
+        registered: Arc<AvroSchema>,
+    },
+    Protobuf(Arc<ProtoSchema>),
+    /// JSON schema: the record value is a JSON document, there's no need to parse the schema
+    Json,
+}
+
+impl Schema {
+    /// Parse a schema, given its type as returned by a schema registry.
+    /// When the type is missing the schema is an Avro schema
+    pub(crate) fn parse(schema_type: Option<&str>, schema: &str) -> Result<Self, InterceptError> {
+        match schema_type.unwrap_or("AVRO") {
+            "AVRO" => {
+                let mut registered: serde_json::Value = serde_json::from_str(schema)?;
+                let mut original = registered.clone();
+                to_original(&mut original);
+                to_registered(&mut registered);
+                Ok(Schema::Avro {
+                    original: Arc::new(AvroSchema::new(original)),
+                    registered: Arc::new(AvroSchema::new(registered)),
+                })
+            }
+            "PROTOBUF" => Ok(Schema::Protobuf(Arc::new(ProtoSchema::parse(schema)?))),
+            "JSON" => Ok(Schema::Json),
+            _ => Err("Unsupported schema type".into()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SchemaResponse {
+    schema: String,
+    schema_type: Option<String>,
+}
+
+/// Client of a Confluent-compatible schema registry.
+/// Schemas are immutable once registered, so they are cached by id and shared
+/// by all the connections of a Kafka inlet
+#[derive(Debug, Clone)]
+pub struct SchemaRegistryClient {
+    http_client: reqwest::Client,
+    base_url: String,
+    schemas: Arc<Mutex<HashMap<u32, Schema>>>,
+}
+
+impl SchemaRegistryClient {
+    pub fn new(base_url: impl Into<String>) -> ockam_core::Result<Self> {
+        let base_url = base_url.into().trim_end_matches('/').to_string();
+        let http_client = reqwest::ClientBuilder::new()
+            .build()
+            .map_err(|e| ApiError::core(format!("Failed to create http client: {e}")))?;
+        Ok(Self {
+            http_client,
+            base_url,
+            schemas: Default::default(),
+        })
+    }
+
+    /// Return the schema with the given id, fetching it from the registry if it is not cached
+    pub(crate) async fn schema(&self, id: u32) -> Result<Schema, InterceptError> {
+        if let Some(schema) = self.schemas.lock().unwrap().get(&id) {
+            return Ok(schema.clone());
+        }
+
+        let url = format!("{}/schemas/ids/{id}", self.base_url);
+        let response = self
+            .http_client
+            .get(&url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| {
+                warn!("cannot fetch the schema {id} from {url}: {e}");
+                InterceptError::Generic("Cannot fetch the schema from the schema registry")
+            })?
+            .json::<SchemaResponse>()
+            .await
+            .map_err(|e| {
+                warn!("cannot parse the schema {id} returned by {url}: {e}");
+                InterceptError::Generic("Invalid response from the schema registry")
+            })?;
+
+        let schema = Schema::parse(response.schema_type.as_deref(), &response.schema)?;
+        self.schemas.lock().unwrap().insert(id, schema.clone());
+        Ok(schema)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn add_schema(&self, id: u32, schema: Schema) {
+        self.schemas.lock().unwrap().insert(id, schema);
+    }
+}
@@ -0,0 +1,377 @@
+//! Rewriting of the schemas registered in a schema registry.
+//!
+//! Encrypted fields are replaced by strings, or stay bytes, so the schemas registered by
+//! the producers are rewritten to declare those fields with a compatible type.
+//! Both types are kept in the schema, the other one being stored in an extra attribute
+//! ignored by the Avro and JSON Schema parsers:
+//!
+//!  - registered schema: `{"name": "id", "type": "string", "ockam.original_type": "long"}`
+//!  - original schema: `{"name": "id", "type": "long", "ockam.encrypted_type": "string"}`
+//!
+//! This way the original schema can be returned to the consumers receiving decrypted records,
+//! and the registered schema can always be recovered from the original one.
+//!
+//! Protobuf schemas are not rewritten: only string and bytes fields can be encrypted.
+
+use crate::kafka::field_encryption::path::{
+    is_selected, remaining_for_field, remaining_for_items, remaining_for_values, FieldPath,
+    PathSegment,
+};
+use crate::kafka::protocol_aware::InterceptError;
+use serde_json::{json, Map, Value};
+
+/// Prefix of the attribute containing the original type of an encrypted value
+const ORIGINAL: &str = "ockam.original_";
+
+/// Prefix of the attribute containing the type of an encrypted value, in an original schema
+const ENCRYPTED: &str = "ockam.encrypted_";
+
+/// Avro attributes which can be rewritten: the type of a field, the items of an array,
+/// and the values of a map
+const AVRO_KEYS: [&str; 3] = ["type", "items", "values"];
+
+/// Name used in the markers of a JSON schema, where the whole schema of a value is rewritten
+const JSON_SCHEMA: &str = "schema";
+
+/// Rewrite a schema so that the values selected by the paths are declared as strings or bytes.
+/// Return None if the schema doesn't need to be rewritten
+pub(crate) fn rewrite_schema(
+    schema_type: Option<&str>,
+    schema: &str,
+    paths: &[FieldPath],
+) -> Result<Option<String>, InterceptError> {
+    let paths: Vec<&[PathSegment]> = paths.iter().map(|p| p.segments()).collect();
+    let rewrite = match schema_type.unwrap_or("AVRO") {
+        "AVRO" => rewrite_avro_type,
+        "JSON" => rewrite_json_schema,
+        _ => return Ok(None),
+    };
+    let mut schema: Value = serde_json::from_str(schema)?;
+    let mut rewritten = to_registered(&mut schema);
+    rewritten |= rewrite(&mut schema, &paths);
+    if rewritten {
+        Ok(Some(schema.to_string()))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Restore the original types of a rewritten Avro schema or JSON schema.
+/// Return None if the schema was not rewritten
+pub(crate) fn restore_schema(schema: &str) -> Option<String> {
+    let mut schema: Value = serde_json::from_str(schema).ok()?;
+    if to_original(&mut schema) {
+        Some(schema.to_string())
+    } else {
+        None
+    }
+}
+
+/// Use the original types of the encrypted values, return true if the schema was modified
+pub(crate) fn to_original(schema: &mut Value) -> bool {
+    swap_types(schema, ORIGINAL, ENCRYPTED)
+}
+
+/// Use the types of the encrypted values, return true if the schema was modified
+pub(crate) fn to_registered(schema: &mut Value) -> bool {
+    swap_types(schema, ENCRYPTED, ORIGINAL)
+}
+
+/// Replace the types stored in the `from` attributes, and store the replaced types
+/// in the `to` attributes
+fn swap_types(schema: &mut Value, from: &str, to: &str) -> bool {
+    match schema {
+        Value::Object(object) => {
+            if let Some(other) = object.remove(&format!("{from}{JSON_SCHEMA}")) {
+                let current = Value::Object(std::mem::take(object));
+                *schema = other;
+                if let Value::Object(object) = schema {
+                    object.insert(format!("{to}{JSON_SCHEMA}"), current);
+                }
+                return true;
+            }
+            let mut swapped = false;
+            for key in AVRO_KEYS {
+                if let Some(other) = object.remove(&format!("{from}{key}")) {
+                    let current = object.insert(key.to_string(), other).unwrap_or_default();
+                    object.insert(format!("{to}{key}"), current);
+                    swapped = true;
+                }
+            }
+            object
+                .values_mut()
+                .fold(swapped, |r, v| swap_types(v, from, to) || r)
+        }
+        Value::Array(values) => values
+            .iter_mut()
+            .fold(false, |r, v| swap_types(v, from, to) || r),
+        _ => false,
+    }
+}
+
+/// Rewrite the Avro type of the values selected by the paths.
+/// Unions are transparent, and named types are only rewritten where they are defined
+fn rewrite_avro_type(schema: &mut Value, paths: &[&[PathSegment]]) -> bool {
+    if paths.is_empty() {
+        return false;
+    }
+    let object = match schema {
+        Value::Array(branches) => {
+            return branches
+                .iter_mut()
+                .fold(false, |r, b| rewrite_avro_type(b, paths) || r)
+        }
+        Value::Object(object) => object,
+        _ => return false,
+    };
+
+    match object.get("type").and_then(|t| t.as_str()) {
+        Some("record") | Some("error") => {
+            let mut rewritten = false;
+            let fields = object.get_mut("fields").and_then(|f| f.as_array_mut());
+            for field in fields.into_iter().flatten() {
+                let name = field
+                    .get("name")
+                    .and_then(|n| n.as_str())
+                    .unwrap_or_default();
+                let remaining = remaining_for_field(paths, name);
+                if let Value::Object(field) = field {
+                    rewritten |= rewrite_avro_child(field, AVRO_KEYS[0], &remaining);
+                }
+            }
+            rewritten
+        }
+        Some("array") => rewrite_avro_child(object, AVRO_KEYS[1], &remaining_for_items(paths)),
+        Some("map") => rewrite_avro_child(object, AVRO_KEYS[2], &remaining_for_values(paths)),
+        _ => false,
+    }
+}
+
+/// Rewrite the type of a record field, array items or map values
+fn rewrite_avro_child(
+    object: &mut Map<String, Value>,
+    key: &str,
+    paths: &[&[PathSegment]],
+) -> bool {
+    let marker = format!("{ORIGINAL}{key}");
+    if object.contains_key(&marker) {
+        return false;
+    }
+    let child = match object.get_mut(key) {
+        Some(child) => child,
+        None => return false,
+    };
+    if !is_selected(paths) {
+        return rewrite_avro_type(child, paths);
+    }
+    match encrypted_avro_type(child) {
+        Some(encrypted) => {
+            let original = std::mem::replace(child, encrypted);
+            object.insert(marker, original);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Return the type declaring an encrypted value, or None if the type is already compatible
+/// with the encrypted value, or can't be rewritten
+fn encrypted_avro_type(avro_type: &Value) -> Option<Value> {
+    let is_compatible = |t: &Value| {
+        let name = t.get("type").unwrap_or(t).as_str();
+        matches!(name, Some("string") | Some("bytes") | Some("null"))
+    };
+
+    match avro_type {
+        Value::Array(branches) => {
+            let others: Vec<usize> = (0..branches.len())
+                .filter(|i| !is_compatible(&branches[*i]))
+                .collect();
+            match others.as_slice() {
+                [] => None,
+                [index] if !defines_named_type(&branches[*index]) => {
+                    let mut branches = branches.clone();
+                    branches[*index] = json!("string");
+                    if branches.iter().filter(|b| *b == &json!("string")).count() > 1 {
+                        warn!("cannot rewrite the Avro union {avro_type}, it already contains a string");
+                        return None;
+                    }
+                    Some(Value::Array(branches))
+                }
+                _ => {
+                    warn!(
+                        "cannot rewrite the Avro union {avro_type} to declare an encrypted field"
+                    );
+                    None
+                }
+            }
+        }
+        t if is_compatible(t) => None,
+        t if defines_named_type(t) => {
+            warn!("cannot rewrite the Avro named type {t} to declare an encrypted field");
+            None
+        }
+        _ => Some(json!("string")),
+    }
+}
+
+/// Return true if a type defines a named type, possibly nested.
+/// Named types can be referenced elsewhere in the schema, so their definition must be kept
+fn defines_named_type(avro_type: &Value) -> bool {
+    match avro_type {
+        Value::Object(object) => {
+            let kind = object.get("type").and_then(|t| t.as_str());
+            matches!(
+                kind,
+                Some("record") | Some("error") | Some("enum") | Some("fixed")
+            ) || object.values().any(defines_named_type)
+        }
+        Value::Array(values) => values.iter().any(defines_named_type),
+        _ => false,
+    }
+}
+
+/// Rewrite the JSON schema of the values selected by the paths.
+/// Only `properties` and `items` are followed, references are not resolved
+fn rewrite_json_schema(schema: &mut Value, paths: &[&[PathSegment]]) -> bool {
+    if paths.is_empty() {
+        return false;
+    }
+    let object = match schema {
+        Value::Object(object) => object,
+        _ => return false,
+    };
+    let mut rewritten = false;
+    if let Some(Value::Object(properties)) = object.get_mut("properties") {
+        for (name, property) in properties.iter_mut() {
+            rewritten |= rewrite_json_child(property, &remaining_for_field(paths, name));
+        }
+    }
+    if let Some(items) = object.get_mut("items") {
+        rewritten |= rewrite_json_child(items, &remaining_for_items(paths));
+    }
+    rewritten
+}
+
+fn rewrite_json_child(schema: &mut Value, paths: &[&[PathSegment]]) -> bool {
+    if !is_selected(paths) {
+        return rewrite_json_schema(schema, paths);
+    }
+    let encrypted = json!({"type": "string"});
+    let marker = format!("{ORIGINAL}{JSON_SCHEMA}");
+    if *schema == encrypted || schema.get(&marker).is_some() {
+        return false;
+    }
+    let original = std::mem::replace(schema, encrypted);
+    schema[marker] = original;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kafka::field_encryption::avro::tests::SCHEMA;
+
+    fn paths(paths: &[&str]) -> Vec<FieldPath> {
+        paths.iter().map(|p| FieldPath::parse(p).unwrap()).collect()
+    }
+
+    #[test]
+    fn rewrite_and_restore_avro_schema() {
+        let paths = paths(&[
+            "id",
+            "customer.email",
+            "amount",
+            "tags[*]",
+            "status",
+            "card",
+        ]);
+        let rewritten = rewrite_schema(None, SCHEMA, &paths).unwrap().unwrap();
+        let value: Value = serde_json::from_str(&rewritten).unwrap();
+
+        let fields = value["fields"].as_array().unwrap();
+        assert_eq!(fields[0]["type"], json!("string"));
+        assert_eq!(fields[0]["ockam.original_type"], json!("long"));
+        // already a string
+        assert_eq!(
+            fields[1]["type"]["fields"][1]["type"],
+            json!(["null", "string"])
+        );
+        assert!(fields[1]["type"]["fields"][1]
+            .get("ockam.original_type")
+            .is_none());
+        // bytes stay bytes
+        assert_eq!(fields[2]["type"], json!("bytes"));
+        assert_eq!(fields[3]["type"], json!("string"));
+        // the items of the array are already strings
+        assert_eq!(fields[4]["type"]["items"], json!("string"));
+        // the enum is a named type
+        assert_eq!(fields[7]["type"]["type"], json!("enum"));
+
+        // rewriting is idempotent
+        assert_eq!(rewrite_schema(None, &rewritten, &paths).unwrap(), None);
+
+        let restored = restore_schema(&rewritten).unwrap();
+        let value: Value = serde_json::from_str(&restored).unwrap();
+        let fields = value["fields"].as_array().unwrap();
+        assert_eq!(fields[0]["type"], json!("long"));
+        assert_eq!(fields[0]["ockam.encrypted_type"], json!("string"));
+        assert_eq!(fields[3]["type"], json!("double"));
+        assert_eq!(restore_schema(SCHEMA), None);
+
+        // the registered schema is recovered from the original schema
+        let registered = rewrite_schema(None, &restored, &[]).unwrap().unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&registered).unwrap(),
+            serde_json::from_str::<Value>(&rewritten).unwrap()
+        );
+    }
+
+    #[test]
+    fn rewrite_and_restore_json_schema() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "id": {"type": "integer"},
+                "items": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {"card": {"type": "string", "pattern": "[0-9]+"}}
+                    }
+                }
+            }
+        })
+        .to_string();
+        let paths = paths(&["id", "items[*].card"]);
+        let rewritten = rewrite_schema(Some("JSON"), &schema, &paths)
+            .unwrap()
+            .unwrap();
+        let value: Value = serde_json::from_str(&rewritten).unwrap();
+        assert_eq!(value["properties"]["id"]["type"], json!("string"));
+        assert_eq!(
+            value["properties"]["items"]["items"]["properties"]["card"]["ockam.original_schema"],
+            json!({"type": "string", "pattern": "[0-9]+"})
+        );
+
+        let restored = restore_schema(&rewritten).unwrap();
+        let value: Value = serde_json::from_str(&restored).unwrap();
+        assert_eq!(
+            value["properties"]["id"],
+            json!({"type": "integer", "ockam.encrypted_schema": {"type": "string"}})
+        );
+        let registered = rewrite_schema(Some("JSON"), &restored, &paths)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&registered).unwrap(),
+            serde_json::from_str::<Value>(&rewritten).unwrap()
+        );
+
+        // Protobuf schemas are not rewritten
+        assert_eq!(
+            rewrite_schema(Some("PROTOBUF"), "syntax = \"proto3\";", &paths).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use crate::kafka::key_exchange::controller::{
+    InnerSecureChannelController, KafkaKeyExchangeControllerImpl,
+};
+use ockam::identity::{Identifier, IdentityError, Nonce, NOISE_NONCE_LEN};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Address, Error, Result};
+use std::time::{Duration, Instant};
+use tokio::sync::MutexGuard;
+
+/// Minimum interval between two updates of the last usage time of a stored consumer key.
+/// It avoids writing to the database for each decrypted record
+const CONSUMER_KEY_USAGE_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+impl KafkaKeyExchangeControllerImpl {
+    /// Keep a copy of the key of the key exchange only secure channel used to decrypt records,
+    /// so that those records can still be decrypted once the secure channel is gone.
+    /// The keys which were not used during the retention period are deleted at the same time.
+    pub(crate) async fn retain_consumer_key(
+        &self,
+        decryptor_remote_address: &Address,
+    ) -> Result<()> {
+        let mut inner = self.inner.lock().await;
+        if let Some(last_refresh) = inner.retained_consumer_keys.get(decryptor_remote_address) {
+            if last_refresh.elapsed() < CONSUMER_KEY_USAGE_REFRESH_INTERVAL {
+                return Ok(());
+            }
+        }
+        inner
+            .retained_consumer_keys
+            .insert(decryptor_remote_address.clone(), Instant::now());
+
+        // only the secure channels used for key exchange are retained
+        let Some(secure_channel) = inner
+            .secure_channels
+            .secure_channel_repository()
+            .get(decryptor_remote_address)
+            .await?
+        else {
+            return Ok(());
+        };
+        if secure_channel.state().is_some() {
+            return Ok(());
+        }
+
+        let node_name = inner.node_manager.node_name();
+        let cli_state = &inner.node_manager.cli_state;
+        cli_state
+            .store_kafka_consumer_key(
+                &node_name,
+                decryptor_remote_address,
+                secure_channel.their_identifier(),
+                secure_channel.decryption_key_handle(),
+            )
+            .await?;
+
+        let deleted = cli_state
+            .delete_expired_kafka_consumer_keys(&node_name, self.consumer_key_retention)
+            .await?;
+        if deleted > 0 {
+            debug!("deleted {deleted} expired kafka consumer keys");
+        }
+        Ok(())
+    }
+
+    /// Decrypt the content with a stored consumer key, when the secure channel which was used
+    /// to exchange that key doesn't exist anymore.
+    /// Return None if there is no stored key for the decryptor address.
+    pub(crate) async fn decrypt_with_consumer_key(
+        &self,
+        decryptor_remote_address: &Address,
+        mut encrypted_content: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>> {
+        let mut inner = self.inner.lock().await;
+        let node_name = inner.node_manager.node_name();
+        let Some(key) = inner
+            .node_manager
+            .cli_state
+            .get_kafka_consumer_key(&node_name, decryptor_remote_address)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        Self::authorize_producer(&inner, key.producer_identifier()).await?;
+
+        let vault = inner.secure_channels.vault().secure_channel_vault;
+        if !inner
+            .loaded_consumer_keys
+            .contains(decryptor_remote_address)
+        {
+            vault.load_aead_key(key.key_handle()).await?;
+            inner
+                .loaded_consumer_keys
+                .insert(decryptor_remote_address.clone());
+        }
+
+        if encrypted_content.len() < NOISE_NONCE_LEN {
+            return Err(IdentityError::InvalidNonce)?;
+        }
+        let nonce = Nonce::try_from(&encrypted_content[..NOISE_NONCE_LEN])?;
+        let decrypted_content = vault
+            .aead_decrypt(
+                key.key_handle(),
+                &mut encrypted_content[NOISE_NONCE_LEN..],
+                &nonce.to_aes_gcm_nonce(),
+                &[],
+            )
+            .await?;
+        Ok(Some(decrypted_content.to_vec()))
+    }
+
+    /// Check that the producer is authorized by the producer policy
+    pub(crate) async fn authorize_producer(
+        inner: &MutexGuard<'_, InnerSecureChannelController>,
+        producer_identifier: &Identifier,
+    ) -> Result<()> {
+        let authorized = inner
+            .producer_policy_access_control
+            .is_identity_authorized(producer_identifier)
+            .await?;
+
+        if authorized {
+            Ok(())
+        } else {
+            Err(Error::new(
+                Origin::Transport,
+                Kind::Invalid,
+                format!(
+                    "unauthorized secure channel for producer with identifier {}",
+                    producer_identifier
+                ),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_state::ExportedKafkaConsumerKey;
+    use crate::kafka::key_exchange::KafkaKeyExchangeController;
+    use crate::kafka::{ConsumerPublishing, ConsumerResolution};
+    use crate::test_utils::{start_manager_for_tests, TestNode};
+    use ockam_abac::expr::{eq, str};
+    use ockam_abac::{subject_identifier_attribute, Action, Env, Resource, ResourceType};
+    use ockam_node::Context;
+    use ockam_vault::AEAD_SECRET_LENGTH;
+
+    #[ockam_macros::test]
+    async fn records_are_decrypted_with_a_stored_consumer_key(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        TestNode::clean().await?;
+        let handle = start_manager_for_tests(context, None, None).await?;
+        let node_manager = handle.node_manager.node_manager.clone();
+        let secure_channels = handle.node_manager.secure_channels();
+        let identities = secure_channels.identities();
+        let allowed = identities.identities_creation().create_identity().await?;
+        let denied = identities.identities_creation().create_identity().await?;
+
+        let policies = node_manager.policies();
+        let resource = Resource::new("kafka-producer-test", ResourceType::KafkaProducer);
+        policies
+            .store_policy_for_resource_name(
+                &resource.resource_name,
+                &Action::HandleMessage,
+                &eq([subject_identifier_attribute(), str(allowed.to_string())]),
+            )
+            .await?;
+        let producer_policy_access_control = policies.make_policy_access_control(
+            identities.identities_attributes(),
+            resource,
+            Action::HandleMessage,
+            Env::new(),
+            None,
+        );
+        let consumer_policy_access_control = policies.make_policy_access_control(
+            identities.identities_attributes(),
+            Resource::new("kafka-consumer-test", ResourceType::KafkaConsumer),
+            Action::HandleMessage,
+            Env::new(),
+            None,
+        );
+        let controller = KafkaKeyExchangeControllerImpl::new(
+            node_manager.clone(),
+            secure_channels.clone(),
+            ConsumerResolution::None,
+            ConsumerPublishing::None,
+            consumer_policy_access_control,
+            producer_policy_access_control,
+        );
+
+        // the keys of two secure channels which are already closed
+        let secret = vec![7; AEAD_SECRET_LENGTH];
+        let keys = [
+            ("allowed_decryptor", &allowed),
+            ("denied_decryptor", &denied),
+        ]
+        .into_iter()
+        .map(|(address, producer)| ExportedKafkaConsumerKey {
+            decryptor_address: address.to_string(),
+            producer_identifier: producer.clone(),
+            secret: secret.clone(),
+            last_used_at: 1,
+        })
+        .collect();
+        node_manager
+            .cli_state
+            .import_kafka_consumer_keys(&node_manager.node_name(), keys)
+            .await?;
+
+        // a record encrypted by the producer with the exchanged key
+        let vault = secure_channels.vault().secure_channel_vault;
+        let buffer = vault.import_secret_buffer(secret).await?;
+        let key = vault.convert_secret_buffer_to_aead_key(buffer).await?;
+        let nonce = Nonce::from(3);
+        let mut encrypted = nonce.to_noise_nonce().to_vec();
+        encrypted.extend_from_slice(b"hello");
+        // room for the authentication tag
+        encrypted.extend_from_slice(&[0; 16]);
+        vault
+            .aead_encrypt(
+                &key,
+                &mut encrypted[NOISE_NONCE_LEN..],
+                &nonce.to_aes_gcm_nonce(),
+                &[],
+            )
+            .await?;
+
+        let decrypted = controller
+            .decrypt_content(
+                context,
+                &Address::from_string("allowed_decryptor"),
+                encrypted.clone(),
+            )
+            .await?;
+        assert_eq!(decrypted, b"hello".to_vec());
+
+        // the producer policy is still checked
+        let result = controller
+            .decrypt_content(
+                context,
+                &Address::from_string("denied_decryptor"),
+                encrypted.clone(),
+            )
+            .await;
+        assert!(result.is_err());
+
+        // without a stored key, the record can't be decrypted
+        let result = controller
+            .decrypt_content(context, &Address::from_string("unknown"), encrypted)
+            .await;
+        assert!(result.is_err());
+
+        Ok(())
+    }
+}
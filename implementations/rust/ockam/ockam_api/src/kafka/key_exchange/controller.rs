@@ -1,4 +1,6 @@
-use crate::kafka::key_exchange::{KafkaKeyExchangeController, TopicPartition};
+use crate::kafka::key_exchange::{
+    KafkaKeyExchangeController, TopicPartition, DEFAULT_CONSUMER_KEY_RETENTION,
+};
 use crate::kafka::protocol_aware::KafkaEncryptedContent;
 use crate::kafka::{ConsumerPublishing, ConsumerResolution};
 use crate::nodes::models::relay::ReturnTiming;
@@ -12,11 +14,14 @@ use ockam_core::compat::collections::{HashMap, HashSet};
 use ockam_core::{async_trait, route, Address};
 use ockam_node::Context;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 
 #[derive(Clone)]
 pub(crate) struct KafkaKeyExchangeControllerImpl {
     pub(crate) inner: Arc<Mutex<InnerSecureChannelController>>,
+    // how long the consumer keys are kept after their last usage
+    pub(crate) consumer_key_retention: Duration,
 }
 
 #[async_trait]
@@ -63,12 +68,29 @@ impl KafkaKeyExchangeController for KafkaKeyExchangeControllerImpl {
         consumer_decryptor_address: &Address,
         encrypted_content: Vec<u8>,
     ) -> ockam_core::Result<Vec<u8>> {
-        let secure_channel_decryptor_api_address = self
+        let secure_channel_decryptor_api_address = match self
             .get_or_load_secure_channel_decryptor_api_address_for(
                 context,
                 consumer_decryptor_address,
             )
-            .await?;
+            .await
+        {
+            Ok(address) => address,
+            Err(error) => {
+                // the secure channel is gone, but its key might have been retained
+                return match self
+                    .decrypt_with_consumer_key(consumer_decryptor_address, encrypted_content)
+                    .await?
+                {
+                    Some(decrypted_content) => Ok(decrypted_content),
+                    None => Err(error),
+                };
+            }
+        };
+
+        if let Err(error) = self.retain_consumer_key(consumer_decryptor_address).await {
+            warn!("cannot retain the kafka consumer key {consumer_decryptor_address}: {error}");
+        }
 
         let decrypt_response = context
             .send_and_receive(
@@ -144,6 +166,10 @@ pub struct InnerSecureChannelController {
     pub(crate) secure_channels: Arc<SecureChannels>,
     pub(crate) consumer_policy_access_control: PolicyAccessControl,
     pub(crate) producer_policy_access_control: PolicyAccessControl,
+    // last time the consumer key of a decryptor address was retained
+    pub(crate) retained_consumer_keys: HashMap<Address, Instant>,
+    // retained consumer keys already loaded in the vault
+    pub(crate) loaded_consumer_keys: HashSet<Address>,
 }
 
 impl KafkaKeyExchangeControllerImpl {
@@ -166,7 +192,16 @@ impl KafkaKeyExchangeControllerImpl {
                 consumer_publishing,
                 consumer_policy_access_control,
                 producer_policy_access_control,
+                retained_consumer_keys: Default::default(),
+                loaded_consumer_keys: Default::default(),
             })),
+            consumer_key_retention: DEFAULT_CONSUMER_KEY_RETENTION,
         }
     }
+
+    /// Set how long the consumer keys are kept after their last usage
+    pub(crate) fn with_consumer_key_retention(mut self, retention: Duration) -> Self {
+        self.consumer_key_retention = retention;
+        self
+    }
 }
@@ -3,10 +3,18 @@ use minicbor::{CborLen, Decode, Encode};
 use ockam_core::{async_trait, Address};
 use ockam_multiaddr::MultiAddr;
 use ockam_node::Context;
+use std::time::Duration;
 
+mod consumer_keys;
 pub(crate) mod controller;
 mod secure_channels;
 
+/// Default duration during which the keys of a consumer are kept after their last usage.
+/// It corresponds to the default retention period of Kafka topics.
+/// The actual retention period of the topics is not retrieved from the Kafka cluster,
+/// so a different retention must be configured for topics keeping their records longer
+pub const DEFAULT_CONSUMER_KEY_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
 /// Describe how to reach the consumer node: either directly or through a relay
 #[derive(Debug, Clone, Encode, Decode, CborLen)]
 #[rustfmt::skip]
@@ -240,22 +240,7 @@ impl KafkaKeyExchangeControllerImpl {
             }
         };
 
-        let authorized = inner
-            .producer_policy_access_control
-            .is_identity_authorized(&their_identifier)
-            .await?;
-
-        if authorized {
-            Ok(decryptor_api_address)
-        } else {
-            Err(Error::new(
-                Origin::Transport,
-                Kind::Invalid,
-                format!(
-                    "unauthorized secure channel for producer with identifier {}",
-                    their_identifier
-                ),
-            ))
-        }
+        Self::authorize_producer(&inner, &their_identifier).await?;
+        Ok(decryptor_api_address)
     }
 }
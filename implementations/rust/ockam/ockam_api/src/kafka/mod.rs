@@ -1,23 +1,33 @@
 //!This service allows encrypted transparent communication from the kafka producer
 //! to the kafka consumer without any modification in the existing application.
 
+mod field_encryption;
 mod inlet_controller;
 pub(crate) mod key_exchange;
 mod outlet_controller;
 pub(crate) mod protocol_aware;
+mod record_encryption;
+mod schema_registry_proxy;
 #[cfg(test)]
 mod tests;
+mod topic_policy;
 
+pub use field_encryption::{FieldPath, PathSegment, SchemaRegistryClient};
 pub(crate) use inlet_controller::KafkaInletController;
-pub use key_exchange::{ConsumerPublishing, ConsumerResolution};
+pub use key_exchange::{ConsumerPublishing, ConsumerResolution, DEFAULT_CONSUMER_KEY_RETENTION};
 use ockam::identity::Identifier;
 use ockam_abac::expr::{eq, or, str};
 use ockam_abac::{subject_has_credential_policy_expression, subject_identifier_attribute, Expr};
 use ockam_core::Address;
 pub(crate) use outlet_controller::KafkaOutletController;
+pub use record_encryption::*;
+pub(crate) use schema_registry_proxy::SchemaRegistryInterceptorFactory;
+pub use topic_policy::{parse_topic_policy, KafkaTopicPolicies, TopicEncryption, TopicPolicy};
+pub(crate) use topic_policy::{TopicAuthorization, TopicOperation};
 
 pub const KAFKA_OUTLET_INTERCEPTOR_ADDRESS: &str = "kafka_interceptor";
 pub const KAFKA_OUTLET_BOOTSTRAP_ADDRESS: &str = "kafka_bootstrap";
+pub const KAFKA_OUTLET_SCHEMA_REGISTRY_ADDRESS: &str = "kafka_schema_registry";
 
 pub fn kafka_outlet_address(broker_id: i32) -> Address {
     format!("kafka_outlet_{}", broker_id).into()
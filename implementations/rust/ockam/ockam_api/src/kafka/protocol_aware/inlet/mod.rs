@@ -4,7 +4,10 @@ use crate::kafka::protocol_aware::{
     CorrelationId, KafkaMessageInterceptor, KafkaMessageInterceptorWrapper, RequestInfo,
     TopicUuidMap, MAX_KAFKA_MESSAGE_SIZE,
 };
-use crate::kafka::KafkaInletController;
+use crate::kafka::{
+    DeterministicKeySecret, FieldPath, KafkaInletController, KafkaRecordEncryption,
+    KafkaTopicPolicies, SchemaRegistryClient, TopicEncryption,
+};
 use ockam_core::async_trait;
 use ockam_core::compat::collections::HashMap;
 use ockam_transport_tcp::{PortalInterceptor, PortalInterceptorFactory};
@@ -23,7 +26,11 @@ pub(crate) struct InletInterceptorImpl {
     key_exchange_controller: Arc<dyn KafkaKeyExchangeController>,
     inlet_map: KafkaInletController,
     encrypt_content: bool,
-    encrypted_fields: Vec<String>,
+    encrypted_fields: Vec<FieldPath>,
+    record_encryption: KafkaRecordEncryption,
+    deterministic_key_secret: Option<DeterministicKeySecret>,
+    schema_registry: Option<SchemaRegistryClient>,
+    topic_policies: KafkaTopicPolicies,
 }
 
 #[async_trait]
@@ -43,10 +50,67 @@ impl InletInterceptorImpl {
             key_exchange_controller,
             inlet_map,
             encrypt_content,
-            encrypted_fields,
+            encrypted_fields: encrypted_fields
+                .iter()
+                .map(|field| FieldPath::parse_or_field(field))
+                .collect(),
+            record_encryption: Default::default(),
+            deterministic_key_secret: None,
+            schema_registry: None,
+            topic_policies: Default::default(),
+        }
+    }
+
+    /// Encrypt the keys and headers of the records, in addition to their values
+    pub(crate) fn with_record_encryption(
+        mut self,
+        record_encryption: KafkaRecordEncryption,
+    ) -> Self {
+        self.record_encryption = record_encryption;
+        self
+    }
+
+    /// Use a secret to encrypt the keys of the topics with deterministic key encryption.
+    /// It is required if the keys of some topics are deterministically encrypted
+    pub(crate) fn with_deterministic_key_secret(
+        mut self,
+        deterministic_key_secret: Option<DeterministicKeySecret>,
+    ) -> Self {
+        self.deterministic_key_secret = deterministic_key_secret;
+        self
+    }
+
+    /// Use a schema registry to decode the records serialized with a schema id,
+    /// when encrypting specific fields
+    pub(crate) fn with_schema_registry(
+        mut self,
+        schema_registry: Option<SchemaRegistryClient>,
+    ) -> Self {
+        self.schema_registry = schema_registry;
+        self
+    }
+
+    /// Encrypt or pass through the records of some topics, regardless of the content encryption
+    /// of the inlet
+    pub(crate) fn with_topic_policies(mut self, topic_policies: KafkaTopicPolicies) -> Self {
+        self.topic_policies = topic_policies;
+        self
+    }
+
+    /// Return true if the records of the topic are encrypted
+    pub(crate) fn is_topic_encrypted(&self, topic: &str) -> bool {
+        match self.topic_policies.encryption(topic) {
+            Some(TopicEncryption::Encrypted) => true,
+            Some(TopicEncryption::Passthrough) => false,
+            None => self.encrypt_content,
         }
     }
 
+    /// Return true if the records of at least one topic are encrypted
+    pub(crate) fn has_encrypted_topics(&self) -> bool {
+        self.encrypt_content || self.topic_policies.has_encrypted_topics()
+    }
+
     #[cfg(test)]
     pub(crate) fn add_request(
         &self,
@@ -70,6 +134,13 @@ pub(crate) struct KafkaInletInterceptorFactory {
     inlet_map: KafkaInletController,
     encrypt_content: bool,
     encrypted_fields: Vec<String>,
+    record_encryption: KafkaRecordEncryption,
+    // shared by all the connections of the inlet, to cache the schemas
+    schema_registry: Option<SchemaRegistryClient>,
+    topic_policies: KafkaTopicPolicies,
+    // shared by all the connections of the inlet, so that equal keys
+    // are encrypted to the same value
+    deterministic_key_secret: Option<DeterministicKeySecret>,
 }
 
 impl KafkaInletInterceptorFactory {
@@ -78,6 +149,9 @@ impl KafkaInletInterceptorFactory {
         inlet_map: KafkaInletController,
         encrypt_content: bool,
         encrypted_fields: Vec<String>,
+        record_encryption: KafkaRecordEncryption,
+        schema_registry: Option<SchemaRegistryClient>,
+        topic_policies: KafkaTopicPolicies,
     ) -> Self {
         Self {
             secure_channel_controller,
@@ -85,20 +159,39 @@ impl KafkaInletInterceptorFactory {
             inlet_map,
             encrypt_content,
             encrypted_fields,
+            record_encryption,
+            schema_registry,
+            topic_policies,
+            deterministic_key_secret: None,
         }
     }
+
+    /// Use a secret to encrypt the keys of the topics with deterministic key encryption
+    pub(crate) fn with_deterministic_key_secret(
+        mut self,
+        deterministic_key_secret: Option<DeterministicKeySecret>,
+    ) -> Self {
+        self.deterministic_key_secret = deterministic_key_secret;
+        self
+    }
 }
 
 impl PortalInterceptorFactory for KafkaInletInterceptorFactory {
     fn create(&self) -> Arc<dyn PortalInterceptor> {
         Arc::new(KafkaMessageInterceptorWrapper::new(
-            Arc::new(InletInterceptorImpl::new(
-                Arc::new(self.secure_channel_controller.clone()),
-                self.uuid_to_name.clone(),
-                self.inlet_map.clone(),
-                self.encrypt_content,
-                self.encrypted_fields.clone(),
-            )),
+            Arc::new(
+                InletInterceptorImpl::new(
+                    Arc::new(self.secure_channel_controller.clone()),
+                    self.uuid_to_name.clone(),
+                    self.inlet_map.clone(),
+                    self.encrypt_content,
+                    self.encrypted_fields.clone(),
+                )
+                .with_record_encryption(self.record_encryption.clone())
+                .with_deterministic_key_secret(self.deterministic_key_secret.clone())
+                .with_schema_registry(self.schema_registry.clone())
+                .with_topic_policies(self.topic_policies.clone()),
+            ),
             MAX_KAFKA_MESSAGE_SIZE,
         ))
     }
@@ -1,17 +1,21 @@
+use crate::kafka::field_encryption::FieldEncryptedRecord;
 use crate::kafka::protocol_aware::inlet::InletInterceptorImpl;
-use crate::kafka::protocol_aware::utils::{decode_body, encode_request};
-use crate::kafka::protocol_aware::RequestInfo;
-use crate::kafka::protocol_aware::{InterceptError, KafkaMessageRequestInterceptor};
+use crate::kafka::protocol_aware::utils::{
+    decode_body, decode_record_batches, encode_record_batches, encode_request,
+};
+use crate::kafka::protocol_aware::{
+    InterceptError, KafkaMessageRequestInterceptor, RequestInfo, PRODUCE_NODE_ENDPOINTS_VERSION,
+    SHARE_GROUP_API_KEYS,
+};
+use crate::kafka::{KeyEncryption, TopicRecordEncryption, ENCRYPTED_KEY_HEADER};
 use bytes::{Bytes, BytesMut};
 use kafka_protocol::messages::fetch_request::FetchRequest;
 use kafka_protocol::messages::produce_request::{PartitionProduceData, ProduceRequest};
 use kafka_protocol::messages::request_header::RequestHeader;
 use kafka_protocol::messages::{ApiKey, ApiVersionsRequest, TopicName};
 use kafka_protocol::protocol::buf::ByteBuf;
-use kafka_protocol::protocol::{Decodable, Message};
-use kafka_protocol::records::{
-    Compression, RecordBatchDecoder, RecordBatchEncoder, RecordEncodeOptions,
-};
+use kafka_protocol::protocol::{Decodable, Message, StrBytes};
+use kafka_protocol::records::Record;
 use minicbor::encode::Encoder;
 use ockam_core::async_trait;
 use ockam_node::Context;
@@ -39,6 +43,11 @@ impl KafkaMessageRequestInterceptor for InletInterceptorImpl {
 
         let api_key_num = buffer.peek_bytes(0..2).try_get_i16()?;
 
+        if SHARE_GROUP_API_KEYS.contains(&api_key_num) {
+            warn!("share groups are not supported, rejecting request api: {api_key_num}");
+            return Err(InterceptError::InvalidData);
+        }
+
         let api_key = ApiKey::try_from(api_key_num).map_err(|_| {
             warn!("unknown request api: {api_key_num}");
             InterceptError::InvalidData
@@ -71,17 +80,26 @@ impl KafkaMessageRequestInterceptor for InletInterceptorImpl {
             }
 
             ApiKey::Produce => {
-                if self.encrypt_content {
+                if self.has_encrypted_topics() {
                     return self
                         .handle_produce_request(context, &mut buffer, &header)
                         .await;
+                } else if header.request_api_version >= PRODUCE_NODE_ENDPOINTS_VERSION {
+                    // the response may contain the endpoints of new partition leaders
+                    let request: ProduceRequest =
+                        decode_body(&mut buffer, header.request_api_version)?;
+                    self.map_produce_request(&header, &request);
                 }
             }
             ApiKey::Fetch => {
                 self.handle_fetch_request(context, &mut buffer, &header)
                     .await?;
             }
-            ApiKey::Metadata | ApiKey::FindCoordinator => {
+            // the responses of these requests contain broker addresses or topic ids
+            ApiKey::Metadata
+            | ApiKey::FindCoordinator
+            | ApiKey::DescribeCluster
+            | ApiKey::DescribeTopicPartitions => {
                 self.request_map.lock().unwrap().insert(
                     header.correlation_id,
                     RequestInfo {
@@ -168,6 +186,11 @@ impl InletInterceptorImpl {
                     })?
             };
 
+            // the consumer doesn't need encryption keys for passthrough topics
+            if !self.is_topic_encrypted(&topic_id) {
+                continue;
+            }
+
             let partitions: Vec<i32> = topic
                 .partitions
                 .iter()
@@ -197,25 +220,32 @@ impl InletInterceptorImpl {
         header: &RequestHeader,
     ) -> Result<BytesMut, InterceptError> {
         let mut request: ProduceRequest = decode_body(buffer, header.request_api_version)?;
+        self.map_produce_request(header, &request);
 
         // the content can be set in multiple topics and partitions in a single message
         // for each we wrap the content and add the secure channel identifier of
         // the encrypted content
         for topic in request.topic_data.iter_mut() {
+            if !self.is_topic_encrypted(topic.name.as_str()) {
+                continue;
+            }
+            let record_encryption = self.record_encryption.for_topic(topic.name.as_str());
             for data in &mut topic.partition_data {
                 if let Some(content) = data.records.take() {
-                    let mut content = BytesMut::from(content.as_ref());
-                    let mut records = RecordBatchDecoder::decode::<
-                        BytesMut,
-                        fn(&mut Bytes, Compression) -> Result<BytesMut, _>,
-                    >(&mut content)
-                    .map_err(|_| InterceptError::InvalidData)?;
+                    // each batch is re-encoded with its original compression codec
+                    let mut batches = decode_record_batches(&content)?;
+                    // producers never send control records, and a truncated batch
+                    // would be forwarded to the broker without being encrypted
+                    if batches.iter().any(|b| b.unchanged.is_some()) {
+                        warn!("invalid record batch in a produce request, closing connection");
+                        return Err(InterceptError::InvalidData);
+                    }
 
-                    for record in records.iter_mut() {
+                    for record in batches.iter_mut().flat_map(|b| b.records.iter_mut()) {
                         if let Some(record_value) = record.value.take() {
                             let buffer = if !self.encrypted_fields.is_empty() {
-                                // if we encrypt only specific fields, we assume the record must be
-                                // valid JSON map
+                                // if we encrypt only specific fields, the record must be valid JSON,
+                                // or serialized with a schema registered in the schema registry
                                 self.encrypt_specific_fields(
                                     context,
                                     &topic.name,
@@ -229,24 +259,19 @@ impl InletInterceptorImpl {
                             };
                             record.value = Some(buffer.into());
                         }
+                        if !record_encryption.is_none() {
+                            self.encrypt_key_and_headers(
+                                context,
+                                &topic.name,
+                                data,
+                                record,
+                                &record_encryption,
+                            )
+                            .await?;
+                        }
                     }
 
-                    let mut encoded = BytesMut::new();
-                    RecordBatchEncoder::encode::<
-                        BytesMut,
-                        std::slice::Iter<'_, kafka_protocol::records::Record>,
-                        fn(&mut BytesMut, &mut BytesMut, Compression) -> Result<(), _>,
-                    >(
-                        &mut encoded,
-                        records.iter(),
-                        &RecordEncodeOptions {
-                            version: 2,
-                            compression: Compression::None,
-                        },
-                    )
-                    .map_err(|_| InterceptError::InvalidData)?;
-
-                    data.records = Some(encoded.freeze());
+                    data.records = Some(encode_record_batches(&batches)?);
                 }
             }
         }
@@ -259,6 +284,21 @@ impl InletInterceptorImpl {
         )
     }
 
+    /// Keep track of the produce requests whose response contains the endpoints of the
+    /// partition leaders, so that they can be replaced with inlet addresses
+    fn map_produce_request(&self, header: &RequestHeader, request: &ProduceRequest) {
+        // without acknowledgment, the broker doesn't send any response
+        if header.request_api_version >= PRODUCE_NODE_ENDPOINTS_VERSION && request.acks != 0 {
+            self.request_map.lock().unwrap().insert(
+                header.correlation_id,
+                RequestInfo {
+                    request_api_key: ApiKey::Produce,
+                    request_api_version: header.request_api_version,
+                },
+            );
+        }
+    }
+
     async fn encrypt_whole_record(
         &self,
         context: &mut Context,
@@ -281,6 +321,63 @@ impl InletInterceptorImpl {
         Ok(write_buffer)
     }
 
+    async fn encrypt_key_and_headers(
+        &self,
+        context: &mut Context,
+        topic_name: &TopicName,
+        data: &mut PartitionProduceData,
+        record: &mut Record,
+        record_encryption: &TopicRecordEncryption,
+    ) -> Result<(), InterceptError> {
+        for (name, value) in record.headers.iter_mut() {
+            if record_encryption.headers.is_encrypted(name) {
+                if let Some(header_value) = value.take() {
+                    let encrypted = self
+                        .encrypt_whole_record(context, topic_name, data, header_value)
+                        .await?;
+                    *value = Some(encrypted.into());
+                }
+            }
+        }
+
+        if let Some(key) = record.key.take() {
+            let key = match record_encryption.key {
+                KeyEncryption::None => key,
+                KeyEncryption::Randomized => self
+                    .encrypt_whole_record(context, topic_name, data, key)
+                    .await?
+                    .into(),
+                KeyEncryption::Deterministic => {
+                    // the key is replaced by a value which is the same for equal keys,
+                    // so that the records are still partitioned by key.
+                    // The original key is sent encrypted in a header
+                    let Some(deterministic_key_secret) = &self.deterministic_key_secret else {
+                        warn!("no secret to encrypt the record keys deterministically, closing connection");
+                        return Err(InterceptError::InvalidData);
+                    };
+                    let header_name = StrBytes::from_static_str(ENCRYPTED_KEY_HEADER);
+                    if record.headers.contains_key(&header_name) {
+                        warn!(
+                            "a record already has a {ENCRYPTED_KEY_HEADER} header, closing connection"
+                        );
+                        return Err(InterceptError::InvalidData);
+                    }
+                    let deterministic_key =
+                        deterministic_key_secret.encrypt_key(topic_name.as_str(), &key);
+                    let encrypted_key = self
+                        .encrypt_whole_record(context, topic_name, data, key)
+                        .await?;
+                    record
+                        .headers
+                        .insert(header_name, Some(encrypted_key.into()));
+                    deterministic_key.into()
+                }
+            };
+            record.key = Some(key);
+        }
+        Ok(())
+    }
+
     async fn encrypt_specific_fields(
         &self,
         context: &mut Context,
@@ -288,35 +385,27 @@ impl InletInterceptorImpl {
         data: &mut PartitionProduceData,
         record_value: &Bytes,
     ) -> Result<Vec<u8>, InterceptError> {
-        let mut record_value = serde_json::from_slice::<serde_json::Value>(record_value)?;
-
-        if let serde_json::Value::Object(map) = &mut record_value {
-            for field in &self.encrypted_fields {
-                if let Some(value) = map.get_mut(field) {
-                    let encrypted_content = self
-                        .key_exchange_controller
-                        .encrypt_content(
-                            context,
-                            topic_name,
-                            data.index,
-                            serde_json::to_vec(value).map_err(|_| InterceptError::InvalidData)?,
-                        )
-                        .await
-                        .map_err(InterceptError::Ockam)?;
-
-                    let mut write_buffer = Vec::with_capacity(1024);
-                    let mut encoder = Encoder::new(&mut write_buffer);
-                    encoder
-                        .encode(encrypted_content)
-                        .map_err(|_| InterceptError::InvalidData)?;
-                    *value = serde_json::Value::String(hex::encode(&write_buffer));
-                }
+        let mut record =
+            FieldEncryptedRecord::decode(record_value, self.schema_registry.as_ref(), false)
+                .await?;
+
+        for mut field in record.select(&self.encrypted_fields)? {
+            if let Some(plain_text) = field.plain_text()? {
+                let encrypted_content = self
+                    .key_exchange_controller
+                    .encrypt_content(context, topic_name, data.index, plain_text)
+                    .await
+                    .map_err(InterceptError::Ockam)?;
+
+                let mut write_buffer = Vec::with_capacity(1024);
+                let mut encoder = Encoder::new(&mut write_buffer);
+                encoder
+                    .encode(encrypted_content)
+                    .map_err(|_| InterceptError::InvalidData)?;
+                field.set_encrypted(write_buffer);
             }
-        } else {
-            warn!("only JSON objects are supported for field encryption");
-            return Err("Only JSON objects are supported".into());
         }
 
-        Ok(record_value.to_string().as_bytes().to_vec())
+        record.encode()
     }
 }
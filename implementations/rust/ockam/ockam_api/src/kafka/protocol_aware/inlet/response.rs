@@ -1,19 +1,25 @@
+use crate::kafka::field_encryption::FieldEncryptedRecord;
 use crate::kafka::protocol_aware::inlet::InletInterceptorImpl;
-use crate::kafka::protocol_aware::utils::{decode_body, encode_response};
+use crate::kafka::protocol_aware::utils::{
+    data_records_mut, decode_body, decode_record_batches, encode_record_batches, encode_response,
+};
 use crate::kafka::protocol_aware::{
     InterceptError, KafkaEncryptedContent, KafkaMessageResponseInterceptor, RequestInfo,
+    FETCH_NODE_ENDPOINTS_VERSION, SHARE_GROUP_API_KEYS,
+};
+use crate::kafka::{
+    KafkaInletController, KeyEncryption, TopicRecordEncryption, ENCRYPTED_KEY_HEADER,
 };
-use crate::kafka::KafkaInletController;
 use bytes::{Bytes, BytesMut};
+use kafka_protocol::messages::fetch_response::FetchableTopicResponse;
 use kafka_protocol::messages::{
-    ApiKey, ApiVersionsResponse, CreateTopicsResponse, FetchResponse, FindCoordinatorResponse,
-    ListOffsetsResponse, MetadataResponse, ProduceResponse, ResponseHeader,
+    ApiKey, ApiVersionsResponse, BrokerId, CreateTopicsResponse, DescribeClusterResponse,
+    DescribeTopicPartitionsResponse, FetchResponse, FindCoordinatorResponse, ListOffsetsResponse,
+    MetadataResponse, ProduceResponse, ResponseHeader, TopicName,
 };
 use kafka_protocol::protocol::buf::ByteBuf;
 use kafka_protocol::protocol::{Decodable, Message, StrBytes};
-use kafka_protocol::records::{
-    Compression, RecordBatchDecoder, RecordBatchEncoder, RecordEncodeOptions,
-};
+use kafka_protocol::records::Record;
 use minicbor::Decoder;
 use ockam_core::async_trait;
 use ockam_node::Context;
@@ -65,13 +71,36 @@ impl KafkaMessageResponseInterceptor for InletInterceptorImpl {
                 }
 
                 ApiKey::Fetch => {
-                    if self.encrypt_content {
+                    if self.has_encrypted_topics()
+                        || request_info.request_api_version >= FETCH_NODE_ENDPOINTS_VERSION
+                    {
                         return self
                             .handle_fetch_response(context, &mut buffer, &request_info, &header)
                             .await;
                     }
                 }
 
+                ApiKey::Produce => {
+                    return self
+                        .handle_produce_response(context, &mut buffer, &request_info, &header)
+                        .await;
+                }
+
+                ApiKey::DescribeCluster => {
+                    return self
+                        .handle_describe_cluster_response(
+                            context,
+                            &mut buffer,
+                            &request_info,
+                            &header,
+                        )
+                        .await;
+                }
+
+                ApiKey::DescribeTopicPartitions => {
+                    self.handle_describe_topic_partitions_response(&mut buffer, &request_info)?;
+                }
+
                 ApiKey::FindCoordinator => {
                     return self
                         .handle_find_coordinator_response(
@@ -128,6 +157,21 @@ impl InletInterceptorImpl {
         // To avoid breakage every time a client or server is updated, we reduce the
         // version of the protocol to the supported version for each api.
 
+        // The APIs which are unknown to ockam are removed from the response so that the
+        // clients don't use them. The share groups are not supported, the clients use
+        // consumer groups instead.
+        response.api_keys.retain(|api_version| {
+            if SHARE_GROUP_API_KEYS.contains(&api_version.api_key) {
+                debug!("removing share group api key: {}", api_version.api_key);
+                return false;
+            }
+            let known = ApiKey::try_from(api_version.api_key).is_ok();
+            if !known {
+                warn!("removing unknown api key: {}", api_version.api_key);
+            }
+            known
+        });
+
         for api_version in response.api_keys.iter_mut() {
             let result = ApiKey::try_from(api_version.api_key);
             let api_key = match result {
@@ -147,6 +191,8 @@ impl InletInterceptorImpl {
                 ApiKey::ApiVersions => ApiVersionsResponse::VERSIONS,
                 ApiKey::CreateTopics => CreateTopicsResponse::VERSIONS,
                 ApiKey::FindCoordinator => FindCoordinatorResponse::VERSIONS,
+                ApiKey::DescribeCluster => DescribeClusterResponse::VERSIONS,
+                ApiKey::DescribeTopicPartitions => DescribeTopicPartitionsResponse::VERSIONS,
                 _ => {
                     // we only need to check the APIs that we actually use
                     continue;
@@ -194,37 +240,25 @@ impl InletInterceptorImpl {
         // we need to keep a map of topic uuid to topic name since fetch
         // operations only use uuid
         if request_info.request_api_version >= 10 {
-            for topic in &response.topics {
-                let topic_id = topic.topic_id.to_string();
-                let topic_name = if let Some(name) = &topic.name {
-                    name.to_string()
-                } else {
-                    continue;
-                };
-
-                trace!("metadata adding to map: {topic_id} => {topic_name}");
-                self.uuid_to_name
-                    .lock()
-                    .unwrap()
-                    .insert(topic_id, topic_name);
-            }
+            self.add_topic_ids(
+                response
+                    .topics
+                    .iter()
+                    .map(|topic| (topic.topic_id.to_string(), topic.name.as_ref())),
+            );
         }
 
         trace!("metadata response before: {:?}", &response);
 
         for broker in response.brokers.iter_mut() {
-            let inlet_address = inlet_map
-                .assert_inlet_for_broker(context, broker.node_id.0)
-                .await?;
-
-            trace!(
-                "inlet_address: {} for broker {}",
-                &inlet_address,
-                broker.node_id.0
-            );
-
-            broker.host = StrBytes::from_string(inlet_address.hostname());
-            broker.port = inlet_address.port() as i32;
+            Self::replace_broker_address(
+                context,
+                inlet_map,
+                broker.node_id,
+                &mut broker.host,
+                &mut broker.port,
+            )
+            .await?;
         }
         trace!("metadata response after: {:?}", &response);
 
@@ -252,20 +286,28 @@ impl InletInterceptorImpl {
         // the format changed to array since version 4
         if request_info.request_api_version >= 4 {
             for coordinator in response.coordinators.iter_mut() {
-                let inlet_address = inlet_map
-                    .assert_inlet_for_broker(context, coordinator.node_id.0)
-                    .await?;
-
-                coordinator.host = StrBytes::from_string(inlet_address.hostname());
-                coordinator.port = inlet_address.port() as i32;
-            }
-        } else {
-            let inlet_address = inlet_map
-                .assert_inlet_for_broker(context, response.node_id.0)
+                // a coordinator which can't be found has no node id
+                if coordinator.error_code != 0 {
+                    continue;
+                }
+                Self::replace_broker_address(
+                    context,
+                    inlet_map,
+                    coordinator.node_id,
+                    &mut coordinator.host,
+                    &mut coordinator.port,
+                )
                 .await?;
-
-            response.host = StrBytes::from_string(inlet_address.hostname());
-            response.port = inlet_address.port() as i32;
+            }
+        } else if response.error_code == 0 {
+            Self::replace_broker_address(
+                context,
+                inlet_map,
+                response.node_id,
+                &mut response.host,
+                &mut response.port,
+            )
+            .await?;
         }
 
         encode_response(
@@ -285,20 +327,56 @@ impl InletInterceptorImpl {
     ) -> Result<BytesMut, InterceptError> {
         let mut response: FetchResponse = decode_body(buffer, request_info.request_api_version)?;
 
+        for endpoint in response.node_endpoints.iter_mut() {
+            Self::replace_broker_address(
+                context,
+                &self.inlet_map,
+                endpoint.node_id,
+                &mut endpoint.host,
+                &mut endpoint.port,
+            )
+            .await?;
+        }
+
+        if self.has_encrypted_topics() {
+            self.decrypt_fetch_response(context, &mut response, request_info)
+                .await?;
+        }
+
+        encode_response(
+            header,
+            &response,
+            request_info.request_api_version,
+            ApiKey::Fetch,
+        )
+    }
+
+    async fn decrypt_fetch_response(
+        &self,
+        context: &mut Context,
+        fetch_response: &mut FetchResponse,
+        request_info: &RequestInfo,
+    ) -> Result<(), InterceptError> {
         // in every response we want to decrypt the message content
         // we take every record batch content, unwrap and decode it
         // using the relative secure channel
-        for response in response.responses.iter_mut() {
+        for response in fetch_response.responses.iter_mut() {
+            let record_encryption =
+                if self.record_encryption.is_empty() && self.topic_policies.is_empty() {
+                    TopicRecordEncryption::default()
+                } else {
+                    let topic_name = self.fetched_topic_name(response, request_info)?;
+                    if !self.is_topic_encrypted(&topic_name) {
+                        continue;
+                    }
+                    self.record_encryption.for_topic(&topic_name)
+                };
+
             for partition in response.partitions.iter_mut() {
                 if let Some(content) = partition.records.take() {
-                    let mut content = BytesMut::from(content.as_ref());
-                    let mut records = RecordBatchDecoder::decode::<
-                        BytesMut,
-                        fn(&mut Bytes, Compression) -> Result<BytesMut, _>,
-                    >(&mut content)
-                    .map_err(|_| InterceptError::InvalidData)?;
-
-                    for record in records.iter_mut() {
+                    let mut batches = decode_record_batches(&content)?;
+
+                    for record in data_records_mut(&mut batches) {
                         if let Some(record_value) = record.value.take() {
                             let decrypted_content = if self.encrypted_fields.is_empty() {
                                 self.decrypt_whole_record(context, record_value).await?
@@ -307,35 +385,190 @@ impl InletInterceptorImpl {
                             };
                             record.value = Some(decrypted_content.into());
                         }
+                        if !record_encryption.is_none() {
+                            self.decrypt_key_and_headers(context, record, &record_encryption)
+                                .await?;
+                        }
                     }
 
-                    let mut encoded = BytesMut::new();
-                    RecordBatchEncoder::encode::<
-                        BytesMut,
-                        std::slice::Iter<'_, kafka_protocol::records::Record>,
-                        fn(&mut BytesMut, &mut BytesMut, Compression) -> Result<(), _>,
-                    >(
-                        &mut encoded,
-                        records.iter(),
-                        &RecordEncodeOptions {
-                            version: 2,
-                            compression: Compression::None,
-                        },
-                    )
-                    .map_err(|_| InterceptError::InvalidData)?;
-                    partition.records = Some(encoded.freeze());
+                    partition.records = Some(encode_record_batches(&batches)?);
                 }
             }
         }
+        Ok(())
+    }
+
+    /// The produce responses may contain the endpoints of new partition leaders
+    async fn handle_produce_response(
+        &self,
+        context: &mut Context,
+        buffer: &mut Bytes,
+        request_info: &RequestInfo,
+        header: &ResponseHeader,
+    ) -> Result<BytesMut, InterceptError> {
+        let mut response: ProduceResponse = decode_body(buffer, request_info.request_api_version)?;
+
+        for endpoint in response.node_endpoints.iter_mut() {
+            Self::replace_broker_address(
+                context,
+                &self.inlet_map,
+                endpoint.node_id,
+                &mut endpoint.host,
+                &mut endpoint.port,
+            )
+            .await?;
+        }
 
         encode_response(
             header,
             &response,
             request_info.request_api_version,
-            ApiKey::Fetch,
+            ApiKey::Produce,
+        )
+    }
+
+    // the brokers of the cluster are used by admin clients
+    async fn handle_describe_cluster_response(
+        &self,
+        context: &mut Context,
+        buffer: &mut Bytes,
+        request_info: &RequestInfo,
+        header: &ResponseHeader,
+    ) -> Result<BytesMut, InterceptError> {
+        let mut response: DescribeClusterResponse =
+            decode_body(buffer, request_info.request_api_version)?;
+
+        for broker in response.brokers.iter_mut() {
+            Self::replace_broker_address(
+                context,
+                &self.inlet_map,
+                broker.broker_id,
+                &mut broker.host,
+                &mut broker.port,
+            )
+            .await?;
+        }
+
+        encode_response(
+            header,
+            &response,
+            request_info.request_api_version,
+            ApiKey::DescribeCluster,
         )
     }
 
+    /// Recent clients use DescribeTopicPartitions instead of Metadata to get the topic ids
+    fn handle_describe_topic_partitions_response(
+        &self,
+        buffer: &mut Bytes,
+        request_info: &RequestInfo,
+    ) -> Result<(), InterceptError> {
+        let response: DescribeTopicPartitionsResponse =
+            decode_body(buffer, request_info.request_api_version)?;
+        self.add_topic_ids(
+            response
+                .topics
+                .iter()
+                .map(|topic| (topic.topic_id.to_string(), topic.name.as_ref())),
+        );
+        Ok(())
+    }
+
+    /// Keep the names of the topics, indexed by their ids
+    fn add_topic_ids<'a>(&self, topics: impl Iterator<Item = (String, Option<&'a TopicName>)>) {
+        let mut uuid_to_name = self.uuid_to_name.lock().unwrap();
+        for (topic_id, topic_name) in topics {
+            if let Some(topic_name) = topic_name {
+                trace!("adding to map: {topic_id} => {}", topic_name.as_str());
+                uuid_to_name.insert(topic_id, topic_name.to_string());
+            }
+        }
+    }
+
+    /// Replace the address of a broker with the address of the inlet dedicated to that broker
+    async fn replace_broker_address(
+        context: &mut Context,
+        inlet_map: &KafkaInletController,
+        broker_id: BrokerId,
+        host: &mut StrBytes,
+        port: &mut i32,
+    ) -> Result<(), InterceptError> {
+        let inlet_address = inlet_map
+            .assert_inlet_for_broker(context, broker_id.0)
+            .await?;
+        trace!("inlet_address: {inlet_address} for broker {}", broker_id.0);
+
+        *host = StrBytes::from_string(inlet_address.hostname());
+        *port = inlet_address.port() as i32;
+        Ok(())
+    }
+
+    /// Return the name of the topic of fetched records.
+    /// Recent versions of the protocol only return the topic id, which is mapped to the
+    /// topic name with the previous metadata responses
+    fn fetched_topic_name(
+        &self,
+        response: &FetchableTopicResponse,
+        request_info: &RequestInfo,
+    ) -> Result<String, InterceptError> {
+        if request_info.request_api_version <= 12 {
+            Ok(response.topic.to_string())
+        } else {
+            let topic_id = response.topic_id.to_string();
+            self.uuid_to_name
+                .lock()
+                .unwrap()
+                .get(&topic_id)
+                .cloned()
+                .ok_or_else(|| {
+                    warn!("missing map from uuid {topic_id} to name");
+                    InterceptError::InvalidData
+                })
+        }
+    }
+
+    async fn decrypt_key_and_headers(
+        &self,
+        context: &mut Context,
+        record: &mut Record,
+        record_encryption: &TopicRecordEncryption,
+    ) -> Result<(), InterceptError> {
+        match record_encryption.key {
+            KeyEncryption::None => {}
+            KeyEncryption::Randomized => {
+                if let Some(key) = record.key.take() {
+                    record.key = Some(self.decrypt_whole_record(context, key).await?.into());
+                }
+            }
+            KeyEncryption::Deterministic => {
+                // the original key was encrypted in a header
+                if let Some(Some(encrypted_key)) = record
+                    .headers
+                    .shift_remove(&StrBytes::from_static_str(ENCRYPTED_KEY_HEADER))
+                {
+                    record.key = Some(
+                        self.decrypt_whole_record(context, encrypted_key)
+                            .await?
+                            .into(),
+                    );
+                }
+            }
+        }
+
+        for (name, value) in record.headers.iter_mut() {
+            if record_encryption.headers.is_encrypted(name) {
+                if let Some(header_value) = value.take() {
+                    *value = Some(
+                        self.decrypt_whole_record(context, header_value)
+                            .await?
+                            .into(),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
     async fn decrypt_whole_record(
         &self,
         context: &mut Context,
@@ -359,45 +592,31 @@ impl InletInterceptorImpl {
         context: &mut Context,
         record_value: Bytes,
     ) -> Result<Vec<u8>, InterceptError> {
-        let mut record_value = serde_json::from_slice::<serde_json::Value>(&record_value)?;
-
-        if let serde_json::Value::Object(map) = &mut record_value {
-            for field in &self.encrypted_fields {
-                // when the encrypted field is present is expected to be a hex encoded string
-                // wrapped by the KafkaEncryptedContent struct
-                if let Some(value) = map.get_mut(field) {
-                    let encrypted_content = if let serde_json::Value::String(string) = value {
-                        hex::decode(string).map_err(|_| "Encrypted is not a valid hex string")?
-                    } else {
-                        error!("encrypted field is not a hex string");
-                        return Err("The encrypted field is not a hex-encoded string".into());
-                    };
-
-                    let message_wrapper: KafkaEncryptedContent =
-                        Decoder::new(&encrypted_content).decode()?;
-
-                    let decrypted_content = self
-                        .key_exchange_controller
-                        .decrypt_content(
-                            context,
-                            &message_wrapper.consumer_decryptor_address,
-                            message_wrapper.content,
-                        )
-                        .await
-                        .map_err(InterceptError::Ockam)?;
+        let mut record =
+            FieldEncryptedRecord::decode(&record_value, self.schema_registry.as_ref(), true)
+                .await?;
 
-                    *value = serde_json::from_slice(decrypted_content.as_slice())?;
-                }
+        for mut field in record.select(&self.encrypted_fields)? {
+            // when the encrypted field is present it is expected to contain
+            // the KafkaEncryptedContent struct
+            if let Some(encrypted_content) = field.encrypted()? {
+                let message_wrapper: KafkaEncryptedContent =
+                    Decoder::new(&encrypted_content).decode()?;
+
+                let decrypted_content = self
+                    .key_exchange_controller
+                    .decrypt_content(
+                        context,
+                        &message_wrapper.consumer_decryptor_address,
+                        message_wrapper.content,
+                    )
+                    .await
+                    .map_err(InterceptError::Ockam)?;
+
+                field.set_decrypted(decrypted_content)?;
             }
-            serde_json::to_vec(&record_value).map_err(|error| {
-                error!("cannot serialize decrypted fields");
-                error.into()
-            })
-        } else {
-            error!(
-                "cannot decrypt specific fields, expected a JSON object but got a different type"
-            );
-            Err("Only JSON objects are supported in the message".into())
         }
+
+        record.encode()
     }
 }
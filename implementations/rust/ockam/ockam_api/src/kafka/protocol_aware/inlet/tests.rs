@@ -1,9 +1,15 @@
 use crate::kafka::key_exchange::KafkaKeyExchangeController;
 use crate::kafka::protocol_aware::inlet::InletInterceptorImpl;
+use crate::kafka::protocol_aware::utils::{
+    decode_record_batches, encode_record_batches, RecordBatch,
+};
 use crate::kafka::protocol_aware::{
     utils, KafkaEncryptedContent, KafkaMessageRequestInterceptor, KafkaMessageResponseInterceptor,
 };
-use crate::kafka::KafkaInletController;
+use crate::kafka::{
+    DeterministicKeySecret, HeadersEncryption, KafkaInletController, KafkaRecordEncryption,
+    KeyEncryption, ENCRYPTED_KEY_HEADER,
+};
 use bytes::{Bytes, BytesMut};
 use kafka_protocol::messages::fetch_response::{FetchableTopicResponse, PartitionData};
 use kafka_protocol::messages::produce_request::{PartitionProduceData, TopicProduceData};
@@ -364,3 +370,515 @@ pub async fn json_decrypt_specific_fields(context: &mut Context) -> ockam::Resul
 
     Ok(())
 }
+
+#[ockam::test]
+pub async fn json_encrypt_and_decrypt_nested_fields(context: &mut Context) -> ockam::Result<()> {
+    let interceptor = InletInterceptorImpl::new(
+        Arc::new(MockKafkaKeyExchangeController {}),
+        Default::default(),
+        KafkaInletController::stub(),
+        true,
+        vec!["$.customer.email".to_string(), "items[*].card".to_string()],
+    );
+    let original = json!({
+        "customer": {"name": "alice", "email": "alice@example.com"},
+        "items": [{"id": 1, "card": "1234"}, {"id": 2, "card": {"number": "5678"}}]
+    });
+
+    let encrypted_request = interceptor
+        .intercept_request(
+            context,
+            create_kafka_produce_request(original.to_string().as_bytes()),
+        )
+        .await
+        .unwrap();
+
+    let request = parse_produce_request(&encrypted_request);
+    let mut batch_content = request.topic_data[0].partition_data[0]
+        .records
+        .clone()
+        .unwrap();
+    let records = RecordBatchDecoder::decode::<
+        Bytes,
+        fn(&mut Bytes, Compression) -> Result<Bytes, _>,
+    >(&mut batch_content)
+    .unwrap();
+    let encrypted_value = records[0].value.clone().unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&encrypted_value).unwrap();
+
+    assert_eq!(json["customer"]["name"], json!("alice"));
+    assert_eq!(
+        decode_field_value(json["customer"]["email"].as_str().unwrap().to_string()),
+        json!("alice@example.com")
+    );
+    assert_eq!(json["items"][1]["id"], json!(2));
+    assert_eq!(
+        decode_field_value(json["items"][1]["card"].as_str().unwrap().to_string()),
+        json!({"number": "5678"})
+    );
+
+    interceptor.add_request(1, ApiKey::Fetch, TEST_KAFKA_API_VERSION);
+    let decrypted_response = interceptor
+        .intercept_response(context, create_kafka_fetch_response(&encrypted_value))
+        .await
+        .unwrap();
+
+    let response = parse_fetch_response(&decrypted_response);
+    let mut records = response.responses[0].partitions[0].records.clone().unwrap();
+    let records = RecordBatchDecoder::decode::<
+        Bytes,
+        fn(&mut Bytes, Compression) -> Result<Bytes, _>,
+    >(&mut records)
+    .unwrap();
+    let value: serde_json::Value =
+        serde_json::from_slice(records[0].value.as_ref().unwrap()).unwrap();
+    assert_eq!(value, original);
+
+    Ok(())
+}
+
+/// Records sent by an idempotent producer, optionally within a transaction
+fn create_producer_records(transactional: bool) -> Vec<Record> {
+    (0..3)
+        .map(|i| Record {
+            transactional,
+            control: false,
+            partition_leader_epoch: 0,
+            producer_id: 1234,
+            producer_epoch: 7,
+            timestamp_type: TimestampType::Creation,
+            offset: i,
+            sequence: 42 + i as i32,
+            timestamp: 1000 + i,
+            key: Some(Bytes::from(format!("key-{i}"))),
+            value: Some(Bytes::from(format!("value-{i}"))),
+            headers: Default::default(),
+        })
+        .collect()
+}
+
+fn create_kafka_produce_request_with_batch(batch: &RecordBatch) -> BytesMut {
+    let header = RequestHeader::default()
+        .with_request_api_key(ApiKey::Produce as i16)
+        .with_request_api_version(TEST_KAFKA_API_VERSION)
+        .with_correlation_id(1)
+        .with_client_id(Some(StrBytes::from_static_str("my-client-id")));
+
+    let topic_data = vec![TopicProduceData::default()
+        .with_name(TopicName::from(StrBytes::from_static_str("topic-name")))
+        .with_partition_data(vec![PartitionProduceData::default()
+            .with_index(1)
+            .with_records(Some(
+                encode_record_batches(std::slice::from_ref(batch)).unwrap(),
+            ))])];
+    let request = ProduceRequest::default().with_topic_data(topic_data);
+
+    utils::encode_request(&header, &request, TEST_KAFKA_API_VERSION, ApiKey::Produce).unwrap()
+}
+
+fn create_kafka_fetch_response_with_records(records: Bytes) -> BytesMut {
+    let header = ResponseHeader::default().with_correlation_id(1);
+    let response = FetchResponse::default().with_responses(vec![FetchableTopicResponse::default()
+        .with_topic(TopicName::from(StrBytes::from_static_str("topic-name")))
+        .with_topic_id(Default::default())
+        .with_partitions(vec![PartitionData::default()
+            .with_partition_index(1)
+            .with_records(Some(records))])]);
+
+    utils::encode_response(&header, &response, TEST_KAFKA_API_VERSION, ApiKey::Fetch).unwrap()
+}
+
+/// Return the compression codec stored in the attributes of a v2 record batch
+fn batch_compression(records: &Bytes) -> i16 {
+    i16::from_be_bytes([records[21], records[22]]) & 0x7
+}
+
+#[ockam::test]
+pub async fn produce_and_fetch_preserve_batch_metadata(context: &mut Context) -> ockam::Result<()> {
+    let interceptor = InletInterceptorImpl::new(
+        Arc::new(MockKafkaKeyExchangeController {}),
+        Default::default(),
+        KafkaInletController::stub(),
+        true,
+        vec![],
+    );
+
+    for compression in [
+        Compression::None,
+        Compression::Gzip,
+        Compression::Snappy,
+        Compression::Lz4,
+        Compression::Zstd,
+    ] {
+        for transactional in [false, true] {
+            let original = create_producer_records(transactional);
+            let batch = RecordBatch::new(
+                original.clone(),
+                RecordEncodeOptions {
+                    version: 2,
+                    compression,
+                },
+            );
+
+            // the produce request sent to the broker is encrypted but keeps the batch metadata
+            let encrypted_request = interceptor
+                .intercept_request(context, create_kafka_produce_request_with_batch(&batch))
+                .await
+                .unwrap();
+            let request = parse_produce_request(&encrypted_request);
+            let encrypted_records = request.topic_data[0].partition_data[0]
+                .records
+                .clone()
+                .unwrap();
+            assert_eq!(batch_compression(&encrypted_records), compression as i16);
+
+            let batches = decode_record_batches(&encrypted_records).unwrap();
+            assert_eq!(batches.len(), 1);
+            assert_eq!(batches[0].options.compression, compression);
+            assert_eq!(batches[0].records.len(), original.len());
+            for (encrypted, original) in batches[0].records.iter().zip(original.iter()) {
+                assert_eq!(encrypted.producer_id, original.producer_id);
+                assert_eq!(encrypted.producer_epoch, original.producer_epoch);
+                assert_eq!(encrypted.sequence, original.sequence);
+                assert_eq!(encrypted.offset, original.offset);
+                assert_eq!(encrypted.transactional, transactional);
+                assert_eq!(encrypted.timestamp, original.timestamp);
+                assert_eq!(encrypted.key, original.key);
+                assert_ne!(encrypted.value, original.value);
+            }
+
+            // the fetch response sent to the consumer is decrypted with the same metadata
+            interceptor.add_request(1, ApiKey::Fetch, TEST_KAFKA_API_VERSION);
+            let decrypted_response = interceptor
+                .intercept_response(
+                    context,
+                    create_kafka_fetch_response_with_records(encrypted_records),
+                )
+                .await
+                .unwrap();
+            let response = parse_fetch_response(&decrypted_response);
+            let decrypted_records = response.responses[0].partitions[0].records.clone().unwrap();
+            assert_eq!(batch_compression(&decrypted_records), compression as i16);
+
+            let batches = decode_record_batches(&decrypted_records).unwrap();
+            assert_eq!(batches.len(), 1);
+            assert_eq!(batches[0].records, original);
+        }
+    }
+
+    Ok(())
+}
+
+/// Transaction marker written by the transaction coordinator
+fn create_commit_marker(offset: i64) -> Record {
+    Record {
+        transactional: true,
+        control: true,
+        partition_leader_epoch: 0,
+        producer_id: 1234,
+        producer_epoch: 7,
+        timestamp_type: TimestampType::Creation,
+        offset,
+        sequence: -1,
+        timestamp: 2000,
+        // control record key: version 0, type 1 (commit)
+        key: Some(Bytes::from_static(&[0, 0, 0, 1])),
+        value: Some(Bytes::from_static(&[0, 0, 0, 0, 0, 0])),
+        headers: Default::default(),
+    }
+}
+
+#[ockam::test]
+pub async fn fetch_keeps_control_and_truncated_batches(context: &mut Context) -> ockam::Result<()> {
+    let interceptor = InletInterceptorImpl::new(
+        Arc::new(MockKafkaKeyExchangeController {}),
+        Default::default(),
+        KafkaInletController::stub(),
+        true,
+        vec![],
+    );
+    // encrypt a transaction of 3 records
+    let original = create_producer_records(true);
+    let encrypted_request = interceptor
+        .intercept_request(
+            context,
+            create_kafka_produce_request_with_batch(&RecordBatch::new(
+                original.clone(),
+                RecordEncodeOptions {
+                    version: 2,
+                    compression: Compression::None,
+                },
+            )),
+        )
+        .await
+        .unwrap();
+    let request = parse_produce_request(&encrypted_request);
+    let encrypted_records = request.topic_data[0].partition_data[0]
+        .records
+        .clone()
+        .unwrap();
+
+    // the broker returns the transaction, its commit marker
+    // and the beginning of the next batch, truncated by the maximum fetch size
+    let control_batch = encode_record_batches(&[RecordBatch::new(
+        vec![create_commit_marker(3)],
+        RecordEncodeOptions {
+            version: 2,
+            compression: Compression::None,
+        },
+    )])
+    .unwrap();
+    let truncated_batch = encrypted_records.slice(..encrypted_records.len() / 2);
+    let mut fetched = BytesMut::new();
+    fetched.extend_from_slice(&encrypted_records);
+    fetched.extend_from_slice(&control_batch);
+    fetched.extend_from_slice(&truncated_batch);
+
+    interceptor.add_request(1, ApiKey::Fetch, TEST_KAFKA_API_VERSION);
+    let decrypted_response = interceptor
+        .intercept_response(
+            context,
+            create_kafka_fetch_response_with_records(fetched.freeze()),
+        )
+        .await
+        .unwrap();
+    let response = parse_fetch_response(&decrypted_response);
+    let decrypted_records = response.responses[0].partitions[0].records.clone().unwrap();
+
+    let batches = decode_record_batches(&decrypted_records).unwrap();
+    assert_eq!(batches.len(), 3);
+    assert_eq!(batches[0].records, original);
+    assert_eq!(batches[1].unchanged, Some(control_batch));
+    assert_eq!(batches[2].unchanged, Some(truncated_batch.clone()));
+
+    // a truncated batch is never forwarded to the broker unencrypted
+    let header = RequestHeader::default()
+        .with_request_api_key(ApiKey::Produce as i16)
+        .with_request_api_version(TEST_KAFKA_API_VERSION)
+        .with_correlation_id(1);
+    let request = ProduceRequest::default().with_topic_data(vec![TopicProduceData::default()
+        .with_name(TopicName::from(StrBytes::from_static_str("topic-name")))
+        .with_partition_data(vec![PartitionProduceData::default()
+            .with_index(1)
+            .with_records(Some(truncated_batch))])]);
+    let request =
+        utils::encode_request(&header, &request, TEST_KAFKA_API_VERSION, ApiKey::Produce).unwrap();
+    let result = interceptor.intercept_request(context, request).await;
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+fn create_record_with_key_and_headers(key: &str) -> Record {
+    let mut record = Record {
+        transactional: false,
+        control: false,
+        partition_leader_epoch: 0,
+        producer_id: 0,
+        producer_epoch: 0,
+        timestamp_type: TimestampType::Creation,
+        offset: 0,
+        sequence: 0,
+        timestamp: 0,
+        key: Some(Bytes::from(key.to_string())),
+        value: Some(Bytes::from_static(b"value")),
+        headers: Default::default(),
+    };
+    record.headers.insert(
+        StrBytes::from_static_str("customer-id"),
+        Some(Bytes::from_static(b"1234")),
+    );
+    record.headers.insert(
+        StrBytes::from_static_str("trace-id"),
+        Some(Bytes::from_static(b"abcd")),
+    );
+    record
+}
+
+/// Send a record through the producer side of the interceptor and return the record
+/// received by the broker
+async fn produce_record(
+    context: &mut Context,
+    interceptor: &InletInterceptorImpl,
+    record: Record,
+) -> Record {
+    let batch = RecordBatch::new(
+        vec![record],
+        RecordEncodeOptions {
+            version: 2,
+            compression: Compression::None,
+        },
+    );
+    let encrypted_request = interceptor
+        .intercept_request(context, create_kafka_produce_request_with_batch(&batch))
+        .await
+        .unwrap();
+    let request = parse_produce_request(&encrypted_request);
+    let records = request.topic_data[0].partition_data[0]
+        .records
+        .clone()
+        .unwrap();
+    decode_record_batches(&records).unwrap()[0].records[0].clone()
+}
+
+/// Send a record through the consumer side of the interceptor and return the record
+/// received by the consumer
+async fn fetch_record(
+    context: &mut Context,
+    interceptor: &InletInterceptorImpl,
+    record: Record,
+) -> Record {
+    let batch = RecordBatch::new(
+        vec![record],
+        RecordEncodeOptions {
+            version: 2,
+            compression: Compression::None,
+        },
+    );
+    interceptor.add_request(1, ApiKey::Fetch, TEST_KAFKA_API_VERSION);
+    interceptor.uuid_to_name.lock().unwrap().insert(
+        "00000000-0000-0000-0000-000000000000".to_string(),
+        "topic-name".to_string(),
+    );
+    let decrypted_response = interceptor
+        .intercept_response(
+            context,
+            create_kafka_fetch_response_with_records(
+                encode_record_batches(std::slice::from_ref(&batch)).unwrap(),
+            ),
+        )
+        .await
+        .unwrap();
+    let response = parse_fetch_response(&decrypted_response);
+    let records = response.responses[0].partitions[0].records.clone().unwrap();
+    decode_record_batches(&records).unwrap()[0].records[0].clone()
+}
+
+fn make_interceptor(record_encryption: KafkaRecordEncryption) -> InletInterceptorImpl {
+    InletInterceptorImpl::new(
+        Arc::new(MockKafkaKeyExchangeController {}),
+        Default::default(),
+        KafkaInletController::stub(),
+        true,
+        vec![],
+    )
+    .with_record_encryption(record_encryption)
+    .with_deterministic_key_secret(Some(DeterministicKeySecret::new(b"secret")))
+}
+
+#[ockam::test]
+pub async fn encrypt_and_decrypt_randomized_key_and_all_headers(
+    context: &mut Context,
+) -> ockam::Result<()> {
+    let interceptor = make_interceptor(
+        KafkaRecordEncryption::default()
+            .with_key_encryption("topic-name", KeyEncryption::Randomized)
+            .with_headers_encryption("topic-name", HeadersEncryption::All),
+    );
+
+    let original = create_record_with_key_and_headers("key");
+    let encrypted = produce_record(context, &interceptor, original.clone()).await;
+    assert_ne!(encrypted.key, original.key);
+    for name in ["customer-id", "trace-id"] {
+        let name = StrBytes::from_static_str(name);
+        assert_ne!(encrypted.headers.get(&name), original.headers.get(&name));
+    }
+
+    let decrypted = fetch_record(context, &interceptor, encrypted).await;
+    assert_eq!(decrypted, original);
+    Ok(())
+}
+
+#[ockam::test]
+pub async fn encrypt_and_decrypt_deterministic_key_and_named_headers(
+    context: &mut Context,
+) -> ockam::Result<()> {
+    let interceptor = make_interceptor(
+        KafkaRecordEncryption::default()
+            .with_key_encryption("topic-name", KeyEncryption::Deterministic)
+            .with_headers_encryption(
+                "topic-name",
+                HeadersEncryption::Named(vec!["customer-id".to_string()]),
+            ),
+    );
+
+    let original = create_record_with_key_and_headers("key");
+    let encrypted = produce_record(context, &interceptor, original.clone()).await;
+
+    // equal keys are encrypted to equal values
+    let other = produce_record(context, &interceptor, original.clone()).await;
+    assert_eq!(encrypted.key, other.key);
+    assert_ne!(encrypted.key, original.key);
+    let different = produce_record(
+        context,
+        &interceptor,
+        create_record_with_key_and_headers("other key"),
+    )
+    .await;
+    assert_ne!(encrypted.key, different.key);
+
+    // only the named header is encrypted, and the original key is sent in a header
+    let customer_id = StrBytes::from_static_str("customer-id");
+    let trace_id = StrBytes::from_static_str("trace-id");
+    assert_ne!(
+        encrypted.headers.get(&customer_id),
+        original.headers.get(&customer_id)
+    );
+    assert_eq!(
+        encrypted.headers.get(&trace_id),
+        original.headers.get(&trace_id)
+    );
+    assert!(encrypted
+        .headers
+        .contains_key(&StrBytes::from_static_str(ENCRYPTED_KEY_HEADER)));
+
+    let decrypted = fetch_record(context, &interceptor, encrypted).await;
+    assert_eq!(decrypted, original);
+    Ok(())
+}
+
+#[ockam::test]
+pub async fn records_with_the_encrypted_key_header_are_rejected(
+    context: &mut Context,
+) -> ockam::Result<()> {
+    let interceptor = make_interceptor(
+        KafkaRecordEncryption::default()
+            .with_key_encryption("topic-name", KeyEncryption::Deterministic),
+    );
+
+    let mut record = create_record_with_key_and_headers("key");
+    record.headers.insert(
+        StrBytes::from_static_str(ENCRYPTED_KEY_HEADER),
+        Some(Bytes::from_static(b"user value")),
+    );
+    let batch = RecordBatch::new(
+        vec![record],
+        RecordEncodeOptions {
+            version: 2,
+            compression: Compression::None,
+        },
+    );
+    let result = interceptor
+        .intercept_request(context, create_kafka_produce_request_with_batch(&batch))
+        .await;
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[ockam::test]
+pub async fn keys_and_headers_of_other_topics_are_not_encrypted(
+    context: &mut Context,
+) -> ockam::Result<()> {
+    let interceptor = make_interceptor(
+        KafkaRecordEncryption::default()
+            .with_key_encryption("other-topic", KeyEncryption::Randomized)
+            .with_headers_encryption("other-topic", HeadersEncryption::All),
+    );
+
+    let original = create_record_with_key_and_headers("key");
+    let encrypted = produce_record(context, &interceptor, original.clone()).await;
+    assert_eq!(encrypted.key, original.key);
+    assert_eq!(encrypted.headers, original.headers);
+    assert_ne!(encrypted.value, original.value);
+    Ok(())
+}
@@ -2,22 +2,22 @@ use bytes::BytesMut;
 use kafka_protocol::messages::ApiKey;
 use kafka_protocol::protocol::buf::NotEnoughBytesError;
 use minicbor::{CborLen, Decode, Encode};
+use ockam::identity::Identifier;
 use ockam_core::compat::{
     collections::HashMap,
     fmt::Debug,
     sync::{Arc, Mutex},
 };
-use ockam_core::{async_trait, Address};
+use ockam_core::{async_trait, Address, LocalInfo, SecureChannelLocalInfo};
 use ockam_node::Context;
 
 pub(crate) mod outlet;
 mod tests;
 
 pub(crate) mod inlet;
-mod length_delimited;
 pub(super) mod utils;
 
-use crate::kafka::protocol_aware::length_delimited::{length_encode, KafkaMessageDecoder};
+use crate::protocol_interceptor::{Framing, LengthPrefixedFraming};
 use ockam_core::errcode::{Kind, Origin};
 use ockam_transport_tcp::{Direction, PortalInterceptor};
 
@@ -40,6 +40,17 @@ pub(crate) trait KafkaMessageRequestInterceptor: Send + Sync + 'static {
         context: &mut Context,
         original: BytesMut,
     ) -> Result<BytesMut, InterceptError>;
+
+    /// Same as [`KafkaMessageRequestInterceptor::intercept_request`], with the identifier
+    /// of the secure channel the request was received from, if any
+    async fn intercept_request_from(
+        &self,
+        context: &mut Context,
+        _identifier: Option<&Identifier>,
+        original: BytesMut,
+    ) -> Result<BytesMut, InterceptError> {
+        self.intercept_request(context, original).await
+    }
 }
 
 #[async_trait]
@@ -58,10 +69,9 @@ pub(crate) trait KafkaMessageInterceptor:
 }
 
 pub struct KafkaMessageInterceptorWrapper {
-    decoder_from_inlet: Arc<Mutex<KafkaMessageDecoder>>,
-    decoder_from_outlet: Arc<Mutex<KafkaMessageDecoder>>,
+    decoder_from_inlet: Arc<Mutex<LengthPrefixedFraming>>,
+    decoder_from_outlet: Arc<Mutex<LengthPrefixedFraming>>,
     message_interceptor: Arc<dyn KafkaMessageInterceptor>,
-    max_message_size: u32,
 }
 
 /// Converts a generic interceptor trait into kafka specific interceptor
@@ -71,10 +81,13 @@ impl KafkaMessageInterceptorWrapper {
         max_message_size: u32,
     ) -> Self {
         Self {
-            decoder_from_inlet: Arc::new(Mutex::new(KafkaMessageDecoder::new())),
-            decoder_from_outlet: Arc::new(Mutex::new(KafkaMessageDecoder::new())),
+            decoder_from_inlet: Arc::new(Mutex::new(LengthPrefixedFraming::new(
+                max_message_size as usize,
+            ))),
+            decoder_from_outlet: Arc::new(Mutex::new(LengthPrefixedFraming::new(
+                max_message_size as usize,
+            ))),
             message_interceptor,
-            max_message_size,
         }
     }
 }
@@ -87,23 +100,39 @@ impl PortalInterceptor for KafkaMessageInterceptorWrapper {
         direction: Direction,
         buffer: &[u8],
     ) -> ockam_core::Result<Option<Vec<u8>>> {
-        let mut encoded_buffer: Option<BytesMut> = None;
+        self.intercept_with_local_info(context, direction, &[], buffer)
+            .await
+    }
 
-        let messages = {
-            let decoder = match direction {
-                Direction::FromOutletToInlet => &self.decoder_from_outlet,
-                Direction::FromInletToOutlet => &self.decoder_from_inlet,
-            };
+    async fn intercept_with_local_info(
+        &self,
+        context: &mut Context,
+        direction: Direction,
+        local_info: &[LocalInfo],
+        buffer: &[u8],
+    ) -> ockam_core::Result<Option<Vec<u8>>> {
+        let identifier: Option<Identifier> =
+            SecureChannelLocalInfo::find_info_from_list(local_info)
+                .ok()
+                .map(|info| info.their_identifier().into());
+
+        let mut encoded_buffer = vec![];
 
-            let mut guard = decoder.lock().unwrap();
-            guard.extract_complete_messages(BytesMut::from(buffer), self.max_message_size)?
+        let decoder = match direction {
+            Direction::FromOutletToInlet => &self.decoder_from_outlet,
+            Direction::FromInletToOutlet => &self.decoder_from_inlet,
         };
+        let messages = decoder.lock().unwrap().decode(buffer)?;
 
         for complete_kafka_message in messages {
             let transformed_message = match direction {
                 Direction::FromInletToOutlet => {
                     self.message_interceptor
-                        .intercept_request(context, complete_kafka_message)
+                        .intercept_request_from(
+                            context,
+                            identifier.as_ref(),
+                            complete_kafka_message,
+                        )
                         .await
                 }
                 Direction::FromOutletToInlet => {
@@ -131,15 +160,17 @@ impl PortalInterceptor for KafkaMessageInterceptorWrapper {
                 }
             })?;
 
-            // avoid copying the first message
-            if let Some(encoded_buffer) = encoded_buffer.as_mut() {
-                encoded_buffer.extend_from_slice(length_encode(transformed_message)?.as_ref());
-            } else {
-                encoded_buffer = Some(length_encode(transformed_message)?)
-            }
+            decoder
+                .lock()
+                .unwrap()
+                .encode(transformed_message, &mut encoded_buffer)?;
         }
 
-        Ok(encoded_buffer.map(|buffer| buffer.freeze().to_vec()))
+        if encoded_buffer.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(encoded_buffer))
+        }
     }
 }
 
@@ -153,6 +184,22 @@ pub(crate) struct KafkaEncryptedContent {
     #[n(1)] pub content: Vec<u8>
 }
 
+/// First version of the Produce responses which contain the endpoints of the new
+/// partition leaders
+pub(crate) const PRODUCE_NODE_ENDPOINTS_VERSION: i16 = 10;
+
+/// First version of the Fetch responses which contain the endpoints of the new
+/// partition leaders
+pub(crate) const FETCH_NODE_ENDPOINTS_VERSION: i16 = 16;
+
+/// API keys of the share groups (KIP-932): ShareGroupHeartbeat, ShareGroupDescribe,
+/// ShareFetch, ShareAcknowledge and the share group state APIs.
+/// They can't be decoded with the current version of `kafka-protocol`, so the records
+/// returned by ShareFetch couldn't be decrypted. They are removed from the ApiVersions
+/// responses, which makes the clients fall back to consumer groups, and their requests
+/// are rejected
+pub(crate) const SHARE_GROUP_API_KEYS: [i16; 9] = [76, 77, 78, 79, 83, 84, 85, 86, 87];
+
 /// By default, kafka supports up to 1MB messages. 16MB is the maximum suggested
 pub(crate) const MAX_KAFKA_MESSAGE_SIZE: u32 = 16 * 1024 * 1024;
 
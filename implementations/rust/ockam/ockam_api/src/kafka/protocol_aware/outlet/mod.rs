@@ -1,8 +1,10 @@
 use crate::kafka::protocol_aware::{
     CorrelationId, KafkaMessageInterceptor, KafkaMessageInterceptorWrapper, RequestInfo,
-    MAX_KAFKA_MESSAGE_SIZE,
+    TopicUuidMap, MAX_KAFKA_MESSAGE_SIZE,
 };
-use crate::kafka::KafkaOutletController;
+use crate::kafka::{KafkaOutletController, TopicAuthorization};
+use kafka_protocol::messages::fetch_request::FetchTopic;
+use kafka_protocol::messages::produce_request::TopicProduceData;
 use ockam_core::compat::collections::HashMap;
 use ockam_core::flow_control::FlowControlId;
 use ockam_transport_tcp::{PortalInterceptor, PortalInterceptorFactory};
@@ -11,19 +13,27 @@ use std::sync::{Arc, Mutex};
 mod request;
 mod response;
 
+#[cfg(test)]
+mod tests;
+
 pub(crate) struct KafkaOutletInterceptorFactory {
     outlet_controller: KafkaOutletController,
     spawner_flow_control_id: FlowControlId,
+    topic_authorization: Option<TopicAuthorization>,
+    uuid_to_name: TopicUuidMap,
 }
 
 impl KafkaOutletInterceptorFactory {
     pub(crate) fn new(
         outlet_controller: KafkaOutletController,
         spawner_flow_control_id: FlowControlId,
+        topic_authorization: Option<TopicAuthorization>,
     ) -> Self {
         Self {
             outlet_controller,
             spawner_flow_control_id,
+            topic_authorization,
+            uuid_to_name: Default::default(),
         }
     }
 }
@@ -31,22 +41,42 @@ impl KafkaOutletInterceptorFactory {
 impl PortalInterceptorFactory for KafkaOutletInterceptorFactory {
     fn create(&self) -> Arc<dyn PortalInterceptor> {
         Arc::new(KafkaMessageInterceptorWrapper::new(
-            Arc::new(OutletInterceptorImpl::new(
-                self.outlet_controller.clone(),
-                self.spawner_flow_control_id.clone(),
-            )),
+            Arc::new(
+                OutletInterceptorImpl::new(
+                    self.outlet_controller.clone(),
+                    self.spawner_flow_control_id.clone(),
+                )
+                .with_topic_authorization(
+                    self.topic_authorization.clone(),
+                    self.uuid_to_name.clone(),
+                ),
+            ),
             MAX_KAFKA_MESSAGE_SIZE,
         ))
     }
 }
 
-/// Intercepts responses of type `Metadata` to extract the list of brokers
-/// then creates an outlet for each of them through [`KafkaOutletController`]
+/// Topics removed from a request because the inlet is not authorized to use them.
+/// They are added back to the response with a `TOPIC_AUTHORIZATION_FAILED` error
+#[derive(Clone, Debug)]
+pub(crate) enum DeniedTopics {
+    Produce(Vec<TopicProduceData>),
+    Fetch(Vec<FetchTopic>),
+}
+
+/// Intercepts the responses containing broker addresses (`Metadata`, `FindCoordinator`,
+/// `DescribeCluster`, and the node endpoints of `Produce` and `Fetch`) to extract the list
+/// of brokers then creates an outlet for each of them through [`KafkaOutletController`].
+/// When the topics are protected by policies, the Produce and Fetch requests
+/// are also checked against the identity of the inlet
 #[derive(Clone)]
 pub(crate) struct OutletInterceptorImpl {
     request_map: Arc<Mutex<HashMap<CorrelationId, RequestInfo>>>,
+    denied_topics: Arc<Mutex<HashMap<CorrelationId, DeniedTopics>>>,
     outlet_controller: KafkaOutletController,
     flow_control_id: FlowControlId,
+    topic_authorization: Option<TopicAuthorization>,
+    uuid_to_name: TopicUuidMap,
 }
 
 impl OutletInterceptorImpl {
@@ -56,10 +86,25 @@ impl OutletInterceptorImpl {
     ) -> Self {
         Self {
             request_map: Arc::new(Mutex::new(HashMap::new())),
+            denied_topics: Arc::new(Mutex::new(HashMap::new())),
             outlet_controller,
             flow_control_id,
+            topic_authorization: None,
+            uuid_to_name: Default::default(),
         }
     }
+
+    /// Check the policies of the topics used by Produce and Fetch requests.
+    /// The map of topic ids to topic names is shared by all the connections of the outlet
+    pub(crate) fn with_topic_authorization(
+        mut self,
+        topic_authorization: Option<TopicAuthorization>,
+        uuid_to_name: TopicUuidMap,
+    ) -> Self {
+        self.topic_authorization = topic_authorization.filter(|a| a.is_enabled());
+        self.uuid_to_name = uuid_to_name;
+        self
+    }
 }
 
 impl KafkaMessageInterceptor for OutletInterceptorImpl {}
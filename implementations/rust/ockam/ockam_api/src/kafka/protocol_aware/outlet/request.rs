@@ -1,17 +1,32 @@
-use crate::kafka::protocol_aware::outlet::OutletInterceptorImpl;
-use crate::kafka::protocol_aware::{InterceptError, KafkaMessageRequestInterceptor, RequestInfo};
-use bytes::BytesMut;
-use kafka_protocol::messages::{ApiKey, RequestHeader};
+use crate::kafka::protocol_aware::outlet::{DeniedTopics, OutletInterceptorImpl};
+use crate::kafka::protocol_aware::utils::{decode_body, encode_request};
+use crate::kafka::protocol_aware::{
+    InterceptError, KafkaMessageRequestInterceptor, RequestInfo, FETCH_NODE_ENDPOINTS_VERSION,
+    PRODUCE_NODE_ENDPOINTS_VERSION,
+};
+use crate::kafka::{TopicAuthorization, TopicOperation};
+use bytes::{Bytes, BytesMut};
+use kafka_protocol::messages::{ApiKey, FetchRequest, ProduceRequest, RequestHeader};
 use kafka_protocol::protocol::buf::ByteBuf;
 use kafka_protocol::protocol::Decodable;
+use ockam::identity::Identifier;
 use ockam_core::async_trait;
 use ockam_node::Context;
 
 #[async_trait]
 impl KafkaMessageRequestInterceptor for OutletInterceptorImpl {
     async fn intercept_request(
+        &self,
+        context: &mut Context,
+        original: BytesMut,
+    ) -> Result<BytesMut, InterceptError> {
+        self.intercept_request_from(context, None, original).await
+    }
+
+    async fn intercept_request_from(
         &self,
         _context: &mut Context,
+        identifier: Option<&Identifier>,
         mut original: BytesMut,
     ) -> Result<BytesMut, InterceptError> {
         // Inside the request we can find the api key (kind of request), the protocol version of the request,
@@ -48,18 +63,188 @@ impl KafkaMessageRequestInterceptor for OutletInterceptorImpl {
             api_key
         );
 
-        // we only need to keep track of the metadata request/response
-        // to dynamically create an outlet for each broker
-        if api_key == ApiKey::Metadata {
-            self.request_map.lock().unwrap().insert(
-                header.correlation_id,
-                RequestInfo {
-                    request_api_key: ApiKey::Metadata,
-                    request_api_version: header.request_api_version,
-                },
-            );
+        match api_key {
+            // we need to keep track of the metadata request/response
+            // to dynamically create an outlet for each broker
+            // the same applies to the other responses containing broker addresses
+            ApiKey::Metadata | ApiKey::FindCoordinator | ApiKey::DescribeCluster => {
+                self.map_request(&header, api_key);
+            }
+            ApiKey::DescribeTopicPartitions => {
+                if self.topic_authorization.is_some() {
+                    self.map_request(&header, api_key);
+                }
+            }
+            ApiKey::Produce => {
+                if let Some(topic_authorization) = &self.topic_authorization {
+                    return self
+                        .authorize_produce_request(
+                            topic_authorization,
+                            identifier,
+                            &mut buffer,
+                            &header,
+                            original,
+                        )
+                        .await;
+                } else if header.request_api_version >= PRODUCE_NODE_ENDPOINTS_VERSION {
+                    let request: ProduceRequest =
+                        decode_body(&mut buffer, header.request_api_version)?;
+                    // without acknowledgment, the broker doesn't send any response
+                    if request.acks != 0 {
+                        self.map_request(&header, api_key);
+                    }
+                }
+            }
+            ApiKey::Fetch => {
+                if let Some(topic_authorization) = &self.topic_authorization {
+                    return self
+                        .authorize_fetch_request(
+                            topic_authorization,
+                            identifier,
+                            &mut buffer,
+                            &header,
+                            original,
+                        )
+                        .await;
+                } else if header.request_api_version >= FETCH_NODE_ENDPOINTS_VERSION {
+                    self.map_request(&header, api_key);
+                }
+            }
+            _ => {}
         }
 
         Ok(original)
     }
 }
+
+impl OutletInterceptorImpl {
+    /// Remove the topics which can't be produced to by the inlet from the request
+    async fn authorize_produce_request(
+        &self,
+        topic_authorization: &TopicAuthorization,
+        identifier: Option<&Identifier>,
+        buffer: &mut Bytes,
+        header: &RequestHeader,
+        original: BytesMut,
+    ) -> Result<BytesMut, InterceptError> {
+        let mut request: ProduceRequest = decode_body(buffer, header.request_api_version)?;
+
+        let mut allowed = vec![];
+        let mut denied = vec![];
+        for topic in request.topic_data.drain(..) {
+            if topic_authorization
+                .is_authorized(identifier, topic.name.as_str(), TopicOperation::Produce)
+                .await?
+            {
+                allowed.push(topic);
+            } else {
+                warn!(
+                    "the inlet {:?} is not authorized to produce to the topic {}",
+                    identifier,
+                    topic.name.as_str()
+                );
+                denied.push(topic);
+            }
+        }
+
+        // without acknowledgment, the broker doesn't send any response
+        if denied.is_empty() {
+            if request.acks != 0 && header.request_api_version >= PRODUCE_NODE_ENDPOINTS_VERSION {
+                self.map_request(header, ApiKey::Produce);
+            }
+            return Ok(original);
+        }
+
+        if request.acks != 0 {
+            self.add_denied_topics(header, ApiKey::Produce, DeniedTopics::Produce(denied));
+        }
+
+        request.topic_data = allowed;
+        encode_request(
+            header,
+            &request,
+            header.request_api_version,
+            ApiKey::Produce,
+        )
+    }
+
+    /// Remove the topics which can't be fetched from by the inlet from the request
+    async fn authorize_fetch_request(
+        &self,
+        topic_authorization: &TopicAuthorization,
+        identifier: Option<&Identifier>,
+        buffer: &mut Bytes,
+        header: &RequestHeader,
+        original: BytesMut,
+    ) -> Result<BytesMut, InterceptError> {
+        let mut request: FetchRequest = decode_body(buffer, header.request_api_version)?;
+
+        let mut allowed = vec![];
+        let mut denied = vec![];
+        for topic in request.topics.drain(..) {
+            let topic_name = if header.request_api_version <= 12 {
+                Some(topic.topic.to_string())
+            } else {
+                // recent versions only use the topic id, mapped to the topic name
+                // with the previous metadata responses
+                self.uuid_to_name
+                    .lock()
+                    .unwrap()
+                    .get(&topic.topic_id.to_string())
+                    .cloned()
+            };
+
+            let authorized = match topic_name.as_ref() {
+                Some(topic_name) => {
+                    topic_authorization
+                        .is_authorized(identifier, topic_name, TopicOperation::Fetch)
+                        .await?
+                }
+                None => {
+                    warn!("missing map from uuid {} to name", topic.topic_id);
+                    false
+                }
+            };
+
+            if authorized {
+                allowed.push(topic);
+            } else {
+                warn!(
+                    "the inlet {:?} is not authorized to fetch from the topic {:?}",
+                    identifier, topic_name
+                );
+                denied.push(topic);
+            }
+        }
+
+        if denied.is_empty() {
+            if header.request_api_version >= FETCH_NODE_ENDPOINTS_VERSION {
+                self.map_request(header, ApiKey::Fetch);
+            }
+            return Ok(original);
+        }
+
+        self.add_denied_topics(header, ApiKey::Fetch, DeniedTopics::Fetch(denied));
+        request.topics = allowed;
+        encode_request(header, &request, header.request_api_version, ApiKey::Fetch)
+    }
+
+    /// Keep track of a request to intercept its response
+    fn map_request(&self, header: &RequestHeader, api_key: ApiKey) {
+        self.request_map.lock().unwrap().insert(
+            header.correlation_id,
+            RequestInfo {
+                request_api_key: api_key,
+                request_api_version: header.request_api_version,
+            },
+        );
+    }
+
+    fn add_denied_topics(&self, header: &RequestHeader, api_key: ApiKey, denied: DeniedTopics) {
+        self.map_request(header, api_key);
+        self.denied_topics
+            .lock()
+            .unwrap()
+            .insert(header.correlation_id, denied);
+    }
+}
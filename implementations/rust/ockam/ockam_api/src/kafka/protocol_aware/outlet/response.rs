@@ -1,10 +1,19 @@
-use crate::kafka::protocol_aware::outlet::OutletInterceptorImpl;
-use crate::kafka::protocol_aware::utils::decode_body;
-use crate::kafka::protocol_aware::{InterceptError, KafkaMessageResponseInterceptor};
-use bytes::BytesMut;
-use kafka_protocol::messages::{ApiKey, MetadataResponse, ResponseHeader};
+use crate::kafka::protocol_aware::outlet::{DeniedTopics, OutletInterceptorImpl};
+use crate::kafka::protocol_aware::utils::{decode_body, encode_response};
+use crate::kafka::protocol_aware::{
+    InterceptError, KafkaMessageResponseInterceptor, RequestInfo, FETCH_NODE_ENDPOINTS_VERSION,
+    PRODUCE_NODE_ENDPOINTS_VERSION,
+};
+use bytes::{Bytes, BytesMut};
+use kafka_protocol::error::ResponseError;
+use kafka_protocol::messages::fetch_response::{FetchableTopicResponse, PartitionData};
+use kafka_protocol::messages::produce_response::{PartitionProduceResponse, TopicProduceResponse};
+use kafka_protocol::messages::{
+    ApiKey, BrokerId, DescribeClusterResponse, DescribeTopicPartitionsResponse, FetchResponse,
+    FindCoordinatorResponse, MetadataResponse, ProduceResponse, ResponseHeader,
+};
 use kafka_protocol::protocol::buf::ByteBuf;
-use kafka_protocol::protocol::Decodable;
+use kafka_protocol::protocol::{Decodable, StrBytes};
 use ockam_core::async_trait;
 use ockam_node::Context;
 
@@ -20,12 +29,7 @@ impl KafkaMessageResponseInterceptor for OutletInterceptorImpl {
         // we can/need to decode only mapped requests
         let correlation_id = buffer.peek_bytes(0..4).try_get_i32()?;
 
-        let result = self
-            .request_map
-            .lock()
-            .unwrap()
-            .get(&correlation_id)
-            .cloned();
+        let result = self.request_map.lock().unwrap().remove(&correlation_id);
 
         if let Some(request_info) = result {
             let result = ResponseHeader::decode(
@@ -35,7 +39,7 @@ impl KafkaMessageResponseInterceptor for OutletInterceptorImpl {
                     .response_header_version(request_info.request_api_version),
             );
 
-            let _header = match result {
+            let header = match result {
                 Ok(header) => header,
                 Err(_) => {
                     // the error doesn't contain any useful information
@@ -52,24 +56,38 @@ impl KafkaMessageResponseInterceptor for OutletInterceptorImpl {
                 request_info.request_api_key
             );
 
-            // the responses of metadata request contain the list of brokers addresses,
-            // we override them with the outlets addresses
-            if request_info.request_api_key == ApiKey::Metadata {
-                let response: MetadataResponse =
-                    decode_body(&mut buffer, request_info.request_api_version)?;
-
-                for broker in response.brokers {
-                    let address = format!("{}:{}", broker.host.as_str(), broker.port);
-                    let outlet_address = self
-                        .outlet_controller
-                        .assert_outlet_for_broker(context, broker.node_id.0, address)
+            match request_info.request_api_key {
+                // the responses of metadata request contain the list of brokers addresses,
+                // we override them with the outlets addresses
+                ApiKey::Metadata => {
+                    self.handle_metadata_response(context, &mut buffer, &request_info)
                         .await?;
-
-                    // allow the interceptor to reach the outlet
-                    context
-                        .flow_controls()
-                        .add_consumer(&outlet_address, &self.flow_control_id);
                 }
+                ApiKey::FindCoordinator => {
+                    self.handle_find_coordinator_response(context, &mut buffer, &request_info)
+                        .await?;
+                }
+                ApiKey::DescribeCluster => {
+                    self.handle_describe_cluster_response(context, &mut buffer, &request_info)
+                        .await?;
+                }
+                ApiKey::DescribeTopicPartitions => {
+                    self.handle_describe_topic_partitions_response(&mut buffer, &request_info)?;
+                }
+                ApiKey::Produce | ApiKey::Fetch => {
+                    self.handle_node_endpoints(context, buffer.clone(), &request_info)
+                        .await?;
+                    let denied = self.denied_topics.lock().unwrap().remove(&correlation_id);
+                    if let Some(denied) = denied {
+                        return self.add_denied_topics_errors(
+                            &mut buffer,
+                            &request_info,
+                            &header,
+                            denied,
+                        );
+                    }
+                }
+                _ => {}
             }
         } else {
             debug!(
@@ -81,3 +99,212 @@ impl KafkaMessageResponseInterceptor for OutletInterceptorImpl {
         Ok(original)
     }
 }
+
+impl OutletInterceptorImpl {
+    async fn handle_metadata_response(
+        &self,
+        context: &mut Context,
+        buffer: &mut Bytes,
+        request_info: &RequestInfo,
+    ) -> Result<(), InterceptError> {
+        let response: MetadataResponse = decode_body(buffer, request_info.request_api_version)?;
+
+        // fetch operations with recent versions of the protocol only use the topic id,
+        // the topic names are needed to check the topic policies
+        if self.topic_authorization.is_some() && request_info.request_api_version >= 10 {
+            let mut uuid_to_name = self.uuid_to_name.lock().unwrap();
+            for topic in &response.topics {
+                if let Some(name) = &topic.name {
+                    uuid_to_name.insert(topic.topic_id.to_string(), name.to_string());
+                }
+            }
+        }
+
+        for broker in response.brokers {
+            self.assert_outlet_for_broker(context, broker.node_id, &broker.host, broker.port)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn handle_find_coordinator_response(
+        &self,
+        context: &mut Context,
+        buffer: &mut Bytes,
+        request_info: &RequestInfo,
+    ) -> Result<(), InterceptError> {
+        let response: FindCoordinatorResponse =
+            decode_body(buffer, request_info.request_api_version)?;
+
+        // the format changed to array since version 4
+        if request_info.request_api_version >= 4 {
+            for coordinator in response.coordinators {
+                if coordinator.error_code == 0 {
+                    self.assert_outlet_for_broker(
+                        context,
+                        coordinator.node_id,
+                        &coordinator.host,
+                        coordinator.port,
+                    )
+                    .await?;
+                }
+            }
+        } else if response.error_code == 0 {
+            self.assert_outlet_for_broker(context, response.node_id, &response.host, response.port)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn handle_describe_cluster_response(
+        &self,
+        context: &mut Context,
+        buffer: &mut Bytes,
+        request_info: &RequestInfo,
+    ) -> Result<(), InterceptError> {
+        let response: DescribeClusterResponse =
+            decode_body(buffer, request_info.request_api_version)?;
+        for broker in response.brokers {
+            self.assert_outlet_for_broker(context, broker.broker_id, &broker.host, broker.port)
+                .await?;
+        }
+        Ok(())
+    }
+
+    fn handle_describe_topic_partitions_response(
+        &self,
+        buffer: &mut Bytes,
+        request_info: &RequestInfo,
+    ) -> Result<(), InterceptError> {
+        let response: DescribeTopicPartitionsResponse =
+            decode_body(buffer, request_info.request_api_version)?;
+        let mut uuid_to_name = self.uuid_to_name.lock().unwrap();
+        for topic in &response.topics {
+            if let Some(name) = &topic.name {
+                uuid_to_name.insert(topic.topic_id.to_string(), name.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// The produce and fetch responses may contain the endpoints of new partition leaders
+    async fn handle_node_endpoints(
+        &self,
+        context: &mut Context,
+        mut buffer: Bytes,
+        request_info: &RequestInfo,
+    ) -> Result<(), InterceptError> {
+        let api_version = request_info.request_api_version;
+        let endpoints: Vec<(BrokerId, StrBytes, i32)> = match request_info.request_api_key {
+            ApiKey::Produce if api_version >= PRODUCE_NODE_ENDPOINTS_VERSION => {
+                let response: ProduceResponse = decode_body(&mut buffer, api_version)?;
+                response
+                    .node_endpoints
+                    .into_iter()
+                    .map(|e| (e.node_id, e.host, e.port))
+                    .collect()
+            }
+            ApiKey::Fetch if api_version >= FETCH_NODE_ENDPOINTS_VERSION => {
+                let response: FetchResponse = decode_body(&mut buffer, api_version)?;
+                response
+                    .node_endpoints
+                    .into_iter()
+                    .map(|e| (e.node_id, e.host, e.port))
+                    .collect()
+            }
+            _ => vec![],
+        };
+
+        for (broker_id, host, port) in endpoints {
+            self.assert_outlet_for_broker(context, broker_id, &host, port)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Create an outlet for a broker, if it doesn't exist yet
+    async fn assert_outlet_for_broker(
+        &self,
+        context: &mut Context,
+        broker_id: BrokerId,
+        host: &StrBytes,
+        port: i32,
+    ) -> Result<(), InterceptError> {
+        let address = format!("{}:{}", host.as_str(), port);
+        let outlet_address = self
+            .outlet_controller
+            .assert_outlet_for_broker(context, broker_id.0, address)
+            .await?;
+
+        // allow the interceptor to reach the outlet
+        context
+            .flow_controls()
+            .add_consumer(&outlet_address, &self.flow_control_id);
+        Ok(())
+    }
+
+    /// Add the topics removed from a request to its response, with
+    /// a `TOPIC_AUTHORIZATION_FAILED` error for each of their partitions
+    fn add_denied_topics_errors(
+        &self,
+        buffer: &mut Bytes,
+        request_info: &RequestInfo,
+        header: &ResponseHeader,
+        denied: DeniedTopics,
+    ) -> Result<BytesMut, InterceptError> {
+        let error_code = ResponseError::TopicAuthorizationFailed.code();
+        let api_version = request_info.request_api_version;
+
+        match denied {
+            DeniedTopics::Produce(topics) => {
+                let mut response: ProduceResponse = decode_body(buffer, api_version)?;
+                for topic in topics {
+                    response.responses.push(
+                        TopicProduceResponse::default()
+                            .with_name(topic.name)
+                            .with_partition_responses(
+                                topic
+                                    .partition_data
+                                    .iter()
+                                    .map(|partition| {
+                                        PartitionProduceResponse::default()
+                                            .with_index(partition.index)
+                                            .with_error_code(error_code)
+                                            .with_base_offset(-1)
+                                            .with_log_append_time_ms(-1)
+                                            .with_log_start_offset(-1)
+                                    })
+                                    .collect(),
+                            ),
+                    );
+                }
+                encode_response(header, &response, api_version, ApiKey::Produce)
+            }
+            DeniedTopics::Fetch(topics) => {
+                let mut response: FetchResponse = decode_body(buffer, api_version)?;
+                for topic in topics {
+                    response.responses.push(
+                        FetchableTopicResponse::default()
+                            .with_topic(topic.topic)
+                            .with_topic_id(topic.topic_id)
+                            .with_partitions(
+                                topic
+                                    .partitions
+                                    .iter()
+                                    .map(|partition| {
+                                        PartitionData::default()
+                                            .with_partition_index(partition.partition)
+                                            .with_error_code(error_code)
+                                            .with_high_watermark(-1)
+                                            .with_last_stable_offset(-1)
+                                            .with_log_start_offset(-1)
+                                    })
+                                    .collect(),
+                            ),
+                    );
+                }
+                encode_response(header, &response, api_version, ApiKey::Fetch)
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use crate::kafka::protocol_aware::outlet::OutletInterceptorImpl;
+use crate::kafka::protocol_aware::utils::{decode_body, encode_request, encode_response};
+use crate::kafka::protocol_aware::{
+    KafkaMessageRequestInterceptor, KafkaMessageResponseInterceptor,
+};
+use crate::kafka::{
+    KafkaOutletController, KafkaTopicPolicies, TopicAuthorization, TopicEncryption,
+};
+use crate::test_utils::{start_manager_for_tests, TestNode};
+use bytes::BytesMut;
+use kafka_protocol::error::ResponseError;
+use kafka_protocol::messages::produce_request::{PartitionProduceData, TopicProduceData};
+use kafka_protocol::messages::produce_response::{PartitionProduceResponse, TopicProduceResponse};
+use kafka_protocol::messages::{
+    ApiKey, ProduceRequest, ProduceResponse, RequestHeader, ResponseHeader, TopicName,
+};
+use kafka_protocol::protocol::{Decodable, StrBytes};
+use ockam_abac::expr::{eq, str};
+use ockam_abac::{subject_identifier_attribute, PolicyExpression};
+use ockam_core::flow_control::FlowControls;
+use ockam_node::Context;
+
+const TEST_KAFKA_API_VERSION: i16 = 9;
+
+fn topic_name(name: &'static str) -> TopicName {
+    TopicName::from(StrBytes::from_static_str(name))
+}
+
+fn create_produce_request(topics: &[&'static str]) -> BytesMut {
+    let header = RequestHeader::default()
+        .with_request_api_key(ApiKey::Produce as i16)
+        .with_request_api_version(TEST_KAFKA_API_VERSION)
+        .with_correlation_id(1)
+        .with_client_id(Some(StrBytes::from_static_str("my-client-id")));
+
+    let topic_data = topics
+        .iter()
+        .map(|topic| {
+            TopicProduceData::default()
+                .with_name(topic_name(topic))
+                .with_partition_data(vec![PartitionProduceData::default().with_index(0)])
+        })
+        .collect();
+    let request = ProduceRequest::default()
+        .with_acks(1)
+        .with_topic_data(topic_data);
+
+    encode_request(&header, &request, TEST_KAFKA_API_VERSION, ApiKey::Produce).unwrap()
+}
+
+fn parse_produce_request(content: BytesMut) -> ProduceRequest {
+    let mut buffer = content.freeze();
+    RequestHeader::decode(
+        &mut buffer,
+        ApiKey::Produce.request_header_version(TEST_KAFKA_API_VERSION),
+    )
+    .unwrap();
+    decode_body(&mut buffer, TEST_KAFKA_API_VERSION).unwrap()
+}
+
+fn create_produce_response(topic: &'static str) -> BytesMut {
+    let header = ResponseHeader::default().with_correlation_id(1);
+    let response = ProduceResponse::default().with_responses(vec![TopicProduceResponse::default()
+        .with_name(topic_name(topic))
+        .with_partition_responses(vec![PartitionProduceResponse::default()
+            .with_index(0)
+            .with_base_offset(10)])]);
+    encode_response(&header, &response, TEST_KAFKA_API_VERSION, ApiKey::Produce).unwrap()
+}
+
+fn parse_produce_response(content: BytesMut) -> ProduceResponse {
+    let mut buffer = content.freeze();
+    ResponseHeader::decode(
+        &mut buffer,
+        ApiKey::Produce.response_header_version(TEST_KAFKA_API_VERSION),
+    )
+    .unwrap();
+    decode_body(&mut buffer, TEST_KAFKA_API_VERSION).unwrap()
+}
+
+#[ockam_macros::test]
+async fn denied_topics_are_removed_and_reported_as_unauthorized(
+    context: &mut Context,
+) -> ockam::Result<()> {
+    TestNode::clean().await?;
+    let handle = start_manager_for_tests(context, None, None).await?;
+    let node_manager = handle.node_manager.node_manager.clone();
+
+    let identities = handle.secure_channels.identities();
+    let allowed = identities.identities_creation().create_identity().await?;
+    let denied = identities.identities_creation().create_identity().await?;
+
+    let policies = KafkaTopicPolicies::default()
+        .with_encryption("orders", TopicEncryption::Passthrough)
+        .with_expression(
+            "payments-*",
+            PolicyExpression::FullExpression(eq([
+                subject_identifier_attribute(),
+                str(allowed.to_string()),
+            ])),
+        );
+    let topic_authorization =
+        TopicAuthorization::new(policies, identities.identities_attributes(), None);
+
+    let interceptor = OutletInterceptorImpl::new(
+        KafkaOutletController::new(node_manager, None, false),
+        FlowControls::generate_flow_control_id(),
+    )
+    .with_topic_authorization(Some(topic_authorization), Default::default());
+
+    // the allowed identity sends the request unchanged
+    let request = create_produce_request(&["orders", "payments-eu"]);
+    let result = interceptor
+        .intercept_request_from(context, Some(&allowed), request.clone())
+        .await
+        .unwrap();
+    assert_eq!(result, request);
+    let response = create_produce_response("orders");
+    let result = interceptor
+        .intercept_response(context, response.clone())
+        .await
+        .unwrap();
+    assert_eq!(result, response);
+
+    // the denied topic is removed from the request of another identity
+    let result = interceptor
+        .intercept_request_from(context, Some(&denied), request)
+        .await
+        .unwrap();
+    let forwarded = parse_produce_request(result);
+    let topics: Vec<_> = forwarded
+        .topic_data
+        .iter()
+        .map(|t| t.name.to_string())
+        .collect();
+    assert_eq!(topics, vec!["orders".to_string()]);
+
+    // and added back to the response with an authorization error
+    let result = interceptor
+        .intercept_response(context, create_produce_response("orders"))
+        .await
+        .unwrap();
+    let response = parse_produce_response(result);
+    assert_eq!(response.responses.len(), 2);
+    assert_eq!(response.responses[0].partition_responses[0].error_code, 0);
+    assert_eq!(response.responses[1].name, topic_name("payments-eu"));
+    let partition = &response.responses[1].partition_responses[0];
+    assert_eq!(partition.index, 0);
+    assert_eq!(
+        partition.error_code,
+        ResponseError::TopicAuthorizationFailed.code()
+    );
+    assert_eq!(partition.base_offset, -1);
+
+    // a request which is not received from a secure channel can't use protected topics
+    let result = interceptor
+        .intercept_request_from(context, None, create_produce_request(&["payments-eu"]))
+        .await
+        .unwrap();
+    assert!(parse_produce_request(result).topic_data.is_empty());
+
+    Ok(())
+}
@@ -7,28 +7,39 @@ mod test {
     use crate::kafka::protocol_aware::{
         KafkaMessageRequestInterceptor, KafkaMessageResponseInterceptor,
     };
+    use crate::kafka::protocol_aware::{
+        FETCH_NODE_ENDPOINTS_VERSION, PRODUCE_NODE_ENDPOINTS_VERSION, SHARE_GROUP_API_KEYS,
+    };
     use crate::kafka::{ConsumerPublishing, ConsumerResolution};
     use crate::port_range::PortRange;
-    use crate::test_utils::TestNode;
-    use kafka_protocol::messages::ApiKey;
+    use crate::test_utils::{NodeManagerHandle, TestNode};
+    use bytes::BytesMut;
+    use kafka_protocol::messages::api_versions_response::ApiVersion;
+    use kafka_protocol::messages::describe_cluster_response::DescribeClusterBroker;
+    use kafka_protocol::messages::describe_topic_partitions_response::DescribeTopicPartitionsResponseTopic;
+    use kafka_protocol::messages::fetch_request::FetchTopic;
+    use kafka_protocol::messages::find_coordinator_response::Coordinator;
+    use kafka_protocol::messages::metadata_response::MetadataResponseBroker;
     use kafka_protocol::messages::BrokerId;
+    use kafka_protocol::messages::{fetch_response, produce_response, ApiKey};
+    use kafka_protocol::messages::{
+        AddPartitionsToTxnRequest, DescribeClusterRequest, DescribeClusterResponse,
+        DescribeTopicPartitionsRequest, DescribeTopicPartitionsResponse, EndTxnRequest,
+        FetchRequest, FetchResponse, FindCoordinatorRequest, FindCoordinatorResponse,
+        InitProducerIdRequest, ProduceRequest, ProduceResponse, TopicName,
+    };
     use kafka_protocol::messages::{ApiVersionsRequest, MetadataRequest, MetadataResponse};
     use kafka_protocol::messages::{ApiVersionsResponse, RequestHeader, ResponseHeader};
-    use kafka_protocol::protocol::StrBytes;
+    use kafka_protocol::protocol::{Decodable, Encodable, Message, StrBytes};
     use ockam_abac::{Action, Env, Resource, ResourceType};
     use ockam_core::route;
     use ockam_multiaddr::MultiAddr;
     use ockam_node::Context;
+    use std::path::PathBuf;
     use std::sync::Arc;
+    use uuid::Uuid;
 
-    #[allow(non_snake_case)]
-    #[ockam_macros::test(timeout = 5_000)]
-    async fn interceptor__basic_messages_with_several_api_versions__parsed_correctly(
-        context: &mut Context,
-    ) -> ockam::Result<()> {
-        TestNode::clean().await?;
-        let handle = crate::test_utils::start_manager_for_tests(context, None, None).await?;
-
+    fn create_inlet_interceptor(handle: &NodeManagerHandle) -> InletInterceptorImpl {
         let inlet_map = KafkaInletController::new(
             (*handle.node_manager).clone(),
             MultiAddr::default(),
@@ -67,13 +78,23 @@ mod test {
             producer_policy_access_control,
         );
 
-        let interceptor = InletInterceptorImpl::new(
+        InletInterceptorImpl::new(
             Arc::new(secure_channel_controller),
             Default::default(),
             inlet_map,
             true,
             vec![],
-        );
+        )
+    }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 5_000)]
+    async fn interceptor__basic_messages_with_several_api_versions__parsed_correctly(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        TestNode::clean().await?;
+        let handle = crate::test_utils::start_manager_for_tests(context, None, None).await?;
+        let interceptor = create_inlet_interceptor(&handle);
 
         let mut correlation_id = 0;
 
@@ -162,4 +183,567 @@ mod test {
         }
         Ok(())
     }
+
+    /// Send a request and its response through the interceptor, and return the
+    /// intercepted response
+    async fn intercept<Req: Encodable, Resp: Encodable + Decodable>(
+        interceptor: &InletInterceptorImpl,
+        context: &mut Context,
+        api_key: ApiKey,
+        api_version: i16,
+        request: &Req,
+        response: &Resp,
+    ) -> Resp {
+        let (_, response) = intercept_raw(
+            interceptor,
+            context,
+            api_key,
+            api_version,
+            request,
+            response,
+        )
+        .await;
+        let mut buffer = response.freeze();
+        ResponseHeader::decode(&mut buffer, api_key.response_header_version(api_version)).unwrap();
+        Resp::decode(&mut buffer, api_version).unwrap()
+    }
+
+    /// Send a request and its response through the interceptor, and return the
+    /// intercepted request and response, as sent
+    async fn intercept_raw<Req: Encodable, Resp: Encodable>(
+        interceptor: &InletInterceptorImpl,
+        context: &mut Context,
+        api_key: ApiKey,
+        api_version: i16,
+        request: &Req,
+        response: &Resp,
+    ) -> (BytesMut, BytesMut) {
+        let correlation_id = 42;
+        let request = interceptor
+            .intercept_request(
+                context,
+                encode_request(
+                    &RequestHeader::default()
+                        .with_request_api_version(api_version)
+                        .with_correlation_id(correlation_id)
+                        .with_request_api_key(api_key as i16),
+                    request,
+                    api_version,
+                    api_key,
+                )
+                .unwrap(),
+            )
+            .await
+            .unwrap_or_else(|e| panic!("{api_key:?} v{api_version} request: {e:?}"));
+
+        let response = interceptor
+            .intercept_response(
+                context,
+                encode_response(
+                    &ResponseHeader::default().with_correlation_id(correlation_id),
+                    response,
+                    api_version,
+                    api_key,
+                )
+                .unwrap(),
+            )
+            .await
+            .unwrap_or_else(|e| panic!("{api_key:?} v{api_version} response: {e:?}"));
+        (request, response)
+    }
+
+    fn broker_host() -> StrBytes {
+        StrBytes::from_static_str("broker.internal")
+    }
+
+    fn assert_inlet_address(host: &StrBytes, port: i32) {
+        assert_eq!(host.as_str(), "127.0.0.1");
+        assert_eq!(port, 0);
+    }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 5_000)]
+    async fn api_versions_response__unknown_apis__are_removed(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        TestNode::clean().await?;
+        let handle = crate::test_utils::start_manager_for_tests(context, None, None).await?;
+        let interceptor = create_inlet_interceptor(&handle);
+
+        let api_version = |api_key: i16, max_version: i16| {
+            ApiVersion::default()
+                .with_api_key(api_key)
+                .with_min_version(0)
+                .with_max_version(max_version)
+        };
+        // 78 is the api key of ShareFetch, which is not supported
+        let response = ApiVersionsResponse::default().with_api_keys(vec![
+            api_version(ApiKey::DescribeTopicPartitions as i16, 5),
+            api_version(ApiKey::InitProducerId as i16, 5),
+            api_version(78, 1),
+        ]);
+
+        let response = intercept(
+            &interceptor,
+            context,
+            ApiKey::ApiVersions,
+            3,
+            &ApiVersionsRequest::default(),
+            &response,
+        )
+        .await;
+
+        assert_eq!(
+            response.api_keys,
+            vec![
+                api_version(
+                    ApiKey::DescribeTopicPartitions as i16,
+                    DescribeTopicPartitionsResponse::VERSIONS.max
+                ),
+                api_version(ApiKey::InitProducerId as i16, 5),
+            ]
+        );
+        Ok(())
+    }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 10_000)]
+    async fn broker_addresses__all_api_versions__replaced_with_inlet_address(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        TestNode::clean().await?;
+        let handle = crate::test_utils::start_manager_for_tests(context, None, None).await?;
+        let interceptor = create_inlet_interceptor(&handle);
+        let broker_id = BrokerId::from(1);
+
+        for api_version in
+            DescribeClusterRequest::VERSIONS.min..=DescribeClusterRequest::VERSIONS.max
+        {
+            let response = intercept(
+                &interceptor,
+                context,
+                ApiKey::DescribeCluster,
+                api_version,
+                &DescribeClusterRequest::default(),
+                &DescribeClusterResponse::default().with_brokers(vec![
+                    DescribeClusterBroker::default()
+                        .with_broker_id(broker_id)
+                        .with_host(broker_host())
+                        .with_port(9092),
+                ]),
+            )
+            .await;
+            assert_inlet_address(&response.brokers[0].host, response.brokers[0].port);
+        }
+
+        for api_version in
+            FindCoordinatorRequest::VERSIONS.min..=FindCoordinatorRequest::VERSIONS.max
+        {
+            let response = if api_version >= 4 {
+                FindCoordinatorResponse::default().with_coordinators(vec![Coordinator::default()
+                    .with_node_id(broker_id)
+                    .with_host(broker_host())
+                    .with_port(9092)])
+            } else {
+                FindCoordinatorResponse::default()
+                    .with_node_id(broker_id)
+                    .with_host(broker_host())
+                    .with_port(9092)
+            };
+            let response = intercept(
+                &interceptor,
+                context,
+                ApiKey::FindCoordinator,
+                api_version,
+                &FindCoordinatorRequest::default(),
+                &response,
+            )
+            .await;
+            if api_version >= 4 {
+                let coordinator = &response.coordinators[0];
+                assert_inlet_address(&coordinator.host, coordinator.port);
+            } else {
+                assert_inlet_address(&response.host, response.port);
+            }
+        }
+
+        for api_version in PRODUCE_NODE_ENDPOINTS_VERSION..=ProduceRequest::VERSIONS.max {
+            let response = intercept(
+                &interceptor,
+                context,
+                ApiKey::Produce,
+                api_version,
+                &ProduceRequest::default().with_acks(1),
+                &ProduceResponse::default().with_node_endpoints(vec![
+                    produce_response::NodeEndpoint::default()
+                        .with_node_id(broker_id)
+                        .with_host(broker_host())
+                        .with_port(9092),
+                ]),
+            )
+            .await;
+            let endpoint = &response.node_endpoints[0];
+            assert_inlet_address(&endpoint.host, endpoint.port);
+        }
+
+        for api_version in FETCH_NODE_ENDPOINTS_VERSION..=FetchRequest::VERSIONS.max {
+            let response = intercept(
+                &interceptor,
+                context,
+                ApiKey::Fetch,
+                api_version,
+                &FetchRequest::default(),
+                &FetchResponse::default().with_node_endpoints(vec![
+                    fetch_response::NodeEndpoint::default()
+                        .with_node_id(broker_id)
+                        .with_host(broker_host())
+                        .with_port(9092),
+                ]),
+            )
+            .await;
+            let endpoint = &response.node_endpoints[0];
+            assert_inlet_address(&endpoint.host, endpoint.port);
+        }
+        Ok(())
+    }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 5_000)]
+    async fn describe_topic_partitions__topic_ids__used_to_fetch_by_topic_id(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        TestNode::clean().await?;
+        let handle = crate::test_utils::start_manager_for_tests(context, None, None).await?;
+        let interceptor = create_inlet_interceptor(&handle);
+        let topic_id = Uuid::from_u128(7);
+
+        // without the topic name, a fetch request by topic id is rejected
+        let fetch_request = FetchRequest::default()
+            .with_topics(vec![FetchTopic::default().with_topic_id(topic_id)]);
+        let result = interceptor
+            .intercept_request(
+                context,
+                encode_request(
+                    &RequestHeader::default()
+                        .with_request_api_version(13)
+                        .with_request_api_key(ApiKey::Fetch as i16),
+                    &fetch_request,
+                    13,
+                    ApiKey::Fetch,
+                )
+                .unwrap(),
+            )
+            .await;
+        assert!(result.is_err());
+
+        for api_version in DescribeTopicPartitionsRequest::VERSIONS.min
+            ..=DescribeTopicPartitionsRequest::VERSIONS.max
+        {
+            intercept(
+                &interceptor,
+                context,
+                ApiKey::DescribeTopicPartitions,
+                api_version,
+                &DescribeTopicPartitionsRequest::default(),
+                &DescribeTopicPartitionsResponse::default().with_topics(vec![
+                    DescribeTopicPartitionsResponseTopic::default()
+                        .with_topic_id(topic_id)
+                        .with_name(Some(TopicName::from(StrBytes::from_static_str("orders")))),
+                ]),
+            )
+            .await;
+        }
+
+        intercept(
+            &interceptor,
+            context,
+            ApiKey::Fetch,
+            13,
+            &fetch_request,
+            &FetchResponse::default(),
+        )
+        .await;
+        Ok(())
+    }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 5_000)]
+    async fn transactional_requests__all_api_versions__forwarded_unchanged(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        TestNode::clean().await?;
+        let handle = crate::test_utils::start_manager_for_tests(context, None, None).await?;
+        let interceptor = create_inlet_interceptor(&handle);
+
+        let mut requests = vec![];
+        for api_version in InitProducerIdRequest::VERSIONS.min..=InitProducerIdRequest::VERSIONS.max
+        {
+            let request = InitProducerIdRequest::default()
+                .with_transactional_id(Some(StrBytes::from_static_str("tx").into()));
+            requests.push(encode(ApiKey::InitProducerId, api_version, &request));
+        }
+        for api_version in
+            AddPartitionsToTxnRequest::VERSIONS.min..=AddPartitionsToTxnRequest::VERSIONS.max
+        {
+            let request = AddPartitionsToTxnRequest::default();
+            requests.push(encode(ApiKey::AddPartitionsToTxn, api_version, &request));
+        }
+        for api_version in EndTxnRequest::VERSIONS.min..=EndTxnRequest::VERSIONS.max {
+            let request = EndTxnRequest::default()
+                .with_transactional_id(StrBytes::from_static_str("tx").into())
+                .with_committed(true);
+            requests.push(encode(ApiKey::EndTxn, api_version, &request));
+        }
+
+        for (api_key, api_version, request) in requests {
+            let result = interceptor
+                .intercept_request(context, request.clone())
+                .await
+                .unwrap_or_else(|e| panic!("{api_key:?} v{api_version}: {e:?}"));
+            assert_eq!(result, request, "{api_key:?} v{api_version}");
+        }
+        Ok(())
+    }
+
+    /// Intercept a request and its response, and compare them to their golden files
+    async fn check_golden_files<Req: Encodable, Resp: Encodable>(
+        interceptor: &InletInterceptorImpl,
+        context: &mut Context,
+        api_key: ApiKey,
+        api_version: i16,
+        request: &Req,
+        response: &Resp,
+    ) {
+        let (request, response) = intercept_raw(
+            interceptor,
+            context,
+            api_key,
+            api_version,
+            request,
+            response,
+        )
+        .await;
+        assert_golden_file(&format!("{api_key:?}_v{api_version}_request"), &request);
+        assert_golden_file(&format!("{api_key:?}_v{api_version}_response"), &response);
+    }
+
+    /// Compare some intercepted data, hex-encoded, with a file of the `golden` directory.
+    /// A missing golden file is created, and all the golden files are written again when
+    /// the OCKAM_UPDATE_GOLDEN_FILES environment variable is set.
+    /// The new golden files must be reviewed and committed
+    fn assert_golden_file(name: &str, data: &[u8]) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/kafka/protocol_aware/golden")
+            .join(format!("{name}.hex"));
+        let actual = hex::encode(data);
+        if std::env::var_os("OCKAM_UPDATE_GOLDEN_FILES").is_some() || !path.exists() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, format!("{actual}\n")).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            expected.trim(),
+            actual,
+            "{name} differs from {}",
+            path.display()
+        );
+    }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 30_000)]
+    async fn intercepted_messages__all_api_versions__match_golden_files(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        TestNode::clean().await?;
+        let handle = crate::test_utils::start_manager_for_tests(context, None, None).await?;
+        let interceptor = create_inlet_interceptor(&handle);
+        let broker_id = BrokerId::from(1);
+
+        for api_version in ApiVersionsRequest::VERSIONS.min..=ApiVersionsRequest::VERSIONS.max {
+            let response = ApiVersionsResponse::default().with_api_keys(vec![
+                ApiVersion::default()
+                    .with_api_key(ApiKey::Fetch as i16)
+                    .with_min_version(0)
+                    .with_max_version(i16::MAX),
+                ApiVersion::default()
+                    .with_api_key(78)
+                    .with_min_version(0)
+                    .with_max_version(1),
+            ]);
+            check_golden_files(
+                &interceptor,
+                context,
+                ApiKey::ApiVersions,
+                api_version,
+                &ApiVersionsRequest::default(),
+                &response,
+            )
+            .await;
+        }
+
+        for api_version in MetadataRequest::VERSIONS.min..=MetadataRequest::VERSIONS.max {
+            let response = MetadataResponse::default()
+                .with_controller_id(broker_id)
+                .with_brokers(vec![MetadataResponseBroker::default()
+                    .with_node_id(broker_id)
+                    .with_host(broker_host())
+                    .with_port(9092)]);
+            check_golden_files(
+                &interceptor,
+                context,
+                ApiKey::Metadata,
+                api_version,
+                &MetadataRequest::default(),
+                &response,
+            )
+            .await;
+        }
+
+        for api_version in
+            DescribeClusterRequest::VERSIONS.min..=DescribeClusterRequest::VERSIONS.max
+        {
+            let response = DescribeClusterResponse::default().with_brokers(vec![
+                DescribeClusterBroker::default()
+                    .with_broker_id(broker_id)
+                    .with_host(broker_host())
+                    .with_port(9092),
+            ]);
+            check_golden_files(
+                &interceptor,
+                context,
+                ApiKey::DescribeCluster,
+                api_version,
+                &DescribeClusterRequest::default(),
+                &response,
+            )
+            .await;
+        }
+
+        for api_version in
+            FindCoordinatorRequest::VERSIONS.min..=FindCoordinatorRequest::VERSIONS.max
+        {
+            let response = if api_version >= 4 {
+                FindCoordinatorResponse::default().with_coordinators(vec![Coordinator::default()
+                    .with_node_id(broker_id)
+                    .with_host(broker_host())
+                    .with_port(9092)])
+            } else {
+                FindCoordinatorResponse::default()
+                    .with_node_id(broker_id)
+                    .with_host(broker_host())
+                    .with_port(9092)
+            };
+            check_golden_files(
+                &interceptor,
+                context,
+                ApiKey::FindCoordinator,
+                api_version,
+                &FindCoordinatorRequest::default(),
+                &response,
+            )
+            .await;
+        }
+
+        for api_version in ProduceRequest::VERSIONS.min..=ProduceRequest::VERSIONS.max {
+            let response = if api_version >= PRODUCE_NODE_ENDPOINTS_VERSION {
+                ProduceResponse::default().with_node_endpoints(vec![
+                    produce_response::NodeEndpoint::default()
+                        .with_node_id(broker_id)
+                        .with_host(broker_host())
+                        .with_port(9092),
+                ])
+            } else {
+                ProduceResponse::default()
+            };
+            check_golden_files(
+                &interceptor,
+                context,
+                ApiKey::Produce,
+                api_version,
+                &ProduceRequest::default().with_acks(1),
+                &response,
+            )
+            .await;
+        }
+
+        for api_version in FetchRequest::VERSIONS.min..=FetchRequest::VERSIONS.max {
+            let response = if api_version >= FETCH_NODE_ENDPOINTS_VERSION {
+                FetchResponse::default().with_node_endpoints(vec![
+                    fetch_response::NodeEndpoint::default()
+                        .with_node_id(broker_id)
+                        .with_host(broker_host())
+                        .with_port(9092),
+                ])
+            } else {
+                FetchResponse::default()
+            };
+            check_golden_files(
+                &interceptor,
+                context,
+                ApiKey::Fetch,
+                api_version,
+                &FetchRequest::default(),
+                &response,
+            )
+            .await;
+        }
+
+        for api_version in DescribeTopicPartitionsRequest::VERSIONS.min
+            ..=DescribeTopicPartitionsRequest::VERSIONS.max
+        {
+            let response = DescribeTopicPartitionsResponse::default().with_topics(vec![
+                DescribeTopicPartitionsResponseTopic::default()
+                    .with_topic_id(Uuid::from_u128(7))
+                    .with_name(Some(TopicName::from(StrBytes::from_static_str("orders")))),
+            ]);
+            check_golden_files(
+                &interceptor,
+                context,
+                ApiKey::DescribeTopicPartitions,
+                api_version,
+                &DescribeTopicPartitionsRequest::default(),
+                &response,
+            )
+            .await;
+        }
+        Ok(())
+    }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 5_000)]
+    async fn share_group_requests__are_rejected(context: &mut Context) -> ockam::Result<()> {
+        TestNode::clean().await?;
+        let handle = crate::test_utils::start_manager_for_tests(context, None, None).await?;
+        let interceptor = create_inlet_interceptor(&handle);
+
+        for api_key in SHARE_GROUP_API_KEYS {
+            // api key, api version, correlation id and an empty client id
+            let mut request = BytesMut::new();
+            request.extend_from_slice(&api_key.to_be_bytes());
+            request.extend_from_slice(&0_i16.to_be_bytes());
+            request.extend_from_slice(&42_i32.to_be_bytes());
+            request.extend_from_slice(&(-1_i16).to_be_bytes());
+            assert!(
+                interceptor
+                    .intercept_request(context, request)
+                    .await
+                    .is_err(),
+                "{api_key}"
+            );
+        }
+        Ok(())
+    }
+
+    fn encode<T: Encodable>(
+        api_key: ApiKey,
+        api_version: i16,
+        body: &T,
+    ) -> (ApiKey, i16, BytesMut) {
+        let header = RequestHeader::default()
+            .with_request_api_version(api_version)
+            .with_request_api_key(api_key as i16);
+        let request = encode_request(&header, body, api_version, api_key).unwrap();
+        (api_key, api_version, request)
+    }
 }
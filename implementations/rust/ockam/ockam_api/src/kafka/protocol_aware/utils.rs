@@ -1,8 +1,22 @@
 use crate::kafka::protocol_aware::InterceptError;
-use bytes::BytesMut;
+use bytes::{Buf, Bytes, BytesMut};
 use kafka_protocol::messages::ApiKey;
 use kafka_protocol::protocol::buf::ByteBuf;
 use kafka_protocol::protocol::{Decodable, Encodable};
+use kafka_protocol::records::{
+    Compression, Record, RecordBatchDecoder, RecordBatchEncoder, RecordEncodeOptions,
+};
+
+/// Size of the base offset and of the batch length which precede every record batch
+const BATCH_HEADER_SIZE: usize = 12;
+/// Offset of the magic byte, which gives the version of a record batch
+const MAGIC_BYTE_OFFSET: usize = 16;
+/// Offset of the attributes of a legacy (v0, v1) message
+const LEGACY_ATTRIBUTES_OFFSET: usize = 17;
+/// Offset of the attributes of a v2 record batch
+const ATTRIBUTES_OFFSET: usize = 21;
+/// Flag set in the attributes of a v2 record batch containing control records
+const CONTROL_BATCH_FLAG: u8 = 0x20;
 
 pub(crate) fn decode_body<T, B>(buffer: &mut B, api_version: i16) -> Result<T, InterceptError>
 where
@@ -53,3 +67,128 @@ pub(crate) fn encode_response<H: Encodable, T: Encodable>(
 
     Ok(buffer)
 }
+
+/// Records of a single record batch, with the options needed to encode them back
+/// the way the producer or the broker encoded them.
+///
+/// The producer id, producer epoch, base sequence and transactional flag are kept
+/// on each record, so they are preserved as long as the batch is encoded as a whole
+pub(crate) struct RecordBatch {
+    pub(crate) records: Vec<Record>,
+    pub(crate) options: RecordEncodeOptions,
+    /// Content of a batch which is forwarded as is, without being decoded:
+    /// a batch of control records, or a truncated batch at the end of a fetch response
+    pub(crate) unchanged: Option<Bytes>,
+}
+
+impl RecordBatch {
+    /// Create a batch of records
+    pub(crate) fn new(records: Vec<Record>, options: RecordEncodeOptions) -> Self {
+        Self {
+            records,
+            options,
+            unchanged: None,
+        }
+    }
+
+    /// Create a batch which is forwarded without being decoded
+    fn unchanged(content: Bytes) -> Self {
+        Self {
+            records: vec![],
+            options: RecordEncodeOptions {
+                version: 2,
+                compression: Compression::None,
+            },
+            unchanged: Some(content),
+        }
+    }
+}
+
+/// Return the data records of the given batches, which can be encrypted or decrypted.
+/// Control records and truncated batches are skipped
+pub(crate) fn data_records_mut(batches: &mut [RecordBatch]) -> impl Iterator<Item = &mut Record> {
+    batches
+        .iter_mut()
+        .filter(|b| b.unchanged.is_none())
+        .flat_map(|b| b.records.iter_mut())
+}
+
+/// Decode the record batches contained in the records of a produce request or a fetch response.
+/// Each batch is decoded separately, in order to keep its version and compression codec.
+///
+/// Control batches (transaction markers) are kept unchanged. A broker can also return
+/// a truncated batch at the end of a fetch response when the batch doesn't fit in the
+/// maximum fetch size: this batch is kept unchanged as well, and is ignored by the consumer
+pub(crate) fn decode_record_batches(content: &Bytes) -> Result<Vec<RecordBatch>, InterceptError> {
+    let mut content = content.clone();
+    let mut batches = vec![];
+    while content.has_remaining() {
+        if content.len() <= ATTRIBUTES_OFFSET + 1 {
+            batches.push(RecordBatch::unchanged(content));
+            break;
+        }
+        let batch_length = i32::from_be_bytes(
+            content[8..BATCH_HEADER_SIZE]
+                .try_into()
+                .map_err(|_| InterceptError::InvalidData)?,
+        );
+        let batch_size = BATCH_HEADER_SIZE
+            + usize::try_from(batch_length).map_err(|_| InterceptError::InvalidData)?;
+        if content.len() < batch_size {
+            batches.push(RecordBatch::unchanged(content));
+            break;
+        }
+
+        let version = content[MAGIC_BYTE_OFFSET] as i8;
+        if version >= 2 && content[ATTRIBUTES_OFFSET + 1] & CONTROL_BATCH_FLAG != 0 {
+            batches.push(RecordBatch::unchanged(content.split_to(batch_size)));
+            continue;
+        }
+        let compression = match version {
+            0..=1 => content[LEGACY_ATTRIBUTES_OFFSET] & 0x7,
+            _ => content[ATTRIBUTES_OFFSET + 1] & 0x7,
+        };
+        let compression = match compression {
+            0 => Compression::None,
+            1 => Compression::Gzip,
+            2 => Compression::Snappy,
+            3 => Compression::Lz4,
+            4 => Compression::Zstd,
+            _ => return Err(InterceptError::InvalidData),
+        };
+
+        let mut batch = content.split_to(batch_size);
+        let records = RecordBatchDecoder::decode::<
+            Bytes,
+            fn(&mut Bytes, Compression) -> Result<Bytes, _>,
+        >(&mut batch)
+        .map_err(|_| InterceptError::InvalidData)?;
+
+        batches.push(RecordBatch::new(
+            records,
+            RecordEncodeOptions {
+                version,
+                compression,
+            },
+        ));
+    }
+    Ok(batches)
+}
+
+/// Encode record batches decoded with [`decode_record_batches`]
+pub(crate) fn encode_record_batches(batches: &[RecordBatch]) -> Result<Bytes, InterceptError> {
+    let mut encoded = BytesMut::new();
+    for batch in batches {
+        if let Some(unchanged) = &batch.unchanged {
+            encoded.extend_from_slice(unchanged);
+            continue;
+        }
+        RecordBatchEncoder::encode::<
+            BytesMut,
+            std::slice::Iter<'_, Record>,
+            fn(&mut BytesMut, &mut BytesMut, Compression) -> Result<(), _>,
+        >(&mut encoded, batch.records.iter(), &batch.options)
+        .map_err(|_| InterceptError::InvalidData)?;
+    }
+    Ok(encoded.freeze())
+}
@@ -0,0 +1,279 @@
+use hmac::{Hmac, Mac};
+use minicbor::{CborLen, Decode, Encode};
+use ockam_core::errcode::{Kind, Origin};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// Environment variable containing a secret shared by the producers of a topic,
+/// used to encrypt record keys deterministically.
+/// It must be set, with the same value for all the Kafka inlets producing to the topic,
+/// when the keys of a topic are deterministically encrypted
+pub const OCKAM_KAFKA_DETERMINISTIC_KEY_SECRET: &str = "OCKAM_KAFKA_DETERMINISTIC_KEY_SECRET";
+
+/// Name of the record header containing the encrypted original key,
+/// when the key is deterministically encrypted.
+/// A record produced with a header of the same name is rejected
+pub(crate) const ENCRYPTED_KEY_HEADER: &str = "ockam.encrypted_key";
+
+/// Topic name used to configure the encryption of all the topics
+pub const ALL_TOPICS: &str = "*";
+
+/// Describe how the key of a record is encrypted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+pub enum KeyEncryption {
+    /// The key is sent in clear
+    #[n(0)] #[default] None,
+    /// The key is encrypted like the record value.
+    /// Two equal keys have different encrypted values, so the partition of a record
+    /// can't be chosen based on its key
+    #[n(1)] Randomized,
+    /// The key is replaced by a keyed hash of its value, so that equal keys are still
+    /// sent to the same partition. The original key is encrypted in a record header
+    #[n(2)] Deterministic,
+}
+
+/// Describe which headers of a record are encrypted
+#[derive(Debug, Clone, Default, PartialEq, Eq, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+pub enum HeadersEncryption {
+    /// The headers are sent in clear
+    #[n(0)] #[default] None,
+    /// The values of all the headers are encrypted
+    #[n(1)] All,
+    /// Only the values of the headers with those names are encrypted
+    #[n(2)] Named(#[n(0)] Vec<String>),
+}
+
+impl HeadersEncryption {
+    /// Return true if the header with this name must be encrypted
+    pub fn is_encrypted(&self, header_name: &str) -> bool {
+        match self {
+            HeadersEncryption::None => false,
+            HeadersEncryption::All => header_name != ENCRYPTED_KEY_HEADER,
+            HeadersEncryption::Named(names) => names.iter().any(|n| n == header_name),
+        }
+    }
+}
+
+/// Encryption settings for the keys and headers of the records of a topic
+#[derive(Debug, Clone, Default, PartialEq, Eq, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct TopicRecordEncryption {
+    #[n(1)] pub key: KeyEncryption,
+    #[n(2)] pub headers: HeadersEncryption,
+}
+
+impl TopicRecordEncryption {
+    /// Return true if neither the key nor the headers are encrypted
+    pub fn is_none(&self) -> bool {
+        self.key == KeyEncryption::None && self.headers == HeadersEncryption::None
+    }
+}
+
+/// Encryption settings for the keys and headers of records, per topic.
+/// The settings for the [`ALL_TOPICS`] topic apply to topics without specific settings
+#[derive(Debug, Clone, Default, PartialEq, Eq, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+#[cbor(transparent)]
+pub struct KafkaRecordEncryption(#[n(0)] BTreeMap<String, TopicRecordEncryption>);
+
+impl KafkaRecordEncryption {
+    /// Set the key encryption of a topic
+    pub fn with_key_encryption(mut self, topic: impl Into<String>, key: KeyEncryption) -> Self {
+        self.0.entry(topic.into()).or_default().key = key;
+        self
+    }
+
+    /// Set the headers encryption of a topic.
+    /// Named headers are added to the headers already configured for that topic
+    pub fn with_headers_encryption(
+        mut self,
+        topic: impl Into<String>,
+        headers: HeadersEncryption,
+    ) -> Self {
+        let entry = self.0.entry(topic.into()).or_default();
+        entry.headers = match (entry.headers.clone(), headers) {
+            (HeadersEncryption::Named(mut names), HeadersEncryption::Named(new_names)) => {
+                names.extend(new_names);
+                HeadersEncryption::Named(names)
+            }
+            (_, headers) => headers,
+        };
+        self
+    }
+
+    /// Return true if no record key or header is encrypted
+    pub fn is_empty(&self) -> bool {
+        self.0.values().all(|t| t.is_none())
+    }
+
+    /// Return true if the keys of at least one topic are deterministically encrypted
+    pub fn has_deterministic_keys(&self) -> bool {
+        self.0
+            .values()
+            .any(|t| t.key == KeyEncryption::Deterministic)
+    }
+
+    /// Return the encryption settings of a topic
+    pub fn for_topic(&self, topic: &str) -> TopicRecordEncryption {
+        self.0
+            .get(topic)
+            .or_else(|| self.0.get(ALL_TOPICS))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// Parse the topic encryption of keys, as `TOPIC` or `TOPIC:deterministic`
+pub fn parse_key_encryption(value: &str) -> Result<(String, KeyEncryption), String> {
+    match value.rsplit_once(':') {
+        Some((topic, "deterministic")) if !topic.is_empty() => {
+            Ok((topic.to_string(), KeyEncryption::Deterministic))
+        }
+        Some((topic, "randomized")) if !topic.is_empty() => {
+            Ok((topic.to_string(), KeyEncryption::Randomized))
+        }
+        Some((_, mode)) => Err(format!(
+            "invalid key encryption mode '{mode}', expected 'randomized' or 'deterministic'"
+        )),
+        None if !value.is_empty() => Ok((value.to_string(), KeyEncryption::Randomized)),
+        None => Err("the topic name can't be empty".to_string()),
+    }
+}
+
+/// Parse the topic encryption of headers, as `TOPIC` for all the headers,
+/// or `TOPIC:NAME1,NAME2` for specific headers
+pub fn parse_headers_encryption(value: &str) -> Result<(String, HeadersEncryption), String> {
+    match value.split_once(':') {
+        Some((topic, names)) if !topic.is_empty() => {
+            let names: Vec<String> = names
+                .split(',')
+                .map(|n| n.trim())
+                .filter(|n| !n.is_empty())
+                .map(|n| n.to_string())
+                .collect();
+            if names.is_empty() {
+                return Err(format!("no header names given for the topic '{topic}'"));
+            }
+            Ok((topic.to_string(), HeadersEncryption::Named(names)))
+        }
+        None if !value.is_empty() => Ok((value.to_string(), HeadersEncryption::All)),
+        _ => Err("the topic name can't be empty".to_string()),
+    }
+}
+
+/// Secret used to compute the deterministic encrypted value of record keys
+#[derive(Clone)]
+pub(crate) struct DeterministicKeySecret([u8; 32]);
+
+impl DeterministicKeySecret {
+    /// Read the secret from the [`OCKAM_KAFKA_DETERMINISTIC_KEY_SECRET`] environment variable
+    /// if the keys of some topics are deterministically encrypted.
+    /// The secret must be shared by all the producers of those topics, so an error is returned
+    /// if it is not set
+    pub(crate) fn from_env(
+        record_encryption: &KafkaRecordEncryption,
+    ) -> ockam_core::Result<Option<Self>> {
+        if !record_encryption.has_deterministic_keys() {
+            return Ok(None);
+        }
+        match std::env::var(OCKAM_KAFKA_DETERMINISTIC_KEY_SECRET) {
+            Ok(secret) if !secret.is_empty() => Ok(Some(Self::new(secret.as_bytes()))),
+            _ => Err(ockam_core::Error::new(
+                Origin::Api,
+                Kind::Invalid,
+                format!(
+                    "the {OCKAM_KAFKA_DETERMINISTIC_KEY_SECRET} environment variable must be set \
+                    to encrypt record keys deterministically"
+                ),
+            )),
+        }
+    }
+
+    /// Create a secret from an arbitrary value
+    pub(crate) fn new(secret: &[u8]) -> Self {
+        Self(Sha256::digest(secret).into())
+    }
+
+    /// Return the deterministic value of a record key for a topic
+    pub(crate) fn encrypt_key(&self, topic: &str, key: &[u8]) -> Vec<u8> {
+        let mut mac =
+            <Hmac<Sha256> as Mac>::new_from_slice(&self.0).expect("HMAC accepts keys of any size");
+        mac.update(topic.as_bytes());
+        mac.update(&[0]);
+        mac.update(key);
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_topic_encryption() {
+        assert_eq!(
+            parse_key_encryption("payments").unwrap(),
+            ("payments".to_string(), KeyEncryption::Randomized)
+        );
+        assert_eq!(
+            parse_key_encryption("payments:deterministic").unwrap(),
+            ("payments".to_string(), KeyEncryption::Deterministic)
+        );
+        assert!(parse_key_encryption("payments:other").is_err());
+        assert!(parse_key_encryption(":deterministic").is_err());
+
+        assert_eq!(
+            parse_headers_encryption("*").unwrap(),
+            ("*".to_string(), HeadersEncryption::All)
+        );
+        assert_eq!(
+            parse_headers_encryption("payments:customer-id, email").unwrap(),
+            (
+                "payments".to_string(),
+                HeadersEncryption::Named(vec!["customer-id".to_string(), "email".to_string()])
+            )
+        );
+        assert!(parse_headers_encryption("payments:").is_err());
+    }
+
+    #[test]
+    fn topic_settings_fall_back_to_all_topics() {
+        let encryption = KafkaRecordEncryption::default()
+            .with_key_encryption(ALL_TOPICS, KeyEncryption::Randomized)
+            .with_key_encryption("payments", KeyEncryption::Deterministic)
+            .with_headers_encryption("payments", HeadersEncryption::Named(vec!["a".into()]))
+            .with_headers_encryption("payments", HeadersEncryption::Named(vec!["b".into()]));
+
+        assert_eq!(
+            encryption.for_topic("payments"),
+            TopicRecordEncryption {
+                key: KeyEncryption::Deterministic,
+                headers: HeadersEncryption::Named(vec!["a".into(), "b".into()]),
+            }
+        );
+        assert_eq!(encryption.for_topic("other").key, KeyEncryption::Randomized);
+        assert!(KafkaRecordEncryption::default()
+            .for_topic("other")
+            .is_none());
+    }
+
+    #[test]
+    fn deterministic_keys_preserve_equality() {
+        let secret = DeterministicKeySecret::new(b"secret");
+        assert_eq!(
+            secret.encrypt_key("topic", b"key"),
+            secret.encrypt_key("topic", b"key")
+        );
+        assert_ne!(
+            secret.encrypt_key("topic", b"key"),
+            secret.encrypt_key("topic", b"other key")
+        );
+        assert_ne!(
+            secret.encrypt_key("topic", b"key"),
+            DeterministicKeySecret::new(b"other").encrypt_key("topic", b"key")
+        );
+    }
+}
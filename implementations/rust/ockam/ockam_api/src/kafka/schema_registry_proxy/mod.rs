@@ -0,0 +1,323 @@
+//! Interceptor of the connections to a Confluent-compatible schema registry,
+//! proxied through the same portal as the Kafka brokers.
+//!
+//! The schemas registered by the producers are rewritten so that the encrypted fields are
+//! declared with the type of their encrypted values, a string or bytes, and keep their original
+//! type in an `ockam.original_*` attribute. This way the records sent to the brokers
+//! still match their registered schema.
+//! The schemas returned to the applications are restored to their original types, so that
+//! the consumers can decode the decrypted records.
+
+use crate::kafka::field_encryption::schema_rewrite::{restore_schema, rewrite_schema, to_original};
+use crate::kafka::protocol_aware::InterceptError;
+use crate::kafka::FieldPath;
+use crate::protocol_interceptor::{Framing, HttpData, HttpFraming, HttpMessage};
+use ockam_core::async_trait;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_node::Context;
+use ockam_transport_tcp::{Direction, PortalInterceptor, PortalInterceptorFactory};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+/// Schemas are usually small, 16MB leaves room for large listings of schemas
+const MAX_HTTP_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+pub(crate) struct SchemaRegistryInterceptorFactory {
+    encrypted_fields: Arc<Vec<FieldPath>>,
+}
+
+impl SchemaRegistryInterceptorFactory {
+    pub(crate) fn new(encrypted_fields: Vec<String>) -> Self {
+        Self {
+            encrypted_fields: Arc::new(
+                encrypted_fields
+                    .iter()
+                    .map(|path| FieldPath::parse_or_field(path))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl PortalInterceptorFactory for SchemaRegistryInterceptorFactory {
+    fn create(&self) -> Arc<dyn PortalInterceptor> {
+        Arc::new(SchemaRegistryInterceptor::new(
+            self.encrypted_fields.clone(),
+        ))
+    }
+}
+
+/// Rewrites the schemas of one connection to the schema registry
+pub(crate) struct SchemaRegistryInterceptor {
+    encrypted_fields: Arc<Vec<FieldPath>>,
+    requests: Mutex<HttpFraming>,
+    responses: Mutex<HttpFraming>,
+}
+
+impl SchemaRegistryInterceptor {
+    fn new(encrypted_fields: Arc<Vec<FieldPath>>) -> Self {
+        Self {
+            encrypted_fields,
+            requests: Mutex::new(HttpFraming::new(
+                Direction::FromInletToOutlet,
+                MAX_HTTP_MESSAGE_SIZE,
+            )),
+            responses: Mutex::new(HttpFraming::new(
+                Direction::FromOutletToInlet,
+                MAX_HTTP_MESSAGE_SIZE,
+            )),
+        }
+    }
+
+    fn process(&self, direction: Direction, buffer: &[u8]) -> Result<Vec<u8>, InterceptError> {
+        let data = match direction {
+            Direction::FromInletToOutlet => self.requests.lock().unwrap().decode(buffer)?,
+            Direction::FromOutletToInlet => self.responses.lock().unwrap().decode(buffer)?,
+        };
+
+        let mut result = vec![];
+        for data in data {
+            match data {
+                HttpData::Passthrough(data) => result.extend_from_slice(&data),
+                HttpData::Message(message) => {
+                    let body = match direction {
+                        Direction::FromInletToOutlet => self.rewrite_request(&message)?,
+                        Direction::FromOutletToInlet => restore_response(&message),
+                    };
+                    match body {
+                        Some(body) => result.extend_from_slice(&message.encode_with_body(&body)),
+                        None => result.extend_from_slice(message.raw()),
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Rewrite the schema sent to register a schema, to look up a schema,
+    /// or to check its compatibility
+    fn rewrite_request(&self, request: &HttpMessage) -> Result<Option<Vec<u8>>, InterceptError> {
+        if request.method() != "POST" {
+            return Ok(None);
+        }
+        let mut body = match json_body(request) {
+            Some(body) => body,
+            None => return Ok(None),
+        };
+        let rewritten = match (body.get("schema"), body.get("schemaType")) {
+            (Some(Value::String(schema)), schema_type) => rewrite_schema(
+                schema_type.and_then(Value::as_str),
+                schema,
+                &self.encrypted_fields,
+            )?,
+            _ => None,
+        };
+        match rewritten {
+            Some(schema) => {
+                debug!("rewriting the schema sent by {}", request.start_line());
+                body["schema"] = Value::String(schema);
+                Ok(Some(serde_json::to_vec(&body)?))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// Restore the original types of the schemas returned by the registry
+fn restore_response(response: &HttpMessage) -> Option<Vec<u8>> {
+    if !response
+        .status()
+        .map(|s| (200..300).contains(&s))
+        .unwrap_or(false)
+    {
+        return None;
+    }
+    let mut body = json_body(response)?;
+    if restore_schemas(&mut body) {
+        serde_json::to_vec(&body).ok()
+    } else {
+        None
+    }
+}
+
+/// Restore the schemas in a response: the schema strings of the `/schemas` and `/subjects`
+/// endpoints, and the schema documents returned by the `/schema` endpoints
+fn restore_schemas(value: &mut Value) -> bool {
+    let mut restored = to_original(value);
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                restored |= restore_schemas(value);
+            }
+        }
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match value {
+                    Value::String(schema) if key == "schema" => {
+                        if let Some(original) = restore_schema(schema) {
+                            *schema = original;
+                            restored = true;
+                        }
+                    }
+                    value => restored |= restore_schemas(value),
+                }
+            }
+        }
+        _ => {}
+    }
+    restored
+}
+
+/// Return the body of a message if it is an uncompressed JSON document
+fn json_body(message: &HttpMessage) -> Option<Value> {
+    let encoding = message.header("content-encoding").unwrap_or("identity");
+    if !encoding.eq_ignore_ascii_case("identity") || message.body().is_empty() {
+        return None;
+    }
+    serde_json::from_slice(message.body()).ok()
+}
+
+#[async_trait]
+impl PortalInterceptor for SchemaRegistryInterceptor {
+    async fn intercept(
+        &self,
+        _context: &mut Context,
+        direction: Direction,
+        buffer: &[u8],
+    ) -> ockam_core::Result<Option<Vec<u8>>> {
+        let result = self
+            .process(direction, buffer)
+            .map_err(|error| ockam_core::Error::new(Origin::Transport, Kind::Io, error))?;
+        if result.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(result))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    const SCHEMA: &str = r#"{"type":"record","name":"Payment","fields":[{"name":"id","type":"long"},{"name":"card","type":"bytes"}]}"#;
+
+    /// Minimal schema registry, storing the registered schemas by id
+    #[derive(Default)]
+    struct MockRegistry {
+        schemas: HashMap<u32, String>,
+    }
+
+    impl MockRegistry {
+        fn handle(&mut self, request: &HttpMessage) -> Vec<u8> {
+            let body = match request.method() {
+                "POST" => {
+                    let body: Value = serde_json::from_slice(request.body()).unwrap();
+                    let id = self.schemas.len() as u32 + 1;
+                    self.schemas
+                        .insert(id, body["schema"].as_str().unwrap().to_string());
+                    json!({ "id": id })
+                }
+                _ => {
+                    let id = request.start_line().split(' ').nth(1).unwrap();
+                    let id: u32 = id.trim_start_matches("/schemas/ids/").parse().unwrap();
+                    json!({ "schema": self.schemas[&id] })
+                }
+            };
+            // use a chunked body to check that the responses are reassembled
+            let body = body.to_string();
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{body}\r\n0\r\n\r\n",
+                body.len()
+            )
+            .into_bytes()
+        }
+    }
+
+    fn send(
+        interceptor: &SchemaRegistryInterceptor,
+        registry: &mut MockRegistry,
+        request: &[u8],
+    ) -> HttpMessage {
+        let request = interceptor
+            .process(Direction::FromInletToOutlet, request)
+            .unwrap();
+        let mut framing = HttpFraming::new(Direction::FromInletToOutlet, MAX_HTTP_MESSAGE_SIZE);
+        let request = match framing.decode(&request).unwrap().pop() {
+            Some(HttpData::Message(request)) => request,
+            _ => panic!("expected a request"),
+        };
+
+        let response = registry.handle(&request);
+        let response = interceptor
+            .process(Direction::FromOutletToInlet, &response)
+            .unwrap();
+        let mut framing = HttpFraming::new(Direction::FromOutletToInlet, MAX_HTTP_MESSAGE_SIZE);
+        match framing.decode(&response).unwrap().pop() {
+            Some(HttpData::Message(response)) => response,
+            _ => panic!("expected a response"),
+        }
+    }
+
+    #[test]
+    fn rewrite_registered_schemas_and_restore_fetched_schemas() {
+        let interceptor = SchemaRegistryInterceptor::new(Arc::new(vec![
+            FieldPath::parse("id").unwrap(),
+            FieldPath::parse("card").unwrap(),
+        ]));
+        let mut registry = MockRegistry::default();
+
+        let body = json!({ "schema": SCHEMA }).to_string();
+        let request = format!(
+            "POST /subjects/payments-value/versions HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        let response = send(&interceptor, &mut registry, request.as_bytes());
+        assert_eq!(response.body(), br#"{"id":1}"#);
+
+        // the registry stores the types of the encrypted values
+        let registered: Value = serde_json::from_str(&registry.schemas[&1]).unwrap();
+        assert_eq!(registered["fields"][0]["type"], json!("string"));
+        assert_eq!(
+            registered["fields"][0]["ockam.original_type"],
+            json!("long")
+        );
+        // bytes can store the encrypted value
+        assert_eq!(registered["fields"][1]["type"], json!("bytes"));
+
+        // the applications get the original schema back
+        let response = send(
+            &interceptor,
+            &mut registry,
+            b"GET /schemas/ids/1 HTTP/1.1\r\nHost: localhost\r\n\r\n",
+        );
+        let body: Value = serde_json::from_slice(response.body()).unwrap();
+        let schema: Value = serde_json::from_str(body["schema"].as_str().unwrap()).unwrap();
+        assert_eq!(schema["fields"][0]["type"], json!("long"));
+        assert_eq!(schema["fields"][1]["type"], json!("bytes"));
+        assert_eq!(
+            response.header("content-length"),
+            Some(response.body().len().to_string().as_str())
+        );
+    }
+
+    #[test]
+    fn pass_through_other_requests() {
+        let interceptor =
+            SchemaRegistryInterceptor::new(Arc::new(vec![FieldPath::parse("id").unwrap()]));
+
+        let request = b"GET /subjects HTTP/1.1\r\n\r\n";
+        let result = interceptor
+            .process(Direction::FromInletToOutlet, request)
+            .unwrap();
+        assert_eq!(result, request);
+
+        let response = b"HTTP/1.1 404 Not Found\r\nContent-Length: 17\r\n\r\n{\"error_code\":40}";
+        let result = interceptor
+            .process(Direction::FromOutletToInlet, response)
+            .unwrap();
+        assert_eq!(result, response);
+    }
+}
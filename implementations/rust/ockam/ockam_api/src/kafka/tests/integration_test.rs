@@ -116,6 +116,9 @@ async fn create_kafka_service(
             inlet_controller,
             true,
             vec![],
+            Default::default(),
+            None,
+            Default::default(),
         )),
         Arc::new(AllowAll),
         Arc::new(AllowAll),
@@ -0,0 +1,332 @@
+use minicbor::{CborLen, Decode, Encode};
+use ockam::identity::{Identifier, IdentitiesAttributes};
+use ockam_abac::expr::str;
+use ockam_abac::{Abac, Env, PolicyExpression};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Describe how the records of a topic are handled by a Kafka inlet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+pub enum TopicEncryption {
+    /// The records are encrypted end-to-end, like every topic when the content encryption
+    /// of the inlet is enabled
+    #[n(0)] Encrypted,
+    /// The records are sent and received as they are
+    #[n(1)] Passthrough,
+}
+
+/// Settings for the topics matching a pattern.
+/// A pattern is a topic name where `*` matches any sequence of characters
+/// and `?` matches a single character
+#[derive(Debug, Clone, PartialEq, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct TopicPolicy {
+    #[n(1)] pub topics: String,
+    #[n(2)] pub encryption: Option<TopicEncryption>,
+    #[n(3)] pub expression: Option<PolicyExpression>,
+}
+
+/// Per-topic settings of a Kafka portal.
+/// The encryption settings are used by Kafka inlets, the policy expressions are checked by
+/// Kafka outlets on the Produce and Fetch requests, against the identity of the inlet.
+/// When several patterns match a topic, the first one which was configured is used
+#[derive(Debug, Clone, Default, PartialEq, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+#[cbor(transparent)]
+pub struct KafkaTopicPolicies(#[n(0)] Vec<TopicPolicy>);
+
+impl KafkaTopicPolicies {
+    /// Set how the records of the topics matching a pattern are encrypted
+    pub fn with_encryption(
+        mut self,
+        topics: impl Into<String>,
+        encryption: TopicEncryption,
+    ) -> Self {
+        self.policy_mut(topics.into()).encryption = Some(encryption);
+        self
+    }
+
+    /// Set the policy expression checked when producing to, or fetching from,
+    /// the topics matching a pattern
+    pub fn with_expression(
+        mut self,
+        topics: impl Into<String>,
+        expression: PolicyExpression,
+    ) -> Self {
+        self.policy_mut(topics.into()).expression = Some(expression);
+        self
+    }
+
+    fn policy_mut(&mut self, topics: String) -> &mut TopicPolicy {
+        let index = match self.0.iter().position(|p| p.topics == topics) {
+            Some(index) => index,
+            None => {
+                self.0.push(TopicPolicy {
+                    topics,
+                    encryption: None,
+                    expression: None,
+                });
+                self.0.len() - 1
+            }
+        };
+        &mut self.0[index]
+    }
+
+    /// Return true if no topic is configured
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Return the encryption of a topic, if it was configured
+    pub fn encryption(&self, topic: &str) -> Option<TopicEncryption> {
+        self.0
+            .iter()
+            .filter(|p| matches_pattern(&p.topics, topic))
+            .find_map(|p| p.encryption)
+    }
+
+    /// Return true if the records of some topics are encrypted
+    pub fn has_encrypted_topics(&self) -> bool {
+        self.0
+            .iter()
+            .any(|p| p.encryption == Some(TopicEncryption::Encrypted))
+    }
+
+    /// Return the policy expression of a topic, if it was configured
+    pub fn expression(&self, topic: &str) -> Option<&PolicyExpression> {
+        self.0
+            .iter()
+            .filter(|p| matches_pattern(&p.topics, topic))
+            .find_map(|p| p.expression.as_ref())
+    }
+
+    /// Return true if some topics are protected by a policy expression
+    pub fn has_expressions(&self) -> bool {
+        self.0.iter().any(|p| p.expression.is_some())
+    }
+}
+
+/// Return true if the topic matches the pattern, where `*` matches any sequence
+/// of characters and `?` matches a single character
+fn matches_pattern(pattern: &str, topic: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let topic: Vec<char> = topic.chars().collect();
+
+    // position of the last `*` in the pattern, and of the topic character it was matched with
+    let mut backtrack: Option<(usize, usize)> = None;
+    let (mut p, mut t) = (0, 0);
+    while t < topic.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == topic[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // let the last `*` match one more character
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    p = star + 1;
+                    t = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Parse the policy of some topics, as `PATTERN=EXPRESSION`
+pub fn parse_topic_policy(value: &str) -> Result<(String, PolicyExpression), String> {
+    match value.split_once('=') {
+        Some((topics, expression)) if !topics.trim().is_empty() => {
+            let expression = PolicyExpression::from_str(expression.trim())
+                .map_err(|e| format!("invalid policy expression for '{topics}': {e}"))?;
+            Ok((topics.trim().to_string(), expression))
+        }
+        Some(_) => Err("the topic pattern can't be empty".to_string()),
+        None => Err(format!(
+            "invalid topic policy '{value}', expected <TOPIC_PATTERN>=<EXPRESSION>"
+        )),
+    }
+}
+
+/// Operation on a topic, checked by the topic policies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TopicOperation {
+    Produce,
+    Fetch,
+}
+
+impl TopicOperation {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TopicOperation::Produce => "produce",
+            TopicOperation::Fetch => "fetch",
+        }
+    }
+}
+
+/// Check the policy expressions of the topics against the identity of a Kafka inlet.
+/// The expressions can use the `resource.id` attribute, set to the topic name,
+/// and the `action.id` attribute, set to `produce` or `fetch`
+#[derive(Clone)]
+pub(crate) struct TopicAuthorization {
+    policies: KafkaTopicPolicies,
+    identities_attributes: Arc<IdentitiesAttributes>,
+    authority: Option<Identifier>,
+}
+
+impl TopicAuthorization {
+    pub(crate) fn new(
+        policies: KafkaTopicPolicies,
+        identities_attributes: Arc<IdentitiesAttributes>,
+        authority: Option<Identifier>,
+    ) -> Self {
+        Self {
+            policies,
+            identities_attributes,
+            authority,
+        }
+    }
+
+    /// Return true if the identity can perform the operation on the topic.
+    /// Topics without a policy expression are only protected by the policy of the outlet
+    pub(crate) async fn is_authorized(
+        &self,
+        identifier: Option<&Identifier>,
+        topic: &str,
+        operation: TopicOperation,
+    ) -> ockam_core::Result<bool> {
+        let expression = match self.policies.expression(topic) {
+            Some(expression) => expression.to_expression(),
+            None => return Ok(true),
+        };
+        let identifier = match identifier {
+            Some(identifier) => identifier,
+            None => {
+                warn!("the Kafka request for the topic {topic} was not received from a secure channel");
+                return Ok(false);
+            }
+        };
+
+        let mut environment = Env::new();
+        environment.put("resource.id", str(topic.to_string()));
+        environment.put("action.id", str(operation.as_str().to_string()));
+        Abac::is_identity_authorized_static(
+            self.identities_attributes.clone(),
+            &environment,
+            self.authority.as_ref(),
+            identifier,
+            &expression,
+        )
+        .await
+    }
+
+    /// Return true if some topics are protected by a policy expression
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.policies.has_expressions()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_abac::expr::eq;
+    use ockam_abac::{subject_identifier_attribute, Expr};
+
+    #[test]
+    fn match_topic_patterns() {
+        assert!(matches_pattern("payments", "payments"));
+        assert!(!matches_pattern("payments", "payments-eu"));
+        assert!(matches_pattern("payments-*", "payments-eu"));
+        assert!(matches_pattern("payments-*", "payments-"));
+        assert!(matches_pattern("*-eu", "payments-eu"));
+        assert!(matches_pattern("*", "anything"));
+        assert!(matches_pattern("p*ts-*-?", "payments-eu-1"));
+        assert!(!matches_pattern("p*ts-*-?", "payments-eu-12"));
+        assert!(matches_pattern("a*b*c", "aXbYbZc"));
+        assert!(!matches_pattern("a*b*c", "aXbYbZ"));
+    }
+
+    #[test]
+    fn first_matching_pattern_is_used() {
+        let policies = KafkaTopicPolicies::default()
+            .with_encryption("payments-public", TopicEncryption::Passthrough)
+            .with_encryption("payments-*", TopicEncryption::Encrypted)
+            .with_expression(
+                "payments-*",
+                PolicyExpression::FullExpression(Expr::CONST_TRUE),
+            );
+
+        assert_eq!(
+            policies.encryption("payments-public"),
+            Some(TopicEncryption::Passthrough)
+        );
+        assert_eq!(
+            policies.encryption("payments-eu"),
+            Some(TopicEncryption::Encrypted)
+        );
+        assert_eq!(policies.encryption("orders"), None);
+        assert!(policies.expression("payments-public").is_some());
+        assert!(policies.expression("orders").is_none());
+        assert!(policies.has_encrypted_topics());
+        assert!(policies.has_expressions());
+    }
+
+    #[test]
+    fn parse_topic_policies() {
+        let (topics, expression) =
+            parse_topic_policy(r#"payments-*=(= subject.role "billing")"#).unwrap();
+        assert_eq!(topics, "payments-*");
+        assert_eq!(
+            expression,
+            PolicyExpression::from_str(r#"(= subject.role "billing")"#).unwrap()
+        );
+        assert!(parse_topic_policy("payments").is_err());
+        assert!(parse_topic_policy("=(= subject.role \"billing\")").is_err());
+    }
+
+    #[ockam_macros::test]
+    async fn check_topic_policies(_ctx: &mut ockam_node::Context) -> ockam_core::Result<()> {
+        let identities = ockam::identity::identities().await?;
+        let allowed = identities.identities_creation().create_identity().await?;
+        let denied = identities.identities_creation().create_identity().await?;
+
+        let expression = eq([subject_identifier_attribute(), str(allowed.to_string())]);
+        let authorization = TopicAuthorization::new(
+            KafkaTopicPolicies::default()
+                .with_expression("payments-*", PolicyExpression::FullExpression(expression)),
+            identities.identities_attributes(),
+            None,
+        );
+
+        assert!(
+            authorization
+                .is_authorized(Some(&allowed), "payments-eu", TopicOperation::Produce)
+                .await?
+        );
+        assert!(
+            !authorization
+                .is_authorized(Some(&denied), "payments-eu", TopicOperation::Fetch)
+                .await?
+        );
+        assert!(
+            !authorization
+                .is_authorized(None, "payments-eu", TopicOperation::Fetch)
+                .await?
+        );
+        // topics without a policy are not restricted
+        assert!(
+            authorization
+                .is_authorized(Some(&denied), "orders", TopicOperation::Fetch)
+                .await?
+        );
+        Ok(())
+    }
+}
@@ -27,12 +27,15 @@ pub mod echoer;
 pub mod enroll;
 pub mod error;
 pub mod hop;
+pub mod http_portal;
 pub mod kafka;
 pub mod minicbor_url;
 pub mod nodes;
 pub mod okta;
 pub mod orchestrator;
 pub mod port_range;
+pub mod postgres;
+pub mod protocol_interceptor;
 pub mod session;
 pub mod uppercase;
 mod version;
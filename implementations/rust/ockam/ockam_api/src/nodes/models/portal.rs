@@ -4,6 +4,7 @@ use std::fmt::{Display, Formatter};
 use std::sync::Arc;
 use std::time::Duration;
 
+use clap::ValueEnum;
 use minicbor::{CborLen, Decode, Encode};
 use ockam::identity::Identifier;
 use ockam::transport::HostnamePort;
@@ -57,6 +58,13 @@ pub struct CreateInlet {
     #[n(12)] pub(crate) privileged: bool,
     /// TLS certificate provider route.
     #[n(13)] pub(crate) tls_certificate_provider: Option<MultiAddr>,
+    /// Listen on a Unix domain socket instead of `listen_addr`.
+    #[n(14)] pub(crate) unix_socket: Option<UnixSocketListenAddr>,
+    /// Other outlets exposing the same service as `outlet_addr`, in their order of priority.
+    #[n(15)] pub(crate) additional_outlet_addrs: Option<Vec<MultiAddr>>,
+    /// The strategy used to choose an outlet for each new connection, when there are
+    /// additional outlets.
+    #[n(16)] pub(crate) load_balancing: Option<InletLoadBalancing>,
 }
 
 impl CreateInlet {
@@ -83,6 +91,9 @@ impl CreateInlet {
             disable_tcp_fallback,
             privileged,
             tls_certificate_provider: None,
+            unix_socket: None,
+            additional_outlet_addrs: None,
+            load_balancing: None,
         }
     }
 
@@ -110,6 +121,9 @@ impl CreateInlet {
             disable_tcp_fallback,
             privileged,
             tls_certificate_provider: None,
+            unix_socket: None,
+            additional_outlet_addrs: None,
+            load_balancing: None,
         }
     }
 
@@ -117,6 +131,19 @@ impl CreateInlet {
         self.tls_certificate_provider = Some(provider);
     }
 
+    pub fn set_unix_socket(&mut self, unix_socket: UnixSocketListenAddr) {
+        self.unix_socket = Some(unix_socket);
+    }
+
+    pub fn set_load_balancing(
+        &mut self,
+        additional_outlet_addrs: Vec<MultiAddr>,
+        load_balancing: InletLoadBalancing,
+    ) {
+        self.additional_outlet_addrs = Some(additional_outlet_addrs);
+        self.load_balancing = Some(load_balancing);
+    }
+
     pub fn set_wait_ms(&mut self, ms: u64) {
         self.wait_for_outlet_duration = Some(Duration::from_millis(ms))
     }
@@ -150,6 +177,70 @@ impl CreateInlet {
     }
 }
 
+/// The strategy used by an inlet with several outlets to choose the outlet of each new
+/// connection, among the outlets which are currently reachable
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Encode, Decode, CborLen, Serialize, Deserialize, ValueEnum,
+)]
+#[serde(rename_all = "kebab-case")]
+#[rustfmt::skip]
+pub enum InletLoadBalancing {
+    /// Use the outlets in turn
+    #[default]
+    #[n(0)] RoundRobin,
+    /// Use the outlet with the fewest open connections
+    #[n(1)] LeastConnections,
+    /// Use the first outlet, in their order of priority
+    #[n(2)] Failover,
+}
+
+impl Display for InletLoadBalancing {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InletLoadBalancing::RoundRobin => write!(f, "round-robin"),
+            InletLoadBalancing::LeastConnections => write!(f, "least-connections"),
+            InletLoadBalancing::Failover => write!(f, "failover"),
+        }
+    }
+}
+
+/// A Unix domain socket an inlet listens at
+#[derive(Clone, Debug, Encode, Decode, CborLen, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct UnixSocketListenAddr {
+    /// The path of the socket file
+    #[n(1)] pub path: String,
+    /// The file mode of the socket, for example 0o660
+    #[n(2)] pub mode: Option<u32>,
+    /// The id of the user owning the socket
+    #[n(3)] pub uid: Option<u32>,
+    /// The id of the group owning the socket
+    #[n(4)] pub gid: Option<u32>,
+}
+
+impl UnixSocketListenAddr {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            mode: None,
+            uid: None,
+            gid: None,
+        }
+    }
+
+    pub fn with_mode(mut self, mode: Option<u32>) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_owner(mut self, uid: Option<u32>, gid: Option<u32>) -> Self {
+        self.uid = uid;
+        self.gid = gid;
+        self
+    }
+}
+
 /// Request body to create an outlet
 #[derive(Clone, Debug, Encode, Decode, CborLen)]
 #[rustfmt::skip]
@@ -169,7 +260,15 @@ pub struct CreateOutlet {
     /// will be used.
     #[n(5)] pub policy_expression: Option<PolicyExpression>,
     /// Use eBPF and RawSocket to access TCP packets instead of TCP data stream.
-    #[n(6)] pub privileged: bool
+    #[n(6)] pub privileged: bool,
+    /// Connect to this Unix domain socket instead of `hostname_port`.
+    #[n(7)] pub unix_socket_path: Option<String>,
+    /// Connect to the destination requested by each inlet instead of `hostname_port`,
+    /// if it is accepted by one of these entries
+    #[n(8)] pub allowed_destinations: Option<Vec<String>>,
+    /// The expression for the access control policy of the destinations requested by inlets,
+    /// evaluated with the `resource.hostname` and `resource.port` attributes of the destination
+    #[n(9)] pub destination_policy_expression: Option<PolicyExpression>,
 }
 
 impl CreateOutlet {
@@ -187,12 +286,318 @@ impl CreateOutlet {
             reachable_from_default_secure_channel,
             policy_expression: None,
             privileged,
+            unix_socket_path: None,
+            allowed_destinations: None,
+            destination_policy_expression: None,
+        }
+    }
+
+    pub fn set_policy_expression(&mut self, expression: PolicyExpression) {
+        self.policy_expression = Some(expression);
+    }
+
+    pub fn set_unix_socket_path(&mut self, path: impl Into<String>) {
+        self.unix_socket_path = Some(path.into());
+    }
+
+    pub fn set_allowed_destinations(&mut self, allowed_destinations: Vec<String>) {
+        self.allowed_destinations = Some(allowed_destinations);
+    }
+
+    pub fn set_destination_policy_expression(&mut self, expression: PolicyExpression) {
+        self.destination_policy_expression = Some(expression);
+    }
+}
+
+/// Request body to create a UDP inlet
+#[derive(Clone, Debug, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct CreateUdpInlet {
+    /// The address the portal should listen at.
+    #[n(1)] pub(crate) listen_addr: HostnamePort,
+    /// The address of the UDP outlet.
+    #[n(2)] pub(crate) outlet_addr: MultiAddr,
+    /// A human-friendly alias for this portal endpoint
+    #[b(3)] pub(crate) alias: String,
+    /// An authorised identity for secure channels.
+    /// Only set for non-project addresses as for projects the project's
+    /// authorised identity will be used.
+    #[n(4)] pub(crate) authorized: Option<Identifier>,
+    /// The maximum duration to wait for an outlet to be available
+    #[n(5)] pub(crate) wait_for_outlet_duration: Option<Duration>,
+    /// The expression for the access control policy for this inlet.
+    /// If not set, the policy set for the [TCP inlet resource type](ockam_abac::ResourceType::TcpInlet)
+    /// will be used.
+    #[n(6)] pub(crate) policy_expression: Option<PolicyExpression>,
+    /// Create the inlet and wait for the outlet to connect
+    #[n(7)] pub(crate) wait_connection: bool,
+    /// The duration after which the flow of an idle client is closed
+    #[n(8)] pub(crate) idle_timeout: Option<Duration>,
+}
+
+impl CreateUdpInlet {
+    pub fn new(
+        listen: HostnamePort,
+        to: MultiAddr,
+        alias: String,
+        authorized: Option<Identifier>,
+        wait_connection: bool,
+    ) -> Self {
+        Self {
+            listen_addr: listen,
+            outlet_addr: to,
+            alias,
+            authorized,
+            wait_for_outlet_duration: None,
+            policy_expression: None,
+            wait_connection,
+            idle_timeout: None,
+        }
+    }
+
+    pub fn set_wait_ms(&mut self, ms: u64) {
+        self.wait_for_outlet_duration = Some(Duration::from_millis(ms))
+    }
+
+    pub fn set_policy_expression(&mut self, expression: PolicyExpression) {
+        self.policy_expression = Some(expression);
+    }
+
+    pub fn set_idle_timeout(&mut self, idle_timeout: Duration) {
+        self.idle_timeout = Some(idle_timeout);
+    }
+}
+
+/// Request body to create a UDP outlet
+#[derive(Clone, Debug, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct CreateUdpOutlet {
+    /// The address the portal should send the datagrams to
+    #[n(1)] pub hostname_port: HostnamePort,
+    /// The address the portal should listen to
+    #[n(2)] pub worker_addr: Option<Address>,
+    /// The expression for the access control policy for this outlet.
+    /// If not set, the policy set for the [TCP outlet resource type](ockam_abac::ResourceType::TcpOutlet)
+    /// will be used.
+    #[n(3)] pub policy_expression: Option<PolicyExpression>,
+    /// The duration after which the flow of an idle inlet client is closed
+    #[n(4)] pub idle_timeout: Option<Duration>,
+}
+
+impl CreateUdpOutlet {
+    pub fn new(hostname_port: HostnamePort, worker_addr: Option<Address>) -> Self {
+        Self {
+            hostname_port,
+            worker_addr,
+            policy_expression: None,
+            idle_timeout: None,
+        }
+    }
+
+    pub fn set_policy_expression(&mut self, expression: PolicyExpression) {
+        self.policy_expression = Some(expression);
+    }
+
+    pub fn set_idle_timeout(&mut self, idle_timeout: Duration) {
+        self.idle_timeout = Some(idle_timeout);
+    }
+}
+
+/// Request body to create a proxy inlet
+#[derive(Clone, Debug, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct CreateProxyInlet {
+    /// The address the proxy should listen at.
+    #[n(1)] pub(crate) listen_addr: HostnamePort,
+    /// A human-friendly alias for this proxy inlet
+    #[b(2)] pub(crate) alias: String,
+    /// The outlets serving explicit destinations
+    #[n(3)] pub(crate) routes: Vec<ProxyRoute>,
+    /// The route to the outlet of any other destination, where `{host}` and `{port}`
+    /// are replaced by the requested hostname and port
+    #[n(4)] pub(crate) route_template: Option<String>,
+    /// An authorised identity for secure channels.
+    /// Only set for non-project addresses as for projects the project's
+    /// authorised identity will be used.
+    #[n(5)] pub(crate) authorized: Option<Identifier>,
+    /// The expression for the access control policy of the destinations which don't
+    /// have their own policy expression.
+    /// If not set, the policy set for the [TCP inlet resource type](ockam_abac::ResourceType::TcpInlet)
+    /// will be used.
+    #[n(6)] pub(crate) policy_expression: Option<PolicyExpression>,
+    /// The maximum duration to wait for the outlet of a destination to be available
+    #[n(7)] pub(crate) connect_timeout: Option<Duration>,
+    /// Send the requested destination to the outlets, for outlets forwarding to
+    /// dynamic destinations
+    #[n(8)] pub(crate) forward_destination: bool,
+}
+
+impl CreateProxyInlet {
+    pub fn new(listen: HostnamePort, alias: String, authorized: Option<Identifier>) -> Self {
+        Self {
+            listen_addr: listen,
+            alias,
+            routes: vec![],
+            route_template: None,
+            authorized,
+            policy_expression: None,
+            connect_timeout: None,
+            forward_destination: false,
         }
     }
 
+    pub fn add_route(&mut self, route: ProxyRoute) {
+        self.routes.push(route);
+    }
+
+    pub fn set_route_template(&mut self, route_template: impl Into<String>) {
+        self.route_template = Some(route_template.into());
+    }
+
     pub fn set_policy_expression(&mut self, expression: PolicyExpression) {
         self.policy_expression = Some(expression);
     }
+
+    pub fn set_connect_timeout(&mut self, connect_timeout: Duration) {
+        self.connect_timeout = Some(connect_timeout);
+    }
+
+    pub fn set_forward_destination(&mut self, forward_destination: bool) {
+        self.forward_destination = forward_destination;
+    }
+}
+
+/// The outlet serving a destination of a proxy inlet
+#[derive(Clone, Debug, Encode, Decode, CborLen, Serialize, Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct ProxyRoute {
+    /// The destination requested by the clients: `hostname:port`, `hostname` for any port,
+    /// or `*.domain` and `*.domain:port` for any subdomain
+    #[n(1)] pub destination: String,
+    /// The route to the outlet serving this destination
+    #[n(2)] pub outlet_addr: MultiAddr,
+    /// The expression for the access control policy of this destination
+    #[n(3)] pub policy_expression: Option<PolicyExpression>,
+}
+
+impl ProxyRoute {
+    pub fn new(destination: impl Into<String>, outlet_addr: MultiAddr) -> Self {
+        Self {
+            destination: destination.into(),
+            outlet_addr,
+            policy_expression: None,
+        }
+    }
+
+    pub fn with_policy_expression(mut self, expression: Option<PolicyExpression>) -> Self {
+        self.policy_expression = expression;
+        self
+    }
+}
+
+/// Response body when interacting with a proxy inlet endpoint
+#[derive(Clone, Debug, Encode, Decode, CborLen, Serialize, Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct ProxyInletStatus {
+    #[n(1)] pub bind_addr: String,
+    #[n(2)] pub alias: String,
+    #[n(3)] pub routes: Vec<ProxyRoute>,
+    #[n(4)] pub route_template: Option<String>,
+    /// True if the requested destinations are sent to the outlets
+    #[n(5)] pub forward_destination: bool,
+}
+
+impl ProxyInletStatus {
+    pub fn new(
+        bind_addr: impl Into<String>,
+        alias: impl Into<String>,
+        routes: Vec<ProxyRoute>,
+        route_template: Option<String>,
+    ) -> Self {
+        Self {
+            bind_addr: bind_addr.into(),
+            alias: alias.into(),
+            routes,
+            route_template,
+            forward_destination: false,
+        }
+    }
+
+    pub fn with_forward_destination(mut self, forward_destination: bool) -> Self {
+        self.forward_destination = forward_destination;
+        self
+    }
+}
+
+impl Display for ProxyInletStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Proxy Inlet {} at {}",
+            color_primary(&self.alias),
+            color_primary(&self.bind_addr),
+        )?;
+        for route in &self.routes {
+            writeln!(
+                f,
+                "{}{} served by the outlet at {}",
+                fmt::INDENTATION,
+                color_primary(&route.destination),
+                color_primary(route.outlet_addr.to_string())
+            )?;
+        }
+        if let Some(route_template) = &self.route_template {
+            writeln!(
+                f,
+                "{}Other destinations served by the outlet at {}",
+                fmt::INDENTATION,
+                color_primary(route_template)
+            )?;
+        }
+        if self.forward_destination {
+            writeln!(
+                f,
+                "{}The requested destinations are forwarded to the outlets",
+                fmt::INDENTATION,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Output for ProxyInletStatus {
+    fn item(&self) -> crate::Result<String> {
+        Ok(self.padded_display())
+    }
+}
+
+/// The status of one of the outlets of an inlet with several outlets
+#[derive(Clone, Debug, Encode, Decode, CborLen, Serialize, PartialEq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct InletBackendStatus {
+    #[n(1)] pub outlet_addr: String,
+    #[n(2)] pub status: ConnectionStatus,
+    #[n(3)] pub outlet_route: Option<String>,
+    /// The number of open connections to this outlet
+    #[n(4)] pub connections: u64,
+}
+
+impl Display for InletBackendStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Outlet {} is {} with {} open connection(s)",
+            color_primary(&self.outlet_addr),
+            self.status,
+            color_primary(self.connections.to_string()),
+        )
+    }
 }
 
 /// Response body when interacting with a portal endpoint
@@ -209,6 +614,10 @@ pub struct InletStatus {
     #[n(6)] pub status: ConnectionStatus,
     #[n(7)] pub outlet_addr: String,
     #[n(8)] pub privileged: bool,
+    /// The strategy used to choose one of the outlets, when the inlet has several outlets
+    #[n(9)] pub load_balancing: Option<InletLoadBalancing>,
+    /// The status of each outlet, when the inlet has several outlets
+    #[n(10)] pub backends: Option<Vec<InletBackendStatus>>,
 }
 
 impl InletStatus {
@@ -232,8 +641,20 @@ impl InletStatus {
             status,
             outlet_addr: outlet_addr.into(),
             privileged,
+            load_balancing: None,
+            backends: None,
         }
     }
+
+    pub fn with_backends(
+        mut self,
+        load_balancing: InletLoadBalancing,
+        backends: Vec<InletBackendStatus>,
+    ) -> Self {
+        self.load_balancing = Some(load_balancing);
+        self.backends = Some(backends);
+        self
+    }
 }
 
 impl Display for InletStatus {
@@ -264,6 +685,17 @@ impl Display for InletStatus {
             fmt::INDENTATION,
             color_primary(&self.outlet_addr)
         )?;
+        if let Some(load_balancing) = &self.load_balancing {
+            writeln!(
+                f,
+                "{}Connections are balanced across its outlets with {}",
+                fmt::INDENTATION,
+                color_primary(load_balancing.to_string())
+            )?;
+            for backend in self.backends.iter().flatten() {
+                write!(f, "{}{}{}", fmt::INDENTATION, fmt::INDENTATION, backend)?;
+            }
+        }
         if self.privileged {
             writeln!(
                 f,
@@ -292,6 +724,10 @@ pub struct OutletStatus {
     /// An optional status payload
     #[n(3)] pub payload: Option<String>,
     #[n(4)] pub privileged: bool,
+    /// The Unix domain socket the outlet connects to, instead of `to`
+    #[n(5)] pub unix_socket_path: Option<String>,
+    /// The destinations that inlets can request, when the outlet doesn't connect to `to`
+    #[n(6)] pub allowed_destinations: Option<Vec<String>>,
 }
 
 impl OutletStatus {
@@ -306,6 +742,30 @@ impl OutletStatus {
             worker_addr,
             payload: payload.into(),
             privileged,
+            unix_socket_path: None,
+            allowed_destinations: None,
+        }
+    }
+
+    pub fn with_unix_socket_path(mut self, path: Option<String>) -> Self {
+        self.unix_socket_path = path;
+        self
+    }
+
+    pub fn with_allowed_destinations(mut self, allowed_destinations: Option<Vec<String>>) -> Self {
+        self.allowed_destinations = allowed_destinations;
+        self
+    }
+
+    /// Return the destination of the outlet: a Unix domain socket path, the destinations
+    /// that inlets can request, or a hostname and port
+    pub fn destination(&self) -> String {
+        match (&self.unix_socket_path, &self.allowed_destinations) {
+            (Some(path), _) => path.clone(),
+            (None, Some(allowed_destinations)) => {
+                format!("any of {}", allowed_destinations.join(", "))
+            }
+            (None, None) => self.to.to_string(),
         }
     }
 
@@ -332,7 +792,7 @@ impl Display for OutletStatus {
                     .map_err(|_| std::fmt::Error)?
                     .to_string()
             ),
-            color_primary(self.to.to_string()),
+            color_primary(self.destination()),
         )?;
 
         if self.privileged {
@@ -6,7 +6,7 @@ use minicbor::{CborLen, Decode, Encode};
 use serde::Serialize;
 
 use ockam::identity::models::CredentialAndPurposeKey;
-use ockam::identity::{Identifier, SecureChannel, SecureChannelListener};
+use ockam::identity::{Identifier, RekeyMetrics, SecureChannel, SecureChannelListener};
 use ockam_core::flow_control::FlowControlId;
 use ockam_core::{route, Address, Result};
 use ockam_multiaddr::MultiAddr;
@@ -217,6 +217,8 @@ pub struct ShowSecureChannelResponse {
     #[n(2)] pub route: Option<String>,
     #[n(3)] pub authorized_identifiers: Option<Vec<String>>,
     #[n(4)] pub flow_control_id: Option<FlowControlId>,
+    #[n(5)] pub encryption_rekeys: Option<u64>,
+    #[n(6)] pub decryption_rekeys: Option<u64>,
 }
 
 impl ShowSecureChannelResponse {
@@ -235,15 +237,23 @@ impl ShowSecureChannelResponse {
                 })
                 .unwrap_or(None),
             flow_control_id: info.map(|info| info.sc().flow_control_id().clone()),
+            encryption_rekeys: None,
+            decryption_rekeys: None,
         }
     }
+
+    pub fn with_rekey_metrics(mut self, rekey_metrics: &RekeyMetrics) -> Self {
+        self.encryption_rekeys = Some(rekey_metrics.encryption_rekeys());
+        self.decryption_rekeys = Some(rekey_metrics.decryption_rekeys());
+        self
+    }
 }
 
 impl Output for ShowSecureChannelResponse {
     fn item(&self) -> crate::Result<String> {
         let s = match &self.channel {
             Some(addr) => {
-                format!(
+                let s = format!(
                     "\n  Secure Channel:\n{} {}\n{} {}\n{} {}",
                     "  •         At: ".light_magenta(),
                     ReverseLocalConverter::convert_route(&route![addr.to_string()])?
@@ -259,7 +269,16 @@ impl Output for ShowSecureChannelResponse {
                         .map(|id| id.clone().light_yellow().to_string())
                         .collect::<Vec<String>>()
                         .join("\n\t")
-                )
+                );
+                match (self.encryption_rekeys, self.decryption_rekeys) {
+                    (Some(encryption_rekeys), Some(decryption_rekeys)) => format!(
+                        "{s}\n{} {}",
+                        "  •     Rekeys: ".light_magenta(),
+                        format!("{encryption_rekeys} sent, {decryption_rekeys} received")
+                            .light_yellow()
+                    ),
+                    _ => s,
+                }
             }
             None => format!("{}", "Channel not found".red()),
         };
@@ -1,5 +1,7 @@
 use crate::colors::{color_primary, color_warn};
-use crate::kafka::{ConsumerPublishing, ConsumerResolution};
+use crate::kafka::{
+    ConsumerPublishing, ConsumerResolution, KafkaRecordEncryption, KafkaTopicPolicies,
+};
 use crate::output::Output;
 use minicbor::{CborLen, Decode, Encode};
 use ockam_abac::PolicyExpression;
@@ -8,6 +10,7 @@ use ockam_multiaddr::MultiAddr;
 use ockam_transport_core::HostnamePort;
 use serde::Serialize;
 use std::fmt::Display;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Encode, Decode, CborLen)]
 #[rustfmt::skip]
@@ -58,6 +61,9 @@ pub struct StartKafkaOutletRequest {
     #[n(1)] bootstrap_server_addr: HostnamePort,
     #[n(2)] tls: bool,
     #[n(3)] policy_expression: Option<PolicyExpression>,
+    #[n(4)] schema_registry_addr: Option<HostnamePort>,
+    #[n(5)] schema_registry_tls: bool,
+    #[n(6)] topic_policies: KafkaTopicPolicies,
 }
 
 impl StartKafkaOutletRequest {
@@ -65,11 +71,17 @@ impl StartKafkaOutletRequest {
         bootstrap_server_addr: HostnamePort,
         tls: bool,
         policy_expression: Option<PolicyExpression>,
+        schema_registry_addr: Option<HostnamePort>,
+        schema_registry_tls: bool,
+        topic_policies: KafkaTopicPolicies,
     ) -> Self {
         Self {
             bootstrap_server_addr,
             tls,
             policy_expression,
+            schema_registry_addr,
+            schema_registry_tls,
+            topic_policies,
         }
     }
 
@@ -84,6 +96,18 @@ impl StartKafkaOutletRequest {
     pub fn policy_expression(&self) -> Option<PolicyExpression> {
         self.policy_expression.clone()
     }
+
+    pub fn schema_registry_addr(&self) -> Option<HostnamePort> {
+        self.schema_registry_addr.clone()
+    }
+
+    pub fn schema_registry_tls(&self) -> bool {
+        self.schema_registry_tls
+    }
+
+    pub fn topic_policies(&self) -> KafkaTopicPolicies {
+        self.topic_policies.clone()
+    }
 }
 
 #[derive(Debug, Clone, Encode, Decode, CborLen)]
@@ -100,6 +124,11 @@ pub struct StartKafkaInletRequest {
     #[n(8)] consumer_policy_expression: Option<PolicyExpression>,
     #[n(9)] producer_policy_expression: Option<PolicyExpression>,
     #[n(10)] encrypted_fields: Vec<String>,
+    #[n(11)] record_encryption: KafkaRecordEncryption,
+    #[n(12)] schema_registry: Option<String>,
+    #[n(13)] schema_registry_proxy: Option<HostnamePort>,
+    #[n(14)] topic_policies: KafkaTopicPolicies,
+    #[n(15)] consumer_key_retention: Option<Duration>,
 }
 
 impl StartKafkaInletRequest {
@@ -110,11 +139,16 @@ impl StartKafkaInletRequest {
         kafka_outlet_route: MultiAddr,
         encrypt_content: bool,
         encrypted_fields: Vec<String>,
+        record_encryption: KafkaRecordEncryption,
+        schema_registry: Option<String>,
+        schema_registry_proxy: Option<HostnamePort>,
+        topic_policies: KafkaTopicPolicies,
         consumer_resolution: ConsumerResolution,
         consumer_publishing: ConsumerPublishing,
         inlet_policy_expression: Option<PolicyExpression>,
         consumer_policy_expression: Option<PolicyExpression>,
         producer_policy_expression: Option<PolicyExpression>,
+        consumer_key_retention: Option<Duration>,
     ) -> Self {
         Self {
             bind_address,
@@ -127,6 +161,11 @@ impl StartKafkaInletRequest {
             consumer_policy_expression,
             producer_policy_expression,
             encrypted_fields,
+            record_encryption,
+            schema_registry,
+            schema_registry_proxy,
+            topic_policies,
+            consumer_key_retention,
         }
     }
 
@@ -148,6 +187,22 @@ impl StartKafkaInletRequest {
         self.encrypted_fields.clone()
     }
 
+    pub fn record_encryption(&self) -> KafkaRecordEncryption {
+        self.record_encryption.clone()
+    }
+
+    pub fn schema_registry(&self) -> Option<String> {
+        self.schema_registry.clone()
+    }
+
+    pub fn schema_registry_proxy(&self) -> Option<HostnamePort> {
+        self.schema_registry_proxy.clone()
+    }
+
+    pub fn topic_policies(&self) -> KafkaTopicPolicies {
+        self.topic_policies.clone()
+    }
+
     pub fn consumer_resolution(&self) -> ConsumerResolution {
         self.consumer_resolution.clone()
     }
@@ -167,6 +222,10 @@ impl StartKafkaInletRequest {
     pub fn producer_policy_expression(&self) -> Option<PolicyExpression> {
         self.producer_policy_expression.clone()
     }
+
+    pub fn consumer_key_retention(&self) -> Option<Duration> {
+        self.consumer_key_retention
+    }
 }
 
 /// Request body when instructing a node to start an Uppercase service
@@ -1,4 +1,5 @@
 use crate::cli_state::random_name;
+use crate::nodes::models::portal::ProxyRoute;
 use crate::DefaultAddress;
 
 use ockam::identity::Identifier;
@@ -11,7 +12,9 @@ use ockam_multiaddr::MultiAddr;
 use ockam_node::compat::asynchronous::Mutex as AsyncMutex;
 use ockam_transport_core::HostnamePort;
 
+use crate::nodes::service::tcp_inlets::InletLoadBalancer;
 use crate::session::session::Session;
+use ockam_transport_tcp::TcpInlet;
 use std::fmt::Display;
 use std::hash::Hash;
 use std::sync::Arc;
@@ -117,16 +120,41 @@ impl Display for KafkaServiceKind {
 #[derive(Clone)]
 pub(crate) struct KafkaServiceInfo {
     kind: KafkaServiceKind,
+    schema_registry_address: Option<Address>,
 }
 
 impl KafkaServiceInfo {
     pub fn new(kind: KafkaServiceKind) -> Self {
-        Self { kind }
+        Self {
+            kind,
+            schema_registry_address: None,
+        }
+    }
+
+    /// Address of the worker proxying the schema registry, stopped with the service
+    pub fn with_schema_registry_address(mut self, address: Option<Address>) -> Self {
+        self.schema_registry_address = address;
+        self
     }
 
     pub fn kind(&self) -> &KafkaServiceKind {
         &self.kind
     }
+
+    pub fn schema_registry_address(&self) -> Option<&Address> {
+        self.schema_registry_address.as_ref()
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ProxyInletInfo {
+    pub(crate) bind_addr: String,
+    pub(crate) processor_address: Address,
+    pub(crate) routes: Vec<ProxyRoute>,
+    pub(crate) route_template: Option<String>,
+    /// Names of the resources used for the access control of the destinations
+    pub(crate) resource_names: Vec<String>,
+    pub(crate) forward_destination: bool,
 }
 
 #[derive(Clone)]
@@ -135,6 +163,7 @@ pub(crate) struct InletInfo {
     pub(crate) outlet_addr: MultiAddr,
     pub(crate) session: Arc<AsyncMutex<Session>>,
     pub(crate) privileged: bool,
+    pub(crate) backends: Option<InletBackendsInfo>,
 }
 
 impl InletInfo {
@@ -149,8 +178,43 @@ impl InletInfo {
             outlet_addr,
             session: Arc::new(AsyncMutex::new(session)),
             privileged,
+            backends: None,
         }
     }
+
+    /// Create the information of an inlet with several outlets, the first one being
+    /// the outlet with the highest priority
+    pub(crate) fn with_backends(bind_addr: &str, backends: InletBackendsInfo) -> Self {
+        let (outlet_addr, session) = backends.outlets[0].clone();
+        Self {
+            bind_addr: bind_addr.to_owned(),
+            outlet_addr,
+            session,
+            privileged: false,
+            backends: Some(backends),
+        }
+    }
+
+    /// Return the sessions maintaining the connections to the outlets of the inlet
+    pub(crate) fn sessions(&self) -> Vec<Arc<AsyncMutex<Session>>> {
+        match &self.backends {
+            Some(backends) => backends
+                .outlets
+                .iter()
+                .map(|(_, session)| session.clone())
+                .collect(),
+            None => vec![self.session.clone()],
+        }
+    }
+}
+
+/// The outlets of an inlet with several outlets, and the load balancer choosing
+/// one of them for each new connection
+#[derive(Clone)]
+pub(crate) struct InletBackendsInfo {
+    pub(crate) inlet: Arc<TcpInlet>,
+    pub(crate) load_balancer: Arc<InletLoadBalancer>,
+    pub(crate) outlets: Vec<(MultiAddr, Arc<AsyncMutex<Session>>)>,
 }
 
 #[derive(Clone)]
@@ -158,6 +222,8 @@ pub struct OutletInfo {
     pub(crate) to: HostnamePort,
     pub(crate) worker_addr: Address,
     pub(crate) privileged: bool,
+    pub(crate) unix_socket_path: Option<String>,
+    pub(crate) allowed_destinations: Option<Vec<String>>,
 }
 
 impl OutletInfo {
@@ -170,8 +236,20 @@ impl OutletInfo {
             to,
             worker_addr,
             privileged,
+            unix_socket_path: None,
+            allowed_destinations: None,
         }
     }
+
+    pub(crate) fn with_unix_socket_path(mut self, path: impl Into<String>) -> Self {
+        self.unix_socket_path = Some(path.into());
+        self
+    }
+
+    pub(crate) fn with_allowed_destinations(mut self, allowed_destinations: Vec<String>) -> Self {
+        self.allowed_destinations = Some(allowed_destinations);
+        self
+    }
 }
 
 #[derive(Clone)]
@@ -192,6 +270,9 @@ pub(crate) struct Registry {
     pub(crate) relays: RegistryOf<String, RegistryRelayInfo>,
     pub(crate) inlets: RegistryOf<String, InletInfo>,
     pub(crate) outlets: RegistryOf<Address, OutletInfo>,
+    pub(crate) udp_inlets: RegistryOf<String, InletInfo>,
+    pub(crate) udp_outlets: RegistryOf<Address, OutletInfo>,
+    pub(crate) proxy_inlets: RegistryOf<String, ProxyInletInfo>,
     pub(crate) influxdb_services: RegistryOf<Address, ()>, // TODO: what should we persist here?
 }
 
@@ -253,11 +334,19 @@ impl<K: Hash + Eq + Clone, V: Clone> RegistryOf<K, V> {
 
 impl RegistryOf<Address, OutletInfo> {
     pub fn generate_worker_addr(&self, worker_addr: Option<Address>) -> Address {
+        self.generate_worker_addr_with_default(worker_addr, DefaultAddress::OUTLET_SERVICE)
+    }
+
+    pub fn generate_worker_addr_with_default(
+        &self,
+        worker_addr: Option<Address>,
+        default: &str,
+    ) -> Address {
         match worker_addr {
             Some(addr) => addr,
             None => {
                 // If no worker address is passed, return the default address if it's not in use
-                let default: Address = DefaultAddress::OUTLET_SERVICE.into();
+                let default: Address = default.into();
                 if self.contains_key(&default) {
                     random_name().into()
                 } else {
@@ -14,11 +14,14 @@ pub mod messages;
 mod node_services;
 pub(crate) mod policy;
 mod projects;
+pub mod proxy_inlets;
 pub mod relay;
 mod secure_channel;
 pub mod tcp_inlets;
 pub mod tcp_outlets;
 mod transport;
+pub mod udp_inlets;
+pub mod udp_outlets;
 pub mod workers;
 
 mod certificate_provider;
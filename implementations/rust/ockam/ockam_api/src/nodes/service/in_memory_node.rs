@@ -186,8 +186,10 @@ impl InMemoryNode {
     }
 
     pub async fn stop(&self, ctx: &Context) -> Result<()> {
-        for session in self.registry.inlets.values() {
-            session.session.lock().await.stop().await;
+        for inlet in self.registry.inlets.values() {
+            for session in inlet.sessions() {
+                session.lock().await.stop().await;
+            }
         }
 
         for session in self.registry.relays.values() {
@@ -5,8 +5,11 @@ use crate::kafka::protocol_aware::inlet::KafkaInletInterceptorFactory;
 use crate::kafka::protocol_aware::outlet::KafkaOutletInterceptorFactory;
 use crate::kafka::KafkaOutletController;
 use crate::kafka::{
-    kafka_policy_expression, ConsumerPublishing, ConsumerResolution, KafkaInletController,
+    kafka_policy_expression, ConsumerPublishing, ConsumerResolution, DeterministicKeySecret,
+    KafkaInletController, KafkaRecordEncryption, KafkaTopicPolicies, SchemaRegistryClient,
+    SchemaRegistryInterceptorFactory, TopicAuthorization, DEFAULT_CONSUMER_KEY_RETENTION,
     KAFKA_OUTLET_BOOTSTRAP_ADDRESS, KAFKA_OUTLET_INTERCEPTOR_ADDRESS,
+    KAFKA_OUTLET_SCHEMA_REGISTRY_ADDRESS,
 };
 use crate::nodes::models::portal::OutletAccessControl;
 use crate::nodes::models::services::{
@@ -30,6 +33,7 @@ use ockam_transport_tcp::{
     read_portal_payload_length, PortalInletInterceptor, PortalOutletInterceptor,
 };
 use std::sync::Arc;
+use std::time::Duration;
 
 impl NodeManagerWorker {
     pub(super) async fn start_kafka_inlet_service(
@@ -48,11 +52,16 @@ impl NodeManagerWorker {
                 request.project_route(),
                 request.encrypt_content(),
                 request.encrypted_fields(),
+                request.record_encryption(),
+                request.schema_registry(),
+                request.schema_registry_proxy(),
+                request.topic_policies(),
                 request.consumer_resolution(),
                 request.consumer_publishing(),
                 request.inlet_policy_expression(),
                 request.consumer_policy_expression(),
                 request.producer_policy_expression(),
+                request.consumer_key_retention(),
             )
             .await
         {
@@ -75,6 +84,9 @@ impl NodeManagerWorker {
                 request.bootstrap_server_addr(),
                 request.tls(),
                 request.policy_expression(),
+                request.schema_registry_addr(),
+                request.schema_registry_tls(),
+                request.topic_policies(),
             )
             .await
         {
@@ -121,12 +133,20 @@ impl InMemoryNode {
         outlet_node_multiaddr: MultiAddr,
         encrypt_content: bool,
         encrypted_fields: Vec<String>,
+        record_encryption: KafkaRecordEncryption,
+        schema_registry: Option<String>,
+        schema_registry_proxy: Option<HostnamePort>,
+        topic_policies: KafkaTopicPolicies,
         consumer_resolution: ConsumerResolution,
         consumer_publishing: ConsumerPublishing,
         inlet_policy_expression: Option<PolicyExpression>,
         consumer_policy_expression: Option<PolicyExpression>,
         producer_policy_expression: Option<PolicyExpression>,
+        consumer_key_retention: Option<Duration>,
     ) -> Result<()> {
+        // the secret is shared by all the producers of the deterministically encrypted topics
+        let deterministic_key_secret = DeterministicKeySecret::from_env(&record_encryption)?;
+
         let consumer_policy_access_control = self
             .policy_access_control(
                 self.project_authority().clone(),
@@ -151,6 +171,16 @@ impl InMemoryNode {
             )
             .await?;
 
+        // when the schema registry is proxied, the inlet fetches the schemas through the proxy
+        let schema_registry = schema_registry
+            .or_else(|| {
+                schema_registry_proxy
+                    .as_ref()
+                    .map(|proxy| format!("http://{proxy}"))
+            })
+            .map(SchemaRegistryClient::new)
+            .transpose()?;
+
         let secure_channel_controller = KafkaKeyExchangeControllerImpl::new(
             self.node_manager.clone(),
             self.secure_channels.clone(),
@@ -158,6 +188,9 @@ impl InMemoryNode {
             consumer_publishing,
             consumer_policy_access_control,
             producer_policy_access_control,
+        )
+        .with_consumer_key_retention(
+            consumer_key_retention.unwrap_or(DEFAULT_CONSUMER_KEY_RETENTION),
         );
 
         self.node_manager
@@ -203,13 +236,13 @@ impl InMemoryNode {
         // create the kafka bootstrap inlet
         self.create_inlet(
             context,
-            bind_address,
+            bind_address.clone(),
             route![interceptor_address.clone()],
             route![
                 KAFKA_OUTLET_INTERCEPTOR_ADDRESS,
                 KAFKA_OUTLET_BOOTSTRAP_ADDRESS
             ],
-            outlet_node_multiaddr,
+            outlet_node_multiaddr.clone(),
             inlet_alias,
             inlet_policy_expression.clone(),
             None,
@@ -228,32 +261,77 @@ impl InMemoryNode {
                 self.project_authority().clone(),
                 Resource::new(interceptor_address.to_string(), ResourceType::TcpInlet),
                 Action::HandleMessage,
-                inlet_policy_expression,
+                inlet_policy_expression.clone(),
             )
             .await?;
 
         PortalInletInterceptor::create(
             context,
             interceptor_address.clone(),
-            Arc::new(KafkaInletInterceptorFactory::new(
-                secure_channel_controller,
-                inlet_controller,
-                encrypt_content,
-                encrypted_fields,
-            )),
+            Arc::new(
+                KafkaInletInterceptorFactory::new(
+                    secure_channel_controller,
+                    inlet_controller,
+                    encrypt_content,
+                    encrypted_fields.clone(),
+                    record_encryption,
+                    schema_registry,
+                    topic_policies,
+                )
+                .with_deterministic_key_secret(deterministic_key_secret),
+            ),
             Arc::new(policy_access_control.create_incoming()),
             Arc::new(policy_access_control.create_outgoing(context)?),
             read_portal_payload_length(),
         )?;
 
+        let schema_registry_address = match schema_registry_proxy {
+            Some(schema_registry_proxy) => {
+                let schema_registry_address =
+                    Address::from_string(format!("{}_schema_registry", interceptor_address));
+
+                self.create_inlet(
+                    context,
+                    schema_registry_proxy,
+                    route![schema_registry_address.clone()],
+                    route![KAFKA_OUTLET_SCHEMA_REGISTRY_ADDRESS],
+                    outlet_node_multiaddr,
+                    format!("kafka-schema-registry-{}", random_string()),
+                    inlet_policy_expression,
+                    None,
+                    None,
+                    true,
+                    None,
+                    false,
+                    false,
+                    false,
+                    None,
+                )
+                .await?;
+
+                PortalInletInterceptor::create(
+                    context,
+                    schema_registry_address.clone(),
+                    Arc::new(SchemaRegistryInterceptorFactory::new(encrypted_fields)),
+                    Arc::new(policy_access_control.create_incoming()),
+                    Arc::new(policy_access_control.create_outgoing(context)?),
+                    read_portal_payload_length(),
+                )?;
+                Some(schema_registry_address)
+            }
+            None => None,
+        };
+
         self.registry.kafka_services.insert(
             interceptor_address,
-            KafkaServiceInfo::new(KafkaServiceKind::Inlet),
+            KafkaServiceInfo::new(KafkaServiceKind::Inlet)
+                .with_schema_registry_address(schema_registry_address),
         );
 
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn start_kafka_outlet_service(
         &self,
         context: &Context,
@@ -261,6 +339,9 @@ impl InMemoryNode {
         bootstrap_server_addr: HostnamePort,
         tls: bool,
         outlet_policy_expression: Option<PolicyExpression>,
+        schema_registry_addr: Option<HostnamePort>,
+        schema_registry_tls: bool,
+        topic_policies: KafkaTopicPolicies,
     ) -> Result<()> {
         let default_secure_channel_listener_flow_control_id = context
             .flow_controls()
@@ -293,6 +374,11 @@ impl InMemoryNode {
             Arc::new(KafkaOutletInterceptorFactory::new(
                 outlet_controller.clone(),
                 spawner_flow_control_id.clone(),
+                Some(TopicAuthorization::new(
+                    topic_policies,
+                    self.cli_state.identities_attributes(&self.node_name),
+                    self.project_authority(),
+                )),
             )),
             Arc::new(policy_access_control.create_outgoing(context)?),
             Arc::new(policy_access_control.create_incoming()),
@@ -322,14 +408,34 @@ impl InMemoryNode {
             tls,
             Some(KAFKA_OUTLET_BOOTSTRAP_ADDRESS.into()),
             false,
-            OutletAccessControl::WithPolicyExpression(outlet_policy_expression),
+            OutletAccessControl::WithPolicyExpression(outlet_policy_expression.clone()),
             false,
         )
         .await?;
 
+        // the schema registry is reached directly by the inlets, which rewrite the schemas
+        let schema_registry_address = match schema_registry_addr {
+            Some(schema_registry_addr) => {
+                let address: Address = KAFKA_OUTLET_SCHEMA_REGISTRY_ADDRESS.into();
+                self.create_outlet(
+                    context,
+                    schema_registry_addr,
+                    schema_registry_tls,
+                    Some(address.clone()),
+                    true,
+                    OutletAccessControl::WithPolicyExpression(outlet_policy_expression),
+                    false,
+                )
+                .await?;
+                Some(address)
+            }
+            None => None,
+        };
+
         self.registry.kafka_services.insert(
             service_address,
-            KafkaServiceInfo::new(KafkaServiceKind::Outlet),
+            KafkaServiceInfo::new(KafkaServiceKind::Outlet)
+                .with_schema_registry_address(schema_registry_address),
         );
 
         Ok(())
@@ -357,6 +463,9 @@ impl InMemoryNode {
                             ctx.stop_address(&KAFKA_OUTLET_BOOTSTRAP_ADDRESS.into())?;
                         }
                     }
+                    if let Some(schema_registry_address) = e.schema_registry_address() {
+                        ctx.stop_address(schema_registry_address)?;
+                    }
                     self.registry.kafka_services.remove(&address);
                     Ok(DeleteKafkaServiceResult::ServiceDeleted)
                 } else {
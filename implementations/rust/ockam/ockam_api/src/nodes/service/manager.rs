@@ -400,6 +400,8 @@ impl NodeManager {
                     None,
                     info.privileged,
                 )
+                .with_unix_socket_path(info.unix_socket_path.clone())
+                .with_allowed_destinations(info.allowed_destinations.clone())
             })
             .collect()
     }
@@ -0,0 +1,86 @@
+use ockam::identity::Identifier;
+use ockam_abac::PolicyExpression;
+use ockam_core::api::{Reply, Request};
+use ockam_core::async_trait;
+use ockam_node::Context;
+use ockam_transport_core::HostnamePort;
+use std::time::Duration;
+
+use crate::nodes::models::portal::{CreateProxyInlet, ProxyInletStatus, ProxyRoute};
+use crate::nodes::BackgroundNodeClient;
+
+#[async_trait]
+pub trait ProxyInlets {
+    #[allow(clippy::too_many_arguments)]
+    async fn create_proxy_inlet(
+        &self,
+        ctx: &Context,
+        listen_addr: &HostnamePort,
+        alias: &str,
+        routes: Vec<ProxyRoute>,
+        route_template: Option<String>,
+        authorized_identifier: &Option<Identifier>,
+        policy_expression: &Option<PolicyExpression>,
+        connect_timeout: Option<Duration>,
+        forward_destination: bool,
+    ) -> miette::Result<Reply<ProxyInletStatus>>;
+
+    async fn show_proxy_inlet(
+        &self,
+        ctx: &Context,
+        alias: &str,
+    ) -> miette::Result<Reply<ProxyInletStatus>>;
+
+    async fn delete_proxy_inlet(&self, ctx: &Context, alias: &str) -> miette::Result<Reply<()>>;
+}
+
+#[async_trait]
+impl ProxyInlets for BackgroundNodeClient {
+    async fn create_proxy_inlet(
+        &self,
+        ctx: &Context,
+        listen_addr: &HostnamePort,
+        alias: &str,
+        routes: Vec<ProxyRoute>,
+        route_template: Option<String>,
+        authorized_identifier: &Option<Identifier>,
+        policy_expression: &Option<PolicyExpression>,
+        connect_timeout: Option<Duration>,
+        forward_destination: bool,
+    ) -> miette::Result<Reply<ProxyInletStatus>> {
+        let mut payload = CreateProxyInlet::new(
+            listen_addr.clone(),
+            alias.into(),
+            authorized_identifier.clone(),
+        );
+        for route in routes {
+            payload.add_route(route);
+        }
+        if let Some(route_template) = route_template {
+            payload.set_route_template(route_template)
+        }
+        if let Some(e) = policy_expression.as_ref() {
+            payload.set_policy_expression(e.clone())
+        }
+        if let Some(connect_timeout) = connect_timeout {
+            payload.set_connect_timeout(connect_timeout)
+        }
+        payload.set_forward_destination(forward_destination);
+        let request = Request::post("/node/proxy_inlet").body(payload);
+        self.ask_and_get_reply(ctx, request).await
+    }
+
+    async fn show_proxy_inlet(
+        &self,
+        ctx: &Context,
+        alias: &str,
+    ) -> miette::Result<Reply<ProxyInletStatus>> {
+        let request = Request::get(format!("/node/proxy_inlet/{alias}"));
+        self.ask_and_get_reply(ctx, request).await
+    }
+
+    async fn delete_proxy_inlet(&self, ctx: &Context, alias: &str) -> miette::Result<Reply<()>> {
+        let request = Request::delete(format!("/node/proxy_inlet/{alias}"));
+        self.tell_and_get_reply(ctx, request).await
+    }
+}
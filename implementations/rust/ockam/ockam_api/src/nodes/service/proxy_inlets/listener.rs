@@ -0,0 +1,305 @@
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use ockam::identity::Identifier;
+use ockam::tcp::TcpInletOptions;
+use ockam::transport::HostnamePort;
+use ockam::Result;
+use ockam_abac::PolicyAccessControl;
+use ockam_core::compat::collections::HashMap;
+use ockam_core::{
+    async_trait, AllowAll, IncomingAccessControl, OutgoingAccessControl, Processor,
+    SecureChannelMetadata, TryClone,
+};
+use ockam_multiaddr::MultiAddr;
+use ockam_node::Context;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::nodes::connection::Connection;
+use crate::nodes::service::proxy_inlets::protocol::{
+    ProxyDestination, ProxyFailure, ProxyProtocol,
+};
+use crate::nodes::service::proxy_inlets::routes::{ProxyRoutes, ResolvedRoute};
+use crate::nodes::NodeManager;
+
+/// Maximum time given to a client to send its proxy request.
+/// Clients which connect without sending a complete request are disconnected
+const PROXY_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// State shared by the connections accepted by a proxy inlet
+pub(super) struct ProxyInletState {
+    pub(super) node_manager: Weak<NodeManager>,
+    pub(super) context: Context,
+    pub(super) alias: String,
+    pub(super) routes: ProxyRoutes,
+    /// Access control of each route, None if the route is not restricted
+    pub(super) routes_access_control: Vec<Option<PolicyAccessControl>>,
+    /// Access control of the destinations served by the route template
+    pub(super) template_access_control: Option<PolicyAccessControl>,
+    pub(super) authorized: Option<Identifier>,
+    pub(super) connect_timeout: Duration,
+    /// Send the requested destination to the outlets
+    pub(super) forward_destination: bool,
+    /// Connections to the outlets, indexed by outlet address
+    pub(super) connections: Mutex<HashMap<String, Connection>>,
+}
+
+impl ProxyInletState {
+    /// Negotiate the destination with the client, then create a portal to the outlet
+    /// serving that destination
+    async fn serve(self: Arc<Self>, mut stream: TcpStream) {
+        let request = tokio::time::timeout(
+            PROXY_REQUEST_TIMEOUT,
+            ProxyProtocol::read_request(&mut stream),
+        )
+        .await;
+        let (protocol, destination) = match request {
+            Ok(Ok(request)) => request,
+            Err(_) => {
+                debug!(alias = %self.alias, "the proxy request was not received in time");
+                return;
+            }
+            Ok(Err((protocol, failure))) => {
+                debug!(alias = %self.alias, ?failure, "invalid proxy request");
+                if let Some(protocol) = protocol {
+                    let _ = stream.write_all(&protocol.failed_reply(failure)).await;
+                }
+                return;
+            }
+        };
+
+        if let Err(failure) = self.connect(stream, protocol, &destination).await {
+            info!(alias = %self.alias, %destination, ?failure, "proxy request failed");
+        }
+    }
+
+    async fn connect(
+        &self,
+        mut stream: TcpStream,
+        protocol: ProxyProtocol,
+        destination: &ProxyDestination,
+    ) -> std::result::Result<(), ProxyFailure> {
+        let resolved = match self.routes.resolve(destination) {
+            Some(resolved) => resolved,
+            None => return Self::fail(&mut stream, protocol, ProxyFailure::NoRoute).await,
+        };
+        let node_manager = match self.node_manager.upgrade() {
+            Some(node_manager) => node_manager,
+            None => return Self::fail(&mut stream, protocol, ProxyFailure::Unreachable).await,
+        };
+
+        let connection = match self.connection(&node_manager, &resolved.outlet_addr).await {
+            Ok(connection) => connection,
+            Err(err) => {
+                warn!(alias = %self.alias, %destination, %err, "could not connect to the outlet");
+                return Self::fail(&mut stream, protocol, ProxyFailure::Unreachable).await;
+            }
+        };
+        let outlet_route = match connection.route() {
+            Ok(route) => route,
+            Err(_) => return Self::fail(&mut stream, protocol, ProxyFailure::Unreachable).await,
+        };
+
+        let (incoming_ac, outgoing_ac) = match self.authorize(&resolved, &connection).await {
+            Ok(access_control) => access_control,
+            Err(failure) => return Self::fail(&mut stream, protocol, failure).await,
+        };
+        let mut options = TcpInletOptions::new()
+            .with_incoming_access_control(incoming_ac)
+            .with_outgoing_access_control(outgoing_ac);
+        if self.forward_destination {
+            match HostnamePort::from_str(&destination.to_string()) {
+                Ok(destination) => options = options.with_outlet_destination(destination),
+                Err(_) => return Self::fail(&mut stream, protocol, ProxyFailure::BadRequest).await,
+            }
+        }
+
+        let inlet_stream = match node_manager.tcp_transport.create_inlet_stream(
+            stream,
+            outlet_route,
+            protocol.connected_reply(),
+            protocol.failed_reply(ProxyFailure::Unreachable),
+            options,
+        ) {
+            Ok(inlet_stream) => inlet_stream,
+            Err(err) => {
+                warn!(alias = %self.alias, %destination, %err, "could not create the portal");
+                self.drop_connection(&node_manager, &resolved.outlet_addr);
+                return Err(ProxyFailure::Unreachable);
+            }
+        };
+
+        // the failure reply is written by the portal itself from now on
+        if inlet_stream
+            .wait_connected(&self.context, self.connect_timeout)
+            .await
+            .is_err()
+        {
+            self.drop_connection(&node_manager, &resolved.outlet_addr);
+            return Err(ProxyFailure::Unreachable);
+        }
+        debug!(alias = %self.alias, %destination, outlet = %resolved.outlet_addr, "proxy portal created");
+        Ok(())
+    }
+
+    async fn fail(
+        stream: &mut TcpStream,
+        protocol: ProxyProtocol,
+        failure: ProxyFailure,
+    ) -> std::result::Result<(), ProxyFailure> {
+        let _ = stream.write_all(&protocol.failed_reply(failure)).await;
+        Err(failure)
+    }
+
+    /// Return the connection to an outlet, creating it if necessary
+    async fn connection(
+        &self,
+        node_manager: &NodeManager,
+        outlet_addr: &MultiAddr,
+    ) -> Result<Connection> {
+        let key = outlet_addr.to_string();
+        if let Some(connection) = self.connections.lock().unwrap().get(&key) {
+            return Ok(connection.clone());
+        }
+
+        // each connection attempt gets its own context since they run concurrently
+        let context = self.context.try_clone()?;
+        let connection = node_manager
+            .make_connection(
+                &context,
+                outlet_addr,
+                node_manager.identifier(),
+                self.authorized.clone(),
+                Some(self.connect_timeout),
+            )
+            .await?;
+
+        let mut connections = self.connections.lock().unwrap();
+        match connections.get(&key) {
+            // another client connected to the same outlet in the meantime
+            Some(existing) => {
+                let _ = connection.close(&self.context, node_manager);
+                Ok(existing.clone())
+            }
+            None => {
+                connections.insert(key, connection.clone());
+                Ok(connection)
+            }
+        }
+    }
+
+    /// Close a connection which doesn't reach its outlet anymore
+    fn drop_connection(&self, node_manager: &NodeManager, outlet_addr: &MultiAddr) {
+        let connection = self
+            .connections
+            .lock()
+            .unwrap()
+            .remove(&outlet_addr.to_string());
+        if let Some(connection) = connection {
+            let _ = connection.close(&self.context, node_manager);
+        }
+    }
+
+    /// Close all the connections to the outlets
+    pub(super) fn close_connections(&self, node_manager: &NodeManager) {
+        let connections: Vec<Connection> = self
+            .connections
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_, connection)| connection)
+            .collect();
+        for connection in connections {
+            let _ = connection.close(&self.context, node_manager);
+        }
+    }
+
+    /// Check that the outlet serving a destination is allowed by the policy of the destination,
+    /// and return the access controls of the portal
+    async fn authorize(
+        &self,
+        resolved: &ResolvedRoute,
+        connection: &Connection,
+    ) -> std::result::Result<
+        (
+            Arc<dyn IncomingAccessControl>,
+            Arc<dyn OutgoingAccessControl>,
+        ),
+        ProxyFailure,
+    > {
+        let policy_access_control = match resolved.route_index {
+            Some(index) => self.routes_access_control[index].as_ref(),
+            None => self.template_access_control.as_ref(),
+        };
+        let policy_access_control = match policy_access_control {
+            Some(policy_access_control) => policy_access_control,
+            None => return Ok((Arc::new(AllowAll), Arc::new(AllowAll))),
+        };
+
+        let route = connection.route().map_err(|_| ProxyFailure::Unreachable)?;
+        let their_identifier = self
+            .context
+            .find_terminal_address(route.iter())
+            .ok()
+            .flatten()
+            .and_then(|(_, metadata)| {
+                SecureChannelMetadata::from_terminal_address_metadata(&metadata).ok()
+            })
+            .map(|metadata| Identifier::from(metadata.their_identifier()))
+            .ok_or(ProxyFailure::Denied)?;
+
+        match policy_access_control
+            .is_identity_authorized(&their_identifier)
+            .await
+        {
+            Ok(true) => {}
+            _ => {
+                info!(alias = %self.alias, %their_identifier, "the outlet identity is not authorized");
+                return Err(ProxyFailure::Denied);
+            }
+        }
+
+        let outgoing_ac = policy_access_control
+            .create_outgoing(&self.context)
+            .map_err(|_| ProxyFailure::Unreachable)?;
+        Ok((
+            Arc::new(policy_access_control.create_incoming()),
+            Arc::new(outgoing_ac),
+        ))
+    }
+}
+
+/// Accept the connections of the clients of a proxy inlet
+pub(super) struct ProxyInletListener {
+    pub(super) tcp_listener: TcpListener,
+    pub(super) state: Arc<ProxyInletState>,
+}
+
+#[async_trait]
+impl Processor for ProxyInletListener {
+    type Context = Context;
+
+    async fn shutdown(&mut self, _context: &mut Self::Context) -> Result<()> {
+        debug!(alias = %self.state.alias, "shutting down the proxy inlet listener");
+        if let Some(node_manager) = self.state.node_manager.upgrade() {
+            self.state.close_connections(&node_manager);
+        }
+        Ok(())
+    }
+
+    async fn process(&mut self, _context: &mut Self::Context) -> Result<bool> {
+        match self.tcp_listener.accept().await {
+            Ok((stream, peer)) => {
+                debug!(alias = %self.state.alias, %peer, "new proxy client");
+                let _ = stream.set_nodelay(true);
+                tokio::spawn(self.state.clone().serve(stream));
+            }
+            Err(err) => {
+                warn!(alias = %self.state.alias, %err, "could not accept a proxy client");
+            }
+        }
+        Ok(true)
+    }
+}
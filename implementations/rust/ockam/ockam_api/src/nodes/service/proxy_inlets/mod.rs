@@ -0,0 +1,8 @@
+mod background_node_client;
+mod listener;
+mod node_manager;
+mod node_manager_worker;
+mod protocol;
+mod routes;
+
+pub use background_node_client::*;
@@ -0,0 +1,223 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ockam::identity::Identifier;
+use ockam::Result;
+use ockam_abac::{Action, PolicyAccessControl, PolicyExpression, Resource, ResourceType};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Address, TryClone};
+use ockam_multiaddr::MultiAddr;
+use ockam_node::{Context, ProcessorBuilder};
+use ockam_transport_core::{HostnamePort, TransportError};
+use tokio::net::TcpListener;
+
+use crate::nodes::models::portal::{ProxyInletStatus, ProxyRoute};
+use crate::nodes::registry::ProxyInletInfo;
+use crate::nodes::service::proxy_inlets::listener::{ProxyInletListener, ProxyInletState};
+use crate::nodes::service::proxy_inlets::routes::ProxyRoutes;
+use crate::nodes::NodeManager;
+use crate::session::replacer::MAX_CONNECT_TIME;
+
+impl NodeManager {
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip_all)]
+    pub async fn create_proxy_inlet(
+        self: &Arc<Self>,
+        ctx: &Context,
+        listen_address: HostnamePort,
+        alias: String,
+        routes: Vec<ProxyRoute>,
+        route_template: Option<String>,
+        authorized: Option<Identifier>,
+        policy_expression: Option<PolicyExpression>,
+        connect_timeout: Option<Duration>,
+        forward_destination: bool,
+    ) -> Result<ProxyInletStatus> {
+        debug! {
+            %listen_address,
+            %alias,
+            "creating proxy inlet"
+        }
+
+        if routes.is_empty() && route_template.is_none() {
+            return Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::Invalid,
+                "A proxy inlet needs at least one route or a route template",
+            ));
+        }
+        let proxy_routes = ProxyRoutes::create(&routes, route_template.clone())?;
+
+        // Check that there is no entry in the registry with the same alias
+        if self.registry.proxy_inlets.contains_key(&alias) {
+            let message = format!("A proxy inlet with alias '{alias}' already exists");
+            return Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::AlreadyExists,
+                message,
+            ));
+        }
+
+        // Each route is a distinct resource, so that destinations can have their own policy
+        let mut resource_names = vec![];
+        let mut routes_access_control = vec![];
+        for route in &routes {
+            let resource_name = format!("{alias}/{}", route.destination);
+            let access_control = self
+                .proxy_access_control(
+                    &route.outlet_addr,
+                    &resource_name,
+                    route
+                        .policy_expression
+                        .clone()
+                        .or_else(|| policy_expression.clone()),
+                )
+                .await?;
+            resource_names.push(resource_name);
+            routes_access_control.push(access_control);
+        }
+        let template_access_control = match &route_template {
+            Some(route_template) => {
+                resource_names.push(alias.clone());
+                self.proxy_access_control(
+                    &ProxyRoutes::template_sample(route_template)?,
+                    &alias,
+                    policy_expression.clone(),
+                )
+                .await?
+            }
+            None => None,
+        };
+
+        let socket_addr = ockam_node::compat::asynchronous::resolve_peer(&listen_address).await?;
+        let tcp_listener = TcpListener::bind(socket_addr)
+            .await
+            .map_err(TransportError::from)?;
+        let bind_addr = tcp_listener
+            .local_addr()
+            .map_err(TransportError::from)?
+            .to_string();
+
+        let state = ProxyInletState {
+            node_manager: Arc::downgrade(self),
+            context: ctx.try_clone()?,
+            alias: alias.clone(),
+            routes: proxy_routes,
+            routes_access_control,
+            template_access_control,
+            authorized,
+            connect_timeout: connect_timeout.unwrap_or(MAX_CONNECT_TIME),
+            forward_destination,
+            connections: Mutex::new(Default::default()),
+        };
+        let processor_address = Address::random_tagged("ProxyInletListener");
+        ProcessorBuilder::new(ProxyInletListener {
+            tcp_listener,
+            state: Arc::new(state),
+        })
+        .with_address(processor_address.clone())
+        .start(ctx)?;
+
+        self.registry.proxy_inlets.insert(
+            alias.clone(),
+            ProxyInletInfo {
+                bind_addr: bind_addr.clone(),
+                processor_address,
+                routes: routes.clone(),
+                route_template: route_template.clone(),
+                resource_names,
+                forward_destination,
+            },
+        );
+
+        info! {
+            %bind_addr,
+            %alias,
+            "proxy inlet created"
+        }
+
+        Ok(
+            ProxyInletStatus::new(bind_addr, alias, routes, route_template)
+                .with_forward_destination(forward_destination),
+        )
+    }
+
+    /// Return the access control of a destination,
+    /// or None if the destination has neither an authority nor a policy expression
+    async fn proxy_access_control(
+        &self,
+        outlet_addr: &MultiAddr,
+        resource_name: &str,
+        policy_expression: Option<PolicyExpression>,
+    ) -> Result<Option<PolicyAccessControl>> {
+        let authority = self.outlet_authority(outlet_addr).await?;
+        if authority.is_none() && policy_expression.is_none() {
+            warn! {
+                resource_name,
+                "no policy access control set"
+            }
+            return Ok(None);
+        }
+        self.policy_access_control(
+            authority,
+            Resource::new(resource_name, ResourceType::TcpInlet),
+            Action::HandleMessage,
+            policy_expression,
+        )
+        .await
+        .map(Some)
+    }
+
+    pub async fn delete_proxy_inlet(&self, ctx: &Context, alias: &str) -> Result<ProxyInletStatus> {
+        info!(%alias, "Handling request to delete proxy inlet");
+        if let Some(inlet_to_delete) = self.registry.proxy_inlets.remove(alias) {
+            debug!(%alias, "Successfully removed proxy inlet from node registry");
+            // the listener closes the connections to the outlets when it is stopped
+            ctx.stop_address(&inlet_to_delete.processor_address)?;
+            for resource_name in &inlet_to_delete.resource_names {
+                self.resources()
+                    .delete_resource(&resource_name.as_str().into())
+                    .await?;
+            }
+            Ok(ProxyInletStatus::new(
+                inlet_to_delete.bind_addr,
+                alias,
+                inlet_to_delete.routes,
+                inlet_to_delete.route_template,
+            )
+            .with_forward_destination(inlet_to_delete.forward_destination))
+        } else {
+            error!(%alias, "Proxy inlet not found in the node registry");
+            let message = format!("Proxy inlet with alias {alias} not found");
+            Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::NotFound,
+                message,
+            ))
+        }
+    }
+
+    pub fn show_proxy_inlet(&self, alias: &str) -> Option<ProxyInletStatus> {
+        match self.registry.proxy_inlets.get(alias) {
+            Some(info) => Some(Self::proxy_inlet_status(alias, info)),
+            None => {
+                error!(%alias, "Proxy inlet not found in the node registry");
+                None
+            }
+        }
+    }
+
+    pub fn list_proxy_inlets(&self) -> Vec<ProxyInletStatus> {
+        self.registry
+            .proxy_inlets
+            .entries()
+            .into_iter()
+            .map(|(alias, info)| Self::proxy_inlet_status(&alias, info))
+            .collect()
+    }
+
+    fn proxy_inlet_status(alias: &str, info: ProxyInletInfo) -> ProxyInletStatus {
+        ProxyInletStatus::new(info.bind_addr, alias, info.routes, info.route_template)
+            .with_forward_destination(info.forward_destination)
+    }
+}
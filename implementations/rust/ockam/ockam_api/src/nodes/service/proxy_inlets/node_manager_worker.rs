@@ -0,0 +1,73 @@
+use ockam::Result;
+use ockam_core::api::{Error, Response};
+use ockam_node::Context;
+
+use crate::nodes::models::portal::{CreateProxyInlet, ProxyInletStatus};
+use crate::nodes::NodeManagerWorker;
+
+impl NodeManagerWorker {
+    pub(crate) fn get_proxy_inlets(
+        &self,
+    ) -> Result<Response<Vec<ProxyInletStatus>>, Response<Error>> {
+        Ok(Response::ok().body(self.node_manager.list_proxy_inlets()))
+    }
+
+    #[instrument(skip_all)]
+    pub(crate) async fn create_proxy_inlet(
+        &self,
+        ctx: &Context,
+        create_inlet: CreateProxyInlet,
+    ) -> Result<Response<ProxyInletStatus>, Response<Error>> {
+        let CreateProxyInlet {
+            listen_addr,
+            alias,
+            routes,
+            route_template,
+            authorized,
+            policy_expression,
+            connect_timeout,
+            forward_destination,
+        } = create_inlet;
+        match self
+            .node_manager
+            .create_proxy_inlet(
+                ctx,
+                listen_addr,
+                alias,
+                routes,
+                route_template,
+                authorized,
+                policy_expression,
+                connect_timeout,
+                forward_destination,
+            )
+            .await
+        {
+            Ok(status) => Ok(Response::ok().body(status)),
+            Err(e) => Err(Response::bad_request_no_request(&format!("{e:?}"))),
+        }
+    }
+
+    pub(crate) async fn delete_proxy_inlet(
+        &self,
+        ctx: &Context,
+        alias: &str,
+    ) -> Result<Response<ProxyInletStatus>, Response<Error>> {
+        match self.node_manager.delete_proxy_inlet(ctx, alias).await {
+            Ok(status) => Ok(Response::ok().body(status)),
+            Err(e) => Err(Response::bad_request_no_request(&format!("{e:?}"))),
+        }
+    }
+
+    pub(crate) fn show_proxy_inlet(
+        &self,
+        alias: &str,
+    ) -> Result<Response<ProxyInletStatus>, Response<Error>> {
+        match self.node_manager.show_proxy_inlet(alias) {
+            Some(inlet) => Ok(Response::ok().body(inlet)),
+            None => Err(Response::not_found_no_request(&format!(
+                "Proxy inlet with alias {alias} not found"
+            ))),
+        }
+    }
+}
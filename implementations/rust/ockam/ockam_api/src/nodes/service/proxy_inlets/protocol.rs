@@ -0,0 +1,351 @@
+use std::fmt::{Display, Formatter};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const SOCKS5_VERSION: u8 = 0x05;
+const SOCKS5_NO_AUTHENTICATION: u8 = 0x00;
+const SOCKS5_NO_ACCEPTABLE_METHOD: u8 = 0xFF;
+const SOCKS5_CONNECT: u8 = 0x01;
+const SOCKS5_IPV4: u8 = 0x01;
+const SOCKS5_DOMAIN_NAME: u8 = 0x03;
+const SOCKS5_IPV6: u8 = 0x04;
+
+/// Maximum size of the headers of an HTTP CONNECT request
+const MAX_HTTP_HEADERS_LENGTH: usize = 8192;
+
+/// The protocol used by a client to request a destination to a proxy inlet
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ProxyProtocol {
+    Socks5,
+    HttpConnect,
+}
+
+/// A destination requested by a client
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ProxyDestination {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+}
+
+impl ProxyDestination {
+    pub(crate) fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into().to_lowercase(),
+            port,
+        }
+    }
+}
+
+impl Display for ProxyDestination {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// The reasons why a proxy request can fail
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ProxyFailure {
+    /// The request is malformed
+    BadRequest,
+    /// The client doesn't support unauthenticated SOCKS connections
+    NoAcceptableAuthentication,
+    /// The request is not a CONNECT request
+    CommandNotSupported,
+    /// The SOCKS address type is unknown
+    AddressTypeNotSupported,
+    /// No outlet serves the destination
+    NoRoute,
+    /// The outlet serving the destination is not available
+    Unreachable,
+    /// The outlet serving the destination is not allowed by the destination policy
+    Denied,
+}
+
+impl ProxyProtocol {
+    /// Read the request of a client and return the requested destination
+    pub(crate) async fn read_request<S: AsyncRead + AsyncWrite + Unpin>(
+        stream: &mut S,
+    ) -> Result<(ProxyProtocol, ProxyDestination), (Option<ProxyProtocol>, ProxyFailure)> {
+        let first_byte = stream
+            .read_u8()
+            .await
+            .map_err(|_| (None, ProxyFailure::BadRequest))?;
+        if first_byte == SOCKS5_VERSION {
+            let destination = Self::read_socks5_request(stream)
+                .await
+                .map_err(|failure| (Some(ProxyProtocol::Socks5), failure))?;
+            Ok((ProxyProtocol::Socks5, destination))
+        } else {
+            let destination = Self::read_http_connect_request(stream, first_byte)
+                .await
+                .map_err(|failure| (Some(ProxyProtocol::HttpConnect), failure))?;
+            Ok((ProxyProtocol::HttpConnect, destination))
+        }
+    }
+
+    /// Reply sent to the client once the outlet is connected
+    pub(crate) fn connected_reply(&self) -> Vec<u8> {
+        match self {
+            ProxyProtocol::Socks5 => Self::socks5_reply(0x00),
+            ProxyProtocol::HttpConnect => b"HTTP/1.1 200 Connection established\r\n\r\n".to_vec(),
+        }
+    }
+
+    /// Reply sent to the client when its request fails
+    pub(crate) fn failed_reply(&self, failure: ProxyFailure) -> Vec<u8> {
+        match self {
+            ProxyProtocol::Socks5 => match failure {
+                ProxyFailure::NoAcceptableAuthentication => {
+                    vec![SOCKS5_VERSION, SOCKS5_NO_ACCEPTABLE_METHOD]
+                }
+                ProxyFailure::BadRequest => Self::socks5_reply(0x01),
+                ProxyFailure::Denied => Self::socks5_reply(0x02),
+                ProxyFailure::NoRoute => Self::socks5_reply(0x04),
+                ProxyFailure::Unreachable => Self::socks5_reply(0x05),
+                ProxyFailure::CommandNotSupported => Self::socks5_reply(0x07),
+                ProxyFailure::AddressTypeNotSupported => Self::socks5_reply(0x08),
+            },
+            ProxyProtocol::HttpConnect => {
+                let status = match failure {
+                    ProxyFailure::BadRequest
+                    | ProxyFailure::NoAcceptableAuthentication
+                    | ProxyFailure::AddressTypeNotSupported => "400 Bad Request",
+                    ProxyFailure::Denied => "403 Forbidden",
+                    ProxyFailure::NoRoute => "404 Not Found",
+                    ProxyFailure::CommandNotSupported => "405 Method Not Allowed",
+                    ProxyFailure::Unreachable => "502 Bad Gateway",
+                };
+                format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                    .into_bytes()
+            }
+        }
+    }
+
+    /// SOCKS5 reply, the bound address is not meaningful for a portal and is left empty
+    fn socks5_reply(code: u8) -> Vec<u8> {
+        vec![SOCKS5_VERSION, code, 0x00, SOCKS5_IPV4, 0, 0, 0, 0, 0, 0]
+    }
+
+    /// Read a SOCKS5 greeting, without its first byte, and request, as specified in RFC 1928
+    async fn read_socks5_request<S: AsyncRead + AsyncWrite + Unpin>(
+        stream: &mut S,
+    ) -> Result<ProxyDestination, ProxyFailure> {
+        let bad_request = |_| ProxyFailure::BadRequest;
+
+        let methods_count = stream.read_u8().await.map_err(bad_request)?;
+        let mut methods = vec![0u8; methods_count as usize];
+        stream.read_exact(&mut methods).await.map_err(bad_request)?;
+        if !methods.contains(&SOCKS5_NO_AUTHENTICATION) {
+            return Err(ProxyFailure::NoAcceptableAuthentication);
+        }
+        stream
+            .write_all(&[SOCKS5_VERSION, SOCKS5_NO_AUTHENTICATION])
+            .await
+            .map_err(bad_request)?;
+
+        let mut header = [0u8; 4];
+        stream.read_exact(&mut header).await.map_err(bad_request)?;
+        let [version, command, _reserved, address_type] = header;
+        if version != SOCKS5_VERSION {
+            return Err(ProxyFailure::BadRequest);
+        }
+
+        let host = match address_type {
+            SOCKS5_IPV4 => {
+                let mut ip = [0u8; 4];
+                stream.read_exact(&mut ip).await.map_err(bad_request)?;
+                Ipv4Addr::from(ip).to_string()
+            }
+            SOCKS5_IPV6 => {
+                let mut ip = [0u8; 16];
+                stream.read_exact(&mut ip).await.map_err(bad_request)?;
+                Ipv6Addr::from(ip).to_string()
+            }
+            SOCKS5_DOMAIN_NAME => {
+                let length = stream.read_u8().await.map_err(bad_request)?;
+                let mut domain = vec![0u8; length as usize];
+                stream.read_exact(&mut domain).await.map_err(bad_request)?;
+                String::from_utf8(domain).map_err(|_| ProxyFailure::BadRequest)?
+            }
+            _ => return Err(ProxyFailure::AddressTypeNotSupported),
+        };
+        let port = stream.read_u16().await.map_err(bad_request)?;
+
+        // The whole request is read before rejecting the command, to be able to reply
+        if command != SOCKS5_CONNECT {
+            return Err(ProxyFailure::CommandNotSupported);
+        }
+        Ok(ProxyDestination::new(host, port))
+    }
+
+    /// Read the headers of an HTTP CONNECT request, given its first byte.
+    /// The headers are read one byte at a time to leave any data sent by the client after
+    /// them in the stream.
+    async fn read_http_connect_request<S: AsyncRead + Unpin>(
+        stream: &mut S,
+        first_byte: u8,
+    ) -> Result<ProxyDestination, ProxyFailure> {
+        let mut headers = vec![first_byte];
+        while !headers.ends_with(b"\r\n\r\n") {
+            if headers.len() >= MAX_HTTP_HEADERS_LENGTH {
+                return Err(ProxyFailure::BadRequest);
+            }
+            let byte = stream
+                .read_u8()
+                .await
+                .map_err(|_| ProxyFailure::BadRequest)?;
+            headers.push(byte);
+        }
+
+        let headers = std::str::from_utf8(&headers).map_err(|_| ProxyFailure::BadRequest)?;
+        let request_line = headers.lines().next().unwrap_or_default();
+        let mut parts = request_line.split_whitespace();
+        let (method, target, version) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(method), Some(target), Some(version)) => (method, target, version),
+            _ => return Err(ProxyFailure::BadRequest),
+        };
+        if !version.starts_with("HTTP/") {
+            return Err(ProxyFailure::BadRequest);
+        }
+        if !method.eq_ignore_ascii_case("CONNECT") {
+            return Err(ProxyFailure::CommandNotSupported);
+        }
+
+        let (host, port) = target.rsplit_once(':').ok_or(ProxyFailure::BadRequest)?;
+        let host = host
+            .strip_prefix('[')
+            .and_then(|h| h.strip_suffix(']'))
+            .unwrap_or(host);
+        let port = port.parse().map_err(|_| ProxyFailure::BadRequest)?;
+        if host.is_empty() {
+            return Err(ProxyFailure::BadRequest);
+        }
+        Ok(ProxyDestination::new(host, port))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::duplex;
+
+    async fn read_request(
+        request: &[u8],
+    ) -> (
+        Result<(ProxyProtocol, ProxyDestination), (Option<ProxyProtocol>, ProxyFailure)>,
+        Vec<u8>,
+    ) {
+        let (mut client, mut server) = duplex(1024);
+        client.write_all(request).await.unwrap();
+        let result = ProxyProtocol::read_request(&mut server).await;
+        drop(server);
+        let mut written = vec![];
+        client.read_to_end(&mut written).await.unwrap();
+        (result, written)
+    }
+
+    #[tokio::test]
+    async fn socks5_domain_name_request() {
+        let mut request = vec![0x05, 0x01, 0x00, 0x05, 0x01, 0x00, 0x03, 11];
+        request.extend_from_slice(b"DB.internal");
+        request.extend_from_slice(&5432u16.to_be_bytes());
+
+        let (result, written) = read_request(&request).await;
+        assert_eq!(
+            result.unwrap(),
+            (
+                ProxyProtocol::Socks5,
+                ProxyDestination::new("db.internal", 5432)
+            )
+        );
+        assert_eq!(written, vec![0x05, 0x00]);
+    }
+
+    #[tokio::test]
+    async fn socks5_ipv6_request() {
+        let mut request = vec![0x05, 0x01, 0x00, 0x05, 0x01, 0x00, 0x04];
+        request.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+        request.extend_from_slice(&443u16.to_be_bytes());
+
+        let (result, _) = read_request(&request).await;
+        let (_, destination) = result.unwrap();
+        assert_eq!(destination.to_string(), "[::1]:443");
+    }
+
+    #[tokio::test]
+    async fn socks5_unsupported_requests() {
+        // Only username/password authentication is offered
+        let (result, _) = read_request(&[0x05, 0x01, 0x02]).await;
+        assert_eq!(
+            result.unwrap_err(),
+            (
+                Some(ProxyProtocol::Socks5),
+                ProxyFailure::NoAcceptableAuthentication
+            )
+        );
+
+        // BIND command
+        let mut request = vec![0x05, 0x01, 0x00, 0x05, 0x02, 0x00, 0x01, 127, 0, 0, 1];
+        request.extend_from_slice(&80u16.to_be_bytes());
+        let (result, _) = read_request(&request).await;
+        assert_eq!(
+            result.unwrap_err(),
+            (
+                Some(ProxyProtocol::Socks5),
+                ProxyFailure::CommandNotSupported
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn http_connect_request() {
+        let (result, written) =
+            read_request(b"CONNECT db.internal:5432 HTTP/1.1\r\nHost: db.internal:5432\r\n\r\n")
+                .await;
+        assert_eq!(
+            result.unwrap(),
+            (
+                ProxyProtocol::HttpConnect,
+                ProxyDestination::new("db.internal", 5432)
+            )
+        );
+        assert!(written.is_empty());
+
+        let (result, _) = read_request(b"CONNECT [::1]:443 HTTP/1.1\r\n\r\n").await;
+        assert_eq!(result.unwrap().1, ProxyDestination::new("::1", 443));
+    }
+
+    #[tokio::test]
+    async fn http_unsupported_requests() {
+        let (result, _) = read_request(b"GET http://db.internal/ HTTP/1.1\r\n\r\n").await;
+        assert_eq!(
+            result.unwrap_err(),
+            (
+                Some(ProxyProtocol::HttpConnect),
+                ProxyFailure::CommandNotSupported
+            )
+        );
+
+        let (result, _) = read_request(b"CONNECT db.internal HTTP/1.1\r\n\r\n").await;
+        assert_eq!(
+            result.unwrap_err(),
+            (Some(ProxyProtocol::HttpConnect), ProxyFailure::BadRequest)
+        );
+    }
+
+    #[test]
+    fn failed_replies() {
+        assert_eq!(
+            ProxyProtocol::Socks5.failed_reply(ProxyFailure::Denied),
+            vec![0x05, 0x02, 0x00, 0x01, 0, 0, 0, 0, 0, 0]
+        );
+        assert!(ProxyProtocol::HttpConnect
+            .failed_reply(ProxyFailure::Unreachable)
+            .starts_with(b"HTTP/1.1 502 Bad Gateway\r\n"));
+    }
+}
@@ -0,0 +1,270 @@
+use std::str::FromStr;
+
+use ockam::Result;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_multiaddr::MultiAddr;
+
+use crate::nodes::models::portal::ProxyRoute;
+use crate::nodes::service::proxy_inlets::protocol::ProxyDestination;
+
+/// Placeholder replaced by the requested hostname in a route template
+const HOST_PLACEHOLDER: &str = "{host}";
+/// Placeholder replaced by the requested port in a route template
+const PORT_PLACEHOLDER: &str = "{port}";
+
+/// The destinations requested by a [`ProxyRoute`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct DestinationPattern {
+    host: HostPattern,
+    port: Option<u16>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum HostPattern {
+    /// A single hostname
+    Exact(String),
+    /// Any subdomain of a domain, given with its leading dot
+    Subdomains(String),
+}
+
+impl DestinationPattern {
+    fn parse(destination: &str) -> Result<Self> {
+        let invalid = || {
+            ockam_core::Error::new(
+                Origin::Api,
+                Kind::Invalid,
+                format!("Invalid proxy destination '{destination}', expected hostname[:port] or *.domain[:port]"),
+            )
+        };
+
+        let (host, port) = match destination.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port.parse().map_err(|_| invalid())?)),
+            None => (destination, None),
+        };
+        let host = host
+            .strip_prefix('[')
+            .and_then(|h| h.strip_suffix(']'))
+            .unwrap_or(host)
+            .to_lowercase();
+
+        let host = match host.strip_prefix('*') {
+            Some(domain) if domain.starts_with('.') && domain.len() > 1 => {
+                HostPattern::Subdomains(domain.to_string())
+            }
+            Some(_) => return Err(invalid()),
+            None if host.is_empty() => return Err(invalid()),
+            None => HostPattern::Exact(host),
+        };
+        Ok(Self { host, port })
+    }
+
+    /// Return a score for the destination, higher is more specific, or None if it doesn't match
+    fn matches(&self, destination: &ProxyDestination) -> Option<u8> {
+        if let Some(port) = self.port {
+            if port != destination.port {
+                return None;
+            }
+        }
+        let port_score = self.port.map(|_| 1).unwrap_or(0);
+        match &self.host {
+            HostPattern::Exact(host) if host == &destination.host => Some(2 + port_score),
+            HostPattern::Subdomains(domain) if destination.host.ends_with(domain.as_str()) => {
+                Some(port_score)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The outlet route resolved for a requested destination
+#[derive(Clone, Debug)]
+pub(crate) struct ResolvedRoute {
+    /// Index of the matching route, or None if the route template was used
+    pub(crate) route_index: Option<usize>,
+    pub(crate) outlet_addr: MultiAddr,
+}
+
+/// Map the destinations requested to a proxy inlet to outlet routes
+#[derive(Clone, Debug)]
+pub(crate) struct ProxyRoutes {
+    routes: Vec<(DestinationPattern, MultiAddr)>,
+    route_template: Option<String>,
+}
+
+impl ProxyRoutes {
+    /// Validate the destinations of the routes and the route template
+    pub(crate) fn create(routes: &[ProxyRoute], route_template: Option<String>) -> Result<Self> {
+        let routes = routes
+            .iter()
+            .map(|route| {
+                Ok((
+                    DestinationPattern::parse(&route.destination)?,
+                    route.outlet_addr.clone(),
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if let Some(route_template) = &route_template {
+            Self::template_sample(route_template)?;
+        }
+
+        Ok(Self {
+            routes,
+            route_template,
+        })
+    }
+
+    /// The route template with sample values, used to validate it
+    pub(crate) fn template_sample(route_template: &str) -> Result<MultiAddr> {
+        if !route_template.contains(HOST_PLACEHOLDER) {
+            return Err(ockam_core::Error::new(
+                Origin::Api,
+                Kind::Invalid,
+                format!("The route template '{route_template}' must contain {HOST_PLACEHOLDER}"),
+            ));
+        }
+        Self::fill_template(route_template, &ProxyDestination::new("localhost", 1)).ok_or_else(
+            || {
+                ockam_core::Error::new(
+                    Origin::Api,
+                    Kind::Invalid,
+                    format!("The route template '{route_template}' is not a valid route"),
+                )
+            },
+        )
+    }
+
+    /// Return the route to the outlet serving a destination.
+    /// The most specific route wins: `host:port`, then `host`, then `*.domain:port`,
+    /// then `*.domain`, and finally the route template.
+    pub(crate) fn resolve(&self, destination: &ProxyDestination) -> Option<ResolvedRoute> {
+        let best_route = self
+            .routes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, (pattern, outlet_addr))| {
+                pattern
+                    .matches(destination)
+                    .map(|score| (score, index, outlet_addr))
+            })
+            // keep the first route declared when two routes have the same score
+            .min_by_key(|(score, index, _)| (u8::MAX - score, *index));
+
+        if let Some((_, index, outlet_addr)) = best_route {
+            return Some(ResolvedRoute {
+                route_index: Some(index),
+                outlet_addr: outlet_addr.clone(),
+            });
+        }
+
+        let route_template = self.route_template.as_ref()?;
+        Self::fill_template(route_template, destination).map(|outlet_addr| ResolvedRoute {
+            route_index: None,
+            outlet_addr,
+        })
+    }
+
+    /// Replace the placeholders of a route template.
+    /// Only hostnames made of letters, digits, `-`, `_` and `.` are accepted, so that a
+    /// client can't modify the route itself.
+    fn fill_template(route_template: &str, destination: &ProxyDestination) -> Option<MultiAddr> {
+        let valid_host = !destination.host.is_empty()
+            && destination
+                .host
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+        if !valid_host {
+            return None;
+        }
+        let route = route_template
+            .replace(HOST_PLACEHOLDER, &destination.host)
+            .replace(PORT_PLACEHOLDER, &destination.port.to_string());
+        MultiAddr::from_str(&route).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn routes(destinations: &[&str], route_template: Option<&str>) -> ProxyRoutes {
+        let routes = destinations
+            .iter()
+            .enumerate()
+            .map(|(index, destination)| {
+                ProxyRoute::new(
+                    *destination,
+                    MultiAddr::from_str(&format!("/service/outlet{index}")).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        ProxyRoutes::create(&routes, route_template.map(|t| t.to_string())).unwrap()
+    }
+
+    fn resolve(routes: &ProxyRoutes, host: &str, port: u16) -> Option<String> {
+        routes
+            .resolve(&ProxyDestination::new(host, port))
+            .map(|route| route.outlet_addr.to_string())
+    }
+
+    #[test]
+    fn the_most_specific_route_is_used() {
+        let routes = routes(
+            &[
+                "*.internal",
+                "*.internal:5432",
+                "db.internal",
+                "db.internal:5432",
+            ],
+            None,
+        );
+        assert_eq!(
+            resolve(&routes, "db.internal", 5432).as_deref(),
+            Some("/service/outlet3")
+        );
+        assert_eq!(
+            resolve(&routes, "db.internal", 80).as_deref(),
+            Some("/service/outlet2")
+        );
+        assert_eq!(
+            resolve(&routes, "pg.internal", 5432).as_deref(),
+            Some("/service/outlet1")
+        );
+        assert_eq!(
+            resolve(&routes, "web.internal", 80).as_deref(),
+            Some("/service/outlet0")
+        );
+        assert_eq!(resolve(&routes, "internal", 80), None);
+        assert_eq!(resolve(&routes, "example.com", 80), None);
+    }
+
+    #[test]
+    fn the_route_template_is_used_for_other_destinations() {
+        let routes = routes(
+            &["db.internal"],
+            Some("/service/forward_to_{host}/service/outlet_{port}"),
+        );
+        assert_eq!(
+            resolve(&routes, "db.internal", 5432).as_deref(),
+            Some("/service/outlet0")
+        );
+        assert_eq!(
+            resolve(&routes, "web", 80).as_deref(),
+            Some("/service/forward_to_web/service/outlet_80")
+        );
+        assert_eq!(resolve(&routes, "web/service/other", 80), None);
+    }
+
+    #[test]
+    fn invalid_routes_are_rejected() {
+        let outlet = MultiAddr::from_str("/service/outlet").unwrap();
+        for destination in ["", "*", "*internal", "db:port", "db:70000"] {
+            assert!(
+                ProxyRoutes::create(&[ProxyRoute::new(destination, outlet.clone())], None).is_err(),
+                "{destination}"
+            );
+        }
+        assert!(ProxyRoutes::create(&[], Some("/service/outlet".into())).is_err());
+        assert!(ProxyRoutes::create(&[], Some("/unknown/{host}".into())).is_err());
+    }
+}
@@ -16,8 +16,8 @@ use crate::nodes::{NodeManager, NodeManagerWorker};
 use ockam::identity::models::CredentialAndPurposeKey;
 use ockam::identity::Vault;
 use ockam::identity::{
-    Identifier, Identities, SecureChannelListenerOptions, SecureChannelOptions, SecureChannels,
-    TrustMultiIdentifiersPolicy,
+    HandshakePattern, Identifier, Identities, SecureChannelListenerOptions, SecureChannelOptions,
+    SecureChannels, TrustMultiIdentifiersPolicy,
 };
 use ockam::identity::{SecureChannel, SecureChannelListener};
 use ockam::identity::{SecureChannelSqlxDatabase, TrustEveryonePolicy};
@@ -98,8 +98,19 @@ impl NodeManagerWorker {
             .node_manager
             .get_secure_channel(&address)
             .map(|secure_channel| {
-                Response::ok().body(ShowSecureChannelResponse::new(Some(secure_channel)))
-            })?;
+                let rekey_metrics = self
+                    .node_manager
+                    .secure_channels
+                    .secure_channel_registry()
+                    .get_channel_by_encryptor_address(secure_channel.sc().encryptor_address())
+                    .map(|entry| entry.rekey_metrics().clone());
+                let response = ShowSecureChannelResponse::new(Some(secure_channel));
+                match rekey_metrics {
+                    Some(rekey_metrics) => response.with_rekey_metrics(&rekey_metrics),
+                    None => response,
+                }
+            })
+            .map(|response| Response::ok().body(response))?;
 
         Ok(response)
     }
@@ -236,6 +247,16 @@ impl NodeManager {
             None => options.with_trust_policy(TrustEveryonePolicy),
         };
 
+        // When reconnecting to a single authorized identity, for example when a session
+        // is replaced, its static key is known from the previous handshake and a IK handshake
+        // saves one round trip. A XX handshake is used otherwise
+        let options = match authorized_identifiers.as_deref() {
+            Some([responder]) => {
+                options.with_known_responder(responder.clone(), HandshakePattern::IK)
+            }
+            _ => options,
+        };
+
         let options = if secure_channel_type == SecureChannelType::KeyExchangeOnly {
             // TODO: Should key exchange channels be persisted automatically?
             options.key_exchange_only().persist()?
@@ -346,36 +367,10 @@ impl NodeManager {
         let vault = self.cli_state.make_vault(named_vault).await?;
         let secure_channels = self.build_secure_channels(vault).await?;
 
-        let mut options = SecureChannelListenerOptions::new();
-
-        for api_flow_control_id in &self.api_transport_flow_control_ids {
-            options = options.as_consumer(api_flow_control_id);
-        }
-
-        let options = match authorized_identifiers {
-            Some(ids) => options.with_trust_policy(TrustMultiIdentifiersPolicy::new(ids)),
-            None => options.with_trust_policy(TrustEveryonePolicy),
-        };
-
-        let options = match self.project_authority() {
-            Some(project_authority) => options.with_authority(project_authority),
-            None => options,
-        };
-
-        let options = match self.credential_retriever_creators.project_member.as_ref() {
-            None => options,
-            Some(credential_retriever_creator) => {
-                options.with_credential_retriever_creator(credential_retriever_creator.clone())?
-            }
-        };
-
-        let options = if secure_channel_type == SecureChannelType::KeyExchangeOnly {
-            // TODO: Should key exchange channels be persisted automatically?
-            options.key_exchange_only().persist()?
-        } else {
-            options
-        };
-
+        let options = self.secure_channel_listener_options(
+            authorized_identifiers.clone(),
+            &secure_channel_type,
+        )?;
         let listener = secure_channels.create_secure_channel_listener(
             ctx,
             &identifier,
@@ -413,9 +408,60 @@ impl NodeManager {
             );
         }
 
+        // resume the secure channels spawned by that listener before the node was restarted
+        if secure_channel_type == SecureChannelType::KeyExchangeAndMessages {
+            let options =
+                self.secure_channel_listener_options(authorized_identifiers, &secure_channel_type)?;
+            let resumed = secure_channels
+                .resume_secure_channels_for_listener(ctx, &identifier, &listener, options)
+                .await?;
+            if !resumed.is_empty() {
+                info!(
+                    "Resumed {} secure channels for the listener at {address}",
+                    resumed.len()
+                );
+            }
+        }
+
         Ok(listener)
     }
 
+    fn secure_channel_listener_options(
+        &self,
+        authorized_identifiers: Option<Vec<Identifier>>,
+        secure_channel_type: &SecureChannelType,
+    ) -> Result<SecureChannelListenerOptions> {
+        let mut options = SecureChannelListenerOptions::new();
+
+        for api_flow_control_id in &self.api_transport_flow_control_ids {
+            options = options.as_consumer(api_flow_control_id);
+        }
+
+        let options = match authorized_identifiers {
+            Some(ids) => options.with_trust_policy(TrustMultiIdentifiersPolicy::new(ids)),
+            None => options.with_trust_policy(TrustEveryonePolicy),
+        };
+
+        let options = match self.project_authority() {
+            Some(project_authority) => options.with_authority(project_authority),
+            None => options,
+        };
+
+        let options = match self.credential_retriever_creators.project_member.as_ref() {
+            None => options,
+            Some(credential_retriever_creator) => {
+                options.with_credential_retriever_creator(credential_retriever_creator.clone())?
+            }
+        };
+
+        // the secure channels are persisted so that they can be resumed after a restart
+        match secure_channel_type {
+            // TODO: Should key exchange channels be persisted automatically?
+            SecureChannelType::KeyExchangeOnly => options.key_exchange_only().persist(),
+            SecureChannelType::KeyExchangeAndMessages => options.persist(),
+        }
+    }
+
     pub fn delete_secure_channel_listener(
         &self,
         ctx: &Context,
@@ -463,7 +509,10 @@ impl NodeManager {
         Ok(Arc::new(SecureChannels::new(
             identities,
             self.secure_channels.secure_channel_registry(),
-            SecureChannelSqlxDatabase::make_repository(self.cli_state.database()),
+            SecureChannelSqlxDatabase::make_repository_for_node(
+                self.cli_state.database(),
+                &self.node_name,
+            ),
         )))
     }
 }
@@ -0,0 +1,151 @@
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use colorful::Colorful;
+use tokio::time::timeout;
+
+use ockam::identity::Identifier;
+use ockam::Result;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, Address, Error};
+use ockam_multiaddr::MultiAddr;
+use ockam_node::Context;
+
+use crate::colors::color_primary;
+use crate::error::ApiError;
+use crate::nodes::connection::Connection;
+use crate::nodes::service::tcp_inlets::InletLoadBalancer;
+use crate::nodes::NodeManager;
+use crate::session::replacer::{
+    CurrentInletStatus, ReplacerOutcome, ReplacerOutputKind, SessionReplacer, MAX_RECOVERY_TIME,
+};
+use crate::{fmt_info, fmt_ok, fmt_warn};
+
+/// Maintain the connection to one of the outlets of an inlet with several outlets.
+///
+/// The inlet itself is shared by all the outlets: the replacer only makes the route to its
+/// outlet available to the load balancer of the inlet while the connection is up.
+pub(super) struct InletBackendSessionReplacer {
+    pub(super) node_manager: Weak<NodeManager>,
+    pub(super) context: Context,
+    pub(super) listen_addr: String,
+    pub(super) inlet_address: Option<Address>,
+    pub(super) load_balancer: Arc<InletLoadBalancer>,
+    pub(super) index: usize,
+    pub(super) outlet_addr: MultiAddr,
+    pub(super) authorized: Option<Identifier>,
+    pub(super) wait_for_outlet_duration: Duration,
+    pub(super) secure_channel_identifier: Option<Identifier>,
+
+    // current status
+    pub(super) connection: Option<Connection>,
+}
+
+impl InletBackendSessionReplacer {
+    async fn create_impl(&mut self, node_manager: &NodeManager) -> Result<ReplacerOutcome> {
+        self.load_balancer.set_route(self.index, None);
+        self.close_connection(node_manager);
+
+        let connection = node_manager
+            .make_connection(
+                &self.context,
+                &self.outlet_addr,
+                self.secure_channel_identifier
+                    .clone()
+                    .unwrap_or(node_manager.identifier()),
+                self.authorized.clone(),
+                Some(self.wait_for_outlet_duration),
+            )
+            .await?;
+        let connection = self.connection.insert(connection);
+        let connection_route = connection.route()?;
+        let transport_route = connection.transport_route();
+
+        self.load_balancer
+            .set_route(self.index, Some(connection_route.clone()));
+        info!(outlet = %self.outlet_addr, route = %connection_route, "tcp inlet outlet restored");
+
+        Ok(ReplacerOutcome {
+            ping_route: transport_route,
+            kind: ReplacerOutputKind::Inlet(CurrentInletStatus {
+                worker: self.inlet_address.clone(),
+                route: connection_route,
+            }),
+        })
+    }
+
+    fn close_connection(&mut self, node_manager: &NodeManager) {
+        if let Some(connection) = self.connection.take() {
+            let result = connection.close(&self.context, node_manager);
+            if let Err(err) = result {
+                error!(?err, "Failed to close connection");
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl SessionReplacer for InletBackendSessionReplacer {
+    async fn create(&mut self) -> Result<ReplacerOutcome> {
+        let node_manager = if let Some(node_manager) = self.node_manager.upgrade() {
+            node_manager
+        } else {
+            return Err(Error::new(
+                Origin::Node,
+                Kind::Cancelled,
+                "Node manager is dropped. Can't connect the Inlet to its Outlet.",
+            ));
+        };
+
+        debug!(%self.outlet_addr, "connecting tcp inlet to one of its outlets");
+
+        match timeout(MAX_RECOVERY_TIME, self.create_impl(&node_manager)).await {
+            Err(_) => {
+                warn!(%self.outlet_addr, "timeout connecting tcp inlet to its outlet");
+                Err(ApiError::core("timeout"))
+            }
+            Ok(Err(e)) => {
+                warn!(%self.outlet_addr, err = %e, "failed to connect tcp inlet to its outlet");
+                Err(e)
+            }
+            Ok(Ok(outcome)) => Ok(outcome),
+        }
+    }
+
+    async fn close(&mut self) {
+        self.load_balancer.set_route(self.index, None);
+
+        let node_manager = if let Some(node_manager) = self.node_manager.upgrade() {
+            node_manager
+        } else {
+            warn!("An inlet close was issued after the NodeManager shut down, skipping.");
+            return;
+        };
+
+        self.close_connection(&node_manager);
+    }
+
+    async fn on_session_down(&self) {
+        if let Some(node_manager) = self.node_manager.upgrade() {
+            node_manager.cli_state.notify_message(
+                fmt_warn!(
+                    "The TCP Inlet at {} lost the connection to the TCP Outlet at {}\n",
+                    color_primary(&self.listen_addr),
+                    color_primary(&self.outlet_addr)
+                ) + &fmt_info!(
+                    "New connections will use its other TCP Outlets. Attempting to reconnect...\n"
+                ),
+            );
+        }
+    }
+
+    async fn on_session_replaced(&self) {
+        if let Some(node_manager) = self.node_manager.upgrade() {
+            node_manager.cli_state.notify_message(fmt_ok!(
+                "The TCP Inlet at {} has restored the connection to the TCP Outlet at {}\n",
+                color_primary(&self.listen_addr),
+                color_primary(&self.outlet_addr)
+            ));
+        }
+    }
+}
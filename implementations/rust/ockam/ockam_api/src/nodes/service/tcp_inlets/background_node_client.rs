@@ -1,3 +1,4 @@
+use miette::miette;
 use ockam::identity::Identifier;
 use ockam_abac::PolicyExpression;
 use ockam_core::api::{Reply, Request};
@@ -8,7 +9,9 @@ use ockam_node::Context;
 use ockam_transport_core::HostnamePort;
 use std::time::Duration;
 
-use crate::nodes::models::portal::{CreateInlet, InletStatus};
+use crate::nodes::models::portal::{
+    CreateInlet, InletLoadBalancing, InletStatus, UnixSocketListenAddr,
+};
 use crate::nodes::service::tcp_inlets::Inlets;
 use crate::nodes::BackgroundNodeClient;
 
@@ -101,6 +104,78 @@ impl Inlets for BackgroundNodeClient {
         self.ask_and_get_reply(ctx, request).await
     }
 
+    async fn create_unix_inlet(
+        &self,
+        ctx: &Context,
+        unix_socket: &UnixSocketListenAddr,
+        outlet_addr: &MultiAddr,
+        alias: &str,
+        authorized_identifier: &Option<Identifier>,
+        policy_expression: &Option<PolicyExpression>,
+        wait_for_outlet_timeout: Duration,
+        wait_connection: bool,
+        secure_channel_identifier: &Option<Identifier>,
+    ) -> miette::Result<Reply<InletStatus>> {
+        let request = {
+            // The listen address is not used by a Unix Inlet
+            let mut payload = create_inlet_payload(
+                &HostnamePort::localhost(0),
+                outlet_addr,
+                alias,
+                authorized_identifier,
+                policy_expression,
+                wait_for_outlet_timeout,
+                wait_connection,
+                secure_channel_identifier,
+                false,
+                false,
+                false,
+                &None,
+            );
+            payload.set_unix_socket(unix_socket.clone());
+            Request::post("/node/inlet").body(payload)
+        };
+        self.ask_and_get_reply(ctx, request).await
+    }
+
+    async fn create_balanced_inlet(
+        &self,
+        ctx: &Context,
+        listen_addr: &HostnamePort,
+        outlet_addrs: &[MultiAddr],
+        load_balancing: InletLoadBalancing,
+        alias: &str,
+        authorized_identifier: &Option<Identifier>,
+        policy_expression: &Option<PolicyExpression>,
+        wait_for_outlet_timeout: Duration,
+        wait_connection: bool,
+        secure_channel_identifier: &Option<Identifier>,
+        tls_certificate_provider: &Option<MultiAddr>,
+    ) -> miette::Result<Reply<InletStatus>> {
+        let (outlet_addr, additional_outlet_addrs) = outlet_addrs
+            .split_first()
+            .ok_or_else(|| miette!("At least one outlet address must be provided"))?;
+        let request = {
+            let mut payload = create_inlet_payload(
+                listen_addr,
+                outlet_addr,
+                alias,
+                authorized_identifier,
+                policy_expression,
+                wait_for_outlet_timeout,
+                wait_connection,
+                secure_channel_identifier,
+                false,
+                false,
+                false,
+                tls_certificate_provider,
+            );
+            payload.set_load_balancing(additional_outlet_addrs.to_vec(), load_balancing);
+            Request::post("/node/inlet").body(payload)
+        };
+        self.ask_and_get_reply(ctx, request).await
+    }
+
     async fn show_inlet(&self, ctx: &Context, alias: &str) -> miette::Result<Reply<InletStatus>> {
         let request = Request::get(format!("/node/inlet/{alias}"));
         self.ask_and_get_reply(ctx, request).await
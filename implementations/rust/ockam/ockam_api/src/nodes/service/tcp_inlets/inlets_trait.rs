@@ -7,7 +7,7 @@ use ockam_node::Context;
 use ockam_transport_core::HostnamePort;
 use std::time::Duration;
 
-use crate::nodes::models::portal::InletStatus;
+use crate::nodes::models::portal::{InletLoadBalancing, InletStatus, UnixSocketListenAddr};
 
 #[async_trait]
 pub trait Inlets {
@@ -29,6 +29,36 @@ pub trait Inlets {
         tls_certificate_provider: &Option<MultiAddr>,
     ) -> miette::Result<Reply<InletStatus>>;
 
+    #[allow(clippy::too_many_arguments)]
+    async fn create_unix_inlet(
+        &self,
+        ctx: &Context,
+        unix_socket: &UnixSocketListenAddr,
+        outlet_addr: &MultiAddr,
+        alias: &str,
+        authorized_identifier: &Option<Identifier>,
+        policy_expression: &Option<PolicyExpression>,
+        wait_for_outlet_timeout: Duration,
+        wait_connection: bool,
+        secure_channel_identifier: &Option<Identifier>,
+    ) -> miette::Result<Reply<InletStatus>>;
+
+    #[allow(clippy::too_many_arguments)]
+    async fn create_balanced_inlet(
+        &self,
+        ctx: &Context,
+        listen_addr: &HostnamePort,
+        outlet_addrs: &[MultiAddr],
+        load_balancing: InletLoadBalancing,
+        alias: &str,
+        authorized_identifier: &Option<Identifier>,
+        policy_expression: &Option<PolicyExpression>,
+        wait_for_outlet_timeout: Duration,
+        wait_connection: bool,
+        secure_channel_identifier: &Option<Identifier>,
+        tls_certificate_provider: &Option<MultiAddr>,
+    ) -> miette::Result<Reply<InletStatus>>;
+
     async fn show_inlet(&self, ctx: &Context, alias: &str) -> miette::Result<Reply<InletStatus>>;
 
     async fn delete_inlet(&self, ctx: &Context, inlet_alias: &str) -> miette::Result<Reply<()>>;
@@ -0,0 +1,165 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+use ockam::tcp::{TcpInletConnectionGuard, TcpInletRouteSelector};
+use ockam_core::Route;
+
+use crate::nodes::models::portal::InletLoadBalancing;
+
+/// Choose one of the outlets of an inlet for each new connection, among the outlets
+/// which are currently reachable.
+///
+/// The route to each outlet is set by the session of that outlet while it is up.
+#[derive(Debug)]
+pub(crate) struct InletLoadBalancer {
+    strategy: InletLoadBalancing,
+    backends: Vec<InletBackend>,
+    // Index of the next outlet to use with the round-robin strategy
+    next: AtomicUsize,
+}
+
+#[derive(Debug, Default)]
+struct InletBackend {
+    route: RwLock<Option<Route>>,
+    connections: Arc<AtomicUsize>,
+}
+
+/// Decrement the number of open connections of an outlet when a connection is closed
+struct OpenConnection(Arc<AtomicUsize>);
+
+impl Drop for OpenConnection {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl InletLoadBalancer {
+    /// Create a load balancer for `outlets_count` outlets, given in their order of priority
+    pub(crate) fn new(strategy: InletLoadBalancing, outlets_count: usize) -> Self {
+        Self {
+            strategy,
+            backends: (0..outlets_count)
+                .map(|_| InletBackend::default())
+                .collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn strategy(&self) -> InletLoadBalancing {
+        self.strategy
+    }
+
+    /// Set the route to an outlet, or None when it is not reachable
+    pub(crate) fn set_route(&self, index: usize, route: Option<Route>) {
+        if let Some(backend) = self.backends.get(index) {
+            *backend.route.write().unwrap() = route;
+        }
+    }
+
+    /// Number of open connections to an outlet
+    pub(crate) fn connections(&self, index: usize) -> usize {
+        self.backends
+            .get(index)
+            .map(|backend| backend.connections.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    /// Return the index of the outlet to use for a new connection and the route to it
+    fn select(&self) -> Option<(usize, Route)> {
+        let available = self
+            .backends
+            .iter()
+            .enumerate()
+            .filter_map(|(index, backend)| {
+                let route = backend.route.read().unwrap().clone()?;
+                Some((index, route))
+            })
+            .collect::<Vec<_>>();
+
+        match self.strategy {
+            InletLoadBalancing::RoundRobin => {
+                if available.is_empty() {
+                    return None;
+                }
+                let next = self.next.fetch_add(1, Ordering::Relaxed) % available.len();
+                available.into_iter().nth(next)
+            }
+            InletLoadBalancing::LeastConnections => available
+                .into_iter()
+                .min_by_key(|(index, _)| self.connections(*index)),
+            InletLoadBalancing::Failover => available.into_iter().next(),
+        }
+    }
+}
+
+impl TcpInletRouteSelector for InletLoadBalancer {
+    fn select_route(&self) -> Option<(Route, TcpInletConnectionGuard)> {
+        let (index, route) = self.select()?;
+        let connections = self.backends[index].connections.clone();
+        connections.fetch_add(1, Ordering::Relaxed);
+        debug!(%index, %route, "selected an outlet for a new inlet connection");
+        let guard: TcpInletConnectionGuard = Box::new(OpenConnection(connections));
+        Some((route, guard))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_core::route;
+
+    fn load_balancer(strategy: InletLoadBalancing) -> InletLoadBalancer {
+        let load_balancer = InletLoadBalancer::new(strategy, 3);
+        for index in 0..3 {
+            load_balancer.set_route(index, Some(route![format!("outlet{index}")]));
+        }
+        load_balancer
+    }
+
+    fn select(load_balancer: &InletLoadBalancer) -> (String, TcpInletConnectionGuard) {
+        let (route, guard) = load_balancer.select_route().unwrap();
+        (route.to_string(), guard)
+    }
+
+    #[test]
+    fn round_robin_skips_unreachable_outlets() {
+        let load_balancer = load_balancer(InletLoadBalancing::RoundRobin);
+        let selected = (0..3).map(|_| select(&load_balancer).0).collect::<Vec<_>>();
+        assert_eq!(selected, ["0#outlet0", "0#outlet1", "0#outlet2"]);
+
+        load_balancer.set_route(1, None);
+        let selected = (0..3).map(|_| select(&load_balancer).0).collect::<Vec<_>>();
+        assert_eq!(selected, ["0#outlet2", "0#outlet0", "0#outlet2"]);
+    }
+
+    #[test]
+    fn least_connections_counts_open_connections() {
+        let load_balancer = load_balancer(InletLoadBalancing::LeastConnections);
+        let (first, first_guard) = select(&load_balancer);
+        let (second, _second_guard) = select(&load_balancer);
+        assert_eq!(
+            (first.as_str(), second.as_str()),
+            ("0#outlet0", "0#outlet1")
+        );
+        assert_eq!(load_balancer.connections(0), 1);
+
+        drop(first_guard);
+        assert_eq!(load_balancer.connections(0), 0);
+        assert_eq!(select(&load_balancer).0, "0#outlet0");
+    }
+
+    #[test]
+    fn failover_uses_the_first_reachable_outlet() {
+        let load_balancer = load_balancer(InletLoadBalancing::Failover);
+        assert_eq!(select(&load_balancer).0, "0#outlet0");
+        assert_eq!(select(&load_balancer).0, "0#outlet0");
+
+        load_balancer.set_route(0, None);
+        assert_eq!(select(&load_balancer).0, "0#outlet1");
+
+        for index in 0..3 {
+            load_balancer.set_route(index, None);
+        }
+        assert!(load_balancer.select_route().is_none());
+    }
+}
@@ -1,11 +1,19 @@
+mod backend_session_replacer;
 mod background_node_client;
 mod in_memory_node;
 mod inlets_trait;
+mod load_balancer;
 mod node_manager;
 mod node_manager_worker;
 mod session_replacer;
+#[cfg(unix)]
+mod unix_session_replacer;
 
+use backend_session_replacer::*;
 pub use inlets_trait::*;
+pub(crate) use load_balancer::*;
 use session_replacer::*;
+#[cfg(unix)]
+use unix_session_replacer::*;
 
 pub use background_node_client::create_inlet_payload;
@@ -3,18 +3,29 @@ use std::time::Duration;
 
 use crate::address::get_free_address_for;
 use ockam::identity::Identifier;
+use ockam::tcp::TcpInletOptions;
 use ockam::Result;
-use ockam_abac::{PolicyExpression, Resource, ResourceType};
+use ockam_abac::{Action, PolicyExpression, Resource, ResourceType};
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{Route, TryClone};
+use ockam_multiaddr::proto::Project as ProjectProto;
 use ockam_multiaddr::MultiAddr;
 use ockam_node::compat::asynchronous::Mutex;
 use ockam_node::Context;
 use ockam_transport_core::HostnamePort;
+use ockam_transport_tcp::new_certificate_provider_cache;
 
-use crate::nodes::models::portal::InletStatus;
-use crate::nodes::registry::InletInfo;
-use crate::nodes::service::tcp_inlets::InletSessionReplacer;
+use crate::error::ApiError;
+#[cfg(unix)]
+use crate::nodes::models::portal::UnixSocketListenAddr;
+use crate::nodes::models::portal::{InletBackendStatus, InletLoadBalancing, InletStatus};
+use crate::nodes::registry::{InletBackendsInfo, InletInfo};
+use crate::nodes::service::certificate_provider::ProjectCertificateProvider;
+#[cfg(unix)]
+use crate::nodes::service::tcp_inlets::UnixInletSessionReplacer;
+use crate::nodes::service::tcp_inlets::{
+    InletBackendSessionReplacer, InletLoadBalancer, InletSessionReplacer,
+};
 use crate::nodes::NodeManager;
 use crate::session::connection_status::ConnectionStatus;
 use crate::session::replacer::{ReplacerOutputKind, SessionReplacer, MAX_CONNECT_TIME};
@@ -214,11 +225,308 @@ impl NodeManager {
         Ok(tcp_inlet_status)
     }
 
+    /// Create an inlet forwarding each new connection to one of several outlets exposing
+    /// the same service, chosen with the `load_balancing` strategy among the outlets which
+    /// are currently reachable. The outlets are given in their order of priority.
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip_all)]
+    pub async fn create_balanced_inlet(
+        self: &Arc<Self>,
+        ctx: &Context,
+        listen_address: HostnamePort,
+        outlet_addresses: Vec<MultiAddr>,
+        load_balancing: InletLoadBalancing,
+        alias: String,
+        policy_expression: Option<PolicyExpression>,
+        wait_for_outlet_duration: Option<Duration>,
+        authorized: Option<Identifier>,
+        wait_connection: bool,
+        secure_channel_identifier: Option<Identifier>,
+        tls_certificate_provider: Option<MultiAddr>,
+    ) -> Result<InletStatus> {
+        debug! {
+            %listen_address,
+            outlets = ?outlet_addresses.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+            %load_balancing,
+            %alias,
+            "creating balanced inlet"
+        }
+
+        if outlet_addresses.len() < 2 {
+            return Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::Invalid,
+                "A balanced inlet needs at least two outlets",
+            ));
+        }
+
+        let socket_addr = ockam_node::compat::asynchronous::resolve_peer(&listen_address).await?;
+        let listen_addr = if listen_address.port() == 0 {
+            get_free_address_for(&socket_addr.ip().to_string())
+                .map_err(|err| ockam_core::Error::new(Origin::Transport, Kind::Invalid, err))?
+        } else {
+            socket_addr
+        };
+
+        // Check registry for duplicated alias or bind address
+        {
+            let registry = &self.registry.inlets;
+
+            if registry.contains_key(&alias) {
+                let message = format!("A TCP inlet with alias '{alias}' already exists");
+                return Err(ockam_core::Error::new(
+                    Origin::Node,
+                    Kind::AlreadyExists,
+                    message,
+                ));
+            }
+
+            if registry
+                .values()
+                .iter()
+                .any(|inlet| inlet.bind_addr == listen_addr.to_string())
+            {
+                let message =
+                    format!("A TCP inlet with bind tcp address '{listen_addr}' already exists");
+                return Err(ockam_core::Error::new(
+                    Origin::Node,
+                    Kind::AlreadyExists,
+                    message,
+                ));
+            }
+        }
+
+        let authority = self.outlet_authority(&outlet_addresses[0]).await?;
+        let (incoming_ac, outgoing_ac) = self
+            .access_control(
+                ctx,
+                authority,
+                Resource::new(alias.clone(), ResourceType::TcpInlet),
+                Action::HandleMessage,
+                policy_expression,
+            )
+            .await?;
+        let options = TcpInletOptions::new()
+            .with_incoming_access_control(incoming_ac)
+            .with_outgoing_access_control(outgoing_ac);
+        let options = match tls_certificate_provider {
+            Some(tls_provider) => {
+                options.with_tls_certificate_provider(new_certificate_provider_cache(Arc::new(
+                    ProjectCertificateProvider::new(Arc::downgrade(self), tls_provider),
+                )))
+            }
+            None => options,
+        };
+
+        let load_balancer = Arc::new(InletLoadBalancer::new(
+            load_balancing,
+            outlet_addresses.len(),
+        ));
+        let inlet = self
+            .tcp_transport
+            .create_inlet_with_route_selector(
+                listen_addr.to_string(),
+                load_balancer.clone(),
+                options,
+            )
+            .await?;
+        let inlet_address = inlet.processor_address().cloned();
+
+        let mut outlets = vec![];
+        for (index, outlet_address) in outlet_addresses.iter().enumerate() {
+            let replacer = InletBackendSessionReplacer {
+                node_manager: Arc::downgrade(self),
+                context: ctx.try_clone()?,
+                listen_addr: listen_addr.to_string(),
+                inlet_address: inlet_address.clone(),
+                load_balancer: load_balancer.clone(),
+                index,
+                outlet_addr: outlet_address.clone(),
+                authorized: authorized.clone(),
+                wait_for_outlet_duration: wait_for_outlet_duration.unwrap_or(MAX_CONNECT_TIME),
+                secure_channel_identifier: secure_channel_identifier.clone(),
+                connection: None,
+            };
+            let replacer: Arc<Mutex<dyn SessionReplacer>> = Arc::new(Mutex::new(replacer));
+            let mut session = Session::create(ctx, replacer, None)?;
+
+            if wait_connection {
+                if let Err(err) = session.initial_connect().await {
+                    warn!(outlet = %outlet_address, "Failed to connect the inlet to its outlet: {err}");
+                }
+            }
+
+            session.start_monitoring()?;
+            outlets.push((outlet_address.clone(), Arc::new(Mutex::new(session))));
+        }
+
+        let _ = self
+            .cli_state
+            .create_tcp_inlet(
+                &self.node_name,
+                &listen_addr,
+                &outlet_addresses[0],
+                &alias,
+                false,
+            )
+            .await?;
+
+        let inlet_info = InletInfo::with_backends(
+            &listen_addr.to_string(),
+            InletBackendsInfo {
+                inlet: Arc::new(inlet),
+                load_balancer,
+                outlets,
+            },
+        );
+        self.registry
+            .inlets
+            .insert(alias.clone(), inlet_info.clone());
+
+        info! {
+            %listen_address,
+            %load_balancing,
+            %alias,
+            "balanced inlet created"
+        }
+
+        Ok(Self::inlet_status(&alias, &inlet_info).await)
+    }
+
+    /// Create an inlet listening on a Unix domain socket
+    #[cfg(unix)]
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip_all)]
+    pub async fn create_unix_inlet(
+        self: &Arc<Self>,
+        ctx: &Context,
+        unix_socket: UnixSocketListenAddr,
+        outlet_address: MultiAddr,
+        alias: String,
+        policy_expression: Option<PolicyExpression>,
+        wait_for_outlet_duration: Option<Duration>,
+        authorized: Option<Identifier>,
+        wait_connection: bool,
+        secure_channel_identifier: Option<Identifier>,
+    ) -> Result<InletStatus> {
+        let path = unix_socket.path.clone();
+        debug! {
+            %path,
+            %outlet_address,
+            %alias,
+            "creating unix inlet"
+        }
+
+        // Check registry for duplicated alias or socket path
+        {
+            let registry = &self.registry.inlets;
+
+            if registry.contains_key(&alias) {
+                let message = format!("An inlet with alias '{alias}' already exists");
+                return Err(ockam_core::Error::new(
+                    Origin::Node,
+                    Kind::AlreadyExists,
+                    message,
+                ));
+            }
+
+            if registry
+                .values()
+                .iter()
+                .any(|inlet| inlet.bind_addr == path)
+            {
+                let message =
+                    format!("An inlet listening on the unix socket '{path}' already exists");
+                return Err(ockam_core::Error::new(
+                    Origin::Node,
+                    Kind::AlreadyExists,
+                    message,
+                ));
+            }
+        }
+
+        let replacer = UnixInletSessionReplacer {
+            node_manager: Arc::downgrade(self),
+            context: ctx.try_clone()?,
+            unix_socket,
+            outlet_addr: outlet_address.clone(),
+            authorized,
+            wait_for_outlet_duration: wait_for_outlet_duration.unwrap_or(MAX_CONNECT_TIME),
+            resource: Resource::new(alias.clone(), ResourceType::TcpInlet),
+            policy_expression,
+            secure_channel_identifier,
+            inlet: None,
+            connection: None,
+        };
+
+        let replacer: Arc<Mutex<dyn SessionReplacer>> = Arc::new(Mutex::new(replacer));
+        let mut session = Session::create(ctx, replacer, None)?;
+
+        let outcome = if wait_connection {
+            let result = session
+                .initial_connect()
+                .await
+                .map(|outcome| match outcome {
+                    ReplacerOutputKind::Inlet(status) => status,
+                    _ => {
+                        panic!("Unexpected outcome: {:?}", outcome)
+                    }
+                });
+
+            match result {
+                Ok(status) => Some(status),
+                Err(err) => {
+                    warn!("Failed to create unix inlet: {err}");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let connection_status = session.connection_status();
+
+        session.start_monitoring()?;
+
+        // The inlet is not persisted with the TCP inlets of the node since it has no socket address
+        self.registry.inlets.insert(
+            alias.clone(),
+            InletInfo::new(&path, outlet_address.clone(), session, false),
+        );
+
+        info! {
+            %path,
+            %outlet_address,
+            %alias,
+            "unix inlet created"
+        }
+
+        Ok(InletStatus::new(
+            path,
+            outcome
+                .clone()
+                .and_then(|s| s.worker.map(|address| address.address().to_string())),
+            &alias,
+            None,
+            outcome.map(|s| s.route.to_string()),
+            connection_status,
+            outlet_address.to_string(),
+            false,
+        ))
+    }
+
     pub async fn delete_inlet(&self, alias: &str) -> Result<InletStatus> {
         info!(%alias, "Handling request to delete inlet portal");
         if let Some(inlet_to_delete) = self.registry.inlets.remove(alias) {
             debug!(%alias, "Successfully removed inlet from node registry");
-            inlet_to_delete.session.lock().await.stop().await;
+            for session in inlet_to_delete.sessions() {
+                session.lock().await.stop().await;
+            }
+            if let Some(backends) = &inlet_to_delete.backends {
+                if let Some(address) = backends.inlet.processor_address() {
+                    self.tcp_transport.stop_inlet(address)?;
+                }
+            }
             self.resources().delete_resource(&alias.into()).await?;
             self.cli_state
                 .delete_tcp_inlet(&self.node_name, alias)
@@ -247,95 +555,128 @@ impl NodeManager {
     pub async fn show_inlet(&self, alias: &str) -> Option<InletStatus> {
         info!(%alias, "Handling request to show inlet portal");
         if let Some(inlet_info) = self.registry.inlets.get(alias) {
-            let session = inlet_info.session.lock().await;
-            let connection_status = session.connection_status();
-            let outcome = session.last_outcome();
-            drop(session);
-            if let Some(outcome) = outcome {
-                if let ReplacerOutputKind::Inlet(status) = outcome {
+            Some(Self::inlet_status(alias, &inlet_info).await)
+        } else {
+            error!(%alias, "Inlet not found in the node registry");
+            None
+        }
+    }
+
+    pub async fn list_inlets(&self) -> Vec<InletStatus> {
+        let mut res = vec![];
+        for (alias, info) in self.registry.inlets.entries() {
+            res.push(Self::inlet_status(&alias, &info).await);
+        }
+
+        res
+    }
+
+    async fn inlet_status(alias: &str, info: &InletInfo) -> InletStatus {
+        let session = info.session.lock().await;
+        let connection_status = session.connection_status();
+        let outcome = session.last_outcome();
+        drop(session);
+
+        let status = if let Some(outcome) = outcome {
+            match &outcome {
+                ReplacerOutputKind::Inlet(status) => {
                     let address = match &status.worker {
                         Some(address) => address.address().to_string(),
                         None => "<>".to_string(),
                     };
 
-                    Some(InletStatus::new(
-                        inlet_info.bind_addr.to_string(),
+                    InletStatus::new(
+                        &info.bind_addr,
                         address,
                         alias,
                         None,
                         status.route.to_string(),
                         connection_status,
-                        inlet_info.outlet_addr.to_string(),
-                        inlet_info.privileged,
-                    ))
-                } else {
+                        info.outlet_addr.to_string(),
+                        info.privileged,
+                    )
+                }
+                _ => {
                     panic!("Unexpected outcome: {:?}", outcome)
                 }
-            } else {
-                Some(InletStatus::new(
-                    inlet_info.bind_addr.to_string(),
-                    None,
-                    alias,
-                    None,
-                    None,
-                    connection_status,
-                    inlet_info.outlet_addr.to_string(),
-                    inlet_info.privileged,
-                ))
             }
         } else {
-            error!(%alias, "Inlet not found in the node registry");
-            None
+            InletStatus::new(
+                &info.bind_addr,
+                None,
+                alias,
+                None,
+                None,
+                connection_status,
+                info.outlet_addr.to_string(),
+                info.privileged,
+            )
+        };
+
+        match &info.backends {
+            Some(backends) => Self::with_backends_status(status, backends).await,
+            None => status,
         }
     }
 
-    pub async fn list_inlets(&self) -> Vec<InletStatus> {
-        let mut res = vec![];
-        for (alias, info) in self.registry.inlets.entries() {
-            let session = info.session.lock().await;
+    /// Complete the status of an inlet with several outlets with the status of each outlet.
+    /// The inlet is up as long as one of its outlets is reachable.
+    async fn with_backends_status(
+        mut status: InletStatus,
+        backends: &InletBackendsInfo,
+    ) -> InletStatus {
+        let mut outlets = vec![];
+        for (index, (outlet_addr, session)) in backends.outlets.iter().enumerate() {
+            let session = session.lock().await;
             let connection_status = session.connection_status();
-            let outcome = session.last_outcome();
-            drop(session);
-
-            let status = if let Some(outcome) = outcome {
-                match &outcome {
-                    ReplacerOutputKind::Inlet(status) => {
-                        let address = match &status.worker {
-                            Some(address) => address.address().to_string(),
-                            None => "<>".to_string(),
-                        };
-
-                        InletStatus::new(
-                            &info.bind_addr,
-                            address,
-                            alias,
-                            None,
-                            status.route.to_string(),
-                            connection_status,
-                            info.outlet_addr.to_string(),
-                            info.privileged,
-                        )
-                    }
-                    _ => {
-                        panic!("Unexpected outcome: {:?}", outcome)
-                    }
+            let outlet_route = match session.last_outcome() {
+                Some(ReplacerOutputKind::Inlet(outcome))
+                    if connection_status == ConnectionStatus::Up =>
+                {
+                    Some(outcome.route.to_string())
                 }
-            } else {
-                InletStatus::new(
-                    &info.bind_addr,
-                    None,
-                    alias,
-                    None,
-                    None,
-                    connection_status,
-                    info.outlet_addr.to_string(),
-                    info.privileged,
-                )
+                _ => None,
             };
-
-            res.push(status);
+            outlets.push(InletBackendStatus {
+                outlet_addr: outlet_addr.to_string(),
+                status: connection_status,
+                outlet_route,
+                connections: backends.load_balancer.connections(index) as u64,
+            });
         }
 
-        res
+        status.worker_addr = backends
+            .inlet
+            .processor_address()
+            .map(|address| address.address().to_string());
+        status.outlet_route = None;
+        status.status = if outlets
+            .iter()
+            .any(|outlet| outlet.status == ConnectionStatus::Up)
+        {
+            ConnectionStatus::Up
+        } else {
+            ConnectionStatus::Down
+        };
+        status.with_backends(backends.load_balancer.strategy(), outlets)
+    }
+
+    /// Return the authority to use for the policies of an inlet to the given outlet:
+    /// the authority of the project of the outlet address if any, or the node project authority
+    pub(crate) async fn outlet_authority(
+        &self,
+        outlet_addr: &MultiAddr,
+    ) -> Result<Option<Identifier>> {
+        if let Some(p) = outlet_addr.first() {
+            if let Some(p) = p.cast::<ProjectProto>() {
+                if let Ok(p) = self.cli_state.projects().get_project_by_name(&p).await {
+                    return Ok(Some(
+                        p.authority_identifier()
+                            .ok_or_else(|| ApiError::core("no authority identifier"))?,
+                    ));
+                }
+            }
+        }
+        Ok(self.project_authority())
     }
 }
@@ -1,8 +1,13 @@
+use std::time::Duration;
+
+use ockam::identity::Identifier;
 use ockam::{route, Result};
+use ockam_abac::PolicyExpression;
 use ockam_core::api::{Error, Response};
+use ockam_multiaddr::MultiAddr;
 use ockam_node::Context;
 
-use crate::nodes::models::portal::{CreateInlet, InletStatus};
+use crate::nodes::models::portal::{CreateInlet, InletStatus, UnixSocketListenAddr};
 use crate::nodes::NodeManagerWorker;
 
 impl NodeManagerWorker {
@@ -30,7 +35,57 @@ impl NodeManagerWorker {
             disable_tcp_fallback,
             privileged,
             tls_certificate_provider,
+            unix_socket,
+            additional_outlet_addrs,
+            load_balancing,
         } = create_inlet;
+
+        if let Some(additional_outlet_addrs) = additional_outlet_addrs {
+            if unix_socket.is_some() || enable_udp_puncture || privileged {
+                return Err(Response::bad_request_no_request(
+                    "Unix sockets, UDP puncture and privileged mode are not supported by inlets with several outlets",
+                ));
+            }
+            let outlet_addrs = [vec![outlet_addr], additional_outlet_addrs].concat();
+            return match self
+                .node_manager
+                .create_balanced_inlet(
+                    ctx,
+                    listen_addr,
+                    outlet_addrs,
+                    load_balancing.unwrap_or_default(),
+                    alias,
+                    policy_expression,
+                    wait_for_outlet_duration,
+                    authorized,
+                    wait_connection,
+                    secure_channel_identifier,
+                    tls_certificate_provider,
+                )
+                .await
+            {
+                Ok(status) => Ok(Response::ok().body(status)),
+                Err(e) => Err(Response::bad_request_no_request(&format!("{e:?}"))),
+            };
+        }
+
+        if let Some(unix_socket) = unix_socket {
+            return self
+                .create_unix_inlet(
+                    ctx,
+                    unix_socket,
+                    outlet_addr,
+                    alias,
+                    policy_expression,
+                    wait_for_outlet_duration,
+                    authorized,
+                    wait_connection,
+                    secure_channel_identifier,
+                    enable_udp_puncture || privileged || tls_certificate_provider.is_some(),
+                )
+                .await;
+        }
+
         match self
             .node_manager
             .create_inlet(
@@ -57,6 +112,58 @@ impl NodeManagerWorker {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
+    async fn create_unix_inlet(
+        &self,
+        ctx: &Context,
+        unix_socket: UnixSocketListenAddr,
+        outlet_addr: MultiAddr,
+        alias: String,
+        policy_expression: Option<PolicyExpression>,
+        wait_for_outlet_duration: Option<Duration>,
+        authorized: Option<Identifier>,
+        wait_connection: bool,
+        secure_channel_identifier: Option<Identifier>,
+        tcp_only_options: bool,
+    ) -> Result<Response<InletStatus>, Response<Error>> {
+        if tcp_only_options {
+            return Err(Response::bad_request_no_request(
+                "UDP puncture, privileged mode and TLS are not supported by Unix socket inlets",
+            ));
+        }
+
+        #[cfg(unix)]
+        {
+            match self
+                .node_manager
+                .create_unix_inlet(
+                    ctx,
+                    unix_socket,
+                    outlet_addr,
+                    alias,
+                    policy_expression,
+                    wait_for_outlet_duration,
+                    authorized,
+                    wait_connection,
+                    secure_channel_identifier,
+                )
+                .await
+            {
+                Ok(status) => Ok(Response::ok().body(status)),
+                Err(e) => Err(Response::bad_request_no_request(&format!("{e:?}"))),
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = (ctx, unix_socket, outlet_addr, alias, policy_expression);
+            let _ = (wait_for_outlet_duration, authorized, wait_connection);
+            let _ = secure_channel_identifier;
+            Err(Response::bad_request_no_request(
+                "Unix socket inlets are not supported on this platform",
+            ))
+        }
+    }
+
     pub(crate) async fn delete_inlet(
         &self,
         alias: &str,
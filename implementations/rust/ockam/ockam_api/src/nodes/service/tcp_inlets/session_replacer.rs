@@ -12,7 +12,6 @@ use ockam::Result;
 use ockam_abac::{Action, PolicyExpression, Resource};
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{async_trait, route, Error, IncomingAccessControl, OutgoingAccessControl, Route};
-use ockam_multiaddr::proto::Project as ProjectProto;
 use ockam_multiaddr::MultiAddr;
 use ockam_node::Context;
 use ockam_transport_tcp::TcpInlet;
@@ -69,30 +68,7 @@ impl InletSessionReplacer {
         Arc<dyn IncomingAccessControl>,
         Arc<dyn OutgoingAccessControl>,
     )> {
-        let authority = {
-            if let Some(p) = self.outlet_addr.first() {
-                if let Some(p) = p.cast::<ProjectProto>() {
-                    if let Ok(p) = node_manager
-                        .cli_state
-                        .projects()
-                        .get_project_by_name(&p)
-                        .await
-                    {
-                        Some(
-                            p.authority_identifier()
-                                .ok_or_else(|| ApiError::core("no authority identifier"))?,
-                        )
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        }
-        .or(node_manager.project_authority());
+        let authority = node_manager.outlet_authority(&self.outlet_addr).await?;
 
         node_manager
             .access_control(
@@ -0,0 +1,206 @@
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use colorful::Colorful;
+use tokio::time::timeout;
+
+use ockam::identity::Identifier;
+use ockam::tcp::{TcpInletOptions, UnixInlet};
+use ockam::Result;
+use ockam_abac::{Action, PolicyExpression, Resource};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, Error, Route};
+use ockam_multiaddr::MultiAddr;
+use ockam_node::Context;
+
+use crate::colors::color_primary;
+use crate::error::ApiError;
+use crate::nodes::connection::Connection;
+use crate::nodes::models::portal::UnixSocketListenAddr;
+use crate::nodes::NodeManager;
+use crate::session::replacer::{
+    CurrentInletStatus, ReplacerOutcome, ReplacerOutputKind, SessionReplacer, MAX_RECOVERY_TIME,
+};
+use crate::{fmt_info, fmt_ok, fmt_warn};
+
+pub(super) struct UnixInletSessionReplacer {
+    pub(super) node_manager: Weak<NodeManager>,
+    pub(super) context: Context,
+    pub(super) unix_socket: UnixSocketListenAddr,
+    pub(super) outlet_addr: MultiAddr,
+    pub(super) authorized: Option<Identifier>,
+    pub(super) wait_for_outlet_duration: Duration,
+    pub(super) resource: Resource,
+    pub(super) policy_expression: Option<PolicyExpression>,
+    pub(super) secure_channel_identifier: Option<Identifier>,
+
+    // current status
+    pub(super) inlet: Option<Arc<UnixInlet>>,
+    pub(super) connection: Option<Connection>,
+}
+
+impl UnixInletSessionReplacer {
+    async fn inlet_options(&self, node_manager: &NodeManager) -> Result<TcpInletOptions> {
+        let authority = node_manager.outlet_authority(&self.outlet_addr).await?;
+        let (incoming_ac, outgoing_ac) = node_manager
+            .access_control(
+                &self.context,
+                authority,
+                self.resource.clone(),
+                Action::HandleMessage,
+                self.policy_expression.clone(),
+            )
+            .await?;
+
+        let options = TcpInletOptions::new()
+            .with_incoming_access_control(incoming_ac)
+            .with_outgoing_access_control(outgoing_ac)
+            .with_unix_socket_owner(self.unix_socket.uid, self.unix_socket.gid);
+
+        Ok(match self.unix_socket.mode {
+            Some(mode) => options.with_unix_socket_mode(mode),
+            None => options,
+        })
+    }
+
+    async fn create_impl(&mut self, node_manager: &NodeManager) -> Result<ReplacerOutcome> {
+        self.pause_inlet();
+        self.close_connection(node_manager);
+
+        let connection = node_manager
+            .make_connection(
+                &self.context,
+                &self.outlet_addr,
+                self.secure_channel_identifier
+                    .clone()
+                    .unwrap_or(node_manager.identifier()),
+                self.authorized.clone(),
+                Some(self.wait_for_outlet_duration),
+            )
+            .await?;
+        let connection = self.connection.insert(connection);
+        let connection_route = connection.route()?;
+        let transport_route = connection.transport_route();
+
+        // Drop the last address as it will be appended automatically under the hood
+        let stripped_route: Route = connection_route.clone().modify().pop_back().into();
+
+        // Finally, attempt to create/update inlet using the new route
+        let inlet_address = match self.inlet.clone() {
+            Some(inlet) => {
+                inlet.unpause(&self.context, stripped_route)?;
+                inlet.processor_address().clone()
+            }
+            None => {
+                let options = self.inlet_options(node_manager).await?;
+                let inlet = node_manager
+                    .tcp_transport
+                    .create_unix_inlet(&self.unix_socket.path, connection_route.clone(), options)
+                    .await?;
+                let inlet_address = inlet.processor_address().clone();
+                self.inlet = Some(Arc::new(inlet));
+                inlet_address
+            }
+        };
+
+        info!(address = %inlet_address, route = %connection_route, "unix inlet restored");
+
+        Ok(ReplacerOutcome {
+            ping_route: transport_route,
+            kind: ReplacerOutputKind::Inlet(CurrentInletStatus {
+                worker: Some(inlet_address),
+                route: connection_route,
+            }),
+        })
+    }
+
+    fn pause_inlet(&mut self) {
+        if let Some(inlet) = self.inlet.as_mut() {
+            inlet.pause();
+        }
+    }
+
+    fn close_inlet(&mut self) {
+        if let Some(inlet) = self.inlet.take() {
+            if let Err(err) = inlet.stop(&self.context) {
+                error!(
+                    ?err,
+                    "Failed to remove unix inlet with address {}",
+                    inlet.processor_address()
+                );
+            }
+        }
+    }
+
+    fn close_connection(&mut self, node_manager: &NodeManager) {
+        if let Some(connection) = self.connection.take() {
+            let result = connection.close(&self.context, node_manager);
+            if let Err(err) = result {
+                error!(?err, "Failed to close connection");
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl SessionReplacer for UnixInletSessionReplacer {
+    async fn create(&mut self) -> Result<ReplacerOutcome> {
+        let node_manager = if let Some(node_manager) = self.node_manager.upgrade() {
+            node_manager
+        } else {
+            return Err(Error::new(
+                Origin::Node,
+                Kind::Cancelled,
+                "Node manager is dropped. Can't create the Unix Inlet.",
+            ));
+        };
+
+        debug!(%self.outlet_addr, "creating new unix inlet");
+
+        match timeout(MAX_RECOVERY_TIME, self.create_impl(&node_manager)).await {
+            Err(_) => {
+                warn!(%self.outlet_addr, "timeout creating new unix inlet");
+                Err(ApiError::core("timeout"))
+            }
+            Ok(Err(e)) => {
+                warn!(%self.outlet_addr, err = %e, "failed to create unix inlet");
+                Err(e)
+            }
+            Ok(Ok(route)) => Ok(route),
+        }
+    }
+
+    async fn close(&mut self) {
+        let node_manager = if let Some(node_manager) = self.node_manager.upgrade() {
+            node_manager
+        } else {
+            warn!("A unix inlet close was issued after the NodeManager shut down, skipping.");
+            return;
+        };
+
+        self.close_inlet();
+        self.close_connection(&node_manager);
+    }
+
+    async fn on_session_down(&self) {
+        if let Some(node_manager) = self.node_manager.upgrade() {
+            node_manager.cli_state.notify_message(
+                fmt_warn!(
+                    "The Unix Inlet at {} lost the connection to the Outlet at {}\n",
+                    color_primary(&self.unix_socket.path),
+                    color_primary(&self.outlet_addr)
+                ) + &fmt_info!("Attempting to reconnect...\n"),
+            );
+        }
+    }
+
+    async fn on_session_replaced(&self) {
+        if let Some(node_manager) = self.node_manager.upgrade() {
+            node_manager.cli_state.notify_message(fmt_ok!(
+                "The Unix Inlet at {} has restored the connection to the Outlet at {}\n",
+                color_primary(&self.unix_socket.path),
+                color_primary(&self.outlet_addr)
+            ));
+        }
+    }
+}
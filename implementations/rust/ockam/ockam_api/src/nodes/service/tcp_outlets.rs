@@ -1,10 +1,14 @@
-use ockam::tcp::TcpOutletOptions;
+use std::sync::Arc;
+
+use ockam::identity::Identifier;
+use ockam::tcp::{TcpOutletAllowlist, TcpOutletDestinationAuthorizer, TcpOutletOptions};
 use ockam::transport::HostnamePort;
 use ockam::{Address, Result};
-use ockam_abac::{Action, PolicyExpression, Resource, ResourceType};
+use ockam_abac::expr::{int, str};
+use ockam_abac::{Action, Env, PolicyAccessControl, PolicyExpression, Resource, ResourceType};
 use ockam_core::api::{Error, Request, RequestHeader, Response};
-use ockam_core::async_trait;
 use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, LocalInfoIdentifier};
 use ockam_node::Context;
 
 use crate::nodes::models::portal::{CreateOutlet, OutletAccessControl, OutletStatus};
@@ -28,21 +32,70 @@ impl NodeManagerWorker {
             policy_expression,
             tls,
             privileged,
+            unix_socket_path,
+            allowed_destinations,
+            destination_policy_expression,
         } = create_outlet;
 
-        match self
-            .node_manager
-            .create_outlet(
-                ctx,
-                hostname_port,
-                tls,
-                worker_addr,
-                reachable_from_default_secure_channel,
-                OutletAccessControl::WithPolicyExpression(policy_expression),
-                privileged,
-            )
-            .await
-        {
+        if let Some(allowed_destinations) = allowed_destinations {
+            if unix_socket_path.is_some() || privileged {
+                return Err(Response::bad_request_no_request(
+                    "An outlet forwarding to dynamic destinations can't connect to a Unix domain socket or be privileged",
+                ));
+            }
+            return match self
+                .node_manager
+                .create_dynamic_outlet(
+                    ctx,
+                    allowed_destinations,
+                    tls,
+                    worker_addr,
+                    reachable_from_default_secure_channel,
+                    OutletAccessControl::WithPolicyExpression(policy_expression),
+                    destination_policy_expression,
+                )
+                .await
+            {
+                Ok(outlet_status) => Ok(Response::ok().body(outlet_status)),
+                Err(e) => Err(Response::bad_request_no_request(&format!("{e:?}"))),
+            };
+        }
+
+        let result = match unix_socket_path {
+            #[cfg(unix)]
+            Some(path) => {
+                self.node_manager
+                    .create_unix_outlet(
+                        ctx,
+                        path,
+                        worker_addr,
+                        reachable_from_default_secure_channel,
+                        OutletAccessControl::WithPolicyExpression(policy_expression),
+                    )
+                    .await
+            }
+            #[cfg(not(unix))]
+            Some(_) => {
+                return Err(Response::bad_request_no_request(
+                    "Unix domain socket outlets are not supported on this platform",
+                ))
+            }
+            None => {
+                self.node_manager
+                    .create_outlet(
+                        ctx,
+                        hostname_port,
+                        tls,
+                        worker_addr,
+                        reachable_from_default_secure_channel,
+                        OutletAccessControl::WithPolicyExpression(policy_expression),
+                        privileged,
+                    )
+                    .await
+            }
+        };
+
+        match result {
             Ok(outlet_status) => Ok(Response::ok().body(outlet_status)),
             Err(e) => Err(Response::bad_request_no_request(&format!("{e:?}"))),
         }
@@ -54,12 +107,16 @@ impl NodeManagerWorker {
     ) -> Result<Response<OutletStatus>, Response<Error>> {
         match self.node_manager.delete_outlet(worker_addr).await {
             Ok(res) => match res {
-                Some(outlet_info) => Ok(Response::ok().body(OutletStatus::new(
-                    outlet_info.to,
-                    outlet_info.worker_addr.clone(),
-                    None,
-                    outlet_info.privileged,
-                ))),
+                Some(outlet_info) => Ok(Response::ok().body(
+                    OutletStatus::new(
+                        outlet_info.to,
+                        outlet_info.worker_addr.clone(),
+                        None,
+                        outlet_info.privileged,
+                    )
+                    .with_unix_socket_path(outlet_info.unix_socket_path)
+                    .with_allowed_destinations(outlet_info.allowed_destinations),
+                )),
                 None => Err(Response::bad_request_no_request(&format!(
                     "Outlet with address {worker_addr} not found"
                 ))),
@@ -114,44 +171,15 @@ impl NodeManager {
             ));
         }
 
-        let (incoming_ac, outgoing_ac) = match access_control {
-            OutletAccessControl::AccessControl((incoming_ac, outgoing_ac)) => {
-                (incoming_ac, outgoing_ac)
-            }
-            OutletAccessControl::WithPolicyExpression(expression) => {
-                self.access_control(
-                    ctx,
-                    self.project_authority(),
-                    Resource::new(worker_addr.address(), ResourceType::TcpOutlet),
-                    Action::HandleMessage,
-                    expression,
-                )
-                .await?
-            }
-        };
-
-        let options = {
-            let mut options = TcpOutletOptions::new()
-                .with_incoming_access_control(incoming_ac)
-                .with_outgoing_access_control(outgoing_ac)
-                .with_tls(tls);
-            if self.project_authority().is_none() {
-                for api_transport_flow_control_id in &self.api_transport_flow_control_ids {
-                    options = options.as_consumer(api_transport_flow_control_id)
-                }
-            };
-            if reachable_from_default_secure_channel {
-                // Accept messages from the default secure channel listener
-                if let Some(flow_control_id) = ctx
-                    .flow_controls()
-                    .get_flow_control_with_spawner(&DefaultAddress::SECURE_CHANNEL_LISTENER.into())
-                {
-                    options = options.as_consumer(&flow_control_id)
-                }
-            }
-
-            options
-        };
+        let options = self
+            .outlet_options(
+                ctx,
+                &worker_addr,
+                reachable_from_default_secure_channel,
+                access_control,
+            )
+            .await?
+            .with_tls(tls);
 
         let res = if privileged {
             #[cfg(privileged_portals_support)]
@@ -199,6 +227,196 @@ impl NodeManager {
         })
     }
 
+    /// Create an outlet connecting to a Unix domain socket
+    #[cfg(unix)]
+    #[instrument(skip_all)]
+    pub async fn create_unix_outlet(
+        &self,
+        ctx: &Context,
+        path: String,
+        worker_addr: Option<Address>,
+        reachable_from_default_secure_channel: bool,
+        access_control: OutletAccessControl,
+    ) -> Result<OutletStatus> {
+        let worker_addr = self.registry.outlets.generate_worker_addr(worker_addr);
+
+        debug!(%path, address = %worker_addr, "creating unix outlet");
+
+        // Check registry for a duplicated key
+        if self.registry.outlets.contains_key(&worker_addr) {
+            let message = format!("An outlet with address '{worker_addr}' already exists");
+            return Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::AlreadyExists,
+                message,
+            ));
+        }
+
+        let options = self
+            .outlet_options(
+                ctx,
+                &worker_addr,
+                reachable_from_default_secure_channel,
+                access_control,
+            )
+            .await?;
+
+        if let Err(e) = self
+            .tcp_transport
+            .create_unix_outlet(worker_addr.clone(), &path, options)
+        {
+            warn!(%path, err = %e, "Failed to create unix outlet");
+            let message = format!("Failed to create outlet: {}", e);
+            return Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::Internal,
+                message,
+            ));
+        }
+
+        // The outlet has no hostname and port to connect to, and is not persisted
+        // with the TCP outlets of the node
+        let to = HostnamePort::localhost(0);
+        self.registry.outlets.insert(
+            worker_addr.clone(),
+            OutletInfo::new(to.clone(), Some(&worker_addr), false).with_unix_socket_path(&path),
+        );
+        info!(%path, address = %worker_addr, "unix outlet created");
+        Ok(OutletStatus::new(to, worker_addr, None, false).with_unix_socket_path(Some(path)))
+    }
+
+    /// Create an outlet connecting to the destination requested by each inlet,
+    /// when it is accepted by one of the allowed destinations and by the destination policy
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip_all)]
+    pub async fn create_dynamic_outlet(
+        &self,
+        ctx: &Context,
+        allowed_destinations: Vec<String>,
+        tls: bool,
+        worker_addr: Option<Address>,
+        reachable_from_default_secure_channel: bool,
+        access_control: OutletAccessControl,
+        destination_policy_expression: Option<PolicyExpression>,
+    ) -> Result<OutletStatus> {
+        let worker_addr = self.registry.outlets.generate_worker_addr(worker_addr);
+
+        debug!(?allowed_destinations, address = %worker_addr, "creating dynamic outlet");
+
+        // Check registry for a duplicated key
+        if self.registry.outlets.contains_key(&worker_addr) {
+            let message = format!("An outlet with address '{worker_addr}' already exists");
+            return Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::AlreadyExists,
+                message,
+            ));
+        }
+
+        let allowlist = TcpOutletAllowlist::new(&allowed_destinations)?;
+        let mut options = self
+            .outlet_options(
+                ctx,
+                &worker_addr,
+                reachable_from_default_secure_channel,
+                access_control,
+            )
+            .await?
+            .with_tls(tls);
+
+        // The destinations have their own resource, so that their policy is distinct from
+        // the policy of the messages sent to the outlet
+        if let Some(expression) = destination_policy_expression {
+            let policy_access_control = self
+                .policy_access_control(
+                    self.project_authority(),
+                    Resource::new(
+                        Self::outlet_destination_resource_name(&worker_addr),
+                        ResourceType::TcpOutlet,
+                    ),
+                    Action::HandleMessage,
+                    Some(expression),
+                )
+                .await?;
+            options = options.with_destination_authorizer(Arc::new(OutletDestinationPolicy::new(
+                policy_access_control,
+            )));
+        }
+
+        if let Err(e) =
+            self.tcp_transport
+                .create_dynamic_outlet(worker_addr.clone(), allowlist, options)
+        {
+            warn!(err = %e, "Failed to create dynamic outlet");
+            let message = format!("Failed to create outlet: {}", e);
+            return Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::Internal,
+                message,
+            ));
+        }
+
+        // The outlet has no hostname and port to connect to, and is not persisted
+        // with the TCP outlets of the node
+        let to = HostnamePort::localhost(0);
+        self.registry.outlets.insert(
+            worker_addr.clone(),
+            OutletInfo::new(to.clone(), Some(&worker_addr), false)
+                .with_allowed_destinations(allowed_destinations.clone()),
+        );
+        info!(?allowed_destinations, address = %worker_addr, "dynamic outlet created");
+        Ok(OutletStatus::new(to, worker_addr, None, false)
+            .with_allowed_destinations(Some(allowed_destinations)))
+    }
+
+    fn outlet_destination_resource_name(worker_addr: &Address) -> String {
+        format!("{}/destination", worker_addr.address())
+    }
+
+    async fn outlet_options(
+        &self,
+        ctx: &Context,
+        worker_addr: &Address,
+        reachable_from_default_secure_channel: bool,
+        access_control: OutletAccessControl,
+    ) -> Result<TcpOutletOptions> {
+        let (incoming_ac, outgoing_ac) = match access_control {
+            OutletAccessControl::AccessControl((incoming_ac, outgoing_ac)) => {
+                (incoming_ac, outgoing_ac)
+            }
+            OutletAccessControl::WithPolicyExpression(expression) => {
+                self.access_control(
+                    ctx,
+                    self.project_authority(),
+                    Resource::new(worker_addr.address(), ResourceType::TcpOutlet),
+                    Action::HandleMessage,
+                    expression,
+                )
+                .await?
+            }
+        };
+
+        let mut options = TcpOutletOptions::new()
+            .with_incoming_access_control(incoming_ac)
+            .with_outgoing_access_control(outgoing_ac);
+        if self.project_authority().is_none() {
+            for api_transport_flow_control_id in &self.api_transport_flow_control_ids {
+                options = options.as_consumer(api_transport_flow_control_id)
+            }
+        };
+        if reachable_from_default_secure_channel {
+            // Accept messages from the default secure channel listener
+            if let Some(flow_control_id) = ctx
+                .flow_controls()
+                .get_flow_control_with_spawner(&DefaultAddress::SECURE_CHANNEL_LISTENER.into())
+            {
+                options = options.as_consumer(&flow_control_id)
+            }
+        }
+
+        Ok(options)
+    }
+
     pub async fn delete_outlet(&self, worker_addr: &Address) -> Result<Option<OutletInfo>> {
         info!(%worker_addr, "Handling request to delete outlet portal");
         if let Some(deleted_outlet) = self.registry.outlets.remove(worker_addr) {
@@ -210,6 +428,11 @@ impl NodeManager {
             self.resources()
                 .delete_resource(&worker_addr.address().into())
                 .await?;
+            if deleted_outlet.allowed_destinations.is_some() {
+                self.resources()
+                    .delete_resource(&Self::outlet_destination_resource_name(worker_addr).into())
+                    .await?;
+            }
 
             if let Err(e) = self.tcp_transport.stop_outlet(&deleted_outlet.worker_addr) {
                 warn!(%worker_addr, %e, "Failed to stop outlet worker");
@@ -226,12 +449,16 @@ impl NodeManager {
         info!(%worker_addr, "Handling request to show outlet portal");
         if let Some(outlet_to_show) = self.registry.outlets.get(worker_addr) {
             debug!(%worker_addr, "Outlet not found in node registry");
-            Some(OutletStatus::new(
-                outlet_to_show.to,
-                outlet_to_show.worker_addr.clone(),
-                None,
-                outlet_to_show.privileged,
-            ))
+            Some(
+                OutletStatus::new(
+                    outlet_to_show.to,
+                    outlet_to_show.worker_addr.clone(),
+                    None,
+                    outlet_to_show.privileged,
+                )
+                .with_unix_socket_path(outlet_to_show.unix_socket_path)
+                .with_allowed_destinations(outlet_to_show.allowed_destinations),
+            )
         } else {
             error!(%worker_addr, "Outlet not found in the node registry");
             None
@@ -239,6 +466,43 @@ impl NodeManager {
     }
 }
 
+/// Authorize the destinations requested to a dynamic outlet with a policy where
+/// the `resource.hostname` and `resource.port` attributes are the requested destination
+#[derive(Debug)]
+struct OutletDestinationPolicy {
+    policy_access_control: PolicyAccessControl,
+}
+
+impl OutletDestinationPolicy {
+    fn new(policy_access_control: PolicyAccessControl) -> Self {
+        Self {
+            policy_access_control,
+        }
+    }
+}
+
+#[async_trait]
+impl TcpOutletDestinationAuthorizer for OutletDestinationPolicy {
+    async fn is_authorized(
+        &self,
+        their_identifier: Option<&LocalInfoIdentifier>,
+        destination: &HostnamePort,
+    ) -> Result<bool> {
+        let identifier = match their_identifier {
+            Some(identifier) => Identifier::from(identifier.clone()),
+            None => return Ok(false),
+        };
+        let mut env = Env::new();
+        env.put("resource.hostname", str(destination.hostname()));
+        env.put("resource.port", int(destination.port()));
+        env.put("resource.destination", str(destination.to_string()));
+        self.policy_access_control
+            .with_environment(env)
+            .is_identity_authorized(&identifier)
+            .await
+    }
+}
+
 #[async_trait]
 pub trait Outlets {
     async fn create_outlet(
@@ -250,6 +514,24 @@ pub trait Outlets {
         policy_expression: Option<PolicyExpression>,
         privileged: bool,
     ) -> miette::Result<OutletStatus>;
+
+    async fn create_unix_outlet(
+        &self,
+        ctx: &Context,
+        path: &str,
+        from: Option<&Address>,
+        policy_expression: Option<PolicyExpression>,
+    ) -> miette::Result<OutletStatus>;
+
+    async fn create_dynamic_outlet(
+        &self,
+        ctx: &Context,
+        allowed_destinations: Vec<String>,
+        tls: bool,
+        from: Option<&Address>,
+        policy_expression: Option<PolicyExpression>,
+        destination_policy_expression: Option<PolicyExpression>,
+    ) -> miette::Result<OutletStatus>;
 }
 
 #[async_trait]
@@ -272,4 +554,54 @@ impl Outlets for BackgroundNodeClient {
         let result: OutletStatus = self.ask(ctx, req).await?;
         Ok(result)
     }
+
+    #[instrument(skip_all, fields(path = % path, from = ? from))]
+    async fn create_unix_outlet(
+        &self,
+        ctx: &Context,
+        path: &str,
+        from: Option<&Address>,
+        policy_expression: Option<PolicyExpression>,
+    ) -> miette::Result<OutletStatus> {
+        // The hostname and port are not used by a Unix Outlet
+        let mut payload = CreateOutlet::new(
+            HostnamePort::localhost(0),
+            false,
+            from.cloned(),
+            true,
+            false,
+        );
+        payload.set_unix_socket_path(path);
+        if let Some(policy_expression) = policy_expression {
+            payload.set_policy_expression(policy_expression);
+        }
+        let req = Request::post("/node/outlet").body(payload);
+        let result: OutletStatus = self.ask(ctx, req).await?;
+        Ok(result)
+    }
+
+    #[instrument(skip_all, fields(from = ? from))]
+    async fn create_dynamic_outlet(
+        &self,
+        ctx: &Context,
+        allowed_destinations: Vec<String>,
+        tls: bool,
+        from: Option<&Address>,
+        policy_expression: Option<PolicyExpression>,
+        destination_policy_expression: Option<PolicyExpression>,
+    ) -> miette::Result<OutletStatus> {
+        // The hostname and port are not used by a dynamic Outlet
+        let mut payload =
+            CreateOutlet::new(HostnamePort::localhost(0), tls, from.cloned(), true, false);
+        payload.set_allowed_destinations(allowed_destinations);
+        if let Some(policy_expression) = policy_expression {
+            payload.set_policy_expression(policy_expression);
+        }
+        if let Some(destination_policy_expression) = destination_policy_expression {
+            payload.set_destination_policy_expression(destination_policy_expression);
+        }
+        let req = Request::post("/node/outlet").body(payload);
+        let result: OutletStatus = self.ask(ctx, req).await?;
+        Ok(result)
+    }
 }
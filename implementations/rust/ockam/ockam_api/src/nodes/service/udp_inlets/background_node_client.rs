@@ -0,0 +1,83 @@
+use ockam::identity::Identifier;
+use ockam_abac::PolicyExpression;
+use ockam_core::api::{Reply, Request};
+use ockam_core::async_trait;
+use ockam_multiaddr::MultiAddr;
+use ockam_node::Context;
+use ockam_transport_core::HostnamePort;
+use std::time::Duration;
+
+use crate::nodes::models::portal::{CreateUdpInlet, InletStatus};
+use crate::nodes::BackgroundNodeClient;
+
+#[async_trait]
+pub trait UdpInlets {
+    #[allow(clippy::too_many_arguments)]
+    async fn create_udp_inlet(
+        &self,
+        ctx: &Context,
+        listen_addr: &HostnamePort,
+        outlet_addr: &MultiAddr,
+        alias: &str,
+        authorized_identifier: &Option<Identifier>,
+        policy_expression: &Option<PolicyExpression>,
+        wait_for_outlet_timeout: Duration,
+        wait_connection: bool,
+        idle_timeout: Option<Duration>,
+    ) -> miette::Result<Reply<InletStatus>>;
+
+    async fn show_udp_inlet(
+        &self,
+        ctx: &Context,
+        alias: &str,
+    ) -> miette::Result<Reply<InletStatus>>;
+
+    async fn delete_udp_inlet(&self, ctx: &Context, alias: &str) -> miette::Result<Reply<()>>;
+}
+
+#[async_trait]
+impl UdpInlets for BackgroundNodeClient {
+    async fn create_udp_inlet(
+        &self,
+        ctx: &Context,
+        listen_addr: &HostnamePort,
+        outlet_addr: &MultiAddr,
+        alias: &str,
+        authorized_identifier: &Option<Identifier>,
+        policy_expression: &Option<PolicyExpression>,
+        wait_for_outlet_timeout: Duration,
+        wait_connection: bool,
+        idle_timeout: Option<Duration>,
+    ) -> miette::Result<Reply<InletStatus>> {
+        let mut payload = CreateUdpInlet::new(
+            listen_addr.clone(),
+            outlet_addr.clone(),
+            alias.into(),
+            authorized_identifier.clone(),
+            wait_connection,
+        );
+        if let Some(e) = policy_expression.as_ref() {
+            payload.set_policy_expression(e.clone())
+        }
+        if let Some(idle_timeout) = idle_timeout {
+            payload.set_idle_timeout(idle_timeout)
+        }
+        payload.set_wait_ms(wait_for_outlet_timeout.as_millis() as u64);
+        let request = Request::post("/node/udp_inlet").body(payload);
+        self.ask_and_get_reply(ctx, request).await
+    }
+
+    async fn show_udp_inlet(
+        &self,
+        ctx: &Context,
+        alias: &str,
+    ) -> miette::Result<Reply<InletStatus>> {
+        let request = Request::get(format!("/node/udp_inlet/{alias}"));
+        self.ask_and_get_reply(ctx, request).await
+    }
+
+    async fn delete_udp_inlet(&self, ctx: &Context, alias: &str) -> miette::Result<Reply<()>> {
+        let request = Request::delete(format!("/node/udp_inlet/{alias}"));
+        self.tell_and_get_reply(ctx, request).await
+    }
+}
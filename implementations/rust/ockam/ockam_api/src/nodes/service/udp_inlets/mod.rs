@@ -0,0 +1,7 @@
+mod background_node_client;
+mod node_manager;
+mod node_manager_worker;
+mod session_replacer;
+
+pub use background_node_client::*;
+use session_replacer::*;
@@ -0,0 +1,230 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use ockam::identity::Identifier;
+use ockam::Result;
+use ockam_abac::{PolicyExpression, Resource, ResourceType};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::TryClone;
+use ockam_multiaddr::MultiAddr;
+use ockam_node::compat::asynchronous::Mutex;
+use ockam_node::Context;
+use ockam_transport_core::HostnamePort;
+
+use crate::address::get_free_udp_address_for;
+use crate::nodes::models::portal::InletStatus;
+use crate::nodes::registry::InletInfo;
+use crate::nodes::service::udp_inlets::UdpInletSessionReplacer;
+use crate::nodes::NodeManager;
+use crate::session::connection_status::ConnectionStatus;
+use crate::session::replacer::{ReplacerOutputKind, SessionReplacer, MAX_CONNECT_TIME};
+use crate::session::session::Session;
+
+impl NodeManager {
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip_all)]
+    pub async fn create_udp_inlet(
+        self: &Arc<Self>,
+        ctx: &Context,
+        listen_address: HostnamePort,
+        outlet_address: MultiAddr,
+        alias: String,
+        policy_expression: Option<PolicyExpression>,
+        wait_for_outlet_duration: Option<Duration>,
+        authorized: Option<Identifier>,
+        wait_connection: bool,
+        idle_timeout: Option<Duration>,
+    ) -> Result<InletStatus> {
+        debug! {
+            %listen_address,
+            %outlet_address,
+            %alias,
+            "creating udp inlet"
+        }
+
+        // the port could be zero, to simplify the following code we
+        // resolve the address to a full socket address
+        let socket_addr = ockam_node::compat::asynchronous::resolve_peer(&listen_address).await?;
+        let listen_addr = if listen_address.port() == 0 {
+            get_free_udp_address_for(&socket_addr.ip().to_string())
+                .map_err(|err| ockam_core::Error::new(Origin::Transport, Kind::Invalid, err))?
+        } else {
+            socket_addr
+        };
+
+        // Check registry for duplicated alias or bind address
+        {
+            let registry = &self.registry.udp_inlets;
+
+            // Check that there is no entry in the registry with the same alias
+            if registry.contains_key(&alias) {
+                let message = format!("A UDP inlet with alias '{alias}' already exists");
+                return Err(ockam_core::Error::new(
+                    Origin::Node,
+                    Kind::AlreadyExists,
+                    message,
+                ));
+            }
+
+            // Check that there is no entry in the registry with the same UDP bind address
+            if registry
+                .values()
+                .iter()
+                .any(|inlet| inlet.bind_addr == listen_addr.to_string())
+            {
+                let message =
+                    format!("A UDP inlet with bind udp address '{listen_addr}' already exists");
+                return Err(ockam_core::Error::new(
+                    Origin::Node,
+                    Kind::AlreadyExists,
+                    message,
+                ));
+            }
+        }
+
+        let replacer = UdpInletSessionReplacer {
+            node_manager: Arc::downgrade(self),
+            context: ctx.try_clone()?,
+            listen_addr,
+            outlet_addr: outlet_address.clone(),
+            authorized,
+            wait_for_outlet_duration: wait_for_outlet_duration.unwrap_or(MAX_CONNECT_TIME),
+            resource: Resource::new(alias.clone(), ResourceType::TcpInlet),
+            policy_expression,
+            idle_timeout,
+            inlet: None,
+            connection: None,
+        };
+
+        let replacer: Arc<Mutex<dyn SessionReplacer>> = Arc::new(Mutex::new(replacer));
+        let mut session = Session::create(ctx, replacer, None)?;
+
+        let outcome = if wait_connection {
+            let result = session
+                .initial_connect()
+                .await
+                .map(|outcome| match outcome {
+                    ReplacerOutputKind::Inlet(status) => status,
+                    _ => {
+                        panic!("Unexpected outcome: {:?}", outcome)
+                    }
+                });
+
+            match result {
+                Ok(status) => Some(status),
+                Err(err) => {
+                    warn!("Failed to create udp inlet: {err}");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let connection_status = session.connection_status();
+
+        session.start_monitoring()?;
+
+        self.registry.udp_inlets.insert(
+            alias.clone(),
+            InletInfo::new(
+                &listen_addr.to_string(),
+                outlet_address.clone(),
+                session,
+                false,
+            ),
+        );
+
+        info! {
+            %listen_address,
+            %outlet_address,
+            %alias,
+            "udp inlet created"
+        }
+
+        Ok(InletStatus::new(
+            listen_addr.to_string(),
+            outcome
+                .clone()
+                .and_then(|s| s.worker.map(|address| address.address().to_string())),
+            &alias,
+            None,
+            outcome.map(|s| s.route.to_string()),
+            connection_status,
+            outlet_address.to_string(),
+            false,
+        ))
+    }
+
+    pub async fn delete_udp_inlet(&self, alias: &str) -> Result<InletStatus> {
+        info!(%alias, "Handling request to delete udp inlet portal");
+        if let Some(inlet_to_delete) = self.registry.udp_inlets.remove(alias) {
+            debug!(%alias, "Successfully removed udp inlet from node registry");
+            inlet_to_delete.session.lock().await.stop().await;
+            self.resources().delete_resource(&alias.into()).await?;
+            Ok(InletStatus::new(
+                inlet_to_delete.bind_addr,
+                None,
+                alias,
+                None,
+                None,
+                ConnectionStatus::Down,
+                inlet_to_delete.outlet_addr.to_string(),
+                false,
+            ))
+        } else {
+            error!(%alias, "UDP inlet not found in the node registry");
+            let message = format!("UDP inlet with alias {alias} not found");
+            Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::NotFound,
+                message,
+            ))
+        }
+    }
+
+    pub async fn show_udp_inlet(&self, alias: &str) -> Option<InletStatus> {
+        match self.registry.udp_inlets.get(alias) {
+            Some(inlet_info) => Some(Self::udp_inlet_status(alias, &inlet_info).await),
+            None => {
+                error!(%alias, "UDP inlet not found in the node registry");
+                None
+            }
+        }
+    }
+
+    pub async fn list_udp_inlets(&self) -> Vec<InletStatus> {
+        let mut res = vec![];
+        for (alias, info) in self.registry.udp_inlets.entries() {
+            res.push(Self::udp_inlet_status(&alias, &info).await);
+        }
+        res
+    }
+
+    async fn udp_inlet_status(alias: &str, info: &InletInfo) -> InletStatus {
+        let session = info.session.lock().await;
+        let connection_status = session.connection_status();
+        let outcome = session.last_outcome();
+        drop(session);
+
+        let (worker_addr, outlet_route) = match outcome {
+            Some(ReplacerOutputKind::Inlet(status)) => (
+                status.worker.map(|address| address.address().to_string()),
+                Some(status.route.to_string()),
+            ),
+            Some(outcome) => panic!("Unexpected outcome: {:?}", outcome),
+            None => (None, None),
+        };
+
+        InletStatus::new(
+            &info.bind_addr,
+            worker_addr,
+            alias,
+            None,
+            outlet_route,
+            connection_status,
+            info.outlet_addr.to_string(),
+            false,
+        )
+    }
+}
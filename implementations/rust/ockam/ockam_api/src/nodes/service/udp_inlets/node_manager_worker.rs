@@ -0,0 +1,73 @@
+use ockam::Result;
+use ockam_core::api::{Error, Response};
+use ockam_node::Context;
+
+use crate::nodes::models::portal::{CreateUdpInlet, InletStatus};
+use crate::nodes::NodeManagerWorker;
+
+impl NodeManagerWorker {
+    pub(crate) async fn get_udp_inlets(
+        &self,
+    ) -> Result<Response<Vec<InletStatus>>, Response<Error>> {
+        let inlets = self.node_manager.list_udp_inlets().await;
+        Ok(Response::ok().body(inlets))
+    }
+
+    #[instrument(skip_all)]
+    pub(crate) async fn create_udp_inlet(
+        &self,
+        ctx: &Context,
+        create_inlet: CreateUdpInlet,
+    ) -> Result<Response<InletStatus>, Response<Error>> {
+        let CreateUdpInlet {
+            listen_addr,
+            outlet_addr,
+            alias,
+            authorized,
+            wait_for_outlet_duration,
+            policy_expression,
+            wait_connection,
+            idle_timeout,
+        } = create_inlet;
+        match self
+            .node_manager
+            .create_udp_inlet(
+                ctx,
+                listen_addr,
+                outlet_addr,
+                alias,
+                policy_expression,
+                wait_for_outlet_duration,
+                authorized,
+                wait_connection,
+                idle_timeout,
+            )
+            .await
+        {
+            Ok(status) => Ok(Response::ok().body(status)),
+            Err(e) => Err(Response::bad_request_no_request(&format!("{e:?}"))),
+        }
+    }
+
+    pub(crate) async fn delete_udp_inlet(
+        &self,
+        alias: &str,
+    ) -> Result<Response<InletStatus>, Response<Error>> {
+        match self.node_manager.delete_udp_inlet(alias).await {
+            Ok(status) => Ok(Response::ok().body(status)),
+            Err(e) => Err(Response::bad_request_no_request(&format!("{e:?}"))),
+        }
+    }
+
+    pub(crate) async fn show_udp_inlet(
+        &self,
+        alias: &str,
+    ) -> Result<Response<InletStatus>, Response<Error>> {
+        match self.node_manager.show_udp_inlet(alias).await {
+            Some(inlet) => Ok(Response::ok().body(inlet)),
+            None => Err(Response::not_found_no_request(&format!(
+                "UDP inlet with alias {alias} not found"
+            ))),
+        }
+    }
+}
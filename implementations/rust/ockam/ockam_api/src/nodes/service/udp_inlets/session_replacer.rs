@@ -0,0 +1,206 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use colorful::Colorful;
+use tokio::time::timeout;
+
+use ockam::identity::Identifier;
+use ockam::udp::{UdpInlet, UdpInletOptions};
+use ockam::Result;
+use ockam_abac::{Action, PolicyExpression, Resource};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, Error, Route};
+use ockam_multiaddr::MultiAddr;
+use ockam_node::Context;
+
+use crate::colors::color_primary;
+use crate::error::ApiError;
+use crate::nodes::connection::Connection;
+use crate::nodes::NodeManager;
+use crate::session::replacer::{
+    CurrentInletStatus, ReplacerOutcome, ReplacerOutputKind, SessionReplacer, MAX_RECOVERY_TIME,
+};
+use crate::{fmt_info, fmt_ok, fmt_warn};
+
+pub(super) struct UdpInletSessionReplacer {
+    pub(super) node_manager: Weak<NodeManager>,
+    pub(super) context: Context,
+    pub(super) listen_addr: SocketAddr,
+    pub(super) outlet_addr: MultiAddr,
+    pub(super) authorized: Option<Identifier>,
+    pub(super) wait_for_outlet_duration: Duration,
+    pub(super) resource: Resource,
+    pub(super) policy_expression: Option<PolicyExpression>,
+    pub(super) idle_timeout: Option<Duration>,
+
+    // current status
+    pub(super) inlet: Option<Arc<UdpInlet>>,
+    pub(super) connection: Option<Connection>,
+}
+
+impl UdpInletSessionReplacer {
+    async fn inlet_options(&self, node_manager: &NodeManager) -> Result<UdpInletOptions> {
+        let authority = node_manager.outlet_authority(&self.outlet_addr).await?;
+        let (incoming_ac, outgoing_ac) = node_manager
+            .access_control(
+                &self.context,
+                authority,
+                self.resource.clone(),
+                Action::HandleMessage,
+                self.policy_expression.clone(),
+            )
+            .await?;
+
+        let options = UdpInletOptions::new()
+            .with_incoming_access_control(incoming_ac)
+            .with_outgoing_access_control(outgoing_ac);
+
+        Ok(match self.idle_timeout {
+            Some(idle_timeout) => options.with_idle_timeout(idle_timeout),
+            None => options,
+        })
+    }
+
+    async fn create_impl(&mut self, node_manager: &NodeManager) -> Result<ReplacerOutcome> {
+        self.pause_inlet();
+        self.close_connection(node_manager);
+
+        let connection = node_manager
+            .make_connection(
+                &self.context,
+                &self.outlet_addr,
+                node_manager.identifier(),
+                self.authorized.clone(),
+                Some(self.wait_for_outlet_duration),
+            )
+            .await?;
+        let connection = self.connection.insert(connection);
+        let connection_route = connection.route()?;
+        let transport_route = connection.transport_route();
+
+        // Drop the last address as it will be appended automatically under the hood
+        let stripped_route: Route = connection_route.clone().modify().pop_back().into();
+
+        // Finally, attempt to create/update inlet using the new route
+        let inlet_address = match self.inlet.clone() {
+            Some(inlet) => {
+                inlet.unpause(stripped_route)?;
+                inlet.processor_address().clone()
+            }
+            None => {
+                let options = self.inlet_options(node_manager).await?;
+                let inlet = UdpInlet::create(
+                    &self.context,
+                    self.listen_addr,
+                    connection_route.clone(),
+                    options,
+                )
+                .await?;
+                let inlet_address = inlet.processor_address().clone();
+                self.inlet = Some(Arc::new(inlet));
+                inlet_address
+            }
+        };
+
+        info!(address = %inlet_address, route = %connection_route, "udp inlet restored");
+
+        Ok(ReplacerOutcome {
+            ping_route: transport_route,
+            kind: ReplacerOutputKind::Inlet(CurrentInletStatus {
+                worker: Some(inlet_address),
+                route: connection_route,
+            }),
+        })
+    }
+
+    fn pause_inlet(&mut self) {
+        if let Some(inlet) = self.inlet.as_mut() {
+            inlet.pause();
+        }
+    }
+
+    fn close_inlet(&mut self) {
+        if let Some(inlet) = self.inlet.take() {
+            if let Err(err) = inlet.stop(&self.context) {
+                error!(
+                    ?err,
+                    "Failed to remove udp inlet with address {}",
+                    inlet.processor_address()
+                );
+            }
+        }
+    }
+
+    fn close_connection(&mut self, node_manager: &NodeManager) {
+        if let Some(connection) = self.connection.take() {
+            let result = connection.close(&self.context, node_manager);
+            if let Err(err) = result {
+                error!(?err, "Failed to close connection");
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl SessionReplacer for UdpInletSessionReplacer {
+    async fn create(&mut self) -> Result<ReplacerOutcome> {
+        let node_manager = if let Some(node_manager) = self.node_manager.upgrade() {
+            node_manager
+        } else {
+            return Err(Error::new(
+                Origin::Node,
+                Kind::Cancelled,
+                "Node manager is dropped. Can't create the UDP Inlet.",
+            ));
+        };
+
+        debug!(%self.outlet_addr, "creating new udp inlet");
+
+        match timeout(MAX_RECOVERY_TIME, self.create_impl(&node_manager)).await {
+            Err(_) => {
+                warn!(%self.outlet_addr, "timeout creating new udp inlet");
+                Err(ApiError::core("timeout"))
+            }
+            Ok(Err(e)) => {
+                warn!(%self.outlet_addr, err = %e, "failed to create udp inlet");
+                Err(e)
+            }
+            Ok(Ok(route)) => Ok(route),
+        }
+    }
+
+    async fn close(&mut self) {
+        let node_manager = if let Some(node_manager) = self.node_manager.upgrade() {
+            node_manager
+        } else {
+            warn!("A udp inlet close was issued after the NodeManager shut down, skipping.");
+            return;
+        };
+
+        self.close_inlet();
+        self.close_connection(&node_manager);
+    }
+
+    async fn on_session_down(&self) {
+        if let Some(node_manager) = self.node_manager.upgrade() {
+            node_manager.cli_state.notify_message(
+                fmt_warn!(
+                    "The UDP Inlet at {} lost the connection to the UDP Outlet at {}\n",
+                    color_primary(self.listen_addr.to_string()),
+                    color_primary(&self.outlet_addr)
+                ) + &fmt_info!("Attempting to reconnect...\n"),
+            );
+        }
+    }
+
+    async fn on_session_replaced(&self) {
+        if let Some(node_manager) = self.node_manager.upgrade() {
+            node_manager.cli_state.notify_message(fmt_ok!(
+                "The UDP Inlet at {} has restored the connection to the UDP Outlet at {}\n",
+                color_primary(self.listen_addr.to_string()),
+                color_primary(&self.outlet_addr)
+            ));
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use std::time::Duration;
+
+use ockam::transport::HostnamePort;
+use ockam::udp::{UdpOutlet, UdpOutletOptions};
+use ockam::{Address, Result};
+use ockam_abac::{Action, PolicyExpression, Resource, ResourceType};
+use ockam_core::api::{Error, Request, RequestHeader, Response};
+use ockam_core::async_trait;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_node::Context;
+
+use crate::nodes::models::portal::{CreateUdpOutlet, OutletStatus};
+use crate::nodes::registry::OutletInfo;
+use crate::nodes::service::default_address::DefaultAddress;
+use crate::nodes::BackgroundNodeClient;
+
+use super::{NodeManager, NodeManagerWorker};
+
+impl NodeManagerWorker {
+    #[instrument(skip_all)]
+    pub(super) async fn create_udp_outlet(
+        &self,
+        ctx: &Context,
+        create_outlet: CreateUdpOutlet,
+    ) -> Result<Response<OutletStatus>, Response<Error>> {
+        let CreateUdpOutlet {
+            hostname_port,
+            worker_addr,
+            policy_expression,
+            idle_timeout,
+        } = create_outlet;
+
+        match self
+            .node_manager
+            .create_udp_outlet(
+                ctx,
+                hostname_port,
+                worker_addr,
+                policy_expression,
+                idle_timeout,
+            )
+            .await
+        {
+            Ok(outlet_status) => Ok(Response::ok().body(outlet_status)),
+            Err(e) => Err(Response::bad_request_no_request(&format!("{e:?}"))),
+        }
+    }
+
+    pub(super) async fn delete_udp_outlet(
+        &self,
+        ctx: &Context,
+        worker_addr: &Address,
+    ) -> Result<Response<OutletStatus>, Response<Error>> {
+        match self.node_manager.delete_udp_outlet(ctx, worker_addr).await {
+            Ok(res) => match res {
+                Some(outlet_info) => Ok(Response::ok().body(OutletStatus::new(
+                    outlet_info.to,
+                    outlet_info.worker_addr.clone(),
+                    None,
+                    false,
+                ))),
+                None => Err(Response::bad_request_no_request(&format!(
+                    "UDP outlet with address {worker_addr} not found"
+                ))),
+            },
+            Err(e) => Err(Response::bad_request_no_request(&format!("{e:?}"))),
+        }
+    }
+
+    pub(super) fn show_udp_outlet(
+        &self,
+        worker_addr: &Address,
+    ) -> Result<Response<OutletStatus>, Response<Error>> {
+        match self.node_manager.show_udp_outlet(worker_addr) {
+            Some(outlet) => Ok(Response::ok().body(outlet)),
+            None => Err(Response::not_found_no_request(&format!(
+                "UDP outlet with address {worker_addr} not found"
+            ))),
+        }
+    }
+
+    pub(super) fn get_udp_outlets(&self, req: &RequestHeader) -> Response<Vec<OutletStatus>> {
+        Response::ok()
+            .with_headers(req)
+            .body(self.node_manager.list_udp_outlets())
+    }
+}
+
+impl NodeManager {
+    #[instrument(skip_all)]
+    pub async fn create_udp_outlet(
+        &self,
+        ctx: &Context,
+        to: HostnamePort,
+        worker_addr: Option<Address>,
+        policy_expression: Option<PolicyExpression>,
+        idle_timeout: Option<Duration>,
+    ) -> Result<OutletStatus> {
+        let worker_addr = self
+            .registry
+            .udp_outlets
+            .generate_worker_addr_with_default(worker_addr, DefaultAddress::UDP_OUTLET_SERVICE);
+
+        debug!(%to, address = %worker_addr, "creating udp outlet");
+
+        // Check registry for a duplicated key
+        if self.registry.udp_outlets.contains_key(&worker_addr) {
+            let message = format!("A UDP outlet with address '{worker_addr}' already exists");
+            return Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::AlreadyExists,
+                message,
+            ));
+        }
+
+        let (incoming_ac, outgoing_ac) = self
+            .access_control(
+                ctx,
+                self.project_authority(),
+                Resource::new(worker_addr.address(), ResourceType::TcpOutlet),
+                Action::HandleMessage,
+                policy_expression,
+            )
+            .await?;
+
+        let options = {
+            let mut options = UdpOutletOptions::new()
+                .with_incoming_access_control(incoming_ac)
+                .with_outgoing_access_control(outgoing_ac);
+            if let Some(idle_timeout) = idle_timeout {
+                options = options.with_idle_timeout(idle_timeout);
+            }
+            if self.project_authority().is_none() {
+                for api_transport_flow_control_id in &self.api_transport_flow_control_ids {
+                    options = options.as_consumer(api_transport_flow_control_id)
+                }
+            };
+            // Accept messages from the default secure channel listener
+            if let Some(flow_control_id) = ctx
+                .flow_controls()
+                .get_flow_control_with_spawner(&DefaultAddress::SECURE_CHANNEL_LISTENER.into())
+            {
+                options = options.as_consumer(&flow_control_id)
+            }
+
+            options
+        };
+
+        match UdpOutlet::create(ctx, worker_addr.clone(), to.clone(), options) {
+            Ok(_) => {
+                self.registry.udp_outlets.insert(
+                    worker_addr.clone(),
+                    OutletInfo::new(to.clone(), Some(&worker_addr), false),
+                );
+                info!(%to, address = %worker_addr, "udp outlet created");
+                Ok(OutletStatus::new(to, worker_addr, None, false))
+            }
+            Err(e) => {
+                warn!(at = %to, err = %e, "Failed to create UDP outlet");
+                let message = format!("Failed to create UDP outlet: {}", e);
+                Err(ockam_core::Error::new(
+                    Origin::Node,
+                    Kind::Internal,
+                    message,
+                ))
+            }
+        }
+    }
+
+    pub async fn delete_udp_outlet(
+        &self,
+        ctx: &Context,
+        worker_addr: &Address,
+    ) -> Result<Option<OutletInfo>> {
+        info!(%worker_addr, "Handling request to delete udp outlet portal");
+        if let Some(deleted_outlet) = self.registry.udp_outlets.remove(worker_addr) {
+            debug!(%worker_addr, "Successfully removed udp outlet from node registry");
+
+            self.resources()
+                .delete_resource(&worker_addr.address().into())
+                .await?;
+
+            if let Err(e) = ctx.stop_address(&deleted_outlet.worker_addr) {
+                warn!(%worker_addr, %e, "Failed to stop udp outlet worker");
+            }
+            trace!(%worker_addr, "Successfully stopped udp outlet");
+            Ok(Some(deleted_outlet))
+        } else {
+            warn!(%worker_addr, "UDP outlet not found in the node registry");
+            Ok(None)
+        }
+    }
+
+    pub(super) fn show_udp_outlet(&self, worker_addr: &Address) -> Option<OutletStatus> {
+        self.registry
+            .udp_outlets
+            .get(worker_addr)
+            .map(|outlet| OutletStatus::new(outlet.to, outlet.worker_addr.clone(), None, false))
+    }
+
+    pub fn list_udp_outlets(&self) -> Vec<OutletStatus> {
+        self.registry
+            .udp_outlets
+            .values()
+            .into_iter()
+            .map(|outlet| OutletStatus::new(outlet.to, outlet.worker_addr.clone(), None, false))
+            .collect()
+    }
+}
+
+#[async_trait]
+pub trait UdpOutlets {
+    async fn create_udp_outlet(
+        &self,
+        ctx: &Context,
+        to: HostnamePort,
+        from: Option<&Address>,
+        policy_expression: Option<PolicyExpression>,
+        idle_timeout: Option<Duration>,
+    ) -> miette::Result<OutletStatus>;
+}
+
+#[async_trait]
+impl UdpOutlets for BackgroundNodeClient {
+    #[instrument(skip_all, fields(to = % to, from = ? from))]
+    async fn create_udp_outlet(
+        &self,
+        ctx: &Context,
+        to: HostnamePort,
+        from: Option<&Address>,
+        policy_expression: Option<PolicyExpression>,
+        idle_timeout: Option<Duration>,
+    ) -> miette::Result<OutletStatus> {
+        let mut payload = CreateUdpOutlet::new(to, from.cloned());
+        if let Some(policy_expression) = policy_expression {
+            payload.set_policy_expression(policy_expression);
+        }
+        if let Some(idle_timeout) = idle_timeout {
+            payload.set_idle_timeout(idle_timeout);
+        }
+        let req = Request::post("/node/udp_outlet").body(payload);
+        let result: OutletStatus = self.ask(ctx, req).await?;
+        Ok(result)
+    }
+}
@@ -185,6 +185,38 @@ impl NodeManagerWorker {
             (Delete, ["node", "inlet", alias]) => {
                 encode_response(req, self.delete_inlet(alias).await)?
             }
+            (Get, ["node", "udp_inlet"]) => encode_response(req, self.get_udp_inlets().await)?,
+            (Get, ["node", "udp_inlet", alias]) => {
+                encode_response(req, self.show_udp_inlet(alias).await)?
+            }
+            (Post, ["node", "udp_inlet"]) => {
+                encode_response(req, self.create_udp_inlet(ctx, dec.decode()?).await)?
+            }
+            (Delete, ["node", "udp_inlet", alias]) => {
+                encode_response(req, self.delete_udp_inlet(alias).await)?
+            }
+            (Get, ["node", "proxy_inlet"]) => encode_response(req, self.get_proxy_inlets())?,
+            (Get, ["node", "proxy_inlet", alias]) => {
+                encode_response(req, self.show_proxy_inlet(alias))?
+            }
+            (Post, ["node", "proxy_inlet"]) => {
+                encode_response(req, self.create_proxy_inlet(ctx, dec.decode()?).await)?
+            }
+            (Delete, ["node", "proxy_inlet", alias]) => {
+                encode_response(req, self.delete_proxy_inlet(ctx, alias).await)?
+            }
+            (Get, ["node", "udp_outlet"]) => self.get_udp_outlets(req).to_vec()?,
+            (Get, ["node", "udp_outlet", addr]) => {
+                let addr: Address = addr.to_string().into();
+                encode_response(req, self.show_udp_outlet(&addr))?
+            }
+            (Post, ["node", "udp_outlet"]) => {
+                encode_response(req, self.create_udp_outlet(ctx, dec.decode()?).await)?
+            }
+            (Delete, ["node", "udp_outlet", addr]) => {
+                let addr: Address = addr.to_string().into();
+                encode_response(req, self.delete_udp_outlet(ctx, &addr).await)?
+            }
             (Delete, ["node", "portal"]) => todo!(),
 
             // ==*== InfluxDB Inlets & Outlets  ==*==
@@ -197,6 +229,18 @@ impl NodeManagerWorker {
                 self.start_influxdb_outlet_service(ctx, dec.decode()?).await,
             )?,
 
+            // ==*== HTTP Outlets  ==*==
+            (Post, ["node", "http_outlet"]) => encode_response(
+                req,
+                self.start_http_outlet_service(ctx, dec.decode()?).await,
+            )?,
+
+            // ==*== PostgreSQL Outlets  ==*==
+            (Post, ["node", "postgres_outlet"]) => encode_response(
+                req,
+                self.start_postgres_outlet_service(ctx, dec.decode()?).await,
+            )?,
+
             // ==*== Flow Controls ==*==
             (Post, ["node", "flow_controls", "add_consumer"]) => {
                 encode_response(req, self.add_consumer(ctx, dec.decode()?).await)?
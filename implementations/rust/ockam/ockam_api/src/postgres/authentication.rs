@@ -0,0 +1,208 @@
+use crate::postgres::protocol::invalid;
+use base64_url::base64::engine::general_purpose::STANDARD;
+use base64_url::base64::Engine;
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use ockam_core::compat::rand::random;
+use ockam_core::Result;
+use sha2::{Digest, Sha256};
+
+/// SASL mechanism supported to authenticate with the server
+pub const SCRAM_SHA_256: &str = "SCRAM-SHA-256";
+
+/// Maximum number of iterations accepted to derive the SCRAM password
+const MAX_SCRAM_ITERATIONS: u32 = 1_000_000;
+
+/// Password hashed for the MD5 authentication:
+/// `"md5" + md5(md5(password + user) + salt)`, as a null-terminated string
+pub fn md5_password(user: &str, password: &str, salt: &[u8]) -> Vec<u8> {
+    let credentials = hex::encode(Md5::digest([password.as_bytes(), user.as_bytes()].concat()));
+    let salted = hex::encode(Md5::digest([credentials.as_bytes(), salt].concat()));
+    format!("md5{salted}\0").into_bytes()
+}
+
+/// Client side of a SCRAM-SHA-256 authentication, as described in RFC 5802 and RFC 7677.
+/// Channel binding is not supported since the connection to the server is not encrypted
+pub struct ScramClient {
+    password: String,
+    client_nonce: String,
+    client_first_bare: String,
+    server_signature: Option<Vec<u8>>,
+}
+
+impl ScramClient {
+    /// PostgreSQL ignores the user of the SCRAM messages and uses the user of the
+    /// StartupMessage, which can be left empty
+    pub fn new(user: &str, password: &str) -> Self {
+        let nonce: [u8; 18] = random();
+        Self::new_with_nonce(user, password, &STANDARD.encode(nonce))
+    }
+
+    fn new_with_nonce(user: &str, password: &str, client_nonce: &str) -> Self {
+        let user = user.replace('=', "=3D").replace(',', "=2C");
+        Self {
+            password: password.to_string(),
+            client_nonce: client_nonce.to_string(),
+            client_first_bare: format!("n={user},r={client_nonce}"),
+            server_signature: None,
+        }
+    }
+
+    /// First message sent by the client
+    pub fn client_first_message(&self) -> String {
+        format!("n,,{}", self.client_first_bare)
+    }
+
+    /// Answer the first message of the server with the proof that the client knows
+    /// the password
+    pub fn client_final_message(&mut self, server_first_message: &str) -> Result<String> {
+        let mut nonce = None;
+        let mut salt = None;
+        let mut iterations = None;
+        for attribute in server_first_message.split(',') {
+            let (Some(name), Some(value)) = (attribute.get(..2), attribute.get(2..)) else {
+                continue;
+            };
+            match (name, value) {
+                ("r=", value) => nonce = Some(value),
+                ("s=", value) => {
+                    salt = Some(
+                        STANDARD
+                            .decode(value)
+                            .map_err(|_| invalid("the SCRAM salt is not valid base64"))?,
+                    )
+                }
+                ("i=", value) => {
+                    iterations = Some(
+                        value
+                            .parse::<u32>()
+                            .map_err(|_| invalid("the SCRAM iteration count is invalid"))?,
+                    )
+                }
+                _ => (),
+            }
+        }
+        let (Some(nonce), Some(salt), Some(iterations)) = (nonce, salt, iterations) else {
+            return Err(invalid("the SCRAM server first message is incomplete"));
+        };
+        if !nonce.starts_with(&self.client_nonce) {
+            return Err(invalid(
+                "the SCRAM server nonce doesn't extend the client nonce",
+            ));
+        }
+        if iterations == 0 || iterations > MAX_SCRAM_ITERATIONS {
+            return Err(invalid("the SCRAM iteration count is invalid"));
+        }
+
+        let salted_password = hi(self.password.as_bytes(), &salt, iterations);
+        let client_key = hmac(&salted_password, b"Client Key");
+        let stored_key = Sha256::digest(&client_key);
+
+        // the channel binding is "n,,", encoded as "biws"
+        let client_final_without_proof = format!("c=biws,r={nonce}");
+        let auth_message = format!(
+            "{},{server_first_message},{client_final_without_proof}",
+            self.client_first_bare
+        );
+
+        let client_signature = hmac(&stored_key, auth_message.as_bytes());
+        let proof: Vec<u8> = client_key
+            .iter()
+            .zip(client_signature)
+            .map(|(k, s)| k ^ s)
+            .collect();
+
+        let server_key = hmac(&salted_password, b"Server Key");
+        self.server_signature = Some(hmac(&server_key, auth_message.as_bytes()));
+
+        Ok(format!(
+            "{client_final_without_proof},p={}",
+            STANDARD.encode(proof)
+        ))
+    }
+
+    /// Check that the server knows the password too
+    pub fn verify_server_final_message(&self, server_final_message: &str) -> Result<()> {
+        let Some(expected) = &self.server_signature else {
+            return Err(invalid("the SCRAM client final message was not sent"));
+        };
+        if let Some(error) = server_final_message.strip_prefix("e=") {
+            return Err(invalid(&format!(
+                "the SCRAM authentication failed: {error}"
+            )));
+        }
+        let signature = server_final_message
+            .split(',')
+            .find_map(|attribute| attribute.strip_prefix("v="))
+            .ok_or_else(|| invalid("the SCRAM server signature is missing"))?;
+        let signature = STANDARD
+            .decode(signature)
+            .map_err(|_| invalid("the SCRAM server signature is not valid base64"))?;
+        if &signature != expected {
+            return Err(invalid("the SCRAM server signature is invalid"));
+        }
+        Ok(())
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// PBKDF2 with HMAC-SHA-256, producing a single block
+fn hi(password: &[u8], salt: &[u8], iterations: u32) -> Vec<u8> {
+    let mut u = hmac(password, &[salt, &1u32.to_be_bytes()].concat());
+    let mut result = u.clone();
+    for _ in 1..iterations {
+        u = hmac(password, &u);
+        result.iter_mut().zip(&u).for_each(|(r, u)| *r ^= u);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scram_sha_256_rfc_7677_example() {
+        let mut client = ScramClient::new_with_nonce("user", "pencil", "rOprNGfwEbeRWgbNEkqO");
+        assert_eq!(
+            client.client_first_message(),
+            "n,,n=user,r=rOprNGfwEbeRWgbNEkqO"
+        );
+
+        let server_first = "r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096";
+        assert_eq!(
+            client.client_final_message(server_first).unwrap(),
+            "c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ="
+        );
+
+        assert!(client
+            .verify_server_final_message("v=6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=")
+            .is_ok());
+        assert!(client
+            .verify_server_final_message("v=AAAATRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=")
+            .is_err());
+    }
+
+    #[test]
+    fn scram_server_nonce_must_extend_the_client_nonce() {
+        let mut client = ScramClient::new("", "pencil");
+        assert!(client
+            .client_final_message("r=other,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096")
+            .is_err());
+    }
+
+    #[test]
+    fn md5_hashed_password() {
+        // md5(md5("secret" + "alice") + [1, 2, 3, 4])
+        assert_eq!(
+            md5_password("alice", "secret", &[1, 2, 3, 4]),
+            b"md598a0412b9c31436fc53776e863350083\0".to_vec()
+        );
+    }
+}
@@ -0,0 +1,542 @@
+use minicbor::{CborLen, Decode, Encode};
+use ockam::identity::{AttributesEntry, Identifier, IdentitiesAttributes};
+use ockam_core::compat::rand::random;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, Error, Result};
+use sqlx::postgres::{PgConnectOptions, PgPool, PgPoolOptions};
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tokio::sync::Mutex;
+
+/// Prefix of the database roles created for the Ockam identities
+pub const LEASED_ROLE_PREFIX: &str = "ockam_";
+
+/// Credentials used by the outlet to connect to the database
+#[derive(Clone, PartialEq, Eq)]
+pub struct PostgresCredentials {
+    pub user: String,
+    pub password: String,
+}
+
+impl Debug for PostgresCredentials {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PostgresCredentials")
+            .field("user", &self.user)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Provide the credentials used to connect to the database on behalf of an identity
+#[async_trait]
+pub trait PostgresCredentialsProvider: Send + Sync + 'static {
+    async fn credentials(&self, identifier: &Identifier) -> Result<PostgresCredentials>;
+}
+
+/// The same credentials are used for all the identities
+pub struct FixedCredentials {
+    credentials: PostgresCredentials,
+}
+
+impl FixedCredentials {
+    pub fn new(user: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            credentials: PostgresCredentials {
+                user: user.into(),
+                password: password.into(),
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl PostgresCredentialsProvider for FixedCredentials {
+    async fn credentials(&self, _identifier: &Identifier) -> Result<PostgresCredentials> {
+        Ok(self.credentials.clone())
+    }
+}
+
+/// Role granted to the leased roles of the identities having an attribute value,
+/// written as `<role>:<attribute>=<value>`
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct PostgresRoleMapping {
+    #[n(1)] pub role: String,
+    #[n(2)] pub attribute: String,
+    #[n(3)] pub value: String,
+}
+
+impl PostgresRoleMapping {
+    /// Return true if the attributes of an identity contain the attribute value
+    fn matches(&self, attributes: &AttributesEntry) -> bool {
+        attributes
+            .attrs()
+            .get(self.attribute.as_bytes())
+            .is_some_and(|value| value == self.value.as_bytes())
+    }
+}
+
+impl FromStr for PostgresRoleMapping {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            Error::new(
+                Origin::Application,
+                Kind::Invalid,
+                format!("invalid role mapping '{s}', the format is <role>:<attribute>=<value>"),
+            )
+        };
+        let (role, condition) = s.split_once(':').ok_or_else(invalid)?;
+        let (attribute, value) = condition.split_once('=').ok_or_else(invalid)?;
+        if role.is_empty() || attribute.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            role: role.to_string(),
+            attribute: attribute.to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
+impl Display for PostgresRoleMapping {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}={}", self.role, self.attribute, self.value)
+    }
+}
+
+/// Lease a database role to each identity.
+/// The role is created with an administrator account, is granted a list of roles,
+/// plus the roles mapped to the attributes of the identity, and can only log in until
+/// the end of the lease.
+/// A lease is renewed, with a new password, when half of its duration has elapsed. The
+/// mapped roles are granted or revoked again on renewal, following the current attributes.
+/// The roles of the expired leases are dropped when the next lease is requested
+pub struct PostgresCredentialsLessor {
+    pool: PgPool,
+    granted_roles: Vec<String>,
+    role_mappings: Vec<PostgresRoleMapping>,
+    identities_attributes: Arc<IdentitiesAttributes>,
+    /// Authority attesting the attributes used by the role mappings
+    authority: Option<Identifier>,
+    lease_duration: Duration,
+    leases: Mutex<HashMap<Identifier, Lease>>,
+}
+
+struct Lease {
+    credentials: PostgresCredentials,
+    renew_at: OffsetDateTime,
+    expires_at: OffsetDateTime,
+}
+
+impl PostgresCredentialsLessor {
+    /// The administrator connection is only opened when the first lease is requested
+    pub fn new(
+        admin_options: PgConnectOptions,
+        granted_roles: Vec<String>,
+        role_mappings: Vec<PostgresRoleMapping>,
+        identities_attributes: Arc<IdentitiesAttributes>,
+        authority: Option<Identifier>,
+        lease_duration: Duration,
+    ) -> Result<Self> {
+        if !role_mappings.is_empty() && authority.is_none() {
+            return Err(Error::new(
+                Origin::Application,
+                Kind::Invalid,
+                "the roles can only be mapped to attributes attested by a project authority",
+            ));
+        }
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect_lazy_with(admin_options);
+        Ok(Self {
+            pool,
+            granted_roles,
+            role_mappings,
+            identities_attributes,
+            authority,
+            lease_duration,
+            leases: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Return the roles to grant to the role leased to an identity,
+    /// and the mapped roles to revoke from it
+    async fn roles(&self, identifier: &Identifier) -> Result<(Vec<String>, Vec<String>)> {
+        let attributes = match &self.authority {
+            Some(authority) if !self.role_mappings.is_empty() => {
+                self.identities_attributes
+                    .get_attributes(identifier, authority)
+                    .await?
+            }
+            _ => None,
+        };
+
+        let mut granted = self.granted_roles.clone();
+        let mut revoked = vec![];
+        for mapping in &self.role_mappings {
+            let matches = attributes.as_ref().is_some_and(|a| mapping.matches(a));
+            let roles = if matches { &mut granted } else { &mut revoked };
+            if !roles.contains(&mapping.role) {
+                roles.push(mapping.role.clone());
+            }
+        }
+        revoked.retain(|role| !granted.contains(role));
+        Ok((granted, revoked))
+    }
+
+    /// Remove the expired leases, except the lease of the current identity which is renewed,
+    /// and drop their roles
+    async fn evict_expired_leases(
+        &self,
+        leases: &mut HashMap<Identifier, Lease>,
+        identifier: &Identifier,
+    ) {
+        let now = OffsetDateTime::now_utc();
+        let expired: Vec<Identifier> = leases
+            .iter()
+            .filter(|(i, lease)| *i != identifier && lease.expires_at <= now)
+            .map(|(i, _)| i.clone())
+            .collect();
+        for expired_identifier in expired {
+            let Some(lease) = leases.remove(&expired_identifier) else {
+                continue;
+            };
+            let role = lease.credentials.user;
+            match sqlx::query(&format!("DROP ROLE IF EXISTS {}", quote_identifier(&role)))
+                .execute(&self.pool)
+                .await
+            {
+                Ok(_) => debug!(identifier = %expired_identifier, %role, "dropped an expired role"),
+                Err(e) => {
+                    warn!(identifier = %expired_identifier, %role, "unable to drop an expired role: {e}")
+                }
+            }
+        }
+    }
+
+    /// Name of the role leased to an identity
+    pub fn role_name(identifier: &Identifier) -> String {
+        format!("{LEASED_ROLE_PREFIX}{}", hex::encode(&identifier.0[..16]))
+    }
+
+    async fn lease(&self, identifier: &Identifier) -> Result<Lease> {
+        let role = Self::role_name(identifier);
+        let password = hex::encode(random::<[u8; 32]>());
+        let now = OffsetDateTime::now_utc();
+        let valid_until = (now + self.lease_duration)
+            .format(&Rfc3339)
+            .map_err(|e| Error::new(Origin::Application, Kind::Internal, e))?;
+
+        let role_exists = sqlx::query("SELECT 1 FROM pg_roles WHERE rolname = $1")
+            .bind(&role)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(map_sql_err)?
+            .is_some();
+
+        // the role name and the password are hexadecimal strings, which don't need to be escaped
+        let statement = if role_exists { "ALTER" } else { "CREATE" };
+        sqlx::query(&format!(
+            "{statement} ROLE {} LOGIN PASSWORD '{password}' VALID UNTIL '{valid_until}'",
+            quote_identifier(&role)
+        ))
+        .execute(&self.pool)
+        .await
+        .map_err(map_sql_err)?;
+
+        let (granted_roles, revoked_roles) = self.roles(identifier).await?;
+        for granted_role in &granted_roles {
+            sqlx::query(&format!(
+                "GRANT {} TO {}",
+                quote_identifier(granted_role),
+                quote_identifier(&role)
+            ))
+            .execute(&self.pool)
+            .await
+            .map_err(map_sql_err)?;
+        }
+        // revoking a role which is not granted only raises a warning
+        for revoked_role in &revoked_roles {
+            sqlx::query(&format!(
+                "REVOKE {} FROM {}",
+                quote_identifier(revoked_role),
+                quote_identifier(&role)
+            ))
+            .execute(&self.pool)
+            .await
+            .map_err(map_sql_err)?;
+        }
+
+        debug!(%identifier, %role, %valid_until, ?granted_roles, "leased a database role");
+        Ok(Lease {
+            credentials: PostgresCredentials {
+                user: role,
+                password,
+            },
+            renew_at: now + self.lease_duration / 2,
+            expires_at: now + self.lease_duration,
+        })
+    }
+}
+
+#[async_trait]
+impl PostgresCredentialsProvider for PostgresCredentialsLessor {
+    async fn credentials(&self, identifier: &Identifier) -> Result<PostgresCredentials> {
+        // the leases are locked during the renewal to avoid creating the same role concurrently
+        let mut leases = self.leases.lock().await;
+        self.evict_expired_leases(&mut leases, identifier).await;
+        if let Some(lease) = leases.get(identifier) {
+            if lease.renew_at > OffsetDateTime::now_utc() {
+                return Ok(lease.credentials.clone());
+            }
+        }
+        let lease = self.lease(identifier).await?;
+        let credentials = lease.credentials.clone();
+        leases.insert(identifier.clone(), lease);
+        Ok(credentials)
+    }
+}
+
+/// Quote an identifier, like a role name, to use it in a SQL statement
+fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn map_sql_err(err: sqlx::Error) -> Error {
+    Error::new(Origin::Application, Kind::Io, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::identity::models::IDENTIFIER_LEN;
+    use ockam::identity::utils::now;
+    use ockam::identity::IdentityAttributesSqlxDatabase;
+    use ockam_core::compat::collections::BTreeMap;
+    use ockam_node::database::DatabaseConfiguration;
+    use sqlx::{ConnectOptions, Connection, Row};
+
+    fn random_identifier() -> Identifier {
+        Identifier(random::<[u8; IDENTIFIER_LEN]>())
+    }
+
+    async fn create_identities_attributes() -> Result<Arc<IdentitiesAttributes>> {
+        Ok(Arc::new(IdentitiesAttributes::new(Arc::new(
+            IdentityAttributesSqlxDatabase::create().await?,
+        ))))
+    }
+
+    #[test]
+    fn identifiers_are_quoted() {
+        assert_eq!(quote_identifier("readers"), "\"readers\"");
+        assert_eq!(quote_identifier("a\"b"), "\"a\"\"b\"");
+    }
+
+    #[test]
+    fn role_mappings_are_parsed() {
+        let mapping = PostgresRoleMapping::from_str("readers:team=data:science").unwrap();
+        assert_eq!(
+            mapping,
+            PostgresRoleMapping {
+                role: "readers".to_string(),
+                attribute: "team".to_string(),
+                value: "data:science".to_string(),
+            }
+        );
+        assert_eq!(mapping.to_string(), "readers:team=data:science");
+
+        for invalid in ["readers", "readers:team", ":team=data", "readers:=data"] {
+            assert!(PostgresRoleMapping::from_str(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[tokio::test]
+    async fn roles_are_mapped_to_the_attributes_attested_by_the_authority() -> Result<()> {
+        let identities_attributes = create_identities_attributes().await?;
+        let authority = random_identifier();
+        let lessor = PostgresCredentialsLessor::new(
+            PgConnectOptions::new_without_pgpass(),
+            vec!["users".to_string()],
+            vec![
+                PostgresRoleMapping::from_str("readers:team=data")?,
+                PostgresRoleMapping::from_str("writers:team=ingestion")?,
+                PostgresRoleMapping::from_str("readers:admin=true")?,
+            ],
+            identities_attributes.clone(),
+            Some(authority.clone()),
+            Duration::from_secs(60),
+        )?;
+
+        let put_attributes = |attested_by: Identifier, attributes: &[(&str, &str)]| {
+            let identities_attributes = identities_attributes.clone();
+            let attributes: BTreeMap<Vec<u8>, Vec<u8>> = attributes
+                .iter()
+                .map(|(k, v)| (k.as_bytes().to_vec(), v.as_bytes().to_vec()))
+                .collect();
+            async move {
+                let identifier = random_identifier();
+                identities_attributes
+                    .put_attributes(
+                        &identifier,
+                        AttributesEntry::new(attributes, now()?, None, Some(attested_by)),
+                    )
+                    .await?;
+                Result::<Identifier>::Ok(identifier)
+            }
+        };
+
+        let data = put_attributes(authority.clone(), &[("team", "data")]).await?;
+        assert_eq!(
+            lessor.roles(&data).await?,
+            (
+                vec!["users".to_string(), "readers".to_string()],
+                vec!["writers".to_string()]
+            )
+        );
+
+        // attributes attested by another authority are ignored
+        let other = put_attributes(random_identifier(), &[("team", "ingestion")]).await?;
+        assert_eq!(
+            lessor.roles(&other).await?,
+            (
+                vec!["users".to_string()],
+                vec!["readers".to_string(), "writers".to_string()]
+            )
+        );
+
+        // a role is not revoked if another attribute grants it
+        let admin = put_attributes(authority, &[("team", "ingestion"), ("admin", "true")]).await?;
+        assert_eq!(
+            lessor.roles(&admin).await?,
+            (
+                vec![
+                    "users".to_string(),
+                    "writers".to_string(),
+                    "readers".to_string()
+                ],
+                vec![]
+            )
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn role_mappings_need_an_authority() -> Result<()> {
+        let result = PostgresCredentialsLessor::new(
+            PgConnectOptions::new_without_pgpass(),
+            vec![],
+            vec![PostgresRoleMapping::from_str("readers:team=data")?],
+            create_identities_attributes().await?,
+            None,
+            Duration::from_secs(60),
+        );
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    /// This test needs a local Postgres database, configured with the
+    /// OCKAM_DATABASE_CONNECTION_URL environment variable. The user needs the CREATEROLE privilege
+    #[tokio::test]
+    async fn leased_credentials_can_log_in() -> Result<()> {
+        let Some(configuration) = DatabaseConfiguration::postgres()? else {
+            return Ok(());
+        };
+        let admin_options =
+            PgConnectOptions::from_str(&configuration.connection_string()).map_err(map_sql_err)?;
+
+        let lessor = PostgresCredentialsLessor::new(
+            admin_options.clone(),
+            vec![],
+            vec![],
+            create_identities_attributes().await?,
+            None,
+            Duration::from_secs(60),
+        )?;
+        let identifier = random_identifier();
+
+        let credentials = lessor.credentials(&identifier).await?;
+        assert_eq!(
+            credentials.user,
+            PostgresCredentialsLessor::role_name(&identifier)
+        );
+        // the lease is reused until it needs to be renewed
+        assert_eq!(lessor.credentials(&identifier).await?, credentials);
+
+        let mut connection = admin_options
+            .clone()
+            .username(&credentials.user)
+            .password(&credentials.password)
+            .connect()
+            .await
+            .map_err(map_sql_err)?;
+        let current_user: String = sqlx::query("SELECT current_user")
+            .fetch_one(&mut connection)
+            .await
+            .map_err(map_sql_err)?
+            .get(0);
+        assert_eq!(current_user, credentials.user);
+        connection.close().await.map_err(map_sql_err)?;
+
+        sqlx::query(&format!(
+            "DROP ROLE {}",
+            quote_identifier(&credentials.user)
+        ))
+        .execute(&lessor.pool)
+        .await
+        .map_err(map_sql_err)?;
+        Ok(())
+    }
+
+    /// This test needs a local Postgres database, configured with the
+    /// OCKAM_DATABASE_CONNECTION_URL environment variable. The user needs the CREATEROLE privilege
+    #[tokio::test]
+    async fn expired_leases_are_evicted() -> Result<()> {
+        let Some(configuration) = DatabaseConfiguration::postgres()? else {
+            return Ok(());
+        };
+        let admin_options =
+            PgConnectOptions::from_str(&configuration.connection_string()).map_err(map_sql_err)?;
+
+        let lessor = PostgresCredentialsLessor::new(
+            admin_options,
+            vec![],
+            vec![],
+            create_identities_attributes().await?,
+            None,
+            Duration::from_secs(1),
+        )?;
+        let expired = lessor.credentials(&random_identifier()).await?;
+        tokio::time::sleep(Duration::from_millis(1_100)).await;
+
+        let current = lessor.credentials(&random_identifier()).await?;
+        assert_eq!(lessor.leases.lock().await.len(), 1);
+
+        let role_exists = |role: String| {
+            let pool = lessor.pool.clone();
+            async move {
+                let row = sqlx::query("SELECT 1 FROM pg_roles WHERE rolname = $1")
+                    .bind(role)
+                    .fetch_optional(&pool)
+                    .await
+                    .map_err(map_sql_err)?;
+                Result::<bool>::Ok(row.is_some())
+            }
+        };
+        assert!(!role_exists(expired.user).await?);
+        assert!(role_exists(current.user.clone()).await?);
+
+        sqlx::query(&format!("DROP ROLE {}", quote_identifier(&current.user)))
+            .execute(&lessor.pool)
+            .await
+            .map_err(map_sql_err)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,343 @@
+use crate::postgres::authentication::{md5_password, ScramClient, SCRAM_SHA_256};
+use crate::postgres::credentials::{PostgresCredentials, PostgresCredentialsProvider};
+use crate::postgres::protocol::*;
+use crate::protocol_interceptor::{Connection, ProtocolInterceptor};
+use bytes::BufMut;
+use ockam_core::{async_trait, Result};
+use ockam_node::Context;
+use ockam_transport_tcp::Direction;
+use std::sync::Arc;
+
+/// PostgreSQL doesn't send or accept messages bigger than 1GB
+pub const MAX_POSTGRES_MESSAGE_SIZE: usize = 1 << 30;
+
+/// Terminate the authentication of the PostgreSQL connections on the outlet side.
+///
+/// The user and the password sent by the client are ignored: the user of the StartupMessage
+/// is replaced with the user of the credentials given for the identity of the inlet node,
+/// and the interceptor answers the authentication requests of the server with its password.
+/// The client only receives the outcome of the authentication.
+/// Since the connection to the server is not encrypted, the SSL and GSSAPI encryption
+/// requests of the client are declined
+pub struct PostgresInterceptor {
+    credentials: Arc<dyn PostgresCredentialsProvider>,
+}
+
+impl PostgresInterceptor {
+    pub fn new(credentials: Arc<dyn PostgresCredentialsProvider>) -> Self {
+        Self { credentials }
+    }
+
+    /// Replace the user of the StartupMessage and start the authentication
+    async fn start_authentication(
+        &self,
+        connection: &mut Connection<Self>,
+        content: &[u8],
+    ) -> Result<Vec<PostgresMessage>> {
+        let Some(identifier) = connection.identifier().cloned() else {
+            connection.reply(PostgresMessage::fatal_error(
+                INVALID_AUTHORIZATION_SPECIFICATION,
+                "the connection must be authenticated with an Ockam identity",
+            ));
+            return Ok(vec![]);
+        };
+
+        let credentials = match self.credentials.credentials(&identifier).await {
+            Ok(credentials) => credentials,
+            Err(e) => {
+                warn!(%identifier, "unable to get database credentials: {e}");
+                connection.reply(PostgresMessage::fatal_error(
+                    INVALID_AUTHORIZATION_SPECIFICATION,
+                    "no database credentials are available for this Ockam identity",
+                ));
+                return Ok(vec![]);
+            }
+        };
+
+        let mut startup = StartupMessage::decode(content)?;
+        startup.set_parameter("user", &credentials.user);
+        connection.state = PostgresConnection::Authenticating {
+            credentials,
+            scram: None,
+        };
+        Ok(vec![startup.encode()])
+    }
+
+    /// Answer an authentication request of the server with the credentials
+    fn authenticate(
+        connection: &mut Connection<Self>,
+        code: u32,
+        data: &[u8],
+    ) -> Result<Option<PostgresMessage>> {
+        let PostgresConnection::Authenticating { credentials, scram } = &mut connection.state
+        else {
+            return Err(invalid("unexpected authentication request"));
+        };
+
+        let password_message = match code {
+            AUTHENTICATION_OK => {
+                connection.state = PostgresConnection::Authenticated;
+                return Ok(None);
+            }
+            AUTHENTICATION_CLEARTEXT_PASSWORD => [credentials.password.as_bytes(), &[0]].concat(),
+            AUTHENTICATION_MD5_PASSWORD => {
+                let salt = data
+                    .get(..4)
+                    .ok_or_else(|| invalid("the MD5 salt is missing"))?;
+                md5_password(&credentials.user, &credentials.password, salt)
+            }
+            AUTHENTICATION_SASL => {
+                let mechanisms = data.split(|b| *b == 0);
+                if !mechanisms
+                    .into_iter()
+                    .any(|m| m == SCRAM_SHA_256.as_bytes())
+                {
+                    return Err(invalid("the server doesn't support SCRAM-SHA-256"));
+                }
+                let client = ScramClient::new("", &credentials.password);
+                let first = client.client_first_message();
+                *scram = Some(client);
+
+                let mut content = vec![];
+                content.extend_from_slice(SCRAM_SHA_256.as_bytes());
+                content.put_u8(0);
+                content.put_u32(first.len() as u32);
+                content.extend_from_slice(first.as_bytes());
+                content
+            }
+            AUTHENTICATION_SASL_CONTINUE => {
+                let client = scram
+                    .as_mut()
+                    .ok_or_else(|| invalid("the SASL authentication was not started"))?;
+                client
+                    .client_final_message(&String::from_utf8_lossy(data))?
+                    .into_bytes()
+            }
+            AUTHENTICATION_SASL_FINAL => {
+                let client = scram
+                    .as_ref()
+                    .ok_or_else(|| invalid("the SASL authentication was not started"))?;
+                client.verify_server_final_message(&String::from_utf8_lossy(data))?;
+                // the server sends AuthenticationOk next
+                return Ok(None);
+            }
+            code => {
+                return Err(invalid(&format!(
+                    "the authentication method {code} is not supported"
+                )))
+            }
+        };
+        Ok(Some(PostgresMessage::Typed(
+            PASSWORD_MESSAGE,
+            password_message,
+        )))
+    }
+}
+
+/// State of a PostgreSQL connection
+pub enum PostgresConnection {
+    /// Waiting for the StartupMessage
+    Startup,
+    /// The server authenticates the credentials of the identity
+    Authenticating {
+        credentials: PostgresCredentials,
+        scram: Option<ScramClient>,
+    },
+    /// All the messages are forwarded
+    Authenticated,
+}
+
+#[async_trait]
+impl ProtocolInterceptor for PostgresInterceptor {
+    type Framing = PostgresFraming;
+    type State = PostgresConnection;
+
+    fn connection_state(&self) -> PostgresConnection {
+        PostgresConnection::Startup
+    }
+
+    fn framing(&self, direction: Direction, _state: &PostgresConnection) -> PostgresFraming {
+        PostgresFraming::new(direction, MAX_POSTGRES_MESSAGE_SIZE)
+    }
+
+    async fn intercept_request(
+        &self,
+        _context: &mut Context,
+        connection: &mut Connection<Self>,
+        request: PostgresMessage,
+    ) -> Result<Vec<PostgresMessage>> {
+        match (&connection.state, &request) {
+            (PostgresConnection::Startup, PostgresMessage::Untyped(content)) => {
+                match request.code() {
+                    Some(SSL_REQUEST_CODE | GSSENC_REQUEST_CODE) => {
+                        connection.reply(PostgresMessage::Byte(b'N'));
+                        Ok(vec![])
+                    }
+                    Some(CANCEL_REQUEST_CODE) => Ok(vec![request]),
+                    Some(code) if code >> 16 == 3 => {
+                        self.start_authentication(connection, content).await
+                    }
+                    _ => Err(invalid("unsupported protocol version")),
+                }
+            }
+            // the password of the client is not used
+            (
+                PostgresConnection::Authenticating { .. },
+                PostgresMessage::Typed(PASSWORD_MESSAGE, _),
+            ) => Ok(vec![]),
+            _ => Ok(vec![request]),
+        }
+    }
+
+    async fn intercept_response(
+        &self,
+        _context: &mut Context,
+        connection: &mut Connection<Self>,
+        response: PostgresMessage,
+    ) -> Result<Vec<PostgresMessage>> {
+        if !matches!(connection.state, PostgresConnection::Authenticating { .. }) {
+            return Ok(vec![response]);
+        }
+        let Some((code, data)) = response.authentication() else {
+            return Ok(vec![response]);
+        };
+
+        match Self::authenticate(connection, code, data) {
+            Ok(Some(password_message)) => {
+                connection.reply(password_message);
+                Ok(vec![])
+            }
+            Ok(None) if code == AUTHENTICATION_OK => Ok(vec![response]),
+            Ok(None) => Ok(vec![]),
+            Err(e) => {
+                warn!("unable to authenticate with the database: {e}");
+                Ok(vec![PostgresMessage::fatal_error(
+                    INVALID_AUTHORIZATION_SPECIFICATION,
+                    "the authentication with the database failed",
+                )])
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::postgres::credentials::FixedCredentials;
+    use crate::protocol_interceptor::testing::{InterceptorHarness, PassThrough};
+    use crate::protocol_interceptor::{Framing, ProtocolInterceptorFactory};
+    use ockam::identity::models::IDENTIFIER_LEN;
+    use ockam::identity::Identifier;
+    use ockam_transport_tcp::PortalInterceptorFactory;
+
+    fn encode(messages: Vec<PostgresMessage>) -> Vec<u8> {
+        let mut framing = PostgresFraming::new(Direction::FromOutletToInlet, 1024);
+        let mut buffer = vec![];
+        for message in messages {
+            framing.encode(message, &mut buffer).unwrap();
+        }
+        buffer
+    }
+
+    fn startup(user: &str) -> PostgresMessage {
+        StartupMessage {
+            protocol_version: PROTOCOL_VERSION_3,
+            parameters: vec![
+                ("user".to_string(), user.to_string()),
+                ("database".to_string(), "orders".to_string()),
+            ],
+        }
+        .encode()
+    }
+
+    fn authentication(code: u32, data: &[u8]) -> PostgresMessage {
+        PostgresMessage::Typed(AUTHENTICATION, [&code.to_be_bytes()[..], data].concat())
+    }
+
+    fn factory() -> impl PortalInterceptorFactory {
+        ProtocolInterceptorFactory::new(PostgresInterceptor::new(Arc::new(FixedCredentials::new(
+            "ockam_reader",
+            "secret",
+        ))))
+    }
+
+    #[ockam_macros::test]
+    async fn credentials_are_injected(context: &mut Context) -> ockam::Result<()> {
+        let outlet = factory();
+        let client_ssl_request = encode(vec![PostgresMessage::Untyped(
+            SSL_REQUEST_CODE.to_be_bytes().to_vec(),
+        )]);
+        let client_startup = encode(vec![startup("alice")]);
+        let client_password = encode(vec![PostgresMessage::Typed(
+            PASSWORD_MESSAGE,
+            b"wrong\0".to_vec(),
+        )]);
+        let server_md5_request = encode(vec![authentication(
+            AUTHENTICATION_MD5_PASSWORD,
+            &[1, 2, 3, 4],
+        )]);
+        let server_ok = encode(vec![
+            authentication(AUTHENTICATION_OK, &[]),
+            PostgresMessage::Typed(b'Z', b"I".to_vec()),
+        ]);
+        let query = encode(vec![PostgresMessage::Typed(b'Q', b"SELECT 1\0".to_vec())]);
+
+        let replayed = InterceptorHarness::new(&PassThrough, &outlet)
+            .with_chunk_size(3)
+            .with_inlet_identifier(Identifier([1; IDENTIFIER_LEN]))
+            .replay(
+                context,
+                vec![
+                    (Direction::FromInletToOutlet, client_ssl_request),
+                    (Direction::FromInletToOutlet, client_startup),
+                    (Direction::FromOutletToInlet, server_md5_request),
+                    (Direction::FromInletToOutlet, client_password),
+                    (Direction::FromOutletToInlet, server_ok.clone()),
+                    (Direction::FromInletToOutlet, query.clone()),
+                ],
+            )
+            .await?;
+
+        // the server receives the user and the password of the credentials
+        let server_password = encode(vec![PostgresMessage::Typed(
+            PASSWORD_MESSAGE,
+            md5_password("ockam_reader", "secret", &[1, 2, 3, 4]),
+        )]);
+        assert_eq!(
+            replayed.received_by_server,
+            [
+                encode(vec![startup("ockam_reader")]),
+                server_password,
+                query
+            ]
+            .concat()
+        );
+        // the client only receives the outcome of the authentication
+        assert_eq!(
+            replayed.received_by_client,
+            [encode(vec![PostgresMessage::Byte(b'N')]), server_ok].concat()
+        );
+        Ok(())
+    }
+
+    #[ockam_macros::test]
+    async fn connections_without_identity_are_rejected(context: &mut Context) -> ockam::Result<()> {
+        let outlet = factory();
+        let replayed = InterceptorHarness::new(&PassThrough, &outlet)
+            .replay(
+                context,
+                vec![(Direction::FromInletToOutlet, encode(vec![startup("alice")]))],
+            )
+            .await?;
+
+        assert!(replayed.received_by_server.is_empty());
+        assert_eq!(
+            replayed.received_by_client,
+            encode(vec![PostgresMessage::fatal_error(
+                INVALID_AUTHORIZATION_SPECIFICATION,
+                "the connection must be authenticated with an Ockam identity",
+            )])
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,21 @@
+//! PostgreSQL-aware portals.
+//!
+//! The outlet terminates the authentication of the PostgreSQL connections: clients connect
+//! with any password, and the outlet authenticates with the credentials of the identity
+//! of the inlet node, either fixed or leased per identity.
+//! The access to the database is then only authorized by the policy of the outlet.
+
+mod authentication;
+mod credentials;
+mod interceptor;
+pub mod portal;
+pub mod protocol;
+
+pub use authentication::{md5_password, ScramClient};
+pub use credentials::{
+    FixedCredentials, PostgresCredentials, PostgresCredentialsLessor, PostgresCredentialsProvider,
+    PostgresRoleMapping, LEASED_ROLE_PREFIX,
+};
+pub use interceptor::{PostgresConnection, PostgresInterceptor, MAX_POSTGRES_MESSAGE_SIZE};
+pub use portal::{PostgresLeaseConfig, PostgresOutletConfig, PostgresPortals};
+pub use protocol::{PostgresFraming, PostgresMessage, StartupMessage};
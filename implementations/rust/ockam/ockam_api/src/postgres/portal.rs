@@ -0,0 +1,272 @@
+use crate::nodes::models::portal::{CreateOutlet, OutletAccessControl, OutletStatus};
+use crate::nodes::{BackgroundNodeClient, NodeManagerWorker};
+use crate::postgres::credentials::{
+    FixedCredentials, PostgresCredentialsLessor, PostgresCredentialsProvider, PostgresRoleMapping,
+};
+use crate::postgres::interceptor::PostgresInterceptor;
+use crate::protocol_interceptor::ProtocolInterceptorFactory;
+use crate::{ApiError, DefaultAddress};
+use minicbor::{CborLen, Decode, Encode};
+use ockam::flow_control::FlowControls;
+use ockam::{Address, Context, Result};
+use ockam_abac::PolicyExpression;
+use ockam_abac::{Action, Resource, ResourceType};
+use ockam_core::api::{Error, Request, Response};
+use ockam_core::async_trait;
+use ockam_transport_core::HostnamePort;
+use ockam_transport_tcp::{read_portal_payload_length, PortalOutletInterceptor};
+use sqlx::postgres::PgConnectOptions;
+use std::sync::Arc;
+use std::time::Duration;
+
+impl NodeManagerWorker {
+    pub(crate) async fn start_postgres_outlet_service(
+        &self,
+        ctx: &Context,
+        body: CreatePostgresOutlet,
+    ) -> Result<Response<OutletStatus>, Response<Error>> {
+        debug!("Starting PostgreSQL Outlet service");
+        let CreateOutlet {
+            hostname_port,
+            worker_addr,
+            reachable_from_default_secure_channel,
+            policy_expression,
+            privileged,
+            tls,
+            unix_socket_path,
+            allowed_destinations,
+            destination_policy_expression: _,
+        } = body.tcp_outlet;
+        if unix_socket_path.is_some() {
+            return Err(Response::bad_request_no_request(
+                "PostgreSQL outlets can't connect to a Unix domain socket",
+            ));
+        }
+        if allowed_destinations.is_some() {
+            return Err(Response::bad_request_no_request(
+                "PostgreSQL outlets can't forward to dynamic destinations",
+            ));
+        }
+        let address = self
+            .node_manager
+            .registry
+            .outlets
+            .generate_worker_addr(worker_addr);
+        let outlet_address: Address = format!("{}_outlet", address.address()).into();
+
+        let credentials: Arc<dyn PostgresCredentialsProvider> = match body.postgres_config {
+            PostgresOutletConfig::FixedCredentials(user, password) => {
+                Arc::new(FixedCredentials::new(user, password))
+            }
+            PostgresOutletConfig::LeasedCredentials(lease_config) => {
+                let mut admin_options = PgConnectOptions::new_without_pgpass()
+                    .host(&hostname_port.hostname())
+                    .port(hostname_port.port())
+                    .username(&lease_config.admin_user)
+                    .password(&lease_config.admin_password);
+                if let Some(database) = &lease_config.database {
+                    admin_options = admin_options.database(database);
+                }
+                let lessor = PostgresCredentialsLessor::new(
+                    admin_options,
+                    lease_config.granted_roles,
+                    lease_config.role_mappings,
+                    self.node_manager
+                        .secure_channels
+                        .identities()
+                        .identities_attributes(),
+                    self.node_manager.project_authority(),
+                    lease_config.lease_duration,
+                )
+                .map_err(|e| Response::bad_request_no_request(&e.to_string()))?;
+                Arc::new(lessor)
+            }
+        };
+
+        // Start the interceptor
+        self.create_postgres_outlet_interceptor(
+            ctx,
+            address,
+            outlet_address.clone(),
+            policy_expression.clone(),
+            credentials,
+        )
+        .await
+        .map_err(|e| Response::bad_request_no_request(&format!("{e:?}")))?;
+
+        // Start the outlet
+        match self
+            .node_manager
+            .create_outlet(
+                ctx,
+                hostname_port,
+                tls,
+                Some(outlet_address),
+                reachable_from_default_secure_channel,
+                OutletAccessControl::WithPolicyExpression(policy_expression),
+                privileged,
+            )
+            .await
+        {
+            Ok(outlet_status) => Ok(Response::ok().body(outlet_status)),
+            Err(e) => Err(Response::bad_request_no_request(&format!("{e:?}"))),
+        }
+    }
+
+    async fn create_postgres_outlet_interceptor(
+        &self,
+        ctx: &Context,
+        interceptor_address: Address,
+        outlet_address: Address,
+        outlet_policy_expression: Option<PolicyExpression>,
+        credentials: Arc<dyn PostgresCredentialsProvider>,
+    ) -> Result<(), Error> {
+        debug!(%interceptor_address, %outlet_address, ?outlet_policy_expression, "Creating postgres outlet interceptor");
+        let default_secure_channel_listener_flow_control_id = ctx
+            .flow_controls()
+            .get_flow_control_with_spawner(&DefaultAddress::SECURE_CHANNEL_LISTENER.into())
+            .ok_or_else(|| {
+                ApiError::core("Unable to get flow control for secure channel listener")
+            })?;
+
+        let policy_access_control = self
+            .node_manager
+            .policy_access_control(
+                self.node_manager.project_authority().clone(),
+                Resource::new(outlet_address.to_string(), ResourceType::TcpOutlet),
+                Action::HandleMessage,
+                outlet_policy_expression,
+            )
+            .await?;
+
+        let spawner_flow_control_id = FlowControls::generate_flow_control_id();
+
+        let interceptor_factory = Arc::new(ProtocolInterceptorFactory::new(
+            PostgresInterceptor::new(credentials),
+        ));
+
+        PortalOutletInterceptor::create(
+            ctx,
+            interceptor_address.clone(),
+            Some(spawner_flow_control_id.clone()),
+            interceptor_factory,
+            Arc::new(policy_access_control.create_outgoing(ctx)?),
+            Arc::new(policy_access_control.create_incoming()),
+            read_portal_payload_length(),
+        )?;
+
+        // every secure channel can reach this service
+        let flow_controls = ctx.flow_controls();
+        flow_controls.add_consumer(
+            &interceptor_address,
+            &default_secure_channel_listener_flow_control_id,
+        );
+
+        // this spawner flow control id is used to control communication with dynamically created
+        // outlets
+        flow_controls.add_spawner(&interceptor_address, &spawner_flow_control_id);
+
+        // allow communication with the tcp outlet
+        flow_controls.add_consumer(&outlet_address, &spawner_flow_control_id);
+        Ok(())
+    }
+}
+
+#[async_trait]
+pub trait PostgresPortals {
+    async fn create_postgres_outlet(
+        &self,
+        ctx: &Context,
+        to: HostnamePort,
+        from: Option<&Address>,
+        policy_expression: Option<PolicyExpression>,
+        postgres_config: PostgresOutletConfig,
+    ) -> miette::Result<OutletStatus>;
+}
+
+#[async_trait]
+impl PostgresPortals for BackgroundNodeClient {
+    #[instrument(skip(self, ctx, postgres_config))]
+    async fn create_postgres_outlet(
+        &self,
+        ctx: &Context,
+        to: HostnamePort,
+        from: Option<&Address>,
+        policy_expression: Option<PolicyExpression>,
+        postgres_config: PostgresOutletConfig,
+    ) -> miette::Result<OutletStatus> {
+        let mut outlet_payload = CreateOutlet::new(to, false, from.cloned(), true, false);
+        if let Some(policy_expression) = policy_expression {
+            outlet_payload.set_policy_expression(policy_expression);
+        }
+        let payload = CreatePostgresOutlet::new(outlet_payload, postgres_config);
+        let req = Request::post("/node/postgres_outlet").body(payload);
+        self.ask(ctx, req).await
+    }
+}
+
+/// Request body to create a PostgreSQL outlet
+#[derive(Clone, Debug, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct CreatePostgresOutlet {
+    #[n(1)] pub(crate) tcp_outlet: CreateOutlet,
+    #[n(2)] pub(crate) postgres_config: PostgresOutletConfig,
+}
+
+impl CreatePostgresOutlet {
+    pub fn new(tcp_outlet: CreateOutlet, postgres_config: PostgresOutletConfig) -> Self {
+        Self {
+            tcp_outlet,
+            postgres_config,
+        }
+    }
+}
+
+/// Credentials used by a PostgreSQL outlet to connect to the database
+#[derive(Clone, Debug, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub enum PostgresOutletConfig {
+    /// A role is leased to each identity
+    #[n(0)] LeasedCredentials(#[n(0)] PostgresLeaseConfig),
+    /// The same user and password are used for all the identities
+    #[n(1)] FixedCredentials(#[n(0)] String, #[n(1)] String),
+}
+
+/// Configuration of the roles leased to the identities
+#[derive(Clone, Debug, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct PostgresLeaseConfig {
+    /// User allowed to create roles
+    #[n(1)] pub(crate) admin_user: String,
+    #[n(2)] pub(crate) admin_password: String,
+    /// Database used by the administrator connection
+    #[n(3)] pub(crate) database: Option<String>,
+    /// Roles granted to the leased roles
+    #[n(4)] pub(crate) granted_roles: Vec<String>,
+    #[n(5)] pub(crate) lease_duration: Duration,
+    /// Roles granted to the leased roles of the identities having some attributes
+    #[n(6)] pub(crate) role_mappings: Vec<PostgresRoleMapping>,
+}
+
+impl PostgresLeaseConfig {
+    pub fn new(
+        admin_user: String,
+        admin_password: String,
+        database: Option<String>,
+        granted_roles: Vec<String>,
+        role_mappings: Vec<PostgresRoleMapping>,
+        lease_duration: Duration,
+    ) -> Self {
+        Self {
+            admin_user,
+            admin_password,
+            database,
+            granted_roles,
+            lease_duration,
+            role_mappings,
+        }
+    }
+}
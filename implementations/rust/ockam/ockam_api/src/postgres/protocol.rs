@@ -0,0 +1,307 @@
+use crate::protocol_interceptor::Framing;
+use bytes::{Buf, BufMut, BytesMut};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use ockam_transport_tcp::Direction;
+
+/// Version 3.0 of the protocol, sent in the StartupMessage
+pub const PROTOCOL_VERSION_3: u32 = 196608;
+/// Code of the request to use TLS, sent in place of a protocol version
+pub const SSL_REQUEST_CODE: u32 = 80877103;
+/// Code of the request to use GSSAPI encryption, sent in place of a protocol version
+pub const GSSENC_REQUEST_CODE: u32 = 80877104;
+/// Code of the request to cancel a query, sent in place of a protocol version
+pub const CANCEL_REQUEST_CODE: u32 = 80877102;
+
+/// Type of the authentication messages sent by the server
+pub const AUTHENTICATION: u8 = b'R';
+/// Type of the messages containing a password, or a SASL response, sent by the client
+pub const PASSWORD_MESSAGE: u8 = b'p';
+/// Type of the error messages sent by the server
+pub const ERROR_RESPONSE: u8 = b'E';
+
+/// Authentication succeeded
+pub const AUTHENTICATION_OK: u32 = 0;
+/// The server requests a clear-text password
+pub const AUTHENTICATION_CLEARTEXT_PASSWORD: u32 = 3;
+/// The server requests an MD5-hashed password
+pub const AUTHENTICATION_MD5_PASSWORD: u32 = 5;
+/// The server starts a SASL authentication
+pub const AUTHENTICATION_SASL: u32 = 10;
+/// The server sends a SASL challenge
+pub const AUTHENTICATION_SASL_CONTINUE: u32 = 11;
+/// The server sends the outcome of a SASL authentication
+pub const AUTHENTICATION_SASL_FINAL: u32 = 12;
+
+/// SQLSTATE code of authorization errors
+pub const INVALID_AUTHORIZATION_SPECIFICATION: &str = "28000";
+
+const LENGTH_SIZE: usize = 4;
+
+/// Message of the PostgreSQL frontend/backend protocol
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PostgresMessage {
+    /// Message without a type, sent by the client at the start of a connection:
+    /// StartupMessage, SSLRequest, GSSENCRequest or CancelRequest.
+    /// The content doesn't include the length of the message
+    Untyped(Vec<u8>),
+    /// Message with a type and its content, without the length of the message
+    Typed(u8, Vec<u8>),
+    /// Single byte, sent by the server to answer an SSLRequest or a GSSENCRequest
+    Byte(u8),
+}
+
+impl PostgresMessage {
+    /// Code of an untyped message: the protocol version or the code of a request
+    pub fn code(&self) -> Option<u32> {
+        match self {
+            PostgresMessage::Untyped(content) if content.len() >= 4 => {
+                Some((&content[..4]).get_u32())
+            }
+            _ => None,
+        }
+    }
+
+    /// Authentication message sent by the server, with its code and its data
+    pub fn authentication(&self) -> Option<(u32, &[u8])> {
+        match self {
+            PostgresMessage::Typed(AUTHENTICATION, content) if content.len() >= 4 => {
+                Some(((&content[..4]).get_u32(), &content[4..]))
+            }
+            _ => None,
+        }
+    }
+
+    /// Fatal error sent by the server before closing the connection
+    pub fn fatal_error(code: &str, message: &str) -> Self {
+        let mut content = vec![];
+        for (field, value) in [
+            (b'S', "FATAL"),
+            (b'V', "FATAL"),
+            (b'C', code),
+            (b'M', message),
+        ] {
+            content.push(field);
+            content.extend_from_slice(value.as_bytes());
+            content.push(0);
+        }
+        content.push(0);
+        PostgresMessage::Typed(ERROR_RESPONSE, content)
+    }
+}
+
+/// First message sent by the client, with the protocol version and the parameters
+/// of the connection: user, database, ...
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StartupMessage {
+    pub protocol_version: u32,
+    pub parameters: Vec<(String, String)>,
+}
+
+impl StartupMessage {
+    /// Decode the content of an untyped message
+    pub fn decode(content: &[u8]) -> Result<Self> {
+        if content.len() < 4 {
+            return Err(invalid("the startup message is too short"));
+        }
+        let protocol_version = (&content[..4]).get_u32();
+
+        let mut parameters = vec![];
+        let mut fields = content[4..].split(|b| *b == 0);
+        loop {
+            let name = fields
+                .next()
+                .ok_or_else(|| invalid("the startup message parameters are not terminated"))?;
+            if name.is_empty() {
+                break;
+            }
+            let value = fields
+                .next()
+                .ok_or_else(|| invalid("a startup message parameter has no value"))?;
+            parameters.push((utf8(name)?, utf8(value)?));
+        }
+
+        Ok(Self {
+            protocol_version,
+            parameters,
+        })
+    }
+
+    /// Encode the message as the content of an untyped message
+    pub fn encode(&self) -> PostgresMessage {
+        let mut content = self.protocol_version.to_be_bytes().to_vec();
+        for (name, value) in &self.parameters {
+            content.extend_from_slice(name.as_bytes());
+            content.push(0);
+            content.extend_from_slice(value.as_bytes());
+            content.push(0);
+        }
+        content.push(0);
+        PostgresMessage::Untyped(content)
+    }
+
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Set the value of a parameter, replacing its previous value
+    pub fn set_parameter(&mut self, name: &str, value: &str) {
+        match self.parameters.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.parameters.push((name.to_string(), value.to_string())),
+        }
+    }
+}
+
+/// Framing of the messages of one direction of a PostgreSQL connection.
+/// The messages sent by the client don't have a type until the StartupMessage is sent
+pub struct PostgresFraming {
+    buffer: BytesMut,
+    typed: bool,
+    max_message_size: usize,
+}
+
+impl PostgresFraming {
+    pub fn new(direction: Direction, max_message_size: usize) -> Self {
+        Self {
+            buffer: BytesMut::new(),
+            typed: direction == Direction::FromOutletToInlet,
+            max_message_size,
+        }
+    }
+}
+
+impl Framing for PostgresFraming {
+    type Message = PostgresMessage;
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<PostgresMessage>> {
+        self.buffer.extend_from_slice(data);
+
+        let mut messages = vec![];
+        loop {
+            let type_size = if self.typed { 1 } else { 0 };
+            if self.buffer.len() < type_size + LENGTH_SIZE {
+                break;
+            }
+            let length = (&self.buffer[type_size..type_size + LENGTH_SIZE]).get_u32() as usize;
+            if length < LENGTH_SIZE {
+                return Err(invalid("the message length is invalid"));
+            }
+            if length > self.max_message_size {
+                return Err(Error::new(
+                    Origin::Transport,
+                    Kind::ResourceExhausted,
+                    "message is bigger than the maximum size",
+                ));
+            }
+            if self.buffer.len() < type_size + length {
+                break;
+            }
+
+            let message = if self.typed {
+                let message_type = self.buffer.get_u8();
+                self.buffer.advance(LENGTH_SIZE);
+                PostgresMessage::Typed(message_type, self.buffer.split_to(length - 4).to_vec())
+            } else {
+                self.buffer.advance(LENGTH_SIZE);
+                let message = PostgresMessage::Untyped(self.buffer.split_to(length - 4).to_vec());
+                // the messages following the StartupMessage have a type
+                if message.code().map_or(false, |code| code >> 16 == 3) {
+                    self.typed = true;
+                }
+                message
+            };
+            messages.push(message);
+        }
+        Ok(messages)
+    }
+
+    fn encode(&mut self, message: PostgresMessage, buffer: &mut Vec<u8>) -> Result<()> {
+        match message {
+            PostgresMessage::Untyped(content) => {
+                buffer.put_u32(length(&content)?);
+                buffer.extend_from_slice(&content);
+            }
+            PostgresMessage::Typed(message_type, content) => {
+                buffer.put_u8(message_type);
+                buffer.put_u32(length(&content)?);
+                buffer.extend_from_slice(&content);
+            }
+            PostgresMessage::Byte(byte) => buffer.put_u8(byte),
+        }
+        Ok(())
+    }
+}
+
+fn length(content: &[u8]) -> Result<u32> {
+    u32::try_from(content.len() + LENGTH_SIZE).map_err(|_| {
+        Error::new(
+            Origin::Transport,
+            Kind::ResourceExhausted,
+            "message is bigger than 4GB",
+        )
+    })
+}
+
+fn utf8(value: &[u8]) -> Result<String> {
+    String::from_utf8(value.to_vec()).map_err(|_| invalid("a parameter is not valid UTF-8"))
+}
+
+pub(crate) fn invalid(message: &str) -> Error {
+    Error::new(Origin::Transport, Kind::Protocol, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_messages_are_typed_after_the_startup_message() {
+        let mut framing = PostgresFraming::new(Direction::FromInletToOutlet, 1024);
+        let startup = StartupMessage {
+            protocol_version: PROTOCOL_VERSION_3,
+            parameters: vec![
+                ("user".to_string(), "alice".to_string()),
+                ("database".to_string(), "orders".to_string()),
+            ],
+        };
+
+        let mut data = vec![];
+        let ssl_request = PostgresMessage::Untyped(SSL_REQUEST_CODE.to_be_bytes().to_vec());
+        framing.encode(ssl_request.clone(), &mut data).unwrap();
+        framing.encode(startup.encode(), &mut data).unwrap();
+        let query = PostgresMessage::Typed(b'Q', b"SELECT 1\0".to_vec());
+        framing.encode(query.clone(), &mut data).unwrap();
+
+        let mut messages = vec![];
+        for chunk in data.chunks(5) {
+            messages.extend(framing.decode(chunk).unwrap());
+        }
+        assert_eq!(messages, vec![ssl_request, startup.encode(), query]);
+        assert_eq!(messages[0].code(), Some(SSL_REQUEST_CODE));
+
+        let PostgresMessage::Untyped(content) = &messages[1] else {
+            panic!("the startup message is untyped")
+        };
+        let mut decoded = StartupMessage::decode(content).unwrap();
+        assert_eq!(decoded, startup);
+        decoded.set_parameter("user", "ockam_user");
+        assert_eq!(decoded.parameter("user"), Some("ockam_user"));
+        assert_eq!(decoded.parameter("database"), Some("orders"));
+    }
+
+    #[test]
+    fn server_messages_are_typed() {
+        let mut framing = PostgresFraming::new(Direction::FromOutletToInlet, 16);
+        let data = [b'R', 0, 0, 0, 8, 0, 0, 0, 0];
+        let messages = framing.decode(&data).unwrap();
+        assert_eq!(
+            messages[0].authentication(),
+            Some((AUTHENTICATION_OK, &[][..]))
+        );
+        assert!(framing.decode(&[b'D', 0, 0, 0, 17]).is_err());
+    }
+}
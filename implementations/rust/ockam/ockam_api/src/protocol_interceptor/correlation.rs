@@ -0,0 +1,137 @@
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+/// Default maximum number of requests waiting for their responses on a connection
+pub const DEFAULT_MAX_PENDING_REQUESTS: usize = 1024;
+
+/// Requests waiting for their responses, matched by an identifier sent with both,
+/// like the correlation id of Kafka messages
+pub struct CorrelationMap<K, V> {
+    pending: HashMap<K, V>,
+    max_pending: usize,
+}
+
+impl<K: Eq + Hash, V> CorrelationMap<K, V> {
+    pub fn new(max_pending: usize) -> Self {
+        Self {
+            pending: HashMap::new(),
+            max_pending,
+        }
+    }
+
+    /// Keep the information about a request until its response is received
+    pub fn insert(&mut self, key: K, value: V) -> Result<()> {
+        if self.pending.len() >= self.max_pending && !self.pending.contains_key(&key) {
+            return Err(too_many_pending_requests());
+        }
+        self.pending.insert(key, value);
+        Ok(())
+    }
+
+    /// Return the information about the request of a response
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.pending.remove(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+impl<K: Eq + Hash, V> Default for CorrelationMap<K, V> {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_PENDING_REQUESTS)
+    }
+}
+
+/// Requests waiting for their responses, for the protocols where the responses are sent
+/// in the same order as the requests, like HTTP/1.1
+pub struct PendingRequests<V> {
+    pending: VecDeque<V>,
+    max_pending: usize,
+}
+
+impl<V> PendingRequests<V> {
+    pub fn new(max_pending: usize) -> Self {
+        Self {
+            pending: VecDeque::new(),
+            max_pending,
+        }
+    }
+
+    /// Keep the information about a request until its response is received
+    pub fn push(&mut self, value: V) -> Result<()> {
+        if self.pending.len() >= self.max_pending {
+            return Err(too_many_pending_requests());
+        }
+        self.pending.push_back(value);
+        Ok(())
+    }
+
+    /// Return the information about the oldest request, when its response is received
+    pub fn pop(&mut self) -> Option<V> {
+        self.pending.pop_front()
+    }
+
+    /// Return the information about the oldest request, without removing it.
+    /// This is useful when a request receives several responses, like the informational
+    /// responses of HTTP
+    pub fn peek(&self) -> Option<&V> {
+        self.pending.front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+impl<V> Default for PendingRequests<V> {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_PENDING_REQUESTS)
+    }
+}
+
+fn too_many_pending_requests() -> Error {
+    Error::new(
+        Origin::Transport,
+        Kind::ResourceExhausted,
+        "too many requests are waiting for a response",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn responses_are_matched_with_their_requests() {
+        let mut map = CorrelationMap::new(2);
+        map.insert(1, "metadata").unwrap();
+        map.insert(2, "fetch").unwrap();
+        assert!(map.insert(3, "produce").is_err());
+
+        assert_eq!(map.remove(&2), Some("fetch"));
+        assert_eq!(map.remove(&2), None);
+        map.insert(3, "produce").unwrap();
+        assert_eq!(map.len(), 2);
+
+        let mut pending = PendingRequests::new(2);
+        pending.push("GET /a").unwrap();
+        pending.push("GET /b").unwrap();
+        assert!(pending.push("GET /c").is_err());
+        assert_eq!(pending.peek(), Some(&"GET /a"));
+        assert_eq!(pending.pop(), Some("GET /a"));
+        assert_eq!(pending.pop(), Some("GET /b"));
+        assert!(pending.is_empty());
+    }
+}
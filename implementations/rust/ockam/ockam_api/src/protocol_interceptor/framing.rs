@@ -0,0 +1,194 @@
+use bytes::{Buf, BytesMut};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+
+/// Delimit the messages of a protocol in one direction of a connection.
+/// The data of a portal is received in arbitrary pieces, the framing keeps the incomplete
+/// messages until the rest of their data is received
+pub trait Framing: Send + 'static {
+    /// Message extracted from the data of the connection
+    type Message: Send + 'static;
+
+    /// Accumulate the data received from the connection and return the complete messages
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<Self::Message>>;
+
+    /// Append the encoded message to the buffer
+    fn encode(&mut self, message: Self::Message, buffer: &mut Vec<u8>) -> Result<()>;
+}
+
+/// Framing of messages prefixed with their length as a big-endian `u32`, like Kafka messages
+pub struct LengthPrefixedFraming {
+    buffer: BytesMut,
+    max_message_size: usize,
+}
+
+const LENGTH_PREFIX_SIZE: usize = 4;
+
+impl LengthPrefixedFraming {
+    pub fn new(max_message_size: usize) -> Self {
+        Self {
+            buffer: BytesMut::new(),
+            max_message_size,
+        }
+    }
+}
+
+impl Framing for LengthPrefixedFraming {
+    /// The content of a message, without its length
+    type Message = BytesMut;
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<BytesMut>> {
+        self.buffer.extend_from_slice(data);
+
+        let mut messages = vec![];
+        while self.buffer.len() >= LENGTH_PREFIX_SIZE {
+            let mut prefix = &self.buffer[..LENGTH_PREFIX_SIZE];
+            let length = prefix.get_u32() as usize;
+            if length > self.max_message_size {
+                return Err(Error::new(
+                    Origin::Transport,
+                    Kind::ResourceExhausted,
+                    "message is bigger than the maximum size",
+                ));
+            }
+            if self.buffer.len() < LENGTH_PREFIX_SIZE + length {
+                break;
+            }
+            self.buffer.advance(LENGTH_PREFIX_SIZE);
+            messages.push(self.buffer.split_to(length));
+        }
+        Ok(messages)
+    }
+
+    fn encode(&mut self, message: BytesMut, buffer: &mut Vec<u8>) -> Result<()> {
+        let length = u32::try_from(message.len()).map_err(|_| {
+            Error::new(
+                Origin::Transport,
+                Kind::ResourceExhausted,
+                "message is bigger than 4GB",
+            )
+        })?;
+        buffer.extend_from_slice(&length.to_be_bytes());
+        buffer.extend_from_slice(&message);
+        Ok(())
+    }
+}
+
+/// Framing of messages terminated by a delimiter, like the commands of text protocols
+pub struct LineFraming {
+    buffer: Vec<u8>,
+    delimiter: &'static [u8],
+    max_line_length: usize,
+}
+
+impl LineFraming {
+    pub fn new(delimiter: &'static [u8], max_line_length: usize) -> Self {
+        Self {
+            buffer: vec![],
+            delimiter,
+            max_line_length,
+        }
+    }
+
+    /// Lines terminated by `\r\n`
+    pub fn crlf(max_line_length: usize) -> Self {
+        Self::new(b"\r\n", max_line_length)
+    }
+
+    /// Lines terminated by `\n`
+    pub fn lf(max_line_length: usize) -> Self {
+        Self::new(b"\n", max_line_length)
+    }
+}
+
+impl Framing for LineFraming {
+    /// A line, without its delimiter
+    type Message = Vec<u8>;
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        // only search the delimiter in the new data, and the end of the previous data
+        // in case the delimiter was split
+        let mut from = self
+            .buffer
+            .len()
+            .saturating_sub(self.delimiter.len().saturating_sub(1));
+        self.buffer.extend_from_slice(data);
+
+        let mut lines = vec![];
+        let mut start = 0;
+        while let Some(position) = self.buffer[from..]
+            .windows(self.delimiter.len())
+            .position(|w| w == self.delimiter)
+        {
+            let end = from + position;
+            lines.push(self.buffer[start..end].to_vec());
+            start = end + self.delimiter.len();
+            from = start;
+        }
+        self.buffer.drain(..start);
+
+        if self.buffer.len() > self.max_line_length {
+            return Err(Error::new(
+                Origin::Transport,
+                Kind::ResourceExhausted,
+                "line is longer than the maximum length",
+            ));
+        }
+        Ok(lines)
+    }
+
+    fn encode(&mut self, line: Vec<u8>, buffer: &mut Vec<u8>) -> Result<()> {
+        buffer.extend_from_slice(&line);
+        buffer.extend_from_slice(self.delimiter);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn length_prefixed_messages_in_pieces() {
+        let mut framing = LengthPrefixedFraming::new(16);
+        let data = [
+            &[0, 0, 0, 3][..],
+            b"abc",
+            &[0, 0, 0, 0],
+            &[0, 0, 0, 2],
+            b"de",
+        ]
+        .concat();
+
+        // the length prefix itself can be split
+        assert!(framing.decode(&data[..2]).unwrap().is_empty());
+        assert!(framing.decode(&data[2..6]).unwrap().is_empty());
+        let messages = framing.decode(&data[6..]).unwrap();
+        assert_eq!(messages, vec!["abc", "", "de"]);
+
+        let mut encoded = vec![];
+        for message in messages {
+            framing.encode(message, &mut encoded).unwrap();
+        }
+        assert_eq!(encoded, data);
+
+        assert!(framing.decode(&[0, 0, 0, 17]).is_err());
+    }
+
+    #[test]
+    fn lines_in_pieces() {
+        let mut framing = LineFraming::crlf(8);
+        assert!(framing.decode(b"PING\r").unwrap().is_empty());
+        assert_eq!(
+            framing.decode(b"\nSET a\r\nGET").unwrap(),
+            vec![b"PING".to_vec(), b"SET a".to_vec()]
+        );
+        assert_eq!(framing.decode(b" a\r\n").unwrap(), vec![b"GET a".to_vec()]);
+
+        let mut encoded = vec![];
+        framing.encode(b"OK".to_vec(), &mut encoded).unwrap();
+        assert_eq!(encoded, b"OK\r\n");
+
+        assert!(framing.decode(b"too long line").is_err());
+    }
+}
@@ -0,0 +1,329 @@
+mod streaming;
+
+pub use streaming::*;
+
+use crate::protocol_interceptor::Framing;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use ockam_transport_tcp::Direction;
+
+const HEAD_SEPARATOR: &[u8] = b"\r\n\r\n";
+const LINE_SEPARATOR: &[u8] = b"\r\n";
+
+/// HTTP/1.1 request or response, with its body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpMessage {
+    /// Start line and headers, without the final empty line
+    head: String,
+    /// Body, after removing the chunked transfer encoding
+    body: Vec<u8>,
+    /// Message as forwarded: the head is encoded again, and the body is sent as received
+    raw: Vec<u8>,
+}
+
+impl HttpMessage {
+    /// Request line or status line
+    pub fn start_line(&self) -> &str {
+        self.head.lines().next().unwrap_or_default()
+    }
+
+    /// Method of a request
+    pub fn method(&self) -> &str {
+        self.start_line().split(' ').next().unwrap_or_default()
+    }
+
+    /// Status code of a response
+    pub fn status(&self) -> Option<u16> {
+        self.start_line().split(' ').nth(1)?.parse().ok()
+    }
+
+    /// Value of a header, the name is case-insensitive
+    pub fn header(&self, name: &str) -> Option<&str> {
+        header(&self.head, name)
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// The message as forwarded
+    pub fn raw(&self) -> &[u8] {
+        &self.raw
+    }
+
+    /// Encode the message with a different body, sent with a content length
+    pub fn encode_with_body(&self, body: &[u8]) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(self.head.len() + body.len() + 64);
+        for (index, line) in self.head.split("\r\n").enumerate() {
+            let name = line.split(':').next().unwrap_or_default().trim();
+            if index > 0
+                && (name.eq_ignore_ascii_case("content-length")
+                    || name.eq_ignore_ascii_case("transfer-encoding"))
+            {
+                continue;
+            }
+            encoded.extend_from_slice(line.as_bytes());
+            encoded.extend_from_slice(LINE_SEPARATOR);
+        }
+        encoded.extend_from_slice(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes());
+        encoded.extend_from_slice(body);
+        encoded
+    }
+
+    /// Return the same message with a different body, sent with a content length
+    pub fn with_body(&self, body: Vec<u8>) -> HttpMessage {
+        let raw = self.encode_with_body(&body);
+        let head_length = find(&raw, HEAD_SEPARATOR).unwrap_or_default();
+        HttpMessage {
+            head: String::from_utf8_lossy(&raw[..head_length]).to_string(),
+            body,
+            raw,
+        }
+    }
+}
+
+/// Framing of one direction of an HTTP/1.1 connection, returning complete messages.
+/// The messages are delimited by an [`HttpStreamingFraming`], and their body is buffered.
+/// When a message can't be delimited, like a response terminated by the end of the connection,
+/// the data is passed through without being decoded for the rest of the connection
+pub struct HttpFraming {
+    framing: HttpStreamingFraming,
+    /// Message whose body is being received
+    current: Option<PartialMessage>,
+    passthrough: bool,
+    max_message_size: usize,
+}
+
+/// Data extracted from a connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpData {
+    /// A complete request or response
+    Message(HttpMessage),
+    /// Data which could not be delimited
+    Passthrough(Vec<u8>),
+}
+
+struct PartialMessage {
+    raw: Vec<u8>,
+    head_length: usize,
+    chunked: bool,
+}
+
+impl PartialMessage {
+    fn new(head: &HttpHead) -> Self {
+        let mut raw = vec![];
+        head.encode(&mut raw);
+        Self {
+            head_length: raw.len(),
+            raw,
+            chunked: head.has_token("transfer-encoding", "chunked"),
+        }
+    }
+
+    fn complete(self) -> HttpMessage {
+        let body = &self.raw[self.head_length..];
+        let body = if self.chunked {
+            decode_chunks(body)
+        } else {
+            body.to_vec()
+        };
+        let head = String::from_utf8_lossy(&self.raw[..self.head_length - HEAD_SEPARATOR.len()])
+            .to_string();
+        HttpMessage {
+            head,
+            body,
+            raw: self.raw,
+        }
+    }
+}
+
+impl HttpFraming {
+    /// Create the framing of the requests when the direction is [`Direction::FromInletToOutlet`],
+    /// and of the responses otherwise
+    pub fn new(direction: Direction, max_message_size: usize) -> Self {
+        Self {
+            framing: HttpStreamingFraming::new(
+                direction,
+                HttpConnectionShared::default(),
+                max_message_size,
+                max_message_size,
+            ),
+            current: None,
+            passthrough: false,
+            max_message_size,
+        }
+    }
+
+    fn extract(&mut self, data: &[u8]) -> Result<Vec<HttpData>> {
+        if self.passthrough {
+            return Ok(vec![HttpData::Passthrough(data.to_vec())]);
+        }
+
+        let mut result = vec![];
+        let mut passthrough = vec![];
+        for message in self.framing.decode(data)? {
+            if self.passthrough {
+                self.framing.encode(message, &mut passthrough)?;
+                continue;
+            }
+            match message {
+                HttpStreamingMessage::Head { head, has_body } => {
+                    let message = PartialMessage::new(&head);
+                    if !has_body {
+                        result.push(HttpData::Message(message.complete()));
+                    } else if head.header("content-length").is_none() && !message.chunked {
+                        // the response body is terminated by the end of the connection
+                        self.passthrough = true;
+                        passthrough = message.raw;
+                    } else {
+                        self.current = Some(message);
+                    }
+                }
+                HttpStreamingMessage::Body { data, last } => {
+                    let Some(current) = self.current.as_mut() else {
+                        return Err(invalid("HTTP body received without a head"));
+                    };
+                    current.raw.extend_from_slice(&data);
+                    if current.raw.len() > self.max_message_size {
+                        warn!("the HTTP message exceeds {} bytes", self.max_message_size);
+                        return Err(Error::new(
+                            Origin::Transport,
+                            Kind::ResourceExhausted,
+                            "HTTP message too large",
+                        ));
+                    }
+                    if last {
+                        if let Some(current) = self.current.take() {
+                            result.push(HttpData::Message(current.complete()));
+                        }
+                    }
+                }
+                // a connection switching to another protocol is passed through
+                message => {
+                    self.passthrough = true;
+                    self.framing.encode(message, &mut passthrough)?;
+                }
+            }
+        }
+        if !passthrough.is_empty() {
+            result.push(HttpData::Passthrough(passthrough));
+        }
+        Ok(result)
+    }
+}
+
+impl Framing for HttpFraming {
+    type Message = HttpData;
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<HttpData>> {
+        self.extract(data)
+    }
+
+    fn encode(&mut self, message: HttpData, buffer: &mut Vec<u8>) -> Result<()> {
+        match message {
+            HttpData::Message(message) => buffer.extend_from_slice(&message.raw),
+            HttpData::Passthrough(data) => buffer.extend_from_slice(&data),
+        }
+        Ok(())
+    }
+}
+
+/// Remove the chunked transfer encoding of a complete body
+fn decode_chunks(mut data: &[u8]) -> Vec<u8> {
+    let mut body = vec![];
+    while let Ok(httparse::Status::Complete((start, size))) = httparse::parse_chunk_size(data) {
+        let Some(chunk) = usize::try_from(size)
+            .ok()
+            .filter(|size| *size > 0)
+            .and_then(|size| data.get(start..start + size))
+        else {
+            break;
+        };
+        body.extend_from_slice(chunk);
+        data = data
+            .get(start + chunk.len() + LINE_SEPARATOR.len()..)
+            .unwrap_or_default();
+    }
+    body
+}
+
+fn invalid(message: &'static str) -> Error {
+    Error::new(Origin::Transport, Kind::Protocol, message)
+}
+
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.split("\r\n").skip(1).find_map(|line| {
+        let (header_name, value) = line.split_once(':')?;
+        if header_name.trim().eq_ignore_ascii_case(name) {
+            Some(value.trim())
+        } else {
+            None
+        }
+    })
+}
+
+fn find(data: &[u8], pattern: &[u8]) -> Option<usize> {
+    data.windows(pattern.len()).position(|w| w == pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(data: Vec<HttpData>) -> Vec<HttpMessage> {
+        data.into_iter()
+            .map(|d| match d {
+                HttpData::Message(message) => message,
+                HttpData::Passthrough(_) => panic!("unexpected passthrough"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn extract_requests_in_pieces() {
+        let mut framing = HttpFraming::new(Direction::FromInletToOutlet, 1024);
+        let data = b"POST /subjects/a/versions HTTP/1.1\r\nHost: localhost\r\nContent-Length: 4\r\n\r\nbodyGET /schemas/ids/1 HTTP/1.1\r\n\r\n";
+
+        assert!(framing.decode(&data[..20]).unwrap().is_empty());
+        let requests = messages(framing.decode(&data[20..]).unwrap());
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].method(), "POST");
+        assert_eq!(requests[0].header("content-length"), Some("4"));
+        assert_eq!(requests[0].body(), b"body");
+        assert_eq!(requests[1].start_line(), "GET /schemas/ids/1 HTTP/1.1");
+
+        let raw = [requests[0].raw(), requests[1].raw()].concat();
+        assert_eq!(raw, data);
+    }
+
+    #[test]
+    fn extract_chunked_response() {
+        let mut framing = HttpFraming::new(Direction::FromOutletToInlet, 1024);
+        let data = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2;ext=1\r\nde\r\n0\r\n\r\n";
+
+        assert!(framing.decode(&data[..data.len() - 2]).unwrap().is_empty());
+        let responses = messages(framing.decode(&data[data.len() - 2..]).unwrap());
+        assert_eq!(responses[0].status(), Some(200));
+        assert_eq!(responses[0].body(), b"abcde");
+
+        let encoded = responses[0].encode_with_body(b"xyz");
+        assert_eq!(
+            encoded,
+            b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\nxyz".to_vec()
+        );
+        let response = responses[0].with_body(b"xyz".to_vec());
+        assert_eq!(response.raw(), encoded);
+        assert_eq!(response.header("content-length"), Some("3"));
+    }
+
+    #[test]
+    fn pass_through_responses_without_length() {
+        let mut framing = HttpFraming::new(Direction::FromOutletToInlet, 1024);
+        let data = framing.decode(b"HTTP/1.1 200 OK\r\n\r\nsome").unwrap();
+        assert!(
+            matches!(&data[..], [HttpData::Passthrough(d)] if d == b"HTTP/1.1 200 OK\r\n\r\nsome")
+        );
+        let data = framing.decode(b" data").unwrap();
+        assert!(matches!(&data[..], [HttpData::Passthrough(d)] if d == b" data"));
+    }
+}
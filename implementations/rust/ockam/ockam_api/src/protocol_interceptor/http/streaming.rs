@@ -0,0 +1,635 @@
+use crate::protocol_interceptor::{Framing, DEFAULT_MAX_PENDING_REQUESTS};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use ockam_transport_tcp::Direction;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Connection preface sent by an HTTP/2 client, RFC 9113 section 3.4
+pub const HTTP2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// Maximum number of headers of an HTTP/1.1 message
+const MAX_HEADERS: usize = 128;
+const HTTP2_FRAME_HEADER_SIZE: usize = 9;
+const LINE_SEPARATOR: &[u8] = b"\r\n";
+
+pub const HTTP2_DATA: u8 = 0x0;
+pub const HTTP2_HEADERS: u8 = 0x1;
+pub const HTTP2_RST_STREAM: u8 = 0x3;
+pub const HTTP2_PUSH_PROMISE: u8 = 0x5;
+pub const HTTP2_WINDOW_UPDATE: u8 = 0x8;
+pub const HTTP2_CONTINUATION: u8 = 0x9;
+
+pub const HTTP2_END_STREAM: u8 = 0x1;
+pub const HTTP2_END_HEADERS: u8 = 0x4;
+pub const HTTP2_PADDED: u8 = 0x8;
+pub const HTTP2_PRIORITY: u8 = 0x20;
+
+/// Start line and headers of an HTTP/1.1 request or response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpHead {
+    /// Request line or status line
+    pub start_line: String,
+    pub headers: Vec<(String, Vec<u8>)>,
+}
+
+impl HttpHead {
+    /// Head of a response without body
+    pub fn empty_response(status: u16, reason: &str) -> Self {
+        Self {
+            start_line: format!("HTTP/1.1 {status} {reason}"),
+            headers: vec![("Content-Length".to_string(), b"0".to_vec())],
+        }
+    }
+
+    /// Method of a request
+    pub fn method(&self) -> &str {
+        self.start_line.split(' ').next().unwrap_or_default()
+    }
+
+    /// Target of a request: a path, an absolute URI, an authority or `*`
+    pub fn target(&self) -> &str {
+        self.start_line.split(' ').nth(1).unwrap_or_default()
+    }
+
+    /// Status code of a response
+    pub fn status(&self) -> Option<u16> {
+        self.start_line.split(' ').nth(1)?.parse().ok()
+    }
+
+    /// Value of a header, the name is case-insensitive
+    pub fn header(&self, name: &str) -> Option<&[u8]> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_slice())
+    }
+
+    /// Return true if a header value contains a token, case-insensitively,
+    /// like `chunked` in `Transfer-Encoding: gzip, chunked`
+    pub fn has_token(&self, name: &str, token: &str) -> bool {
+        self.headers
+            .iter()
+            .filter(|(n, _)| n.eq_ignore_ascii_case(name))
+            .flat_map(|(_, v)| v.split(|b| *b == b','))
+            .any(|t| trim(t).eq_ignore_ascii_case(token.as_bytes()))
+    }
+
+    pub fn encode(&self, buffer: &mut Vec<u8>) {
+        buffer.extend_from_slice(self.start_line.as_bytes());
+        buffer.extend_from_slice(LINE_SEPARATOR);
+        for (name, value) in &self.headers {
+            buffer.extend_from_slice(name.as_bytes());
+            buffer.extend_from_slice(b": ");
+            buffer.extend_from_slice(value);
+            buffer.extend_from_slice(LINE_SEPARATOR);
+        }
+        buffer.extend_from_slice(LINE_SEPARATOR);
+    }
+}
+
+/// HTTP/2 frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Http2Frame {
+    pub frame_type: u8,
+    pub flags: u8,
+    pub stream_id: u32,
+    pub payload: Vec<u8>,
+}
+
+impl Http2Frame {
+    pub fn has_flag(&self, flag: u8) -> bool {
+        self.flags & flag != 0
+    }
+
+    pub fn encode(&self, buffer: &mut Vec<u8>) {
+        buffer.extend_from_slice(&(self.payload.len() as u32).to_be_bytes()[1..]);
+        buffer.push(self.frame_type);
+        buffer.push(self.flags);
+        buffer.extend_from_slice(&(self.stream_id & 0x7fff_ffff).to_be_bytes());
+        buffer.extend_from_slice(&self.payload);
+    }
+}
+
+/// Part of an HTTP connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpStreamingMessage {
+    /// Head of an HTTP/1.1 message, followed by [`HttpStreamingMessage::Body`] parts
+    /// when the message has a body
+    Head { head: HttpHead, has_body: bool },
+    /// Part of the body of the last message, as received, with its chunked encoding.
+    /// The last part of a body is marked, unless the body is terminated by the end of
+    /// the connection
+    Body { data: Vec<u8>, last: bool },
+    /// Connection preface of an HTTP/2 client
+    Preface,
+    /// A single HTTP/2 frame, or a complete header block: a HEADERS or a PUSH_PROMISE
+    /// frame followed by its CONTINUATION frames
+    Frames(Vec<Http2Frame>),
+    /// Data of a connection which switched to another protocol
+    Passthrough(Vec<u8>),
+}
+
+/// Protocol of an HTTP connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpProtocol {
+    Http1,
+    /// HTTP/2 without TLS, with prior knowledge
+    Http2,
+    /// The connection switched to another protocol, after a `101 Switching Protocols` response
+    Switched,
+}
+
+/// Kind of a request, which determines how its response is delimited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind {
+    Regular,
+    /// The response to a HEAD request has no body
+    Head,
+}
+
+/// State of a connection shared by the framings of both directions
+#[derive(Clone)]
+pub struct HttpConnectionShared {
+    inner: Arc<Mutex<SharedState>>,
+}
+
+struct SharedState {
+    protocol: HttpProtocol,
+    forwarded_requests: VecDeque<RequestKind>,
+}
+
+impl Default for HttpConnectionShared {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(SharedState {
+                protocol: HttpProtocol::Http1,
+                forwarded_requests: VecDeque::new(),
+            })),
+        }
+    }
+}
+
+impl HttpConnectionShared {
+    pub fn protocol(&self) -> HttpProtocol {
+        self.lock().protocol
+    }
+
+    fn set_protocol(&self, protocol: HttpProtocol) {
+        self.lock().protocol = protocol
+    }
+
+    /// Record that a request was sent to the server, to delimit its response
+    pub fn request_forwarded(&self, kind: RequestKind) -> Result<()> {
+        let mut shared = self.lock();
+        if shared.forwarded_requests.len() >= DEFAULT_MAX_PENDING_REQUESTS {
+            return Err(Error::new(
+                Origin::Transport,
+                Kind::ResourceExhausted,
+                "too many requests are waiting for a response",
+            ));
+        }
+        shared.forwarded_requests.push_back(kind);
+        Ok(())
+    }
+
+    fn response_received(&self) -> RequestKind {
+        self.lock()
+            .forwarded_requests
+            .pop_front()
+            .unwrap_or(RequestKind::Regular)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SharedState> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Framing of one direction of an HTTP connection.
+///
+/// HTTP/1.1 bodies are not buffered: they are returned in parts as they are received.
+/// A connection starting with the HTTP/2 connection preface is delimited in HTTP/2 frames,
+/// and the data of a connection switching to another protocol is passed through
+pub struct HttpStreamingFraming {
+    buffer: Vec<u8>,
+    is_response: bool,
+    shared: HttpConnectionShared,
+    protocol: HttpProtocol,
+    started: bool,
+    body: Option<BodyState>,
+    header_block: Vec<Http2Frame>,
+    max_head_size: usize,
+    max_frame_size: usize,
+}
+
+/// Remaining part of an HTTP/1.1 body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BodyState {
+    Remaining(usize),
+    /// Waiting for the size of the next chunk
+    ChunkSize,
+    /// Remaining data of a chunk, including its line separator
+    Chunk(usize),
+    /// Trailers following the last chunk, terminated by an empty line
+    Trailers,
+    /// Body of a response terminated by the end of the connection
+    UntilClose,
+}
+
+impl HttpStreamingFraming {
+    /// Create the framing of the requests when the direction is [`Direction::FromInletToOutlet`],
+    /// and of the responses otherwise
+    pub fn new(
+        direction: Direction,
+        shared: HttpConnectionShared,
+        max_head_size: usize,
+        max_frame_size: usize,
+    ) -> Self {
+        Self {
+            buffer: vec![],
+            is_response: matches!(direction, Direction::FromOutletToInlet),
+            shared,
+            protocol: HttpProtocol::Http1,
+            started: false,
+            body: None,
+            header_block: vec![],
+            max_head_size,
+            max_frame_size,
+        }
+    }
+
+    /// Switch to the protocol selected by the other direction, between two messages
+    fn follow_protocol(&mut self) {
+        if self.protocol == HttpProtocol::Http1 && self.body.is_none() {
+            self.protocol = self.shared.protocol();
+        }
+    }
+
+    fn decode_http1(&mut self, messages: &mut Vec<HttpStreamingMessage>) -> Result<bool> {
+        if let Some(body) = self.body {
+            return self.decode_body(body, messages);
+        }
+
+        if !self.is_response && !self.started {
+            if self.buffer.len() < HTTP2_PREFACE.len() && HTTP2_PREFACE.starts_with(&self.buffer) {
+                return Ok(false);
+            }
+            self.started = true;
+            if self.buffer.starts_with(HTTP2_PREFACE) {
+                self.buffer.drain(..HTTP2_PREFACE.len());
+                self.protocol = HttpProtocol::Http2;
+                self.shared.set_protocol(HttpProtocol::Http2);
+                messages.push(HttpStreamingMessage::Preface);
+                return Ok(true);
+            }
+        }
+
+        let Some((head, head_length)) = self.parse_head()? else {
+            if self.buffer.len() > self.max_head_size {
+                return Err(Error::new(
+                    Origin::Transport,
+                    Kind::ResourceExhausted,
+                    "the HTTP head is too large",
+                ));
+            }
+            return Ok(false);
+        };
+        self.buffer.drain(..head_length);
+
+        let body = if self.is_response {
+            self.response_body(&head)?
+        } else {
+            request_body(&head)?
+        };
+        self.body = body;
+        messages.push(HttpStreamingMessage::Head {
+            head,
+            has_body: body.is_some(),
+        });
+        Ok(true)
+    }
+
+    fn parse_head(&self) -> Result<Option<(HttpHead, usize)>> {
+        let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+        let (start_line, length, headers) = if self.is_response {
+            let mut response = httparse::Response::new(&mut headers);
+            let httparse::Status::Complete(length) =
+                response.parse(&self.buffer).map_err(invalid_head)?
+            else {
+                return Ok(None);
+            };
+            let start_line = format!(
+                "HTTP/1.{} {} {}",
+                response.version.unwrap_or(1),
+                response.code.unwrap_or_default(),
+                response.reason.unwrap_or_default()
+            );
+            (start_line, length, response.headers)
+        } else {
+            let mut request = httparse::Request::new(&mut headers);
+            let httparse::Status::Complete(length) =
+                request.parse(&self.buffer).map_err(invalid_head)?
+            else {
+                return Ok(None);
+            };
+            let start_line = format!(
+                "{} {} HTTP/1.{}",
+                request.method.unwrap_or_default(),
+                request.path.unwrap_or_default(),
+                request.version.unwrap_or(1)
+            );
+            (start_line, length, request.headers)
+        };
+        let head = HttpHead {
+            start_line,
+            headers: headers
+                .iter()
+                .map(|h| (h.name.to_string(), h.value.to_vec()))
+                .collect(),
+        };
+        Ok(Some((head, length)))
+    }
+
+    fn response_body(&mut self, head: &HttpHead) -> Result<Option<BodyState>> {
+        let status = head.status().unwrap_or_default();
+        if status == 101 {
+            self.shared.response_received();
+            self.switch_protocol();
+            return Ok(None);
+        }
+        // informational responses precede the final response of a request
+        if status < 200 {
+            return Ok(None);
+        }
+        if self.shared.response_received() == RequestKind::Head || status == 204 || status == 304 {
+            return Ok(None);
+        }
+        Ok(Some(body_length(head)?.unwrap_or(BodyState::UntilClose)))
+    }
+
+    fn switch_protocol(&mut self) {
+        self.protocol = HttpProtocol::Switched;
+        self.shared.set_protocol(HttpProtocol::Switched);
+    }
+
+    /// Extract the next part of a body
+    fn decode_body(
+        &mut self,
+        body: BodyState,
+        messages: &mut Vec<HttpStreamingMessage>,
+    ) -> Result<bool> {
+        let (length, next) = match body {
+            BodyState::Remaining(remaining) => {
+                let length = remaining.min(self.buffer.len());
+                let next = Some(remaining - length).filter(|r| *r > 0);
+                (length, next.map(BodyState::Remaining))
+            }
+            BodyState::UntilClose => (self.buffer.len(), Some(BodyState::UntilClose)),
+            BodyState::ChunkSize => match httparse::parse_chunk_size(&self.buffer) {
+                Ok(httparse::Status::Complete((length, 0))) => (length, Some(BodyState::Trailers)),
+                Ok(httparse::Status::Complete((length, size))) => {
+                    let size = usize::try_from(size)
+                        .ok()
+                        .and_then(|s| s.checked_add(LINE_SEPARATOR.len()))
+                        .ok_or_else(|| invalid("the HTTP chunk is too large"))?;
+                    (length, Some(BodyState::Chunk(size)))
+                }
+                Ok(httparse::Status::Partial) => (0, Some(BodyState::ChunkSize)),
+                Err(_) => return Err(invalid("invalid HTTP chunk size")),
+            },
+            BodyState::Chunk(remaining) => {
+                let length = remaining.min(self.buffer.len());
+                let next = match remaining - length {
+                    0 => BodyState::ChunkSize,
+                    remaining => BodyState::Chunk(remaining),
+                };
+                (length, Some(next))
+            }
+            BodyState::Trailers => match find(&self.buffer, LINE_SEPARATOR) {
+                // the empty line terminating the trailers
+                Some(0) => (LINE_SEPARATOR.len(), None),
+                Some(position) => (position + LINE_SEPARATOR.len(), Some(BodyState::Trailers)),
+                None => (0, Some(BodyState::Trailers)),
+            },
+        };
+
+        if length == 0 && next == Some(body) {
+            if self.buffer.len() > self.max_head_size {
+                return Err(invalid("the HTTP chunk size or trailer line is too large"));
+            }
+            return Ok(false);
+        }
+        let data: Vec<u8> = self.buffer.drain(..length).collect();
+        self.body = next;
+        let last = next.is_none();
+        // consecutive parts of the same body are merged
+        match messages.last_mut() {
+            Some(HttpStreamingMessage::Body {
+                data: previous,
+                last: previous_last,
+            }) if !*previous_last => {
+                previous.extend_from_slice(&data);
+                *previous_last = last;
+            }
+            _ => messages.push(HttpStreamingMessage::Body { data, last }),
+        }
+        Ok(true)
+    }
+
+    fn decode_http2(&mut self, messages: &mut Vec<HttpStreamingMessage>) -> Result<bool> {
+        if self.buffer.len() < HTTP2_FRAME_HEADER_SIZE {
+            return Ok(false);
+        }
+        let length = u32::from_be_bytes([0, self.buffer[0], self.buffer[1], self.buffer[2]]);
+        let length = length as usize;
+        if length > self.max_frame_size {
+            return Err(Error::new(
+                Origin::Transport,
+                Kind::ResourceExhausted,
+                "the HTTP/2 frame is too large",
+            ));
+        }
+        if self.buffer.len() < HTTP2_FRAME_HEADER_SIZE + length {
+            return Ok(false);
+        }
+        let frame = Http2Frame {
+            frame_type: self.buffer[3],
+            flags: self.buffer[4],
+            stream_id: u32::from_be_bytes([
+                self.buffer[5],
+                self.buffer[6],
+                self.buffer[7],
+                self.buffer[8],
+            ]) & 0x7fff_ffff,
+            payload: self.buffer[HTTP2_FRAME_HEADER_SIZE..HTTP2_FRAME_HEADER_SIZE + length]
+                .to_vec(),
+        };
+        self.buffer.drain(..HTTP2_FRAME_HEADER_SIZE + length);
+
+        // the frames of a header block are kept together, since no other frame
+        // can be sent between them
+        let in_block = !self.header_block.is_empty();
+        match frame.frame_type {
+            HTTP2_CONTINUATION if in_block => {
+                if frame.stream_id != self.header_block[0].stream_id {
+                    return Err(invalid("unexpected HTTP/2 CONTINUATION frame"));
+                }
+            }
+            HTTP2_CONTINUATION => return Err(invalid("unexpected HTTP/2 CONTINUATION frame")),
+            _ if in_block => return Err(invalid("the HTTP/2 header block is not complete")),
+            HTTP2_HEADERS | HTTP2_PUSH_PROMISE => (),
+            _ => {
+                messages.push(HttpStreamingMessage::Frames(vec![frame]));
+                return Ok(true);
+            }
+        }
+
+        let end_headers = frame.has_flag(HTTP2_END_HEADERS);
+        self.header_block.push(frame);
+        if end_headers {
+            messages.push(HttpStreamingMessage::Frames(std::mem::take(
+                &mut self.header_block,
+            )));
+        } else {
+            let size: usize = self.header_block.iter().map(|f| f.payload.len()).sum();
+            if size > self.max_head_size {
+                return Err(Error::new(
+                    Origin::Transport,
+                    Kind::ResourceExhausted,
+                    "the HTTP/2 header block is too large",
+                ));
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl Framing for HttpStreamingFraming {
+    type Message = HttpStreamingMessage;
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<HttpStreamingMessage>> {
+        self.buffer.extend_from_slice(data);
+
+        let mut messages = vec![];
+        loop {
+            self.follow_protocol();
+            let decoded = match self.protocol {
+                HttpProtocol::Http1 => self.decode_http1(&mut messages)?,
+                HttpProtocol::Http2 => self.decode_http2(&mut messages)?,
+                HttpProtocol::Switched => {
+                    if !self.buffer.is_empty() {
+                        messages.push(HttpStreamingMessage::Passthrough(std::mem::take(
+                            &mut self.buffer,
+                        )));
+                    }
+                    false
+                }
+            };
+            if !decoded {
+                break;
+            }
+        }
+        Ok(messages)
+    }
+
+    fn encode(&mut self, message: HttpStreamingMessage, buffer: &mut Vec<u8>) -> Result<()> {
+        match message {
+            HttpStreamingMessage::Head { head, .. } => head.encode(buffer),
+            HttpStreamingMessage::Body { data, .. } | HttpStreamingMessage::Passthrough(data) => {
+                buffer.extend_from_slice(&data)
+            }
+            HttpStreamingMessage::Preface => buffer.extend_from_slice(HTTP2_PREFACE),
+            HttpStreamingMessage::Frames(frames) => {
+                for frame in frames {
+                    frame.encode(buffer)
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Return the body of a request.
+/// The requests which can't be delimited unambiguously are rejected, to prevent
+/// request smuggling
+fn request_body(head: &HttpHead) -> Result<Option<BodyState>> {
+    if head
+        .headers
+        .iter()
+        .any(|(name, _)| name.eq_ignore_ascii_case("transfer-encoding"))
+    {
+        let chunked_last = head
+            .headers
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("transfer-encoding"))
+            .flat_map(|(_, value)| value.split(|b| *b == b','))
+            .last()
+            .map_or(false, |t| trim(t).eq_ignore_ascii_case(b"chunked"));
+        if !chunked_last {
+            return Err(invalid(
+                "the transfer encoding of the request is not chunked",
+            ));
+        }
+    }
+    body_length(head)
+}
+
+/// Return the body delimited by the chunked transfer encoding or by the content length
+fn body_length(head: &HttpHead) -> Result<Option<BodyState>> {
+    let content_lengths: Vec<&[u8]> = head
+        .headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .map(|(_, value)| value.as_slice())
+        .collect();
+
+    if head.has_token("transfer-encoding", "chunked") {
+        if !content_lengths.is_empty() {
+            return Err(invalid(
+                "the HTTP message has both a content length and a transfer encoding",
+            ));
+        }
+        return Ok(Some(BodyState::ChunkSize));
+    }
+
+    match content_lengths[..] {
+        [] => Ok(None),
+        [length] => {
+            let length = std::str::from_utf8(length)
+                .ok()
+                .filter(|l| !l.is_empty() && l.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|l| l.parse::<usize>().ok())
+                .ok_or_else(|| invalid("invalid HTTP content length"))?;
+            Ok(Some(BodyState::Remaining(length)).filter(|_| length > 0))
+        }
+        _ => Err(invalid("the HTTP message has several content lengths")),
+    }
+}
+
+fn trim(value: &[u8]) -> &[u8] {
+    let start = value
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(value.len());
+    let end = value
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(start, |p| p + 1);
+    &value[start..end]
+}
+
+fn find(data: &[u8], pattern: &[u8]) -> Option<usize> {
+    data.windows(pattern.len()).position(|w| w == pattern)
+}
+
+fn invalid_head(error: httparse::Error) -> Error {
+    Error::new(
+        Origin::Transport,
+        Kind::Protocol,
+        format!("invalid HTTP head: {error}"),
+    )
+}
+
+fn invalid(message: &'static str) -> Error {
+    Error::new(Origin::Transport, Kind::Protocol, message)
+}
@@ -0,0 +1,320 @@
+//! Framework to write protocol-aware portal interceptors.
+//!
+//! A [`ProtocolInterceptor`] receives the complete messages of a protocol instead of the
+//! pieces of data sent through a portal:
+//!
+//!  - a [`Framing`] delimits the messages of each direction of a connection:
+//!    [`LengthPrefixedFraming`], [`LineFraming`], [`HttpFraming`] for complete HTTP/1.1
+//!    messages, or [`HttpStreamingFraming`] for HTTP/1.1 and HTTP/2 messages received in parts,
+//!  - a state is kept for each connection, with a [`CorrelationMap`] or [`PendingRequests`]
+//!    to match the responses with their requests,
+//!  - messages can be sent back to the sender of a message with [`Connection::reply`],
+//!    for example to answer a request without forwarding it,
+//!  - a [`ProtocolInterceptorFactory`] creates a [`PortalInterceptor`] for each connection,
+//!    to be used on either side of a portal.
+//!
+//! The [`testing::InterceptorHarness`] replays captured connections through the interceptors
+//! of both sides of a portal.
+
+mod correlation;
+mod framing;
+pub mod http;
+pub mod testing;
+
+pub use correlation::{CorrelationMap, PendingRequests, DEFAULT_MAX_PENDING_REQUESTS};
+pub use framing::{Framing, LengthPrefixedFraming, LineFraming};
+pub use http::{
+    Http2Frame, HttpConnectionShared, HttpData, HttpFraming, HttpHead, HttpMessage, HttpProtocol,
+    HttpStreamingFraming, HttpStreamingMessage, RequestKind, HTTP2_PREFACE,
+};
+
+use ockam::identity::Identifier;
+use ockam_core::{async_trait, LocalInfo, Result, SecureChannelLocalInfo};
+use ockam_node::Context;
+use ockam_transport_tcp::{
+    Direction, InterceptedData, PortalInterceptor, PortalInterceptorFactory,
+};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Message of the framing of a protocol
+pub type ProtocolMessage<P> = <<P as ProtocolInterceptor>::Framing as Framing>::Message;
+
+/// Interceptor of the messages of a protocol.
+/// The requests are sent from the inlet to the outlet, and the responses from the outlet
+/// to the inlet
+#[async_trait]
+pub trait ProtocolInterceptor: Send + Sync + 'static {
+    /// Framing of the messages of the protocol
+    type Framing: Framing;
+    /// State of a connection
+    type State: Send + 'static;
+
+    /// Create the state of a new connection
+    fn connection_state(&self) -> Self::State;
+
+    /// Create the framing of the messages sent in one direction of a new connection.
+    /// The framing can share some data with the state of the connection, for example when
+    /// a message changes the protocol used by both directions of the connection
+    fn framing(&self, direction: Direction, state: &Self::State) -> Self::Framing;
+
+    /// Intercept a request.
+    /// Return the messages sent in place of the request, none to drop it
+    async fn intercept_request(
+        &self,
+        context: &mut Context,
+        connection: &mut Connection<Self>,
+        request: ProtocolMessage<Self>,
+    ) -> Result<Vec<ProtocolMessage<Self>>>;
+
+    /// Intercept a response.
+    /// Return the messages sent in place of the response, none to drop it
+    async fn intercept_response(
+        &self,
+        context: &mut Context,
+        connection: &mut Connection<Self>,
+        response: ProtocolMessage<Self>,
+    ) -> Result<Vec<ProtocolMessage<Self>>>;
+}
+
+/// A connection intercepted by a [`ProtocolInterceptor`]
+pub struct Connection<P: ProtocolInterceptor + ?Sized> {
+    /// State of the connection, specific to the protocol
+    pub state: P::State,
+    identifier: Option<Identifier>,
+    replies: Vec<ProtocolMessage<P>>,
+}
+
+impl<P: ProtocolInterceptor + ?Sized> Connection<P> {
+    /// Identifier of the other side of the portal, when the messages are received
+    /// from a secure channel
+    pub fn identifier(&self) -> Option<&Identifier> {
+        self.identifier.as_ref()
+    }
+
+    /// Send a message back to the sender of the intercepted message:
+    /// to the client when a request is intercepted, to the server when a response
+    /// is intercepted.
+    /// The replies are not intercepted again by this side of the portal
+    pub fn reply(&mut self, message: ProtocolMessage<P>) {
+        self.replies.push(message);
+    }
+}
+
+/// Create a [`PortalInterceptor`] from a [`ProtocolInterceptor`] for each connection
+pub struct ProtocolInterceptorFactory<P: ProtocolInterceptor> {
+    protocol: Arc<P>,
+}
+
+impl<P: ProtocolInterceptor> ProtocolInterceptorFactory<P> {
+    pub fn new(protocol: P) -> Self {
+        Self {
+            protocol: Arc::new(protocol),
+        }
+    }
+}
+
+impl<P: ProtocolInterceptor> PortalInterceptorFactory for ProtocolInterceptorFactory<P> {
+    fn create(&self) -> Arc<dyn PortalInterceptor> {
+        Arc::new(ProtocolPortalInterceptor::new(self.protocol.clone()))
+    }
+}
+
+/// Delimit the messages of one connection, and pass them to a [`ProtocolInterceptor`]
+struct ProtocolPortalInterceptor<P: ProtocolInterceptor> {
+    protocol: Arc<P>,
+    connection: Mutex<ConnectionData<P>>,
+}
+
+struct ConnectionData<P: ProtocolInterceptor> {
+    connection: Connection<P>,
+    requests: P::Framing,
+    responses: P::Framing,
+}
+
+impl<P: ProtocolInterceptor> ProtocolPortalInterceptor<P> {
+    fn new(protocol: Arc<P>) -> Self {
+        let state = protocol.connection_state();
+        let connection = ConnectionData {
+            requests: protocol.framing(Direction::FromInletToOutlet, &state),
+            responses: protocol.framing(Direction::FromOutletToInlet, &state),
+            connection: Connection {
+                state,
+                identifier: None,
+                replies: vec![],
+            },
+        };
+        Self {
+            protocol,
+            connection: Mutex::new(connection),
+        }
+    }
+}
+
+#[async_trait]
+impl<P: ProtocolInterceptor> PortalInterceptor for ProtocolPortalInterceptor<P> {
+    async fn intercept(
+        &self,
+        context: &mut Context,
+        direction: Direction,
+        buffer: &[u8],
+    ) -> Result<Option<Vec<u8>>> {
+        self.intercept_with_local_info(context, direction, &[], buffer)
+            .await
+    }
+
+    async fn intercept_with_local_info(
+        &self,
+        context: &mut Context,
+        direction: Direction,
+        local_info: &[LocalInfo],
+        buffer: &[u8],
+    ) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .intercept_with_reply(context, direction, local_info, buffer)
+            .await?
+            .forward)
+    }
+
+    async fn intercept_with_reply(
+        &self,
+        context: &mut Context,
+        direction: Direction,
+        local_info: &[LocalInfo],
+        buffer: &[u8],
+    ) -> Result<InterceptedData> {
+        let mut guard = self.connection.lock().await;
+        let ConnectionData {
+            connection,
+            requests,
+            responses,
+        } = &mut *guard;
+
+        if let Ok(info) = SecureChannelLocalInfo::find_info_from_list(local_info) {
+            connection.identifier = Some(info.their_identifier().into());
+        }
+
+        // the replies are sent in the other direction
+        let (framing, reply_framing) = match direction {
+            Direction::FromInletToOutlet => (requests, responses),
+            Direction::FromOutletToInlet => (responses, requests),
+        };
+
+        let mut forward = vec![];
+        let mut reply = vec![];
+        for message in framing.decode(buffer)? {
+            let messages = match direction {
+                Direction::FromInletToOutlet => {
+                    self.protocol
+                        .intercept_request(context, connection, message)
+                        .await?
+                }
+                Direction::FromOutletToInlet => {
+                    self.protocol
+                        .intercept_response(context, connection, message)
+                        .await?
+                }
+            };
+            for message in messages {
+                framing.encode(message, &mut forward)?;
+            }
+            for message in connection.replies.drain(..) {
+                reply_framing.encode(message, &mut reply)?;
+            }
+        }
+
+        Ok(InterceptedData {
+            forward: Some(forward).filter(|data| !data.is_empty()),
+            reply: Some(reply).filter(|data| !data.is_empty()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::InterceptorHarness;
+    use super::*;
+    use ockam::identity::models::IDENTIFIER_LEN;
+
+    /// Line protocol where the server replies to `GET <key>` requests.
+    /// The interceptor only allows one identity to read keys, and tags the responses
+    /// with the key which was requested
+    struct KeyValueInterceptor {
+        allowed: Identifier,
+    }
+
+    #[async_trait]
+    impl ProtocolInterceptor for KeyValueInterceptor {
+        type Framing = LineFraming;
+        type State = PendingRequests<Vec<u8>>;
+
+        fn connection_state(&self) -> Self::State {
+            PendingRequests::default()
+        }
+
+        fn framing(&self, _direction: Direction, _state: &Self::State) -> LineFraming {
+            LineFraming::crlf(1024)
+        }
+
+        async fn intercept_request(
+            &self,
+            _context: &mut Context,
+            connection: &mut Connection<Self>,
+            request: Vec<u8>,
+        ) -> Result<Vec<Vec<u8>>> {
+            // the requests are checked on the outlet side, where the identity is known
+            if connection
+                .identifier()
+                .map_or(false, |i| i != &self.allowed)
+            {
+                connection.reply(b"denied".to_vec());
+                return Ok(vec![]);
+            }
+            let key = request.strip_prefix(b"GET ").unwrap_or_default().to_vec();
+            connection.state.push(key)?;
+            Ok(vec![request])
+        }
+
+        async fn intercept_response(
+            &self,
+            _context: &mut Context,
+            connection: &mut Connection<Self>,
+            response: Vec<u8>,
+        ) -> Result<Vec<Vec<u8>>> {
+            let key = connection.state.pop().unwrap_or_default();
+            Ok(vec![[&key[..], b"=", &response[..]].concat()])
+        }
+    }
+
+    #[ockam_macros::test]
+    async fn requests_and_responses_are_correlated(context: &mut Context) -> ockam::Result<()> {
+        let allowed = Identifier([1; IDENTIFIER_LEN]);
+        let factory = ProtocolInterceptorFactory::new(KeyValueInterceptor {
+            allowed: allowed.clone(),
+        });
+
+        let capture = vec![
+            (Direction::FromInletToOutlet, b"GET a\r\nGET b\r\n".to_vec()),
+            (Direction::FromOutletToInlet, b"1\r\n2\r\n".to_vec()),
+        ];
+
+        // the messages are reassembled when they are split in small pieces
+        let replayed = InterceptorHarness::new(&factory, &factory)
+            .with_chunk_size(3)
+            .with_inlet_identifier(allowed)
+            .replay(context, capture.clone())
+            .await?;
+        assert_eq!(replayed.received_by_server, b"GET a\r\nGET b\r\n");
+        // both sides tag the responses
+        assert_eq!(replayed.received_by_client, b"a=a=1\r\nb=b=2\r\n");
+
+        let replayed = InterceptorHarness::new(&factory, &factory)
+            .with_inlet_identifier(Identifier([2; IDENTIFIER_LEN]))
+            .replay(context, capture[..1].to_vec())
+            .await?;
+        assert!(replayed.received_by_server.is_empty());
+        // the reply of the outlet side is tagged by the inlet side
+        assert_eq!(replayed.received_by_client, b"a=denied\r\nb=denied\r\n");
+        Ok(())
+    }
+}
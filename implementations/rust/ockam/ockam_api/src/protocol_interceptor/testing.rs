@@ -0,0 +1,143 @@
+use ockam::identity::Identifier;
+use ockam_core::{async_trait, LocalInfo, Result, SecureChannelLocalInfo};
+use ockam_node::Context;
+use ockam_transport_tcp::{Direction, PortalInterceptor, PortalInterceptorFactory};
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// Replay the data captured on a connection through the interceptors of both sides of a portal.
+/// As in a portal, the requests go through the inlet interceptor then through the outlet
+/// interceptor, and the responses go through the outlet interceptor then through
+/// the inlet interceptor
+pub struct InterceptorHarness {
+    inlet: Arc<dyn PortalInterceptor>,
+    outlet: Arc<dyn PortalInterceptor>,
+    chunk_size: Option<usize>,
+    inlet_local_info: Vec<LocalInfo>,
+    outlet_local_info: Vec<LocalInfo>,
+}
+
+/// Data received by each end of a replayed connection
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayedConnection {
+    /// Data received by the server, behind the outlet
+    pub received_by_server: Vec<u8>,
+    /// Data received by the client, connected to the inlet
+    pub received_by_client: Vec<u8>,
+}
+
+impl InterceptorHarness {
+    /// Create the interceptors of a new connection
+    pub fn new(
+        inlet_factory: &dyn PortalInterceptorFactory,
+        outlet_factory: &dyn PortalInterceptorFactory,
+    ) -> Self {
+        Self {
+            inlet: inlet_factory.create(),
+            outlet: outlet_factory.create(),
+            chunk_size: None,
+            inlet_local_info: vec![],
+            outlet_local_info: vec![],
+        }
+    }
+
+    /// Split the captured data in pieces of a given size, to check that the messages
+    /// are reassembled
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = Some(chunk_size.max(1));
+        self
+    }
+
+    /// Identifier of the inlet node, received by the outlet interceptor with the requests
+    pub fn with_inlet_identifier(mut self, identifier: Identifier) -> Self {
+        self.outlet_local_info = Self::local_info(identifier);
+        self
+    }
+
+    /// Identifier of the outlet node, received by the inlet interceptor with the responses
+    pub fn with_outlet_identifier(mut self, identifier: Identifier) -> Self {
+        self.inlet_local_info = Self::local_info(identifier);
+        self
+    }
+
+    /// Replay the data captured in each direction, in order.
+    /// The replies of an interceptor are sent back through the interceptors located
+    /// between the interceptor and the sender of the intercepted data
+    pub async fn replay(
+        &self,
+        context: &mut Context,
+        capture: impl IntoIterator<Item = (Direction, Vec<u8>)>,
+    ) -> Result<ReplayedConnection> {
+        let mut replayed = ReplayedConnection::default();
+        for (direction, data) in capture {
+            let chunk_size = self.chunk_size.unwrap_or(data.len()).max(1);
+            for chunk in data.chunks(chunk_size) {
+                // data in transit, with the number of interceptors it went through
+                let mut in_transit = VecDeque::from([(direction, 0, chunk.to_vec())]);
+                while let Some((direction, hops, data)) = in_transit.pop_front() {
+                    let (interceptors, local_info, received) = match direction {
+                        Direction::FromInletToOutlet => (
+                            [&self.inlet, &self.outlet],
+                            &self.outlet_local_info,
+                            &mut replayed.received_by_server,
+                        ),
+                        Direction::FromOutletToInlet => (
+                            [&self.outlet, &self.inlet],
+                            &self.inlet_local_info,
+                            &mut replayed.received_by_client,
+                        ),
+                    };
+                    let Some(interceptor) = interceptors.get(hops) else {
+                        received.extend_from_slice(&data);
+                        continue;
+                    };
+
+                    // the data sent by the inlet or the outlet is not received from a secure channel
+                    let local_info = if hops == 0 { &[][..] } else { local_info };
+                    let intercepted = interceptor
+                        .intercept_with_reply(context, direction, local_info, &data)
+                        .await?;
+                    if let Some(reply) = intercepted.reply {
+                        in_transit.push_back((Self::reverse(direction), 2 - hops, reply));
+                    }
+                    if let Some(forward) = intercepted.forward {
+                        in_transit.push_back((direction, hops + 1, forward));
+                    }
+                }
+            }
+        }
+        Ok(replayed)
+    }
+
+    fn reverse(direction: Direction) -> Direction {
+        match direction {
+            Direction::FromInletToOutlet => Direction::FromOutletToInlet,
+            Direction::FromOutletToInlet => Direction::FromInletToOutlet,
+        }
+    }
+
+    fn local_info(identifier: Identifier) -> Vec<LocalInfo> {
+        SecureChannelLocalInfo::mark(vec![], identifier.into()).unwrap_or_default()
+    }
+}
+
+/// Interceptor forwarding the data unchanged, for the side of a portal without interceptor
+pub struct PassThrough;
+
+#[async_trait]
+impl PortalInterceptor for PassThrough {
+    async fn intercept(
+        &self,
+        _context: &mut Context,
+        _direction: Direction,
+        buffer: &[u8],
+    ) -> Result<Option<Vec<u8>>> {
+        Ok(Some(buffer.to_vec()))
+    }
+}
+
+impl PortalInterceptorFactory for PassThrough {
+    fn create(&self) -> Arc<dyn PortalInterceptor> {
+        Arc::new(PassThrough)
+    }
+}
@@ -169,6 +169,21 @@ impl<W: TerminalWriter + Debug> Terminal<W> {
         ))
     }
 
+    /// Prompt the user for a secret value, like a passphrase, without echoing it.
+    /// If `confirmation` is set, the user must type the value twice.
+    pub fn read_password(&self, msg: impl AsRef<str>, confirmation: bool) -> Result<String> {
+        if !self.can_ask_for_user_input() {
+            return Err(miette!(
+                "Cannot ask for a password in a non-interactive terminal"
+            ))?;
+        }
+        let mut password = dialoguer::Password::new().with_prompt(msg.as_ref());
+        if confirmation {
+            password = password.with_confirmation("Confirm", "The values don't match");
+        }
+        Ok(password.interact().map_err(UiError::Dialoguer)?)
+    }
+
     pub fn confirmed_with_flag_or_prompt(
         &self,
         flag: bool,
@@ -14,7 +14,7 @@ use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
+use tokio::net::{TcpStream, UdpSocket};
 use tokio::runtime::Runtime;
 use tokio::spawn;
 use tokio::time::timeout;
@@ -80,6 +80,484 @@ async fn inlet_outlet_local_successful(context: &mut Context) -> ockam::Result<(
     Ok(())
 }
 
+#[ockam_macros::test]
+async fn udp_inlet_outlet_local_successful(context: &mut Context) -> ockam::Result<()> {
+    TestNode::clean().await?;
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let server_addr = server.local_addr().unwrap();
+    spawn(async move {
+        let mut buf = [0u8; 1024];
+        while let Ok((len, peer)) = server.recv_from(&mut buf).await {
+            let _ = server.send_to(&buf[..len], peer).await;
+        }
+    });
+    let node_manager_handle = start_manager_for_tests(context, None, None).await?;
+
+    let outlet_status = node_manager_handle
+        .node_manager
+        .create_udp_outlet(context, HostnamePort::from(server_addr), None, None, None)
+        .await?;
+    assert_eq!(outlet_status.worker_addr.address(), "udp_outlet");
+
+    let inlet_status = node_manager_handle
+        .node_manager
+        .create_udp_inlet(
+            context,
+            HostnamePort::localhost(0),
+            MultiAddr::from_str("/secure/api/service/udp_outlet")?,
+            "alias".to_string(),
+            None,
+            None,
+            None,
+            true,
+            None,
+        )
+        .await?;
+
+    assert_eq!(inlet_status.alias, "alias");
+    assert_eq!(inlet_status.status, ConnectionStatus::Up);
+    assert_ne!(inlet_status.bind_addr, "127.0.0.1:0");
+
+    let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    client
+        .send_to(b"hello", &inlet_status.bind_addr)
+        .await
+        .unwrap();
+    let mut buf = [0u8; 5];
+    let (len, _) = timeout(Duration::from_secs(5), client.recv_from(&mut buf))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(&buf[..len], b"hello");
+
+    node_manager_handle
+        .node_manager
+        .delete_udp_inlet("alias")
+        .await?;
+    assert!(node_manager_handle
+        .node_manager
+        .list_udp_inlets()
+        .await
+        .is_empty());
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[ockam_macros::test]
+async fn unix_inlet_outlet_local_successful(context: &mut Context) -> ockam::Result<()> {
+    use ockam_api::nodes::models::portal::UnixSocketListenAddr;
+    use std::os::unix::fs::PermissionsExt;
+    use tokio::net::{UnixListener, UnixStream};
+
+    TestNode::clean().await?;
+    let id = rand::random::<u32>();
+    let server_path = std::env::temp_dir().join(format!("ockam-server-{id}.sock"));
+    let inlet_path = std::env::temp_dir().join(format!("ockam-inlet-{id}.sock"));
+
+    let listener = UnixListener::bind(&server_path).unwrap();
+    spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = [0u8; 5];
+        stream.read_exact(&mut buf).await.unwrap();
+        stream.write_all(&buf).await.unwrap();
+    });
+    let node_manager_handle = start_manager_for_tests(context, None, None).await?;
+
+    let outlet_status = node_manager_handle
+        .node_manager
+        .create_unix_outlet(
+            context,
+            server_path.display().to_string(),
+            Some(Address::from_string("outlet")),
+            true,
+            OutletAccessControl::AccessControl((Arc::new(AllowAll), Arc::new(AllowAll))),
+        )
+        .await?;
+    assert_eq!(outlet_status.worker_addr.address(), "outlet");
+    assert_eq!(
+        outlet_status.destination(),
+        server_path.display().to_string()
+    );
+
+    let inlet_status = node_manager_handle
+        .node_manager
+        .create_unix_inlet(
+            context,
+            UnixSocketListenAddr::new(inlet_path.display().to_string()).with_mode(Some(0o600)),
+            MultiAddr::from_str("/secure/api/service/outlet")?,
+            "alias".to_string(),
+            None,
+            None,
+            None,
+            true,
+            None,
+        )
+        .await?;
+
+    assert_eq!(inlet_status.alias, "alias");
+    assert_eq!(inlet_status.status, ConnectionStatus::Up);
+    assert_eq!(inlet_status.bind_addr, inlet_path.display().to_string());
+    let mode = std::fs::metadata(&inlet_path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let mut stream = UnixStream::connect(&inlet_path).await.unwrap();
+    stream.write_all(b"hello").await.unwrap();
+    let mut buf = [0u8; 5];
+    stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"hello");
+
+    node_manager_handle
+        .node_manager
+        .delete_inlet("alias")
+        .await?;
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    assert!(!inlet_path.exists());
+
+    let _ = std::fs::remove_file(&server_path);
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn balanced_inlet_local_successful(context: &mut Context) -> ockam::Result<()> {
+    use ockam_api::nodes::models::portal::InletLoadBalancing;
+    use tokio::net::TcpListener;
+
+    TestNode::clean().await?;
+    let node_manager_handle = start_manager_for_tests(context, None, None).await?;
+
+    // Each server writes its name to the connections it accepts
+    for name in ["outlet_a", "outlet_b"] {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = HostnamePort::from(listener.local_addr().unwrap());
+        spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                stream.write_all(name.as_bytes()).await.unwrap();
+                spawn(async move {
+                    let mut buf = vec![];
+                    let _ = stream.read_to_end(&mut buf).await;
+                });
+            }
+        });
+        node_manager_handle
+            .node_manager
+            .create_outlet(
+                context,
+                server_addr,
+                false,
+                Some(Address::from_string(name)),
+                true,
+                OutletAccessControl::AccessControl((Arc::new(AllowAll), Arc::new(AllowAll))),
+                false,
+            )
+            .await?;
+    }
+
+    let unreachable_port = {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().port()
+    };
+    let outlet_addrs = vec![
+        MultiAddr::from_str(&format!(
+            "/ip4/127.0.0.1/tcp/{unreachable_port}/service/outlet_a"
+        ))?,
+        MultiAddr::from_str("/secure/api/service/outlet_a")?,
+        MultiAddr::from_str("/secure/api/service/outlet_b")?,
+    ];
+
+    let read_name = |bind_addr: String| async move {
+        let mut socket = TcpStream::connect(bind_addr).await.unwrap();
+        let mut name = [0u8; 8];
+        socket.read_exact(&mut name).await.unwrap();
+        (socket, String::from_utf8(name.to_vec()).unwrap())
+    };
+
+    // Round-robin uses the reachable outlets in turn
+    let inlet_status = node_manager_handle
+        .node_manager
+        .create_balanced_inlet(
+            context,
+            HostnamePort::localhost(0),
+            outlet_addrs.clone(),
+            InletLoadBalancing::RoundRobin,
+            "round-robin".to_string(),
+            None,
+            Some(Duration::from_secs(2)),
+            None,
+            true,
+            None,
+            None,
+        )
+        .await?;
+    assert_eq!(inlet_status.status, ConnectionStatus::Up);
+    assert_eq!(
+        inlet_status.load_balancing,
+        Some(InletLoadBalancing::RoundRobin)
+    );
+    let statuses = inlet_status
+        .backends
+        .iter()
+        .flatten()
+        .map(|b| b.status)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        statuses,
+        [
+            ConnectionStatus::Down,
+            ConnectionStatus::Up,
+            ConnectionStatus::Up
+        ]
+    );
+
+    let mut sockets = vec![];
+    for expected in ["outlet_a", "outlet_b", "outlet_a"] {
+        let (socket, name) = read_name(inlet_status.bind_addr.clone()).await;
+        assert_eq!(name, expected);
+        sockets.push(socket);
+    }
+
+    let inlet_status = node_manager_handle
+        .node_manager
+        .show_inlet("round-robin")
+        .await
+        .unwrap();
+    let connections = inlet_status
+        .backends
+        .iter()
+        .flatten()
+        .map(|b| b.connections)
+        .collect::<Vec<_>>();
+    assert_eq!(connections, [0, 2, 1]);
+
+    // Failover always uses the first reachable outlet
+    let inlet_status = node_manager_handle
+        .node_manager
+        .create_balanced_inlet(
+            context,
+            HostnamePort::localhost(0),
+            outlet_addrs,
+            InletLoadBalancing::Failover,
+            "failover".to_string(),
+            None,
+            Some(Duration::from_secs(2)),
+            None,
+            true,
+            None,
+            None,
+        )
+        .await?;
+    for _ in 0..2 {
+        let (_socket, name) = read_name(inlet_status.bind_addr.clone()).await;
+        assert_eq!(name, "outlet_a");
+    }
+
+    node_manager_handle
+        .node_manager
+        .delete_inlet("round-robin")
+        .await?;
+    node_manager_handle
+        .node_manager
+        .delete_inlet("failover")
+        .await?;
+    assert!(TcpStream::connect(inlet_status.bind_addr).await.is_err());
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn proxy_inlet_local_successful(context: &mut Context) -> ockam::Result<()> {
+    use ockam_abac::PolicyExpression;
+    use ockam_api::nodes::models::portal::ProxyRoute;
+
+    TestNode::clean().await?;
+    let echo_server_handle = start_tcp_echo_server().await;
+    let node_manager_handle = start_manager_for_tests(context, None, None).await?;
+
+    node_manager_handle
+        .node_manager
+        .create_outlet(
+            context,
+            echo_server_handle.chosen_addr.clone(),
+            false,
+            Some(Address::from_string("outlet")),
+            true,
+            OutletAccessControl::AccessControl((Arc::new(AllowAll), Arc::new(AllowAll))),
+            false,
+        )
+        .await?;
+
+    let outlet_addr = MultiAddr::from_str("/secure/api/service/outlet")?;
+    let inlet_status = node_manager_handle
+        .node_manager
+        .create_proxy_inlet(
+            context,
+            HostnamePort::localhost(0),
+            "proxy".to_string(),
+            vec![
+                ProxyRoute::new("db.internal:5432", outlet_addr.clone()),
+                ProxyRoute::new("denied.internal", outlet_addr.clone()).with_policy_expression(
+                    Some(PolicyExpression::from_str("(= subject.role \"admin\")")?),
+                ),
+            ],
+            None,
+            None,
+            None,
+            Some(Duration::from_secs(5)),
+            false,
+        )
+        .await?;
+    assert_eq!(inlet_status.alias, "proxy");
+    assert_ne!(inlet_status.bind_addr, "127.0.0.1:0");
+
+    // SOCKS5 request for a routed destination
+    let mut socket = TcpStream::connect(&inlet_status.bind_addr).await.unwrap();
+    let mut request = vec![0x05, 0x01, 0x00, 0x05, 0x01, 0x00, 0x03, 11];
+    request.extend_from_slice(b"db.internal");
+    request.extend_from_slice(&5432u16.to_be_bytes());
+    socket.write_all(&request).await.unwrap();
+    let mut buf = [0u8; 12];
+    socket.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, [0x05, 0x00, 0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0]);
+    socket.write_all(b"hello").await.unwrap();
+    let mut buf = [0u8; 5];
+    socket.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"hello");
+
+    // HTTP CONNECT requests
+    let http_connect = |destination: &'static str| {
+        let bind_addr = inlet_status.bind_addr.clone();
+        async move {
+            let mut socket = TcpStream::connect(bind_addr).await.unwrap();
+            socket
+                .write_all(format!("CONNECT {destination} HTTP/1.1\r\n\r\n").as_bytes())
+                .await
+                .unwrap();
+            let mut status_line = vec![0u8; 12];
+            socket.read_exact(&mut status_line).await.unwrap();
+            (socket, String::from_utf8(status_line).unwrap())
+        }
+    };
+
+    let (mut socket, status_line) = http_connect("db.internal:5432").await;
+    assert_eq!(status_line, "HTTP/1.1 200");
+    let mut headers_end = vec![0u8; "Connection established\r\n\r\n".len() + 1];
+    socket.read_exact(&mut headers_end).await.unwrap();
+    socket.write_all(b"hello").await.unwrap();
+    let mut buf = [0u8; 5];
+    socket.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"hello");
+
+    let (_, status_line) = http_connect("web.internal:80").await;
+    assert_eq!(status_line, "HTTP/1.1 404");
+
+    let (_, status_line) = http_connect("denied.internal:5432").await;
+    assert_eq!(status_line, "HTTP/1.1 403");
+
+    node_manager_handle
+        .node_manager
+        .delete_proxy_inlet(context, "proxy")
+        .await?;
+    assert!(node_manager_handle
+        .node_manager
+        .list_proxy_inlets()
+        .is_empty());
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn dynamic_outlet_through_proxy_inlet_local_successful(
+    context: &mut Context,
+) -> ockam::Result<()> {
+    use ockam_abac::PolicyExpression;
+    use ockam_api::nodes::models::portal::ProxyRoute;
+
+    TestNode::clean().await?;
+    let echo_server_handle = start_tcp_echo_server().await;
+    let echo_port = echo_server_handle.chosen_addr.port();
+    let node_manager_handle = start_manager_for_tests(context, None, None).await?;
+
+    let outlet_status = node_manager_handle
+        .node_manager
+        .create_dynamic_outlet(
+            context,
+            vec![format!("127.0.0.0/8:{echo_port}"), "localhost".to_string()],
+            false,
+            Some(Address::from_string("outlet")),
+            true,
+            OutletAccessControl::AccessControl((Arc::new(AllowAll), Arc::new(AllowAll))),
+            Some(PolicyExpression::from_str(&format!(
+                "(and (= resource.hostname \"127.0.0.1\") (= resource.port {echo_port}))"
+            ))?),
+        )
+        .await?;
+    assert_eq!(
+        outlet_status.destination(),
+        format!("any of 127.0.0.0/8:{echo_port}, localhost")
+    );
+
+    let outlet_addr = MultiAddr::from_str("/secure/api/service/outlet")?;
+    let inlet_status = node_manager_handle
+        .node_manager
+        .create_proxy_inlet(
+            context,
+            HostnamePort::localhost(0),
+            "proxy".to_string(),
+            vec![
+                ProxyRoute::new("127.0.0.1", outlet_addr.clone()),
+                ProxyRoute::new("10.0.0.1", outlet_addr.clone()),
+                ProxyRoute::new("localhost", outlet_addr.clone()),
+            ],
+            None,
+            None,
+            None,
+            Some(Duration::from_secs(5)),
+            true,
+        )
+        .await?;
+    assert!(inlet_status.forward_destination);
+
+    let http_connect = |destination: String| {
+        let bind_addr = inlet_status.bind_addr.clone();
+        async move {
+            let mut socket = TcpStream::connect(bind_addr).await.unwrap();
+            socket
+                .write_all(format!("CONNECT {destination} HTTP/1.1\r\n\r\n").as_bytes())
+                .await
+                .unwrap();
+            let mut status_line = vec![0u8; 12];
+            socket.read_exact(&mut status_line).await.unwrap();
+            (socket, String::from_utf8(status_line).unwrap())
+        }
+    };
+
+    // The outlet connects to the destination requested through the proxy
+    let (mut socket, status_line) = http_connect(format!("127.0.0.1:{echo_port}")).await;
+    assert_eq!(status_line, "HTTP/1.1 200");
+    let mut headers_end = vec![0u8; "Connection established\r\n\r\n".len() + 1];
+    socket.read_exact(&mut headers_end).await.unwrap();
+    socket.write_all(b"hello").await.unwrap();
+    let mut buf = [0u8; 5];
+    socket.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"hello");
+
+    // Refused by the allowed destinations
+    let (_, status_line) = http_connect(format!("10.0.0.1:{echo_port}")).await;
+    assert_eq!(status_line, "HTTP/1.1 502");
+
+    // Refused by the destination policy
+    let (_, status_line) = http_connect(format!("localhost:{echo_port}")).await;
+    assert_eq!(status_line, "HTTP/1.1 502");
+
+    node_manager_handle
+        .node_manager
+        .delete_outlet(&Address::from_string("outlet"))
+        .await?;
+
+    Ok(())
+}
+
 #[test]
 fn portal_node_goes_down_reconnect() {
     // in this test we manually create three nodes with a shared runtime, then:
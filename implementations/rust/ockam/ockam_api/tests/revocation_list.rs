@@ -0,0 +1,103 @@
+use crate::common::common::{change_client_identifier, start_authority, AuthorityInfo};
+use ockam::identity::models::CredentialHash;
+use ockam::identity::secure_channels;
+use ockam_api::authenticator::direct::Members;
+use ockam_api::authenticator::revocation::Revocations;
+use ockam_core::Result;
+use ockam_node::Context;
+use std::collections::BTreeMap;
+
+mod common;
+
+#[ockam_macros::test]
+async fn admin_can_revoke_credentials_and_identifiers(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels().await?;
+
+    let AuthorityInfo {
+        authority_identifier,
+        admins,
+    } = start_authority(ctx, secure_channels.clone(), 1).await?;
+    let admin = &admins[0];
+    let credentials_verification = secure_channels
+        .identities()
+        .credentials()
+        .credentials_verification();
+
+    let revocation_list = admin.client.get_revocation_list(ctx).await.unwrap();
+    let (issuer, data) = credentials_verification
+        .verify_revocation_list(&[authority_identifier.clone()], &revocation_list)
+        .await?;
+    assert_eq!(issuer, authority_identifier);
+    assert_eq!(data.version, 0);
+
+    let member = secure_channels
+        .identities()
+        .identities_creation()
+        .create_identity()
+        .await?;
+    admin
+        .client
+        .add_member(ctx, member.clone(), BTreeMap::default())
+        .await
+        .unwrap();
+
+    let credential_hash = CredentialHash([1u8; 32]);
+    admin
+        .client
+        .revoke_credential(ctx, credential_hash.clone())
+        .await
+        .unwrap();
+    admin
+        .client
+        .revoke_identifier(ctx, member.clone())
+        .await
+        .unwrap();
+
+    // a revoked identifier is not a member anymore
+    let members = admin.client.list_member_ids(ctx).await.unwrap();
+    assert!(!members.contains(&member));
+
+    let revocation_list = admin.client.get_revocation_list(ctx).await.unwrap();
+    let (_, data) = credentials_verification
+        .verify_revocation_list(&[authority_identifier.clone()], &revocation_list)
+        .await?;
+    assert_eq!(data.version, 2);
+    assert_eq!(data.revoked_credentials, vec![credential_hash]);
+    assert_eq!(data.revoked_identifiers, vec![member]);
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn non_enroller_cant_revoke(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels().await?;
+
+    let AuthorityInfo { admins, .. } = start_authority(ctx, secure_channels.clone(), 1).await?;
+    let admin = &admins[0];
+
+    let member = secure_channels
+        .identities()
+        .identities_creation()
+        .create_identity()
+        .await?;
+    admin
+        .client
+        .add_member(ctx, member.clone(), BTreeMap::default())
+        .await
+        .unwrap();
+    let member_client = change_client_identifier(&admin.client, &member, None);
+
+    assert!(member_client
+        .revoke_credential(ctx, CredentialHash([1u8; 32]))
+        .await
+        .is_err());
+    assert!(member_client
+        .revoke_identifier(ctx, admin.identifier.clone())
+        .await
+        .is_err());
+
+    // the list can still be retrieved by members
+    assert!(member_client.get_revocation_list(ctx).await.is_ok());
+
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use ockam::identity::SecureChannelListener;
+use ockam_api::nodes::service::SecureChannelType;
+use ockam_api::test_utils::{start_manager_for_tests, TestNode};
+use ockam_core::route;
+use ockam_multiaddr::MultiAddr;
+use ockam_node::Context;
+
+/// The secure channels spawned by the api listener of a node are persisted, and resumed
+/// when that listener is created again, for example when the node restarts
+#[ockam_macros::test]
+async fn secure_channel_is_resumed_with_its_listener(context: &mut Context) -> ockam::Result<()> {
+    TestNode::clean().await?;
+    let node_manager_handle = start_manager_for_tests(context, None, None).await?;
+    let node_manager = node_manager_handle.node_manager.clone();
+
+    let secure_channel = node_manager
+        .create_secure_channel(
+            context,
+            MultiAddr::from_str("/service/api")?,
+            None,
+            None,
+            None,
+            None,
+            SecureChannelType::KeyExchangeAndMessages,
+        )
+        .await?;
+    let echo_route = route![secure_channel.encryptor_address().clone(), "echo"];
+    let reply: String = context
+        .send_and_receive(echo_route.clone(), "hello".to_string())
+        .await?;
+    assert_eq!(reply, "hello");
+
+    // stop the listener and its secure channel, as if the node was stopped
+    let responder = node_manager_handle
+        .secure_channels
+        .secure_channel_registry()
+        .get_channel_list()
+        .into_iter()
+        .find(|c| !c.is_initiator())
+        .unwrap();
+    let listener: SecureChannelListener =
+        node_manager.delete_secure_channel_listener(context, &"api".into())?;
+    context.stop_address(responder.encryptor_messaging_address())?;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // the secure channel is resumed with the listener
+    node_manager
+        .create_secure_channel_listener(
+            listener.address().clone(),
+            None,
+            None,
+            context,
+            SecureChannelType::KeyExchangeAndMessages,
+        )
+        .await?;
+    let reply: String = context
+        .send_and_receive(echo_route, "hello again".to_string())
+        .await?;
+    assert_eq!(reply, "hello again");
+
+    Ok(())
+}
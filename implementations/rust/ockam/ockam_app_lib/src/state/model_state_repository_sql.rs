@@ -132,6 +132,8 @@ impl TcpOutletStatusRow {
             worker_addr,
             payload: self.payload.to_option(),
             privileged: self.privileged.to_bool(),
+            unix_socket_path: None,
+            allowed_destinations: None,
         })
     }
 }
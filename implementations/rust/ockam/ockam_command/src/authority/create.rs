@@ -21,6 +21,7 @@ use crate::node::node_callback::NodeCallback;
 use crate::node::util::run_ockam;
 use crate::util::foreground_args::{wait_for_exit_signal, ForegroundArgs};
 use crate::util::parsers::internet_address_parser;
+use crate::vault::util::ask_for_vault_passphrases_if_needed;
 use crate::{docs, CommandGlobalOpts, Result};
 
 const LONG_ABOUT: &str = include_str!("./static/create/long_about.txt");
@@ -221,7 +222,12 @@ impl CreateCommand {
         args.push("--tcp-callback-port".to_string());
         args.push(node_callback.callback_port().to_string());
 
-        let handle = run_ockam(args, opts.global_args.quiet)?;
+        ask_for_vault_passphrases_if_needed(opts).await?;
+        let handle = run_ockam(
+            args,
+            opts.state.vault_passphrases_env(),
+            opts.global_args.quiet,
+        )?;
 
         tokio::select! {
             _ = handle.wait_with_output() => { std::process::exit(1) }
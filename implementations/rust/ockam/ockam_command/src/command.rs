@@ -4,6 +4,7 @@ use crate::command_global_opts::CommandGlobalOpts;
 use crate::global_args::GlobalArgs;
 use crate::subcommand::OckamSubcommand;
 use crate::upgrade::check_if_an_upgrade_is_available;
+use crate::vault::util::set_vault_passphrase_prompt;
 use crate::version::Version;
 use crate::{add_command_error_event, docs, ErrorReportHandler};
 use clap::Parser;
@@ -251,6 +252,7 @@ impl OckamCommand {
         );
 
         let options = CommandGlobalOpts::new(self.global_args.clone(), cli_state, terminal);
+        set_vault_passphrase_prompt(&options);
 
         options.log_inputs(arguments, &self.subcommand);
 
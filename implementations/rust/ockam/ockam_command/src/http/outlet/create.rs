@@ -0,0 +1,146 @@
+use crate::node::util::initialize_default_node;
+use crate::util::parsers::hostname_parser;
+use crate::{Command, CommandGlobalOpts};
+use async_trait::async_trait;
+use clap::Args;
+use colorful::Colorful;
+use ockam::transport::SchemeHostnamePort;
+use ockam::{Address, Context};
+use ockam_abac::PolicyExpression;
+use ockam_api::address::extract_address_value;
+use ockam_api::colors::color_primary;
+use ockam_api::http_portal::{
+    is_valid_attribute_name, parse_http_route_policy, HttpPortals, HttpRoutePolicies,
+};
+use ockam_api::nodes::BackgroundNodeClient;
+use ockam_api::{fmt_log, fmt_ok, fmt_warn};
+
+/// Create HTTP Outlets
+#[derive(Clone, Debug, Args)]
+pub struct CreateCommand {
+    /// Address of your HTTP Outlet, which is part of a route used in other commands.
+    /// This unique address identifies the HTTP Outlet worker on the Node on your local machine.
+    /// Examples are `/service/my-outlet` or `my-outlet`.
+    /// If not provided, `outlet` will be used, or a random address will be generated if `outlet` is taken.
+    /// You will need this address when creating a TCP Inlet using `ockam tcp-inlet create`.
+    #[arg(value_parser = extract_address_value)]
+    pub name: Option<String>,
+
+    /// Address where your HTTP server is running, in the format `<scheme>://<hostname>:<port>`.
+    /// At least the port must be provided. The default scheme is `tcp` and the default hostname is `127.0.0.1`.
+    #[arg(long, display_order = 900, id = "SOCKET_ADDRESS", value_parser = hostname_parser)]
+    pub to: SchemeHostnamePort,
+
+    /// Alternative to the <NAME> positional argument.
+    /// Address of your HTTP Outlet, which is part of a route used in other commands.
+    #[arg(long, display_order = 902, id = "OUTLET_ADDRESS", value_parser = extract_address_value)]
+    pub from: Option<String>,
+
+    /// Your HTTP Outlet will be created on this node. If you don't provide it, the default
+    /// node will be used
+    #[arg(long, display_order = 903, id = "NODE_NAME", value_parser = extract_address_value)]
+    pub at: Option<String>,
+
+    /// Policy expression that will be used for access control to the HTTP Outlet.
+    /// If you don't provide it, the policy set for the "tcp-outlet" resource type will be used.
+    ///
+    /// You can check the fallback policy with `ockam policy show --resource-type tcp-outlet`.
+    #[arg(
+        long,
+        visible_alias = "expression",
+        display_order = 904,
+        id = "POLICY_EXPRESSION"
+    )]
+    pub allow: Option<PolicyExpression>,
+
+    /// Policy expression checked for the requests of a route, as `[METHOD ]<PATH_PREFIX>=<EXPRESSION>`,
+    /// for example `POST /reports=(= subject.role "analyst")`. A prefix matches whole path segments.
+    /// The expression is evaluated against the identity of the inlet, with `resource.id` set to
+    /// the path of the request and `action.id` set to its method. The route with the longest
+    /// prefix is used, and the routes without a policy are only protected by the policy of the
+    /// outlet. This argument can be repeated
+    #[arg(long = "route-policy", value_name = "[METHOD ]PATH_PREFIX=EXPRESSION", value_parser = parse_http_route_policy)]
+    pub route_policies: Vec<(Option<String>, String, PolicyExpression)>,
+
+    /// Attribute of the identity of the inlet sent to the server in an `X-Ockam-Attribute-<name>`
+    /// header. The identifier is always sent in an `X-Ockam-Identifier` header.
+    /// This argument can be repeated
+    #[arg(long = "inject-attribute", value_name = "NAME", value_parser = parse_attribute_name)]
+    pub injected_attributes: Vec<String>,
+}
+
+fn parse_attribute_name(name: &str) -> Result<String, String> {
+    if is_valid_attribute_name(name) {
+        Ok(name.to_string())
+    } else {
+        Err(format!(
+            "invalid attribute name '{name}', only letters, digits, '-', '_' and '.' can be sent in a header name"
+        ))
+    }
+}
+
+#[async_trait]
+impl Command for CreateCommand {
+    const NAME: &'static str = "http-outlet create";
+
+    async fn run(mut self, ctx: &Context, opts: CommandGlobalOpts) -> crate::Result<()> {
+        initialize_default_node(ctx, &opts).await?;
+        let cmd = self.parse_args(&opts).await?;
+
+        let route_policies = cmd.route_policies.iter().fold(
+            HttpRoutePolicies::default(),
+            |policies, (method, path_prefix, expression)| {
+                policies.with_expression(method.clone(), path_prefix, expression.clone())
+            },
+        );
+
+        let node = BackgroundNodeClient::create(ctx, &opts.state, &cmd.at).await?;
+        let outlet_status = {
+            let pb = opts.terminal.spinner();
+            if let Some(pb) = pb.as_ref() {
+                pb.set_message(format!(
+                    "Creating a new HTTP Outlet to {}...\n",
+                    color_primary(cmd.to.to_string())
+                ));
+            }
+            node.create_http_outlet(
+                ctx,
+                cmd.to.clone().into(),
+                cmd.name.clone().map(Address::from).as_ref(),
+                cmd.allow.clone(),
+                route_policies,
+                cmd.injected_attributes.clone(),
+            )
+            .await?
+        };
+
+        opts.terminal
+            .stdout()
+            .plain(fmt_ok!(
+                "Created a new HTTP Outlet in the Node {} at {} bound to {}\n\n",
+                color_primary(node.node_name()),
+                color_primary(&outlet_status.worker_addr),
+                color_primary(&cmd.to)
+            ))
+            .machine(&outlet_status.worker_addr)
+            .json_obj(&outlet_status)?
+            .write_line()?;
+        Ok(())
+    }
+}
+
+impl CreateCommand {
+    async fn parse_args(mut self, opts: &CommandGlobalOpts) -> miette::Result<Self> {
+        if let Some(from) = self.from.as_ref() {
+            if self.name.is_some() {
+                opts.terminal.write_line(
+                    fmt_warn!("The <NAME> argument is being overridden by the --from flag")
+                        + &fmt_log!("Consider using either the <NAME> argument or the --from flag"),
+                )?;
+            }
+            self.name = Some(from.clone());
+        }
+
+        Ok(self)
+    }
+}
@@ -1,17 +1,18 @@
 use async_trait::async_trait;
 use clap::Args;
 use colorful::Colorful;
-use miette::IntoDiagnostic;
+use miette::{miette, IntoDiagnostic};
 use ockam::identity::models::ChangeHistory;
-use ockam::identity::IdentitiesVerification;
+use ockam::identity::{IdentitiesVerification, RecoveryKey};
 use ockam_api::cli_state::journeys::{JourneyEvent, IDENTIFIER, IDENTITY_NAME};
 use ockam_api::cli_state::{random_name, NamedVault};
 use ockam_api::colors::{color_primary, OckamColor};
 use ockam_api::terminal::notification::NotificationHandler;
 use ockam_api::{fmt_log, fmt_ok};
 use ockam_node::Context;
-use ockam_vault::SoftwareVaultForVerifyingSignatures;
+use ockam_vault::{SoftwareVaultForVerifyingSignatures, VerifyingPublicKey};
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 use crate::{docs, Command, CommandGlobalOpts};
 
@@ -39,6 +40,15 @@ pub struct CreateCommand {
     /// Identity to import in hex format
     #[arg(long, value_name = "IDENTITY", conflicts_with = "key_id")]
     identity: Option<String>,
+
+    /// Generate an offline recovery key for the identity and write its secret to this file.
+    /// The file must be kept in a safe place, outside of this machine
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["key_id", "identity"]
+    )]
+    recovery_key_file: Option<PathBuf>,
 }
 
 #[async_trait]
@@ -62,13 +72,23 @@ impl Command for CreateCommand {
 
 impl CreateCommand {
     async fn create(self, opts: CommandGlobalOpts, vault: NamedVault) -> miette::Result<()> {
-        let identity = match &self.key_id {
-            Some(key_id) => {
+        let identity = match (&self.key_id, &self.recovery_key_file) {
+            (Some(key_id), _) => {
                 opts.state
                     .create_identity_with_key_id(&self.name, &vault.name(), key_id.as_ref())
                     .await?
             }
-            None => {
+            (None, Some(recovery_key_file)) => {
+                let recovery_public_key = write_recovery_key(recovery_key_file).await?;
+                opts.state
+                    .create_identity_with_recovery_key(
+                        &self.name,
+                        &vault.name(),
+                        recovery_public_key,
+                    )
+                    .await?
+            }
+            (None, None) => {
                 opts.state
                     .create_identity_with_name_and_vault(&self.name, &vault.name())
                     .await?
@@ -141,6 +161,24 @@ impl CreateCommand {
     }
 }
 
+/// Generate a new recovery key, write its secret to a new file and return its public key
+pub(crate) async fn write_recovery_key(path: &PathBuf) -> miette::Result<VerifyingPublicKey> {
+    if path.exists() {
+        return Err(miette!(
+            "The recovery key file {} already exists",
+            path.display()
+        ));
+    }
+    let (recovery_key, secret) = RecoveryKey::generate().await.into_diagnostic()?;
+    std::fs::write(path, secret).into_diagnostic()?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).into_diagnostic()?;
+    }
+    recovery_key.public_key().await.into_diagnostic()
+}
+
 #[cfg(test)]
 mod tests {
     use crate::run::parser::resource::utils::parse_cmd_from_args;
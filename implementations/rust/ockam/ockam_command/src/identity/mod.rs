@@ -3,6 +3,7 @@ use clap::{Args, Subcommand};
 pub use create::CreateCommand;
 pub(crate) use delete::DeleteCommand;
 pub(crate) use list::ListCommand;
+pub(crate) use recover::RecoverCommand;
 pub(crate) use show::ShowCommand;
 
 use crate::identity::default::DefaultCommand;
@@ -14,6 +15,7 @@ mod create;
 mod default;
 mod delete;
 mod list;
+mod recover;
 mod show;
 
 const LONG_ABOUT: &str = include_str!("./static/long_about.txt");
@@ -37,6 +39,7 @@ pub enum IdentitySubcommand {
     List(ListCommand),
     Default(DefaultCommand),
     Delete(DeleteCommand),
+    Recover(RecoverCommand),
 }
 
 impl IdentityCommand {
@@ -47,6 +50,7 @@ impl IdentityCommand {
             IdentitySubcommand::List(c) => c.run(opts).await,
             IdentitySubcommand::Delete(c) => c.run(opts).await,
             IdentitySubcommand::Default(c) => c.run(opts).await,
+            IdentitySubcommand::Recover(c) => c.run(ctx, opts).await,
         }
     }
 
@@ -57,6 +61,7 @@ impl IdentityCommand {
             IdentitySubcommand::List(c) => c.name(),
             IdentitySubcommand::Delete(c) => c.name(),
             IdentitySubcommand::Default(c) => c.name(),
+            IdentitySubcommand::Recover(c) => c.name(),
         }
         .to_string()
     }
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use clap::Args;
+use colorful::Colorful;
+use miette::IntoDiagnostic;
+use ockam::identity::models::ChangeHistory;
+use ockam::identity::RecoveryKey;
+use ockam_api::cli_state::journeys::{JourneyEvent, IDENTIFIER, IDENTITY_NAME};
+use ockam_api::colors::color_primary;
+use ockam_api::fmt_ok;
+use ockam_node::Context;
+
+use crate::identity::create::write_recovery_key;
+use crate::{docs, Command, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/recover/long_about.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/recover/after_long_help.txt");
+
+/// Recover an identity with its offline recovery key
+#[derive(Clone, Debug, Args)]
+#[command(
+long_about = docs::about(LONG_ABOUT),
+after_long_help = docs::after_help(AFTER_LONG_HELP)
+)]
+pub struct RecoverCommand {
+    /// Name of the recovered identity
+    pub name: String,
+
+    /// The name of the Vault where the new Identity key will be stored
+    #[arg(long, value_name = "VAULT_NAME")]
+    pub vault: Option<String>,
+
+    /// Identity to recover, in hex format
+    #[arg(long, value_name = "IDENTITY")]
+    identity: String,
+
+    /// File containing the recovery key created with the identity
+    #[arg(long, value_name = "PATH")]
+    recovery_key_file: PathBuf,
+
+    /// Generate a new recovery key for the recovered identity and write its secret to this file
+    #[arg(long, value_name = "PATH")]
+    new_recovery_key_file: Option<PathBuf>,
+}
+
+#[async_trait]
+impl Command for RecoverCommand {
+    const NAME: &'static str = "identity recover";
+
+    async fn run(self, _ctx: &Context, opts: CommandGlobalOpts) -> crate::Result<()> {
+        let vault = match &self.vault {
+            Some(vault_name) => opts.state.get_or_create_named_vault(vault_name).await?,
+            None => opts.state.get_or_create_default_named_vault().await?,
+        };
+
+        let change_history = ChangeHistory::import_from_string(&self.identity).into_diagnostic()?;
+        let secret = std::fs::read_to_string(&self.recovery_key_file).into_diagnostic()?;
+        let recovery_key = RecoveryKey::import(&secret).await.into_diagnostic()?;
+        let new_recovery_public_key = match &self.new_recovery_key_file {
+            Some(path) => Some(write_recovery_key(path).await?),
+            None => None,
+        };
+
+        let identity = opts
+            .state
+            .recover_identity(
+                &self.name,
+                &vault.name(),
+                change_history,
+                recovery_key,
+                new_recovery_public_key,
+            )
+            .await?;
+
+        let identifier = identity.identifier().to_string();
+        let mut attributes = HashMap::new();
+        attributes.insert(IDENTIFIER, identifier.clone());
+        attributes.insert(IDENTITY_NAME, self.name.clone());
+        opts.state
+            .add_journey_event(JourneyEvent::IdentityCreated, attributes)
+            .await?;
+
+        opts.terminal
+            .stdout()
+            .plain(fmt_ok!(
+                "Identity {} recovered successfully as {}",
+                color_primary(&identifier),
+                color_primary(&self.name)
+            ))
+            .machine(identifier.clone())
+            .json(serde_json::json!({ "identifier": &identifier }))
+            .write_line()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run::parser::resource::utils::parse_cmd_from_args;
+
+    use super::*;
+
+    #[test]
+    fn command_can_be_parsed_from_name() {
+        let cmd = parse_cmd_from_args(
+            RecoverCommand::NAME,
+            &[
+                "i".to_string(),
+                "--identity".to_string(),
+                "81825837830101583285f6820081".to_string(),
+                "--recovery-key-file".to_string(),
+                "i.recovery".to_string(),
+            ],
+        );
+        assert!(cmd.is_ok());
+    }
+}
@@ -191,6 +191,13 @@ impl Display for ShowIdentity {
                 "    primary_public_key:      {}",
                 change.primary_public_key
             )?;
+            for additional_public_key in &change.additional_public_keys {
+                writeln!(f, "    additional_public_key:   {}", additional_public_key)?;
+            }
+            writeln!(f, "    threshold:               {}", change.threshold)?;
+            if let Some(recovery_key_hash) = &change.recovery_key_hash {
+                writeln!(f, "    recovery_key_hash:       {}", recovery_key_hash)?;
+            }
             writeln!(
                 f,
                 "    revoke_all_purpose_keys: {}",
@@ -211,6 +218,11 @@ impl Output for ShowIdentity {
 struct Change {
     pub identifier: String,
     pub primary_public_key: VerifyingPublicKeyDisplay,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub additional_public_keys: Vec<VerifyingPublicKeyDisplay>,
+    pub threshold: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery_key_hash: Option<String>,
     pub revoke_all_purpose_keys: bool,
 }
 
@@ -219,6 +231,15 @@ impl From<VerifiedChange> for Change {
         Self {
             identifier: hex::encode(value.change_hash()),
             primary_public_key: VerifyingPublicKeyDisplay(value.primary_public_key().to_owned()),
+            additional_public_keys: value
+                .public_keys()
+                .iter()
+                .skip(1)
+                .cloned()
+                .map(VerifyingPublicKeyDisplay)
+                .collect(),
+            threshold: value.threshold(),
+            recovery_key_hash: value.data().recovery_key_hash.as_ref().map(|h| h.to_hex()),
             revoke_all_purpose_keys: value.data().revoke_all_purpose_keys,
         }
     }
@@ -2,7 +2,7 @@ use crate::kafka::kafka_default_project_route;
 use crate::kafka::make_brokers_port_range;
 use crate::node::util::initialize_default_node;
 use crate::tcp::util::alias_parser;
-use crate::util::parsers::hostname_parser;
+use crate::util::parsers::{duration_parser, hostname_parser};
 use crate::util::{print_warning_for_deprecated_flag_replaced, process_nodes_multiaddr};
 use crate::{
     docs,
@@ -18,7 +18,10 @@ use ockam::transport::SchemeHostnamePort;
 use ockam_abac::PolicyExpression;
 use ockam_api::colors::{color_primary, color_warn};
 use ockam_api::config::lookup::InternetAddress;
-use ockam_api::kafka::{ConsumerPublishing, ConsumerResolution};
+use ockam_api::kafka::{
+    parse_headers_encryption, parse_key_encryption, ConsumerPublishing, ConsumerResolution,
+    HeadersEncryption, KafkaRecordEncryption, KafkaTopicPolicies, KeyEncryption, TopicEncryption,
+};
 use ockam_api::nodes::models::services::{StartKafkaInletRequest, StartServiceRequest};
 use ockam_api::nodes::BackgroundNodeClient;
 use ockam_api::output::Output;
@@ -29,6 +32,7 @@ use ockam_multiaddr::MultiAddr;
 use ockam_node::Context;
 use serde::Serialize;
 use std::fmt::Write;
+use std::time::Duration;
 
 /// Create a new Kafka Inlet.
 /// Kafka clients v3.7.0 and earlier are supported.
@@ -100,7 +104,10 @@ pub struct CreateCommand {
     )]
     pub no_content_encryption: bool,
 
-    /// The fields to encrypt in the kafka messages, assuming the record is a valid JSON map.
+    /// The fields to encrypt in the kafka messages. A field is a top-level name, or a path
+    /// to nested fields like `customer.email`, `items[0].card` or `items[*].card`.
+    /// The records must be valid JSON, or Avro, Protobuf or JSON values serialized with a
+    /// schema registry (see `--schema-registry`).
     /// By default, the whole record is encrypted.
     #[arg(
         long,
@@ -110,6 +117,59 @@ pub struct CreateCommand {
     )]
     pub encrypted_fields: Vec<String>,
 
+    /// The URL of the schema registry used to decode the records serialized with a schema id
+    /// (Confluent wire format), when encrypting specific fields
+    #[arg(long, value_name = "URL", requires = "encrypted_fields")]
+    pub schema_registry: Option<String>,
+
+    /// Proxy the schema registry of the Kafka Outlet (see `ockam kafka-outlet create
+    /// --schema-registry`) at this address. The schemas registered through the proxy are rewritten
+    /// so that the encrypted fields are declared as strings or bytes, and the schemas fetched
+    /// through the proxy are restored to their original types.
+    /// It is also used to decode the records, unless `--schema-registry` is set.
+    #[arg(long, value_name = "SOCKET_ADDRESS", requires = "encrypted_fields", value_parser = hostname_parser)]
+    pub schema_registry_proxy: Option<SchemeHostnamePort>,
+
+    /// Encrypt the keys of the records of a topic, as `<TOPIC>` or `<TOPIC>:deterministic`.
+    /// Deterministic encryption maps equal keys to equal encrypted keys, so that records
+    /// are still partitioned by key. It requires the OCKAM_KAFKA_DETERMINISTIC_KEY_SECRET
+    /// environment variable to be set to the same secret for all the producers of the topic.
+    /// Use `*` to encrypt the keys of all topics.
+    #[arg(
+        long = "encrypted-key",
+        value_name = "TOPIC[:deterministic]",
+        value_parser = parse_key_encryption
+    )]
+    pub encrypted_keys: Vec<(String, KeyEncryption)>,
+
+    /// Encrypt the headers of the records of a topic, as `<TOPIC>` for all the headers,
+    /// or `<TOPIC>:<NAME>,<NAME>` for specific headers. Use `*` for all topics.
+    #[arg(
+        long = "encrypted-headers",
+        value_name = "TOPIC[:NAMES]",
+        value_parser = parse_headers_encryption
+    )]
+    pub encrypted_headers: Vec<(String, HeadersEncryption)>,
+
+    /// Encrypt the records of the topics matching a pattern, even when the content encryption
+    /// is disabled. In a pattern, `*` matches any sequence of characters and `?` matches a
+    /// single character.
+    #[arg(long = "encrypted-topic", value_name = "TOPIC_PATTERN")]
+    pub encrypted_topics: Vec<String>,
+
+    /// Send and receive the records of the topics matching a pattern without encrypting them.
+    /// It takes precedence over `--encrypted-topic` when a topic matches both.
+    #[arg(long = "passthrough-topic", value_name = "TOPIC_PATTERN")]
+    pub passthrough_topics: Vec<String>,
+
+    /// How long the keys used by the consumers to decrypt records are kept after their last
+    /// usage, so that records can still be read again once their secure channel is closed.
+    /// The retention period of the topics is not read from the Kafka cluster, so this
+    /// value must be at least the longest retention period of the encrypted topics,
+    /// otherwise older records can not be decrypted anymore. Defaults to 7 days
+    #[arg(long, value_name = "DURATION", value_parser = duration_parser)]
+    pub consumer_key_retention: Option<Duration>,
+
     #[arg(help = docs::about("\
     Policy expression that will be used for access control to the Kafka Inlet. \
     If you don't provide it, the policy set for the \"tcp-inlet\" resource type will be used. \
@@ -181,11 +241,16 @@ impl Command for CreateCommand {
                 cmd.to.clone(),
                 !cmd.no_content_encryption,
                 cmd.encrypted_fields.clone(),
+                cmd.record_encryption(),
+                cmd.schema_registry.clone(),
+                cmd.schema_registry_proxy.clone().map(|proxy| proxy.into()),
+                cmd.topic_policies(),
                 consumer_resolution,
                 consumer_publishing,
                 cmd.inlet_policy_expression.clone(),
                 cmd.consumer_policy_expression.clone(),
                 cmd.producer_policy_expression.clone(),
+                cmd.consumer_key_retention,
             );
             let payload = StartServiceRequest::new(payload, &cmd.name);
             let req = Request::post("/node/services/kafka_inlet").body(payload);
@@ -251,6 +316,36 @@ impl CreateCommand {
     fn brokers_port_range(&self) -> PortRange {
         self.brokers_port_range.unwrap()
     }
+
+    fn record_encryption(&self) -> KafkaRecordEncryption {
+        let record_encryption = self
+            .encrypted_keys
+            .iter()
+            .fold(KafkaRecordEncryption::default(), |e, (topic, key)| {
+                e.with_key_encryption(topic, *key)
+            });
+        self.encrypted_headers
+            .iter()
+            .fold(record_encryption, |e, (topic, headers)| {
+                e.with_headers_encryption(topic, headers.clone())
+            })
+    }
+
+    fn topic_policies(&self) -> KafkaTopicPolicies {
+        let passthrough = self
+            .passthrough_topics
+            .iter()
+            .map(|topics| (topics, TopicEncryption::Passthrough));
+        let encrypted = self
+            .encrypted_topics
+            .iter()
+            .map(|topics| (topics, TopicEncryption::Encrypted));
+        passthrough
+            .chain(encrypted)
+            .fold(KafkaTopicPolicies::default(), |p, (topics, encryption)| {
+                p.with_encryption(topics, encryption)
+            })
+    }
 }
 
 #[derive(Serialize)]
@@ -0,0 +1,89 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use clap::Args;
+use colorful::Colorful;
+use miette::{miette, IntoDiagnostic};
+use ockam_api::colors::color_primary;
+use ockam_api::{fmt_ok, fmt_warn};
+use ockam_node::Context;
+
+use crate::{docs, node::NodeOpts, Command, CommandGlobalOpts};
+
+const AFTER_LONG_HELP: &str = include_str!("./static/export_keys/after_long_help.txt");
+
+/// Export the keys used by the Kafka consumers of a node to decrypt records.
+/// They can be imported by another node of the same consumer group to decrypt the
+/// records produced before it joined the group.
+#[derive(Clone, Debug, Args)]
+#[command(after_long_help = docs::after_help(AFTER_LONG_HELP))]
+pub struct ExportKeysCommand {
+    #[command(flatten)]
+    pub node_opts: NodeOpts,
+
+    /// New file where the keys are written, as JSON, readable only by the current user.
+    /// An existing file is never overwritten. The keys are printed if no file is given
+    #[arg(long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+}
+
+#[async_trait]
+impl Command for ExportKeysCommand {
+    const NAME: &'static str = "kafka-inlet export-keys";
+
+    async fn run(self, _ctx: &Context, opts: CommandGlobalOpts) -> crate::Result<()> {
+        let node = opts
+            .state
+            .get_node_or_default(&self.node_opts.at_node)
+            .await?;
+        let keys = opts.state.export_kafka_consumer_keys(&node.name()).await?;
+        let json = serde_json::to_string_pretty(&keys).into_diagnostic()?;
+
+        match &self.output {
+            Some(path) => {
+                write_secret_file(path, json.as_bytes())?;
+                opts.terminal
+                    .stdout()
+                    .plain(
+                        fmt_ok!(
+                            "Exported {} Kafka consumer keys of the node {} to {}\n",
+                            keys.len(),
+                            color_primary(node.name()),
+                            color_primary(path.display().to_string())
+                        ) + &fmt_warn!(
+                            "This file contains secrets, make sure to keep it in a safe place"
+                        ),
+                    )
+                    .write_line()?;
+            }
+            None => {
+                opts.terminal
+                    .stdout()
+                    .plain(&json)
+                    .json(&json)
+                    .write_line()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write secrets to a new file which can only be read and written by the current user
+fn write_secret_file(path: &Path, content: &[u8]) -> miette::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => {
+            miette!("The file {} already exists", path.display())
+        }
+        _ => miette!("Cannot create the file {}: {e}", path.display()),
+    })?;
+    file.write_all(content).into_diagnostic()
+}
@@ -0,0 +1,56 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use clap::Args;
+use colorful::Colorful;
+use miette::IntoDiagnostic;
+use ockam_api::cli_state::ExportedKafkaConsumerKey;
+use ockam_api::colors::color_primary;
+use ockam_api::fmt_ok;
+use ockam_node::Context;
+
+use crate::{docs, node::NodeOpts, Command, CommandGlobalOpts};
+
+const AFTER_LONG_HELP: &str = include_str!("./static/import_keys/after_long_help.txt");
+
+/// Import the Kafka consumer keys exported by another node with `ockam kafka-inlet export-keys`.
+/// The keys already known by the node are skipped.
+#[derive(Clone, Debug, Args)]
+#[command(after_long_help = docs::after_help(AFTER_LONG_HELP))]
+pub struct ImportKeysCommand {
+    #[command(flatten)]
+    pub node_opts: NodeOpts,
+
+    /// File containing the exported keys
+    #[arg(value_name = "PATH")]
+    pub file: PathBuf,
+}
+
+#[async_trait]
+impl Command for ImportKeysCommand {
+    const NAME: &'static str = "kafka-inlet import-keys";
+
+    async fn run(self, _ctx: &Context, opts: CommandGlobalOpts) -> crate::Result<()> {
+        let node = opts
+            .state
+            .get_node_or_default(&self.node_opts.at_node)
+            .await?;
+        let content = std::fs::read_to_string(&self.file).into_diagnostic()?;
+        let keys: Vec<ExportedKafkaConsumerKey> =
+            serde_json::from_str(&content).into_diagnostic()?;
+        let imported = opts
+            .state
+            .import_kafka_consumer_keys(&node.name(), keys)
+            .await?;
+
+        opts.terminal
+            .stdout()
+            .plain(fmt_ok!(
+                "Imported {} Kafka consumer keys into the node {}",
+                imported,
+                color_primary(node.name())
+            ))
+            .write_line()?;
+        Ok(())
+    }
+}
@@ -2,6 +2,8 @@ use clap::{command, Args, Subcommand};
 
 use crate::kafka::inlet::create::CreateCommand;
 use crate::kafka::inlet::delete::DeleteCommand;
+use crate::kafka::inlet::export_keys::ExportKeysCommand;
+use crate::kafka::inlet::import_keys::ImportKeysCommand;
 use crate::kafka::inlet::list::ListCommand;
 use crate::kafka::inlet::show::ShowCommand;
 use crate::{Command, CommandGlobalOpts};
@@ -10,6 +12,8 @@ use ockam_node::Context;
 
 pub(crate) mod create;
 pub(crate) mod delete;
+pub(crate) mod export_keys;
+pub(crate) mod import_keys;
 pub(crate) mod list;
 pub(crate) mod show;
 
@@ -27,6 +31,8 @@ pub enum KafkaInletSubcommand {
     Show(ShowCommand),
     Delete(DeleteCommand),
     List(ListCommand),
+    ExportKeys(ExportKeysCommand),
+    ImportKeys(ImportKeysCommand),
 }
 
 impl KafkaInletCommand {
@@ -36,6 +42,8 @@ impl KafkaInletCommand {
             KafkaInletSubcommand::Show(c) => c.run(ctx, opts).await,
             KafkaInletSubcommand::Delete(c) => c.run(ctx, opts).await,
             KafkaInletSubcommand::List(c) => c.run(ctx, opts).await,
+            KafkaInletSubcommand::ExportKeys(c) => c.run(ctx, opts).await,
+            KafkaInletSubcommand::ImportKeys(c) => c.run(ctx, opts).await,
         }
     }
 
@@ -45,6 +53,8 @@ impl KafkaInletCommand {
             KafkaInletSubcommand::Show(c) => c.name(),
             KafkaInletSubcommand::Delete(c) => c.name(),
             KafkaInletSubcommand::List(c) => c.name(),
+            KafkaInletSubcommand::ExportKeys(c) => c.name(),
+            KafkaInletSubcommand::ImportKeys(c) => c.name(),
         }
     }
 }
@@ -11,6 +11,7 @@ use ockam::Context;
 use ockam_abac::PolicyExpression;
 use ockam_api::address::extract_address_value;
 use ockam_api::colors::{color_primary, color_warn};
+use ockam_api::kafka::{parse_topic_policy, KafkaTopicPolicies};
 use ockam_api::nodes::models::services::StartKafkaOutletRequest;
 use ockam_api::nodes::models::services::StartServiceRequest;
 use ockam_api::nodes::BackgroundNodeClient;
@@ -60,6 +61,20 @@ pub struct CreateCommand {
     \n\nYou can check the fallback policy with `ockam policy show --resource-type tcp-outlet`."))]
     #[arg(long = "allow", id = "EXPRESSION")]
     pub policy_expression: Option<PolicyExpression>,
+
+    /// The address of the schema registry, made available to the Kafka Inlets created
+    /// with `--schema-registry-proxy`
+    #[arg(long, value_name = "[tls://]HOSTNAME:PORT", value_parser = hostname_parser)]
+    pub schema_registry: Option<SchemeHostnamePort>,
+
+    /// Policy expression checked when a Kafka Inlet produces to, or fetches from, the topics
+    /// matching a pattern, as `<TOPIC_PATTERN>=<EXPRESSION>`. In a pattern, `*` matches any
+    /// sequence of characters and `?` matches a single character. The expression is evaluated
+    /// against the identity of the inlet, with `resource.id` set to the topic name and
+    /// `action.id` set to `produce` or `fetch`. Unauthorized requests fail with a
+    /// `TOPIC_AUTHORIZATION_FAILED` error. When several patterns match a topic, the first one is used.
+    #[arg(long = "topic-policy", value_name = "TOPIC_PATTERN=EXPRESSION", value_parser = parse_topic_policy)]
+    pub topic_policies: Vec<(String, PolicyExpression)>,
 }
 
 #[async_trait]
@@ -83,6 +98,17 @@ impl Command for CreateCommand {
                 cmd.bootstrap_server.clone().into(),
                 cmd.tls || cmd.bootstrap_server.is_tls(),
                 cmd.policy_expression,
+                cmd.schema_registry.clone().map(|registry| registry.into()),
+                cmd.schema_registry
+                    .as_ref()
+                    .map(|registry| registry.is_tls())
+                    .unwrap_or(false),
+                cmd.topic_policies.iter().fold(
+                    KafkaTopicPolicies::default(),
+                    |policies, (topics, expression)| {
+                        policies.with_expression(topics, expression.clone())
+                    },
+                ),
             );
             let payload = StartServiceRequest::new(payload, &cmd.name);
             let req = Request::post("/node/services/kafka_outlet").body(payload);
@@ -55,9 +55,16 @@ impl CreateCommand {
             no_publishing: false,
             no_content_encryption: false,
             encrypted_fields: vec![],
+            schema_registry: None,
+            schema_registry_proxy: None,
+            encrypted_keys: vec![],
+            encrypted_headers: vec![],
+            encrypted_topics: vec![],
+            passthrough_topics: vec![],
             inlet_policy_expression: None,
             consumer_policy_expression: None,
             producer_policy_expression: None,
+            consumer_key_retention: None,
         }
         .run(ctx, opts)
         .await
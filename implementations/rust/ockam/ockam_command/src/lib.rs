@@ -43,6 +43,7 @@ pub mod environment;
 pub mod error;
 mod flow_control;
 mod global_args;
+mod http;
 pub mod identity;
 mod influxdb;
 mod kafka;
@@ -56,9 +57,11 @@ mod operation;
 mod output;
 pub mod pager;
 mod policy;
+mod postgres;
 mod project;
 mod project_admin;
 mod project_member;
+mod proxy;
 mod relay;
 mod rendezvous;
 mod reset;
@@ -75,6 +78,8 @@ mod subcommand;
 mod subscription;
 pub mod tcp;
 mod terminal;
+mod udp;
+mod unix;
 mod upgrade;
 pub mod util;
 pub mod value_parsers;
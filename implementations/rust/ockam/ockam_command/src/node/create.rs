@@ -6,6 +6,7 @@ use crate::shared_args::TrustOpts;
 use crate::util::foreground_args::ForegroundArgs;
 use crate::util::print_warning_for_deprecated_flag_no_effect;
 use crate::value_parsers::is_url;
+use crate::vault::util::ask_for_vault_passphrases_if_needed;
 use crate::{docs, Command, CommandGlobalOpts, Result};
 use async_trait::async_trait;
 use clap::Args;
@@ -197,6 +198,9 @@ impl Command for CreateCommand {
     #[instrument(skip_all)]
     async fn run(mut self, ctx: &Context, opts: CommandGlobalOpts) -> miette::Result<()> {
         self.parse_args(&opts).await?;
+        if !self.foreground_args.child_process {
+            ask_for_vault_passphrases_if_needed(&opts).await?;
+        }
 
         if self.should_run_config() {
             self.run_config(ctx, opts).await
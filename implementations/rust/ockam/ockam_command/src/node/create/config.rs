@@ -131,6 +131,10 @@ pub struct NodeConfig {
     #[serde(flatten)]
     pub tcp_inlets: TcpInlets,
     #[serde(flatten)]
+    pub udp_outlets: UdpOutlets,
+    #[serde(flatten)]
+    pub udp_inlets: UdpInlets,
+    #[serde(flatten)]
     pub influxdb_inlets: InfluxDBInlets,
     #[serde(flatten)]
     pub influxdb_outlets: InfluxDBOutlets,
@@ -274,6 +278,8 @@ impl NodeConfig {
             self.relays.into_parsed_commands(node_name)?.into(),
             self.tcp_outlets.into_parsed_commands(node_name)?.into(),
             self.tcp_inlets.into_parsed_commands(node_name)?.into(),
+            self.udp_outlets.into_parsed_commands(node_name)?.into(),
+            self.udp_inlets.into_parsed_commands(node_name)?.into(),
             self.influxdb_outlets
                 .into_parsed_commands(node_name)?
                 .into(),
@@ -319,6 +325,8 @@ impl NodeConfig {
             self.relays.into_parsed_commands(node_name)?.into(),
             self.tcp_outlets.into_parsed_commands(node_name)?.into(),
             self.tcp_inlets.into_parsed_commands(node_name)?.into(),
+            self.udp_outlets.into_parsed_commands(node_name)?.into(),
+            self.udp_inlets.into_parsed_commands(node_name)?.into(),
             self.influxdb_outlets
                 .into_parsed_commands(node_name)?
                 .into(),
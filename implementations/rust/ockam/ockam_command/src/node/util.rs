@@ -191,11 +191,19 @@ pub fn spawn_node(opts: &CommandGlobalOpts, cmd: CreateCommand) -> miette::Resul
 
     args.push(name.to_owned());
 
-    run_ockam(args, opts.global_args.quiet)
+    run_ockam(
+        args,
+        opts.state.vault_passphrases_env(),
+        opts.global_args.quiet,
+    )
 }
 
 /// Run the ockam command line with specific arguments
-pub fn run_ockam(args: Vec<String>, quiet: bool) -> miette::Result<Child> {
+pub fn run_ockam(
+    args: Vec<String>,
+    envs: Vec<(String, String)>,
+    quiet: bool,
+) -> miette::Result<Child> {
     info!("spawning a new process");
 
     // On systems with non-obvious path setups (or during
@@ -210,6 +218,7 @@ pub fn run_ockam(args: Vec<String>, quiet: bool) -> miette::Result<Child> {
     unsafe {
         TokioCommand::new(ockam_exe)
             .args(args)
+            .envs(envs)
             .stdout(subprocess_stdio(quiet))
             .stderr(subprocess_stdio(quiet))
             .stdin(Stdio::null())
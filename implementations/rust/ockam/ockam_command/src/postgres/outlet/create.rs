@@ -0,0 +1,179 @@
+use crate::node::util::initialize_default_node;
+use crate::util::parsers::duration_parser;
+use crate::util::parsers::hostname_parser;
+use crate::{Command, CommandGlobalOpts};
+use async_trait::async_trait;
+use clap::Args;
+use colorful::Colorful;
+use miette::miette;
+use ockam::transport::SchemeHostnamePort;
+use ockam::{Address, Context};
+use ockam_abac::PolicyExpression;
+use ockam_api::address::extract_address_value;
+use ockam_api::colors::color_primary;
+use ockam_api::nodes::BackgroundNodeClient;
+use ockam_api::postgres::{
+    PostgresLeaseConfig, PostgresOutletConfig, PostgresPortals, PostgresRoleMapping,
+};
+use ockam_api::{fmt_log, fmt_ok, fmt_warn};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Default duration of the roles leased to the identities
+const DEFAULT_LEASE_DURATION: Duration = Duration::from_secs(3600);
+
+/// Create PostgreSQL Outlets
+#[derive(Clone, Debug, Args)]
+pub struct CreateCommand {
+    /// Address of your PostgreSQL Outlet, which is part of a route used in other commands.
+    /// This unique address identifies the PostgreSQL Outlet worker on the Node on your local machine.
+    /// Examples are `/service/my-outlet` or `my-outlet`.
+    /// If not provided, `outlet` will be used, or a random address will be generated if `outlet` is taken.
+    /// You will need this address when creating a TCP Inlet using `ockam tcp-inlet create`.
+    #[arg(value_parser = extract_address_value)]
+    pub name: Option<String>,
+
+    /// Address where your PostgreSQL server is running, in the format `<scheme>://<hostname>:<port>`.
+    /// At least the port must be provided. The default scheme is `tcp` and the default hostname is `127.0.0.1`.
+    #[arg(long, display_order = 900, id = "SOCKET_ADDRESS", value_parser = hostname_parser)]
+    pub to: SchemeHostnamePort,
+
+    /// Alternative to the <NAME> positional argument.
+    /// Address of your PostgreSQL Outlet, which is part of a route used in other commands.
+    #[arg(long, display_order = 902, id = "OUTLET_ADDRESS", value_parser = extract_address_value)]
+    pub from: Option<String>,
+
+    /// Your PostgreSQL Outlet will be created on this node. If you don't provide it, the default
+    /// node will be used
+    #[arg(long, display_order = 903, id = "NODE_NAME", value_parser = extract_address_value)]
+    pub at: Option<String>,
+
+    /// Policy expression that will be used for access control to the PostgreSQL Outlet.
+    /// If you don't provide it, the policy set for the "tcp-outlet" resource type will be used.
+    ///
+    /// You can check the fallback policy with `ockam policy show --resource-type tcp-outlet`.
+    #[arg(
+        long,
+        visible_alias = "expression",
+        display_order = 904,
+        id = "POLICY_EXPRESSION"
+    )]
+    pub allow: Option<PolicyExpression>,
+
+    /// User used to connect to the server for all the identities
+    #[arg(long, requires = "password", conflicts_with("LeaseConfigArgs"))]
+    user: Option<String>,
+
+    /// Password of the user used for all the identities
+    #[arg(long, requires = "user")]
+    password: Option<String>,
+
+    #[clap(flatten)]
+    lease_config: Option<LeaseConfigArgs>,
+}
+
+#[derive(Args, Clone, Debug)]
+#[group(multiple = true)]
+pub struct LeaseConfigArgs {
+    /// User allowed to create the roles leased to the identities
+    #[arg(long, value_name = "USER")]
+    pub admin_user: String,
+
+    /// Password of the administrator user
+    #[arg(long, value_name = "PASSWORD")]
+    pub admin_password: String,
+
+    /// Database used to connect with the administrator user
+    #[arg(long, value_name = "DATABASE")]
+    pub admin_database: Option<String>,
+
+    /// Role granted to the leased roles. This argument can be repeated
+    #[arg(long = "grant", value_name = "ROLE")]
+    pub granted_roles: Vec<String>,
+
+    /// Role granted to the leased roles of the identities having an attribute value,
+    /// attested by the project authority, in the format `<role>:<attribute>=<value>`.
+    /// The attributes are checked again each time a lease is renewed.
+    /// This argument can be repeated
+    #[arg(long = "grant-if", value_name = "ROLE:ATTRIBUTE=VALUE", value_parser = PostgresRoleMapping::from_str)]
+    pub role_mappings: Vec<PostgresRoleMapping>,
+
+    /// The duration for which a leased role can log in. The default is one hour
+    #[arg(long, value_name = "DURATION", value_parser = duration_parser)]
+    pub lease_duration: Option<Duration>,
+}
+
+#[async_trait]
+impl Command for CreateCommand {
+    const NAME: &'static str = "postgres-outlet create";
+
+    async fn run(mut self, ctx: &Context, opts: CommandGlobalOpts) -> crate::Result<()> {
+        initialize_default_node(ctx, &opts).await?;
+        let cmd = self.parse_args(&opts).await?;
+
+        let postgres_config = if let (Some(user), Some(password)) = (cmd.user, cmd.password) {
+            PostgresOutletConfig::FixedCredentials(user, password)
+        } else if let Some(config) = cmd.lease_config {
+            PostgresOutletConfig::LeasedCredentials(PostgresLeaseConfig::new(
+                config.admin_user,
+                config.admin_password,
+                config.admin_database,
+                config.granted_roles,
+                config.role_mappings,
+                config.lease_duration.unwrap_or(DEFAULT_LEASE_DURATION),
+            ))
+        } else {
+            return Err(miette!(
+                "Either configure a fixed user and password, or the arguments to lease roles"
+            ))?;
+        };
+
+        let node = BackgroundNodeClient::create(ctx, &opts.state, &cmd.at).await?;
+        let outlet_status = {
+            let pb = opts.terminal.spinner();
+            if let Some(pb) = pb.as_ref() {
+                pb.set_message(format!(
+                    "Creating a new PostgreSQL Outlet to {}...\n",
+                    color_primary(cmd.to.to_string())
+                ));
+            }
+            node.create_postgres_outlet(
+                ctx,
+                cmd.to.clone().into(),
+                cmd.name.clone().map(Address::from).as_ref(),
+                cmd.allow.clone(),
+                postgres_config,
+            )
+            .await?
+        };
+
+        opts.terminal
+            .stdout()
+            .plain(fmt_ok!(
+                "Created a new PostgreSQL Outlet in the Node {} at {} bound to {}\n\n",
+                color_primary(node.node_name()),
+                color_primary(&outlet_status.worker_addr),
+                color_primary(&cmd.to)
+            ))
+            .machine(&outlet_status.worker_addr)
+            .json_obj(&outlet_status)?
+            .write_line()?;
+        Ok(())
+    }
+}
+
+impl CreateCommand {
+    async fn parse_args(mut self, opts: &CommandGlobalOpts) -> miette::Result<Self> {
+        if let Some(from) = self.from.as_ref() {
+            if self.name.is_some() {
+                opts.terminal.write_line(
+                    fmt_warn!("The <NAME> argument is being overridden by the --from flag")
+                        + &fmt_log!("Consider using either the <NAME> argument or the --from flag"),
+                )?;
+            }
+            self.name = Some(from.clone());
+        }
+
+        Ok(self)
+    }
+}
@@ -23,6 +23,7 @@ use ockam_api::terminal::fmt;
 use ockam_api::CliState;
 use ockam_node::Context;
 
+use crate::project_member::revoke::RevokeCommand;
 use crate::project_member::show::ShowCommand;
 use crate::shared_args::IdentityOpts;
 use crate::{docs, Command, CommandGlobalOpts};
@@ -31,6 +32,7 @@ mod add;
 pub(crate) mod delete;
 mod list;
 mod list_ids;
+mod revoke;
 mod show;
 
 const LONG_ABOUT: &str = include_str!("./static/long_about.txt");
@@ -56,6 +58,7 @@ impl ProjectMemberCommand {
             ProjectMemberSubcommand::Add(c) => c.run(ctx, opts).await,
             ProjectMemberSubcommand::Show(c) => c.run(ctx, opts).await,
             ProjectMemberSubcommand::Delete(c) => c.run(ctx, opts).await,
+            ProjectMemberSubcommand::Revoke(c) => c.run(ctx, opts).await,
         }
     }
 
@@ -66,6 +69,7 @@ impl ProjectMemberCommand {
             ProjectMemberSubcommand::Add(c) => c.name(),
             ProjectMemberSubcommand::Show(c) => c.name(),
             ProjectMemberSubcommand::Delete(c) => c.name(),
+            ProjectMemberSubcommand::Revoke(c) => c.name(),
         }
     }
 }
@@ -82,6 +86,8 @@ pub enum ProjectMemberSubcommand {
     Show(ShowCommand),
     #[command(display_order = 800)]
     Delete(DeleteCommand),
+    #[command(display_order = 800)]
+    Revoke(RevokeCommand),
 }
 
 pub(super) async fn authority_client(
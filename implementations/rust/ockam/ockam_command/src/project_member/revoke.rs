@@ -0,0 +1,122 @@
+use super::authority_client;
+use crate::shared_args::IdentityOpts;
+use crate::{docs, Command, CommandGlobalOpts};
+use async_trait::async_trait;
+use clap::Args;
+use colorful::Colorful;
+use miette::miette;
+use ockam::identity::models::CredentialHash;
+use ockam::identity::Identifier;
+use ockam::Context;
+use ockam_api::authenticator::revocation::Revocations;
+use ockam_api::colors::color_primary;
+use ockam_api::fmt_ok;
+use serde::Serialize;
+use std::fmt::Display;
+
+const LONG_ABOUT: &str = include_str!("./static/revoke/long_about.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/revoke/after_long_help.txt");
+
+/// Revoke the credentials of a Project member
+#[derive(Clone, Debug, Args)]
+#[command(
+long_about = docs::about(LONG_ABOUT),
+after_long_help = docs::after_help(AFTER_LONG_HELP),
+)]
+pub struct RevokeCommand {
+    #[command(flatten)]
+    identity_opts: IdentityOpts,
+
+    /// The Project that the member belongs to
+    #[arg(long, short, value_name = "PROJECT_NAME")]
+    project_name: Option<String>,
+
+    /// The Identifier of the member whose credentials must be revoked
+    #[arg(value_name = "IDENTIFIER")]
+    member: Option<Identifier>,
+
+    /// The hex encoded hash of a single credential to revoke
+    #[arg(long, value_name = "CREDENTIAL_HASH", conflicts_with = "member")]
+    credential_hash: Option<CredentialHash>,
+}
+
+#[async_trait]
+impl Command for RevokeCommand {
+    const NAME: &'static str = "project-member revoke";
+
+    async fn run(self, ctx: &Context, opts: CommandGlobalOpts) -> crate::Result<()> {
+        let (authority_node_client, project_name) =
+            authority_client(ctx, &opts, &self.identity_opts, &self.project_name).await?;
+
+        let revoked = match (self.member, self.credential_hash) {
+            (Some(member), None) => {
+                authority_node_client
+                    .revoke_identifier(ctx, member.clone())
+                    .await?;
+                Revoked::Identifier(member.to_string())
+            }
+            (None, Some(credential_hash)) => {
+                let hash = credential_hash.to_string();
+                authority_node_client
+                    .revoke_credential(ctx, credential_hash)
+                    .await?;
+                Revoked::Credential(hash)
+            }
+            _ => {
+                return Err(miette!(
+                    "You need to specify either an identifier or a credential hash to revoke."
+                ))
+            }
+        };
+
+        let output = RevokeOutput {
+            project: project_name,
+            revoked,
+        };
+        opts.terminal
+            .stdout()
+            .plain(output.to_string())
+            .json_obj(&output)?
+            .write_line()?;
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Revoked {
+    Identifier(String),
+    Credential(String),
+}
+
+#[derive(Serialize)]
+struct RevokeOutput {
+    project: String,
+    revoked: Revoked,
+}
+
+impl Display for RevokeOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.revoked {
+            Revoked::Identifier(identifier) => writeln!(
+                f,
+                "{}",
+                fmt_ok!(
+                    "All the credentials of {} were revoked in the Project {}",
+                    color_primary(identifier),
+                    self.project
+                )
+            ),
+            Revoked::Credential(hash) => writeln!(
+                f,
+                "{}",
+                fmt_ok!(
+                    "The credential {} was revoked in the Project {}",
+                    color_primary(hash),
+                    self.project
+                )
+            ),
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use async_trait::async_trait;
+use clap::Args;
+use colorful::Colorful;
+use miette::{miette, IntoDiagnostic};
+use ockam::identity::Identifier;
+use ockam::transport::SchemeHostnamePort;
+use ockam::Context;
+use ockam_abac::PolicyExpression;
+use ockam_api::address::extract_address_value;
+use ockam_api::cli_state::random_name;
+use ockam_api::colors::color_primary;
+use ockam_api::nodes::models::portal::{ProxyInletStatus, ProxyRoute};
+use ockam_api::nodes::service::proxy_inlets::ProxyInlets;
+use ockam_api::nodes::BackgroundNodeClient;
+use ockam_api::{fmt_log, fmt_ok};
+use ockam_multiaddr::proto;
+use ockam_multiaddr::{MultiAddr, Protocol as _};
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::node::util::initialize_default_node;
+use crate::shared_args::OptionalTimeoutArg;
+use crate::tcp::inlet::create::CreateCommand as TcpInletCreateCommand;
+use crate::tcp::util::alias_parser;
+use crate::util::parsers::{duration_parser, hostname_parser};
+use crate::{Command, CommandGlobalOpts};
+
+/// Create a Proxy Inlet
+#[derive(Clone, Debug, Args)]
+pub struct CreateCommand {
+    /// Assign a name to this Proxy Inlet
+    #[arg(id = "NAME", value_parser = alias_parser)]
+    pub name: Option<String>,
+
+    /// Node on which to start the Proxy Inlet.
+    #[arg(long, display_order = 900, id = "NODE_NAME", value_parser = extract_address_value)]
+    pub at: Option<String>,
+
+    /// Address on which to accept SOCKS5 and HTTP CONNECT requests, in the format `<hostname>:<port>`.
+    /// At least the port must be provided. The default hostname is `127.0.0.1`.
+    #[arg(long, display_order = 900, id = "SOCKET_ADDRESS", default_value_t = proxy_inlet_default_from_addr(), value_parser = hostname_parser)]
+    pub from: SchemeHostnamePort,
+
+    /// Outlet serving a destination, in the format `<destination>=<route>`. The destination
+    /// can be `hostname:port`, `hostname` for any port, or `*.domain` and `*.domain:port`
+    /// for any subdomain. The route is a route to an Outlet or the name of an Outlet service,
+    /// as in `ockam tcp-inlet create --to`. For example `db.internal:5432=/project/default/service/forward_to_db/secure/api/service/outlet`.
+    /// This argument can be repeated.
+    #[arg(long = "route", display_order = 900, id = "DESTINATION=ROUTE")]
+    pub routes: Vec<String>,
+
+    /// Route to the Outlet of the destinations which don't match any `--route`,
+    /// where `{host}` and `{port}` are replaced by the requested hostname and port.
+    /// For example `/project/default/service/forward_to_{host}/secure/api/service/outlet`.
+    #[arg(long, display_order = 900, id = "ROUTE_TEMPLATE")]
+    pub route_template: Option<String>,
+
+    /// Authorized identifier for secure channel connection
+    #[arg(long, name = "AUTHORIZED", display_order = 900)]
+    pub authorized: Option<Identifier>,
+
+    #[arg(help = crate::docs::about("\
+     Policy expression that will be used for access control to the destinations of the Proxy Inlet. \
+     If you don't provide it, the policy set for the \"tcp-inlet\" resource type will be used. \
+     \n\nYou can check the fallback policy with `ockam policy show --resource-type tcp-inlet`."))]
+    #[arg(
+        long,
+        visible_alias = "expression",
+        display_order = 900,
+        id = "POLICY_EXPRESSION"
+    )]
+    pub allow: Option<PolicyExpression>,
+
+    /// Time to wait for the Outlet of a destination to be available, before replying with an error.
+    /// If you don't provide it, the Proxy Inlet waits up to 15 seconds.
+    #[arg(long, display_order = 900, id = "CONNECT_TIMEOUT", value_parser = duration_parser)]
+    pub connect_timeout: Option<Duration>,
+
+    /// Send the requested destination to the Outlets, which must be created with
+    /// `ockam tcp-outlet create --allow-destination` to connect to it
+    #[arg(long, display_order = 900)]
+    pub forward_destination: bool,
+
+    #[command(flatten)]
+    pub timeout: OptionalTimeoutArg,
+}
+
+pub(crate) fn proxy_inlet_default_from_addr() -> SchemeHostnamePort {
+    SchemeHostnamePort::from_str("127.0.0.1:1080").unwrap()
+}
+
+#[async_trait]
+impl Command for CreateCommand {
+    const NAME: &'static str = "proxy-inlet create";
+
+    async fn run(self, ctx: &Context, opts: CommandGlobalOpts) -> crate::Result<()> {
+        initialize_default_node(ctx, &opts).await?;
+        let (cmd, routes) = self.parse_args(&opts).await?;
+
+        let mut node = BackgroundNodeClient::create(ctx, &opts.state, &cmd.at).await?;
+        cmd.timeout.timeout.map(|t| node.set_timeout_mut(t));
+
+        let inlet_status: ProxyInletStatus = {
+            let pb = opts.terminal.spinner();
+            if let Some(pb) = pb.as_ref() {
+                pb.set_message(format!(
+                    "Creating Proxy Inlet at {}...\n",
+                    color_primary(cmd.from.to_string())
+                ));
+            }
+            node.create_proxy_inlet(
+                ctx,
+                cmd.from.hostname_port(),
+                cmd.name.as_ref().expect(
+                    "The `name` argument should be set to its default value if not provided",
+                ),
+                routes,
+                cmd.route_template.clone(),
+                &cmd.authorized,
+                &cmd.allow,
+                cmd.connect_timeout,
+                cmd.forward_destination,
+            )
+            .await?
+            .miette_success("create proxy inlet")?
+        };
+
+        let mut plain = fmt_ok!(
+            "Created a new Proxy Inlet in the Node {} listening on {}\n",
+            color_primary(node.node_name()),
+            color_primary(&inlet_status.bind_addr),
+        );
+        for route in &inlet_status.routes {
+            plain += &fmt_log!(
+                "sending connections to {} to the Outlet at {}\n",
+                color_primary(&route.destination),
+                color_primary(route.outlet_addr.to_string())
+            );
+        }
+        if let Some(route_template) = &inlet_status.route_template {
+            plain += &fmt_log!(
+                "sending other connections to the Outlet at {}\n",
+                color_primary(route_template)
+            );
+        }
+        if inlet_status.forward_destination {
+            plain += &fmt_log!("forwarding the requested destinations to the Outlets\n");
+        }
+
+        opts.terminal
+            .stdout()
+            .plain(plain)
+            .machine(inlet_status.bind_addr.to_string())
+            .json(serde_json::json!(&inlet_status))
+            .write_line()?;
+
+        Ok(())
+    }
+}
+
+impl CreateCommand {
+    pub async fn parse_args(
+        mut self,
+        opts: &CommandGlobalOpts,
+    ) -> miette::Result<(Self, Vec<ProxyRoute>)> {
+        self.name = self.name.or_else(|| Some(random_name()));
+        if self.routes.is_empty() && self.route_template.is_none() {
+            return Err(miette!(
+                "at least one --route or a --route-template must be provided"
+            ))?;
+        }
+
+        let mut routes = vec![];
+        for route in &self.routes {
+            let (destination, to) = route.split_once('=').ok_or_else(|| {
+                miette!("invalid route '{route}', expected <destination>=<route>")
+            })?;
+            let to = TcpInletCreateCommand::parse_arg_to(&opts.state, to, None).await?;
+            let to = MultiAddr::from_str(&to).into_diagnostic()?;
+            routes.push(ProxyRoute::new(destination, to));
+        }
+
+        let project_routes = routes
+            .iter()
+            .any(|route| route.outlet_addr.matches(0, &[proto::Project::CODE.into()]))
+            || self
+                .route_template
+                .as_ref()
+                .is_some_and(|template| template.starts_with("/project/"));
+        if project_routes && self.authorized.is_some() {
+            return Err(miette!(
+                "--authorized can not be used with project addresses"
+            ))?;
+        }
+        Ok((self, routes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run::parser::resource::utils::parse_cmd_from_args;
+
+    use super::*;
+
+    #[test]
+    fn command_can_be_parsed_from_name() {
+        let cmd = parse_cmd_from_args(
+            CreateCommand::NAME,
+            &[
+                "--route".to_string(),
+                "db.internal:5432=/service/outlet".to_string(),
+                "--route-template".to_string(),
+                "/service/forward_to_{host}/secure/api/service/outlet".to_string(),
+            ],
+        );
+        assert!(cmd.is_ok());
+    }
+}
@@ -0,0 +1,45 @@
+use async_trait::async_trait;
+use clap::Args;
+
+use ockam_api::nodes::models::portal::ProxyInletStatus;
+use ockam_api::nodes::BackgroundNodeClient;
+use ockam_core::api::Request;
+use ockam_node::Context;
+
+use crate::node::NodeOpts;
+use crate::{Command, CommandGlobalOpts};
+
+/// List Proxy Inlets on the default node
+#[derive(Args, Clone, Debug)]
+pub struct ListCommand {
+    #[command(flatten)]
+    node: NodeOpts,
+}
+
+#[async_trait]
+impl Command for ListCommand {
+    const NAME: &'static str = "proxy-inlet list";
+
+    async fn run(self, ctx: &Context, opts: CommandGlobalOpts) -> crate::Result<()> {
+        let node = BackgroundNodeClient::create(ctx, &opts.state, &self.node.at_node).await?;
+        let inlets: Vec<ProxyInletStatus> = {
+            let pb = opts.terminal.spinner();
+            if let Some(pb) = pb.as_ref() {
+                pb.set_message(format!("Listing Proxy Inlets on {}...", node.node_name()));
+            }
+            node.ask(ctx, Request::get("/node/proxy_inlet")).await?
+        };
+
+        let plain = opts.terminal.build_list(
+            &inlets,
+            &format!("No Proxy Inlets found on {}", node.node_name()),
+        )?;
+        opts.terminal
+            .stdout()
+            .plain(plain)
+            .json_obj(&inlets)?
+            .write_line()?;
+
+        Ok(())
+    }
+}
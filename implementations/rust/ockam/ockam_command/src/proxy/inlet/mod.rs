@@ -0,0 +1,51 @@
+use clap::{Args, Subcommand};
+
+use create::CreateCommand;
+use delete::DeleteCommand;
+use list::ListCommand;
+use ockam_node::Context;
+
+use crate::{docs, Command, CommandGlobalOpts};
+
+pub(crate) mod create;
+mod delete;
+mod list;
+
+const LONG_ABOUT: &str = include_str!("./static/long_about.txt");
+
+/// Manage Proxy Inlets
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    subcommand_required = true,
+    long_about = docs::about(LONG_ABOUT),
+)]
+pub struct ProxyInletCommand {
+    #[command(subcommand)]
+    pub subcommand: ProxyInletSubCommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum ProxyInletSubCommand {
+    Create(CreateCommand),
+    Delete(DeleteCommand),
+    List(ListCommand),
+}
+
+impl ProxyInletCommand {
+    pub async fn run(self, ctx: &Context, opts: CommandGlobalOpts) -> miette::Result<()> {
+        match self.subcommand {
+            ProxyInletSubCommand::Create(c) => c.run(ctx, opts).await,
+            ProxyInletSubCommand::Delete(c) => c.run(ctx, opts).await,
+            ProxyInletSubCommand::List(c) => c.run(ctx, opts).await,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match &self.subcommand {
+            ProxyInletSubCommand::Create(c) => c.name(),
+            ProxyInletSubCommand::Delete(c) => c.name(),
+            ProxyInletSubCommand::List(c) => c.name(),
+        }
+    }
+}
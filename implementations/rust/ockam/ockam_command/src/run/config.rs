@@ -34,6 +34,10 @@ pub struct Config {
     #[serde(flatten)]
     pub tcp_inlets: TcpInlets,
     #[serde(flatten)]
+    pub udp_outlets: UdpOutlets,
+    #[serde(flatten)]
+    pub udp_inlets: UdpInlets,
+    #[serde(flatten)]
     pub kafka_inlet: KafkaInlet,
     #[serde(flatten)]
     pub kafka_outlet: KafkaOutlet,
@@ -69,6 +73,8 @@ impl Config {
             self.policies.into_parsed_commands()?.into(),
             self.tcp_outlets.into_parsed_commands(None)?.into(),
             self.tcp_inlets.into_parsed_commands(None)?.into(),
+            self.udp_outlets.into_parsed_commands(None)?.into(),
+            self.udp_inlets.into_parsed_commands(None)?.into(),
             self.kafka_inlet.into_parsed_commands(None)?.into(),
             self.kafka_outlet.into_parsed_commands(None)?.into(),
         ])
@@ -257,6 +263,8 @@ mod tests {
                     .collect::<BTreeMap<_, _>>(),
                 })),
             },
+            udp_outlets: UdpOutlets { udp_outlets: None },
+            udp_inlets: UdpInlets { udp_inlets: None },
             kafka_inlet: KafkaInlet {
                 kafka_inlet: Some(ResourceNameOrMap::RandomlyNamedMap(
                     UnnamedResources::Single(Args {
@@ -328,6 +336,8 @@ mod tests {
             policies: Policies { policies: None },
             tcp_outlets: TcpOutlets { tcp_outlets: None },
             tcp_inlets: TcpInlets { tcp_inlets: None },
+            udp_outlets: UdpOutlets { udp_outlets: None },
+            udp_inlets: UdpInlets { udp_inlets: None },
             kafka_inlet: KafkaInlet { kafka_inlet: None },
             kafka_outlet: KafkaOutlet { kafka_outlet: None },
             relays: Relays { relays: None },
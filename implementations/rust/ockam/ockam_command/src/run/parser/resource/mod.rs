@@ -11,6 +11,8 @@ pub use relays::Relays;
 pub use tcp_inlets::TcpInlets;
 pub use tcp_outlets::TcpOutlets;
 pub use traits::*;
+pub use udp_inlets::UdpInlets;
+pub use udp_outlets::UdpOutlets;
 pub use vaults::Vaults;
 
 mod identities;
@@ -26,5 +28,7 @@ mod relays;
 mod tcp_inlets;
 mod tcp_outlets;
 mod traits;
+mod udp_inlets;
+mod udp_outlets;
 pub(crate) mod utils;
 mod vaults;
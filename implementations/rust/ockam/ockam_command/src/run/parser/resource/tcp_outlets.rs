@@ -76,14 +76,14 @@ mod tests {
         assert!(cmds[0].from.is_none());
         assert_eq!(
             cmds[0].to,
-            SchemeHostnamePort::from_str("tcp://127.0.0.1:6060").unwrap()
+            Some(SchemeHostnamePort::from_str("tcp://127.0.0.1:6060").unwrap())
         );
         assert_eq!(cmds[0].at.as_ref().unwrap(), "n");
         assert_eq!(cmds[1].name.clone().unwrap(), "to2");
         assert_eq!(cmds[1].from.clone().unwrap(), "my_outlet");
         assert_eq!(
             cmds[1].to,
-            SchemeHostnamePort::from_str("tls://127.0.0.1:6061").unwrap()
+            Some(SchemeHostnamePort::from_str("tls://127.0.0.1:6061").unwrap())
         );
         assert_eq!(cmds[1].at.as_ref(), Some(&default_node_name));
     }
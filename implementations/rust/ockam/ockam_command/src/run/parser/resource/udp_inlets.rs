@@ -0,0 +1,92 @@
+use miette::{miette, Result};
+use ockam_api::colors::color_primary;
+use serde::{Deserialize, Serialize};
+
+use crate::run::parser::building_blocks::{ArgsToCommands, ResourceNameOrMap};
+
+use crate::run::parser::resource::utils::parse_cmd_from_args;
+use crate::udp::inlet::create::CreateCommand;
+use crate::{udp::inlet, Command, OckamSubcommand};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct UdpInlets {
+    #[serde(alias = "udp-inlets", alias = "udp-inlet")]
+    pub udp_inlets: Option<ResourceNameOrMap>,
+}
+
+impl UdpInlets {
+    fn get_subcommand(args: &[String]) -> Result<CreateCommand> {
+        if let OckamSubcommand::UdpInlet(cmd) = parse_cmd_from_args(CreateCommand::NAME, args)? {
+            if let inlet::UdpInletSubCommand::Create(c) = cmd.subcommand {
+                return Ok(c);
+            }
+        }
+        Err(miette!(format!(
+            "Failed to parse {} command",
+            color_primary(CreateCommand::NAME)
+        )))
+    }
+
+    pub fn into_parsed_commands(
+        self,
+        default_node_name: Option<&String>,
+    ) -> Result<Vec<CreateCommand>> {
+        match self.udp_inlets {
+            Some(c) => {
+                let mut cmds = c.into_commands(Self::get_subcommand)?;
+                if let Some(node_name) = default_node_name.as_ref() {
+                    for cmd in cmds.iter_mut() {
+                        if cmd.at.is_none() {
+                            cmd.at = Some(node_name.to_string())
+                        }
+                    }
+                }
+                Ok(cmds)
+            }
+            None => Ok(vec![]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::transport::SchemeHostnamePort;
+    use std::time::Duration;
+
+    #[test]
+    fn udp_inlet_config() {
+        let named = r#"
+            udp_inlets:
+              dns:
+                from: 5353
+                at: n
+              syslog:
+                from: '5514'
+                to: syslog
+                idle-timeout: 10s
+        "#;
+        let parsed: UdpInlets = serde_yaml::from_str(named).unwrap();
+        let default_node_name = "n1".to_string();
+        let cmds = parsed
+            .into_parsed_commands(Some(&default_node_name))
+            .unwrap();
+        assert_eq!(cmds.len(), 2);
+        assert_eq!(cmds[0].name.as_ref().unwrap(), "dns");
+        assert_eq!(
+            cmds[0].from,
+            SchemeHostnamePort::new("tcp", "127.0.0.1", 5353).unwrap()
+        );
+        assert_eq!(cmds[0].to, "udp_outlet");
+        assert_eq!(cmds[0].at.as_ref().unwrap(), "n");
+        assert_eq!(cmds[0].idle_timeout, None);
+        assert_eq!(cmds[1].name.as_ref().unwrap(), "syslog");
+        assert_eq!(
+            cmds[1].from,
+            SchemeHostnamePort::new("tcp", "127.0.0.1", 5514).unwrap()
+        );
+        assert_eq!(cmds[1].to, "syslog");
+        assert_eq!(cmds[1].at.as_ref(), Some(&default_node_name));
+        assert_eq!(cmds[1].idle_timeout, Some(Duration::from_secs(10)));
+    }
+}
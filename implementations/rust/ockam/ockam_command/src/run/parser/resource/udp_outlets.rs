@@ -0,0 +1,91 @@
+use miette::{miette, Result};
+use ockam_api::colors::color_primary;
+use serde::{Deserialize, Serialize};
+
+use crate::run::parser::building_blocks::{ArgsToCommands, ResourceNameOrMap};
+
+use crate::run::parser::resource::utils::parse_cmd_from_args;
+
+use crate::udp::outlet::create::CreateCommand;
+use crate::{udp::outlet, Command, OckamSubcommand};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct UdpOutlets {
+    #[serde(alias = "udp-outlets", alias = "udp-outlet")]
+    pub udp_outlets: Option<ResourceNameOrMap>,
+}
+
+impl UdpOutlets {
+    fn get_subcommand(args: &[String]) -> Result<CreateCommand> {
+        if let OckamSubcommand::UdpOutlet(cmd) = parse_cmd_from_args(CreateCommand::NAME, args)? {
+            if let outlet::UdpOutletSubCommand::Create(c) = cmd.subcommand {
+                return Ok(c);
+            }
+        }
+        Err(miette!(format!(
+            "Failed to parse {} command",
+            color_primary(CreateCommand::NAME)
+        )))
+    }
+
+    pub fn into_parsed_commands(
+        self,
+        default_node_name: Option<&String>,
+    ) -> Result<Vec<CreateCommand>> {
+        match self.udp_outlets {
+            Some(c) => {
+                let mut cmds = c.into_commands(Self::get_subcommand)?;
+                if let Some(node_name) = default_node_name {
+                    for cmd in cmds.iter_mut() {
+                        if cmd.at.is_none() {
+                            cmd.at = Some(node_name.to_string())
+                        }
+                    }
+                }
+                Ok(cmds)
+            }
+            None => Ok(vec![]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::transport::SchemeHostnamePort;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    #[test]
+    fn udp_outlet_config() {
+        let config = r#"
+            udp_outlets:
+              dns:
+                to: 53
+                at: n
+              syslog:
+                to: 127.0.0.1:514
+                idle-timeout: 30s
+        "#;
+        let parsed: UdpOutlets = serde_yaml::from_str(config).unwrap();
+        let default_node_name = "n1".to_string();
+        let cmds = parsed
+            .into_parsed_commands(Some(&default_node_name))
+            .unwrap();
+        assert_eq!(cmds.len(), 2);
+        assert_eq!(cmds[0].name.clone().unwrap(), "dns");
+        assert_eq!(
+            cmds[0].to,
+            SchemeHostnamePort::from_str("127.0.0.1:53").unwrap()
+        );
+        assert_eq!(cmds[0].at.as_ref().unwrap(), "n");
+        assert_eq!(cmds[0].idle_timeout, None);
+        assert_eq!(cmds[1].name.clone().unwrap(), "syslog");
+        assert_eq!(
+            cmds[1].to,
+            SchemeHostnamePort::from_str("127.0.0.1:514").unwrap()
+        );
+        assert_eq!(cmds[1].at.as_ref(), Some(&default_node_name));
+        assert_eq!(cmds[1].idle_timeout, Some(Duration::from_secs(30)));
+    }
+}
@@ -24,6 +24,7 @@ use crate::docs;
 use crate::enroll::EnrollCommand;
 use crate::environment::EnvironmentCommand;
 use crate::flow_control::FlowControlCommand;
+use crate::http::outlet::HttpOutletCommand;
 use crate::identity::IdentityCommand;
 use crate::influxdb::inlet::InfluxDBInletCommand;
 use crate::influxdb::outlet::InfluxDBOutletCommand;
@@ -38,9 +39,11 @@ use crate::message::MessageCommand;
 use crate::migrate_database::MigrateDatabaseCommand;
 use crate::node::{NodeCommand, NodeSubcommand};
 use crate::policy::PolicyCommand;
+use crate::postgres::outlet::PostgresOutletCommand;
 use crate::project::ProjectCommand;
 use crate::project_admin::ProjectAdminCommand;
 use crate::project_member::ProjectMemberCommand;
+use crate::proxy::inlet::ProxyInletCommand;
 use crate::relay::RelayCommand;
 use crate::rendezvous::RendezvousCommand;
 use crate::reset::ResetCommand;
@@ -59,6 +62,10 @@ use crate::tcp::connection::TcpConnectionCommand;
 use crate::tcp::inlet::TcpInletCommand;
 use crate::tcp::listener::TcpListenerCommand;
 use crate::tcp::outlet::TcpOutletCommand;
+use crate::udp::inlet::UdpInletCommand;
+use crate::udp::outlet::UdpOutletCommand;
+use crate::unix::inlet::UnixInletCommand;
+use crate::unix::outlet::UnixOutletCommand;
 use crate::vault::VaultCommand;
 use crate::worker::WorkerCommand;
 use crate::Error;
@@ -89,6 +96,16 @@ pub enum OckamSubcommand {
     TcpOutlet(TcpOutletCommand),
     #[command(name = command::name("tcp-inlet"), hide = command::hide("tcp-inlet"))]
     TcpInlet(TcpInletCommand),
+    #[command(name = command::name("udp-outlet"), hide = command::hide("udp-outlet"))]
+    UdpOutlet(UdpOutletCommand),
+    #[command(name = command::name("udp-inlet"), hide = command::hide("udp-inlet"))]
+    UdpInlet(UdpInletCommand),
+    #[command(name = command::name("unix-outlet"), hide = command::hide("unix-outlet"))]
+    UnixOutlet(UnixOutletCommand),
+    #[command(name = command::name("unix-inlet"), hide = command::hide("unix-inlet"))]
+    UnixInlet(UnixInletCommand),
+    #[command(name = command::name("proxy-inlet"), hide = command::hide("proxy-inlet"))]
+    ProxyInlet(ProxyInletCommand),
     #[command(name = command::name("kafka-inlet"), hide = command::hide("kafka-inlet"))]
     KafkaInlet(KafkaInletCommand),
     #[command(name = command::name("kafka-outlet"), hide = command::hide("kafka-outlet"))]
@@ -97,6 +114,10 @@ pub enum OckamSubcommand {
     InfluxDBInlet(InfluxDBInletCommand),
     #[command(name = command::name("influxdb-outlet"), hide = command::hide("influxdb-outlet"))]
     InfluxDBOutlet(InfluxDBOutletCommand),
+    #[command(name = command::name("postgres-outlet"), hide = command::hide("postgres-outlet"))]
+    PostgresOutlet(PostgresOutletCommand),
+    #[command(name = command::name("http-outlet"), hide = command::hide("http-outlet"))]
+    HttpOutlet(HttpOutletCommand),
     #[command(name = command::name("rendezvous"), hide = command::hide("rendezvous") || docs::hide())]
     Rendezvous(RendezvousCommand),
     #[command(name = command::name("status"), hide = command::hide("status"))]
@@ -173,10 +194,17 @@ impl OckamSubcommand {
             OckamSubcommand::Relay(c) => c.run(ctx, opts).await,
             OckamSubcommand::TcpOutlet(c) => c.run(ctx, opts).await,
             OckamSubcommand::TcpInlet(c) => c.run(ctx, opts).await,
+            OckamSubcommand::UdpOutlet(c) => c.run(ctx, opts).await,
+            OckamSubcommand::UdpInlet(c) => c.run(ctx, opts).await,
+            OckamSubcommand::UnixOutlet(c) => c.run(ctx, opts).await,
+            OckamSubcommand::UnixInlet(c) => c.run(ctx, opts).await,
+            OckamSubcommand::ProxyInlet(c) => c.run(ctx, opts).await,
             OckamSubcommand::KafkaInlet(c) => c.run(ctx, opts).await,
             OckamSubcommand::KafkaOutlet(c) => c.run(ctx, opts).await,
             OckamSubcommand::InfluxDBInlet(c) => c.run(ctx, opts).await,
             OckamSubcommand::InfluxDBOutlet(c) => c.run(ctx, opts).await,
+            OckamSubcommand::PostgresOutlet(c) => c.run(ctx, opts).await,
+            OckamSubcommand::HttpOutlet(c) => c.run(ctx, opts).await,
             OckamSubcommand::Rendezvous(c) => c.run(ctx, opts).await,
             OckamSubcommand::Status(c) => c.run(ctx, opts).await,
             OckamSubcommand::Reset(c) => c.run(ctx, opts).await,
@@ -321,10 +349,17 @@ impl OckamSubcommand {
             OckamSubcommand::Relay(c) => c.name(),
             OckamSubcommand::TcpOutlet(c) => c.name(),
             OckamSubcommand::TcpInlet(c) => c.name(),
+            OckamSubcommand::UdpOutlet(c) => c.name(),
+            OckamSubcommand::UdpInlet(c) => c.name(),
+            OckamSubcommand::UnixOutlet(c) => c.name(),
+            OckamSubcommand::UnixInlet(c) => c.name(),
+            OckamSubcommand::ProxyInlet(c) => c.name(),
             OckamSubcommand::KafkaInlet(c) => c.name(),
             OckamSubcommand::KafkaOutlet(c) => c.name(),
             OckamSubcommand::InfluxDBInlet(c) => c.name(),
             OckamSubcommand::InfluxDBOutlet(c) => c.name(),
+            OckamSubcommand::PostgresOutlet(c) => c.name(),
+            OckamSubcommand::HttpOutlet(c) => c.name(),
             OckamSubcommand::Rendezvous(c) => c.name(),
             OckamSubcommand::Status(c) => c.name(),
             OckamSubcommand::Reset(c) => c.name(),
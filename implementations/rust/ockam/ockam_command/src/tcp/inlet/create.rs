@@ -23,7 +23,7 @@ use ockam_api::cli_state::journeys::{
 };
 use ockam_api::cli_state::{random_name, CliState};
 use ockam_api::colors::{color_primary, color_primary_alt};
-use ockam_api::nodes::models::portal::InletStatus;
+use ockam_api::nodes::models::portal::{InletLoadBalancing, InletStatus};
 use ockam_api::nodes::service::tcp_inlets::Inlets;
 use ockam_api::nodes::BackgroundNodeClient;
 use ockam_api::{fmt_info, fmt_log, fmt_ok, fmt_warn, ConnectionStatus};
@@ -72,6 +72,25 @@ pub struct CreateCommand {
     #[arg(long, display_order = 900, id = "ROUTE", default_value_t = tcp_inlet_default_to_addr())]
     pub to: String,
 
+    /// Route to another TCP Outlet exposing the same service as the one of `--to`, or the name
+    /// of its service. This argument can be repeated.
+    ///
+    /// Each new connection is then forwarded to one of the reachable TCP Outlets, chosen with
+    /// the `--load-balancing` strategy. The TCP Outlets are listed in their order of priority,
+    /// starting with the one of `--to`.
+    #[arg(long, display_order = 900, id = "ADDITIONAL_ROUTE", conflicts_with_all = ["privileged", "udp", "no_tcp_fallback"])]
+    pub also_to: Vec<String>,
+
+    /// Strategy used to choose the TCP Outlet of each new connection when `--also-to` is used
+    #[arg(
+        long,
+        display_order = 900,
+        value_enum,
+        default_value_t = InletLoadBalancing::RoundRobin,
+        requires = "ADDITIONAL_ROUTE"
+    )]
+    pub load_balancing: InletLoadBalancing,
+
     /// Name of the relay that this TCP Inlet will use to connect to the TCP Outlet.
     ///
     /// Use this flag when you are using `--to` to specify the service name of a TCP Outlet
@@ -184,8 +203,8 @@ impl Command for CreateCommand {
             }
 
             loop {
-                let result: Reply<InletStatus> = node
-                    .create_inlet(
+                let result: Reply<InletStatus> = if cmd.also_to.is_empty() {
+                    node.create_inlet(
                         ctx,
                         cmd.from.hostname_port(),
                         &cmd.to(),
@@ -200,7 +219,23 @@ impl Command for CreateCommand {
                         cmd.privileged,
                         &cmd.tls_certificate_provider,
                     )
-                    .await?;
+                    .await?
+                } else {
+                    node.create_balanced_inlet(
+                        ctx,
+                        cmd.from.hostname_port(),
+                        &cmd.outlet_addrs(),
+                        cmd.load_balancing,
+                        cmd.name.as_ref().expect("The `name` argument should be set to its default value if not provided"),
+                        &cmd.authorized,
+                        &cmd.allow,
+                        cmd.connection_wait,
+                        !cmd.no_connection_wait,
+                        &cmd.secure_channel_identifier(&opts.state).await?,
+                        &cmd.tls_certificate_provider,
+                    )
+                    .await?
+                };
 
                 match result {
                     Reply::Successful(inlet_status) => {
@@ -263,6 +298,14 @@ impl Command for CreateCommand {
                 )
         };
 
+        if !cmd.also_to.is_empty() {
+            plain += &fmt_info!(
+                "Its connections are balanced across {} TCP Outlets with the {} strategy\n",
+                color_primary((cmd.also_to.len() + 1).to_string()),
+                color_primary(cmd.load_balancing.to_string())
+            );
+        }
+
         if cmd.privileged {
             plain += &fmt_info!(
                 "This TCP Inlet is operating in {} mode\n",
@@ -286,6 +329,14 @@ impl CreateCommand {
         MultiAddr::from_str(&self.to).unwrap()
     }
 
+    /// All the outlet addresses, in their order of priority
+    pub fn outlet_addrs(&self) -> Vec<MultiAddr> {
+        std::iter::once(&self.to)
+            .chain(self.also_to.iter())
+            .map(|to| MultiAddr::from_str(to).unwrap())
+            .collect()
+    }
+
     pub async fn secure_channel_identifier(
         &self,
         state: &CliState,
@@ -340,7 +391,17 @@ impl CreateCommand {
         port_is_free_guard(&from)?;
 
         self.to = Self::parse_arg_to(&opts.state, self.to, self.via.as_ref()).await?;
-        if self.to().matches(0, &[proto::Project::CODE.into()]) && self.authorized.is_some() {
+        let mut also_to = vec![];
+        for to in self.also_to {
+            also_to.push(Self::parse_arg_to(&opts.state, to, None).await?);
+        }
+        self.also_to = also_to;
+        if self
+            .outlet_addrs()
+            .iter()
+            .any(|to| to.matches(0, &[proto::Project::CODE.into()]))
+            && self.authorized.is_some()
+        {
             return Err(miette!(
                 "--authorized can not be used with project addresses"
             ))?;
@@ -440,6 +501,31 @@ mod tests {
         assert!(cmd.is_ok());
     }
 
+    #[test]
+    fn command_can_be_parsed_with_several_outlets() {
+        let cmd = parse_cmd_from_args(
+            CreateCommand::NAME,
+            &[
+                "--to".to_string(),
+                "/node/n1/service/outlet".to_string(),
+                "--also-to".to_string(),
+                "/node/n2/service/outlet".to_string(),
+                "--also-to".to_string(),
+                "/node/n3/service/outlet".to_string(),
+                "--load-balancing".to_string(),
+                "least-connections".to_string(),
+            ],
+        );
+        assert!(cmd.is_ok());
+
+        // The strategy can only be set with additional outlets
+        let cmd = parse_cmd_from_args(
+            CreateCommand::NAME,
+            &["--load-balancing".to_string(), "failover".to_string()],
+        );
+        assert!(cmd.is_err());
+    }
+
     #[ockam_macros::test]
     async fn parse_arg_to(ctx: &mut Context) -> ockam_core::Result<()> {
         // Setup
@@ -40,8 +40,27 @@ pub struct CreateCommand {
     pub name: Option<String>,
 
     /// TCP address where your TCP server is running: domain:port. Your Outlet will send raw TCP traffic to it
-    #[arg(long, id = "SOCKET_ADDRESS", display_order = 900, value_parser = hostname_parser)]
-    pub to: SchemeHostnamePort,
+    #[arg(long, id = "SOCKET_ADDRESS", display_order = 900, value_parser = hostname_parser, required_unless_present = "ALLOWED_DESTINATION")]
+    pub to: Option<SchemeHostnamePort>,
+
+    /// Connect to the destination requested by each TCP Inlet instead of a single TCP server,
+    /// if it is accepted by one of these entries. An entry is `<host>[:<port>]` or
+    /// `<host>:<first port>-<last port>`, where the host is `*`, a hostname, `*.domain`,
+    /// an IP address or a network such as `10.0.0.0/8`. This argument can be repeated.
+    #[arg(long = "allow-destination", id = "ALLOWED_DESTINATION", display_order = 900, conflicts_with_all = ["SOCKET_ADDRESS", "privileged"])]
+    pub allowed_destinations: Vec<String>,
+
+    #[arg(help = docs::about("\
+    Policy expression that the destinations requested by TCP Inlets must satisfy, \
+    in addition to the `--allow-destination` entries. The `resource.hostname`, `resource.port` \
+    and `resource.destination` attributes are the requested destination."))]
+    #[arg(
+        long,
+        display_order = 900,
+        id = "DESTINATION_POLICY_EXPRESSION",
+        requires = "ALLOWED_DESTINATION"
+    )]
+    pub destination_policy: Option<PolicyExpression>,
 
     /// If set, the outlet will establish a TLS connection over TCP
     #[arg(long, display_order = 900, id = "BOOLEAN")]
@@ -90,18 +109,33 @@ impl Command for CreateCommand {
             if let Some(pb) = pb.as_ref() {
                 pb.set_message(format!(
                     "Creating a new TCP Outlet to {}...\n",
-                    color_primary(cmd.to.to_string())
+                    color_primary(cmd.destination())
                 ));
             }
-            node.create_outlet(
-                ctx,
-                cmd.to.clone().into(),
-                cmd.tls,
-                cmd.name.clone().map(Address::from).as_ref(),
-                cmd.allow.clone(),
-                cmd.privileged,
-            )
-            .await?
+            match &cmd.to {
+                Some(to) => {
+                    node.create_outlet(
+                        ctx,
+                        to.clone().into(),
+                        cmd.tls,
+                        cmd.name.clone().map(Address::from).as_ref(),
+                        cmd.allow.clone(),
+                        cmd.privileged,
+                    )
+                    .await?
+                }
+                None => {
+                    node.create_dynamic_outlet(
+                        ctx,
+                        cmd.allowed_destinations.clone(),
+                        cmd.tls,
+                        cmd.name.clone().map(Address::from).as_ref(),
+                        cmd.allow.clone(),
+                        cmd.destination_policy.clone(),
+                    )
+                    .await?
+                }
+            }
         };
         cmd.add_outlet_created_journey_event(&opts, node_name, &outlet_status)
             .await?;
@@ -112,7 +146,7 @@ impl Command for CreateCommand {
             "Created a new TCP Outlet in the Node {} at {} bound to {}\n",
             color_primary(node_name),
             color_primary(worker_route.to_string()),
-            color_primary(cmd.to.to_string())
+            color_primary(outlet_status.destination())
         );
 
         if cmd.privileged {
@@ -148,6 +182,14 @@ impl CreateCommand {
         Ok(self)
     }
 
+    /// The TCP server of the Outlet, or the destinations that TCP Inlets can request
+    fn destination(&self) -> String {
+        match &self.to {
+            Some(to) => to.to_string(),
+            None => format!("any of {}", self.allowed_destinations.join(", ")),
+        }
+    }
+
     pub async fn add_outlet_created_journey_event(
         &self,
         opts: &CommandGlobalOpts,
@@ -160,7 +202,7 @@ impl CreateCommand {
             TCP_OUTLET_FROM,
             outlet_status.worker_route().into_diagnostic()?.to_string(),
         );
-        attributes.insert(TCP_OUTLET_TO, self.to.to_string());
+        attributes.insert(TCP_OUTLET_TO, self.destination());
         attributes.insert(NODE_NAME, node_name.to_string());
         opts.state
             .add_journey_event(JourneyEvent::TcpOutletCreated, attributes)
@@ -183,4 +225,23 @@ mod tests {
         );
         assert!(cmd.is_ok());
     }
+
+    #[test]
+    fn command_can_be_parsed_with_allowed_destinations() {
+        let cmd = parse_cmd_from_args(
+            CreateCommand::NAME,
+            &[
+                "--allow-destination".to_string(),
+                "10.0.0.0/8:22".to_string(),
+                "--allow-destination".to_string(),
+                "*.internal".to_string(),
+                "--destination-policy".to_string(),
+                "(= resource.port 22)".to_string(),
+            ],
+        );
+        assert!(cmd.is_ok());
+
+        let cmd = parse_cmd_from_args(CreateCommand::NAME, &[]);
+        assert!(cmd.is_err());
+    }
 }
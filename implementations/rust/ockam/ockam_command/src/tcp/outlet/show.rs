@@ -138,7 +138,7 @@ impl ShowCommandTui for ShowTui {
         let info = OutletInformation {
             node_name: self.node.node_name().to_string(),
             worker_address: outlet_status.worker_route().into_diagnostic()?,
-            to: outlet_status.to.to_string(),
+            to: outlet_status.destination(),
         };
         self.terminal()
             .stdout()
@@ -292,6 +292,11 @@ pub enum PluralTerm {
     ProjectAdmin,
     TcpInlet,
     TcpOutlet,
+    UdpInlet,
+    UdpOutlet,
+    UnixInlet,
+    UnixOutlet,
+    ProxyInlet,
     KafkaInlet,
     KafkaOutlet,
     Policy,
@@ -311,6 +316,11 @@ impl PluralTerm {
             PluralTerm::ProjectAdmin => "project admin",
             PluralTerm::TcpInlet => "tcp inlet",
             PluralTerm::TcpOutlet => "tcp outlet",
+            PluralTerm::UdpInlet => "udp inlet",
+            PluralTerm::UdpOutlet => "udp outlet",
+            PluralTerm::UnixInlet => "unix inlet",
+            PluralTerm::UnixOutlet => "unix outlet",
+            PluralTerm::ProxyInlet => "proxy inlet",
             PluralTerm::KafkaInlet => "kafka inlet",
             PluralTerm::KafkaOutlet => "kafka outlet",
             PluralTerm::Policy => "policy",
@@ -330,6 +340,11 @@ impl PluralTerm {
             PluralTerm::ProjectAdmin => "project admins",
             PluralTerm::TcpInlet => "tcp inlets",
             PluralTerm::TcpOutlet => "tcp outlets",
+            PluralTerm::UdpInlet => "udp inlets",
+            PluralTerm::UdpOutlet => "udp outlets",
+            PluralTerm::UnixInlet => "unix inlets",
+            PluralTerm::UnixOutlet => "unix outlets",
+            PluralTerm::ProxyInlet => "proxy inlets",
             PluralTerm::KafkaInlet => "kafka inlets",
             PluralTerm::KafkaOutlet => "kafka outlets",
             PluralTerm::Policy => "policies",
@@ -0,0 +1,208 @@
+use async_trait::async_trait;
+use clap::Args;
+use colorful::Colorful;
+use miette::{miette, IntoDiagnostic};
+use ockam::identity::Identifier;
+use ockam::transport::SchemeHostnamePort;
+use ockam::Context;
+use ockam_abac::PolicyExpression;
+use ockam_api::address::extract_address_value;
+use ockam_api::cli_state::random_name;
+use ockam_api::colors::color_primary;
+use ockam_api::nodes::models::portal::InletStatus;
+use ockam_api::nodes::service::udp_inlets::UdpInlets;
+use ockam_api::nodes::BackgroundNodeClient;
+use ockam_api::{fmt_info, fmt_log, fmt_ok, fmt_warn, ConnectionStatus};
+use ockam_multiaddr::proto;
+use ockam_multiaddr::{MultiAddr, Protocol as _};
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::node::util::initialize_default_node;
+use crate::shared_args::OptionalTimeoutArg;
+use crate::tcp::inlet::create::CreateCommand as TcpInletCreateCommand;
+use crate::tcp::util::alias_parser;
+use crate::util::parsers::{duration_parser, hostname_parser};
+use crate::{Command, CommandGlobalOpts};
+
+/// Create a UDP Inlet
+#[derive(Clone, Debug, Args)]
+pub struct CreateCommand {
+    /// Assign a name to this UDP Inlet
+    #[arg(id = "NAME", value_parser = alias_parser)]
+    pub name: Option<String>,
+
+    /// Node on which to start the UDP Inlet.
+    #[arg(long, display_order = 900, id = "NODE_NAME", value_parser = extract_address_value)]
+    pub at: Option<String>,
+
+    /// Address on which to accept UDP datagrams, in the format `<hostname>:<port>`.
+    /// At least the port must be provided. The default hostname is `127.0.0.1`.
+    /// If the argument is not set, a random port will be used on the default address.
+    #[arg(long, display_order = 900, id = "SOCKET_ADDRESS", hide_default_value = true, default_value_t = udp_inlet_default_from_addr(), value_parser = hostname_parser)]
+    pub from: SchemeHostnamePort,
+
+    /// Route to a UDP Outlet or the name of the UDP Outlet service you want to connect to.
+    ///
+    /// If you are connecting to a local node, you can provide the route as `/node/n/service/udp_outlet`.
+    ///
+    /// If you are connecting to a remote node through a relay in the Orchestrator you can either
+    /// provide the full route to the UDP Outlet as `/project/myproject/service/forward_to_myrelay/secure/api/service/udp_outlet`,
+    /// or just the name of the service as `udp_outlet` or `/service/udp_outlet`.
+    /// If you are passing just the service name, consider using `--via` to specify the
+    /// relay name (e.g. `ockam udp-inlet create --to udp_outlet --via myrelay`).
+    #[arg(long, display_order = 900, id = "ROUTE", default_value = "udp_outlet")]
+    pub to: String,
+
+    /// Name of the relay that this UDP Inlet will use to connect to the UDP Outlet.
+    #[arg(long, display_order = 900, id = "RELAY_NAME")]
+    pub via: Option<String>,
+
+    /// Authorized identifier for secure channel connection
+    #[arg(long, name = "AUTHORIZED", display_order = 900)]
+    pub authorized: Option<Identifier>,
+
+    #[arg(help = crate::docs::about("\
+     Policy expression that will be used for access control to the UDP Inlet. \
+     If you don't provide it, the policy set for the \"tcp-inlet\" resource type will be used. \
+     \n\nYou can check the fallback policy with `ockam policy show --resource-type tcp-inlet`."))]
+    #[arg(
+        long,
+        visible_alias = "expression",
+        display_order = 900,
+        id = "POLICY_EXPRESSION"
+    )]
+    pub allow: Option<PolicyExpression>,
+
+    /// Time after which the flow of a client which didn't send or receive any datagram is closed.
+    /// If you don't provide it, a flow is closed after 2 minutes of inactivity.
+    #[arg(long, display_order = 900, id = "IDLE_TIMEOUT", value_parser = duration_parser)]
+    pub idle_timeout: Option<Duration>,
+
+    /// Time to wait for the outlet to be available.
+    #[arg(long, display_order = 900, id = "WAIT", default_value = "5s", value_parser = duration_parser)]
+    pub connection_wait: Duration,
+
+    #[command(flatten)]
+    pub timeout: OptionalTimeoutArg,
+
+    /// Create the UDP Inlet without waiting for the UDP Outlet to connect
+    #[arg(long, default_value = "false")]
+    pub no_connection_wait: bool,
+}
+
+pub(crate) fn udp_inlet_default_from_addr() -> SchemeHostnamePort {
+    SchemeHostnamePort::from_str("127.0.0.1:0").unwrap()
+}
+
+#[async_trait]
+impl Command for CreateCommand {
+    const NAME: &'static str = "udp-inlet create";
+
+    async fn run(self, ctx: &Context, opts: CommandGlobalOpts) -> crate::Result<()> {
+        initialize_default_node(ctx, &opts).await?;
+        let cmd = self.parse_args(&opts).await?;
+
+        let mut node = BackgroundNodeClient::create(ctx, &opts.state, &cmd.at).await?;
+        cmd.timeout.timeout.map(|t| node.set_timeout_mut(t));
+
+        let inlet_status: InletStatus = {
+            let pb = opts.terminal.spinner();
+            if let Some(pb) = pb.as_ref() {
+                pb.set_message(format!(
+                    "Creating UDP Inlet at {}...\n",
+                    color_primary(cmd.from.to_string())
+                ));
+            }
+            node.create_udp_inlet(
+                ctx,
+                cmd.from.hostname_port(),
+                &cmd.to(),
+                cmd.name.as_ref().expect(
+                    "The `name` argument should be set to its default value if not provided",
+                ),
+                &cmd.authorized,
+                &cmd.allow,
+                cmd.connection_wait,
+                !cmd.no_connection_wait,
+                cmd.idle_timeout,
+            )
+            .await?
+            .miette_success("create udp inlet")?
+        };
+
+        let created_message = format!(
+            "Created a new UDP Inlet in the Node {} bound to {}",
+            color_primary(node.node_name()),
+            color_primary(&inlet_status.bind_addr),
+        );
+
+        let plain = if cmd.no_connection_wait {
+            fmt_ok!("{created_message}\n")
+                + &fmt_info!(
+                    "It will automatically connect to the UDP Outlet at {} as soon as it is available\n",
+                    color_primary(&cmd.to)
+                )
+        } else if inlet_status.status == ConnectionStatus::Up {
+            fmt_ok!("{created_message}\n")
+                + &fmt_log!(
+                    "sending datagrams to the UDP Outlet at {}\n",
+                    color_primary(&cmd.to)
+                )
+        } else {
+            fmt_warn!("{created_message}\n")
+                + &fmt_log!(
+                    "but it failed to connect to the UDP Outlet at {}\n",
+                    color_primary(&cmd.to)
+                )
+                + &fmt_info!(
+                    "It will automatically connect to the UDP Outlet as soon as it is available\n",
+                )
+        };
+
+        opts.terminal
+            .stdout()
+            .plain(plain)
+            .machine(inlet_status.bind_addr.to_string())
+            .json(serde_json::json!(&inlet_status))
+            .write_line()?;
+
+        Ok(())
+    }
+}
+
+impl CreateCommand {
+    pub fn to(&self) -> MultiAddr {
+        MultiAddr::from_str(&self.to).unwrap()
+    }
+
+    pub async fn parse_args(mut self, opts: &CommandGlobalOpts) -> miette::Result<Self> {
+        self.name = self.name.or_else(|| Some(random_name()));
+        self.to =
+            TcpInletCreateCommand::parse_arg_to(&opts.state, self.to, self.via.as_ref()).await?;
+        if self.to().matches(0, &[proto::Project::CODE.into()]) && self.authorized.is_some() {
+            return Err(miette!(
+                "--authorized can not be used with project addresses"
+            ))?;
+        }
+        // validate the route
+        MultiAddr::from_str(&self.to).into_diagnostic()?;
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run::parser::resource::utils::parse_cmd_from_args;
+
+    use super::*;
+
+    #[test]
+    fn command_can_be_parsed_from_name() {
+        let cmd = parse_cmd_from_args(
+            CreateCommand::NAME,
+            &["--from".to_string(), "127.0.0.1:5353".to_string()],
+        );
+        assert!(cmd.is_ok());
+    }
+}
@@ -0,0 +1,116 @@
+use async_trait::async_trait;
+use clap::Args;
+use colorful::Colorful;
+use miette::IntoDiagnostic;
+use ockam::transport::SchemeHostnamePort;
+use ockam::Address;
+use ockam::Context;
+use ockam_abac::PolicyExpression;
+use ockam_api::address::extract_address_value;
+use ockam_api::colors::color_primary;
+use ockam_api::fmt_ok;
+use ockam_api::nodes::service::udp_outlets::UdpOutlets;
+use ockam_api::nodes::BackgroundNodeClient;
+use std::time::Duration;
+
+use crate::node::util::initialize_default_node;
+use crate::util::parsers::{duration_parser, hostname_parser};
+use crate::{docs, Command, CommandGlobalOpts};
+
+/// Create a UDP Outlet that runs adjacent to a UDP server
+#[derive(Clone, Debug, Args)]
+pub struct CreateCommand {
+    /// Address of your UDP Outlet, which is part of a route used in other commands.
+    /// This unique address identifies the UDP Outlet worker on the Node on your local machine.
+    /// Examples are `/service/my-outlet` or `my-outlet`.
+    /// If not provided, `udp_outlet` will be used, or a random address will be generated if `udp_outlet` is taken.
+    /// You will need this address when creating a UDP Inlet using `ockam udp-inlet create`.
+    #[arg(value_parser = extract_address_value)]
+    pub name: Option<String>,
+
+    /// UDP address where your UDP server is running: domain:port. Your Outlet will send the datagrams to it
+    #[arg(long, id = "SOCKET_ADDRESS", display_order = 900, value_parser = hostname_parser)]
+    pub to: SchemeHostnamePort,
+
+    /// Your UDP Outlet will be created on this node. If you don't provide it, the default
+    /// node will be used
+    #[arg(long, display_order = 903, id = "NODE_NAME", value_parser = extract_address_value)]
+    pub at: Option<String>,
+
+    #[arg(help = docs::about("\
+    Policy expression that will be used for access control to the UDP Outlet. \
+    If you don't provide it, the policy set for the \"tcp-outlet\" resource type will be used. \
+    \n\nYou can check the fallback policy with `ockam policy show --resource-type tcp-outlet`"))]
+    #[arg(
+        long,
+        visible_alias = "expression",
+        display_order = 904,
+        id = "POLICY_EXPRESSION"
+    )]
+    pub allow: Option<PolicyExpression>,
+
+    /// Time after which a flow which didn't send or receive any datagram is closed.
+    /// If you don't provide it, a flow is closed after 2 minutes of inactivity.
+    #[arg(long, display_order = 905, id = "IDLE_TIMEOUT", value_parser = duration_parser)]
+    pub idle_timeout: Option<Duration>,
+}
+
+#[async_trait]
+impl Command for CreateCommand {
+    const NAME: &'static str = "udp-outlet create";
+
+    async fn run(self, ctx: &Context, opts: CommandGlobalOpts) -> crate::Result<()> {
+        initialize_default_node(ctx, &opts).await?;
+
+        let node = BackgroundNodeClient::create(ctx, &opts.state, &self.at).await?;
+        let outlet_status = {
+            let pb = opts.terminal.spinner();
+            if let Some(pb) = pb.as_ref() {
+                pb.set_message(format!(
+                    "Creating a new UDP Outlet to {}...\n",
+                    color_primary(self.to.to_string())
+                ));
+            }
+            node.create_udp_outlet(
+                ctx,
+                self.to.clone().into(),
+                self.name.clone().map(Address::from).as_ref(),
+                self.allow.clone(),
+                self.idle_timeout,
+            )
+            .await?
+        };
+
+        let worker_route = outlet_status.worker_route().into_diagnostic()?;
+
+        opts.terminal
+            .stdout()
+            .plain(fmt_ok!(
+                "Created a new UDP Outlet in the Node {} at {} bound to {}\n",
+                color_primary(node.node_name()),
+                color_primary(worker_route.to_string()),
+                color_primary(self.to.to_string())
+            ))
+            .machine(worker_route)
+            .json(serde_json::to_string(&outlet_status).into_diagnostic()?)
+            .write_line()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run::parser::resource::utils::parse_cmd_from_args;
+
+    use super::*;
+
+    #[test]
+    fn command_can_be_parsed_from_name() {
+        let cmd = parse_cmd_from_args(
+            CreateCommand::NAME,
+            &["--to".to_string(), "127.0.0.1:53".to_string()],
+        );
+        assert!(cmd.is_ok());
+    }
+}
@@ -0,0 +1,56 @@
+use async_trait::async_trait;
+use clap::Args;
+
+use ockam_api::nodes::models::portal::OutletStatus;
+use ockam_api::nodes::BackgroundNodeClient;
+use ockam_core::api::Request;
+use ockam_node::Context;
+
+use crate::node::NodeOpts;
+use crate::{Command, CommandGlobalOpts};
+
+/// List UDP Outlets on the default node
+#[derive(Args, Clone, Debug)]
+pub struct ListCommand {
+    #[command(flatten)]
+    node: NodeOpts,
+}
+
+#[async_trait]
+impl Command for ListCommand {
+    const NAME: &'static str = "udp-outlet list";
+
+    async fn run(self, ctx: &Context, opts: CommandGlobalOpts) -> crate::Result<()> {
+        let node = BackgroundNodeClient::create(ctx, &opts.state, &self.node.at_node).await?;
+        let outlets: Vec<OutletStatus> = {
+            let pb = opts.terminal.spinner();
+            if let Some(pb) = pb.as_ref() {
+                pb.set_message(format!("Listing UDP Outlets on {}...", node.node_name()));
+            }
+            node.ask(ctx, Request::get("/node/udp_outlet")).await?
+        };
+
+        let plain = opts.terminal.build_list(
+            &outlets,
+            &format!("No UDP Outlets found on {}", node.node_name()),
+        )?;
+        let json: Vec<_> = outlets
+            .iter()
+            .map(|outlet| {
+                Ok(serde_json::json!({
+                    "from": outlet.worker_route()?,
+                    "to": outlet.to,
+                }))
+            })
+            .flat_map(|res: Result<_, ockam_core::Error>| res.ok())
+            .collect();
+
+        opts.terminal
+            .stdout()
+            .plain(plain)
+            .json(serde_json::json!(json))
+            .write_line()?;
+
+        Ok(())
+    }
+}
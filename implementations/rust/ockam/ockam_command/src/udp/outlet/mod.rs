@@ -0,0 +1,51 @@
+use clap::{Args, Subcommand};
+
+use create::CreateCommand;
+use delete::DeleteCommand;
+use list::ListCommand;
+use ockam_node::Context;
+
+use crate::{docs, Command, CommandGlobalOpts};
+
+pub(crate) mod create;
+mod delete;
+mod list;
+
+const LONG_ABOUT: &str = include_str!("./static/long_about.txt");
+
+/// Manage UDP Outlets
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    subcommand_required = true,
+    long_about = docs::about(LONG_ABOUT),
+)]
+pub struct UdpOutletCommand {
+    #[command(subcommand)]
+    pub subcommand: UdpOutletSubCommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum UdpOutletSubCommand {
+    Create(CreateCommand),
+    Delete(DeleteCommand),
+    List(ListCommand),
+}
+
+impl UdpOutletCommand {
+    pub async fn run(self, ctx: &Context, opts: CommandGlobalOpts) -> miette::Result<()> {
+        match self.subcommand {
+            UdpOutletSubCommand::Create(c) => c.run(ctx, opts).await,
+            UdpOutletSubCommand::Delete(c) => c.run(ctx, opts).await,
+            UdpOutletSubCommand::List(c) => c.run(ctx, opts).await,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match &self.subcommand {
+            UdpOutletSubCommand::Create(c) => c.name(),
+            UdpOutletSubCommand::Delete(c) => c.name(),
+            UdpOutletSubCommand::List(c) => c.name(),
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use async_trait::async_trait;
+use clap::error::{Error, ErrorKind};
+use clap::Args;
+use colorful::Colorful;
+use miette::{miette, IntoDiagnostic};
+use ockam::identity::Identifier;
+use ockam::Context;
+use ockam_abac::PolicyExpression;
+use ockam_api::address::extract_address_value;
+use ockam_api::cli_state::random_name;
+use ockam_api::colors::color_primary;
+use ockam_api::nodes::models::portal::{InletStatus, UnixSocketListenAddr};
+use ockam_api::nodes::service::tcp_inlets::Inlets;
+use ockam_api::nodes::BackgroundNodeClient;
+use ockam_api::{fmt_info, fmt_log, fmt_ok, fmt_warn, ConnectionStatus};
+use ockam_multiaddr::proto;
+use ockam_multiaddr::{MultiAddr, Protocol as _};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::node::util::initialize_default_node;
+use crate::shared_args::OptionalTimeoutArg;
+use crate::tcp::inlet::create::CreateCommand as TcpInletCreateCommand;
+use crate::tcp::util::alias_parser;
+use crate::util::parsers::duration_parser;
+use crate::{Command, CommandGlobalOpts};
+
+/// Create a Unix Inlet
+#[derive(Clone, Debug, Args)]
+pub struct CreateCommand {
+    /// Assign a name to this Unix Inlet
+    #[arg(id = "NAME", value_parser = alias_parser)]
+    pub name: Option<String>,
+
+    /// Node on which to start the Unix Inlet.
+    #[arg(long, display_order = 900, id = "NODE_NAME", value_parser = extract_address_value)]
+    pub at: Option<String>,
+
+    /// Path of the Unix domain socket on which to accept connections, for example `/tmp/docker.sock`.
+    /// A relative path is resolved against the current directory.
+    #[arg(long, display_order = 900, id = "SOCKET_PATH")]
+    pub from: PathBuf,
+
+    /// File mode of the socket, in octal. For example `660` only lets the owner and the group
+    /// of the socket connect to it. If you don't provide it, the mode depends on the umask of the node.
+    #[arg(long, display_order = 900, id = "MODE", value_parser = file_mode_parser)]
+    pub mode: Option<u32>,
+
+    /// Numeric user id of the owner of the socket
+    #[arg(long, display_order = 900, id = "UID")]
+    pub owner: Option<u32>,
+
+    /// Numeric group id of the owner of the socket
+    #[arg(long, display_order = 900, id = "GID")]
+    pub group: Option<u32>,
+
+    /// Route to an Outlet or the name of the Outlet service you want to connect to.
+    /// The Outlet can either be a Unix Outlet or a TCP Outlet.
+    ///
+    /// If you are connecting to a local node, you can provide the route as `/node/n/service/outlet`.
+    ///
+    /// If you are connecting to a remote node through a relay in the Orchestrator you can either
+    /// provide the full route to the Outlet as `/project/myproject/service/forward_to_myrelay/secure/api/service/outlet`,
+    /// or just the name of the service as `outlet` or `/service/outlet`.
+    /// If you are passing just the service name, consider using `--via` to specify the
+    /// relay name (e.g. `ockam unix-inlet create --to outlet --via myrelay`).
+    #[arg(long, display_order = 900, id = "ROUTE", default_value = "outlet")]
+    pub to: String,
+
+    /// Name of the relay that this Unix Inlet will use to connect to the Outlet.
+    #[arg(long, display_order = 900, id = "RELAY_NAME")]
+    pub via: Option<String>,
+
+    /// Authorized identifier for secure channel connection
+    #[arg(long, name = "AUTHORIZED", display_order = 900)]
+    pub authorized: Option<Identifier>,
+
+    #[arg(help = crate::docs::about("\
+     Policy expression that will be used for access control to the Unix Inlet. \
+     If you don't provide it, the policy set for the \"tcp-inlet\" resource type will be used. \
+     \n\nYou can check the fallback policy with `ockam policy show --resource-type tcp-inlet`."))]
+    #[arg(
+        long,
+        visible_alias = "expression",
+        display_order = 900,
+        id = "POLICY_EXPRESSION"
+    )]
+    pub allow: Option<PolicyExpression>,
+
+    /// Time to wait for the outlet to be available.
+    #[arg(long, display_order = 900, id = "WAIT", default_value = "5s", value_parser = duration_parser)]
+    pub connection_wait: Duration,
+
+    #[command(flatten)]
+    pub timeout: OptionalTimeoutArg,
+
+    /// Create the Unix Inlet without waiting for the Outlet to connect
+    #[arg(long, default_value = "false")]
+    pub no_connection_wait: bool,
+}
+
+/// Parse a file mode given in octal, with an optional `0o` prefix
+pub(crate) fn file_mode_parser(arg: &str) -> std::result::Result<u32, clap::Error> {
+    let digits = arg.strip_prefix("0o").unwrap_or(arg);
+    match u32::from_str_radix(digits, 8) {
+        Ok(mode) if mode <= 0o7777 => Ok(mode),
+        _ => Err(Error::raw(
+            ErrorKind::InvalidValue,
+            "Invalid file mode, expected an octal number such as 660",
+        )),
+    }
+}
+
+#[async_trait]
+impl Command for CreateCommand {
+    const NAME: &'static str = "unix-inlet create";
+
+    async fn run(self, ctx: &Context, opts: CommandGlobalOpts) -> crate::Result<()> {
+        initialize_default_node(ctx, &opts).await?;
+        let cmd = self.parse_args(&opts).await?;
+
+        let mut node = BackgroundNodeClient::create(ctx, &opts.state, &cmd.at).await?;
+        cmd.timeout.timeout.map(|t| node.set_timeout_mut(t));
+
+        let inlet_status: InletStatus = {
+            let pb = opts.terminal.spinner();
+            if let Some(pb) = pb.as_ref() {
+                pb.set_message(format!(
+                    "Creating Unix Inlet at {}...\n",
+                    color_primary(cmd.from.display().to_string())
+                ));
+            }
+            node.create_unix_inlet(
+                ctx,
+                &cmd.unix_socket(),
+                &cmd.to(),
+                cmd.name.as_ref().expect(
+                    "The `name` argument should be set to its default value if not provided",
+                ),
+                &cmd.authorized,
+                &cmd.allow,
+                cmd.connection_wait,
+                !cmd.no_connection_wait,
+                &None,
+            )
+            .await?
+            .miette_success("create unix inlet")?
+        };
+
+        let created_message = format!(
+            "Created a new Unix Inlet in the Node {} listening on {}",
+            color_primary(node.node_name()),
+            color_primary(&inlet_status.bind_addr),
+        );
+
+        let plain = if cmd.no_connection_wait {
+            fmt_ok!("{created_message}\n")
+                + &fmt_info!(
+                    "It will automatically connect to the Outlet at {} as soon as it is available\n",
+                    color_primary(&cmd.to)
+                )
+        } else if inlet_status.status == ConnectionStatus::Up {
+            fmt_ok!("{created_message}\n")
+                + &fmt_log!(
+                    "sending connections to the Outlet at {}\n",
+                    color_primary(&cmd.to)
+                )
+        } else {
+            fmt_warn!("{created_message}\n")
+                + &fmt_log!(
+                    "but it failed to connect to the Outlet at {}\n",
+                    color_primary(&cmd.to)
+                )
+                + &fmt_info!(
+                    "It will automatically connect to the Outlet as soon as it is available\n",
+                )
+        };
+
+        opts.terminal
+            .stdout()
+            .plain(plain)
+            .machine(inlet_status.bind_addr.to_string())
+            .json(serde_json::json!(&inlet_status))
+            .write_line()?;
+
+        Ok(())
+    }
+}
+
+impl CreateCommand {
+    pub fn to(&self) -> MultiAddr {
+        MultiAddr::from_str(&self.to).unwrap()
+    }
+
+    pub fn unix_socket(&self) -> UnixSocketListenAddr {
+        UnixSocketListenAddr::new(self.from.display().to_string())
+            .with_mode(self.mode)
+            .with_owner(self.owner, self.group)
+    }
+
+    pub async fn parse_args(mut self, opts: &CommandGlobalOpts) -> miette::Result<Self> {
+        self.name = self.name.or_else(|| Some(random_name()));
+        // The node may run in another directory
+        if self.from.is_relative() {
+            self.from = std::env::current_dir().into_diagnostic()?.join(&self.from);
+        }
+        self.to =
+            TcpInletCreateCommand::parse_arg_to(&opts.state, self.to, self.via.as_ref()).await?;
+        if self.to().matches(0, &[proto::Project::CODE.into()]) && self.authorized.is_some() {
+            return Err(miette!(
+                "--authorized can not be used with project addresses"
+            ))?;
+        }
+        // validate the route
+        MultiAddr::from_str(&self.to).into_diagnostic()?;
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run::parser::resource::utils::parse_cmd_from_args;
+
+    use super::*;
+
+    #[test]
+    fn command_can_be_parsed_from_name() {
+        let cmd = parse_cmd_from_args(
+            CreateCommand::NAME,
+            &[
+                "--from".to_string(),
+                "/tmp/docker.sock".to_string(),
+                "--mode".to_string(),
+                "660".to_string(),
+            ],
+        );
+        assert!(cmd.is_ok());
+    }
+
+    #[test]
+    fn file_mode_is_parsed_as_octal() {
+        assert_eq!(file_mode_parser("660").unwrap(), 0o660);
+        assert_eq!(file_mode_parser("0o600").unwrap(), 0o600);
+        assert!(file_mode_parser("800").is_err());
+        assert!(file_mode_parser("17777").is_err());
+    }
+}
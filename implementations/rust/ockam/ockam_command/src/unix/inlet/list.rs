@@ -0,0 +1,51 @@
+use async_trait::async_trait;
+use clap::Args;
+use std::path::Path;
+
+use ockam_api::nodes::models::portal::InletStatus;
+use ockam_api::nodes::BackgroundNodeClient;
+use ockam_core::api::Request;
+use ockam_node::Context;
+
+use crate::node::NodeOpts;
+use crate::{Command, CommandGlobalOpts};
+
+/// List Unix Inlets on the default node
+#[derive(Args, Clone, Debug)]
+pub struct ListCommand {
+    #[command(flatten)]
+    node: NodeOpts,
+}
+
+#[async_trait]
+impl Command for ListCommand {
+    const NAME: &'static str = "unix-inlet list";
+
+    async fn run(self, ctx: &Context, opts: CommandGlobalOpts) -> crate::Result<()> {
+        let node = BackgroundNodeClient::create(ctx, &opts.state, &self.node.at_node).await?;
+        let inlets: Vec<InletStatus> = {
+            let pb = opts.terminal.spinner();
+            if let Some(pb) = pb.as_ref() {
+                pb.set_message(format!("Listing Unix Inlets on {}...", node.node_name()));
+            }
+            let inlets: Vec<InletStatus> = node.ask(ctx, Request::get("/node/inlet")).await?;
+            // Unix Inlets are bound to an absolute socket path
+            inlets
+                .into_iter()
+                .filter(|i| Path::new(&i.bind_addr).is_absolute())
+                .collect()
+        };
+
+        let plain = opts.terminal.build_list(
+            &inlets,
+            &format!("No Unix Inlets found on {}", node.node_name()),
+        )?;
+        opts.terminal
+            .stdout()
+            .plain(plain)
+            .json_obj(&inlets)?
+            .write_line()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use clap::{Args, Subcommand};
+
+use create::CreateCommand;
+use delete::DeleteCommand;
+use list::ListCommand;
+use ockam_node::Context;
+
+use crate::{docs, Command, CommandGlobalOpts};
+
+pub(crate) mod create;
+mod delete;
+mod list;
+
+const LONG_ABOUT: &str = include_str!("./static/long_about.txt");
+
+/// Manage Unix Inlets
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    subcommand_required = true,
+    long_about = docs::about(LONG_ABOUT),
+)]
+pub struct UnixInletCommand {
+    #[command(subcommand)]
+    pub subcommand: UnixInletSubCommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum UnixInletSubCommand {
+    Create(CreateCommand),
+    Delete(DeleteCommand),
+    List(ListCommand),
+}
+
+impl UnixInletCommand {
+    pub async fn run(self, ctx: &Context, opts: CommandGlobalOpts) -> miette::Result<()> {
+        match self.subcommand {
+            UnixInletSubCommand::Create(c) => c.run(ctx, opts).await,
+            UnixInletSubCommand::Delete(c) => c.run(ctx, opts).await,
+            UnixInletSubCommand::List(c) => c.run(ctx, opts).await,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match &self.subcommand {
+            UnixInletSubCommand::Create(c) => c.name(),
+            UnixInletSubCommand::Delete(c) => c.name(),
+            UnixInletSubCommand::List(c) => c.name(),
+        }
+    }
+}
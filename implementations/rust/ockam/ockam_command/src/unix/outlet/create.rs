@@ -0,0 +1,114 @@
+use async_trait::async_trait;
+use clap::Args;
+use colorful::Colorful;
+use miette::IntoDiagnostic;
+use ockam::Address;
+use ockam::Context;
+use ockam_abac::PolicyExpression;
+use ockam_api::address::extract_address_value;
+use ockam_api::colors::color_primary;
+use ockam_api::fmt_ok;
+use ockam_api::nodes::service::tcp_outlets::Outlets;
+use ockam_api::nodes::BackgroundNodeClient;
+use std::path::PathBuf;
+
+use crate::node::util::initialize_default_node;
+use crate::{docs, Command, CommandGlobalOpts};
+
+/// Create a Unix Outlet that connects to a Unix domain socket
+#[derive(Clone, Debug, Args)]
+pub struct CreateCommand {
+    /// Address of your Unix Outlet, which is part of a route used in other commands.
+    /// This unique address identifies the Unix Outlet worker on the Node on your local machine.
+    /// Examples are `/service/my-outlet` or `my-outlet`.
+    /// If not provided, `outlet` will be used, or a random address will be generated if `outlet` is taken.
+    /// You will need this address when creating a Unix Inlet using `ockam unix-inlet create`,
+    /// or a TCP Inlet using `ockam tcp-inlet create`.
+    #[arg(value_parser = extract_address_value)]
+    pub name: Option<String>,
+
+    /// Path of the Unix domain socket your Outlet will connect to, for example `/var/run/docker.sock`.
+    /// A relative path is resolved against the current directory.
+    #[arg(long, id = "SOCKET_PATH", display_order = 900)]
+    pub to: PathBuf,
+
+    /// Your Unix Outlet will be created on this node. If you don't provide it, the default
+    /// node will be used
+    #[arg(long, display_order = 903, id = "NODE_NAME", value_parser = extract_address_value)]
+    pub at: Option<String>,
+
+    #[arg(help = docs::about("\
+    Policy expression that will be used for access control to the Unix Outlet. \
+    If you don't provide it, the policy set for the \"tcp-outlet\" resource type will be used. \
+    \n\nYou can check the fallback policy with `ockam policy show --resource-type tcp-outlet`"))]
+    #[arg(
+        long,
+        visible_alias = "expression",
+        display_order = 904,
+        id = "POLICY_EXPRESSION"
+    )]
+    pub allow: Option<PolicyExpression>,
+}
+
+#[async_trait]
+impl Command for CreateCommand {
+    const NAME: &'static str = "unix-outlet create";
+
+    async fn run(self, ctx: &Context, opts: CommandGlobalOpts) -> crate::Result<()> {
+        initialize_default_node(ctx, &opts).await?;
+
+        // The node may run in another directory
+        let to = std::env::current_dir().into_diagnostic()?.join(&self.to);
+        let to = to.display().to_string();
+
+        let node = BackgroundNodeClient::create(ctx, &opts.state, &self.at).await?;
+        let outlet_status = {
+            let pb = opts.terminal.spinner();
+            if let Some(pb) = pb.as_ref() {
+                pb.set_message(format!(
+                    "Creating a new Unix Outlet to {}...\n",
+                    color_primary(&to)
+                ));
+            }
+            node.create_unix_outlet(
+                ctx,
+                &to,
+                self.name.clone().map(Address::from).as_ref(),
+                self.allow.clone(),
+            )
+            .await?
+        };
+
+        let worker_route = outlet_status.worker_route().into_diagnostic()?;
+
+        opts.terminal
+            .stdout()
+            .plain(fmt_ok!(
+                "Created a new Unix Outlet in the Node {} at {} connecting to {}\n",
+                color_primary(node.node_name()),
+                color_primary(worker_route.to_string()),
+                color_primary(&to)
+            ))
+            .machine(worker_route)
+            .json(serde_json::to_string(&outlet_status).into_diagnostic()?)
+            .write_line()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run::parser::resource::utils::parse_cmd_from_args;
+
+    use super::*;
+
+    #[test]
+    fn command_can_be_parsed_from_name() {
+        let cmd = parse_cmd_from_args(
+            CreateCommand::NAME,
+            &["--to".to_string(), "/var/run/docker.sock".to_string()],
+        );
+        assert!(cmd.is_ok());
+    }
+}
@@ -0,0 +1,129 @@
+use async_trait::async_trait;
+use clap::Args;
+use colorful::Colorful;
+use console::Term;
+
+use crate::node::NodeOpts;
+use crate::tcp::util::alias_parser;
+use crate::{Command, CommandGlobalOpts};
+use ockam::Context;
+use ockam_api::colors::color_primary;
+use ockam_api::fmt_ok;
+use ockam_api::nodes::models::portal::OutletStatus;
+use ockam_api::nodes::BackgroundNodeClient;
+use ockam_api::terminal::{Terminal, TerminalStream};
+use ockam_core::api::Request;
+use ockam_core::TryClone;
+
+use crate::terminal::tui::DeleteCommandTui;
+use crate::tui::PluralTerm;
+
+/// Delete a Unix Outlet
+#[derive(Clone, Debug, Args)]
+pub struct DeleteCommand {
+    /// Delete the Outlet with this address. If you don't provide an address, you will be
+    /// prompted to select from a list of available Outlets to delete
+    #[arg(display_order = 900, id = "ALIAS", value_parser = alias_parser)]
+    alias: Option<String>,
+
+    /// Node on which to stop the Unix Outlet. If you don't provide it, the default node will be used
+    #[command(flatten)]
+    node_opts: NodeOpts,
+
+    /// Confirm the deletion without prompting
+    #[arg(display_order = 901, long, short)]
+    yes: bool,
+
+    /// Delete all the Unix Outlets
+    #[arg(long)]
+    all: bool,
+}
+
+#[async_trait]
+impl Command for DeleteCommand {
+    const NAME: &'static str = "unix-outlet delete";
+
+    async fn run(self, ctx: &Context, opts: CommandGlobalOpts) -> crate::Result<()> {
+        Ok(DeleteTui::run(ctx, opts, self).await?)
+    }
+}
+
+#[derive(TryClone)]
+struct DeleteTui {
+    ctx: Context,
+    opts: CommandGlobalOpts,
+    cmd: DeleteCommand,
+    node: BackgroundNodeClient,
+}
+
+impl DeleteTui {
+    pub async fn run(
+        ctx: &Context,
+        opts: CommandGlobalOpts,
+        cmd: DeleteCommand,
+    ) -> miette::Result<()> {
+        let node = BackgroundNodeClient::create(ctx, &opts.state, &cmd.node_opts.at_node).await?;
+        let tui = Self {
+            ctx: ctx.try_clone()?,
+            opts,
+            cmd,
+            node,
+        };
+        tui.delete().await
+    }
+}
+
+#[async_trait]
+impl DeleteCommandTui for DeleteTui {
+    const ITEM_NAME: PluralTerm = PluralTerm::UnixOutlet;
+
+    fn cmd_arg_item_name(&self) -> Option<String> {
+        self.cmd.alias.clone()
+    }
+
+    fn cmd_arg_delete_all(&self) -> bool {
+        self.cmd.all
+    }
+
+    fn cmd_arg_confirm_deletion(&self) -> bool {
+        self.cmd.yes
+    }
+
+    fn terminal(&self) -> Terminal<TerminalStream<Term>> {
+        self.opts.terminal.clone()
+    }
+
+    async fn list_items_names(&self) -> miette::Result<Vec<String>> {
+        let outlets: Vec<OutletStatus> = self
+            .node
+            .ask(&self.ctx, Request::get("/node/outlet"))
+            .await?;
+        Ok(outlets
+            .iter()
+            .filter(|outlet| outlet.unix_socket_path.is_some())
+            .map(|outlet| outlet.worker_addr.address().to_string())
+            .collect())
+    }
+
+    async fn delete_single(&self, item_name: &str) -> miette::Result<()> {
+        let node_name = self.node.node_name();
+        let _: OutletStatus = self
+            .node
+            .ask(
+                &self.ctx,
+                Request::delete(format!("/node/outlet/{item_name}")),
+            )
+            .await?;
+        self.terminal()
+            .stdout()
+            .plain(fmt_ok!(
+                "Unix Outlet with address {} on node {} has been deleted",
+                color_primary(item_name),
+                color_primary(node_name)
+            ))
+            .machine(item_name)
+            .json(serde_json::json!({ "alias": item_name, "node": node_name }))
+            .write_line()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use async_trait::async_trait;
+use clap::Args;
+
+use ockam_api::nodes::models::portal::OutletStatus;
+use ockam_api::nodes::BackgroundNodeClient;
+use ockam_core::api::Request;
+use ockam_node::Context;
+
+use crate::node::NodeOpts;
+use crate::{Command, CommandGlobalOpts};
+
+/// List Unix Outlets on the default node
+#[derive(Args, Clone, Debug)]
+pub struct ListCommand {
+    #[command(flatten)]
+    node: NodeOpts,
+}
+
+#[async_trait]
+impl Command for ListCommand {
+    const NAME: &'static str = "unix-outlet list";
+
+    async fn run(self, ctx: &Context, opts: CommandGlobalOpts) -> crate::Result<()> {
+        let node = BackgroundNodeClient::create(ctx, &opts.state, &self.node.at_node).await?;
+        let outlets: Vec<OutletStatus> = {
+            let pb = opts.terminal.spinner();
+            if let Some(pb) = pb.as_ref() {
+                pb.set_message(format!("Listing Unix Outlets on {}...", node.node_name()));
+            }
+            let outlets: Vec<OutletStatus> = node.ask(ctx, Request::get("/node/outlet")).await?;
+            outlets
+                .into_iter()
+                .filter(|o| o.unix_socket_path.is_some())
+                .collect()
+        };
+
+        let plain = opts.terminal.build_list(
+            &outlets,
+            &format!("No Unix Outlets found on {}", node.node_name()),
+        )?;
+        let json: Vec<_> = outlets
+            .iter()
+            .map(|outlet| {
+                Ok(serde_json::json!({
+                    "from": outlet.worker_route()?,
+                    "to": outlet.destination(),
+                }))
+            })
+            .flat_map(|res: Result<_, ockam_core::Error>| res.ok())
+            .collect();
+
+        opts.terminal
+            .stdout()
+            .plain(plain)
+            .json(serde_json::json!(json))
+            .write_line()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use clap::{Args, Subcommand};
+
+use create::CreateCommand;
+use delete::DeleteCommand;
+use list::ListCommand;
+use ockam_node::Context;
+
+use crate::{docs, Command, CommandGlobalOpts};
+
+pub(crate) mod create;
+mod delete;
+mod list;
+
+const LONG_ABOUT: &str = include_str!("./static/long_about.txt");
+
+/// Manage Unix Outlets
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    subcommand_required = true,
+    long_about = docs::about(LONG_ABOUT),
+)]
+pub struct UnixOutletCommand {
+    #[command(subcommand)]
+    pub subcommand: UnixOutletSubCommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum UnixOutletSubCommand {
+    Create(CreateCommand),
+    Delete(DeleteCommand),
+    List(ListCommand),
+}
+
+impl UnixOutletCommand {
+    pub async fn run(self, ctx: &Context, opts: CommandGlobalOpts) -> miette::Result<()> {
+        match self.subcommand {
+            UnixOutletSubCommand::Create(c) => c.run(ctx, opts).await,
+            UnixOutletSubCommand::Delete(c) => c.run(ctx, opts).await,
+            UnixOutletSubCommand::List(c) => c.run(ctx, opts).await,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match &self.subcommand {
+            UnixOutletSubCommand::Create(c) => c.name(),
+            UnixOutletSubCommand::Delete(c) => c.name(),
+            UnixOutletSubCommand::List(c) => c.name(),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use colorful::Colorful;
+use miette::IntoDiagnostic;
+use ockam_api::fmt_ok;
+
+use crate::vault::util::get_vault_passphrase;
+use crate::{docs, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/change-passphrase/long_about.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/change-passphrase/after_long_help.txt");
+
+/// Environment variable used to provide the new passphrase of a vault
+pub const OCKAM_VAULT_NEW_PASSPHRASE: &str = "OCKAM_VAULT_NEW_PASSPHRASE";
+
+/// Change the passphrase protecting the secrets of an encrypted vault
+#[derive(Clone, Debug, Args)]
+#[command(
+long_about = docs::about(LONG_ABOUT),
+after_long_help = docs::after_help(AFTER_LONG_HELP)
+)]
+pub struct ChangePassphraseCommand {
+    #[arg()]
+    name: String,
+
+    /// File containing the current key of the vault
+    #[arg(long, value_name = "PATH")]
+    key_file: Option<PathBuf>,
+
+    /// File containing the new key of the vault
+    #[arg(long, value_name = "PATH")]
+    new_key_file: Option<PathBuf>,
+}
+
+impl ChangePassphraseCommand {
+    pub fn name(&self) -> String {
+        "vault change-passphrase".into()
+    }
+
+    pub async fn run(&self, opts: CommandGlobalOpts) -> miette::Result<()> {
+        let old_passphrase = get_vault_passphrase(&opts, self.key_file.as_deref(), false)?;
+        let new_passphrase = match (
+            &self.new_key_file,
+            std::env::var(OCKAM_VAULT_NEW_PASSPHRASE),
+        ) {
+            (Some(new_key_file), _) => std::fs::read(new_key_file).into_diagnostic()?,
+            (None, Ok(new_passphrase)) => new_passphrase.into_bytes(),
+            (None, Err(_)) => opts
+                .terminal
+                .read_password("New vault passphrase", true)?
+                .into_bytes(),
+        };
+        opts.state
+            .change_vault_passphrase(&self.name, &old_passphrase, &new_passphrase)
+            .await?;
+        opts.terminal
+            .stdout()
+            .plain(fmt_ok!(
+                "The passphrase of the vault {} has been changed",
+                self.name
+            ))
+            .write_line()?;
+        Ok(())
+    }
+}
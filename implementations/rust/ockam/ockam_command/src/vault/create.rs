@@ -8,6 +8,7 @@ use ockam_api::{fmt_info, fmt_ok};
 
 use ockam_node::Context;
 
+use crate::vault::util::get_vault_passphrase;
 use crate::{docs, Command, CommandGlobalOpts};
 
 const LONG_ABOUT: &str = include_str!("./static/create/long_about.txt");
@@ -28,6 +29,26 @@ pub struct CreateCommand {
 
     #[arg(long, default_value = "false")]
     pub aws_kms: bool,
+
+    /// Path to a PKCS#11 module, for example a HSM library or SoftHSM2.
+    /// Signing keys and static X25519 keys are then stored on the token.
+    /// The user PIN of the token is read from the OCKAM_PKCS11_PIN environment variable
+    #[arg(long, value_name = "MODULE_PATH", conflicts_with_all = ["path", "aws_kms"])]
+    pub pkcs11_module: Option<PathBuf>,
+
+    /// Label of the PKCS#11 token to use. By default, the first token with a login is used
+    #[arg(long, value_name = "TOKEN_LABEL", requires = "pkcs11_module")]
+    pub pkcs11_token_label: Option<String>,
+
+    /// Encrypt the secrets of the vault with a passphrase.
+    /// The passphrase is read from the OCKAM_VAULT_PASSPHRASE environment variable,
+    /// from the file given with --key-file, or asked interactively
+    #[arg(long, default_value = "false")]
+    pub encrypted: bool,
+
+    /// File containing the key used to encrypt the secrets of the vault, instead of a passphrase
+    #[arg(long, value_name = "PATH", requires = "encrypted")]
+    pub key_file: Option<PathBuf>,
 }
 
 #[async_trait]
@@ -41,10 +62,28 @@ impl Command for CreateCommand {
         ))?;
         }
 
-        let vault = opts
-            .state
-            .create_named_vault(self.name, self.path, UseAwsKms::from(self.aws_kms))
-            .await?;
+        // get the passphrase first, so that no vault is created if it is not provided
+        let passphrase = if self.encrypted {
+            Some(get_vault_passphrase(&opts, self.key_file.as_deref(), true)?)
+        } else {
+            None
+        };
+
+        let vault = match self.pkcs11_module {
+            Some(module) => {
+                opts.state
+                    .create_pkcs11_vault(self.name, module, self.pkcs11_token_label)
+                    .await?
+            }
+            None => {
+                opts.state
+                    .create_named_vault(self.name, self.path, UseAwsKms::from(self.aws_kms))
+                    .await?
+            }
+        };
+        if let Some(passphrase) = passphrase {
+            opts.state.encrypt_vault(&vault.name(), &passphrase).await?;
+        }
 
         opts.terminal
             .stdout()
@@ -67,4 +106,17 @@ mod tests {
         let cmd = parse_cmd_from_args(CreateCommand::NAME, &[]);
         assert!(cmd.is_ok());
     }
+
+    #[test]
+    fn pkcs11_module_conflicts_with_aws_kms() {
+        let cmd = parse_cmd_from_args(
+            CreateCommand::NAME,
+            &[
+                "--pkcs11-module".to_string(),
+                "/usr/lib/softhsm/libsofthsm2.so".to_string(),
+                "--aws-kms".to_string(),
+            ],
+        );
+        assert!(cmd.is_err());
+    }
 }
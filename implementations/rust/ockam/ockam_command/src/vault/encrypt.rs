@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use colorful::Colorful;
+use ockam_api::fmt_ok;
+
+use crate::vault::util::get_vault_passphrase;
+use crate::{docs, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/encrypt/long_about.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/encrypt/after_long_help.txt");
+
+/// Encrypt the secrets of an existing vault with a passphrase
+#[derive(Clone, Debug, Args)]
+#[command(
+long_about = docs::about(LONG_ABOUT),
+after_long_help = docs::after_help(AFTER_LONG_HELP)
+)]
+pub struct EncryptCommand {
+    #[arg()]
+    name: String,
+
+    /// File containing the key used to encrypt the secrets of the vault, instead of a passphrase
+    #[arg(long, value_name = "PATH")]
+    key_file: Option<PathBuf>,
+}
+
+impl EncryptCommand {
+    pub fn name(&self) -> String {
+        "vault encrypt".into()
+    }
+
+    pub async fn run(&self, opts: CommandGlobalOpts) -> miette::Result<()> {
+        let passphrase = get_vault_passphrase(&opts, self.key_file.as_deref(), true)?;
+        opts.state.encrypt_vault(&self.name, &passphrase).await?;
+        opts.terminal
+            .stdout()
+            .plain(fmt_ok!(
+                "The secrets of the vault {} are now encrypted",
+                self.name
+            ))
+            .write_line()?;
+        Ok(())
+    }
+}
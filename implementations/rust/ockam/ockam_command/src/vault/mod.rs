@@ -1,7 +1,9 @@
 use clap::{Args, Subcommand};
 
+use crate::vault::change_passphrase::ChangePassphraseCommand;
 pub use crate::vault::create::CreateCommand;
 use crate::vault::delete::DeleteCommand;
+use crate::vault::encrypt::EncryptCommand;
 use crate::vault::list::ListCommand;
 use crate::vault::move_vault::MoveCommand;
 use crate::vault::show::ShowCommand;
@@ -9,12 +11,14 @@ use crate::{docs, Command, CommandGlobalOpts};
 
 use ockam_node::Context;
 
+mod change_passphrase;
 mod create;
 mod delete;
+mod encrypt;
 mod list;
 mod move_vault;
 mod show;
-mod util;
+pub(crate) mod util;
 
 const LONG_ABOUT: &str = include_str!("./static/long_about.txt");
 
@@ -37,6 +41,8 @@ pub enum VaultSubcommand {
     Show(ShowCommand),
     Delete(DeleteCommand),
     List(ListCommand),
+    Encrypt(EncryptCommand),
+    ChangePassphrase(ChangePassphraseCommand),
 }
 
 impl VaultCommand {
@@ -47,6 +53,8 @@ impl VaultCommand {
             VaultSubcommand::Show(cmd) => cmd.run(opts).await,
             VaultSubcommand::List(cmd) => cmd.run(opts).await,
             VaultSubcommand::Delete(cmd) => cmd.run(opts).await,
+            VaultSubcommand::Encrypt(cmd) => cmd.run(opts).await,
+            VaultSubcommand::ChangePassphrase(cmd) => cmd.run(opts).await,
         }
     }
 
@@ -57,6 +65,8 @@ impl VaultCommand {
             VaultSubcommand::Show(c) => c.name(),
             VaultSubcommand::Delete(c) => c.name(),
             VaultSubcommand::List(c) => c.name(),
+            VaultSubcommand::Encrypt(c) => c.name(),
+            VaultSubcommand::ChangePassphrase(c) => c.name(),
         }
     }
 }
@@ -1,11 +1,63 @@
+use std::path::Path;
+use std::sync::Arc;
+
 use colorful::Colorful;
 use indoc::formatdoc;
+use miette::IntoDiagnostic;
 
 use ockam_api::cli_state::vaults::NamedVault;
-use ockam_api::cli_state::{UseAwsKms, VaultType};
+use ockam_api::cli_state::{vault_passphrase_from_env, UseAwsKms, VaultType};
 use ockam_api::colors::OckamColor;
 use ockam_api::output::{indent, Output};
 
+use crate::CommandGlobalOpts;
+
+/// Return the passphrase of an encrypted vault, read from a key file if one is given,
+/// then from the environment, or finally asked to the user
+pub(crate) fn get_vault_passphrase(
+    opts: &CommandGlobalOpts,
+    key_file: Option<&Path>,
+    confirmation: bool,
+) -> miette::Result<Vec<u8>> {
+    if let Some(key_file) = key_file {
+        return std::fs::read(key_file).into_diagnostic();
+    }
+    if let Some(passphrase) = vault_passphrase_from_env()? {
+        return Ok(passphrase);
+    }
+    Ok(opts
+        .terminal
+        .read_password("Vault passphrase", confirmation)?
+        .into_bytes())
+}
+
+/// If the terminal is interactive, ask for the passphrase of an encrypted vault
+/// the first time that vault is used and its passphrase is not provided by the environment
+pub(crate) fn set_vault_passphrase_prompt(opts: &CommandGlobalOpts) {
+    if !opts.terminal.can_ask_for_user_input() {
+        return;
+    }
+    let terminal = opts.terminal.clone();
+    opts.state
+        .set_vault_passphrase_prompt(Arc::new(move |vault_name: &str| {
+            terminal
+                .read_password(format!("Passphrase of the vault {vault_name}"), false)
+                .ok()
+                .map(|passphrase| passphrase.into_bytes())
+        }));
+}
+
+/// Ask for the passphrases of all the encrypted vaults, if they are not provided by the environment,
+/// so that they can be passed to the nodes started by this process
+pub(crate) async fn ask_for_vault_passphrases_if_needed(
+    opts: &CommandGlobalOpts,
+) -> miette::Result<()> {
+    if !opts.terminal.can_ask_for_user_input() {
+        return Ok(());
+    }
+    Ok(opts.state.unlock_encrypted_vaults().await?)
+}
+
 #[derive(serde::Serialize)]
 pub struct VaultOutput {
     vault: NamedVault,
@@ -104,6 +156,23 @@ impl Output for VaultOutput {
                     .color(OckamColor::PrimaryResource.color()),
                 uses_aws_kms = uses_aws_kms,
             ),
+            VaultType::Pkcs11Vault {
+                module,
+                token_label,
+            } => formatdoc!(
+                r#"Name: {name}
+            Type: PKCS#11
+            Module: {module}
+            Token: {token_label}"#,
+                name = name,
+                module = module
+                    .to_string_lossy()
+                    .to_string()
+                    .color(OckamColor::PrimaryResource.color()),
+                token_label = token_label
+                    .unwrap_or_else(|| "first token with a login".to_string())
+                    .color(OckamColor::PrimaryResource.color()),
+            ),
         })
     }
 }
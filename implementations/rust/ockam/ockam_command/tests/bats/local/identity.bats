@@ -99,3 +99,18 @@ teardown() {
   run_success "$OCKAM" identity show --full --encoding hex
   assert_output "$exported"
 }
+
+@test "identity - recover with a recovery key" {
+  run_success "$OCKAM" identity create i --recovery-key-file "$BATS_TEST_TMPDIR/i.recovery"
+  identifier=$output
+  run_success "$OCKAM" identity show i --full --encoding hex
+  exported=$output
+
+  # Lose the identity and its vault
+  run_success "$OCKAM" reset -y
+
+  run_success "$OCKAM" identity recover i --identity "$exported" --recovery-key-file "$BATS_TEST_TMPDIR/i.recovery"
+  assert_output --partial "$identifier"
+  run_success "$OCKAM" identity show i
+  assert_output "$identifier"
+}
@@ -4,7 +4,7 @@ use ockam_vault::{VaultForSigning, VaultForVerifyingSignatures};
 use crate::models::{CredentialData, PurposeKeyAttestationData};
 use crate::{
     CredentialsCreation, CredentialsVerification, IdentitiesCreation, IdentityAttributesRepository,
-    PurposeKeys,
+    PurposeKeys, RevocationLists,
 };
 
 /// Structure with both [`CredentialData`] and [`PurposeKeyAttestationData`] that we get
@@ -24,6 +24,7 @@ pub struct Credentials {
     purpose_keys: Arc<PurposeKeys>,
     identities_creation: Arc<IdentitiesCreation>,
     identity_attributes_repository: Arc<dyn IdentityAttributesRepository>,
+    revocation_lists: RevocationLists,
 }
 
 impl Credentials {
@@ -34,6 +35,7 @@ impl Credentials {
         purpose_keys: Arc<PurposeKeys>,
         identities_creation: Arc<IdentitiesCreation>,
         identity_attributes_repository: Arc<dyn IdentityAttributesRepository>,
+        revocation_lists: RevocationLists,
     ) -> Self {
         Self {
             credential_vault,
@@ -41,6 +43,7 @@ impl Credentials {
             purpose_keys,
             identities_creation,
             identity_attributes_repository,
+            revocation_lists,
         }
     }
 
@@ -65,6 +68,7 @@ impl Credentials {
             self.purpose_keys.purpose_keys_verification(),
             self.verifying_vault.clone(),
             self.identity_attributes_repository.clone(),
+            self.revocation_lists.clone(),
         ))
     }
 }
@@ -8,12 +8,13 @@ use ockam_vault::VaultForVerifyingSignatures;
 
 use crate::identities::AttributesEntry;
 use crate::models::{
-    CredentialAndPurposeKey, CredentialData, Identifier, PurposePublicKey, VersionedData,
+    Credential, CredentialAndPurposeKey, CredentialData, CredentialHash, Identifier,
+    PurposePublicKey, RevocationListAndPurposeKey, RevocationListData, VersionedData,
 };
 use crate::utils::now;
 use crate::{
     CredentialAndPurposeKeyData, IdentityAttributesRepository, IdentityError,
-    PurposeKeyVerification, TimestampInSeconds,
+    PurposeKeyVerification, RevocationLists, TimestampInSeconds,
 };
 
 /// We allow Credentials to be created in the future related to this machine's time due to
@@ -25,6 +26,7 @@ pub struct CredentialsVerification {
     purpose_keys_verification: Arc<PurposeKeyVerification>,
     verifying_vault: Arc<dyn VaultForVerifyingSignatures>,
     identities_attributes_repository: Arc<dyn IdentityAttributesRepository>,
+    revocation_lists: RevocationLists,
 }
 
 impl CredentialsVerification {
@@ -33,31 +35,59 @@ impl CredentialsVerification {
         purpose_keys_verification: Arc<PurposeKeyVerification>,
         verifying_vault: Arc<dyn VaultForVerifyingSignatures>,
         identities_attributes_repository: Arc<dyn IdentityAttributesRepository>,
+        revocation_lists: RevocationLists,
     ) -> Self {
         Self {
             purpose_keys_verification,
             verifying_vault,
             identities_attributes_repository,
+            revocation_lists,
         }
     }
 }
 
 impl CredentialsVerification {
-    /// Verify a [`Credential`]
+    /// Verify a [`Credential`] and check that it wasn't revoked by its Authority
     pub async fn verify_credential(
         &self,
         expected_subject: Option<&Identifier>,
         authorities: &[Identifier],
         credential_and_purpose_key: &CredentialAndPurposeKey,
     ) -> Result<CredentialAndPurposeKeyData> {
-        Self::verify_credential_static(
+        let res = Self::verify_credential_static(
             self.purpose_keys_verification.clone(),
             self.verifying_vault.clone(),
             expected_subject,
             authorities,
             credential_and_purpose_key,
         )
-        .await
+        .await?;
+
+        if let Some(subject) = &res.credential_data.subject {
+            let credential_hash = self
+                .compute_credential_hash(&credential_and_purpose_key.credential)
+                .await?;
+            if self.revocation_lists.is_revoked(
+                &res.purpose_key_data.subject,
+                subject,
+                &credential_hash,
+            ) {
+                warn!(
+                    "the credential {} of {} was revoked",
+                    credential_hash, subject
+                );
+                return Err(IdentityError::CredentialRevoked)?;
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Compute the [`CredentialHash`] used to revoke a [`Credential`]
+    pub async fn compute_credential_hash(&self, credential: &Credential) -> Result<CredentialHash> {
+        Ok(CredentialHash(
+            self.verifying_vault.sha256(&credential.data).await?.0,
+        ))
     }
 
     /// Verify a [`Credential`]
@@ -202,6 +232,15 @@ impl CredentialsVerification {
         let credential_data = credential.credential_data;
         let purpose_key_data = credential.purpose_key_data;
 
+        let credential_hash = self
+            .compute_credential_hash(&credential_and_purpose_key_attestation.credential)
+            .await?;
+        self.revocation_lists.record_presented_credential(
+            &purpose_key_data.subject,
+            subject,
+            credential_hash,
+        );
+
         let attributes_display = credential_data.get_attributes_display();
         let attributes: BTreeMap<_, _> = credential_data
             .subject_attributes
@@ -239,4 +278,102 @@ impl CredentialsVerification {
 
         Ok(())
     }
+
+    /// Verify a [`crate::models::RevocationList`] and return the Authority that signed it
+    pub async fn verify_revocation_list(
+        &self,
+        authorities: &[Identifier],
+        revocation_list_and_purpose_key: &RevocationListAndPurposeKey,
+    ) -> Result<(Identifier, RevocationListData)> {
+        let purpose_key_data = self
+            .purpose_keys_verification
+            .verify_purpose_key_attestation(
+                None,
+                &revocation_list_and_purpose_key.purpose_key_attestation,
+            )
+            .await?;
+
+        if !authorities.contains(&purpose_key_data.subject) {
+            warn!(
+                "unknown authority on a revocation list: {}. Accepted authorities: {:?}",
+                purpose_key_data.subject, authorities
+            );
+            return Err(IdentityError::UnknownAuthority)?;
+        }
+
+        let public_key = match purpose_key_data.public_key.clone() {
+            PurposePublicKey::SecureChannelStatic(_) => {
+                return Err(IdentityError::InvalidKeyType)?;
+            }
+            PurposePublicKey::CredentialSigning(public_key) => public_key,
+        };
+
+        let revocation_list = &revocation_list_and_purpose_key.revocation_list;
+        let versioned_data_hash = self.verifying_vault.sha256(&revocation_list.data).await?;
+        let signature = revocation_list.signature.clone().into();
+
+        if !self
+            .verifying_vault
+            .verify_signature(&public_key.into(), &versioned_data_hash.0, &signature)
+            .await?
+        {
+            return Err(IdentityError::RevocationListVerificationFailed)?;
+        }
+
+        let revocation_list_data = revocation_list.get_revocation_list_data()?;
+
+        if revocation_list_data.created_at < purpose_key_data.created_at
+            || revocation_list_data.created_at > purpose_key_data.expires_at
+        {
+            // The list must be signed while the purpose key is valid
+            return Err(IdentityError::RevocationListVerificationFailed)?;
+        }
+
+        let now = now()?;
+        if revocation_list_data.created_at > now
+            && revocation_list_data.created_at - now > MAX_ALLOWED_TIME_DRIFT
+        {
+            // The list can't be created in the future
+            return Err(IdentityError::RevocationListVerificationFailed)?;
+        }
+
+        Ok((purpose_key_data.subject, revocation_list_data))
+    }
+
+    /// Receive a [`crate::models::RevocationList`]: verify it, cache it if it is newer than
+    /// the cached one, and remove the attributes of the subjects whose credential is revoked.
+    /// Return those subjects
+    pub async fn receive_revocation_list(
+        &self,
+        authorities: &[Identifier],
+        revocation_list_and_purpose_key: &RevocationListAndPurposeKey,
+    ) -> Result<Vec<Identifier>> {
+        let (authority, revocation_list_data) = self
+            .verify_revocation_list(authorities, revocation_list_and_purpose_key)
+            .await?;
+        let version = revocation_list_data.version;
+
+        let Some(revoked_subjects) = self
+            .revocation_lists
+            .update(&authority, revocation_list_data)
+        else {
+            debug!(%authority, version, "ignored an outdated revocation list");
+            return Ok(vec![]);
+        };
+
+        info!(
+            %authority,
+            version,
+            "received a revocation list revoking the credentials of {} known identities",
+            revoked_subjects.len()
+        );
+
+        for subject in &revoked_subjects {
+            self.identities_attributes_repository
+                .delete_attributes(subject, &authority)
+                .await?;
+        }
+
+        Ok(revoked_subjects)
+    }
 }
@@ -5,6 +5,9 @@ use ockam_core::Route;
 
 use crate::Identifier;
 
+/// Address of the revocation list service on an Authority node
+pub const AUTHORITY_NODE_REVOCATION_LIST_SERVICE_ADDRESS: &str = "revocation_list";
+
 enum CredentialIssuerServiceAddress {
     Controller,
     AuthorityNode,
@@ -50,6 +53,8 @@ pub struct RemoteCredentialRetrieverInfo {
     pub api_service_address: String,
     /// Request method, e.g. Post or Get
     pub request_method: Method,
+    /// Address of the revocation list service on the remote node, if it has one
+    pub revocation_list_service_address: Option<String>,
 }
 
 impl RemoteCredentialRetrieverInfo {
@@ -62,6 +67,7 @@ impl RemoteCredentialRetrieverInfo {
             CredentialIssuerApiServiceAddress::AuthorityNode.to_string(),
             Method::Post,
         )
+        .with_revocation_list_service_address(AUTHORITY_NODE_REVOCATION_LIST_SERVICE_ADDRESS)
     }
 
     /// Create info for a project admin credential that we get from the Orchestrator
@@ -100,6 +106,16 @@ impl RemoteCredentialRetrieverInfo {
             service_address,
             api_service_address,
             request_method,
+            revocation_list_service_address: None,
         }
     }
+
+    /// Periodically retrieve the revocation list of the issuer from that service
+    pub fn with_revocation_list_service_address(
+        mut self,
+        revocation_list_service_address: impl Into<String>,
+    ) -> Self {
+        self.revocation_list_service_address = Some(revocation_list_service_address.into());
+        self
+    }
 }
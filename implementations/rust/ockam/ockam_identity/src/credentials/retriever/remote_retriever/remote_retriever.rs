@@ -3,7 +3,7 @@ use tracing::{debug, error, info, trace, warn};
 
 use ockam_core::api::Request;
 use ockam_core::compat::string::String;
-use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::compat::sync::{Arc, RwLock, Weak};
 use ockam_core::compat::time::Duration;
 use ockam_core::compat::vec::Vec;
 use ockam_core::{route, Address, Result};
@@ -11,7 +11,7 @@ use ockam_node::compat::asynchronous::Mutex;
 use ockam_node::Context;
 use ockam_transport_core::Transport;
 
-use crate::models::CredentialAndPurposeKey;
+use crate::models::{CredentialAndPurposeKey, RevocationListAndPurposeKey};
 use crate::utils::now;
 use crate::{
     get_default_timeout, CachedCredentialRetriever, Identifier, RemoteCredentialRetrieverInfo,
@@ -32,6 +32,14 @@ pub const DEFAULT_CREDENTIAL_REQUEST_TIMEOUT: Duration = Duration::from_secs(15)
 /// Start refresh in the background before it expires
 pub const DEFAULT_CREDENTIAL_PROACTIVE_REFRESH_GAP: TimestampInSeconds = TimestampInSeconds(60);
 
+/// Default interval between 2 retrievals of the issuer revocation list
+pub const DEFAULT_REVOCATION_LIST_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Maximum interval between 2 retrievals of the issuer revocation list.
+/// The interval is doubled, up to that value, each time the revocation list can't be retrieved,
+/// for example when the issuer has no revocation list service
+pub const MAX_REVOCATION_LIST_REFRESH_INTERVAL: Duration = Duration::from_secs(3600);
+
 /// Timing options for retrieving remote credentials
 #[derive(Clone, Copy)]
 pub struct RemoteCredentialRetrieverTimingOptions {
@@ -46,6 +54,8 @@ pub struct RemoteCredentialRetrieverTimingOptions {
     /// Time gap used to consider credential expired before its actual expiration
     /// to account for time errors on different machines
     pub clock_skew_gap: TimestampInSeconds,
+    /// Interval between 2 retrievals of the issuer revocation list
+    pub revocation_list_refresh_interval: Duration,
 }
 
 impl Default for RemoteCredentialRetrieverTimingOptions {
@@ -56,6 +66,7 @@ impl Default for RemoteCredentialRetrieverTimingOptions {
             min_refresh_interval: DEFAULT_MIN_REFRESH_CREDENTIAL_INTERVAL,
             proactive_refresh_gap: DEFAULT_PROACTIVE_REFRESH_CREDENTIAL_TIME_GAP,
             clock_skew_gap: DEFAULT_CREDENTIAL_CLOCK_SKEW_GAP,
+            revocation_list_refresh_interval: DEFAULT_REVOCATION_LIST_REFRESH_INTERVAL,
         }
     }
 }
@@ -81,6 +92,9 @@ pub struct RemoteCredentialRetriever {
     pub(super) last_presented_credential: Arc<RwLock<Option<LastPresentedCredential>>>,
     /// Subscribers addresses that we will notify when credential is refreshed
     pub(super) subscribers: Arc<RwLock<Vec<Address>>>,
+    /// Shared by the clones of this retriever. The background refresh of the revocation list
+    /// stops once the last clone is dropped
+    revocation_list_refresh: Arc<()>,
 }
 
 impl RemoteCredentialRetriever {
@@ -110,6 +124,7 @@ impl RemoteCredentialRetriever {
             is_initialized: Arc::new(Mutex::new(false)),
             last_presented_credential: Arc::new(RwLock::new(None)),
             subscribers: Default::default(),
+            revocation_list_refresh: Default::default(),
         }
     }
 
@@ -165,6 +180,10 @@ impl RemoteCredentialRetriever {
             self.schedule_credentials_refresh_impl(refresh_in.duration, false);
         }
 
+        if let Some(service_address) = &self.issuer_info.revocation_list_service_address {
+            self.refresh_revocation_list_in_background(service_address.clone());
+        }
+
         *is_initialized = true;
 
         Ok(())
@@ -264,15 +283,8 @@ impl RemoteCredentialRetriever {
 }
 
 impl RemoteCredentialRetriever {
-    async fn get_new_credential(&self) -> Result<()> {
-        debug!(subject=%self.subject, issuer=%self.issuer_info.issuer,
-            "retrieving a new credential");
-        let cache = self
-            .secure_channels
-            .identities
-            .cached_credentials_repository();
-
-        let client = SecureClient::new(
+    fn make_secure_client(&self) -> SecureClient {
+        SecureClient::new(
             self.secure_channels.clone(),
             None,
             self.transport.clone(),
@@ -281,7 +293,18 @@ impl RemoteCredentialRetriever {
             &self.subject,
             self.timing_options.secure_channel_creation_timeout,
             self.timing_options.request_timeout,
-        );
+        )
+    }
+
+    async fn get_new_credential(&self) -> Result<()> {
+        debug!(subject=%self.subject, issuer=%self.issuer_info.issuer,
+            "retrieving a new credential");
+        let cache = self
+            .secure_channels
+            .identities
+            .cached_credentials_repository();
+
+        let client = self.make_secure_client();
 
         let credential: CredentialAndPurposeKey = client
             .ask(
@@ -362,18 +385,85 @@ impl RemoteCredentialRetriever {
             info!(issuer=%s.issuer_info.issuer, is_retry,
                 "scheduled background credentials refresh in {} seconds",
                 wait.as_secs());
-            s.ctx
-                .sleep_long_until(*now().unwrap() + wait.as_secs())
-                .await;
+            match now() {
+                Ok(now) => s.ctx.sleep_long_until(*now + wait.as_secs()).await,
+                Err(_) => s.ctx.sleep(wait).await,
+            }
             debug!(issuer=%s.issuer_info.issuer, is_retry,
                 "executing background credentials refresh");
             if let Some(err) = s.get_new_credential().await.err() {
                 error!(subject=%s.subject, is_retry, %err,
                     "error refreshing credential in the background");
-                s.schedule_credentials_refresh(now().unwrap(), true);
+                match now() {
+                    Ok(now) => s.schedule_credentials_refresh(now, true),
+                    Err(_) => s.schedule_credentials_refresh_impl(
+                        s.timing_options.min_refresh_interval,
+                        true,
+                    ),
+                }
             } else {
                 debug!(issuer=%s.issuer_info.issuer, is_retry, "credentials refreshed");
             }
         });
     }
+
+    /// Retrieve the revocation list of the issuer and stop the secure channels
+    /// established with revoked identities
+    async fn get_revocation_list(
+        ctx: &Context,
+        client: &SecureClient,
+        secure_channels: &SecureChannels,
+        issuer: &Identifier,
+        service_address: &str,
+    ) -> Result<()> {
+        debug!(%issuer, "retrieving the revocation list");
+        let revocation_list: RevocationListAndPurposeKey = client
+            .ask(ctx, service_address, Request::get("/"))
+            .await?
+            .success()?;
+
+        secure_channels
+            .receive_revocation_list(ctx, &[issuer.clone()], &revocation_list)
+            .await
+    }
+
+    /// Periodically retrieve the revocation list of the issuer.
+    /// The task only keeps a weak reference to this retriever, and stops once it is dropped
+    fn refresh_revocation_list_in_background(&self, service_address: String) {
+        let retriever: Weak<()> = Arc::downgrade(&self.revocation_list_refresh);
+        let ctx = self.ctx.clone();
+        let client = self.make_secure_client();
+        let secure_channels = self.secure_channels.clone();
+        let issuer = self.issuer_info.issuer.clone();
+        let refresh_interval = self.timing_options.revocation_list_refresh_interval;
+
+        ockam_node::spawn(async move {
+            let mut wait = refresh_interval;
+            while retriever.strong_count() > 0 {
+                match Self::get_revocation_list(
+                    &ctx,
+                    &client,
+                    &secure_channels,
+                    &issuer,
+                    &service_address,
+                )
+                .await
+                {
+                    Ok(()) => wait = refresh_interval,
+                    Err(err) => {
+                        wait = (wait * 2)
+                            .min(MAX_REVOCATION_LIST_REFRESH_INTERVAL.max(refresh_interval));
+                        warn!(%issuer, %err,
+                            "error refreshing the revocation list in the background, retrying in {} seconds",
+                            wait.as_secs());
+                    }
+                }
+                match now() {
+                    Ok(now) => ctx.sleep_long_until(*now + wait.as_secs()).await,
+                    Err(_) => ctx.sleep(wait).await,
+                }
+            }
+            debug!(%issuer, "the credential retriever was dropped, stopping the revocation list refresh");
+        });
+    }
 }
@@ -0,0 +1,166 @@
+use ockam_core::compat::collections::{BTreeMap, BTreeSet};
+use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::compat::vec::Vec;
+
+use crate::models::{CredentialHash, Identifier, RevocationListData};
+
+/// Cache of the latest [`crate::models::RevocationList`] received from each Authority.
+///
+/// It is used to reject revoked credentials and to find the identities which presented
+/// a credential that was revoked afterwards.
+#[derive(Clone, Default)]
+pub struct RevocationLists {
+    state: Arc<RwLock<RevocationListsState>>,
+}
+
+#[derive(Default)]
+struct RevocationListsState {
+    /// Latest revocation list for each Authority
+    lists: BTreeMap<Identifier, AuthorityRevocationList>,
+    /// Hash of the last credential presented by a subject, for each Authority
+    presented_credentials: BTreeMap<(Identifier, Identifier), CredentialHash>,
+}
+
+struct AuthorityRevocationList {
+    version: u64,
+    revoked_credentials: BTreeSet<CredentialHash>,
+    revoked_identifiers: BTreeSet<Identifier>,
+}
+
+impl AuthorityRevocationList {
+    fn is_revoked(&self, subject: &Identifier, credential_hash: &CredentialHash) -> bool {
+        self.revoked_identifiers.contains(subject)
+            || self.revoked_credentials.contains(credential_hash)
+    }
+}
+
+impl RevocationLists {
+    /// Version of the latest revocation list received from an Authority
+    pub fn version(&self, authority: &Identifier) -> Option<u64> {
+        self.state
+            .read()
+            .unwrap()
+            .lists
+            .get(authority)
+            .map(|list| list.version)
+    }
+
+    /// Return true if a credential issued by `authority` to `subject` was revoked
+    pub fn is_revoked(
+        &self,
+        authority: &Identifier,
+        subject: &Identifier,
+        credential_hash: &CredentialHash,
+    ) -> bool {
+        self.state
+            .read()
+            .unwrap()
+            .lists
+            .get(authority)
+            .map(|list| list.is_revoked(subject, credential_hash))
+            .unwrap_or(false)
+    }
+
+    /// Replace the revocation list of an Authority if the new list has a greater version.
+    /// Return `None` if the list was ignored, otherwise the subjects whose last presented
+    /// credential is revoked
+    pub(crate) fn update(
+        &self,
+        authority: &Identifier,
+        data: RevocationListData,
+    ) -> Option<Vec<Identifier>> {
+        let mut state = self.state.write().unwrap();
+
+        if let Some(current) = state.lists.get(authority) {
+            if current.version >= data.version {
+                return None;
+            }
+        }
+
+        let list = AuthorityRevocationList {
+            version: data.version,
+            revoked_credentials: data.revoked_credentials.into_iter().collect(),
+            revoked_identifiers: data.revoked_identifiers.into_iter().collect(),
+        };
+
+        let mut revoked_subjects: BTreeSet<Identifier> = list.revoked_identifiers.clone();
+        revoked_subjects.extend(
+            state
+                .presented_credentials
+                .iter()
+                .filter(|((issuer, _), hash)| {
+                    issuer == authority && list.revoked_credentials.contains(hash)
+                })
+                .map(|((_, subject), _)| subject.clone()),
+        );
+
+        state.lists.insert(authority.clone(), list);
+
+        Some(revoked_subjects.into_iter().collect())
+    }
+
+    /// Remember the last credential presented by a subject
+    pub(crate) fn record_presented_credential(
+        &self,
+        authority: &Identifier,
+        subject: &Identifier,
+        credential_hash: CredentialHash,
+    ) {
+        self.state
+            .write()
+            .unwrap()
+            .presented_credentials
+            .insert((authority.clone(), subject.clone()), credential_hash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TimestampInSeconds;
+
+    #[test]
+    fn test_revocation_lists() {
+        let authority = Identifier([1u8; 32]);
+        let other_authority = Identifier([2u8; 32]);
+        let alice = Identifier([3u8; 32]);
+        let bob = Identifier([4u8; 32]);
+        let alice_credential = CredentialHash([5u8; 32]);
+        let bob_credential = CredentialHash([6u8; 32]);
+
+        let lists = RevocationLists::default();
+        lists.record_presented_credential(&authority, &alice, alice_credential.clone());
+        lists.record_presented_credential(&authority, &bob, bob_credential.clone());
+        assert!(!lists.is_revoked(&authority, &alice, &alice_credential));
+        assert_eq!(lists.version(&authority), None);
+
+        let revoked = lists.update(&authority, list(1, vec![alice_credential.clone()], vec![]));
+        assert_eq!(revoked, Some(vec![alice.clone()]));
+        assert_eq!(lists.version(&authority), Some(1));
+        assert!(lists.is_revoked(&authority, &alice, &alice_credential));
+        assert!(!lists.is_revoked(&authority, &bob, &bob_credential));
+        assert!(!lists.is_revoked(&other_authority, &alice, &alice_credential));
+
+        // older or identical versions are ignored
+        assert_eq!(lists.update(&authority, list(1, vec![], vec![])), None);
+        assert!(lists.is_revoked(&authority, &alice, &alice_credential));
+
+        let revoked = lists.update(&authority, list(2, vec![], vec![bob.clone()]));
+        assert_eq!(revoked, Some(vec![bob.clone()]));
+        assert!(!lists.is_revoked(&authority, &alice, &alice_credential));
+        assert!(lists.is_revoked(&authority, &bob, &CredentialHash([0u8; 32])));
+    }
+
+    fn list(
+        version: u64,
+        revoked_credentials: Vec<CredentialHash>,
+        revoked_identifiers: Vec<Identifier>,
+    ) -> RevocationListData {
+        RevocationListData {
+            version,
+            revoked_credentials,
+            revoked_identifiers,
+            created_at: TimestampInSeconds(0),
+        }
+    }
+}
@@ -62,14 +62,38 @@ pub enum IdentityError {
     AddressIsNotSubscribedForThatCredentialRetriever,
     /// Credential retriever couldn't return a credential
     NoCredential,
-    /// Persistence is currently only supported for key exchange only channels
-    PersistentSupportIsLimited,
+    /// A persisted Secure Channel can't be resumed with the given role or options
+    PersistentSecureChannelCannotBeResumed,
     /// Secure Channel not found in the storage
     PersistentSecureChannelNotFound,
     /// Unknown Secure Channel Role value
     UnknownRole,
     /// Handshake ended up in an internal invalid state
     HandshakeInternalError,
+    /// The padding of a Secure Channel message doesn't match the expected padding policy
+    InvalidPadding,
+    /// The other party of a Secure Channel uses a different padding policy
+    PaddingPolicyMismatch,
+    /// Unknown Revocation List version
+    UnknownRevocationListVersion,
+    /// Invalid data_type value for Revocation List
+    InvalidRevocationListDataType,
+    /// Revocation List verification failed
+    RevocationListVerificationFailed,
+    /// Invalid Credential hash
+    InvalidCredentialHash(String),
+    /// The Credential was revoked by its Authority
+    CredentialRevoked,
+    /// The threshold of a multi-key Change is 0 or greater than its number of keys
+    InvalidChangeThreshold,
+    /// The recovery key is invalid or doesn't match the hash committed in the Identity
+    InvalidRecoveryKey,
+    /// A Change has more keys than [`crate::models::MAX_CHANGE_KEYS`]
+    TooManyChangeKeys,
+    /// The same key is listed several times in a Change
+    DuplicateChangeKey,
+    /// A key signing a Change doesn't belong to the previous Change
+    UnknownChangeKey,
 }
 
 impl ockam_core::compat::error::Error for IdentityError {}
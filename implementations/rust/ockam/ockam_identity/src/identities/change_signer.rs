@@ -0,0 +1,46 @@
+use core::fmt::{Debug, Formatter};
+
+use ockam_core::compat::sync::Arc;
+use ockam_core::Result;
+use ockam_vault::{SigningSecretKeyHandle, VaultForSigning, VerifyingPublicKey};
+
+/// Key of a multi-key Identity stored outside of the Identity vault.
+///
+/// The keys of a multi-key Identity can be held by different custodians, each with their own
+/// vault, for example a hardware token. Each custodian signs the new change with its own vault
+/// when the Identity is rotated.
+#[derive(Clone)]
+pub struct ChangeSigner {
+    vault: Arc<dyn VaultForSigning>,
+    handle: SigningSecretKeyHandle,
+}
+
+impl Debug for ChangeSigner {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ChangeSigner")
+            .field("handle", &self.handle)
+            .finish()
+    }
+}
+
+impl ChangeSigner {
+    /// Create a signer from a key stored in a vault
+    pub fn new(vault: Arc<dyn VaultForSigning>, handle: SigningSecretKeyHandle) -> Self {
+        Self { vault, handle }
+    }
+
+    /// Vault storing the key
+    pub fn vault(&self) -> Arc<dyn VaultForSigning> {
+        self.vault.clone()
+    }
+
+    /// Handle of the key in its vault
+    pub fn handle(&self) -> &SigningSecretKeyHandle {
+        &self.handle
+    }
+
+    /// Public key of the signer
+    pub async fn public_key(&self) -> Result<VerifyingPublicKey> {
+        self.vault.get_verifying_public_key(&self.handle).await
+    }
+}
@@ -21,7 +21,7 @@ use crate::purpose_keys::storage::PurposeKeysSqlxDatabase;
 use crate::IdentitiesBuilder;
 use crate::{
     Credentials, Identifier, IdentitiesCreation, IdentitiesVerification, Identity,
-    IdentityAttributesRepository, PurposeKeys, Vault,
+    IdentityAttributesRepository, PurposeKeys, RevocationLists, Vault,
 };
 
 /// This struct supports all the services related to identities
@@ -32,6 +32,7 @@ pub struct Identities {
     identity_attributes_repository: Arc<dyn IdentityAttributesRepository>,
     purpose_keys_repository: Arc<dyn PurposeKeysRepository>,
     cached_credentials_repository: Arc<dyn CredentialRepository>,
+    revocation_lists: RevocationLists,
 }
 
 impl Identities {
@@ -55,6 +56,11 @@ impl Identities {
         self.cached_credentials_repository.clone()
     }
 
+    /// Return the revocation lists received from the authorities
+    pub fn revocation_lists(&self) -> RevocationLists {
+        self.revocation_lists.clone()
+    }
+
     /// Get an [`Identity`] from the repository
     pub async fn get_identity(&self, identifier: &Identifier) -> Result<Identity> {
         self.identities_verification()
@@ -124,6 +130,7 @@ impl Identities {
             self.purpose_keys(),
             self.identities_creation().clone(),
             self.identity_attributes_repository.clone(),
+            self.revocation_lists.clone(),
         ))
     }
 }
@@ -143,6 +150,7 @@ impl Identities {
             identity_attributes_repository,
             purpose_keys_repository,
             cached_credentials_repository,
+            revocation_lists: Default::default(),
         }
     }
 
@@ -1,10 +1,11 @@
 use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
 use ockam_core::Result;
-use ockam_vault::{SigningKeyType, SigningSecretKeyHandle};
+use ockam_vault::{SigningKeyType, SigningSecretKeyHandle, VerifyingPublicKey};
 
 use crate::models::TimestampInSeconds;
 use crate::utils::now;
-use crate::IdentityOptions;
+use crate::{ChangeSigner, IdentityOptions, RecoveryKey};
 use crate::{Identifier, IdentitiesCreation};
 
 /// Default TTL for an Identity key
@@ -29,6 +30,12 @@ pub struct IdentityBuilder {
 
     revoke_all_purpose_keys: bool,
     key: Key,
+    additional_keys: Vec<Key>,
+    additional_signers: Vec<ChangeSigner>,
+    previous_signers: Vec<ChangeSigner>,
+    threshold: Option<u8>,
+    recovery_public_key: Option<VerifyingPublicKey>,
+    recovery_key: Option<RecoveryKey>,
     ttl: Ttl,
 }
 
@@ -39,6 +46,12 @@ impl IdentityBuilder {
             identities_creation,
             revoke_all_purpose_keys: false,
             key: Key::Generate(SigningKeyType::EdDSACurve25519),
+            additional_keys: vec![],
+            additional_signers: vec![],
+            previous_signers: vec![],
+            threshold: None,
+            recovery_public_key: None,
+            recovery_key: None,
             ttl: Ttl::CreatedNowWithTtl(DEFAULT_IDENTITY_TTL),
         }
     }
@@ -55,6 +68,52 @@ impl IdentityBuilder {
         self
     }
 
+    /// Add an existing key to the keys which can sign the Identity changes
+    pub fn with_additional_existing_key(
+        mut self,
+        signing_secret_key_handle: SigningSecretKeyHandle,
+    ) -> Self {
+        self.additional_keys
+            .push(Key::Existing(signing_secret_key_handle));
+        self
+    }
+
+    /// Add a fresh key with the given type to the keys which can sign the Identity changes
+    pub fn with_additional_random_key(mut self, key_type: SigningKeyType) -> Self {
+        self.additional_keys.push(Key::Generate(key_type));
+        self
+    }
+
+    /// Add a key stored in another vault to the keys which can sign the Identity changes
+    pub fn with_additional_signer(mut self, additional_signer: ChangeSigner) -> Self {
+        self.additional_signers.push(additional_signer);
+        self
+    }
+
+    /// Sign the change with a key of the previous change stored in another vault
+    pub fn with_previous_signer(mut self, previous_signer: ChangeSigner) -> Self {
+        self.previous_signers.push(previous_signer);
+        self
+    }
+
+    /// Set the number of keys required to sign the Identity changes (1 by default)
+    pub fn with_threshold(mut self, threshold: u8) -> Self {
+        self.threshold = Some(threshold);
+        self
+    }
+
+    /// Commit the hash of a recovery key which can approve the next change on its own
+    pub fn with_recovery_public_key(mut self, recovery_public_key: VerifyingPublicKey) -> Self {
+        self.recovery_public_key = Some(recovery_public_key);
+        self
+    }
+
+    /// Sign the change with the recovery key committed in the previous change
+    pub fn with_recovery_key(mut self, recovery_key: RecoveryKey) -> Self {
+        self.recovery_key = Some(recovery_key);
+        self
+    }
+
     /// Set attestations_valid_from and attestations_valid_until timestamps
     pub fn with_timestamps(
         mut self,
@@ -82,15 +141,13 @@ impl IdentityBuilder {
 
     /// Create the corresponding [`IdentityOptions`] object
     pub async fn build_options(self) -> Result<IdentityOptions> {
-        let key = match self.key {
-            Key::Generate(stype) => {
-                self.identities_creation
-                    .identity_vault
-                    .generate_signing_secret_key(stype)
-                    .await?
-            }
-            Key::Existing(signing_secret_key_handle) => signing_secret_key_handle,
-        };
+        let key = Self::get_or_generate_key(&self.identities_creation, self.key).await?;
+
+        let mut additional_keys = Vec::with_capacity(self.additional_keys.len());
+        for additional_key in self.additional_keys {
+            additional_keys
+                .push(Self::get_or_generate_key(&self.identities_creation, additional_key).await?);
+        }
 
         let (attestations_valid_from, attestations_valid_until) = match self.ttl {
             Ttl::CreatedNowWithTtl(ttl) => {
@@ -105,16 +162,52 @@ impl IdentityBuilder {
             } => (attestations_valid_from, attestations_valid_until),
         };
 
-        let options = IdentityOptions::new(
+        let mut options = IdentityOptions::new(
             key,
             self.revoke_all_purpose_keys,
             attestations_valid_from,
             attestations_valid_until,
         );
 
+        if !additional_keys.is_empty()
+            || !self.additional_signers.is_empty()
+            || self.threshold.is_some()
+        {
+            options = options
+                .with_additional_keys(additional_keys, self.threshold.unwrap_or(1))
+                .with_additional_signers(self.additional_signers);
+        }
+
+        if !self.previous_signers.is_empty() {
+            options = options.with_previous_signers(self.previous_signers);
+        }
+
+        if let Some(recovery_public_key) = self.recovery_public_key {
+            options = options.with_recovery_public_key(recovery_public_key);
+        }
+
+        if let Some(recovery_key) = self.recovery_key {
+            options = options.with_recovery_key(recovery_key);
+        }
+
         Ok(options)
     }
 
+    async fn get_or_generate_key(
+        identities_creation: &IdentitiesCreation,
+        key: Key,
+    ) -> Result<SigningSecretKeyHandle> {
+        match key {
+            Key::Generate(stype) => {
+                identities_creation
+                    .identity_vault
+                    .generate_signing_secret_key(stype)
+                    .await
+            }
+            Key::Existing(signing_secret_key_handle) => Ok(signing_secret_key_handle),
+        }
+    }
+
     /// Create the corresponding [`Identity`]
     pub async fn build(self) -> Result<Identifier> {
         let identities_creation = self.identities_creation.clone();
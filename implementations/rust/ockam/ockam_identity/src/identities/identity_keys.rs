@@ -1,8 +1,12 @@
 use crate::identity::Identity;
-use crate::models::{Change, ChangeData, ChangeHash, ChangeHistory};
-use crate::{IdentityError, IdentityOptions};
+use crate::models::{
+    Change, ChangeData, ChangeHash, ChangeHistory, IndexedChangeSignature, RecoverySignature,
+    VersionedData, MAX_CHANGE_KEYS, MULTI_KEY_CHANGE_VERSION,
+};
+use crate::{compute_recovery_key_hash, ChangeSigner, IdentityError, IdentityOptions};
 
 use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
 use ockam_core::Result;
 
 use ockam_vault::{SigningSecretKeyHandle, VaultForSigning, VaultForVerifyingSignatures};
@@ -14,6 +18,14 @@ pub struct IdentitiesKeys {
     verifying_vault: Arc<dyn VaultForVerifyingSignatures>,
 }
 
+/// Previous change of an Identity, approving a new change
+struct PreviousChange {
+    change_hash: ChangeHash,
+    version: u8,
+    /// Keys of the previous change signing the new change, with their index
+    signers: Vec<(usize, ChangeSigner)>,
+}
+
 impl IdentitiesKeys {
     pub(crate) async fn create_initial_key(&self, options: IdentityOptions) -> Result<Identity> {
         let change = self.make_change(options, None).await?;
@@ -47,19 +59,64 @@ impl IdentitiesKeys {
     pub async fn rotate_key_with_options(
         &self,
         identity: Identity,
-        options: IdentityOptions,
+        mut options: IdentityOptions,
     ) -> Result<Identity> {
-        let last_change = match identity.changes().last() {
-            Some(last_change) => last_change,
-            None => return Err(IdentityError::EmptyIdentity)?,
+        let (last_change, last_raw_change) = match (
+            identity.changes().last(),
+            identity.change_history().0.last(),
+        ) {
+            (Some(last_change), Some(last_raw_change)) => (last_change, last_raw_change),
+            _ => return Err(IdentityError::EmptyIdentity)?,
         };
+        let last_versioned_data: VersionedData = minicbor::decode(&last_raw_change.data)?;
+
+        // The keys of the previous change found in the Identity vault, and the keys
+        // held by other custodians, sign the new change
+        let last_secret_keys = self.get_secret_keys(&identity).await?;
+        let mut signers: Vec<(usize, ChangeSigner)> = last_secret_keys
+            .iter()
+            .map(|(key_index, secret_key)| {
+                (
+                    *key_index,
+                    ChangeSigner::new(self.identity_vault.clone(), secret_key.clone()),
+                )
+            })
+            .collect();
+        for signer in core::mem::take(&mut options.previous_signers) {
+            let public_key = signer.public_key().await?;
+            let key_index = match last_change
+                .public_keys()
+                .iter()
+                .position(|k| k == &public_key)
+            {
+                Some(key_index) => key_index,
+                None => return Err(IdentityError::UnknownChangeKey)?,
+            };
+            if signers.iter().all(|(index, _)| *index != key_index) {
+                signers.push((key_index, signer));
+            }
+        }
 
-        let last_secret_key = self.get_secret_key(&identity).await?;
+        if let Some(recovery_key) = &options.recovery_key {
+            // The recovery key approves the change on its own
+            if last_change.data().recovery_key_hash
+                != Some(recovery_key.hash(self.verifying_vault.clone()).await?)
+            {
+                return Err(IdentityError::InvalidRecoveryKey)?;
+            }
+        } else if signers.len() < last_change.threshold() {
+            // Not enough keys to approve the change
+            return Err(IdentityError::IdentityVerificationFailed)?;
+        }
 
         let change = self
             .make_change(
                 options,
-                Some((last_change.change_hash().clone(), last_secret_key.clone())),
+                Some(PreviousChange {
+                    change_hash: last_change.change_hash().clone(),
+                    version: last_versioned_data.version,
+                    signers,
+                }),
             )
             .await?;
 
@@ -67,16 +124,19 @@ impl IdentitiesKeys {
             .add_change(change, self.verifying_vault.clone())
             .await?;
 
-        if self
-            .identity_vault
-            .delete_signing_secret_key(last_secret_key)
-            .await
-            .is_err()
-        {
-            error!(
-                "Error deleting old Identity Key for {}",
-                identity.identifier()
-            );
+        // Only the keys of the Identity vault are deleted
+        for (_, last_secret_key) in last_secret_keys {
+            if self
+                .identity_vault
+                .delete_signing_secret_key(last_secret_key)
+                .await
+                .is_err()
+            {
+                error!(
+                    "Error deleting old Identity Key for {}",
+                    identity.identifier()
+                );
+            }
         }
 
         Ok(identity)
@@ -92,6 +152,36 @@ impl IdentitiesKeys {
             Err(IdentityError::EmptyIdentity)?
         }
     }
+
+    /// Return the secret keys of an identity which are present in the vault,
+    /// with their index in the list of keys of the last change
+    pub async fn get_secret_keys(
+        &self,
+        identity: &Identity,
+    ) -> Result<Vec<(usize, SigningSecretKeyHandle)>> {
+        let last_change = match identity.changes().last() {
+            Some(last_change) => last_change,
+            None => return Err(IdentityError::EmptyIdentity)?,
+        };
+
+        let mut secret_keys = vec![];
+        for (key_index, public_key) in last_change.public_keys().iter().enumerate() {
+            let secret_key = match self.identity_vault.get_secret_key_handle(public_key).await {
+                Ok(secret_key) => secret_key,
+                Err(_) => continue,
+            };
+            // Some vaults return a handle even when the key is not present
+            if self
+                .identity_vault
+                .get_verifying_public_key(&secret_key)
+                .await
+                .is_ok()
+            {
+                secret_keys.push((key_index, secret_key));
+            }
+        }
+        Ok(secret_keys)
+    }
 }
 
 /// Private  functions
@@ -100,27 +190,81 @@ impl IdentitiesKeys {
     async fn make_change(
         &self,
         identity_options: IdentityOptions,
-        previous: Option<(ChangeHash, SigningSecretKeyHandle)>,
+        previous: Option<PreviousChange>,
     ) -> Result<Change> {
         let secret_key = identity_options.signing_secret_key_handle;
         let public_key = self
             .identity_vault
             .get_verifying_public_key(&secret_key)
             .await?;
-        let (previous_change, previous_key) = previous
-            .map(|(x, y)| (Some(x), Some(y)))
-            .unwrap_or((None, None));
+
+        let mut additional_signers: Vec<ChangeSigner> = identity_options
+            .additional_signing_secret_key_handles
+            .into_iter()
+            .map(|secret_key| ChangeSigner::new(self.identity_vault.clone(), secret_key))
+            .collect();
+        additional_signers.extend(identity_options.additional_signers);
+        if additional_signers.len() >= MAX_CHANGE_KEYS {
+            return Err(IdentityError::TooManyChangeKeys)?;
+        }
+        let mut additional_public_keys = Vec::with_capacity(additional_signers.len());
+        for additional_signer in additional_signers.iter() {
+            additional_public_keys.push(additional_signer.public_key().await?.into());
+        }
+
+        let (previous_change, previous_version, previous_signers) = match previous {
+            Some(previous) => (
+                Some(previous.change_hash),
+                previous.version,
+                previous.signers,
+            ),
+            None => (None, 1, vec![]),
+        };
+
+        if previous_change.is_none() && identity_options.recovery_key.is_some() {
+            // There is no previous change committing a recovery key
+            return Err(IdentityError::InvalidRecoveryKey)?;
+        }
+
+        let recovery_key_hash = match identity_options.recovery_public_key {
+            Some(recovery_public_key) => Some(
+                compute_recovery_key_hash(
+                    &recovery_public_key.into(),
+                    self.verifying_vault.clone(),
+                )
+                .await?,
+            ),
+            None => None,
+        };
         let change_data = ChangeData {
             previous_change,
             primary_public_key: public_key.into(),
             revoke_all_purpose_keys: identity_options.revoke_all_purpose_keys,
             attestations_valid_from: identity_options.attestations_valid_from,
             attestations_valid_until: identity_options.attestations_valid_until,
+            additional_public_keys: if additional_public_keys.is_empty() {
+                None
+            } else {
+                Some(additional_public_keys)
+            },
+            threshold: identity_options.threshold,
+            recovery_key_hash,
         };
 
+        // Fail early if the threshold can't be reached with the given keys
+        change_data.threshold()?;
+
+        // Once an Identity uses several keys, its history can only be verified
+        // by the verifiers supporting multi-key changes
+        let version = if change_data.is_multi_key() || previous_version == MULTI_KEY_CHANGE_VERSION
+        {
+            MULTI_KEY_CHANGE_VERSION
+        } else {
+            1
+        };
         let change_data = ockam_core::cbor_encode_preallocate(&change_data)?;
 
-        let versioned_data = Change::create_versioned_data(change_data);
+        let versioned_data = Change::create_versioned_data_with_version(version, change_data);
         let versioned_data = ockam_core::cbor_encode_preallocate(&versioned_data)?;
 
         let hash = self.verifying_vault.sha256(&versioned_data).await?;
@@ -128,13 +272,47 @@ impl IdentitiesKeys {
         let self_signature = self.identity_vault.sign(&secret_key, &hash.0).await?;
         let self_signature = self_signature.into();
 
-        // If we have previous_key passed we should sign using it
-        // If there is no previous_key - we're creating new identity, so we just generated the key
-        let previous_signature = match previous_key {
-            Some(previous_key) => {
-                let previous_signature = self.identity_vault.sign(&previous_key, &hash.0).await?;
+        let mut additional_signatures = Vec::with_capacity(additional_signers.len());
+        for (i, additional_signer) in additional_signers.iter().enumerate() {
+            let signature = additional_signer
+                .vault()
+                .sign(additional_signer.handle(), &hash.0)
+                .await?;
+            additional_signatures.push(IndexedChangeSignature {
+                key_index: key_index(i + 1)?,
+                signature: signature.into(),
+            });
+        }
+
+        // If we have previous signers passed we should sign using them
+        // If there are no previous signers - we're creating new identity, so we just generated the keys
+        let mut previous_signature = None;
+        let mut additional_previous_signatures = vec![];
+        for (index, previous_signer) in previous_signers {
+            let signature = previous_signer
+                .vault()
+                .sign(previous_signer.handle(), &hash.0)
+                .await?;
+            if index == 0 {
+                previous_signature = Some(signature.into());
+            } else {
+                additional_previous_signatures.push(IndexedChangeSignature {
+                    key_index: key_index(index)?,
+                    signature: signature.into(),
+                });
+            }
+        }
 
-                Some(previous_signature.into())
+        let recovery_signature = match identity_options.recovery_key {
+            Some(recovery_key) => {
+                let signature = recovery_key
+                    .vault()
+                    .sign(recovery_key.handle(), &hash.0)
+                    .await?;
+                Some(RecoverySignature {
+                    public_key: recovery_key.public_key().await?.into(),
+                    signature: signature.into(),
+                })
             }
             None => None,
         };
@@ -143,12 +321,31 @@ impl IdentitiesKeys {
             data: versioned_data,
             signature: self_signature,
             previous_signature,
+            additional_signatures: if additional_signatures.is_empty() {
+                None
+            } else {
+                Some(additional_signatures)
+            },
+            additional_previous_signatures: if additional_previous_signatures.is_empty() {
+                None
+            } else {
+                Some(additional_previous_signatures)
+            },
+            recovery_signature,
         };
 
         Ok(change)
     }
 }
 
+/// Convert the index of a key of a change, which is bounded by [`MAX_CHANGE_KEYS`]
+fn key_index(index: usize) -> Result<u8> {
+    if index >= MAX_CHANGE_KEYS {
+        return Err(IdentityError::TooManyChangeKeys.into());
+    }
+    u8::try_from(index).map_err(|_| IdentityError::TooManyChangeKeys.into())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1,5 +1,6 @@
-use crate::TimestampInSeconds;
-use ockam_vault::SigningSecretKeyHandle;
+use crate::{ChangeSigner, RecoveryKey, TimestampInSeconds};
+use ockam_core::compat::vec::Vec;
+use ockam_vault::{SigningSecretKeyHandle, VerifyingPublicKey};
 
 /// Options to create an Identity key
 pub struct IdentityOptions {
@@ -7,6 +8,12 @@ pub struct IdentityOptions {
     pub(super) revoke_all_purpose_keys: bool,
     pub(super) attestations_valid_from: TimestampInSeconds,
     pub(super) attestations_valid_until: TimestampInSeconds,
+    pub(super) additional_signing_secret_key_handles: Vec<SigningSecretKeyHandle>,
+    pub(super) additional_signers: Vec<ChangeSigner>,
+    pub(super) previous_signers: Vec<ChangeSigner>,
+    pub(super) threshold: Option<u8>,
+    pub(super) recovery_public_key: Option<VerifyingPublicKey>,
+    pub(super) recovery_key: Option<RecoveryKey>,
 }
 
 impl IdentityOptions {
@@ -22,9 +29,55 @@ impl IdentityOptions {
             revoke_all_purpose_keys,
             attestations_valid_from,
             attestations_valid_until,
+            additional_signing_secret_key_handles: vec![],
+            additional_signers: vec![],
+            previous_signers: vec![],
+            threshold: None,
+            recovery_public_key: None,
+            recovery_key: None,
         }
     }
 
+    /// Add keys which, together with the new key, can approve the next change.
+    /// `threshold` is the number of keys required to sign the changes
+    pub fn with_additional_keys(
+        mut self,
+        additional_signing_secret_key_handles: Vec<SigningSecretKeyHandle>,
+        threshold: u8,
+    ) -> Self {
+        self.additional_signing_secret_key_handles = additional_signing_secret_key_handles;
+        self.threshold = Some(threshold);
+        self
+    }
+
+    /// Add keys stored in other vaults which, together with the new key and the additional
+    /// keys, can approve the next change. The threshold is set with [`Self::with_additional_keys`]
+    pub fn with_additional_signers(mut self, additional_signers: Vec<ChangeSigner>) -> Self {
+        self.additional_signers = additional_signers;
+        self
+    }
+
+    /// Sign the new change with keys of the previous change stored in other vaults,
+    /// in addition to the keys of the previous change found in the Identity vault
+    pub fn with_previous_signers(mut self, previous_signers: Vec<ChangeSigner>) -> Self {
+        self.previous_signers = previous_signers;
+        self
+    }
+
+    /// Commit the hash of a recovery key in the new change,
+    /// so that this recovery key can approve the next change on its own
+    pub fn with_recovery_public_key(mut self, recovery_public_key: VerifyingPublicKey) -> Self {
+        self.recovery_public_key = Some(recovery_public_key);
+        self
+    }
+
+    /// Sign the new change with the recovery key committed in the previous change.
+    /// The keys of the previous change are then not needed
+    pub fn with_recovery_key(mut self, recovery_key: RecoveryKey) -> Self {
+        self.recovery_key = Some(recovery_key);
+        self
+    }
+
     /// New key
     pub fn signing_secret_key_handle(&self) -> &SigningSecretKeyHandle {
         &self.signing_secret_key_handle
@@ -44,4 +97,34 @@ impl IdentityOptions {
     pub fn attestations_valid_until(&self) -> TimestampInSeconds {
         self.attestations_valid_until
     }
+
+    /// Additional keys
+    pub fn additional_signing_secret_key_handles(&self) -> &[SigningSecretKeyHandle] {
+        &self.additional_signing_secret_key_handles
+    }
+
+    /// Additional keys stored in other vaults
+    pub fn additional_signers(&self) -> &[ChangeSigner] {
+        &self.additional_signers
+    }
+
+    /// Keys of the previous change stored in other vaults
+    pub fn previous_signers(&self) -> &[ChangeSigner] {
+        &self.previous_signers
+    }
+
+    /// Number of keys required to sign the changes, 1 if not set
+    pub fn threshold(&self) -> Option<u8> {
+        self.threshold
+    }
+
+    /// Public key of the recovery key committed in the new change
+    pub fn recovery_public_key(&self) -> Option<&VerifyingPublicKey> {
+        self.recovery_public_key.as_ref()
+    }
+
+    /// Recovery key used to sign the new change
+    pub fn recovery_key(&self) -> Option<&RecoveryKey> {
+        self.recovery_key.as_ref()
+    }
 }
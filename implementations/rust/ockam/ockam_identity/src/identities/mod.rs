@@ -1,3 +1,4 @@
+mod change_signer;
 #[allow(clippy::module_inception)]
 mod identities;
 mod identities_attributes;
@@ -7,8 +8,10 @@ mod identities_verification;
 mod identity_builder;
 mod identity_keys;
 mod identity_options;
+mod recovery_key;
 mod storage;
 
+pub use change_signer::*;
 pub use identities::*;
 pub use identities_attributes::*;
 pub use identities_builder::*;
@@ -17,4 +20,5 @@ pub use identities_verification::*;
 pub use identity_builder::*;
 pub use identity_keys::*;
 pub use identity_options::*;
+pub use recovery_key::*;
 pub use storage::*;
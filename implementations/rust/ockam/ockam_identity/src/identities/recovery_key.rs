@@ -0,0 +1,106 @@
+use core::fmt::{Debug, Formatter};
+
+use ockam_core::compat::string::String;
+use ockam_core::compat::sync::Arc;
+use ockam_core::Result;
+#[cfg(feature = "storage")]
+use ockam_vault::{EdDSACurve25519SecretKey, SigningSecret, SoftwareVaultForSigning};
+use ockam_vault::{
+    SigningSecretKeyHandle, VaultForSigning, VaultForVerifyingSignatures, VerifyingPublicKey,
+};
+
+use crate::models::{PrimaryPublicKey, RecoveryKeyHash};
+#[cfg(feature = "storage")]
+use crate::IdentityError;
+
+/// Offline key which can approve the next change of an Identity on its own.
+///
+/// The hash of its public key is committed in a [`crate::models::ChangeData`] when the Identity
+/// is created or rotated, while its secret is kept outside of the Identity vault,
+/// for example in a file or on paper. If the Identity vault is lost, the recovery key can then
+/// sign a new change for the same [`crate::Identifier`].
+#[derive(Clone)]
+pub struct RecoveryKey {
+    vault: Arc<dyn VaultForSigning>,
+    handle: SigningSecretKeyHandle,
+}
+
+impl Debug for RecoveryKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RecoveryKey")
+            .field("handle", &self.handle)
+            .finish()
+    }
+}
+
+impl RecoveryKey {
+    /// Create a recovery key from a key stored in a vault
+    pub fn new(vault: Arc<dyn VaultForSigning>, handle: SigningSecretKeyHandle) -> Self {
+        Self { vault, handle }
+    }
+
+    /// Generate a new EdDSACurve25519 recovery key in an in-memory vault.
+    /// Return the recovery key and its secret, hex-encoded, which must be stored offline
+    #[cfg(feature = "storage")]
+    pub async fn generate() -> Result<(Self, String)> {
+        let secret: [u8; 32] = ockam_core::compat::rand::random();
+        let exported = hex::encode(secret);
+        Ok((Self::import(&exported).await?, exported))
+    }
+
+    /// Import a recovery key from its hex-encoded secret into an in-memory vault
+    #[cfg(feature = "storage")]
+    pub async fn import(secret: &str) -> Result<Self> {
+        let secret: [u8; 32] = hex::decode(secret.trim())
+            .map_err(|_| IdentityError::InvalidRecoveryKey)?
+            .try_into()
+            .map_err(|_| IdentityError::InvalidRecoveryKey)?;
+        let vault = SoftwareVaultForSigning::create().await?;
+        let handle = vault
+            .import_key(SigningSecret::EdDSACurve25519(
+                EdDSACurve25519SecretKey::new(secret),
+            ))
+            .await?;
+        Ok(Self::new(vault, handle))
+    }
+
+    /// Vault storing the recovery key
+    pub fn vault(&self) -> Arc<dyn VaultForSigning> {
+        self.vault.clone()
+    }
+
+    /// Handle of the recovery key in its vault
+    pub fn handle(&self) -> &SigningSecretKeyHandle {
+        &self.handle
+    }
+
+    /// Public key of the recovery key
+    pub async fn public_key(&self) -> Result<VerifyingPublicKey> {
+        self.vault.get_verifying_public_key(&self.handle).await
+    }
+
+    /// Hash of the recovery key, as committed in an Identity change
+    pub async fn hash(
+        &self,
+        verifying_vault: Arc<dyn VaultForVerifyingSignatures>,
+    ) -> Result<RecoveryKeyHash> {
+        compute_recovery_key_hash(&self.public_key().await?.into(), verifying_vault).await
+    }
+}
+
+/// Compute the [`RecoveryKeyHash`] of a public key
+pub async fn compute_recovery_key_hash(
+    public_key: &PrimaryPublicKey,
+    verifying_vault: Arc<dyn VaultForVerifyingSignatures>,
+) -> Result<RecoveryKeyHash> {
+    let public_key = ockam_core::cbor_encode_preallocate(public_key)?;
+    let hash = verifying_vault.sha256(&public_key).await?;
+    Ok(RecoveryKeyHash(hash.0))
+}
+
+impl RecoveryKeyHash {
+    /// Hex representation of the hash
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+}
@@ -23,6 +23,10 @@ pub trait IdentityAttributesRepository: Send + Sync + 'static {
 
     /// Remove all expired attributes
     async fn delete_expired_attributes(&self, now: TimestampInSeconds) -> Result<()>;
+
+    /// Remove the attributes attested by the given authority for the given identity identifier
+    async fn delete_attributes(&self, subject: &Identifier, attested_by: &Identifier)
+        -> Result<()>;
 }
 
 #[cfg(feature = "std")]
@@ -43,4 +47,12 @@ impl<T: IdentityAttributesRepository> IdentityAttributesRepository for AutoRetry
     async fn delete_expired_attributes(&self, now: TimestampInSeconds) -> Result<()> {
         retry!(self.wrapped.delete_expired_attributes(now))
     }
+
+    async fn delete_attributes(
+        &self,
+        subject: &Identifier,
+        attested_by: &Identifier,
+    ) -> Result<()> {
+        retry!(self.wrapped.delete_attributes(subject, attested_by))
+    }
 }
@@ -95,6 +95,20 @@ impl IdentityAttributesRepository for IdentityAttributesSqlxDatabase {
             .bind(&self.node_name);
         query.execute(&*self.database.pool).await.void()
     }
+
+    async fn delete_attributes(
+        &self,
+        subject: &Identifier,
+        attested_by: &Identifier,
+    ) -> Result<()> {
+        let query = query(
+            "DELETE FROM identity_attributes WHERE identifier = $1 AND attested_by = $2 AND node_name = $3",
+        )
+        .bind(subject)
+        .bind(attested_by)
+        .bind(&self.node_name);
+        query.execute(&*self.database.pool).await.void()
+    }
 }
 
 // Database serialization / deserialization
@@ -268,6 +282,51 @@ mod tests {
         .await
     }
 
+    #[tokio::test]
+    async fn test_delete_attributes() -> Result<()> {
+        with_dbs(|db| async move {
+            let repository: Arc<dyn IdentityAttributesRepository> =
+                Arc::new(IdentityAttributesSqlxDatabase::new(db, "node"));
+
+            let now = now()?;
+            let identifier1 = create_identity().await?;
+            let identifier2 = create_identity().await?;
+            let attributes1 = create_attributes_entry(&identifier1, now, None).await?;
+            let attributes2 = create_attributes_entry(&identifier2, now, None).await?;
+            repository
+                .put_attributes(&identifier1, attributes1.clone())
+                .await?;
+            repository
+                .put_attributes(&identifier2, attributes2.clone())
+                .await?;
+
+            // attributes are only deleted if they were attested by the given authority
+            repository
+                .delete_attributes(&identifier1, &identifier2)
+                .await?;
+            let result = repository
+                .get_attributes(&identifier1, &identifier1)
+                .await?;
+            assert_eq!(result, Some(attributes1));
+
+            repository
+                .delete_attributes(&identifier1, &identifier1)
+                .await?;
+            let result = repository
+                .get_attributes(&identifier1, &identifier1)
+                .await?;
+            assert_eq!(result, None);
+
+            let result = repository
+                .get_attributes(&identifier2, &identifier2)
+                .await?;
+            assert_eq!(result, Some(attributes2));
+
+            Ok(())
+        })
+        .await
+    }
+
     /// HELPERS
     async fn create_attributes_entry(
         identifier: &Identifier,
@@ -6,7 +6,9 @@ pub enum IdentityHistoryComparison {
     Equal,
     /// Some changes don't match between current identity and known identity
     Conflict,
-    /// Current identity is more recent than known identity
+    /// Current identity is more recent than known identity.
+    /// Since both identities are verified, each additional change was approved by
+    /// the threshold of keys of the previous change
     Newer,
     /// Known identity is more recent
     Older,
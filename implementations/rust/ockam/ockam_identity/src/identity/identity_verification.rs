@@ -1,7 +1,10 @@
-use crate::models::{Change, ChangeData, ChangeHash, ChangeSignature, VersionedData};
+use crate::models::{
+    Change, ChangeData, ChangeHash, ChangeSignature, IndexedChangeSignature, VersionedData,
+};
 use crate::verified_change::VerifiedChange;
-use crate::{Identity, IdentityError};
+use crate::{compute_recovery_key_hash, Identity, IdentityError};
 
+use ockam_core::compat::collections::BTreeSet;
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::vec::Vec;
 use ockam_core::Result;
@@ -94,10 +97,20 @@ impl Identity {
                 return Err(IdentityError::IdentityVerificationFailed)?;
             }
 
+            let threshold = change_details.change_data.threshold()?;
+            let public_keys = change_details
+                .change_data
+                .public_keys()
+                .into_iter()
+                .map(|k| k.into())
+                .collect();
+
             to_be_verified_changes.push(VerifiedChange::new(
                 change_details.change_data.clone(),
                 change_details.change_hash.clone(),
                 change_details.change_data.primary_public_key.clone().into(),
+                public_keys,
+                threshold,
             ));
 
             previous_change_details = Some(change_details);
@@ -140,6 +153,51 @@ impl Identity {
             .await
     }
 
+    /// Count the valid signatures made by distinct keys of a [`Change`].
+    /// The primary key signature has the index 0, additional signatures must use
+    /// an index in `1..public_keys.len()`. Any invalid signature fails the verification
+    async fn count_valid_signatures(
+        public_keys: &[VerifyingPublicKey],
+        hash: [u8; 32],
+        primary_signature: Option<&ChangeSignature>,
+        additional_signatures: Option<&Vec<IndexedChangeSignature>>,
+        vault: Arc<dyn VaultForVerifyingSignatures>,
+    ) -> Result<usize> {
+        let mut signatures = Vec::new();
+        if let Some(primary_signature) = primary_signature {
+            signatures.push((0usize, primary_signature));
+        }
+        for additional_signature in additional_signatures.into_iter().flatten() {
+            if additional_signature.key_index == 0 {
+                // The primary key signature has its own field
+                return Err(IdentityError::IdentityVerificationFailed)?;
+            }
+            signatures.push((
+                additional_signature.key_index as usize,
+                &additional_signature.signature,
+            ));
+        }
+
+        let mut signers = BTreeSet::new();
+        for (key_index, signature) in signatures {
+            let public_key = match public_keys.get(key_index) {
+                Some(public_key) => public_key,
+                None => return Err(IdentityError::IdentityVerificationFailed)?,
+            };
+
+            if !signers.insert(key_index) {
+                // The same key can't be counted twice
+                return Err(IdentityError::IdentityVerificationFailed)?;
+            }
+
+            if !Self::verify_change_signature(public_key, hash, signature, vault.clone()).await? {
+                return Err(IdentityError::IdentityVerificationFailed)?;
+            }
+        }
+
+        Ok(signers.len())
+    }
+
     /// WARNING: This function assumes all existing changes in chain are verified.
     /// WARNING: Correctness of changes sequence is not verified here.
     async fn verify_change_signatures(
@@ -150,35 +208,67 @@ impl Identity {
         let new_change_details = Self::get_change_details(new_change, vault.clone()).await?;
 
         if let Some(last_verified_change) = last_verified_change {
-            if let Some(previous_signature) = &new_change.previous_signature {
-                if !Self::verify_change_signature(
-                    last_verified_change.primary_public_key(),
-                    new_change_details.change_full_hash,
-                    previous_signature,
-                    vault.clone(),
-                )
-                .await?
-                {
-                    return Err(IdentityError::IdentityVerificationFailed)?;
+            // The new change must be approved by enough keys of the previous change,
+            // or by the recovery key committed in the previous change
+            let recovered = match &new_change.recovery_signature {
+                Some(recovery_signature) => {
+                    let recovery_key_hash =
+                        compute_recovery_key_hash(&recovery_signature.public_key, vault.clone())
+                            .await?;
+                    if last_verified_change.data().recovery_key_hash != Some(recovery_key_hash)
+                        || !Self::verify_change_signature(
+                            &recovery_signature.public_key.clone().into(),
+                            new_change_details.change_full_hash,
+                            &recovery_signature.signature,
+                            vault.clone(),
+                        )
+                        .await?
+                    {
+                        return Err(IdentityError::IdentityVerificationFailed)?;
+                    }
+                    true
                 }
-            } else {
-                // Previous signature should be present if it's not the first change
+                None => false,
+            };
+
+            let previous_signatures_count = Self::count_valid_signatures(
+                last_verified_change.public_keys(),
+                new_change_details.change_full_hash,
+                new_change.previous_signature.as_ref(),
+                new_change.additional_previous_signatures.as_ref(),
+                vault.clone(),
+            )
+            .await?;
+
+            if !recovered && previous_signatures_count < last_verified_change.threshold() {
                 return Err(IdentityError::IdentityVerificationFailed)?;
             }
+        } else if new_change.previous_signature.is_some()
+            || new_change.additional_previous_signatures.is_some()
+            || new_change.recovery_signature.is_some()
+        {
+            // The first change can't be signed by previous keys
+            return Err(IdentityError::IdentityVerificationFailed)?;
         }
 
-        if !Self::verify_change_signature(
-            &new_change_details
-                .change_data
-                .primary_public_key
-                .clone()
-                .into(),
+        let public_keys: Vec<VerifyingPublicKey> = new_change_details
+            .change_data
+            .public_keys()
+            .into_iter()
+            .map(|k| k.into())
+            .collect();
+
+        // The primary key must always sign its own change
+        let self_signatures_count = Self::count_valid_signatures(
+            &public_keys,
             new_change_details.change_full_hash,
-            &new_change.signature,
+            Some(&new_change.signature),
+            new_change.additional_signatures.as_ref(),
             vault,
         )
-        .await?
-        {
+        .await?;
+
+        if self_signatures_count < new_change_details.change_data.threshold()? {
             return Err(IdentityError::IdentityVerificationFailed)?;
         }
 
@@ -1,4 +1,5 @@
 use crate::models::{ChangeData, ChangeHash};
+use ockam_core::compat::vec::Vec;
 use ockam_vault::VerifyingPublicKey;
 
 /// Verified Changes of an [`Identity`]
@@ -7,6 +8,8 @@ pub struct VerifiedChange {
     data: ChangeData,
     change_hash: ChangeHash,
     primary_public_key: VerifyingPublicKey,
+    public_keys: Vec<VerifyingPublicKey>,
+    threshold: usize,
 }
 
 impl VerifiedChange {
@@ -14,11 +17,15 @@ impl VerifiedChange {
         data: ChangeData,
         change_hash: ChangeHash,
         primary_public_key: VerifyingPublicKey,
+        public_keys: Vec<VerifyingPublicKey>,
+        threshold: usize,
     ) -> Self {
         Self {
             data,
             change_hash,
             primary_public_key,
+            public_keys,
+            threshold,
         }
     }
 
@@ -36,4 +43,14 @@ impl VerifiedChange {
     pub fn primary_public_key(&self) -> &VerifyingPublicKey {
         &self.primary_public_key
     }
+
+    /// All the public keys of that change, starting with the primary public key
+    pub fn public_keys(&self) -> &[VerifyingPublicKey] {
+        &self.public_keys
+    }
+
+    /// Number of signatures from [`Self::public_keys`] required to approve the next change
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
 }
@@ -15,6 +15,15 @@ pub struct ChangeHistory(#[n(0)] pub Vec<Change>);
 /// `data_type` value in [`VersionedData`] struct when used with [`Change`]
 pub const CHANGE_DATA_TYPE: u8 = 1;
 
+/// `version` value in [`VersionedData`] struct for a [`Change`] with several keys or a threshold.
+/// Verifiers which only support the version 1 ignore these fields, and would accept the next
+/// [`Change`] with a single signature, so they must reject the whole [`ChangeHistory`] instead.
+/// The [`Change`]s following a version 2 [`Change`] keep that version
+pub const MULTI_KEY_CHANGE_VERSION: u8 = 2;
+
+/// Maximum number of keys of a [`Change`], including its primary public key
+pub const MAX_CHANGE_KEYS: usize = 32;
+
 /// Individual Identity change which implies replacing the old key
 #[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, CborLen)]
 #[rustfmt::skip]
@@ -29,8 +38,43 @@ pub struct Change {
     /// Self-signature over the data using the key
     /// from the previous [`Change`] in the [`ChangeHistory`]
     #[n(2)] pub previous_signature: Option<ChangeSignature>,
+    /// Signatures over the data using the additional keys from this same [`Change`]
+    #[n(3)] pub additional_signatures: Option<Vec<IndexedChangeSignature>>,
+    /// Signatures over the data using the additional keys
+    /// from the previous [`Change`] in the [`ChangeHistory`]
+    #[n(4)] pub additional_previous_signatures: Option<Vec<IndexedChangeSignature>>,
+    /// Signature over the data using the recovery key committed
+    /// in the previous [`Change`] in the [`ChangeHistory`]
+    #[n(5)] pub recovery_signature: Option<RecoverySignature>,
+}
+
+/// [`Change`] signature made with one of the keys of a multi-key [`ChangeData`]
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+pub struct IndexedChangeSignature {
+    /// Index of the signing key in [`ChangeData::additional_public_keys`], starting at 1
+    /// since the index 0 designates [`ChangeData::primary_public_key`]
+    #[n(0)] pub key_index: u8,
+    /// Signature
+    #[n(1)] pub signature: ChangeSignature,
 }
 
+/// [`Change`] signature made with a recovery key
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+pub struct RecoverySignature {
+    /// Public key of the recovery key. Its [`RecoveryKeyHash`] must be
+    /// committed in the previous [`Change`]
+    #[n(0)] pub public_key: PrimaryPublicKey,
+    /// Signature
+    #[n(1)] pub signature: ChangeSignature,
+}
+
+/// SHA256 of the CBOR binary of the [`PrimaryPublicKey`] of a recovery key
+#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode, CborLen)]
+#[cbor(transparent)]
+pub struct RecoveryKeyHash(#[cbor(n(0), with = "minicbor::bytes")] pub [u8; 32]);
+
 /// [`Change`] signature
 #[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, CborLen)]
 #[rustfmt::skip]
@@ -62,6 +106,15 @@ pub struct ChangeData {
     ///  1. Sign a [`super::PurposeKeyAttestation`] that is tied to this Identifier
     ///  2. Sign [`ChangeData`] that belongs to the same [`ChangeHistory`] and goes straight after this one
     #[n(4)] pub attestations_valid_until: TimestampInSeconds,
+    /// Public Keys which, in addition to the primary public key, can sign the next [`Change`].
+    /// Used with `threshold` to require several signatures to rotate an Identity.
+    /// Only allowed with the [`MULTI_KEY_CHANGE_VERSION`]
+    #[n(5)] pub additional_public_keys: Option<Vec<PrimaryPublicKey>>,
+    /// Number of keys of that [`Change`] which must sign that [`Change`] and the next one.
+    /// 1 if absent. Only allowed with the [`MULTI_KEY_CHANGE_VERSION`]
+    #[n(6)] pub threshold: Option<u8>,
+    /// Hash of an offline recovery key which can approve the next [`Change`] on its own
+    #[n(7)] pub recovery_key_hash: Option<RecoveryKeyHash>,
 }
 
 /// [`Change`]'s public key
@@ -0,0 +1,55 @@
+use crate::models::{CredentialSignature, Identifier, PurposeKeyAttestation, TimestampInSeconds};
+use minicbor::{CborLen, Decode, Encode};
+use ockam_core::compat::vec::Vec;
+
+/// `data_type` value in [`VersionedData`] struct when used with [`RevocationList`]
+pub const REVOCATION_LIST_DATA_TYPE: u8 = 4;
+
+/// CredentialHash length
+pub const CREDENTIAL_HASH_LEN: usize = 32;
+
+/// Unique identifier for a [`super::Credential`]
+/// Computed as SHA256 of the [`super::Credential`] data field
+#[derive(Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq, Encode, Decode, CborLen)]
+#[cbor(transparent)]
+pub struct CredentialHash(#[cbor(n(0), with = "minicbor::bytes")] pub [u8; CREDENTIAL_HASH_LEN]);
+
+/// List of revoked [`super::Credential`]s and Identifiers signed by an Authority
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+pub struct RevocationList {
+    /// CBOR serialized [`super::VersionedData`]
+    /// where VersionedData::data is CBOR serialized [`RevocationListData`]
+    /// and VersionedData::data_type is [`REVOCATION_LIST_DATA_TYPE`]
+    #[cbor(with = "minicbor::bytes")]
+    #[n(0)] pub data: Vec<u8>,
+    /// Signature over data field using corresponding Credentials [`super::PurposeKeyAttestation`]
+    #[n(1)] pub signature: CredentialSignature,
+}
+
+/// Data inside a [`RevocationList`]
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+pub struct RevocationListData {
+    /// Version of the list, incremented by the Authority each time the list changes.
+    /// A list can only be replaced by a list with a greater version
+    #[n(0)] pub version: u64,
+    /// [`CredentialHash`]es of the revoked Credentials
+    #[n(1)] pub revoked_credentials: Vec<CredentialHash>,
+    /// Identifiers whose Credentials are all revoked
+    #[n(2)] pub revoked_identifiers: Vec<Identifier>,
+    /// Creation [`TimestampInSeconds`] (UTC)
+    #[n(3)] pub created_at: TimestampInSeconds,
+}
+
+/// [`RevocationList`] and the corresponding [`PurposeKeyAttestation`] that was used to sign that
+/// [`RevocationList`] and will be used to verify it
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+pub struct RevocationListAndPurposeKey {
+    /// [`RevocationList`]
+    #[n(0)] pub revocation_list: RevocationList,
+    /// Corresponding [`PurposeKeyAttestation`] that was used to sign that
+    /// [`RevocationList`] and will be used to verify it
+    #[n(1)] pub purpose_key_attestation: PurposeKeyAttestation,
+}
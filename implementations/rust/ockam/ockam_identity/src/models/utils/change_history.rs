@@ -7,7 +7,7 @@ use ockam_vault::{Signature, VerifyingPublicKey};
 use crate::alloc::string::ToString;
 use crate::models::{
     Change, ChangeData, ChangeHistory, ChangeSignature, PrimaryPublicKey, VersionedData,
-    CHANGE_DATA_TYPE,
+    CHANGE_DATA_TYPE, MAX_CHANGE_KEYS, MULTI_KEY_CHANGE_VERSION,
 };
 use crate::IdentityError;
 
@@ -20,12 +20,21 @@ impl Change {
             data,
         }
     }
+
+    /// Create [`VersionedData`] with the given version and corresponding data_type
+    pub fn create_versioned_data_with_version(version: u8, data: Vec<u8>) -> VersionedData {
+        VersionedData {
+            version,
+            data_type: CHANGE_DATA_TYPE,
+            data,
+        }
+    }
 }
 
 impl ChangeData {
     /// Extract [`ChangeData`] from [`VersionedData`]
     pub fn get_data(versioned_data: &VersionedData) -> Result<Self> {
-        if versioned_data.version != 1 {
+        if versioned_data.version != 1 && versioned_data.version != MULTI_KEY_CHANGE_VERSION {
             return Err(IdentityError::UnknownIdentityVersion)?;
         }
 
@@ -33,7 +42,49 @@ impl ChangeData {
             return Err(IdentityError::InvalidIdentityDataType)?;
         }
 
-        Ok(minicbor::decode(&versioned_data.data)?)
+        let change_data: Self = minicbor::decode(&versioned_data.data)?;
+        if change_data.is_multi_key() && versioned_data.version != MULTI_KEY_CHANGE_VERSION {
+            // Verifiers supporting only the version 1 would ignore the additional keys
+            return Err(IdentityError::UnknownIdentityVersion)?;
+        }
+        Ok(change_data)
+    }
+
+    /// Return true if that [`ChangeData`] has additional keys or a threshold, and requires
+    /// the [`MULTI_KEY_CHANGE_VERSION`]
+    pub fn is_multi_key(&self) -> bool {
+        self.additional_public_keys.is_some() || self.threshold.is_some()
+    }
+
+    /// Return all the public keys of that [`ChangeData`], starting with the primary public key
+    pub fn public_keys(&self) -> Vec<PrimaryPublicKey> {
+        let mut public_keys = vec![self.primary_public_key.clone()];
+        if let Some(additional_public_keys) = &self.additional_public_keys {
+            public_keys.extend(additional_public_keys.iter().cloned());
+        }
+        public_keys
+    }
+
+    /// Return the number of signatures required to sign that [`ChangeData`] and the next one.
+    /// It must be between 1 and the number of public keys, which must be distinct
+    /// and at most [`MAX_CHANGE_KEYS`]
+    pub fn threshold(&self) -> Result<usize> {
+        let public_keys = self.public_keys();
+        if public_keys.len() > MAX_CHANGE_KEYS {
+            return Err(IdentityError::TooManyChangeKeys)?;
+        }
+        for (index, public_key) in public_keys.iter().enumerate() {
+            // A key listed several times would count as several signatures
+            if public_keys[..index].contains(public_key) {
+                return Err(IdentityError::DuplicateChangeKey)?;
+            }
+        }
+
+        let threshold = self.threshold.unwrap_or(1) as usize;
+        if threshold == 0 || threshold > public_keys.len() {
+            return Err(IdentityError::InvalidChangeThreshold)?;
+        }
+        Ok(threshold)
     }
 }
 
@@ -0,0 +1,109 @@
+use core::fmt::{Display, Formatter};
+use core::str::FromStr;
+
+use crate::models::{
+    CredentialHash, RevocationList, RevocationListData, VersionedData, CREDENTIAL_HASH_LEN,
+    REVOCATION_LIST_DATA_TYPE,
+};
+use crate::IdentityError;
+
+use ockam_core::compat::string::String;
+use ockam_core::compat::vec::Vec;
+use ockam_core::{Error, Result};
+
+impl RevocationList {
+    /// Create [`VersionedData`] with corresponding version and data_type
+    pub fn create_versioned_data(data: Vec<u8>) -> VersionedData {
+        VersionedData {
+            version: 1,
+            data_type: REVOCATION_LIST_DATA_TYPE,
+            data,
+        }
+    }
+
+    /// Extract [`RevocationListData`]
+    pub fn get_revocation_list_data(&self) -> Result<RevocationListData> {
+        RevocationListData::get_data(&minicbor::decode(&self.data)?)
+    }
+}
+
+impl RevocationListData {
+    /// Extract [`RevocationListData`] from [`VersionedData`]
+    pub fn get_data(versioned_data: &VersionedData) -> Result<Self> {
+        if versioned_data.version != 1 {
+            return Err(IdentityError::UnknownRevocationListVersion)?;
+        }
+
+        if versioned_data.data_type != REVOCATION_LIST_DATA_TYPE {
+            return Err(IdentityError::InvalidRevocationListDataType)?;
+        }
+
+        Ok(minicbor::decode(&versioned_data.data)?)
+    }
+}
+
+impl Display for CredentialHash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&String::from(self))
+    }
+}
+
+impl From<&CredentialHash> for String {
+    fn from(credential_hash: &CredentialHash) -> Self {
+        hex::encode(credential_hash.0.as_ref())
+    }
+}
+
+impl TryFrom<&str> for CredentialHash {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self> {
+        let value = value.trim();
+        if let Ok(data) = hex::decode(value) {
+            data.as_slice().try_into()
+        } else {
+            Err(IdentityError::InvalidCredentialHash(value.into()))?
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for CredentialHash {
+    type Error = Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if let Ok(value) = <[u8; CREDENTIAL_HASH_LEN]>::try_from(value) {
+            Ok(Self(value))
+        } else {
+            Err(IdentityError::InvalidCredentialHash(hex::encode(value)))?
+        }
+    }
+}
+
+impl FromStr for CredentialHash {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.try_into()
+    }
+}
+
+impl AsRef<[u8]> for CredentialHash {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_credential_hash_string_roundtrip() {
+        let hash = CredentialHash([7u8; CREDENTIAL_HASH_LEN]);
+        let decoded = CredentialHash::from_str(&hash.to_string()).unwrap();
+        assert_eq!(decoded, hash);
+
+        assert!(CredentialHash::from_str("0102").is_err());
+        assert!(CredentialHash::from_str("not hex").is_err());
+    }
+}
@@ -8,8 +8,10 @@ use crate::models::Identifier;
 use crate::secure_channel::encryptor::{Encryptor, KEY_RENEWAL_INTERVAL};
 use crate::secure_channel::handshake::handshake_state_machine::CommonStateMachine;
 use crate::secure_channel::key_tracker::KeyTracker;
+use crate::secure_channel::nonce_reservation::NonceReservation;
 use crate::secure_channel::nonce_tracker::NonceTracker;
-use crate::secure_channel::{Addresses, Role};
+use crate::secure_channel::rekey_policy::RekeyMetrics;
+use crate::secure_channel::{Addresses, PaddingPolicy, Role};
 use crate::{
     DecryptionRequest, DecryptionResponse, Identities, IdentityError, Nonce,
     PlaintextPayloadMessage, RefreshCredentialsMessage, SecureChannelMessage,
@@ -31,6 +33,7 @@ pub(crate) struct DecryptorHandler {
     identities: Arc<Identities>,
     authority: Option<Identifier>,
     shared_state: SecureChannelSharedState,
+    padding_policy: PaddingPolicy,
 }
 
 impl DecryptorHandler {
@@ -49,7 +52,7 @@ impl DecryptorHandler {
         let decryptor = if key_exchange_only {
             Decryptor::new_naive(key, vault)
         } else {
-            Decryptor::new(key, vault)
+            Decryptor::new(key, vault).with_rekey_metrics(shared_state.rekey_metrics.clone())
         };
 
         Self {
@@ -60,9 +63,22 @@ impl DecryptorHandler {
             identities,
             authority,
             shared_state,
+            padding_policy: PaddingPolicy::default(),
         }
     }
 
+    /// Reject the messages which are not padded according to the given policy
+    pub(crate) fn with_padding_policy(mut self, padding_policy: PaddingPolicy) -> Self {
+        self.padding_policy = padding_policy;
+        self
+    }
+
+    /// Replace the default decryptor, for example with a restored one
+    pub(crate) fn with_decryptor(mut self, decryptor: Decryptor) -> Self {
+        self.decryptor = decryptor;
+        self
+    }
+
     #[instrument(skip_all)]
     pub(crate) async fn handle_decrypt_api(
         &mut self,
@@ -142,11 +158,17 @@ impl DecryptorHandler {
         }
     }
 
-    fn handle_close(&mut self, ctx: &mut Context) -> Result<()> {
+    async fn handle_close(&mut self, ctx: &mut Context) -> Result<()> {
         // Prevent sending another Close message
         self.shared_state
             .should_send_close
             .store(false, Ordering::Relaxed);
+        // The channel can't be resumed anymore
+        if let Some(nonce_reservation) = self.decryptor.nonce_reservation.as_mut() {
+            if let Err(err) = nonce_reservation.delete().await {
+                warn!(%err, "Error while deleting the persisted secure channel {}", self.addresses.decryptor_remote);
+            }
+        }
         // Should be enough to stop the encryptor, since it will stop the decryptor
         ctx.stop_address(&self.addresses.encryptor)?;
 
@@ -169,11 +191,18 @@ impl DecryptorHandler {
             self.authority.clone(),
             Some(self.their_identity_id.clone()),
             msg.change_history,
-            msg.credentials,
+            msg.credentials.clone(),
             None,
         )
         .await?;
 
+        // the credentials are verified again when the channel is resumed
+        if let Some(nonce_reservation) = &self.decryptor.nonce_reservation {
+            nonce_reservation
+                .save_their_credentials(&msg.credentials)
+                .await?;
+        }
+
         info!(
             "Successfully handled credentials refresh for {}",
             self.addresses.decryptor_remote
@@ -203,6 +232,8 @@ impl DecryptorHandler {
         // Decrypt the binary
         let (decrypted_payload, nonce) = self.decryptor.decrypt(payload.as_mut_slice()).await?;
         let decrypted_msg: SecureChannelPaddedMessage = minicbor::decode(decrypted_payload)?;
+        self.padding_policy
+            .validate(decrypted_payload.len(), decrypted_msg.padding.len())?;
 
         match decrypted_msg.message {
             SecureChannelMessage::Payload(decrypted_msg) => {
@@ -212,7 +243,7 @@ impl DecryptorHandler {
             SecureChannelMessage::RefreshCredentials(decrypted_msg) => {
                 self.handle_refresh_credentials(ctx, decrypted_msg).await?
             }
-            SecureChannelMessage::Close => self.handle_close(ctx)?,
+            SecureChannelMessage::Close => self.handle_close(ctx).await?,
         };
 
         Ok(())
@@ -228,6 +259,8 @@ pub(crate) struct Decryptor {
     vault: Arc<dyn VaultForSecureChannels>,
     key_tracker: KeyTracker,
     nonce_tracker: Option<NonceTracker>,
+    nonce_reservation: Option<NonceReservation>,
+    rekey_metrics: RekeyMetrics,
 }
 
 impl Decryptor {
@@ -236,15 +269,57 @@ impl Decryptor {
             vault,
             key_tracker: KeyTracker::new(key, KEY_RENEWAL_INTERVAL),
             nonce_tracker: Some(NonceTracker::new()),
+            nonce_reservation: None,
+            rekey_metrics: RekeyMetrics::default(),
+        }
+    }
+
+    /// Creates a Decryptor for a persistent secure channel which is restarted.
+    /// Only the nonces starting from `first_nonce` are accepted
+    pub fn restore(
+        key: AeadSecretKeyHandle,
+        vault: Arc<dyn VaultForSecureChannels>,
+        number_of_rekeys: u64,
+        first_nonce: Nonce,
+    ) -> Self {
+        Self {
+            vault,
+            key_tracker: KeyTracker::restore(key, KEY_RENEWAL_INTERVAL, number_of_rekeys),
+            nonce_tracker: Some(NonceTracker::starting_at(first_nonce)),
+            nonce_reservation: None,
+            rekey_metrics: RekeyMetrics::default(),
         }
     }
 
+    /// Save the decryption state of a persistent secure channel while decrypting messages
+    pub(crate) fn with_nonce_reservation(mut self, nonce_reservation: NonceReservation) -> Self {
+        self.nonce_reservation = Some(nonce_reservation);
+        self
+    }
+
+    /// Count the key renewals
+    pub(crate) fn with_rekey_metrics(mut self, rekey_metrics: RekeyMetrics) -> Self {
+        self.rekey_metrics = rekey_metrics;
+        self
+    }
+
+    /// Wait until the state being saved in the background, if any, is saved
+    #[cfg(test)]
+    pub(crate) async fn wait_for_nonce_reservation(&mut self) -> Result<()> {
+        if let Some(nonce_reservation) = self.nonce_reservation.as_mut() {
+            nonce_reservation.wait_for_next_reservation().await?;
+        }
+        Ok(())
+    }
+
     /// Creates a new Decryptor without rekeying and nonce tracking
     pub fn new_naive(key: AeadSecretKeyHandle, vault: Arc<dyn VaultForSecureChannels>) -> Self {
         Self {
             vault,
             key_tracker: KeyTracker::new(key, KEY_RENEWAL_INTERVAL),
             nonce_tracker: None,
+            nonce_reservation: None,
+            rekey_metrics: RekeyMetrics::default(),
         }
     }
 
@@ -262,15 +337,23 @@ impl Decryptor {
         };
 
         let rekey_key;
+        let mut rekeys_done = 0;
 
         let rekeying = self.nonce_tracker.is_some();
         let key = if rekeying {
             // get the key corresponding to the current nonce and
-            // rekey if necessary
+            // rekey if necessary, once per interval skipped by the other party
             if let Some(key) = self.key_tracker.get_key(nonce)? {
                 key
             } else {
-                rekey_key = Encryptor::rekey(&self.vault, &self.key_tracker.current_key).await?;
+                rekeys_done = self.key_tracker.number_of_rekeys_to(nonce);
+                let mut key = Encryptor::rekey(&self.vault, &self.key_tracker.current_key).await?;
+                for _ in 1..rekeys_done {
+                    let next_key = Encryptor::rekey(&self.vault, &key).await?;
+                    let skipped_key = core::mem::replace(&mut key, next_key);
+                    self.vault.delete_aead_secret_key(skipped_key).await?;
+                }
+                rekey_key = key;
                 &rekey_key
             }
         } else {
@@ -292,13 +375,40 @@ impl Decryptor {
         match result {
             Ok(result) => {
                 self.nonce_tracker = nonce_tracker;
-                if let Some(key_to_delete) = self.key_tracker.update_key(&key.clone())? {
+                let number_of_rekeys = self.key_tracker.number_of_rekeys();
+                if let Some(key_to_delete) =
+                    self.key_tracker.update_key(&key.clone(), rekeys_done)?
+                {
+                    // the key might still be saved by a nonce reservation
+                    if let Some(nonce_reservation) = self.nonce_reservation.as_mut() {
+                        nonce_reservation.wait_for_next_reservation().await?;
+                    }
                     self.vault.delete_aead_secret_key(key_to_delete).await?;
                 }
+                if self.key_tracker.number_of_rekeys() != number_of_rekeys {
+                    self.rekey_metrics.record_decryption_rekey();
+                }
+
+                // make sure that this nonce can't be accepted again after a restart
+                if let Some(nonce_reservation) = self.nonce_reservation.as_mut() {
+                    nonce_reservation
+                        .reserve(
+                            &self.key_tracker.current_key,
+                            self.key_tracker.number_of_rekeys(),
+                            nonce,
+                        )
+                        .await?;
+                }
 
                 Ok((result, nonce))
             }
-            Err(err) => Err(err),
+            Err(err) => {
+                // the keys created for an invalid message are not kept
+                if rekeys_done > 0 {
+                    let _ = self.vault.delete_aead_secret_key(key.clone()).await;
+                }
+                Err(err)
+            }
         }
     }
 
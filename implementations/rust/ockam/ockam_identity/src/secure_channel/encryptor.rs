@@ -5,13 +5,20 @@ use ockam_vault::{AeadSecretKeyHandle, VaultForSecureChannels};
 use tracing_attributes::instrument;
 
 use crate::secure_channel::handshake::handshake::AES_GCM_TAGSIZE;
-use crate::{Nonce, MAX_NONCE, NOISE_NONCE_LEN};
+use crate::secure_channel::nonce_reservation::NonceReservation;
+use crate::secure_channel::rekey_policy::{KeyUsage, RekeyMetrics, RekeyPolicy};
+use crate::utils::now;
+use crate::{IdentityError, Nonce, MAX_NONCE, NOISE_NONCE_LEN};
 
 pub(crate) struct Encryptor {
     key: AeadSecretKeyHandle,
+    number_of_rekeys: u64,
     nonce: Nonce,
     vault: Arc<dyn VaultForSecureChannels>,
     rekeying: bool,
+    nonce_reservation: Option<NonceReservation>,
+    key_usage: Option<KeyUsage>,
+    rekey_metrics: RekeyMetrics,
 }
 
 // To simplify the implementation, we use the same constant for the size of the message
@@ -44,6 +51,17 @@ impl Encryptor {
 
     #[instrument(skip_all)]
     pub async fn encrypt(&mut self, payload: &mut [u8]) -> Result<()> {
+        // when the rekey policy requires it, skip the rest of the current key interval
+        // so that both parties switch to a new key with this message
+        if self.rekeying && self.nonce.value() % KEY_RENEWAL_INTERVAL != 0 {
+            if let Some(key_usage) = &self.key_usage {
+                if key_usage.is_exhausted(now()?) {
+                    self.nonce = KeyUsage::next_interval_start(self.nonce)
+                        .ok_or(IdentityError::NonceOverflow)?;
+                }
+            }
+        }
+
         let current_nonce = self.nonce;
 
         self.nonce.increment()?;
@@ -53,8 +71,24 @@ impl Encryptor {
             && current_nonce.value() % KEY_RENEWAL_INTERVAL == 0
         {
             let new_key = Self::rekey(&self.vault, &self.key).await?;
+            // the old key might still be saved by a nonce reservation
+            if let Some(nonce_reservation) = self.nonce_reservation.as_mut() {
+                nonce_reservation.wait_for_next_reservation().await?;
+            }
             let old_key = core::mem::replace(&mut self.key, new_key);
+            self.number_of_rekeys += 1;
             self.vault.delete_aead_secret_key(old_key).await?;
+            self.rekey_metrics.record_encryption_rekey();
+            if let Some(key_usage) = self.key_usage.as_mut() {
+                key_usage.reset(now()?);
+            }
+        }
+
+        // make sure that this nonce can't be used again after a restart
+        if let Some(nonce_reservation) = self.nonce_reservation.as_mut() {
+            nonce_reservation
+                .reserve(&self.key, self.number_of_rekeys, current_nonce)
+                .await?;
         }
 
         payload[..NOISE_NONCE_LEN].copy_from_slice(&current_nonce.to_noise_nonce());
@@ -68,6 +102,10 @@ impl Encryptor {
             )
             .await?;
 
+        if let Some(key_usage) = self.key_usage.as_mut() {
+            key_usage.record(payload.len() - NOISE_NONCE_LEN);
+        }
+
         Ok(())
     }
 
@@ -79,10 +117,76 @@ impl Encryptor {
     ) -> Self {
         Self {
             key,
+            number_of_rekeys: 0,
             nonce,
             vault,
             rekeying,
+            nonce_reservation: None,
+            key_usage: None,
+            rekey_metrics: RekeyMetrics::default(),
+        }
+    }
+
+    /// Creates an Encryptor for a persistent secure channel which is restarted.
+    ///
+    /// The key was obtained after `number_of_rekeys` rekeys, and might precede the key
+    /// used for the nonce before `nonce`, in which case it is rekeyed up to that key
+    pub(crate) async fn restore(
+        key: AeadSecretKeyHandle,
+        number_of_rekeys: u64,
+        nonce: Nonce,
+        vault: Arc<dyn VaultForSecureChannels>,
+        rekeying: bool,
+    ) -> Result<Self> {
+        let mut key = key;
+        let mut current_number_of_rekeys = number_of_rekeys;
+        if rekeying {
+            while current_number_of_rekeys < Self::number_of_rekeys_before(nonce) {
+                let new_key = Self::rekey(&vault, &key).await?;
+                let old_key = core::mem::replace(&mut key, new_key);
+                vault.delete_aead_secret_key(old_key).await?;
+                current_number_of_rekeys += 1;
+            }
         }
+
+        let mut encryptor = Self::new(key, nonce, vault, rekeying);
+        encryptor.number_of_rekeys = current_number_of_rekeys;
+        Ok(encryptor)
+    }
+
+    /// Number of rekeys done to get the key used for the nonce preceding `nonce`.
+    /// The key is renewed before using the first nonce of each [`KEY_RENEWAL_INTERVAL`]
+    pub(crate) fn number_of_rekeys_before(nonce: Nonce) -> u64 {
+        nonce.value().saturating_sub(1) / KEY_RENEWAL_INTERVAL
+    }
+
+    /// Renew the key when one of the limits of the [`RekeyPolicy`] is reached
+    pub(crate) fn with_rekey_policy(mut self, rekey_policy: RekeyPolicy) -> Result<Self> {
+        if !rekey_policy.is_empty() {
+            self.key_usage = Some(KeyUsage::new(rekey_policy, now()?));
+        }
+        Ok(self)
+    }
+
+    /// Count the key renewals
+    pub(crate) fn with_rekey_metrics(mut self, rekey_metrics: RekeyMetrics) -> Self {
+        self.rekey_metrics = rekey_metrics;
+        self
+    }
+
+    /// Save the encryption state of a persistent secure channel while encrypting messages
+    pub(crate) fn with_nonce_reservation(mut self, nonce_reservation: NonceReservation) -> Self {
+        self.nonce_reservation = Some(nonce_reservation);
+        self
+    }
+
+    /// Wait until the state being saved in the background, if any, is saved
+    #[cfg(test)]
+    pub(crate) async fn wait_for_nonce_reservation(&mut self) -> Result<()> {
+        if let Some(nonce_reservation) = self.nonce_reservation.as_mut() {
+            nonce_reservation.wait_for_next_reservation().await?;
+        }
+        Ok(())
     }
 
     #[instrument(skip_all)]
@@ -18,6 +18,8 @@ use crate::secure_channel::addresses::Addresses;
 use crate::secure_channel::api::{EncryptionRequest, EncryptionResponse};
 use crate::secure_channel::encryptor::Encryptor;
 use crate::secure_channel::handshake::handshake::AES_GCM_TAGSIZE;
+use crate::secure_channel::rekey_policy::RekeyMetrics;
+use crate::secure_channel::PaddingPolicy;
 use crate::{
     ChangeHistoryRepository, CredentialRetriever, Identifier, IdentityError, Nonce,
     PlaintextPayloadMessage, RefreshCredentialsMessage, SecureChannelMessage,
@@ -53,6 +55,8 @@ pub(crate) struct SecureChannelSharedState {
     /// Allows Decryptor to flag that we're closing the channel because we received a Close message from the other side,
     /// therefore, we don't need to send that message again to the other side
     pub(crate) should_send_close: Arc<AtomicBool>,
+    /// Number of key renewals done by the encryptor and the decryptor
+    pub(crate) rekey_metrics: RekeyMetrics,
 }
 
 pub(crate) struct EncryptorWorker {
@@ -65,6 +69,7 @@ pub(crate) struct EncryptorWorker {
     credential_retriever: Option<Arc<dyn CredentialRetriever>>,
     last_presented_credential: Option<CredentialAndPurposeKey>,
     shared_state: SecureChannelSharedState,
+    padding_policy: PaddingPolicy,
 }
 
 impl EncryptorWorker {
@@ -90,9 +95,16 @@ impl EncryptorWorker {
             credential_retriever,
             last_presented_credential,
             shared_state,
+            padding_policy: PaddingPolicy::default(),
         }
     }
 
+    /// Pad the messages according to the given policy
+    pub(crate) fn with_padding_policy(mut self, padding_policy: PaddingPolicy) -> Self {
+        self.padding_policy = padding_policy;
+        self
+    }
+
     /// Encrypt the message
     async fn encrypt(
         &mut self,
@@ -210,7 +222,7 @@ impl EncryptorWorker {
         };
 
         let msg = SecureChannelMessage::Payload(msg);
-        let msg = Self::add_padding(msg);
+        let msg = self.padding_policy.pad(msg);
 
         let payload = self.encrypt(ctx, msg).await?;
 
@@ -286,7 +298,7 @@ impl EncryptorWorker {
             credentials: vec![credential.clone()],
         };
         let msg = SecureChannelMessage::RefreshCredentials(msg);
-        let msg = Self::add_padding(msg);
+        let msg = self.padding_policy.pad(msg);
 
         let msg = self.encrypt(ctx, msg).await?;
 
@@ -316,7 +328,7 @@ impl EncryptorWorker {
 
     async fn send_close_channel(&mut self, ctx: &Context) -> Result<()> {
         let msg = SecureChannelMessage::Close;
-        let msg = Self::add_padding(msg);
+        let msg = self.padding_policy.pad(msg);
 
         // Encrypt the message
         let msg = self.encrypt(ctx, msg).await?;
@@ -332,19 +344,6 @@ impl EncryptorWorker {
 
         Ok(())
     }
-
-    fn add_padding(msg: SecureChannelMessage) -> SecureChannelPaddedMessage {
-        // Naїve padding of 0 to 255 zeros
-        // let padding_length: u8 = ockam_core::compat::rand::random();
-        // let padding = vec![0u8; padding_length as usize];
-
-        let padding = vec![];
-
-        SecureChannelPaddedMessage {
-            message: msg,
-            padding: padding.into(),
-        }
-    }
 }
 
 #[async_trait]
@@ -16,6 +16,12 @@ pub enum XXError {
     ExceededMaxMessageLen,
     /// Invalid internal state.
     InvalidInternalState,
+    /// The other party didn't perform the required hybrid key exchange.
+    HybridKeyExchangeRequired,
+    /// The other party performed a hybrid key exchange which was not offered.
+    UnexpectedHybridKeyExchange,
+    /// The static key of the other party is required by the handshake pattern but is unknown.
+    UnknownStaticKey,
 }
 
 impl StdError for XXError {}
@@ -29,6 +35,9 @@ impl fmt::Display for XXError {
                 write!(f, "exceeded maximum allowed message length for noise")
             }
             Self::InvalidInternalState => write!(f, "invalid internal state"),
+            Self::HybridKeyExchangeRequired => write!(f, "hybrid key exchange is required"),
+            Self::UnexpectedHybridKeyExchange => write!(f, "unexpected hybrid key exchange"),
+            Self::UnknownStaticKey => write!(f, "the static key of the other party is unknown"),
         }
     }
 }
@@ -41,6 +50,9 @@ impl From<XXError> for Error {
             XXError::MessageLenMismatch => Kind::Misuse,
             XXError::ExceededMaxMessageLen => Kind::Invalid,
             XXError::InvalidInternalState => Kind::Internal,
+            XXError::HybridKeyExchangeRequired => Kind::Unsupported,
+            XXError::UnexpectedHybridKeyExchange => Kind::Invalid,
+            XXError::UnknownStaticKey => Kind::NotFound,
         };
 
         Error::new(Origin::KeyExchange, kind, err)
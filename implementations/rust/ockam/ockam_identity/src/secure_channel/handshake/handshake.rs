@@ -1,7 +1,7 @@
 #![allow(unexpected_cfgs)]
 use crate::secure_channel::handshake::error::XXError;
 use crate::secure_channel::handshake::handshake_state_machine::{HandshakeKeys, Status};
-use crate::secure_channel::Role;
+use crate::secure_channel::{HandshakePattern, Role};
 use crate::Nonce;
 use cfg_if::cfg_if;
 use ockam_core::compat::sync::Arc;
@@ -9,8 +9,9 @@ use ockam_core::compat::vec::Vec;
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{Error, Result};
 use ockam_vault::{
-    AeadSecretKeyHandle, HKDFNumberOfOutputs, SecretBufferHandle, VaultForSecureChannels,
-    X25519PublicKey, X25519SecretKeyHandle, X25519_PUBLIC_KEY_LENGTH,
+    AeadSecretKeyHandle, HKDFNumberOfOutputs, MLKEM768Ciphertext, MLKEM768DecapsulationKeyHandle,
+    MLKEM768EncapsulationKey, SecretBufferHandle, VaultForSecureChannels, X25519PublicKey,
+    X25519SecretKeyHandle, X25519_PUBLIC_KEY_LENGTH,
 };
 use sha2::{Digest, Sha256};
 #[cfg(feature = "debugger")]
@@ -236,6 +237,264 @@ impl Handshake {
         Ok(payload)
     }
 
+    /// Initialize the handshake variables for a IK or KK handshake.
+    /// The prologue is mixed first, followed by the pre-messages containing the static keys
+    /// known in advance: the initiator static key for KK, then the responder static key.
+    /// `rs` is the static key of the other party, when known
+    pub(super) async fn initialize_with_known_keys(
+        &mut self,
+        role: Role,
+        handshake_pattern: HandshakePattern,
+        prologue: &[u8],
+        rs: Option<X25519PublicKey>,
+    ) -> Result<()> {
+        // the responder side was already initialized for a XX handshake
+        if let Some(ck) = self.state.ck.take() {
+            self.vault.delete_secret_buffer(ck).await?;
+        }
+        self.protocol_name = handshake_pattern.protocol_name();
+        self.initialize().await?;
+
+        let mut state = self.state.clone();
+        state.mix_hash(prologue);
+        state.rs = rs;
+
+        let s_pub_key = self.get_public_key(state.s()?).await?;
+        if handshake_pattern == HandshakePattern::KK {
+            let initiator_static_key = if role.is_initiator() {
+                s_pub_key.clone()
+            } else {
+                state.rs()?.clone()
+            };
+            state.mix_hash(&initiator_static_key.0);
+        }
+        let responder_static_key = if role.is_initiator() {
+            state.rs()?.clone()
+        } else {
+            s_pub_key
+        };
+        state.mix_hash(&responder_static_key.0);
+
+        self.state = state;
+        Ok(())
+    }
+
+    /// Encode the first message of a IK or KK handshake, sent from the initiator to the responder
+    /// That message contains: the initiator ephemeral public key + a Diffie-Hellman key +
+    ///   the initiator static public key (encrypted, IK only) + a Diffie-Hellman key +
+    ///   an encrypted payload containing the initiator identity / signature / credentials
+    pub(super) async fn encode_short_message1(
+        &mut self,
+        handshake_pattern: HandshakePattern,
+        payload: &[u8],
+    ) -> Result<Vec<u8>> {
+        #[cfg(feature = "debugger")]
+        debug!("Encoding short message 1");
+
+        let mut state = self.state.clone();
+        // output e.pubKey
+        let e_pub_key = self.get_public_key(state.e()?).await?;
+        state.mix_hash(&e_pub_key.0);
+        let mut message1 = e_pub_key.0.to_vec();
+
+        // ck, k = HKDF(ck, DH(e, rs), 2)
+        let dh = self.dh(state.e()?, state.rs()?).await?;
+        self.hkdf(&mut state, dh).await?;
+
+        if handshake_pattern == HandshakePattern::IK {
+            // encrypt and output s.pubKey
+            let s_pub_key = self.get_public_key(state.s()?).await?;
+            let c = self.encrypt_and_hash(&mut state, &s_pub_key.0).await?;
+            message1.extend_from_slice(c.as_slice());
+        }
+
+        // ck, k = HKDF(ck, DH(s, rs), 2)
+        let dh = self.dh(state.s()?, state.rs()?).await?;
+        self.hkdf(&mut state, dh).await?;
+
+        // encrypt and output payload
+        let c = self.encrypt_and_hash(&mut state, payload).await?;
+        message1.extend(c);
+
+        if message1.len() > NOISE_MAX_MESSAGE_SIZE {
+            return Err(XXError::ExceededMaxMessageLen)?;
+        }
+
+        self.state = state;
+        Ok(message1)
+    }
+
+    /// Decode the first message of a IK or KK handshake sent by the initiator
+    pub(super) async fn decode_short_message1(
+        &mut self,
+        handshake_pattern: HandshakePattern,
+        message1: &[u8],
+    ) -> Result<Vec<u8>> {
+        #[cfg(feature = "debugger")]
+        debug!("Decoding short message 1");
+
+        if message1.len() > NOISE_MAX_MESSAGE_SIZE {
+            return Err(XXError::ExceededMaxMessageLen)?;
+        }
+
+        let mut state = self.state.clone();
+        // read e.pubKey
+        let re_pub_key = Self::read_key(message1)?;
+        state.re = Some(X25519PublicKey(*re_pub_key));
+        state.mix_hash(re_pub_key);
+
+        // ck, k = HKDF(ck, DH(s, re), 2)
+        let dh = self.dh(state.s()?, state.re()?).await?;
+        self.hkdf(&mut state, dh).await?;
+
+        let c = if handshake_pattern == HandshakePattern::IK {
+            // decrypt rs.pubKey
+            let rs_pub_key = Self::read_ik_message1_encrypted_key(message1)?;
+            let rs_pub_key = self.hash_and_decrypt(&mut state, rs_pub_key).await?;
+            let rs_pub_key = X25519PublicKey(
+                rs_pub_key
+                    .try_into()
+                    .map_err(|_| XXError::MessageLenMismatch)?,
+            );
+            state.rs = Some(rs_pub_key);
+            Self::read_ik_message1_payload(message1)?
+        } else {
+            Self::read_short_message_payload(message1)?
+        };
+
+        // ck, k = HKDF(ck, DH(s, rs), 2)
+        let dh = self.dh(state.s()?, state.rs()?).await?;
+        self.hkdf(&mut state, dh).await?;
+
+        // decrypt payload
+        let payload = self.hash_and_decrypt(&mut state, c).await?;
+
+        self.state = state;
+        Ok(payload)
+    }
+
+    /// Encode the second (and last) message of a IK or KK handshake
+    /// That message contains: the responder ephemeral public key + two Diffie-Hellman keys +
+    ///   an encrypted payload containing the responder identity / signature / credentials
+    pub(super) async fn encode_short_message2(&mut self, payload: &[u8]) -> Result<Vec<u8>> {
+        #[cfg(feature = "debugger")]
+        debug!("Encoding short message 2");
+
+        let mut state = self.state.clone();
+        // output e.pubKey
+        let e_pub_key = self.get_public_key(state.e()?).await?;
+        state.mix_hash(&e_pub_key.0);
+        let mut message2 = e_pub_key.0.to_vec();
+
+        // ck, k = HKDF(ck, DH(e, re), 2)
+        let dh = self.dh(state.e()?, state.re()?).await?;
+        self.hkdf(&mut state, dh).await?;
+
+        // ck, k = HKDF(ck, DH(e, rs), 2)
+        let dh = self.dh(state.e()?, state.rs()?).await?;
+        self.hkdf(&mut state, dh).await?;
+
+        // encrypt and output payload
+        let c = self.encrypt_and_hash(&mut state, payload).await?;
+        message2.extend(c);
+
+        if message2.len() > NOISE_MAX_MESSAGE_SIZE {
+            return Err(XXError::ExceededMaxMessageLen)?;
+        }
+
+        self.state = state;
+        Ok(message2)
+    }
+
+    /// Decode the second (and last) message of a IK or KK handshake sent by the responder
+    pub(super) async fn decode_short_message2(&mut self, message2: &[u8]) -> Result<Vec<u8>> {
+        #[cfg(feature = "debugger")]
+        debug!("Decoding short message 2");
+
+        if message2.len() > NOISE_MAX_MESSAGE_SIZE {
+            return Err(XXError::ExceededMaxMessageLen)?;
+        }
+
+        let mut state = self.state.clone();
+        // decode re.pubKey
+        let re_pub_key = Self::read_key(message2)?;
+        state.re = Some(X25519PublicKey(*re_pub_key));
+        state.mix_hash(re_pub_key);
+
+        // ck, k = HKDF(ck, DH(e, re), 2)
+        let dh = self.dh(state.e()?, state.re()?).await?;
+        self.hkdf(&mut state, dh).await?;
+
+        // ck, k = HKDF(ck, DH(s, re), 2)
+        let dh = self.dh(state.s()?, state.re()?).await?;
+        self.hkdf(&mut state, dh).await?;
+
+        // decrypt payload
+        let c = Self::read_short_message_payload(message2)?;
+        let payload = self.hash_and_decrypt(&mut state, c).await?;
+
+        self.state = state;
+        Ok(payload)
+    }
+
+    /// Generate an ephemeral ML-KEM-768 key pair for a hybrid key exchange and return the
+    /// encapsulation key which must be sent to the responder in message 1
+    pub(super) async fn generate_ml_kem_768_key(&mut self) -> Result<MLKEM768EncapsulationKey> {
+        let mut state = self.state.clone();
+        let ekem = self
+            .vault
+            .generate_ephemeral_ml_kem_768_decapsulation_key()
+            .await?;
+        let encapsulation_key = self.vault.get_ml_kem_768_encapsulation_key(&ekem).await?;
+        state.ekem = Some(ekem);
+
+        self.state = state;
+        Ok(encapsulation_key)
+    }
+
+    /// Encapsulate a fresh shared secret to the initiator ML-KEM-768 encapsulation key
+    /// and return the ciphertext which must be sent back in message 2
+    pub(super) async fn encapsulate_ml_kem_768_secret(
+        &mut self,
+        encapsulation_key: &MLKEM768EncapsulationKey,
+    ) -> Result<MLKEM768Ciphertext> {
+        let mut state = self.state.clone();
+        let (ciphertext, shared_secret) =
+            self.vault.ml_kem_768_encapsulate(encapsulation_key).await?;
+        state.kem_secret = Some(shared_secret);
+
+        self.state = state;
+        Ok(ciphertext)
+    }
+
+    /// Decapsulate the shared secret from the ciphertext sent by the responder in message 2
+    pub(super) async fn decapsulate_ml_kem_768_secret(
+        &mut self,
+        ciphertext: &MLKEM768Ciphertext,
+    ) -> Result<()> {
+        let mut state = self.state.clone();
+        let Some(ekem) = &state.ekem else {
+            return Err(XXError::UnexpectedHybridKeyExchange)?;
+        };
+        let shared_secret = self.vault.ml_kem_768_decapsulate(ekem, ciphertext).await?;
+        state.kem_secret = Some(shared_secret);
+
+        self.state = state;
+        Ok(())
+    }
+
+    /// Mix the ML-KEM-768 shared secret into the chaining key once message 2 has been processed
+    /// so that the final keys depend on both the X25519 and the ML-KEM-768 key exchanges
+    pub(super) async fn mix_ml_kem_768_secret(&mut self) -> Result<()> {
+        let mut state = self.state.clone();
+        // ck, k = HKDF(ck, KEM shared secret, 2)
+        let kem_secret = state.take_kem_secret()?;
+        self.hkdf(&mut state, kem_secret).await?;
+
+        self.state = state;
+        Ok(())
+    }
+
     /// Set the final state of the state machine by creating the encryption / decryption keys
     /// and return the other party identity
     pub(super) async fn set_final_state(&mut self, role: Role) -> Result<()> {
@@ -407,6 +666,13 @@ impl Handshake {
             .delete_ephemeral_x25519_secret_key(self.state.take_e()?)
             .await?;
 
+        if let Some(ekem) = self.state.ekem.take() {
+            _ = self
+                .vault
+                .delete_ephemeral_ml_kem_768_decapsulation_key(ekem)
+                .await?;
+        }
+
         Ok(())
     }
 }
@@ -452,6 +718,24 @@ impl Handshake {
         Self::read_end::<L>(message)
     }
 
+    /// Read the IK message 1 encrypted key, which is present after the public key
+    fn read_ik_message1_encrypted_key(message: &[u8]) -> Result<&[u8]> {
+        const L: usize = X25519_PUBLIC_KEY_LENGTH + AES_GCM_TAGSIZE;
+        Self::read_middle::<X25519_PUBLIC_KEY_LENGTH, L>(message)
+    }
+
+    /// Read the IK message 1 encrypted payload, which is present after the encrypted key
+    fn read_ik_message1_payload(message: &[u8]) -> Result<&[u8]> {
+        const L: usize = 2 * X25519_PUBLIC_KEY_LENGTH + AES_GCM_TAGSIZE;
+        Self::read_end::<L>(message)
+    }
+
+    /// Read the encrypted payload of the KK message 1 or of the IK / KK message 2,
+    /// which is present after the public key
+    fn read_short_message_payload(message: &[u8]) -> Result<&[u8]> {
+        Self::read_end::<X25519_PUBLIC_KEY_LENGTH>(message)
+    }
+
     /// Read the message 3 encrypted key at the beginning of the message
     fn read_message3_encrypted_key(message: &[u8]) -> Result<&[u8]> {
         const L: usize = X25519_PUBLIC_KEY_LENGTH + AES_GCM_TAGSIZE;
@@ -508,6 +792,8 @@ pub(super) struct HandshakeState {
     n: u64,
     h: [u8; SHA256_SIZE],
     ck: Option<SecretBufferHandle>,
+    ekem: Option<MLKEM768DecapsulationKeyHandle>,
+    kem_secret: Option<SecretBufferHandle>,
     pub(super) status: Status,
 }
 
@@ -526,6 +812,8 @@ impl HandshakeState {
             n: 0,
             h: [0u8; SHA256_SIZE],
             ck: None,
+            ekem: None,
+            kem_secret: None,
             status: Initial,
         }
     }
@@ -573,6 +861,16 @@ impl HandshakeState {
         })
     }
 
+    pub(super) fn take_kem_secret(&mut self) -> Result<SecretBufferHandle> {
+        self.kem_secret.take().ok_or_else(|| {
+            Error::new(
+                Origin::KeyExchange,
+                Kind::Invalid,
+                "key id kem_secret should have been set",
+            )
+        })
+    }
+
     pub(super) fn s(&self) -> Result<&X25519SecretKeyHandle> {
         self.s.as_ref().ok_or_else(|| {
             Error::new(
@@ -709,6 +1007,78 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_hybrid_handshake() -> Result<()> {
+        let vault = SoftwareVaultForSecureChannels::create().await?;
+
+        let initiator_static_key = vault.generate_static_x25519_secret_key().await?;
+        let responder_static_key = vault.generate_static_x25519_secret_key().await?;
+        let mut initiator = Handshake::new(vault.clone(), initiator_static_key).await?;
+        let mut responder = Handshake::new(vault.clone(), responder_static_key).await?;
+        initiator.initialize().await?;
+        responder.initialize().await?;
+
+        let encapsulation_key = initiator.generate_ml_kem_768_key().await?;
+        let message1 = initiator.encode_message1(&encapsulation_key.0).await?;
+        let payload = responder.decode_message1(&message1).await?;
+        assert_eq!(payload, encapsulation_key.0);
+
+        let ciphertext = responder
+            .encapsulate_ml_kem_768_secret(&encapsulation_key)
+            .await?;
+        let message2 = responder.encode_message2(&ciphertext.0).await?;
+        responder.mix_ml_kem_768_secret().await?;
+
+        let payload = initiator.decode_message2(&message2).await?;
+        assert_eq!(payload, ciphertext.0);
+        initiator.decapsulate_ml_kem_768_secret(&ciphertext).await?;
+        initiator.mix_ml_kem_768_secret().await?;
+
+        let message3 = initiator.encode_message3(b"hello").await?;
+        let payload = responder.decode_message3(&message3).await?;
+        assert_eq!(payload, b"hello");
+
+        initiator.set_final_state(Role::Initiator).await?;
+        responder.set_final_state(Role::Responder).await?;
+        assert_eq!(vault.number_of_ephemeral_ml_kem_768_secrets(), 0);
+
+        let initiator_keys = initiator.get_handshake_keys().unwrap();
+        let responder_keys = responder.get_handshake_keys().unwrap();
+
+        let nonce = Nonce::new(0).to_aes_gcm_nonce();
+        let mut message = b"hello".to_vec();
+        message.extend_from_slice(&[0u8; AES_GCM_TAGSIZE]);
+        vault
+            .aead_encrypt(
+                &initiator_keys.encryption_key,
+                message.as_mut_slice(),
+                nonce.as_ref(),
+                &[],
+            )
+            .await?;
+        let decrypted = vault
+            .aead_decrypt(
+                &responder_keys.decryption_key,
+                message.as_mut_slice(),
+                nonce.as_ref(),
+                &[],
+            )
+            .await?;
+        assert_eq!(decrypted, b"hello");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ik_handshake() -> Result<()> {
+        check_short_handshake(HandshakePattern::IK).await
+    }
+
+    #[tokio::test]
+    async fn test_kk_handshake() -> Result<()> {
+        check_short_handshake(HandshakePattern::KK).await
+    }
+
     // --------------------
     // TESTS IMPLEMENTATION
     // --------------------
@@ -790,6 +1160,85 @@ mod tests {
         Ok(())
     }
 
+    async fn check_short_handshake(handshake_pattern: HandshakePattern) -> Result<()> {
+        let vault = SoftwareVaultForSecureChannels::create().await?;
+
+        let initiator_static_key = vault.generate_static_x25519_secret_key().await?;
+        let initiator_public_key = vault.get_x25519_public_key(&initiator_static_key).await?;
+        let responder_static_key = vault.generate_static_x25519_secret_key().await?;
+        let responder_public_key = vault.get_x25519_public_key(&responder_static_key).await?;
+        let mut initiator = Handshake::new(vault.clone(), initiator_static_key).await?;
+        let mut responder = Handshake::new(vault.clone(), responder_static_key).await?;
+
+        // the responder always starts with a XX handshake
+        responder.initialize().await?;
+
+        let prologue = b"prologue";
+        initiator
+            .initialize_with_known_keys(
+                Role::Initiator,
+                handshake_pattern,
+                prologue,
+                Some(responder_public_key),
+            )
+            .await?;
+        let message1 = initiator
+            .encode_short_message1(handshake_pattern, b"initiator")
+            .await?;
+
+        let known_initiator_key = if handshake_pattern == HandshakePattern::KK {
+            Some(initiator_public_key.clone())
+        } else {
+            None
+        };
+        responder
+            .initialize_with_known_keys(
+                Role::Responder,
+                handshake_pattern,
+                prologue,
+                known_initiator_key,
+            )
+            .await?;
+        let payload = responder
+            .decode_short_message1(handshake_pattern, &message1)
+            .await?;
+        assert_eq!(payload, b"initiator");
+        assert_eq!(responder.state.rs()?, &initiator_public_key);
+
+        let message2 = responder.encode_short_message2(b"responder").await?;
+        let payload = initiator.decode_short_message2(&message2).await?;
+        assert_eq!(payload, b"responder");
+
+        initiator.set_final_state(Role::Initiator).await?;
+        responder.set_final_state(Role::Responder).await?;
+
+        let initiator_keys = initiator.get_handshake_keys().unwrap();
+        let responder_keys = responder.get_handshake_keys().unwrap();
+
+        let nonce = Nonce::new(0).to_aes_gcm_nonce();
+        let mut message = b"hello".to_vec();
+        message.extend_from_slice(&[0u8; AES_GCM_TAGSIZE]);
+        vault
+            .aead_encrypt(
+                &responder_keys.encryption_key,
+                message.as_mut_slice(),
+                nonce.as_ref(),
+                &[],
+            )
+            .await?;
+        let decrypted = vault
+            .aead_decrypt(
+                &initiator_keys.decryption_key,
+                message.as_mut_slice(),
+                nonce.as_ref(),
+                &[],
+            )
+            .await?;
+        assert_eq!(decrypted, b"hello");
+
+        Ok(())
+    }
+
     impl Handshake {
         /// Initialize the handshake
         async fn new_with_keys(
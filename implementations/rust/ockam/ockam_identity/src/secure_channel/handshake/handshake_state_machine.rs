@@ -6,13 +6,16 @@ use ockam_core::compat::string::ToString;
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::vec::Vec;
 use ockam_core::{async_trait, Result};
-use ockam_vault::{AeadSecretKeyHandle, X25519PublicKey};
+use ockam_vault::{
+    AeadSecretKeyHandle, MLKEM768Ciphertext, MLKEM768EncapsulationKey, X25519PublicKey,
+};
 
 use crate::models::{
     ChangeHistory, CredentialAndPurposeKey, PurposeKeyAttestation, PurposePublicKey,
 };
 use crate::{
-    CredentialRetriever, Identifier, Identities, IdentityError, SecureChannelTrustInfo, TrustPolicy,
+    CredentialRetriever, HandshakePattern, Identifier, Identities, IdentityError, KeyExchangeMode,
+    KnownPeers, PaddingPolicy, SecureChannelTrustInfo, TrustPolicy,
 };
 
 /// Interface for a state machine in a key exchange protocol
@@ -61,6 +64,7 @@ pub(crate) struct HandshakeResults {
     pub(super) handshake_keys: HandshakeKeys,
     pub(super) their_identifier: Identifier,
     pub(super) presented_credential: Option<CredentialAndPurposeKey>,
+    pub(super) their_credentials: Vec<CredentialAndPurposeKey>,
 }
 
 /// This struct implements functions common to both initiator and the responder state machines
@@ -71,11 +75,16 @@ pub(crate) struct CommonStateMachine {
     pub(super) credential_retriever: Option<Arc<dyn CredentialRetriever>>,
     pub(super) trust_policy: Arc<dyn TrustPolicy>,
     pub(super) authority: Option<Identifier>, // TODO: Replace with ABAC
+    pub(super) key_exchange_mode: KeyExchangeMode,
+    pub(super) known_peers: KnownPeers,
+    pub(super) padding_policy: PaddingPolicy,
     pub(super) presented_credential: Option<CredentialAndPurposeKey>,
     their_identifier: Option<Identifier>,
+    their_credentials: Vec<CredentialAndPurposeKey>,
 }
 
 impl CommonStateMachine {
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn new(
         identities: Arc<Identities>,
         identifier: Identifier,
@@ -83,6 +92,9 @@ impl CommonStateMachine {
         credential_retriever: Option<Arc<dyn CredentialRetriever>>,
         trust_policy: Arc<dyn TrustPolicy>,
         authority: Option<Identifier>,
+        key_exchange_mode: KeyExchangeMode,
+        known_peers: KnownPeers,
+        padding_policy: PaddingPolicy,
     ) -> Self {
         Self {
             identities,
@@ -91,8 +103,12 @@ impl CommonStateMachine {
             credential_retriever,
             trust_policy,
             authority,
+            key_exchange_mode,
+            known_peers,
+            padding_policy,
             presented_credential: None,
             their_identifier: None,
+            their_credentials: vec![],
         }
     }
 
@@ -102,8 +118,13 @@ impl CommonStateMachine {
     ///  - the current Identity Change History
     ///  - the current Secure Channel Purpose Key Attestation
     ///  - the Identity Credentials and corresponding Credentials Purpose Key Attestations
+    ///  - the ML-KEM-768 ciphertext if the responder accepted a hybrid key exchange
+    ///  - the padding policy of the current party
     ///
-    pub(super) async fn make_identity_payload(&mut self) -> Result<Vec<u8>> {
+    pub(super) async fn make_identity_payload(
+        &mut self,
+        ml_kem_768_ciphertext: Option<MLKEM768Ciphertext>,
+    ) -> Result<Vec<u8>> {
         // prepare the payload that will be sent either in message 2 or message 3
         let change_history = self.identities.get_change_history(&self.identifier).await?;
         let credential = match &self.credential_retriever {
@@ -118,30 +139,45 @@ impl CommonStateMachine {
             change_history,
             purpose_key_attestation: self.purpose_key_attestation.clone(),
             credentials,
+            ml_kem_768_ciphertext,
+            accepts_short_handshakes: Some(true),
+            padding_policy: Some(self.padding_policy),
         };
         ockam_core::cbor_encode_preallocate(payload)
     }
 
     /// Verify the identity sent by the other party: the Purpose Key and the credentials must be valid
     /// If everything is valid, store the identity identifier which will used to make the
-    /// final state machine result
+    /// final state machine result.
+    /// The Purpose Key Attestation is also kept, if the other party supports it, so that the
+    /// next handshakes with the same party can use a IK or KK pattern.
+    /// The handshake fails if the other party uses a different padding policy
     pub(super) async fn process_identity_payload(
         &mut self,
         peer: IdentityAndCredentials,
         peer_public_key: X25519PublicKey,
+        expected_identifier: Option<Identifier>,
     ) -> Result<()> {
+        self.padding_policy.check_peer_policy(peer.padding_policy)?;
+        let purpose_key_attestation = peer.purpose_key_attestation.clone();
+        let accepts_short_handshakes = peer.accepts_short_handshakes.unwrap_or(false);
         let identifier = Self::process_identity_payload_static(
             self.identities.clone(),
             Some(self.trust_policy.clone()),
             self.authority.clone(),
-            None,
+            expected_identifier,
             peer.change_history,
-            peer.credentials,
+            peer.credentials.clone(),
             Some((peer.purpose_key_attestation, peer_public_key)),
         )
         .await?;
 
+        if accepts_short_handshakes {
+            self.known_peers
+                .add_peer(identifier.clone(), purpose_key_attestation);
+        }
         self.their_identifier = Some(identifier);
+        self.their_credentials = peer.credentials;
 
         Ok(())
     }
@@ -158,6 +194,7 @@ impl CommonStateMachine {
                 their_identifier,
                 handshake_keys,
                 presented_credential: self.presented_credential.clone(),
+                their_credentials: self.their_credentials.clone(),
             }),
             _ => None,
         }
@@ -232,7 +269,7 @@ impl CommonStateMachine {
     }
 
     /// Verify that the credentials sent by the other party are valid
-    async fn verify_credentials(
+    pub(crate) async fn verify_credentials(
         identities: Arc<Identities>,
         // TODO: Do we really care if the authority is known here?.
         //       Having Authority's change history in the storage is enough to verify credentials
@@ -305,4 +342,46 @@ pub(super) struct IdentityAndCredentials {
     /// Credentials associated to the identity along with corresponding Credentials Purpose Keys
     /// to verify those Credentials
     #[n(2)] pub(super) credentials: Vec<CredentialAndPurposeKey>,
+    /// ML-KEM-768 ciphertext sent by the responder when it accepts a hybrid key exchange.
+    /// The decapsulated secret is mixed in the chaining key after message 2
+    #[n(3)] pub(super) ml_kem_768_ciphertext: Option<MLKEM768Ciphertext>,
+    /// True if this party accepts IK / KK handshakes. Parties which don't set this flag
+    /// are not stored as known peers
+    #[n(4)] pub(super) accepts_short_handshakes: Option<bool>,
+    /// Padding policy of this party, [`PaddingPolicy::None`] if missing
+    #[n(5)] pub(super) padding_policy: Option<PaddingPolicy>,
+}
+
+/// This internal structure is used as a payload of the message 1, after the initiator
+/// ephemeral public key, when the initiator offers a hybrid key exchange or a IK / KK handshake.
+/// Responders not supporting those options ignore the message 1 payload and run a XX handshake
+#[derive(Debug, Clone, Default, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+pub(super) struct Message1Payload {
+    /// Ephemeral ML-KEM-768 encapsulation key of the initiator
+    #[n(0)] pub(super) ml_kem_768_encapsulation_key: Option<MLKEM768EncapsulationKey>,
+    /// Handshake pattern selected by the initiator, XX if missing
+    #[n(1)] pub(super) handshake_pattern: Option<HandshakePattern>,
+    /// Identifier of the initiator for a KK handshake, used by the responder
+    /// to retrieve the initiator static key
+    #[n(2)] pub(super) initiator_identifier: Option<Identifier>,
+    /// Rest of the IK / KK message 1, after the ephemeral public key.
+    /// Every other field of this structure is mixed as a prologue of the IK / KK handshake
+    #[cbor(n(3), with = "minicbor::bytes")] pub(super) short_message1: Option<Vec<u8>>,
+}
+
+impl Message1Payload {
+    /// Return the handshake pattern selected by the initiator
+    pub(super) fn handshake_pattern(&self) -> HandshakePattern {
+        self.handshake_pattern.unwrap_or_default()
+    }
+
+    /// Return the prologue of a IK / KK handshake: this payload without the handshake message
+    pub(super) fn prologue(&self) -> Result<Vec<u8>> {
+        let prologue = Message1Payload {
+            short_message1: None,
+            ..self.clone()
+        };
+        ockam_core::cbor_encode_preallocate(prologue)
+    }
 }
@@ -4,7 +4,7 @@ use ockam_core::compat::boxed::Box;
 use ockam_core::compat::sync::{Arc, RwLock};
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{
-    AddressMetadata, AllowAll, Any, DenyAll, Error, Mailbox, Mailboxes, NeutralMessage,
+    Address, AddressMetadata, AllowAll, Any, DenyAll, Error, Mailbox, Mailboxes, NeutralMessage,
     OutgoingAccessControl, Route, Routed, SecureChannelMetadata,
 };
 use ockam_core::{Result, Worker};
@@ -14,8 +14,8 @@ use ockam_vault::AeadSecretKeyHandle;
 use tracing::{debug, error, info, trace, warn};
 use tracing_attributes::instrument;
 
-use crate::models::Identifier;
-use crate::secure_channel::decryptor::DecryptorHandler;
+use crate::models::{CredentialAndPurposeKey, Identifier};
+use crate::secure_channel::decryptor::{Decryptor, DecryptorHandler};
 use crate::secure_channel::encryptor::Encryptor;
 use crate::secure_channel::encryptor_worker::{
     EncryptorWorker, RemoteRoute, SecureChannelSharedState,
@@ -29,11 +29,12 @@ use crate::secure_channel::handshake::handshake_state_machine::{
 };
 use crate::secure_channel::handshake::initiator_state_machine::InitiatorStateMachine;
 use crate::secure_channel::handshake::responder_state_machine::ResponderStateMachine;
-use crate::secure_channel::{Addresses, Role};
+use crate::secure_channel::nonce_reservation::{NonceReservation, ReservationSide};
+use crate::secure_channel::{Addresses, PaddingPolicy, RekeyPolicy, Role};
 use crate::{
-    ChangeHistoryRepository, CredentialRetriever, IdentityError, PersistedSecureChannel,
-    SecureChannelPurposeKey, SecureChannelRegistryEntry, SecureChannelRepository, SecureChannels,
-    TrustPolicy,
+    ChangeHistoryRepository, CredentialRetriever, HandshakePattern, IdentityError, KeyExchangeMode,
+    PersistedSecureChannel, PersistedSecureChannelState, SecureChannelPurposeKey,
+    SecureChannelRegistryEntry, SecureChannelRepository, SecureChannels, TrustPolicy,
 };
 
 /// This struct implements a Worker receiving and sending messages
@@ -55,6 +56,10 @@ pub(crate) struct HandshakeWorker {
     credential_retriever: Option<Arc<dyn CredentialRetriever>>,
 
     secure_channel_repository: Option<Arc<dyn SecureChannelRepository>>,
+    // Listener which spawned a responder secure channel, persisted to resume the channel
+    listener_address: Option<Address>,
+    rekey_policy: RekeyPolicy,
+    padding_policy: PaddingPolicy,
 
     shared_state: SecureChannelSharedState,
 }
@@ -146,7 +151,13 @@ impl HandshakeWorker {
         timeout: Option<Duration>,
         role: Role,
         key_exchange_only: bool,
+        key_exchange_mode: KeyExchangeMode,
+        handshake_pattern: HandshakePattern,
+        known_responder: Option<Identifier>,
+        rekey_policy: RekeyPolicy,
+        padding_policy: PaddingPolicy,
         secure_channel_repository: Option<Arc<dyn SecureChannelRepository>>,
+        listener_address: Option<Address>,
         encryptor_remote_route: Arc<RwLock<RemoteRoute>>,
     ) -> Result<Option<Identifier>> {
         let vault = secure_channels.identities.vault().secure_channel_vault;
@@ -162,6 +173,11 @@ impl HandshakeWorker {
                     credential_retriever.clone(),
                     trust_policy,
                     authority.clone(),
+                    key_exchange_mode,
+                    secure_channels.known_peers(),
+                    padding_policy,
+                    handshake_pattern,
+                    known_responder,
                 )
                 .await?,
             )
@@ -175,6 +191,9 @@ impl HandshakeWorker {
                     credential_retriever.clone(),
                     trust_policy,
                     authority.clone(),
+                    key_exchange_mode,
+                    secure_channels.known_peers(),
+                    padding_policy,
                 )
                 .await?,
             )
@@ -187,9 +206,13 @@ impl HandshakeWorker {
             (None, None)
         };
 
+        // A persistent secure channel is not closed when its node stops, since it can be resumed
+        // after a restart
+        let is_resumable = !key_exchange_only && secure_channel_repository.is_some();
         let shared_state = SecureChannelSharedState {
-            should_send_close: Arc::new(AtomicBool::new(true)),
+            should_send_close: Arc::new(AtomicBool::new(!is_resumable)),
             remote_route: encryptor_remote_route,
+            rekey_metrics: Default::default(),
         };
         let worker = Self {
             secure_channels,
@@ -205,6 +228,9 @@ impl HandshakeWorker {
             authority,
             change_history_repository: identities.change_history_repository(),
             secure_channel_repository,
+            listener_address,
+            rekey_policy,
+            padding_policy,
             shared_state,
         };
 
@@ -276,19 +302,20 @@ impl HandshakeWorker {
         let return_route = message.return_route;
         let payload = message.payload;
 
-        if let SendMessage(send_message) = self
+        let action = self
             .state_machine
             .as_mut()
             .ok_or(IdentityError::HandshakeInternalError)?
             .on_event(ReceivedMessage(payload))
-            .await?
-        {
-            // set the remote route by taking the most up to date message return route
-            // In the case of the initiator the first return route mentions the secure channel listener
-            // address so we need to wait for the return route corresponding to the remote handshake worker
-            // when it has been spawned
-            self.remote_route = Some(return_route);
+            .await?;
+
+        // set the remote route by taking the most up to date message return route
+        // In the case of the initiator the first return route mentions the secure channel listener
+        // address so we need to wait for the return route corresponding to the remote handshake worker
+        // when it has been spawned
+        self.remote_route = Some(return_route);
 
+        if let SendMessage(send_message) = action {
             context
                 .send_from_address(
                     self.remote_route()?,
@@ -388,6 +415,45 @@ impl HandshakeWorker {
         Mailboxes::new(remote_mailbox, vec![internal_mailbox, api_mailbox])
     }
 
+    /// Start an encryptor worker with its mailboxes
+    pub(crate) fn start_encryptor_worker(
+        context: &Context,
+        addresses: &Addresses,
+        their_identifier: &Identifier,
+        encryptor: EncryptorWorker,
+    ) -> Result<()> {
+        let main_mailbox = Mailbox::new(
+            addresses.encryptor.clone(),
+            Some(AddressMetadata {
+                is_terminal: true,
+                attributes: vec![SecureChannelMetadata::attribute(
+                    their_identifier.clone().into(),
+                )],
+            }),
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        );
+        let api_mailbox = Mailbox::new(
+            addresses.encryptor_api.clone(),
+            None,
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        );
+        let internal_mailbox = Mailbox::new(
+            addresses.encryptor_internal.clone(),
+            None,
+            Arc::new(AllowAll),
+            Arc::new(DenyAll),
+        );
+
+        WorkerBuilder::new(encryptor)
+            .with_mailboxes(Mailboxes::new(
+                main_mailbox,
+                vec![api_mailbox, internal_mailbox],
+            ))
+            .start(context)
+    }
+
     /// Finalize the handshake by creating a `Decryptor` and an `EncryptorWorker`
     /// Note that `EncryptorWorker` is actually started as an independent worker while
     /// the `Decryptor` is directly used by this worker to delegate the decryption of messages
@@ -398,6 +464,18 @@ impl HandshakeWorker {
     ) -> Result<DecryptorHandler> {
         let their_identifier = handshake_results.their_identifier.clone();
 
+        self.shared_state.remote_route.write().unwrap().route = self.remote_route()?;
+
+        // the persisted secure channel must exist before any nonce reservation is saved
+        let nonce_reservations = self
+            .persist(
+                their_identifier.clone(),
+                &handshake_results.handshake_keys.encryption_key,
+                &handshake_results.handshake_keys.decryption_key,
+                handshake_results.their_credentials,
+            )
+            .await;
+
         // create a decryptor to delegate the processing of all messages after the handshake
         let decryptor = DecryptorHandler::new(
             self.secure_channels.identities.clone(),
@@ -410,6 +488,21 @@ impl HandshakeWorker {
             handshake_results.their_identifier.clone(),
             self.shared_state.clone(),
         );
+        let (decryptor, encryptor_nonce_reservation) = match nonce_reservations {
+            Some((encryptor_nonce_reservation, decryptor_nonce_reservation)) => {
+                let decryptor_handler = decryptor.with_decryptor(
+                    Decryptor::new(
+                        handshake_results.handshake_keys.decryption_key.clone(),
+                        self.secure_channels.identities.vault().secure_channel_vault,
+                    )
+                    .with_nonce_reservation(decryptor_nonce_reservation)
+                    .with_rekey_metrics(self.shared_state.rekey_metrics.clone()),
+                );
+                (decryptor_handler, Some(encryptor_nonce_reservation))
+            }
+            None => (decryptor, None),
+        };
+        let decryptor = decryptor.with_padding_policy(self.padding_policy);
 
         // create a separate encryptor worker which will be started independently
         {
@@ -420,62 +513,34 @@ impl HandshakeWorker {
                 (true, self.credential_retriever.clone())
             };
 
-            self.shared_state.remote_route.write().unwrap().route = self.remote_route()?;
+            let mut encryptor = Encryptor::new(
+                handshake_results.handshake_keys.encryption_key,
+                0.into(),
+                self.secure_channels.identities.vault().secure_channel_vault,
+                rekeying,
+            )
+            .with_rekey_policy(self.rekey_policy)?
+            .with_rekey_metrics(self.shared_state.rekey_metrics.clone());
+            if let Some(encryptor_nonce_reservation) = encryptor_nonce_reservation {
+                encryptor = encryptor.with_nonce_reservation(encryptor_nonce_reservation);
+            }
+
             let encryptor = EncryptorWorker::new(
                 self.role.str(),
                 self.key_exchange_only,
                 self.addresses.clone(),
-                Encryptor::new(
-                    handshake_results.handshake_keys.encryption_key,
-                    0.into(),
-                    self.secure_channels.identities.vault().secure_channel_vault,
-                    rekeying,
-                ),
+                encryptor,
                 self.my_identifier.clone(),
                 self.change_history_repository.clone(),
                 credential_retriever,
                 handshake_results.presented_credential,
                 self.shared_state.clone(),
-            );
-
-            let main_mailbox = Mailbox::new(
-                self.addresses.encryptor.clone(),
-                Some(AddressMetadata {
-                    is_terminal: true,
-                    attributes: vec![SecureChannelMetadata::attribute(
-                        their_identifier.clone().into(),
-                    )],
-                }),
-                Arc::new(AllowAll),
-                Arc::new(AllowAll),
-            );
-            let api_mailbox = Mailbox::new(
-                self.addresses.encryptor_api.clone(),
-                None,
-                Arc::new(AllowAll),
-                Arc::new(AllowAll),
-            );
-            let internal_mailbox = Mailbox::new(
-                self.addresses.encryptor_internal.clone(),
-                None,
-                Arc::new(AllowAll),
-                Arc::new(DenyAll),
-            );
+            )
+            .with_padding_policy(self.padding_policy);
 
-            WorkerBuilder::new(encryptor)
-                .with_mailboxes(Mailboxes::new(
-                    main_mailbox,
-                    vec![api_mailbox, internal_mailbox],
-                ))
-                .start(context)?;
+            Self::start_encryptor_worker(context, &self.addresses, &their_identifier, encryptor)?;
         }
 
-        self.persist(
-            their_identifier,
-            &handshake_results.handshake_keys.decryption_key,
-        )
-        .await;
-
         info!(
             local = %self.addresses.encryptor, remote = %self.addresses.decryptor_remote,
             "initialized SecureChannel {}",
@@ -498,7 +563,8 @@ impl HandshakeWorker {
             self.my_identifier.clone(),
             handshake_results.their_identifier,
             their_decryptor_address,
-        );
+        )
+        .with_rekey_metrics(self.shared_state.rekey_metrics.clone());
 
         self.secure_channels
             .secure_channel_registry()
@@ -507,14 +573,24 @@ impl HandshakeWorker {
         Ok(decryptor)
     }
 
-    async fn persist(&self, their_identifier: Identifier, decryption_key: &AeadSecretKeyHandle) {
+    /// Persist the secure channel.
+    /// For a regular secure channel return the nonce reservations which must be used to
+    /// save the encryptor and decryptor state while the channel is used
+    async fn persist(
+        &self,
+        their_identifier: Identifier,
+        encryption_key: &AeadSecretKeyHandle,
+        decryption_key: &AeadSecretKeyHandle,
+        their_credentials: Vec<CredentialAndPurposeKey>,
+    ) -> Option<(NonceReservation, NonceReservation)> {
         let Some(repository) = &self.secure_channel_repository else {
             debug!(local = %self.addresses.encryptor, remote = %self.addresses.decryptor_remote,
                 "Skipping persistence. No repository provided");
-            return;
+            return None;
         };
 
-        let sc = PersistedSecureChannel::new(
+        let vault = self.secure_channels.identities.vault().secure_channel_vault;
+        let mut sc = PersistedSecureChannel::new(
             self.role,
             self.my_identifier.clone(),
             their_identifier,
@@ -522,6 +598,47 @@ impl HandshakeWorker {
             self.addresses.decryptor_api.clone(),
             decryption_key.clone(),
         );
+
+        let their_decryptor_remote = match self.remote_route() {
+            Ok(remote_route) => remote_route.recipient().ok().cloned(),
+            Err(_) => None,
+        };
+        if !self.key_exchange_only {
+            let Some(their_decryptor_remote) = their_decryptor_remote else {
+                warn!(local = %self.addresses.encryptor, remote = %self.addresses.decryptor_remote,
+                    "Skipping persistence. The remote decryptor address is unknown");
+                return None;
+            };
+
+            sc = sc.with_state(
+                PersistedSecureChannelState::new(
+                    self.addresses.encryptor.clone(),
+                    their_decryptor_remote,
+                    self.authority.clone(),
+                    encryption_key.clone(),
+                    0,
+                    0.into(),
+                    0,
+                    0.into(),
+                )
+                .with_their_credentials(their_credentials)
+                .with_listener(self.listener_address.clone()),
+            );
+        };
+
+        // keys are persisted first so that a persisted secure channel always refers to existing keys
+        let mut keys = vec![decryption_key];
+        if !self.key_exchange_only {
+            keys.push(encryption_key);
+        }
+        for key in keys {
+            if let Err(err) = vault.persist_aead_key(key).await {
+                warn!(local = %self.addresses.encryptor, remote = %self.addresses.decryptor_remote, %err,
+                    "Error persisting secure channel key");
+                return None;
+            };
+        }
+
         match repository.put(sc).await {
             Ok(_) => {
                 info!(local = %self.addresses.encryptor, remote = %self.addresses.decryptor_remote,
@@ -530,21 +647,32 @@ impl HandshakeWorker {
             Err(err) => {
                 warn!(local = %self.addresses.encryptor, remote = %self.addresses.decryptor_remote, %err,
                     "Error while persisting secure channel");
-                return;
+                return None;
             }
         }
 
-        if let Err(err) = self
-            .secure_channels
-            .identities
-            .vault()
-            .secure_channel_vault
-            .persist_aead_key(decryption_key)
-            .await
-        {
-            warn!(local = %self.addresses.encryptor, remote = %self.addresses.decryptor_remote, %err,
-                "Error persisting secure channel key");
-        };
+        if self.key_exchange_only {
+            return None;
+        }
+
+        Some((
+            NonceReservation::new(
+                ReservationSide::Encryptor,
+                repository.clone(),
+                vault.clone(),
+                self.addresses.decryptor_remote.clone(),
+                Some(encryption_key.clone()),
+                0.into(),
+            ),
+            NonceReservation::new(
+                ReservationSide::Decryptor,
+                repository.clone(),
+                vault,
+                self.addresses.decryptor_remote.clone(),
+                Some(decryption_key.clone()),
+                0.into(),
+            ),
+        ))
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -578,6 +706,9 @@ impl HandshakeWorker {
             change_history_repository,
             credential_retriever,
             secure_channel_repository,
+            listener_address: None,
+            rekey_policy: RekeyPolicy::default(),
+            padding_policy: PaddingPolicy::default(),
             shared_state,
         }
     }
@@ -4,7 +4,11 @@ use ockam_core::compat::sync::Arc;
 use ockam_core::compat::{boxed::Box, vec::Vec};
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{Error, Result};
-use ockam_vault::{VaultForSecureChannels, X25519PublicKey};
+use ockam_vault::{
+    MLKEM768Ciphertext, MLKEM768EncapsulationKey, VaultForSecureChannels, X25519PublicKey,
+    X25519_PUBLIC_KEY_LENGTH,
+};
+use tracing::debug;
 use Action::*;
 use Event::*;
 use Role::*;
@@ -15,9 +19,12 @@ use crate::secure_channel::handshake::error::XXError;
 use crate::secure_channel::handshake::handshake::Handshake;
 use crate::secure_channel::handshake::handshake_state_machine::{
     Action, CommonStateMachine, Event, HandshakeKeys, HandshakeResults, IdentityAndCredentials,
-    StateMachine, Status,
+    Message1Payload, StateMachine, Status,
+};
+use crate::{
+    CredentialRetriever, HandshakePattern, Identities, KeyExchangeMode, KnownPeers, PaddingPolicy,
+    Role, SecureChannelPurposeKey, TrustPolicy,
 };
-use crate::{CredentialRetriever, Identities, Role, SecureChannelPurposeKey, TrustPolicy};
 
 /// Implementation of a state machine for the key exchange on the initiator side
 #[async_trait]
@@ -27,26 +34,65 @@ impl StateMachine for InitiatorStateMachine {
         match (state.status, event) {
             // Initialize the handshake and send message 1
             (Initial, Initialize) => {
-                self.initialize_handshake().await?;
-                let message1 = self.encode_message1(&[]).await?;
+                let message1 = match self.get_known_responder_static_key().await {
+                    Some(responder_static_key) => {
+                        self.make_short_message1(responder_static_key).await?
+                    }
+                    None => {
+                        self.initialize_handshake().await?;
+                        let message1_payload = self.make_message1_payload().await?;
+                        let message1_payload =
+                            if message1_payload.ml_kem_768_encapsulation_key.is_some() {
+                                ockam_core::cbor_encode_preallocate(message1_payload)?
+                            } else {
+                                vec![]
+                            };
+                        self.encode_message1(&message1_payload).await?
+                    }
+                };
 
                 // Send message 1 and wait for message 2
                 self.handshake.state.status = WaitingForMessage2;
                 Ok(SendMessage(message1))
             }
+            // Process the last message of a IK / KK handshake
+            (WaitingForMessage2, ReceivedMessage(message))
+                if self.handshake_pattern.requires_known_responder() =>
+            {
+                let message2_payload = self.decode_short_message2(&message).await?;
+                let their_identity_payload: IdentityAndCredentials =
+                    minicbor::decode(&message2_payload)?;
+                self.process_ml_kem_768_ciphertext(
+                    their_identity_payload.ml_kem_768_ciphertext.as_ref(),
+                )
+                .await?;
+                self.process_identity_payload(
+                    their_identity_payload,
+                    self.handshake.state.rs()?.clone(),
+                    self.known_responder.clone(),
+                )
+                .await?;
+                self.set_final_state(Initiator).await?;
+                Ok(NoAction)
+            }
             // Process message 2 and send message 3
             (WaitingForMessage2, ReceivedMessage(message)) => {
                 let message2_payload = self.decode_message2(&message).await?;
                 let their_identity_payload: IdentityAndCredentials =
                     minicbor::decode(&message2_payload)?;
+                self.process_ml_kem_768_ciphertext(
+                    their_identity_payload.ml_kem_768_ciphertext.as_ref(),
+                )
+                .await?;
                 self.process_identity_payload(
                     their_identity_payload,
                     self.handshake.state.rs()?.clone(),
+                    None,
                 )
                 .await?;
                 let identity_payload = self
                     .common
-                    .make_identity_payload()
+                    .make_identity_payload(None)
                     .await
                     .map_err(|_e| XXError::InvalidInternalState)?;
                 let message3 = self.encode_message3(&identity_payload).await?;
@@ -74,12 +120,14 @@ impl StateMachine for InitiatorStateMachine {
 pub(super) struct InitiatorStateMachine {
     pub(super) common: CommonStateMachine,
     pub(super) handshake: Handshake,
+    handshake_pattern: HandshakePattern,
+    known_responder: Option<Identifier>,
 }
 
 impl InitiatorStateMachine {
     delegate! {
         to self.common {
-            async fn process_identity_payload(&mut self, peer: IdentityAndCredentials, peer_public_key: X25519PublicKey) -> Result<()>;
+            async fn process_identity_payload(&mut self, peer: IdentityAndCredentials, peer_public_key: X25519PublicKey, expected_identifier: Option<Identifier>) -> Result<()>;
             fn make_handshake_results(&self, handshake_keys: Option<HandshakeKeys>) -> Option<HandshakeResults>;
         }
     }
@@ -87,8 +135,14 @@ impl InitiatorStateMachine {
         to self.handshake {
             #[call(initialize)]
             async fn initialize_handshake(&mut self) -> Result<()>;
+            async fn initialize_with_known_keys(&mut self, role: Role, handshake_pattern: HandshakePattern, prologue: &[u8], rs: Option<X25519PublicKey>) -> Result<()>;
             async fn encode_message1(&mut self, payload: &[u8]) -> Result<Vec<u8>>;
+            async fn encode_short_message1(&mut self, handshake_pattern: HandshakePattern, payload: &[u8]) -> Result<Vec<u8>>;
             async fn decode_message2(&mut self, message: &[u8]) -> Result<Vec<u8>>;
+            async fn decode_short_message2(&mut self, message: &[u8]) -> Result<Vec<u8>>;
+            async fn generate_ml_kem_768_key(&mut self) -> Result<MLKEM768EncapsulationKey>;
+            async fn decapsulate_ml_kem_768_secret(&mut self, ciphertext: &MLKEM768Ciphertext) -> Result<()>;
+            async fn mix_ml_kem_768_secret(&mut self) -> Result<()>;
             async fn encode_message3(&mut self, payload: &[u8]) -> Result<Vec<u8>>;
             async fn set_final_state(&mut self, role: Role) -> Result<()>;
             fn get_handshake_keys(&self) -> Option<HandshakeKeys>;
@@ -97,6 +151,121 @@ impl InitiatorStateMachine {
 }
 
 impl InitiatorStateMachine {
+    /// Prepare the payload of message 1.
+    /// That payload contains an ML-KEM-768 encapsulation key if a hybrid key exchange is offered
+    /// to the responder
+    async fn make_message1_payload(&mut self) -> Result<Message1Payload> {
+        let key_exchange_mode = self.common.key_exchange_mode;
+        let ml_kem_768_encapsulation_key = if key_exchange_mode.is_hybrid() {
+            match self.generate_ml_kem_768_key().await {
+                Ok(encapsulation_key) => Some(encapsulation_key),
+                // only offer the classic key exchange if the vault doesn't support ML-KEM-768
+                Err(err)
+                    if err.code().kind == Kind::Unsupported
+                        && !key_exchange_mode.is_hybrid_required() =>
+                {
+                    debug!(%err, "the vault doesn't support ML-KEM-768, falling back to a classic key exchange");
+                    None
+                }
+                Err(err) => return Err(err),
+            }
+        } else {
+            None
+        };
+
+        Ok(Message1Payload {
+            ml_kem_768_encapsulation_key,
+            ..Default::default()
+        })
+    }
+
+    /// Return the static key of the responder if it is known and a IK or KK handshake
+    /// has been requested. Otherwise fall back to a XX handshake
+    async fn get_known_responder_static_key(&mut self) -> Option<X25519PublicKey> {
+        if !self.handshake_pattern.requires_known_responder() {
+            return None;
+        }
+
+        let responder = self.known_responder.as_ref()?;
+        let static_key = match self
+            .common
+            .known_peers
+            .get_static_key(&self.common.identities, responder)
+            .await
+        {
+            Ok(static_key) => static_key,
+            Err(err) => {
+                debug!(%responder, %err, "the static key of the responder can't be verified");
+                None
+            }
+        };
+
+        if static_key.is_none() {
+            debug!(%responder, "the static key of the responder is unknown, falling back to a XX handshake");
+            self.handshake_pattern = HandshakePattern::XX;
+        }
+        static_key
+    }
+
+    /// Prepare the first message of a IK or KK handshake.
+    /// That message contains the initiator ephemeral public key followed by a CBOR payload
+    /// containing the handshake pattern, the hybrid key exchange offer and the rest of
+    /// the handshake message
+    async fn make_short_message1(
+        &mut self,
+        responder_static_key: X25519PublicKey,
+    ) -> Result<Vec<u8>> {
+        let handshake_pattern = self.handshake_pattern;
+        let mut message1_payload = self.make_message1_payload().await?;
+        message1_payload.handshake_pattern = Some(handshake_pattern);
+        if handshake_pattern == HandshakePattern::KK {
+            message1_payload.initiator_identifier = Some(self.common.identifier.clone());
+        }
+
+        self.initialize_with_known_keys(
+            Initiator,
+            handshake_pattern,
+            &message1_payload.prologue()?,
+            Some(responder_static_key),
+        )
+        .await?;
+        let identity_payload = self
+            .common
+            .make_identity_payload(None)
+            .await
+            .map_err(|_e| XXError::InvalidInternalState)?;
+        let short_message1 = self
+            .encode_short_message1(handshake_pattern, &identity_payload)
+            .await?;
+
+        let (e_pub_key, rest) = short_message1.split_at(X25519_PUBLIC_KEY_LENGTH);
+        message1_payload.short_message1 = Some(rest.to_vec());
+        let mut message1 = e_pub_key.to_vec();
+        message1.extend(ockam_core::cbor_encode_preallocate(message1_payload)?);
+        Ok(message1)
+    }
+
+    /// Mix the ML-KEM-768 shared secret if the responder accepted the hybrid key exchange.
+    /// Otherwise check that falling back to the classic key exchange is allowed
+    async fn process_ml_kem_768_ciphertext(
+        &mut self,
+        ciphertext: Option<&MLKEM768Ciphertext>,
+    ) -> Result<()> {
+        match ciphertext {
+            Some(ciphertext) => {
+                self.decapsulate_ml_kem_768_secret(ciphertext).await?;
+                self.mix_ml_kem_768_secret().await
+            }
+            None if self.common.key_exchange_mode.is_hybrid_required() => {
+                Err(XXError::HybridKeyExchangeRequired)?
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl InitiatorStateMachine {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         vault: Arc<dyn VaultForSecureChannels>,
         identities: Arc<Identities>,
@@ -105,6 +274,11 @@ impl InitiatorStateMachine {
         credential_retriever: Option<Arc<dyn CredentialRetriever>>,
         trust_policy: Arc<dyn TrustPolicy>,
         authority: Option<Identifier>,
+        key_exchange_mode: KeyExchangeMode,
+        known_peers: KnownPeers,
+        padding_policy: PaddingPolicy,
+        handshake_pattern: HandshakePattern,
+        known_responder: Option<Identifier>,
     ) -> Result<InitiatorStateMachine> {
         let common = CommonStateMachine::new(
             identities,
@@ -113,11 +287,16 @@ impl InitiatorStateMachine {
             credential_retriever,
             trust_policy,
             authority,
+            key_exchange_mode,
+            known_peers,
+            padding_policy,
         );
 
         Ok(InitiatorStateMachine {
             common,
             handshake: Handshake::new(vault, purpose_key.key().clone()).await?,
+            handshake_pattern,
+            known_responder,
         })
     }
 }
@@ -4,7 +4,11 @@ use ockam_core::compat::sync::Arc;
 use ockam_core::compat::{boxed::Box, vec::Vec};
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{Error, Result};
-use ockam_vault::{VaultForSecureChannels, X25519PublicKey};
+use ockam_vault::{
+    MLKEM768Ciphertext, MLKEM768EncapsulationKey, VaultForSecureChannels, X25519PublicKey,
+    X25519_PUBLIC_KEY_LENGTH,
+};
+use tracing::debug;
 use Action::*;
 use Event::*;
 use Role::*;
@@ -15,9 +19,12 @@ use crate::secure_channel::handshake::error::XXError;
 use crate::secure_channel::handshake::handshake::Handshake;
 use crate::secure_channel::handshake::handshake_state_machine::{
     Action, CommonStateMachine, Event, HandshakeKeys, HandshakeResults, IdentityAndCredentials,
-    StateMachine, Status,
+    Message1Payload, StateMachine, Status,
+};
+use crate::{
+    CredentialRetriever, HandshakePattern, Identities, KeyExchangeMode, KnownPeers, PaddingPolicy,
+    Role, SecureChannelPurposeKey, TrustPolicy,
 };
-use crate::{CredentialRetriever, Identities, Role, SecureChannelPurposeKey, TrustPolicy};
 
 /// Implementation of a state machine for the key exchange on the responder side
 #[async_trait]
@@ -33,16 +40,39 @@ impl StateMachine for ResponderStateMachine {
             }
             // Process message 1 and send message 2
             (WaitingForMessage1, ReceivedMessage(message)) => {
-                self.decode_message1(&message).await?;
-                let identity_payload = self
-                    .common
-                    .make_identity_payload()
-                    .await
-                    .map_err(|_e| XXError::InvalidInternalState)?;
-                let message2 = self.encode_message2(&identity_payload).await?;
-
-                self.handshake.state.status = WaitingForMessage3;
-                Ok(SendMessage(message2))
+                let message1_payload = Self::decode_message1_payload(&message);
+                match message1_payload {
+                    // Process the first message of a IK / KK handshake and send the last message
+                    Some(message1_payload)
+                        if message1_payload
+                            .handshake_pattern()
+                            .requires_known_responder() =>
+                    {
+                        let message2 = self
+                            .process_short_message1(&message, message1_payload)
+                            .await?;
+                        Ok(SendMessage(message2))
+                    }
+                    message1_payload => {
+                        self.decode_message1(&message).await?;
+                        let ml_kem_768_ciphertext = self
+                            .process_message1_payload(message1_payload.as_ref())
+                            .await?;
+                        let is_hybrid = ml_kem_768_ciphertext.is_some();
+                        let identity_payload = self
+                            .common
+                            .make_identity_payload(ml_kem_768_ciphertext)
+                            .await
+                            .map_err(|_e| XXError::InvalidInternalState)?;
+                        let message2 = self.encode_message2(&identity_payload).await?;
+                        if is_hybrid {
+                            self.mix_ml_kem_768_secret().await?;
+                        }
+
+                        self.handshake.state.status = WaitingForMessage3;
+                        Ok(SendMessage(message2))
+                    }
+                }
             }
             // Process message 3
             (WaitingForMessage3, ReceivedMessage(message)) => {
@@ -52,6 +82,7 @@ impl StateMachine for ResponderStateMachine {
                 self.process_identity_payload(
                     their_identity_payload,
                     self.handshake.state.rs()?.clone(),
+                    None,
                 )
                 .await?;
                 self.set_final_state(Responder).await?;
@@ -82,7 +113,7 @@ pub struct ResponderStateMachine {
 impl ResponderStateMachine {
     delegate! {
         to self.common {
-            async fn process_identity_payload(&mut self, peer: IdentityAndCredentials, peer_public_key: X25519PublicKey) -> Result<()>;
+            async fn process_identity_payload(&mut self, peer: IdentityAndCredentials, peer_public_key: X25519PublicKey, expected_identifier: Option<Identifier>) -> Result<()>;
             fn make_handshake_results(&self, handshake_keys: Option<HandshakeKeys>) -> Option<HandshakeResults>;
         }
     }
@@ -90,8 +121,13 @@ impl ResponderStateMachine {
         to self.handshake {
             #[call(initialize)]
             async fn initialize_handshake(&mut self) -> Result<()>;
+            async fn initialize_with_known_keys(&mut self, role: Role, handshake_pattern: HandshakePattern, prologue: &[u8], rs: Option<X25519PublicKey>) -> Result<()>;
             async fn decode_message1(&mut self, message: &[u8]) -> Result<Vec<u8>>;
+            async fn decode_short_message1(&mut self, handshake_pattern: HandshakePattern, message: &[u8]) -> Result<Vec<u8>>;
             async fn encode_message2(&mut self, payload: &[u8]) -> Result<Vec<u8>>;
+            async fn encode_short_message2(&mut self, payload: &[u8]) -> Result<Vec<u8>>;
+            async fn encapsulate_ml_kem_768_secret(&mut self, encapsulation_key: &MLKEM768EncapsulationKey) -> Result<MLKEM768Ciphertext>;
+            async fn mix_ml_kem_768_secret(&mut self) -> Result<()>;
             async fn decode_message3(&mut self, message: &[u8]) -> Result<Vec<u8>>;
             async fn set_final_state(&mut self, role: Role) -> Result<()>;
             fn get_handshake_keys(&self) -> Option<HandshakeKeys>;
@@ -100,6 +136,128 @@ impl ResponderStateMachine {
 }
 
 impl ResponderStateMachine {
+    /// Decode the payload of message 1 sent after the initiator ephemeral public key.
+    /// That payload is empty, or unknown, if the initiator doesn't offer any handshake option
+    fn decode_message1_payload(message1: &[u8]) -> Option<Message1Payload> {
+        let payload = message1.get(X25519_PUBLIC_KEY_LENGTH..)?;
+        if payload.is_empty() {
+            return None;
+        }
+        minicbor::decode(payload).ok()
+    }
+
+    /// Process the hybrid key exchange offer of the message 1 payload, if the initiator
+    /// supports it.
+    /// Return the ML-KEM-768 ciphertext to send back if that offer is accepted
+    async fn process_message1_payload(
+        &mut self,
+        message1_payload: Option<&Message1Payload>,
+    ) -> Result<Option<MLKEM768Ciphertext>> {
+        let key_exchange_mode = self.common.key_exchange_mode;
+
+        // the hybrid key exchange is ignored if it is not enabled on this side
+        if !key_exchange_mode.is_hybrid() {
+            return Ok(None);
+        }
+
+        match message1_payload.and_then(|p| p.ml_kem_768_encapsulation_key.as_ref()) {
+            Some(encapsulation_key) => {
+                match self.encapsulate_ml_kem_768_secret(encapsulation_key).await {
+                    Ok(ciphertext) => Ok(Some(ciphertext)),
+                    // decline the offer if the vault doesn't support ML-KEM-768
+                    Err(err)
+                        if err.code().kind == Kind::Unsupported
+                            && !key_exchange_mode.is_hybrid_required() =>
+                    {
+                        debug!(%err, "the vault doesn't support ML-KEM-768, falling back to a classic key exchange");
+                        Ok(None)
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+            None if key_exchange_mode.is_hybrid_required() => {
+                Err(XXError::HybridKeyExchangeRequired)?
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Process the first message of a IK or KK handshake and return the second, and last, message.
+    /// For a KK handshake the initiator static key must have been stored during a previous handshake
+    async fn process_short_message1(
+        &mut self,
+        message: &[u8],
+        message1_payload: Message1Payload,
+    ) -> Result<Vec<u8>> {
+        let handshake_pattern = message1_payload.handshake_pattern();
+        let initiator_static_key = if handshake_pattern == HandshakePattern::KK {
+            let initiator = message1_payload
+                .initiator_identifier
+                .as_ref()
+                .ok_or(XXError::UnknownStaticKey)?;
+            let static_key = self
+                .common
+                .known_peers
+                .get_static_key(&self.common.identities, initiator)
+                .await?
+                .ok_or(XXError::UnknownStaticKey)?;
+            Some(static_key)
+        } else {
+            None
+        };
+
+        self.initialize_with_known_keys(
+            Responder,
+            handshake_pattern,
+            &message1_payload.prologue()?,
+            initiator_static_key,
+        )
+        .await?;
+
+        // rebuild the handshake message: e.pubKey followed by the rest of the message
+        let mut message1 = message
+            .get(..X25519_PUBLIC_KEY_LENGTH)
+            .ok_or(XXError::MessageLenMismatch)?
+            .to_vec();
+        message1.extend_from_slice(
+            message1_payload
+                .short_message1
+                .as_ref()
+                .ok_or(XXError::MessageLenMismatch)?,
+        );
+        let short_message1_payload = self
+            .decode_short_message1(handshake_pattern, &message1)
+            .await?;
+        let their_identity_payload: IdentityAndCredentials =
+            minicbor::decode(&short_message1_payload)?;
+        self.process_identity_payload(
+            their_identity_payload,
+            self.handshake.state.rs()?.clone(),
+            message1_payload.initiator_identifier.clone(),
+        )
+        .await?;
+
+        let ml_kem_768_ciphertext = self
+            .process_message1_payload(Some(&message1_payload))
+            .await?;
+        let is_hybrid = ml_kem_768_ciphertext.is_some();
+        let identity_payload = self
+            .common
+            .make_identity_payload(ml_kem_768_ciphertext)
+            .await
+            .map_err(|_e| XXError::InvalidInternalState)?;
+        let message2 = self.encode_short_message2(&identity_payload).await?;
+        if is_hybrid {
+            self.mix_ml_kem_768_secret().await?;
+        }
+
+        self.set_final_state(Responder).await?;
+        Ok(message2)
+    }
+}
+
+impl ResponderStateMachine {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         vault: Arc<dyn VaultForSecureChannels>,
         identities: Arc<Identities>,
@@ -108,6 +266,9 @@ impl ResponderStateMachine {
         credential_retriever: Option<Arc<dyn CredentialRetriever>>,
         trust_policy: Arc<dyn TrustPolicy>,
         authority: Option<Identifier>,
+        key_exchange_mode: KeyExchangeMode,
+        known_peers: KnownPeers,
+        padding_policy: PaddingPolicy,
     ) -> Result<ResponderStateMachine> {
         let common = CommonStateMachine::new(
             identities,
@@ -116,6 +277,9 @@ impl ResponderStateMachine {
             credential_retriever,
             trust_policy,
             authority,
+            key_exchange_mode,
+            known_peers,
+            padding_policy,
         );
 
         Ok(ResponderStateMachine {
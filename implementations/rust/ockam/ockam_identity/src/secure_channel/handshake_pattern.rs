@@ -0,0 +1,42 @@
+use minicbor::{CborLen, Decode, Encode};
+
+use crate::secure_channel::handshake::handshake::PROTOCOL_NAME;
+
+/// Noise handshake pattern used to establish a secure channel
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Encode, Decode, CborLen)]
+#[cbor(index_only)]
+pub enum HandshakePattern {
+    /// Three messages handshake where both parties send their static key to the other party
+    #[default]
+    #[n(0)]
+    XX,
+    /// Two messages handshake where the initiator already knows the static key of the responder.
+    /// The initiator static key is sent in the first message
+    #[n(1)]
+    IK,
+    /// Two messages handshake where both parties already know the static key of the other party.
+    /// The initiator identifier is sent in clear in the first message so that the responder
+    /// can retrieve the initiator static key
+    #[n(2)]
+    KK,
+}
+
+impl HandshakePattern {
+    /// Return true if the static key of the responder must be known before starting the handshake
+    pub fn requires_known_responder(&self) -> bool {
+        *self != HandshakePattern::XX
+    }
+
+    /// Protocol name used to initialize the handshake, padded to 32 bytes.
+    /// The XX part of the default protocol name is replaced with the pattern name
+    pub(crate) fn protocol_name(&self) -> [u8; 32] {
+        let mut protocol_name = *PROTOCOL_NAME;
+        let pattern: &[u8; 2] = match self {
+            HandshakePattern::XX => b"XX",
+            HandshakePattern::IK => b"IK",
+            HandshakePattern::KK => b"KK",
+        };
+        protocol_name[6..8].copy_from_slice(pattern);
+        protocol_name
+    }
+}
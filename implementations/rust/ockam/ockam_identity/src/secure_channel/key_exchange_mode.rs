@@ -0,0 +1,28 @@
+/// Key exchange performed during the secure channel handshake
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum KeyExchangeMode {
+    /// Noise XX handshake only mixing X25519 Diffie-Hellman outputs
+    #[default]
+    Classic,
+    /// Hybrid X25519 + ML-KEM-768 handshake if the other party supports it,
+    /// otherwise fall back to the classic handshake
+    HybridPreferred,
+    /// Hybrid X25519 + ML-KEM-768 handshake.
+    /// The handshake fails if the other party doesn't support it
+    HybridRequired,
+}
+
+impl KeyExchangeMode {
+    /// Return true if a hybrid key exchange must be offered / accepted
+    pub fn is_hybrid(&self) -> bool {
+        match self {
+            KeyExchangeMode::Classic => false,
+            KeyExchangeMode::HybridPreferred | KeyExchangeMode::HybridRequired => true,
+        }
+    }
+
+    /// Return true if the handshake must fail when the hybrid key exchange can't be performed
+    pub fn is_hybrid_required(&self) -> bool {
+        *self == KeyExchangeMode::HybridRequired
+    }
+}
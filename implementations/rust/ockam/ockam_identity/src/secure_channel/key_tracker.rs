@@ -3,8 +3,15 @@ use ockam_vault::AeadSecretKeyHandle;
 use tracing::{trace, warn};
 use tracing_attributes::instrument;
 
+use crate::secure_channel::encryptor::KEY_RENEWAL_INTERVAL;
+use crate::secure_channel::nonce_reservation::MAX_NONCE_GAP;
 use crate::{IdentityError, Nonce};
 
+/// Maximum number of key intervals skipped by a message.
+/// A party resuming a persistent secure channel skips the nonces of its last reservation,
+/// and a restored decryptor only accepts the nonces after the end of its own last reservation
+pub(crate) const MAX_SKIPPED_INTERVALS: u64 = 2 * MAX_NONCE_GAP / KEY_RENEWAL_INTERVAL;
+
 pub(crate) struct KeyTracker {
     pub(crate) current_key: AeadSecretKeyHandle,
     pub(crate) previous_key: Option<AeadSecretKeyHandle>,
@@ -23,6 +30,24 @@ impl KeyTracker {
             renewal_interval,
         }
     }
+
+    /// Create a tracker for a key which was obtained after `number_of_rekeys` rekeys.
+    /// This is used when a persistent secure channel is restarted
+    pub(crate) fn restore(
+        current_key: AeadSecretKeyHandle,
+        renewal_interval: u64,
+        number_of_rekeys: u64,
+    ) -> Self {
+        KeyTracker {
+            number_of_rekeys,
+            ..Self::new(current_key, renewal_interval)
+        }
+    }
+
+    /// Number of rekeys done to obtain the current key
+    pub(crate) fn number_of_rekeys(&self) -> u64 {
+        self.number_of_rekeys
+    }
 }
 
 impl KeyTracker {
@@ -34,7 +59,8 @@ impl KeyTracker {
     /// This is either:
     ///   - the current key if the nonce falls into the current interval
     ///   - the previous key if the nonce falls before the current interval
-    ///   - nothing if the the nonce falls after the current interval -> this indicates that a new key must be created
+    ///   - nothing if the the nonce falls after the current interval -> this indicates that a new key must be created,
+    ///     possibly after skipping up to [`MAX_SKIPPED_INTERVALS`] intervals
     ///   - an error if
     ///      - if the the nonce falls before the previous interval
     ///      - if it the previous nonce but is not set
@@ -64,9 +90,9 @@ impl KeyTracker {
             if nonce_age < self.renewal_interval {
                 Ok(Some(&self.current_key))
             }
-            // if the nonce falls in the next interval
-            // otherwise indicate that we need to create a new key
-            else if nonce_age < self.renewal_interval * 2 {
+            // if the nonce falls in one of the next intervals
+            // indicate that we need to create a new key
+            else if nonce_age < self.renewal_interval * (MAX_SKIPPED_INTERVALS + 2) {
                 Ok(None)
             }
             // otherwise the nonce is too far ahead
@@ -88,11 +114,18 @@ impl KeyTracker {
         }
     }
 
-    // Update the key if a key renewal happened
+    /// Number of key renewals needed to obtain the key of a nonce after the current interval
+    pub(crate) fn number_of_rekeys_to(&self, nonce: Nonce) -> u64 {
+        (nonce.value() / self.renewal_interval).saturating_sub(self.number_of_rekeys)
+    }
+
+    // Update the key if a key renewal happened.
+    // `number_of_rekeys` is the number of renewals done from the current key to obtain a new key
     #[instrument(skip_all)]
     pub(crate) fn update_key(
         &mut self,
         decryption_key: &AeadSecretKeyHandle,
+        number_of_rekeys: u64,
     ) -> Result<Option<AeadSecretKeyHandle>> {
         let mut key_to_delete = None;
         // if the key used for the decryption is not the current key nor the previous key
@@ -102,9 +135,12 @@ impl KeyTracker {
             key_to_delete = self.previous_key.clone();
             self.previous_key.replace(self.current_key.clone());
             self.current_key = decryption_key.clone();
-            if u64::MAX - self.number_of_rekeys * self.renewal_interval < self.renewal_interval {
-                self.max_rekeys_reached = true;
-            } else {
+            for _ in 0..number_of_rekeys {
+                if u64::MAX - self.number_of_rekeys * self.renewal_interval < self.renewal_interval
+                {
+                    self.max_rekeys_reached = true;
+                    break;
+                }
                 self.number_of_rekeys += 1;
             }
         }
@@ -133,7 +169,15 @@ mod tests {
             "the next key must be created"
         );
         assert_eq!(
-            key_tracker.get_key(20.into()).ok(),
+            key_tracker.get_key(20.into()).unwrap(),
+            None,
+            "the next keys must be created"
+        );
+        assert_eq!(key_tracker.number_of_rekeys_to(20.into()), 2);
+        assert_eq!(
+            key_tracker
+                .get_key((10 * (MAX_SKIPPED_INTERVALS + 2)).into())
+                .ok(),
             None,
             "this nonce is too far in the future"
         );
@@ -243,15 +287,27 @@ mod tests {
             renewal_interval: 10,
         };
 
-        assert_eq!(key_tracker.update_key(&handle).unwrap(), None);
-        assert_eq!(key_tracker.update_key(&previous_handle).unwrap(), None);
+        assert_eq!(key_tracker.update_key(&handle, 0).unwrap(), None);
+        assert_eq!(key_tracker.update_key(&previous_handle, 0).unwrap(), None);
         assert_eq!(
-            key_tracker.update_key(&new_handle).unwrap(),
+            key_tracker.update_key(&new_handle, 1).unwrap(),
             Some(previous_handle),
             "the previous key id must be returned in order to be deleted",
         );
         assert_eq!(key_tracker.current_key, new_handle);
-        assert_eq!(key_tracker.previous_key, Some(handle));
+        assert_eq!(key_tracker.previous_key, Some(handle.clone()));
+        assert_eq!(key_tracker.number_of_rekeys(), 6);
+
+        // some intervals were skipped
+        let skipped_handle = b"skipped_handle".to_vec();
+        let skipped_handle = AeadSecretKeyHandle(Aes256GcmSecretKeyHandle(HandleToSecret::new(
+            skipped_handle,
+        )));
+        assert_eq!(
+            key_tracker.update_key(&skipped_handle, 3).unwrap(),
+            Some(handle)
+        );
+        assert_eq!(key_tracker.number_of_rekeys(), 9);
     }
 
     #[test]
@@ -274,7 +330,7 @@ mod tests {
         };
 
         // this brings us to the last interval
-        key_tracker.update_key(&new_handle).unwrap();
+        key_tracker.update_key(&new_handle, 1).unwrap();
         assert!(
             !key_tracker.max_rekeys_reached,
             "the maximum number of rekeys is not yet reached"
@@ -284,7 +340,7 @@ mod tests {
         let new_handle2 = b"new_handle2".to_vec();
         let new_handle2 =
             AeadSecretKeyHandle(Aes256GcmSecretKeyHandle(HandleToSecret::new(new_handle2)));
-        key_tracker.update_key(&new_handle2).unwrap();
+        key_tracker.update_key(&new_handle2, 1).unwrap();
         assert!(
             key_tracker.max_rekeys_reached,
             "the maximum number of rekeys is reached now"
@@ -0,0 +1,163 @@
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::Result;
+use ockam_vault::X25519PublicKey;
+
+use crate::models::{Identifier, PurposeKeyAttestation, PurposePublicKey};
+use crate::Identities;
+
+/// Maximum number of peers which are remembered. When that number is reached,
+/// the least recently used peer is forgotten
+pub const MAX_KNOWN_PEERS: usize = 1024;
+
+/// Secure Channel Purpose Key Attestations of the peers which completed a handshake
+/// with this node.
+/// They are used to start a IK or KK handshake when the same peers are contacted again.
+/// At most [`MAX_KNOWN_PEERS`] peers are kept
+#[derive(Clone, Default)]
+pub struct KnownPeers {
+    peers: Arc<RwLock<Peers>>,
+}
+
+/// Attestations of the known peers, with the last time they were used
+#[derive(Default)]
+struct Peers {
+    attestations: BTreeMap<Identifier, (PurposeKeyAttestation, u64)>,
+    // logical clock incremented each time a peer is added or used
+    clock: u64,
+}
+
+impl Peers {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
+
+impl KnownPeers {
+    /// Store the Secure Channel Purpose Key Attestation of a peer,
+    /// replacing the previous one if any
+    pub fn add_peer(&self, identifier: Identifier, attestation: PurposeKeyAttestation) {
+        let mut peers = self.peers.write().unwrap();
+        if !peers.attestations.contains_key(&identifier)
+            && peers.attestations.len() >= MAX_KNOWN_PEERS
+        {
+            let least_recently_used = peers
+                .attestations
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(identifier, _)| identifier.clone());
+            if let Some(least_recently_used) = least_recently_used {
+                peers.attestations.remove(&least_recently_used);
+            }
+        }
+        let now = peers.tick();
+        peers.attestations.insert(identifier, (attestation, now));
+    }
+
+    /// Return the Secure Channel Purpose Key Attestation of a peer if it is known
+    pub fn get_peer(&self, identifier: &Identifier) -> Option<PurposeKeyAttestation> {
+        let mut peers = self.peers.write().unwrap();
+        let now = peers.tick();
+        let (attestation, last_used) = peers.attestations.get_mut(identifier)?;
+        *last_used = now;
+        Some(attestation.clone())
+    }
+
+    /// Forget a peer.
+    /// This is done when a IK or KK handshake with that peer fails
+    pub fn remove_peer(&self, identifier: &Identifier) -> Option<PurposeKeyAttestation> {
+        self.peers
+            .write()
+            .unwrap()
+            .attestations
+            .remove(identifier)
+            .map(|(attestation, _)| attestation)
+    }
+
+    /// Return the number of known peers
+    pub fn len(&self) -> usize {
+        self.peers.read().unwrap().attestations.len()
+    }
+
+    /// Return true if no peer is known
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the static key of a peer if it is known.
+    /// The attestation is verified again since it might have expired, or the peer identity
+    /// might have changed since it was stored
+    pub(crate) async fn get_static_key(
+        &self,
+        identities: &Identities,
+        identifier: &Identifier,
+    ) -> Result<Option<X25519PublicKey>> {
+        let Some(attestation) = self.get_peer(identifier) else {
+            return Ok(None);
+        };
+
+        let purpose_key = identities
+            .purpose_keys()
+            .purpose_keys_verification()
+            .verify_purpose_key_attestation(Some(identifier), &attestation)
+            .await;
+        let purpose_key = match purpose_key {
+            Ok(purpose_key) => purpose_key,
+            Err(err) => {
+                // the attestation has expired or the peer identity has changed
+                self.remove_peer(identifier);
+                return Err(err);
+            }
+        };
+
+        match purpose_key.public_key {
+            PurposePublicKey::SecureChannelStatic(public_key) => Ok(Some(public_key)),
+            PurposePublicKey::CredentialSigning(_) => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PurposeKeyAttestationSignature;
+    use ockam_vault::EdDSACurve25519Signature;
+
+    fn identifier(i: usize) -> Identifier {
+        let mut identifier = [0u8; 32];
+        identifier[..8].copy_from_slice(&(i as u64).to_be_bytes());
+        Identifier(identifier)
+    }
+
+    fn attestation() -> PurposeKeyAttestation {
+        PurposeKeyAttestation {
+            data: vec![],
+            signature: PurposeKeyAttestationSignature::EdDSACurve25519(EdDSACurve25519Signature(
+                [0u8; 64],
+            )),
+        }
+    }
+
+    #[test]
+    fn test_least_recently_used_peer_is_evicted() {
+        let known_peers = KnownPeers::default();
+        for i in 0..MAX_KNOWN_PEERS {
+            known_peers.add_peer(identifier(i), attestation());
+        }
+        assert_eq!(known_peers.len(), MAX_KNOWN_PEERS);
+
+        // the first peer is used again, so the second one is the least recently used
+        assert!(known_peers.get_peer(&identifier(0)).is_some());
+        known_peers.add_peer(identifier(MAX_KNOWN_PEERS), attestation());
+
+        assert_eq!(known_peers.len(), MAX_KNOWN_PEERS);
+        assert!(known_peers.get_peer(&identifier(0)).is_some());
+        assert!(known_peers.get_peer(&identifier(1)).is_none());
+        assert!(known_peers.get_peer(&identifier(MAX_KNOWN_PEERS)).is_some());
+
+        // replacing the attestation of a known peer doesn't evict anyone
+        known_peers.add_peer(identifier(2), attestation());
+        assert_eq!(known_peers.len(), MAX_KNOWN_PEERS);
+    }
+}
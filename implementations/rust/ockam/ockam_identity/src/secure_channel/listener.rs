@@ -10,7 +10,7 @@ use crate::secure_channel::handshake_worker::HandshakeWorker;
 use crate::secure_channel::options::SecureChannelListenerOptions;
 use crate::secure_channel::role::Role;
 use crate::secure_channels::secure_channels::SecureChannels;
-use crate::SecureChannelRepository;
+use crate::{HandshakePattern, SecureChannelRepository};
 
 pub(crate) struct SecureChannelListenerWorker {
     secure_channels: Arc<SecureChannels>,
@@ -111,7 +111,14 @@ impl Worker for SecureChannelListenerWorker {
             None,
             Role::Responder,
             self.options.key_exchange_only,
+            self.options.key_exchange_mode,
+            // the handshake pattern is selected by the initiator
+            HandshakePattern::XX,
+            None,
+            self.options.rekey_policy,
+            self.options.padding_policy,
             self.secure_channel_repository.clone(),
+            Some(ctx.primary_address().clone()),
             RemoteRoute::create(),
         )
         .await?;
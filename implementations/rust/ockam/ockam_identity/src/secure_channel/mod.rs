@@ -6,13 +6,19 @@ mod decryptor;
 mod encryptor;
 mod encryptor_worker;
 pub(crate) mod handshake;
+mod handshake_pattern;
+mod key_exchange_mode;
 mod key_tracker;
+mod known_peers;
 mod listener;
 mod message;
 mod nonce;
+mod nonce_reservation;
 mod nonce_tracker;
 mod options;
+mod padding_policy;
 mod registry;
+mod rekey_policy;
 mod role;
 
 /// List of trust policies to setup ABAC controls
@@ -22,22 +28,39 @@ pub use access_control::*;
 pub(crate) use addresses::*;
 pub use api::*;
 pub(crate) use decryptor::*;
+pub(crate) use encryptor::*;
 pub(crate) use encryptor_worker::*;
 pub(crate) use handshake::*;
+pub use handshake_pattern::*;
+pub use key_exchange_mode::*;
+pub use known_peers::*;
 pub(crate) use listener::*;
 pub use message::*;
 pub use nonce::*;
+pub(crate) use nonce_reservation::*;
 pub use options::*;
+pub use padding_policy::*;
 pub use registry::*;
+pub use rekey_policy::*;
 pub(crate) use role::*;
 pub use trust_policy::*;
 
 #[cfg(test)]
 mod tests {
+    use crate::models::Identifier;
     use crate::secure_channel::{decryptor::Decryptor, encryptor::Encryptor};
+    use crate::secure_channel::{NonceReservation, ReservationSide, Role, NONCE_RESERVATION_SIZE};
+    use crate::{
+        PersistedSecureChannel, PersistedSecureChannelState, SecureChannelRepository,
+        SecureChannelSqlxDatabase,
+    };
     use ockam_core::compat::rand::RngCore;
-    use ockam_core::Result;
-    use ockam_vault::{SoftwareVaultForSecureChannels, VaultForSecureChannels};
+    use ockam_core::compat::sync::Arc;
+    use ockam_core::{Address, Result};
+    use ockam_vault::storage::SecretsSqlxDatabase;
+    use ockam_vault::{
+        AeadSecretKeyHandle, SoftwareVaultForSecureChannels, VaultForSecureChannels,
+    };
     use rand::seq::SliceRandom;
     use rand::thread_rng;
 
@@ -179,6 +202,155 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_encrypt_decrypt_after_restart() -> Result<()> {
+        let repository: Arc<dyn SecureChannelRepository> =
+            Arc::new(SecureChannelSqlxDatabase::create().await?);
+        let secrets1 = Arc::new(SecretsSqlxDatabase::create().await?);
+        let secrets2 = Arc::new(SecretsSqlxDatabase::create().await?);
+        let vault1 = Arc::new(SoftwareVaultForSecureChannels::new(secrets1.clone()));
+        let vault2 = Arc::new(SoftwareVaultForSecureChannels::new(secrets2.clone()));
+
+        let mut key = [0u8; 32];
+        thread_rng().fill_bytes(&mut key);
+        let key_on_v1 = vault1.import_secret_buffer(key.to_vec()).await?;
+        let key_on_v1 = vault1.convert_secret_buffer_to_aead_key(key_on_v1).await?;
+        let key_on_v2 = vault2.import_secret_buffer(key.to_vec()).await?;
+        let key_on_v2 = vault2.convert_secret_buffer_to_aead_key(key_on_v2).await?;
+
+        let encryptor_side = Address::random_local();
+        let decryptor_side = Address::random_local();
+        for (address, key, vault) in [
+            (&encryptor_side, &key_on_v1, &vault1),
+            (&decryptor_side, &key_on_v2, &vault2),
+        ] {
+            vault.persist_aead_key(key).await?;
+            repository
+                .put(create_persisted_secure_channel(address, key).await?)
+                .await?;
+        }
+
+        let mut encryptor = Encryptor::new(key_on_v1.clone(), 0.into(), vault1.clone(), true)
+            .with_nonce_reservation(NonceReservation::new(
+                ReservationSide::Encryptor,
+                repository.clone(),
+                vault1,
+                encryptor_side.clone(),
+                Some(key_on_v1),
+                0.into(),
+            ));
+        let mut decryptor = Decryptor::new(key_on_v2.clone(), vault2.clone())
+            .with_nonce_reservation(NonceReservation::new(
+                ReservationSide::Decryptor,
+                repository.clone(),
+                vault2.clone(),
+                decryptor_side.clone(),
+                Some(key_on_v2),
+                0.into(),
+            ));
+
+        for n in 0..70 {
+            let mut ciphertext = encrypt(&mut encryptor, n).await?;
+            assert_eq!(vec![n], decryptor.decrypt(&mut ciphertext).await?.0);
+        }
+
+        // the encryptor restarts after a crash, with a new vault, and continues to talk
+        // to the current decryptor
+        encryptor.wait_for_nonce_reservation().await?;
+        let state = repository
+            .get(&encryptor_side)
+            .await?
+            .unwrap()
+            .state()
+            .cloned()
+            .unwrap();
+        let encryption_nonce = state.encryption_nonce().value();
+        assert!(encryption_nonce >= 70);
+        assert!(encryption_nonce - 69 <= NONCE_RESERVATION_SIZE + NONCE_RESERVATION_SIZE / 2);
+        let vault1 = Arc::new(SoftwareVaultForSecureChannels::new(secrets1));
+        vault1.load_aead_key(state.encryption_key_handle()).await?;
+        let mut encryptor = Encryptor::restore(
+            state.encryption_key_handle().clone(),
+            state.encryption_number_of_rekeys(),
+            state.encryption_nonce(),
+            vault1,
+            true,
+        )
+        .await?;
+
+        let mut ciphertexts = vec![];
+        for n in 70..140 {
+            let mut ciphertext = encrypt(&mut encryptor, n).await?;
+            ciphertexts.push(ciphertext.clone());
+            assert_eq!(vec![n], decryptor.decrypt(&mut ciphertext).await?.0);
+        }
+
+        // the decryptor restarts after a crash and rejects the messages it already received
+        decryptor.wait_for_nonce_reservation().await?;
+        let persisted = repository.get(&decryptor_side).await?.unwrap();
+        let state = persisted.state().unwrap();
+        let vault2 = Arc::new(SoftwareVaultForSecureChannels::new(secrets2));
+        vault2
+            .load_aead_key(persisted.decryption_key_handle())
+            .await?;
+        let mut decryptor = Decryptor::restore(
+            persisted.decryption_key_handle().clone(),
+            vault2,
+            state.decryption_number_of_rekeys(),
+            state.decryption_nonce(),
+        );
+        for ciphertext in ciphertexts.iter_mut() {
+            assert!(decryptor.decrypt(ciphertext).await.is_err());
+        }
+
+        // the next messages are rejected until the end of the last decryptor reservation
+        let next_nonce = encryption_nonce + 70;
+        let rejected = state.decryption_nonce().value() - next_nonce;
+        assert!(rejected <= NONCE_RESERVATION_SIZE + NONCE_RESERVATION_SIZE / 2);
+        for _ in 0..rejected {
+            let mut ciphertext = encrypt(&mut encryptor, 1).await?;
+            assert!(decryptor.decrypt(&mut ciphertext).await.is_err());
+        }
+        let mut ciphertext = encrypt(&mut encryptor, 1).await?;
+        assert_eq!(vec![1], decryptor.decrypt(&mut ciphertext).await?.0);
+
+        Ok(())
+    }
+
+    async fn encrypt(encryptor: &mut Encryptor, n: u8) -> Result<Vec<u8>> {
+        let mut ciphertext = vec![0u8; 1 + 24];
+        ciphertext[8] = n;
+        encryptor.encrypt(&mut ciphertext).await?;
+        Ok(ciphertext)
+    }
+
+    async fn create_persisted_secure_channel(
+        decryptor_remote: &Address,
+        key: &AeadSecretKeyHandle,
+    ) -> Result<PersistedSecureChannel> {
+        let identifier = Identifier::try_from(
+            "Ie70dc5545d64724880257acb32b8851e7dd1dd57076838991bc343165df71bfe",
+        )?;
+        Ok(PersistedSecureChannel::new(
+            Role::Initiator,
+            identifier.clone(),
+            identifier,
+            decryptor_remote.clone(),
+            Address::random_local(),
+            key.clone(),
+        )
+        .with_state(PersistedSecureChannelState::new(
+            Address::random_local(),
+            Address::random_local(),
+            None,
+            key.clone(),
+            0,
+            0.into(),
+            0,
+            0.into(),
+        )))
+    }
+
     async fn create_encryptor_decryptor() -> Result<(Encryptor, Decryptor)> {
         let vault1 = SoftwareVaultForSecureChannels::create().await?;
         let vault2 = SoftwareVaultForSecureChannels::create().await?;
@@ -0,0 +1,274 @@
+use ockam_core::compat::sync::Arc;
+#[cfg(feature = "std")]
+use ockam_core::errcode::{Kind, Origin};
+#[cfg(feature = "std")]
+use ockam_core::Error;
+use ockam_core::{Address, Result};
+#[cfg(feature = "std")]
+use ockam_node::tokio::task::JoinHandle;
+use ockam_vault::{AeadSecretKeyHandle, VaultForSecureChannels};
+use tracing::{debug, warn};
+
+use crate::models::CredentialAndPurposeKey;
+use crate::secure_channel::encryptor::KEY_RENEWAL_INTERVAL;
+use crate::{Nonce, SecureChannelRepository};
+
+/// Number of nonces reserved every time the state of a persistent secure channel is saved.
+///
+/// After a restart the encryptor starts from the end of its last reservation, so that a nonce
+/// is never used twice with the same key even if the node crashed. Symmetrically the decryptor
+/// only accepts nonces after the end of its last reservation, so that messages can't be replayed.
+/// The messages sent by a live party with nonces inside that last reservation are then rejected.
+///
+/// The next reservation is saved in the background once half of the current one is used, so
+/// that messages don't wait for the storage. After a restart a party can then be up to
+/// 1.5 x [`NONCE_RESERVATION_SIZE`] nonces ahead of the last nonce received by the other party,
+/// which must stay below [`MAX_NONCE_GAP`], the maximum gap accepted by the other party.
+///
+/// A reservation spans several key intervals so that the state is only saved every few
+/// key renewals. In exchange, up to 1.5 x [`NONCE_RESERVATION_SIZE`] messages sent by a live
+/// party are rejected after a restart of the other party.
+pub(crate) const NONCE_RESERVATION_SIZE: u64 = 4 * KEY_RENEWAL_INTERVAL;
+
+/// Maximum gap accepted between the last nonce received by a decryptor and the next one.
+/// The nonces of the skipped key intervals are decrypted after renewing the key once per
+/// skipped interval
+pub(crate) const MAX_NONCE_GAP: u64 = 2 * NONCE_RESERVATION_SIZE;
+
+const _: () = assert!(NONCE_RESERVATION_SIZE >= KEY_RENEWAL_INTERVAL);
+const _: () = assert!(NONCE_RESERVATION_SIZE + NONCE_RESERVATION_SIZE / 2 <= MAX_NONCE_GAP);
+
+/// Side of the secure channel using a [`NonceReservation`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum ReservationSide {
+    Encryptor,
+    Decryptor,
+}
+
+/// Save the state of an encryptor or a decryptor for a persistent secure channel.
+/// A new range of [`NONCE_RESERVATION_SIZE`] nonces is reserved before the current one is
+/// exhausted, with the current key and its number of rekeys.
+pub(crate) struct NonceReservation {
+    storage: ReservationStorage,
+    // Key saved with the last reservation
+    persisted_key: Option<AeadSecretKeyHandle>,
+    // First nonce outside of the last saved reservation
+    end: Nonce,
+    // Next reservation, being saved in the background
+    #[cfg(feature = "std")]
+    next: Option<JoinHandle<Result<Reservation>>>,
+}
+
+/// Reservation which has been saved
+struct Reservation {
+    key: AeadSecretKeyHandle,
+    end: Nonce,
+}
+
+/// Storage of the reservations of a secure channel
+#[derive(Clone)]
+struct ReservationStorage {
+    side: ReservationSide,
+    repository: Arc<dyn SecureChannelRepository>,
+    vault: Arc<dyn VaultForSecureChannels>,
+    decryptor_remote: Address,
+}
+
+impl NonceReservation {
+    pub(crate) fn new(
+        side: ReservationSide,
+        repository: Arc<dyn SecureChannelRepository>,
+        vault: Arc<dyn VaultForSecureChannels>,
+        decryptor_remote: Address,
+        persisted_key: Option<AeadSecretKeyHandle>,
+        end: Nonce,
+    ) -> Self {
+        Self {
+            storage: ReservationStorage {
+                side,
+                repository,
+                vault,
+                decryptor_remote,
+            },
+            persisted_key,
+            end,
+            #[cfg(feature = "std")]
+            next: None,
+        }
+    }
+
+    /// Return the end of the reservation containing the given nonce.
+    /// Reservations are aligned on [`NONCE_RESERVATION_SIZE`]
+    pub(crate) fn reservation_end(nonce: Nonce) -> Nonce {
+        (nonce.value() - nonce.value() % NONCE_RESERVATION_SIZE + NONCE_RESERVATION_SIZE).into()
+    }
+
+    /// Make sure that the given nonce is reserved before it is used, and save the next
+    /// reservation in the background when the current one is half used.
+    ///
+    /// The key is the current key of the encryptor or the decryptor, obtained after
+    /// `number_of_rekeys` rekeys. This function must be called before that key is deleted.
+    pub(crate) async fn reserve(
+        &mut self,
+        key: &AeadSecretKeyHandle,
+        number_of_rekeys: u64,
+        nonce: Nonce,
+    ) -> Result<()> {
+        #[cfg(feature = "std")]
+        if self.next.as_ref().is_some_and(|next| next.is_finished()) || nonce >= self.end {
+            self.wait_for_next_reservation().await?;
+        }
+
+        if nonce >= self.end {
+            let end = Self::reservation_end(nonce);
+            let reservation = self
+                .storage
+                .save(
+                    self.persisted_key.clone(),
+                    key.clone(),
+                    number_of_rekeys,
+                    end,
+                )
+                .await?;
+            self.saved(reservation);
+        }
+
+        #[cfg(feature = "std")]
+        if self.next.is_none() && nonce.value() + NONCE_RESERVATION_SIZE / 2 >= self.end.value() {
+            let storage = self.storage.clone();
+            let persisted_key = self.persisted_key.clone();
+            let key = key.clone();
+            let end = (self.end.value() + NONCE_RESERVATION_SIZE).into();
+            self.next = Some(ockam_node::spawn(async move {
+                storage
+                    .save(persisted_key, key, number_of_rekeys, end)
+                    .await
+            }));
+        }
+
+        Ok(())
+    }
+
+    /// Wait until the reservation being saved in the background, if any, is saved.
+    /// This must be done before deleting a key which might be used by that reservation
+    pub(crate) async fn wait_for_next_reservation(&mut self) -> Result<()> {
+        #[cfg(feature = "std")]
+        if let Some(next) = self.next.take() {
+            let reservation = next.await.map_err(|err| {
+                Error::new(
+                    Origin::Channel,
+                    Kind::Internal,
+                    format!("the nonce reservation task failed: {err}"),
+                )
+            })??;
+            self.saved(reservation);
+        }
+        Ok(())
+    }
+
+    fn saved(&mut self, reservation: Reservation) {
+        debug!(decryptor_remote = %self.storage.decryptor_remote, side = ?self.storage.side,
+            end = %reservation.end, "Reserved secure channel nonces");
+        self.persisted_key = Some(reservation.key);
+        self.end = reservation.end;
+    }
+
+    /// Save the credentials presented by the other party, so that its attributes
+    /// can be restored when the secure channel is resumed
+    pub(crate) async fn save_their_credentials(
+        &self,
+        their_credentials: &[CredentialAndPurposeKey],
+    ) -> Result<()> {
+        self.storage
+            .repository
+            .update_their_credentials(&self.storage.decryptor_remote, their_credentials)
+            .await
+    }
+
+    /// Delete the persisted secure channel and its keys.
+    /// This is done when the other party closes the channel
+    pub(crate) async fn delete(&mut self) -> Result<()> {
+        if let Err(err) = self.wait_for_next_reservation().await {
+            warn!(decryptor_remote = %self.storage.decryptor_remote, %err,
+                "Error while reserving secure channel nonces");
+        }
+
+        let repository = &self.storage.repository;
+        let vault = &self.storage.vault;
+        let decryptor_remote = &self.storage.decryptor_remote;
+        if let Some(persisted) = repository.get(decryptor_remote).await? {
+            vault
+                .delete_persisted_aead_key(persisted.decryption_key_handle())
+                .await?;
+            if let Some(state) = persisted.state() {
+                vault
+                    .delete_persisted_aead_key(state.encryption_key_handle())
+                    .await?;
+            }
+        }
+        repository.delete(decryptor_remote).await
+    }
+}
+
+impl ReservationStorage {
+    /// Save the key and reserve all the nonces up to `end`.
+    /// The key replaces the previously persisted key in the vault
+    async fn save(
+        &self,
+        persisted_key: Option<AeadSecretKeyHandle>,
+        key: AeadSecretKeyHandle,
+        number_of_rekeys: u64,
+        end: Nonce,
+    ) -> Result<Reservation> {
+        let key_changed = persisted_key.as_ref() != Some(&key);
+        if key_changed {
+            self.vault.persist_aead_key(&key).await?;
+        }
+
+        match self.side {
+            ReservationSide::Encryptor => {
+                self.repository
+                    .update_encryption_state(&self.decryptor_remote, &key, number_of_rekeys, end)
+                    .await?
+            }
+            ReservationSide::Decryptor => {
+                self.repository
+                    .update_decryption_state(&self.decryptor_remote, &key, number_of_rekeys, end)
+                    .await?
+            }
+        }
+
+        // the previous key can only be deleted once the new one is referenced by the storage
+        if key_changed {
+            if let Some(previous_key) = persisted_key {
+                if let Err(err) = self.vault.delete_persisted_aead_key(&previous_key).await {
+                    warn!(decryptor_remote = %self.decryptor_remote, %err,
+                        "Error while deleting a persisted secure channel key");
+                }
+            }
+        }
+
+        Ok(Reservation { key, end })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reservation_end() {
+        assert_eq!(
+            NonceReservation::reservation_end(0.into()),
+            NONCE_RESERVATION_SIZE.into()
+        );
+        assert_eq!(
+            NonceReservation::reservation_end((NONCE_RESERVATION_SIZE - 1).into()),
+            NONCE_RESERVATION_SIZE.into()
+        );
+        assert_eq!(
+            NonceReservation::reservation_end(NONCE_RESERVATION_SIZE.into()),
+            (2 * NONCE_RESERVATION_SIZE).into()
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::secure_channel::encryptor::KEY_RENEWAL_INTERVAL;
+use crate::secure_channel::nonce_reservation::MAX_NONCE_GAP;
 use crate::{IdentityError, Nonce};
 use tracing_attributes::instrument;
 
@@ -23,17 +24,35 @@ impl NonceTracker {
         }
     }
 
+    /// Create a tracker rejecting all the nonces before `first_nonce`.
+    /// This is used when a persistent secure channel is restarted
+    pub(crate) fn starting_at(first_nonce: Nonce) -> Self {
+        if first_nonce.value() == 0 {
+            return Self::new();
+        }
+
+        Self {
+            nonce_bitmap: BitmapType::MAX,
+            current_nonce: (first_nonce.value() - 1).into(),
+        }
+    }
+
     /// Mark a nonce as received, reject all invalid nonce values
     #[instrument(skip_all)]
     pub(crate) fn mark(&self, nonce: Nonce) -> ockam_core::Result<NonceTracker> {
         let new_tracker = if nonce > self.current_nonce {
-            // normal case, we increase the nonce and move the window
+            // normal case, we increase the nonce and move the window.
+            // The other party skips some nonces when it resumes a persistent secure channel
             let relative_shift: u64 = nonce.value() - self.current_nonce.value();
-            if relative_shift > KEY_RENEWAL_INTERVAL {
+            if relative_shift > MAX_NONCE_GAP {
                 return Err(IdentityError::InvalidNonce)?;
             }
             NonceTracker {
-                nonce_bitmap: self.nonce_bitmap.overflowing_shl(relative_shift as u32).0 | 1,
+                nonce_bitmap: self
+                    .nonce_bitmap
+                    .checked_shl(relative_shift as u32)
+                    .unwrap_or(0)
+                    | 1,
                 current_nonce: nonce,
             }
         } else {
@@ -65,7 +84,7 @@ pub fn check_nonce_tracker() {
     tracker = tracker.mark(0.into()).unwrap();
     tracker = tracker.mark(1.into()).unwrap();
     tracker.mark(0.into()).unwrap_err();
-    tracker.mark((KEY_RENEWAL_INTERVAL + 2).into()).unwrap_err();
+    tracker.mark((MAX_NONCE_GAP + 2).into()).unwrap_err();
     tracker = tracker.mark((KEY_RENEWAL_INTERVAL + 1).into()).unwrap();
     tracker.mark(1.into()).unwrap_err();
     tracker = tracker.mark((KEY_RENEWAL_INTERVAL + 2).into()).unwrap();
@@ -82,4 +101,26 @@ pub fn check_nonce_tracker() {
     for n in 4 * KEY_RENEWAL_INTERVAL + 1..5 * KEY_RENEWAL_INTERVAL + 1 {
         tracker = tracker.mark(n.into()).unwrap();
     }
+
+    // the nonces before a gap are rejected
+    let last = 5 * KEY_RENEWAL_INTERVAL;
+    tracker = tracker.mark((last + MAX_NONCE_GAP).into()).unwrap();
+    tracker.mark(last.into()).unwrap_err();
+    tracker.mark((last + MAX_NONCE_GAP).into()).unwrap_err();
+    tracker = tracker.mark((last + MAX_NONCE_GAP - 1).into()).unwrap();
+    tracker
+        .mark((last + 2 * MAX_NONCE_GAP + 1).into())
+        .unwrap_err();
+}
+
+#[test]
+pub fn check_nonce_tracker_starting_at() {
+    let start = 3 * KEY_RENEWAL_INTERVAL;
+    let mut tracker = NonceTracker::starting_at(start.into());
+    for n in start - KEY_RENEWAL_INTERVAL..start {
+        tracker.mark(n.into()).unwrap_err();
+    }
+    tracker = tracker.mark((start + 1).into()).unwrap();
+    tracker = tracker.mark(start.into()).unwrap();
+    tracker.mark(start.into()).unwrap_err();
 }
@@ -6,7 +6,9 @@ use ockam_core::flow_control::{FlowControlId, FlowControlOutgoingAccessControl,
 use ockam_core::{Address, OutgoingAccessControl, Result};
 
 use crate::models::CredentialAndPurposeKey;
-use crate::secure_channel::Addresses;
+use crate::secure_channel::{
+    Addresses, HandshakePattern, KeyExchangeMode, PaddingPolicy, RekeyPolicy,
+};
 use crate::{
     CredentialRetrieverCreator, Identifier, IdentityError, MemoryCredentialRetrieverCreator,
     TrustEveryonePolicy, TrustPolicy,
@@ -35,8 +37,14 @@ pub struct SecureChannelOptions {
     pub(crate) credential_retriever_creator: Option<Arc<dyn CredentialRetrieverCreator>>,
     pub(crate) timeout: Duration,
     pub(crate) key_exchange_only: bool,
-    // Secure Channel will be persisted (currently only supported for key_exchange_only = true)
+    // Secure Channel will be persisted
     pub(crate) is_persistent: bool,
+    pub(crate) key_exchange_mode: KeyExchangeMode,
+    // Identifier of the responder when a IK or KK handshake must be attempted
+    pub(crate) known_responder: Option<Identifier>,
+    pub(crate) handshake_pattern: HandshakePattern,
+    pub(crate) rekey_policy: RekeyPolicy,
+    pub(crate) padding_policy: PaddingPolicy,
 }
 
 impl fmt::Debug for SecureChannelOptions {
@@ -57,6 +65,11 @@ impl SecureChannelOptions {
             timeout: DEFAULT_TIMEOUT,
             key_exchange_only: false,
             is_persistent: false,
+            key_exchange_mode: KeyExchangeMode::default(),
+            known_responder: None,
+            handshake_pattern: HandshakePattern::default(),
+            rekey_policy: RekeyPolicy::default(),
+            padding_policy: PaddingPolicy::default(),
         }
     }
 
@@ -110,15 +123,49 @@ impl SecureChannelOptions {
         self
     }
 
-    /// Secure Channel will be persisted after a successful handshake
-    /// NOTE: Currently only supported after setting key_exchange_only = true
+    /// Secure Channel will be persisted after a successful handshake.
+    /// A regular secure channel can then be resumed after a restart with
+    /// [`crate::SecureChannels::resume_secure_channel`]. Such a channel is not closed
+    /// when the node stops, but only when the other party closes it.
     pub fn persist(mut self) -> Result<Self> {
-        if !self.key_exchange_only {
-            return Err(IdentityError::PersistentSupportIsLimited.into());
-        }
         self.is_persistent = true;
         Ok(self)
     }
+
+    /// Set the [`KeyExchangeMode`] offered to the other party during the handshake.
+    /// A hybrid mode adds an ML-KEM-768 encapsulation to the X25519 key exchange.
+    pub fn with_key_exchange_mode(mut self, key_exchange_mode: KeyExchangeMode) -> Self {
+        self.key_exchange_mode = key_exchange_mode;
+        self
+    }
+
+    /// Renew the encryption key of the channel when one of the limits of the [`RekeyPolicy`]
+    /// is reached. The key is still renewed every 32 messages regardless of that policy
+    pub fn with_rekey_policy(mut self, rekey_policy: RekeyPolicy) -> Self {
+        self.rekey_policy = rekey_policy;
+        self
+    }
+
+    /// Pad the messages sent on the channel according to the [`PaddingPolicy`] in order to
+    /// hide their length. The other party must use the same policy, otherwise the handshake fails
+    pub fn with_padding_policy(mut self, padding_policy: PaddingPolicy) -> Self {
+        self.padding_policy = padding_policy;
+        self
+    }
+
+    /// Use a shorter IK or KK [`HandshakePattern`] if the static key of the responder is known
+    /// from a previous handshake, see [`crate::KnownPeers`]. This saves one round trip.
+    /// The XX handshake is used if that static key is unknown or if its attestation has expired.
+    /// Note that the KK handshake sends the initiator identifier in clear in the first message
+    pub fn with_known_responder(
+        mut self,
+        responder: Identifier,
+        handshake_pattern: HandshakePattern,
+    ) -> Self {
+        self.known_responder = Some(responder);
+        self.handshake_pattern = handshake_pattern;
+        self
+    }
 }
 
 impl SecureChannelOptions {
@@ -183,8 +230,11 @@ pub struct SecureChannelListenerOptions {
     // To obtain our credentials
     pub(crate) credential_retriever_creator: Option<Arc<dyn CredentialRetrieverCreator>>,
     pub(crate) key_exchange_only: bool,
-    // Secure Channel will be persisted (currently only supported for key_exchange_only = true)
+    // Secure Channel will be persisted
     pub(crate) is_persistent: bool,
+    pub(crate) key_exchange_mode: KeyExchangeMode,
+    pub(crate) rekey_policy: RekeyPolicy,
+    pub(crate) padding_policy: PaddingPolicy,
 }
 
 impl fmt::Debug for SecureChannelListenerOptions {
@@ -207,6 +257,9 @@ impl SecureChannelListenerOptions {
             credential_retriever_creator: None,
             key_exchange_only: false,
             is_persistent: false,
+            key_exchange_mode: KeyExchangeMode::default(),
+            rekey_policy: RekeyPolicy::default(),
+            padding_policy: PaddingPolicy::default(),
         }
     }
 
@@ -263,15 +316,35 @@ impl SecureChannelListenerOptions {
         self
     }
 
-    /// Secure Channel will be persisted after a successful handshake
-    /// NOTE: Currently only supported after setting key_exchange_only = true
+    /// Spawned Secure Channels will be persisted after a successful handshake.
+    /// A regular secure channel can then be resumed after a restart with
+    /// [`crate::SecureChannels::resume_secure_channel_for_listener`]. Such a channel is not closed
+    /// when the node stops, but only when the other party closes it.
     pub fn persist(mut self) -> Result<Self> {
-        if !self.key_exchange_only {
-            return Err(IdentityError::PersistentSupportIsLimited.into());
-        }
         self.is_persistent = true;
         Ok(self)
     }
+
+    /// Set the [`KeyExchangeMode`] accepted from the initiators during the handshake.
+    /// With [`KeyExchangeMode::Classic`] the hybrid key exchanges offered by initiators are ignored.
+    pub fn with_key_exchange_mode(mut self, key_exchange_mode: KeyExchangeMode) -> Self {
+        self.key_exchange_mode = key_exchange_mode;
+        self
+    }
+
+    /// Renew the encryption key of the channel when one of the limits of the [`RekeyPolicy`]
+    /// is reached. The key is still renewed every 32 messages regardless of that policy
+    pub fn with_rekey_policy(mut self, rekey_policy: RekeyPolicy) -> Self {
+        self.rekey_policy = rekey_policy;
+        self
+    }
+
+    /// Pad the messages sent on the channel according to the [`PaddingPolicy`] in order to
+    /// hide their length. The other party must use the same policy, otherwise the handshake fails
+    pub fn with_padding_policy(mut self, padding_policy: PaddingPolicy) -> Self {
+        self.padding_policy = padding_policy;
+        self
+    }
 }
 
 impl SecureChannelListenerOptions {
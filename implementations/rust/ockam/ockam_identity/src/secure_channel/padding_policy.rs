@@ -0,0 +1,206 @@
+use minicbor::{CborLen, Decode, Encode};
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+
+use crate::{IdentityError, SecureChannelMessage, SecureChannelPaddedMessage};
+
+/// Maximum padding length for [`PaddingPolicy::Random`]
+const MAX_RANDOM_PADDING: usize = 255;
+
+/// Padding added to each secure channel message in order to hide its length.
+///
+/// Both parties of a secure channel must use the same policy since the decryptor
+/// rejects the messages which were not padded according to its own policy.
+/// Each party sends its policy during the handshake, which fails if the policies differ.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Encode, Decode, CborLen)]
+#[rustfmt::skip]
+pub enum PaddingPolicy {
+    /// No padding, the length of the encrypted message reveals the length of the payload
+    #[default]
+    #[n(0)] None,
+    /// Random padding of 0 to 255 bytes
+    #[n(1)] Random,
+    /// The padded message length is rounded up to a power of two
+    #[n(2)] PowerOfTwo,
+    /// The padded message length is rounded up to a multiple of the cell size.
+    /// A cell size of 0 is treated as 1
+    #[n(3)] FixedCells(#[n(0)] u32),
+}
+
+impl PaddingPolicy {
+    /// Pad a message according to this policy
+    pub(crate) fn pad<'a>(
+        &self,
+        message: SecureChannelMessage<'a>,
+    ) -> SecureChannelPaddedMessage<'a> {
+        let mut padded = SecureChannelPaddedMessage {
+            message,
+            padding: Vec::new().into(),
+        };
+
+        let padding_length = match self {
+            PaddingPolicy::None => 0,
+            PaddingPolicy::Random => ockam_core::compat::rand::random::<u8>() as usize,
+            PaddingPolicy::PowerOfTwo | PaddingPolicy::FixedCells(_) => {
+                let unpadded_length = minicbor::len(&padded);
+                let mut target = self.next_target(unpadded_length);
+                loop {
+                    if let Some(padding_length) = padding_length_for(unpadded_length, target) {
+                        break padding_length;
+                    }
+                    // that exact length can't be obtained because of the encoding of the
+                    // padding length, try the next one
+                    target = self.next_target(target + 1);
+                }
+            }
+        };
+
+        padded.padding = vec![0u8; padding_length].into();
+        padded
+    }
+
+    /// Check that a received message was padded according to this policy.
+    /// `padded_message_length` is the length of the encoded [`SecureChannelPaddedMessage`]
+    pub(crate) fn validate(
+        &self,
+        padded_message_length: usize,
+        padding_length: usize,
+    ) -> Result<()> {
+        let is_valid = match self {
+            PaddingPolicy::None => padding_length == 0,
+            PaddingPolicy::Random => padding_length <= MAX_RANDOM_PADDING,
+            PaddingPolicy::PowerOfTwo => padded_message_length.is_power_of_two(),
+            PaddingPolicy::FixedCells(cell_size) => {
+                padded_message_length % Self::cell_size(*cell_size) == 0
+            }
+        };
+
+        if is_valid {
+            Ok(())
+        } else {
+            Err(IdentityError::InvalidPadding)?
+        }
+    }
+
+    /// Check that the other party of a secure channel uses the same policy.
+    /// A party which doesn't send its policy doesn't pad its messages
+    pub(crate) fn check_peer_policy(&self, their_policy: Option<PaddingPolicy>) -> Result<()> {
+        if their_policy.unwrap_or_default() == *self {
+            Ok(())
+        } else {
+            Err(IdentityError::PaddingPolicyMismatch)?
+        }
+    }
+
+    /// Smallest valid padded message length which is greater or equal to `length`
+    fn next_target(&self, length: usize) -> usize {
+        match self {
+            PaddingPolicy::None | PaddingPolicy::Random => length,
+            PaddingPolicy::PowerOfTwo => length.next_power_of_two(),
+            PaddingPolicy::FixedCells(cell_size) => {
+                let cell_size = Self::cell_size(*cell_size);
+                (length + cell_size - 1) / cell_size * cell_size
+            }
+        }
+    }
+
+    fn cell_size(cell_size: u32) -> usize {
+        (cell_size as usize).max(1)
+    }
+}
+
+/// Return the padding length making a message encoded with an empty padding of
+/// `unpadded_length` bytes exactly `target` bytes long, if there is one.
+/// The length of the padding is encoded on 1, 2, 3, 5 or 9 bytes depending on its value
+fn padding_length_for(unpadded_length: usize, target: usize) -> Option<usize> {
+    // the empty padding length is encoded on 1 byte
+    let available = target.checked_sub(unpadded_length - 1)?;
+    [1, 2, 3, 5, 9].into_iter().find_map(|header_length| {
+        let padding_length = available.checked_sub(header_length)?;
+        (bytes_header_length(padding_length) == header_length).then_some(padding_length)
+    })
+}
+
+/// Length of the CBOR header of a byte string
+fn bytes_header_length(length: usize) -> usize {
+    match length {
+        0..=23 => 1,
+        24..=0xff => 2,
+        0x100..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PlaintextPayloadMessage;
+    use ockam_core::route;
+
+    fn message(payload_length: usize) -> SecureChannelMessage<'static> {
+        SecureChannelMessage::Payload(PlaintextPayloadMessage {
+            onward_route: route!["onward"],
+            return_route: route!["return"],
+            payload: vec![1u8; payload_length].into(),
+        })
+    }
+
+    #[test]
+    fn test_padding() {
+        for payload_length in 0..2000 {
+            for policy in [
+                PaddingPolicy::None,
+                PaddingPolicy::Random,
+                PaddingPolicy::PowerOfTwo,
+                PaddingPolicy::FixedCells(0),
+                PaddingPolicy::FixedCells(512),
+            ] {
+                let padded = policy.pad(message(payload_length));
+                let padded_length = minicbor::len(&padded);
+                policy
+                    .validate(padded_length, padded.padding.len())
+                    .unwrap_or_else(|_| {
+                        panic!("{policy:?} is invalid for a payload of {payload_length} bytes")
+                    });
+            }
+        }
+    }
+
+    #[test]
+    fn test_padding_hides_the_message_length() {
+        let policy = PaddingPolicy::FixedCells(512);
+        assert_eq!(minicbor::len(policy.pad(message(10))), 512);
+        assert_eq!(minicbor::len(policy.pad(message(400))), 512);
+        assert_eq!(minicbor::len(policy.pad(message(600))), 1024);
+
+        let policy = PaddingPolicy::PowerOfTwo;
+        assert_eq!(minicbor::len(policy.pad(message(10))), 64);
+        assert_eq!(minicbor::len(policy.pad(message(20))), 64);
+        assert_eq!(minicbor::len(policy.pad(message(900))), 1024);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(PaddingPolicy::None.validate(100, 0).is_ok());
+        assert!(PaddingPolicy::None.validate(100, 1).is_err());
+        assert!(PaddingPolicy::Random.validate(300, 255).is_ok());
+        assert!(PaddingPolicy::Random.validate(300, 256).is_err());
+        assert!(PaddingPolicy::PowerOfTwo.validate(256, 10).is_ok());
+        assert!(PaddingPolicy::PowerOfTwo.validate(257, 10).is_err());
+        assert!(PaddingPolicy::FixedCells(512).validate(1024, 10).is_ok());
+        assert!(PaddingPolicy::FixedCells(512).validate(1000, 10).is_err());
+    }
+
+    #[test]
+    fn test_check_peer_policy() {
+        assert!(PaddingPolicy::None.check_peer_policy(None).is_ok());
+        assert!(PaddingPolicy::Random.check_peer_policy(None).is_err());
+        assert!(PaddingPolicy::FixedCells(512)
+            .check_peer_policy(Some(PaddingPolicy::FixedCells(512)))
+            .is_ok());
+        assert!(PaddingPolicy::FixedCells(512)
+            .check_peer_policy(Some(PaddingPolicy::FixedCells(256)))
+            .is_err());
+    }
+}
@@ -4,6 +4,7 @@ use ockam_core::compat::vec::Vec;
 use ockam_core::{Address, Result};
 
 use crate::models::Identifier;
+use crate::secure_channel::RekeyMetrics;
 use crate::IdentityError;
 
 /// Known information about particular SecureChannel
@@ -17,6 +18,7 @@ pub struct SecureChannelRegistryEntry {
     my_id: Identifier,
     their_id: Identifier,
     their_decryptor_address: Address,
+    rekey_metrics: RekeyMetrics,
 }
 
 impl SecureChannelRegistryEntry {
@@ -41,9 +43,16 @@ impl SecureChannelRegistryEntry {
             my_id,
             their_id,
             their_decryptor_address,
+            rekey_metrics: RekeyMetrics::default(),
         }
     }
 
+    /// Share the key renewal counters of the channel
+    pub fn with_rekey_metrics(mut self, rekey_metrics: RekeyMetrics) -> Self {
+        self.rekey_metrics = rekey_metrics;
+        self
+    }
+
     /// Encryptor messaging address
     pub fn encryptor_messaging_address(&self) -> &Address {
         &self.encryptor_messaging_address
@@ -83,6 +92,11 @@ impl SecureChannelRegistryEntry {
     pub fn their_decryptor_address(&self) -> Address {
         self.their_decryptor_address.clone()
     }
+
+    /// Number of key renewals done on this channel
+    pub fn rekey_metrics(&self) -> &RekeyMetrics {
+        &self.rekey_metrics
+    }
 }
 
 /// Registry of all known Secure Channels
@@ -0,0 +1,205 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+use ockam_core::compat::sync::Arc;
+
+use crate::secure_channel::encryptor::KEY_RENEWAL_INTERVAL;
+use crate::{Nonce, TimestampInSeconds};
+
+/// Policy deciding when the encryption key of a secure channel must be renewed,
+/// in addition to the renewal done every [`KEY_RENEWAL_INTERVAL`] messages.
+///
+/// The policy is only applied by the encryptor. A rekey is signalled in-band to the other
+/// party by moving the nonce of the next message to the start of the next key interval,
+/// so that both parties derive the same new key for that message.
+///
+/// The limits are checked when a message is encrypted, hence an idle channel renews
+/// its key before sending its next message.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RekeyPolicy {
+    max_age: Option<Duration>,
+    max_bytes: Option<u64>,
+    max_messages: Option<u64>,
+}
+
+impl RekeyPolicy {
+    /// Renew the key when it has been used for longer than `max_age`.
+    /// The age is measured with a precision of one second
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Renew the key when `max_bytes` bytes of payload have been encrypted with it
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Renew the key when `max_messages` messages have been encrypted with it.
+    /// Values larger than [`KEY_RENEWAL_INTERVAL`] have no effect
+    pub fn with_max_messages(mut self, max_messages: u64) -> Self {
+        self.max_messages = Some(max_messages);
+        self
+    }
+
+    /// Maximum age of a key
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+
+    /// Maximum number of bytes encrypted with a key
+    pub fn max_bytes(&self) -> Option<u64> {
+        self.max_bytes
+    }
+
+    /// Maximum number of messages encrypted with a key
+    pub fn max_messages(&self) -> Option<u64> {
+        self.max_messages
+    }
+
+    /// Return true if no limit is set
+    pub fn is_empty(&self) -> bool {
+        self.max_age.is_none() && self.max_bytes.is_none() && self.max_messages.is_none()
+    }
+}
+
+/// Usage of the current encryption key, checked against a [`RekeyPolicy`]
+pub(crate) struct KeyUsage {
+    policy: RekeyPolicy,
+    created_at: TimestampInSeconds,
+    bytes: u64,
+    messages: u64,
+}
+
+impl KeyUsage {
+    pub(crate) fn new(policy: RekeyPolicy, now: TimestampInSeconds) -> Self {
+        Self {
+            policy,
+            created_at: now,
+            bytes: 0,
+            messages: 0,
+        }
+    }
+
+    /// Return true if one of the policy limits is reached
+    pub(crate) fn is_exhausted(&self, now: TimestampInSeconds) -> bool {
+        let too_old = self
+            .policy
+            .max_age
+            .map(|max_age| now.0.saturating_sub(self.created_at.0) >= max_age.as_secs())
+            .unwrap_or(false);
+        let too_many_bytes = self
+            .policy
+            .max_bytes
+            .map(|max_bytes| self.bytes >= max_bytes)
+            .unwrap_or(false);
+        let too_many_messages = self
+            .policy
+            .max_messages
+            .map(|max_messages| self.messages >= max_messages)
+            .unwrap_or(false);
+
+        too_old || too_many_bytes || too_many_messages
+    }
+
+    /// Record a message encrypted with the current key
+    pub(crate) fn record(&mut self, bytes: usize) {
+        self.bytes = self.bytes.saturating_add(bytes as u64);
+        self.messages = self.messages.saturating_add(1);
+    }
+
+    /// Start tracking a new key
+    pub(crate) fn reset(&mut self, now: TimestampInSeconds) {
+        *self = Self::new(self.policy, now);
+    }
+
+    /// Return the first nonce of the next key interval.
+    /// Using that nonce makes both parties switch to a new key
+    pub(crate) fn next_interval_start(nonce: Nonce) -> Option<Nonce> {
+        let interval = nonce.value() / KEY_RENEWAL_INTERVAL + 1;
+        interval
+            .checked_mul(KEY_RENEWAL_INTERVAL)
+            .map(|value| value.into())
+    }
+}
+
+/// Number of key renewals performed on each side of a secure channel since it was started
+/// on this node
+#[derive(Clone, Debug, Default)]
+pub struct RekeyMetrics {
+    encryption_rekeys: Arc<AtomicU64>,
+    decryption_rekeys: Arc<AtomicU64>,
+}
+
+impl RekeyMetrics {
+    /// Number of times the encryption key was renewed
+    pub fn encryption_rekeys(&self) -> u64 {
+        self.encryption_rekeys.load(Ordering::Relaxed)
+    }
+
+    /// Number of times the decryption key was renewed
+    pub fn decryption_rekeys(&self) -> u64 {
+        self.decryption_rekeys.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record_encryption_rekey(&self) {
+        self.encryption_rekeys.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_decryption_rekey(&self) {
+        self.decryption_rekeys.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_usage() {
+        let policy = RekeyPolicy::default()
+            .with_max_age(Duration::from_secs(60))
+            .with_max_bytes(100)
+            .with_max_messages(3);
+
+        let mut usage = KeyUsage::new(policy, TimestampInSeconds(1000));
+        assert!(!usage.is_exhausted(TimestampInSeconds(1000)));
+        assert!(usage.is_exhausted(TimestampInSeconds(1060)), "too old");
+
+        usage.record(10);
+        usage.record(10);
+        assert!(!usage.is_exhausted(TimestampInSeconds(1000)));
+        usage.record(10);
+        assert!(
+            usage.is_exhausted(TimestampInSeconds(1000)),
+            "too many messages"
+        );
+
+        usage.reset(TimestampInSeconds(1000));
+        usage.record(100);
+        assert!(
+            usage.is_exhausted(TimestampInSeconds(1000)),
+            "too many bytes"
+        );
+
+        let usage = KeyUsage::new(RekeyPolicy::default(), TimestampInSeconds(0));
+        assert!(!usage.is_exhausted(TimestampInSeconds(u64::MAX)));
+    }
+
+    #[test]
+    fn test_next_interval_start() {
+        assert_eq!(
+            KeyUsage::next_interval_start(1.into()),
+            Some(KEY_RENEWAL_INTERVAL.into())
+        );
+        assert_eq!(
+            KeyUsage::next_interval_start((KEY_RENEWAL_INTERVAL - 1).into()),
+            Some(KEY_RENEWAL_INTERVAL.into())
+        );
+        assert_eq!(
+            KeyUsage::next_interval_start((KEY_RENEWAL_INTERVAL + 1).into()),
+            Some((2 * KEY_RENEWAL_INTERVAL).into())
+        );
+        assert_eq!(KeyUsage::next_interval_start(u64::MAX.into()), None);
+    }
+}
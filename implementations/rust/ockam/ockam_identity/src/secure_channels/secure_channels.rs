@@ -1,24 +1,26 @@
 use core::sync::atomic::AtomicBool;
-use ockam_core::compat::sync::Arc;
-use ockam_core::flow_control::FlowControls;
-use ockam_core::Result;
-use ockam_core::{Address, Route};
+use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::flow_control::{FlowControlId, FlowControls};
+use ockam_core::{route, Result};
+use ockam_core::{Address, OutgoingAccessControl, Route};
 use ockam_node::{Context, WorkerBuilder};
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::identities::Identities;
-use crate::models::Identifier;
+use crate::models::{Identifier, RevocationListAndPurposeKey};
+use crate::secure_channel::handshake_state_machine::CommonStateMachine;
 use crate::secure_channel::handshake_worker::HandshakeWorker;
 use crate::secure_channel::{
-    Addresses, DecryptorHandler, RemoteRoute, Role, SecureChannelListenerOptions,
-    SecureChannelListenerWorker, SecureChannelOptions, SecureChannelRegistry,
-    SecureChannelSharedState,
+    Addresses, Decryptor, DecryptorHandler, Encryptor, EncryptorWorker, HandshakePattern,
+    KnownPeers, NonceReservation, PaddingPolicy, RekeyPolicy, RemoteRoute, ReservationSide, Role,
+    SecureChannelListenerOptions, SecureChannelListenerWorker, SecureChannelOptions,
+    SecureChannelRegistry, SecureChannelSharedState,
 };
 #[cfg(feature = "storage")]
 use crate::SecureChannelsBuilder;
 use crate::{
-    IdentityError, SecureChannel, SecureChannelListener, SecureChannelRegistryEntry,
-    SecureChannelRepository, Vault,
+    CredentialRetriever, IdentityError, PersistedSecureChannel, SecureChannel,
+    SecureChannelListener, SecureChannelRegistryEntry, SecureChannelRepository, Vault,
 };
 
 /// Identity implementation
@@ -27,6 +29,7 @@ pub struct SecureChannels {
     pub(crate) identities: Arc<Identities>,
     pub(crate) secure_channel_registry: SecureChannelRegistry,
     pub(crate) secure_channel_repository: Arc<dyn SecureChannelRepository>,
+    pub(crate) known_peers: KnownPeers,
 }
 
 impl SecureChannels {
@@ -40,6 +43,7 @@ impl SecureChannels {
             identities,
             secure_channel_registry,
             secure_channel_repository,
+            known_peers: KnownPeers::default(),
         }
     }
 
@@ -75,6 +79,11 @@ impl SecureChannels {
         self.secure_channel_repository.clone()
     }
 
+    /// Return the peers whose static key is known from a previous handshake
+    pub fn known_peers(&self) -> KnownPeers {
+        self.known_peers.clone()
+    }
+
     /// Create a builder for secure channels
     #[cfg(feature = "storage")]
     pub async fn builder() -> Result<SecureChannelsBuilder> {
@@ -116,6 +125,10 @@ impl SecureChannels {
     }
 
     /// Initiate a SecureChannel using `Route` to the SecureChannel listener and [`SecureChannelOptions`]
+    ///
+    /// If a IK or KK handshake fails, for example because the responder changed its static key
+    /// or doesn't know the initiator static key anymore, the responder is removed from the
+    /// [`KnownPeers`] and a XX handshake is attempted instead
     pub async fn create_secure_channel(
         &self,
         ctx: &Context,
@@ -123,11 +136,59 @@ impl SecureChannels {
         route: impl Into<Route>,
         options: impl Into<SecureChannelOptions>,
     ) -> Result<SecureChannel> {
-        let addresses = Addresses::generate(Role::Initiator);
         let options = options.into();
+        let route = route.into();
+
+        let known_responder = options
+            .known_responder
+            .clone()
+            .filter(|_| options.handshake_pattern.requires_known_responder())
+            .filter(|responder| self.known_peers.get_peer(responder).is_some());
+
+        let addresses = Addresses::generate(Role::Initiator);
+        let result = self
+            .start_initiator(
+                ctx,
+                identifier,
+                route.clone(),
+                &options,
+                options.handshake_pattern,
+                addresses.clone(),
+            )
+            .await;
+
+        match (result, known_responder) {
+            (Err(err), Some(responder)) => {
+                warn!(%responder, %err, handshake_pattern=?options.handshake_pattern,
+                    "the handshake with a known responder failed, falling back to a XX handshake");
+                let _ = ctx.stop_address(&addresses.decryptor_remote);
+                self.known_peers.remove_peer(&responder);
+                self.start_initiator(
+                    ctx,
+                    identifier,
+                    route,
+                    &options,
+                    HandshakePattern::XX,
+                    Addresses::generate(Role::Initiator),
+                )
+                .await
+            }
+            (result, _) => result,
+        }
+    }
+
+    /// Start the initiator side of a secure channel with the given handshake pattern
+    async fn start_initiator(
+        &self,
+        ctx: &Context,
+        identifier: &Identifier,
+        route: Route,
+        options: &SecureChannelOptions,
+        handshake_pattern: HandshakePattern,
+        addresses: Addresses,
+    ) -> Result<SecureChannel> {
         let flow_control_id = options.flow_control_id.clone();
 
-        let route = route.into();
         let next = route.next()?;
         options.setup_flow_control(ctx.flow_controls(), &addresses, next);
         let decryptor_outgoing_access_control =
@@ -163,15 +224,21 @@ impl SecureChannels {
             addresses.clone(),
             identifier.clone(),
             purpose_key,
-            options.trust_policy,
+            options.trust_policy.clone(),
             decryptor_outgoing_access_control,
             credential_retriever,
-            options.authority,
+            options.authority.clone(),
             Some(route),
             Some(options.timeout),
             Role::Initiator,
             options.key_exchange_only,
+            options.key_exchange_mode,
+            handshake_pattern,
+            options.known_responder.clone(),
+            options.rekey_policy,
+            options.padding_policy,
             secure_channel_repository,
+            None,
             encryptor_remote_route.clone(),
         )
         .await?
@@ -222,6 +289,7 @@ impl SecureChannels {
         let shared_state = SecureChannelSharedState {
             remote_route: RemoteRoute::create(),                 // Unused
             should_send_close: Arc::new(AtomicBool::new(false)), // Don't need to send anything
+            rekey_metrics: Default::default(),
         };
 
         let mut addresses = Addresses::generate(role);
@@ -289,8 +357,407 @@ impl SecureChannels {
         Ok(sc)
     }
 
+    /// Resume a regular secure channel which was created as an initiator with
+    /// [`SecureChannelOptions::persist`], for example after a node restart.
+    /// `route` is the route to the node of the other party, without the other party decryptor address.
+    /// It is empty if the other party is on the same node.
+    /// The other party must still have its side of the secure channel, live or resumed.
+    pub async fn resume_secure_channel(
+        &self,
+        ctx: &Context,
+        decryptor_remote_address: &Address,
+        route: impl Into<Route>,
+        options: impl Into<SecureChannelOptions>,
+    ) -> Result<SecureChannel> {
+        let persisted_secure_channel = self
+            .get_resumable_secure_channel(decryptor_remote_address, Role::Initiator)
+            .await?;
+        let addresses = Self::resumed_addresses(&persisted_secure_channel);
+        let options = options.into();
+        let flow_control_id = options.flow_control_id.clone();
+
+        let route = route.into();
+        match route.next() {
+            Ok(next) => options.setup_flow_control(ctx.flow_controls(), &addresses, next),
+            // the other party is on the same node
+            Err(_) => SecureChannelOptions::setup_flow_control_producer(
+                &flow_control_id,
+                ctx.flow_controls(),
+                &addresses,
+            ),
+        }
+        let decryptor_outgoing_access_control =
+            options.create_decryptor_outgoing_access_control(ctx.flow_controls());
+        let credential_retriever = match &options.credential_retriever_creator {
+            Some(credential_retriever_creator) => Some(
+                credential_retriever_creator
+                    .create(persisted_secure_channel.my_identifier())
+                    .await?,
+            ),
+            None => None,
+        };
+
+        self.start_resumed_secure_channel(
+            ctx,
+            persisted_secure_channel,
+            addresses,
+            Some(route),
+            decryptor_outgoing_access_control,
+            credential_retriever,
+            options.rekey_policy,
+            options.padding_policy,
+            flow_control_id,
+        )
+        .await
+    }
+
+    /// Resume a regular secure channel which was spawned by a listener created with
+    /// [`SecureChannelListenerOptions::persist`], for example after a node restart.
+    /// The listener must have been restarted at `listener_address`.
+    /// Messages can only be sent on that secure channel once the initiator sent a message,
+    /// since the route to the initiator is unknown until then.
+    pub async fn resume_secure_channel_for_listener(
+        &self,
+        ctx: &Context,
+        listener_address: &Address,
+        decryptor_remote_address: &Address,
+        options: impl Into<SecureChannelListenerOptions>,
+    ) -> Result<SecureChannel> {
+        self.resume_listener_secure_channel(
+            ctx,
+            listener_address,
+            decryptor_remote_address,
+            &options.into(),
+        )
+        .await
+    }
+
+    async fn resume_listener_secure_channel(
+        &self,
+        ctx: &Context,
+        listener_address: &Address,
+        decryptor_remote_address: &Address,
+        options: &SecureChannelListenerOptions,
+    ) -> Result<SecureChannel> {
+        let persisted_secure_channel = self
+            .get_resumable_secure_channel(decryptor_remote_address, Role::Responder)
+            .await?;
+        let addresses = Self::resumed_addresses(&persisted_secure_channel);
+
+        let flow_control_id = options.setup_flow_control_for_channel(
+            ctx.flow_controls(),
+            listener_address,
+            &addresses,
+        );
+        let decryptor_outgoing_access_control = options
+            .create_decryptor_outgoing_access_control(ctx.flow_controls(), flow_control_id.clone());
+        let credential_retriever = match &options.credential_retriever_creator {
+            Some(credential_retriever_creator) => Some(
+                credential_retriever_creator
+                    .create(persisted_secure_channel.my_identifier())
+                    .await?,
+            ),
+            None => None,
+        };
+
+        self.start_resumed_secure_channel(
+            ctx,
+            persisted_secure_channel,
+            addresses,
+            None,
+            decryptor_outgoing_access_control,
+            credential_retriever,
+            options.rekey_policy,
+            options.padding_policy,
+            flow_control_id,
+        )
+        .await
+    }
+
+    /// Resume all the regular secure channels which were spawned by a listener created with
+    /// [`SecureChannelListenerOptions::persist`] at the address of `listener`, for the identifier
+    /// of that listener. This is done once the listener is restarted, for example after a node restart.
+    /// The resumed secure channels use the flow control id of `listener` as their spawner.
+    /// A secure channel which can't be resumed is skipped.
+    pub async fn resume_secure_channels_for_listener(
+        &self,
+        ctx: &Context,
+        identifier: &Identifier,
+        listener: &SecureChannelListener,
+        options: impl Into<SecureChannelListenerOptions>,
+    ) -> Result<Vec<SecureChannel>> {
+        let mut options = options.into();
+        options.flow_control_id = listener.flow_control_id().clone();
+        let listener_address = listener.address();
+        let mut secure_channels = vec![];
+        for persisted_secure_channel in self.secure_channel_repository.get_all().await? {
+            let Some(state) = persisted_secure_channel.state() else {
+                continue;
+            };
+            if persisted_secure_channel.role() != Role::Responder
+                || persisted_secure_channel.my_identifier() != identifier
+                || state.listener() != Some(listener_address)
+            {
+                continue;
+            }
+
+            let decryptor_remote = persisted_secure_channel.decryptor_remote();
+            match self
+                .resume_listener_secure_channel(ctx, listener_address, decryptor_remote, &options)
+                .await
+            {
+                Ok(secure_channel) => secure_channels.push(secure_channel),
+                Err(err) => {
+                    warn!(%decryptor_remote, %err, "Error while resuming a persisted secure channel")
+                }
+            }
+        }
+        Ok(secure_channels)
+    }
+
+    /// Return a persisted regular secure channel if it was created with the expected role
+    async fn get_resumable_secure_channel(
+        &self,
+        decryptor_remote_address: &Address,
+        role: Role,
+    ) -> Result<PersistedSecureChannel> {
+        let Some(persisted_secure_channel) = self
+            .secure_channel_repository
+            .get(decryptor_remote_address)
+            .await?
+        else {
+            return Err(IdentityError::PersistentSecureChannelNotFound)?;
+        };
+
+        if persisted_secure_channel.role() != role || persisted_secure_channel.state().is_none() {
+            return Err(IdentityError::PersistentSecureChannelCannotBeResumed)?;
+        }
+
+        Ok(persisted_secure_channel)
+    }
+
+    /// The addresses used by the other party and the local workers are kept,
+    /// only the internal and api addresses of the encryptor are regenerated
+    fn resumed_addresses(persisted_secure_channel: &PersistedSecureChannel) -> Addresses {
+        let mut addresses = Addresses::generate(persisted_secure_channel.role());
+        addresses.decryptor_remote = persisted_secure_channel.decryptor_remote().clone();
+        addresses.decryptor_api = persisted_secure_channel.decryptor_api().clone();
+        if let Some(state) = persisted_secure_channel.state() {
+            addresses.encryptor = state.encryptor().clone();
+        }
+        addresses
+    }
+
+    /// Start the encryptor and the decryptor of a resumed secure channel
+    #[allow(clippy::too_many_arguments)]
+    async fn start_resumed_secure_channel(
+        &self,
+        ctx: &Context,
+        persisted_secure_channel: PersistedSecureChannel,
+        addresses: Addresses,
+        route: Option<Route>,
+        decryptor_outgoing_access_control: Arc<dyn OutgoingAccessControl>,
+        credential_retriever: Option<Arc<dyn CredentialRetriever>>,
+        rekey_policy: RekeyPolicy,
+        padding_policy: PaddingPolicy,
+        flow_control_id: FlowControlId,
+    ) -> Result<SecureChannel> {
+        let Some(state) = persisted_secure_channel.state().cloned() else {
+            return Err(IdentityError::PersistentSecureChannelCannotBeResumed)?;
+        };
+        info!(
+            "Resuming persisted secure channel: {}",
+            addresses.decryptor_remote
+        );
+
+        let role = persisted_secure_channel.role();
+        let my_identifier = persisted_secure_channel.my_identifier().clone();
+        let their_identifier = persisted_secure_channel.their_identifier().clone();
+        let vault = self.vault().secure_channel_vault;
+        let decryption_key = persisted_secure_channel.decryption_key_handle().clone();
+        let encryption_key = state.encryption_key_handle().clone();
+        vault.load_aead_key(&decryption_key).await?;
+        vault.load_aead_key(&encryption_key).await?;
+
+        // restore the attributes of the other party
+        CommonStateMachine::verify_credentials(
+            self.identities(),
+            state.authority().cloned(),
+            &their_identifier,
+            state.their_credentials().to_vec(),
+        )
+        .await?;
+
+        let remote_route = match route {
+            Some(route) => route + state.their_decryptor_remote().clone(),
+            // The route is updated when the initiator sends a message
+            None => route![state.their_decryptor_remote().clone()],
+        };
+        let shared_state = SecureChannelSharedState {
+            remote_route: Arc::new(RwLock::new(RemoteRoute {
+                route: remote_route.clone(),
+                last_nonce: 0.into(),
+            })),
+            should_send_close: Arc::new(AtomicBool::new(false)),
+            rekey_metrics: Default::default(),
+        };
+
+        let decryptor_handler = DecryptorHandler::new(
+            self.identities(),
+            state.authority().cloned(),
+            role,
+            false,
+            addresses.clone(),
+            decryption_key.clone(),
+            vault.clone(),
+            their_identifier.clone(),
+            shared_state.clone(),
+        )
+        .with_decryptor(
+            Decryptor::restore(
+                decryption_key.clone(),
+                vault.clone(),
+                state.decryption_number_of_rekeys(),
+                state.decryption_nonce(),
+            )
+            .with_nonce_reservation(NonceReservation::new(
+                ReservationSide::Decryptor,
+                self.secure_channel_repository(),
+                vault.clone(),
+                addresses.decryptor_remote.clone(),
+                Some(decryption_key),
+                state.decryption_nonce(),
+            ))
+            .with_rekey_metrics(shared_state.rekey_metrics.clone()),
+        )
+        .with_padding_policy(padding_policy);
+
+        let decryptor_worker = HandshakeWorker::new(
+            Arc::new(self.clone()),
+            None,
+            None,
+            my_identifier.clone(),
+            addresses.clone(),
+            role,
+            false,
+            Some(remote_route),
+            Some(decryptor_handler),
+            state.authority().cloned(),
+            self.identities.change_history_repository(),
+            credential_retriever.clone(),
+            Some(self.secure_channel_repository()),
+            shared_state.clone(),
+        );
+        WorkerBuilder::new(decryptor_worker)
+            .with_mailboxes(HandshakeWorker::create_mailboxes(
+                &addresses,
+                decryptor_outgoing_access_control,
+            ))
+            .start(ctx)?;
+
+        // the encryptor starts from the end of its last reservation
+        let encryptor = Encryptor::restore(
+            encryption_key.clone(),
+            state.encryption_number_of_rekeys(),
+            state.encryption_nonce(),
+            vault.clone(),
+            true,
+        )
+        .await?
+        .with_nonce_reservation(NonceReservation::new(
+            ReservationSide::Encryptor,
+            self.secure_channel_repository(),
+            vault,
+            addresses.decryptor_remote.clone(),
+            Some(encryption_key),
+            state.encryption_nonce(),
+        ))
+        .with_rekey_policy(rekey_policy)?
+        .with_rekey_metrics(shared_state.rekey_metrics.clone());
+        let encryptor_worker = EncryptorWorker::new(
+            role.str(),
+            false,
+            addresses.clone(),
+            encryptor,
+            my_identifier.clone(),
+            self.identities.change_history_repository(),
+            credential_retriever,
+            None,
+            shared_state.clone(),
+        )
+        .with_padding_policy(padding_policy);
+        HandshakeWorker::start_encryptor_worker(
+            ctx,
+            &addresses,
+            &their_identifier,
+            encryptor_worker,
+        )?;
+
+        let info = SecureChannelRegistryEntry::new(
+            addresses.encryptor.clone(),
+            addresses.encryptor_api.clone(),
+            addresses.decryptor_remote.clone(),
+            addresses.decryptor_api.clone(),
+            role.is_initiator(),
+            my_identifier,
+            their_identifier.clone(),
+            state.their_decryptor_remote().clone(),
+        )
+        .with_rekey_metrics(shared_state.rekey_metrics.clone());
+        self.secure_channel_registry.register_channel(info)?;
+
+        Ok(SecureChannel::new(
+            ctx.flow_controls().clone(),
+            their_identifier,
+            shared_state.remote_route,
+            addresses,
+            false,
+            flow_control_id,
+        ))
+    }
+
     /// Stop a SecureChannel given an encryptor address
     pub fn stop_secure_channel(&self, ctx: &Context, channel: &Address) -> Result<()> {
         ctx.stop_address(channel)
     }
+
+    /// Receive a revocation list from one of the given authorities and stop the
+    /// secure channels established with identities whose credential is revoked
+    pub async fn receive_revocation_list(
+        &self,
+        ctx: &Context,
+        authorities: &[Identifier],
+        revocation_list: &RevocationListAndPurposeKey,
+    ) -> Result<()> {
+        let revoked_subjects = self
+            .identities
+            .credentials()
+            .credentials_verification()
+            .receive_revocation_list(authorities, revocation_list)
+            .await?;
+
+        if revoked_subjects.is_empty() {
+            return Ok(());
+        }
+
+        for channel in self.secure_channel_registry.get_channel_list() {
+            if revoked_subjects.contains(channel.their_id()) {
+                info!(
+                    "stopping the secure channel {} with {}: its credential was revoked",
+                    channel.encryptor_messaging_address(),
+                    channel.their_id()
+                );
+                if let Err(err) =
+                    self.stop_secure_channel(ctx, channel.encryptor_messaging_address())
+                {
+                    warn!(
+                        "could not stop the secure channel {}: {err}",
+                        channel.encryptor_messaging_address()
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
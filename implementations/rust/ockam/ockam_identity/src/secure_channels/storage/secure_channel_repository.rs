@@ -1,8 +1,10 @@
+use crate::models::CredentialAndPurposeKey;
 use crate::secure_channel::Role;
 use crate::Identifier;
 use async_trait::async_trait;
 use core::fmt::Debug;
 use ockam_core::compat::boxed::Box;
+use ockam_core::compat::vec::Vec;
 use ockam_core::{Address, Result};
 #[cfg(feature = "std")]
 use ockam_node::database::AutoRetry;
@@ -10,6 +12,8 @@ use ockam_node::database::AutoRetry;
 use ockam_node::retry;
 use ockam_vault::AeadSecretKeyHandle;
 
+use crate::Nonce;
+
 /// Secure Channel that was saved to a storage
 #[derive(Clone, Eq, Debug, PartialEq)]
 pub struct PersistedSecureChannel {
@@ -19,6 +23,120 @@ pub struct PersistedSecureChannel {
     decryptor_remote: Address,
     decryptor_api: Address,
     decryption_key_handle: AeadSecretKeyHandle,
+    state: Option<PersistedSecureChannelState>,
+}
+
+/// State of a regular secure channel which was saved to a storage.
+/// Key exchange only secure channels don't have such a state since they don't
+/// exchange messages and don't rekey
+#[derive(Clone, Eq, Debug, PartialEq)]
+pub struct PersistedSecureChannelState {
+    encryptor: Address,
+    their_decryptor_remote: Address,
+    authority: Option<Identifier>,
+    their_credentials: Vec<CredentialAndPurposeKey>,
+    listener: Option<Address>,
+    encryption_key_handle: AeadSecretKeyHandle,
+    encryption_number_of_rekeys: u64,
+    encryption_nonce: Nonce,
+    decryption_number_of_rekeys: u64,
+    decryption_nonce: Nonce,
+}
+
+impl PersistedSecureChannelState {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        encryptor: Address,
+        their_decryptor_remote: Address,
+        authority: Option<Identifier>,
+        encryption_key_handle: AeadSecretKeyHandle,
+        encryption_number_of_rekeys: u64,
+        encryption_nonce: Nonce,
+        decryption_number_of_rekeys: u64,
+        decryption_nonce: Nonce,
+    ) -> Self {
+        Self {
+            encryptor,
+            their_decryptor_remote,
+            authority,
+            their_credentials: Vec::new(),
+            listener: None,
+            encryption_key_handle,
+            encryption_number_of_rekeys,
+            encryption_nonce,
+            decryption_number_of_rekeys,
+            decryption_nonce,
+        }
+    }
+
+    /// Set the credentials presented by the other party
+    pub(crate) fn with_their_credentials(
+        mut self,
+        their_credentials: Vec<CredentialAndPurposeKey>,
+    ) -> Self {
+        self.their_credentials = their_credentials;
+        self
+    }
+
+    /// Set the address of the listener which spawned a responder secure channel
+    pub(crate) fn with_listener(mut self, listener: Option<Address>) -> Self {
+        self.listener = listener;
+        self
+    }
+
+    /// Encryptor address. See [`Addresses`]
+    pub fn encryptor(&self) -> &Address {
+        &self.encryptor
+    }
+
+    /// Decryptor remote address of the other party
+    pub fn their_decryptor_remote(&self) -> &Address {
+        &self.their_decryptor_remote
+    }
+
+    /// Authority used to verify the credentials of the other party
+    pub fn authority(&self) -> Option<&Identifier> {
+        self.authority.as_ref()
+    }
+
+    /// Last credentials presented by the other party.
+    /// They are verified again when the secure channel is resumed, in order to restore the
+    /// attributes of the other party
+    pub fn their_credentials(&self) -> &[CredentialAndPurposeKey] {
+        &self.their_credentials
+    }
+
+    /// Address of the listener which spawned a responder secure channel
+    pub fn listener(&self) -> Option<&Address> {
+        self.listener.as_ref()
+    }
+
+    /// Current encryption key
+    pub fn encryption_key_handle(&self) -> &AeadSecretKeyHandle {
+        &self.encryption_key_handle
+    }
+
+    /// Number of rekeys done by the encryptor to get the current encryption key
+    pub fn encryption_number_of_rekeys(&self) -> u64 {
+        self.encryption_number_of_rekeys
+    }
+
+    /// First nonce that can be used for encryption after a restart.
+    /// All the nonces before that value might have been used already
+    pub fn encryption_nonce(&self) -> Nonce {
+        self.encryption_nonce
+    }
+
+    /// Number of rekeys done by the decryptor to get the current decryption key
+    pub fn decryption_number_of_rekeys(&self) -> u64 {
+        self.decryption_number_of_rekeys
+    }
+
+    /// First nonce that can be accepted for decryption after a restart.
+    /// All the nonces before that value might have been received already
+    pub fn decryption_nonce(&self) -> Nonce {
+        self.decryption_nonce
+    }
 }
 
 impl PersistedSecureChannel {
@@ -37,9 +155,16 @@ impl PersistedSecureChannel {
             decryptor_remote,
             decryptor_api,
             decryption_key_handle,
+            state: None,
         }
     }
 
+    /// Add the state of a regular secure channel
+    pub(crate) fn with_state(mut self, state: PersistedSecureChannelState) -> Self {
+        self.state = Some(state);
+        self
+    }
+
     /// Role
     pub fn role(&self) -> Role {
         self.role
@@ -69,6 +194,11 @@ impl PersistedSecureChannel {
     pub fn decryption_key_handle(&self) -> &AeadSecretKeyHandle {
         &self.decryption_key_handle
     }
+
+    /// State of a regular secure channel, None for a key exchange only secure channel
+    pub fn state(&self) -> Option<&PersistedSecureChannelState> {
+        self.state.as_ref()
+    }
 }
 
 /// Repository for persisted Secure Channels
@@ -80,9 +210,39 @@ pub trait SecureChannelRepository: Send + Sync + 'static {
         decryptor_remote_address: &Address,
     ) -> Result<Option<PersistedSecureChannel>>;
 
+    /// Get all the persisted secure channels
+    async fn get_all(&self) -> Result<Vec<PersistedSecureChannel>>;
+
     /// Store a secure channel
     async fn put(&self, secure_channel: PersistedSecureChannel) -> Result<()>;
 
+    /// Update the encryption key, its number of rekeys and the first encryption nonce
+    /// to use after a restart
+    async fn update_encryption_state(
+        &self,
+        decryptor_remote_address: &Address,
+        encryption_key_handle: &AeadSecretKeyHandle,
+        encryption_number_of_rekeys: u64,
+        encryption_nonce: Nonce,
+    ) -> Result<()>;
+
+    /// Update the decryption key, its number of rekeys and the first decryption nonce
+    /// to accept after a restart
+    async fn update_decryption_state(
+        &self,
+        decryptor_remote_address: &Address,
+        decryption_key_handle: &AeadSecretKeyHandle,
+        decryption_number_of_rekeys: u64,
+        decryption_nonce: Nonce,
+    ) -> Result<()>;
+
+    /// Update the credentials presented by the other party
+    async fn update_their_credentials(
+        &self,
+        decryptor_remote_address: &Address,
+        their_credentials: &[CredentialAndPurposeKey],
+    ) -> Result<()>;
+
     /// Delete a secure channel
     async fn delete(&self, decryptor_remote_address: &Address) -> Result<()>;
 }
@@ -97,10 +257,54 @@ impl<T: SecureChannelRepository> SecureChannelRepository for AutoRetry<T> {
         retry!(self.wrapped.get(decryptor_remote_address))
     }
 
+    async fn get_all(&self) -> Result<Vec<PersistedSecureChannel>> {
+        retry!(self.wrapped.get_all())
+    }
+
     async fn put(&self, secure_channel: PersistedSecureChannel) -> Result<()> {
         retry!(self.wrapped.put(secure_channel.clone()))
     }
 
+    async fn update_encryption_state(
+        &self,
+        decryptor_remote_address: &Address,
+        encryption_key_handle: &AeadSecretKeyHandle,
+        encryption_number_of_rekeys: u64,
+        encryption_nonce: Nonce,
+    ) -> Result<()> {
+        retry!(self.wrapped.update_encryption_state(
+            decryptor_remote_address,
+            encryption_key_handle,
+            encryption_number_of_rekeys,
+            encryption_nonce
+        ))
+    }
+
+    async fn update_decryption_state(
+        &self,
+        decryptor_remote_address: &Address,
+        decryption_key_handle: &AeadSecretKeyHandle,
+        decryption_number_of_rekeys: u64,
+        decryption_nonce: Nonce,
+    ) -> Result<()> {
+        retry!(self.wrapped.update_decryption_state(
+            decryptor_remote_address,
+            decryption_key_handle,
+            decryption_number_of_rekeys,
+            decryption_nonce
+        ))
+    }
+
+    async fn update_their_credentials(
+        &self,
+        decryptor_remote_address: &Address,
+        their_credentials: &[CredentialAndPurposeKey],
+    ) -> Result<()> {
+        retry!(self
+            .wrapped
+            .update_their_credentials(decryptor_remote_address, their_credentials))
+    }
+
     async fn delete(&self, decryptor_remote_address: &Address) -> Result<()> {
         retry!(self.wrapped.delete(decryptor_remote_address))
     }
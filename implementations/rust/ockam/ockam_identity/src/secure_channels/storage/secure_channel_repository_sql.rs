@@ -2,30 +2,47 @@ use sqlx::*;
 use std::sync::Arc;
 use tracing::debug;
 
-use crate::secure_channel::Role;
+use crate::models::CredentialAndPurposeKey;
+use crate::secure_channel::{Encryptor, Role};
 use crate::Identifier;
 use ockam_core::{async_trait, Address};
 use ockam_core::{Error, Result};
 use ockam_node::database::AutoRetry;
-use ockam_node::database::{FromSqlxError, SqlxDatabase, ToVoid};
+use ockam_node::database::{FromSqlxError, Nullable, SqlxDatabase, ToVoid};
 use ockam_vault::{AeadSecretKeyHandle, HandleToSecret};
 
 use crate::secure_channels::storage::secure_channel_repository::{
-    PersistedSecureChannel, SecureChannelRepository,
+    PersistedSecureChannel, PersistedSecureChannelState, SecureChannelRepository,
 };
+use crate::Nonce;
 
 /// Implementation of `CredentialRepository` trait based on an underlying database
 /// using sqlx as its API, and Sqlite as its driver
 #[derive(Clone)]
 pub struct SecureChannelSqlxDatabase {
     database: SqlxDatabase,
+    // When set, only the secure channels of that node are returned by `get_all`
+    node_name: Option<String>,
 }
 
 impl SecureChannelSqlxDatabase {
     /// Create a new database
     pub fn new(database: SqlxDatabase) -> Self {
         debug!("create a repository for secure channels");
-        Self { database }
+        Self {
+            database,
+            node_name: None,
+        }
+    }
+
+    /// Create a new database for the secure channels of a given node.
+    /// This allows several nodes sharing the same database to resume their own secure channels
+    pub fn new_for_node(database: SqlxDatabase, node_name: &str) -> Self {
+        debug!("create a repository for the secure channels of node {node_name}");
+        Self {
+            database,
+            node_name: Some(node_name.to_string()),
+        }
     }
 
     /// Create a repository
@@ -37,6 +54,18 @@ impl SecureChannelSqlxDatabase {
         }
     }
 
+    /// Create a repository for the secure channels of a given node
+    pub fn make_repository_for_node(
+        database: SqlxDatabase,
+        node_name: &str,
+    ) -> Arc<dyn SecureChannelRepository> {
+        if database.needs_retry() {
+            Arc::new(AutoRetry::new(Self::new_for_node(database, node_name)))
+        } else {
+            Arc::new(Self::new_for_node(database, node_name))
+        }
+    }
+
     /// Create a new in-memory database
     pub async fn create() -> Result<Self> {
         Ok(Self::new(SqlxDatabase::in_memory("secure_channel").await?))
@@ -50,7 +79,7 @@ impl SecureChannelRepository for SecureChannelSqlxDatabase {
         decryptor_remote_address: &Address,
     ) -> Result<Option<PersistedSecureChannel>> {
         let query = query_as(
-            "SELECT role, my_identifier, their_identifier, decryptor_remote_address, decryptor_api_address, decryption_key_handle FROM secure_channel WHERE decryptor_remote_address = $1"
+            "SELECT role, my_identifier, their_identifier, decryptor_remote_address, decryptor_api_address, decryption_key_handle, encryptor_address, their_decryptor_address, authority, encryption_key_handle, encryption_nonce, decryption_number_of_rekeys, decryption_nonce, their_credentials, listener_address, encryption_number_of_rekeys FROM secure_channel WHERE decryptor_remote_address = $1"
             )
             .bind(decryptor_remote_address.to_string());
         let secure_channel: Option<SecureChannelRow> = query
@@ -61,19 +90,104 @@ impl SecureChannelRepository for SecureChannelSqlxDatabase {
         Ok(secure_channel.map(TryInto::try_into).transpose()?)
     }
 
+    async fn get_all(&self) -> Result<Vec<PersistedSecureChannel>> {
+        let secure_channels: Vec<SecureChannelRow> = match &self.node_name {
+            Some(node_name) => query_as(
+                "SELECT role, my_identifier, their_identifier, decryptor_remote_address, decryptor_api_address, decryption_key_handle, encryptor_address, their_decryptor_address, authority, encryption_key_handle, encryption_nonce, decryption_number_of_rekeys, decryption_nonce, their_credentials, listener_address, encryption_number_of_rekeys FROM secure_channel WHERE node_name = $1"
+            )
+            .bind(node_name)
+            .fetch_all(&*self.database.pool)
+            .await
+            .into_core()?,
+            None => query_as(
+                "SELECT role, my_identifier, their_identifier, decryptor_remote_address, decryptor_api_address, decryption_key_handle, encryptor_address, their_decryptor_address, authority, encryption_key_handle, encryption_nonce, decryption_number_of_rekeys, decryption_nonce, their_credentials, listener_address, encryption_number_of_rekeys FROM secure_channel"
+            )
+            .fetch_all(&*self.database.pool)
+            .await
+            .into_core()?,
+        };
+
+        secure_channels
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<_>>>()
+    }
+
     async fn put(&self, secure_channel: PersistedSecureChannel) -> Result<()> {
+        let state = secure_channel.state();
+        let their_credentials = state
+            .map(|s| ockam_core::cbor_encode_preallocate(s.their_credentials()))
+            .transpose()?;
         let query = query(
-            r#"INSERT INTO secure_channel (role, my_identifier, their_identifier, decryptor_remote_address, decryptor_api_address, decryption_key_handle)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            r#"INSERT INTO secure_channel (role, my_identifier, their_identifier, decryptor_remote_address, decryptor_api_address, decryption_key_handle, encryptor_address, their_decryptor_address, authority, encryption_key_handle, encryption_nonce, decryption_number_of_rekeys, decryption_nonce, their_credentials, listener_address, encryption_number_of_rekeys, node_name)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
             ON CONFLICT (decryptor_remote_address)
-            DO UPDATE SET role = $1, my_identifier = $2, their_identifier = $3, decryptor_api_address = $5, decryption_key_handle = $6"#
+            DO UPDATE SET role = $1, my_identifier = $2, their_identifier = $3, decryptor_api_address = $5, decryption_key_handle = $6, encryptor_address = $7, their_decryptor_address = $8, authority = $9, encryption_key_handle = $10, encryption_nonce = $11, decryption_number_of_rekeys = $12, decryption_nonce = $13, their_credentials = $14, listener_address = $15, encryption_number_of_rekeys = $16, node_name = $17"#
             )
             .bind(secure_channel.role().str())
             .bind(secure_channel.my_identifier())
             .bind(secure_channel.their_identifier())
             .bind(secure_channel.decryptor_remote().to_string())
             .bind(secure_channel.decryptor_api().to_string())
-            .bind(secure_channel.decryption_key_handle());
+            .bind(secure_channel.decryption_key_handle())
+            .bind(state.map(|s| s.encryptor().to_string()))
+            .bind(state.map(|s| s.their_decryptor_remote().to_string()))
+            .bind(state.and_then(|s| s.authority().map(|a| a.to_string())))
+            .bind(state.map(|s| s.encryption_key_handle().0 .0.value().clone()))
+            .bind(state.map(|s| s.encryption_nonce().value() as i64))
+            .bind(state.map(|s| s.decryption_number_of_rekeys() as i64))
+            .bind(state.map(|s| s.decryption_nonce().value() as i64))
+            .bind(their_credentials)
+            .bind(state.and_then(|s| s.listener().map(|a| a.to_string())))
+            .bind(state.map(|s| s.encryption_number_of_rekeys() as i64))
+            .bind(self.node_name.clone());
+        query.execute(&*self.database.pool).await.void()
+    }
+
+    async fn update_encryption_state(
+        &self,
+        decryptor_remote_address: &Address,
+        encryption_key_handle: &AeadSecretKeyHandle,
+        encryption_number_of_rekeys: u64,
+        encryption_nonce: Nonce,
+    ) -> Result<()> {
+        let query = query(
+            "UPDATE secure_channel SET encryption_key_handle = $1, encryption_number_of_rekeys = $2, encryption_nonce = $3 WHERE decryptor_remote_address = $4",
+        )
+        .bind(encryption_key_handle)
+        .bind(encryption_number_of_rekeys as i64)
+        .bind(encryption_nonce.value() as i64)
+        .bind(decryptor_remote_address.to_string());
+        query.execute(&*self.database.pool).await.void()
+    }
+
+    async fn update_decryption_state(
+        &self,
+        decryptor_remote_address: &Address,
+        decryption_key_handle: &AeadSecretKeyHandle,
+        decryption_number_of_rekeys: u64,
+        decryption_nonce: Nonce,
+    ) -> Result<()> {
+        let query = query(
+            "UPDATE secure_channel SET decryption_key_handle = $1, decryption_number_of_rekeys = $2, decryption_nonce = $3 WHERE decryptor_remote_address = $4",
+        )
+        .bind(decryption_key_handle)
+        .bind(decryption_number_of_rekeys as i64)
+        .bind(decryption_nonce.value() as i64)
+        .bind(decryptor_remote_address.to_string());
+        query.execute(&*self.database.pool).await.void()
+    }
+
+    async fn update_their_credentials(
+        &self,
+        decryptor_remote_address: &Address,
+        their_credentials: &[CredentialAndPurposeKey],
+    ) -> Result<()> {
+        let query = query(
+            "UPDATE secure_channel SET their_credentials = $1 WHERE decryptor_remote_address = $2",
+        )
+        .bind(ockam_core::cbor_encode_preallocate(their_credentials)?)
+        .bind(decryptor_remote_address.to_string());
         query.execute(&*self.database.pool).await.void()
     }
 
@@ -93,12 +207,83 @@ struct SecureChannelRow {
     decryptor_remote_address: String,
     decryptor_api_address: String,
     decryption_key_handle: Vec<u8>,
+    encryptor_address: Nullable<String>,
+    their_decryptor_address: Nullable<String>,
+    authority: Nullable<String>,
+    encryption_key_handle: Nullable<Vec<u8>>,
+    encryption_nonce: Nullable<i64>,
+    decryption_number_of_rekeys: Nullable<i64>,
+    decryption_nonce: Nullable<i64>,
+    their_credentials: Nullable<Vec<u8>>,
+    listener_address: Nullable<String>,
+    encryption_number_of_rekeys: Nullable<i64>,
+}
+
+impl SecureChannelRow {
+    /// The state is only present for regular secure channels
+    fn state(&self) -> Result<Option<PersistedSecureChannelState>> {
+        let (
+            Some(encryptor_address),
+            Some(their_decryptor_address),
+            Some(encryption_key_handle),
+            Some(encryption_nonce),
+            Some(decryption_number_of_rekeys),
+            Some(decryption_nonce),
+        ) = (
+            self.encryptor_address.to_option(),
+            self.their_decryptor_address.to_option(),
+            self.encryption_key_handle.to_option(),
+            self.encryption_nonce.to_option(),
+            self.decryption_number_of_rekeys.to_option(),
+            self.decryption_nonce.to_option(),
+        )
+        else {
+            return Ok(None);
+        };
+
+        let authority = self
+            .authority
+            .to_option()
+            .map(Identifier::try_from)
+            .transpose()?;
+        let encryption_key_handle =
+            AeadSecretKeyHandle::new(HandleToSecret::new(encryption_key_handle));
+        let encryption_nonce = Nonce::from(encryption_nonce as u64);
+        // the number of rekeys was not stored before the encryption key could be saved ahead
+        // of a rekey. In that case the key is the one used for the nonce preceding the
+        // encryption nonce
+        let encryption_number_of_rekeys = match self.encryption_number_of_rekeys.to_option() {
+            Some(encryption_number_of_rekeys) => encryption_number_of_rekeys as u64,
+            None => Encryptor::number_of_rekeys_before(encryption_nonce),
+        };
+        let their_credentials: Vec<CredentialAndPurposeKey> =
+            match self.their_credentials.to_option() {
+                Some(their_credentials) => minicbor::decode(&their_credentials)?,
+                None => vec![],
+            };
+
+        Ok(Some(
+            PersistedSecureChannelState::new(
+                Address::from_string(encryptor_address),
+                Address::from_string(their_decryptor_address),
+                authority,
+                encryption_key_handle,
+                encryption_number_of_rekeys,
+                encryption_nonce,
+                decryption_number_of_rekeys as u64,
+                Nonce::from(decryption_nonce as u64),
+            )
+            .with_their_credentials(their_credentials)
+            .with_listener(self.listener_address.to_option().map(Address::from_string)),
+        ))
+    }
 }
 
 impl TryFrom<SecureChannelRow> for PersistedSecureChannel {
     type Error = Error;
 
     fn try_from(value: SecureChannelRow) -> std::result::Result<Self, Self::Error> {
+        let state = value.state()?;
         let role = Role::try_from(value.role.as_str())?;
         let my_identifier = Identifier::try_from(value.my_identifier)?;
         let their_identifier = Identifier::try_from(value.their_identifier)?;
@@ -107,14 +292,19 @@ impl TryFrom<SecureChannelRow> for PersistedSecureChannel {
         let decryption_key_handle = HandleToSecret::new(value.decryption_key_handle);
         let decryption_key_handle = AeadSecretKeyHandle::new(decryption_key_handle);
 
-        Ok(PersistedSecureChannel::new(
+        let secure_channel = PersistedSecureChannel::new(
             role,
             my_identifier,
             their_identifier,
             decryptor_remote_address,
             decryptor_api_address,
             decryption_key_handle,
-        ))
+        );
+
+        Ok(match state {
+            Some(state) => secure_channel.with_state(state),
+            None => secure_channel,
+        })
     }
 }
 
@@ -169,4 +359,92 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_secure_channel_repository_with_state() -> Result<()> {
+        let repository = Arc::new(SecureChannelSqlxDatabase::create().await?);
+
+        let decryptor_remote = Address::random_local();
+        let decryption_key_handle = random_key_handle();
+        let encryption_key_handle = random_key_handle();
+        let identifier = Identifier::try_from(
+            "Ie70dc5545d64724880257acb32b8851e7dd1dd57076838991bc343165df71bfe",
+        )?;
+
+        let sc = PersistedSecureChannel::new(
+            Role::Responder,
+            identifier.clone(),
+            identifier.clone(),
+            decryptor_remote.clone(),
+            Address::random_local(),
+            decryption_key_handle,
+        )
+        .with_state(
+            PersistedSecureChannelState::new(
+                Address::random_local(),
+                Address::random_local(),
+                Some(identifier),
+                encryption_key_handle,
+                0,
+                0.into(),
+                0,
+                0.into(),
+            )
+            .with_listener(Some(Address::random_local())),
+        );
+        repository.put(sc.clone()).await?;
+        assert_eq!(repository.get_all().await?, vec![sc]);
+
+        let new_encryption_key_handle = random_key_handle();
+        repository
+            .update_encryption_state(&decryptor_remote, &new_encryption_key_handle, 1, 48.into())
+            .await?;
+        let new_decryption_key_handle = random_key_handle();
+        repository
+            .update_decryption_state(&decryptor_remote, &new_decryption_key_handle, 2, 80.into())
+            .await?;
+
+        let sc = repository.get(&decryptor_remote).await?.unwrap();
+        assert_eq!(sc.decryption_key_handle(), &new_decryption_key_handle);
+        let state = sc.state().unwrap();
+        assert_eq!(state.encryption_key_handle(), &new_encryption_key_handle);
+        assert_eq!(state.encryption_number_of_rekeys(), 1);
+        assert_eq!(state.encryption_nonce(), 48.into());
+        assert_eq!(state.decryption_number_of_rekeys(), 2);
+        assert_eq!(state.decryption_nonce(), 80.into());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_secure_channel_repository_for_node() -> Result<()> {
+        let database = SqlxDatabase::in_memory("secure_channel").await?;
+        let repository1 = SecureChannelSqlxDatabase::new_for_node(database.clone(), "node1");
+        let repository2 = SecureChannelSqlxDatabase::new_for_node(database, "node2");
+        let identifier = Identifier::try_from(
+            "Ie70dc5545d64724880257acb32b8851e7dd1dd57076838991bc343165df71bfe",
+        )?;
+
+        let sc = PersistedSecureChannel::new(
+            Role::Responder,
+            identifier.clone(),
+            identifier,
+            Address::random_local(),
+            Address::random_local(),
+            random_key_handle(),
+        );
+        repository1.put(sc.clone()).await?;
+
+        assert_eq!(repository1.get_all().await?, vec![sc.clone()]);
+        assert_eq!(repository2.get_all().await?, vec![]);
+        assert_eq!(repository2.get(sc.decryptor_remote()).await?, Some(sc));
+
+        Ok(())
+    }
+
+    fn random_key_handle() -> AeadSecretKeyHandle {
+        let mut handle = [0u8; 32];
+        thread_rng().fill_bytes(&mut handle);
+        AeadSecretKeyHandle::new(HandleToSecret::new(handle.to_vec()))
+    }
 }
@@ -3,20 +3,23 @@ use std::sync::atomic::{AtomicU8, Ordering};
 
 use ockam_core::compat::sync::Arc;
 use ockam_core::{
-    route, Address, AllowAll, Any, DenyAll, Mailboxes, Result, Routed, SecureChannelLocalInfo,
-    Worker, SECURE_CHANNEL_IDENTIFIER,
+    async_trait, route, Address, AllowAll, Any, DenyAll, Mailboxes, Result, Routed,
+    SecureChannelLocalInfo, Worker, SECURE_CHANNEL_IDENTIFIER,
 };
 use ockam_identity::models::{CredentialSchemaIdentifier, Identifier};
 use ockam_identity::secure_channels::secure_channels;
 use ockam_identity::utils::AttributesBuilder;
 use ockam_identity::{
-    DecryptionResponse, EncryptionRequest, EncryptionResponse, IdentityAccessControlBuilder,
+    DecryptionResponse, EncryptionRequest, EncryptionResponse, HandshakePattern,
+    IdentityAccessControlBuilder, KeyExchangeMode, PaddingPolicy, RekeyPolicy,
     SecureChannelListenerOptions, SecureChannelOptions, SecureChannels, TrustEveryonePolicy,
     TrustIdentifierPolicy, Vault,
 };
 use ockam_node::{Context, MessageReceiveOptions, WorkerBuilder};
 use ockam_vault::{
+    AeadSecretKeyHandle, HKDFNumberOfOutputs, HashOutput, HkdfOutput, SecretBufferHandle,
     SoftwareVaultForSecureChannels, SoftwareVaultForSigning, SoftwareVaultForVerifyingSignatures,
+    VaultForSecureChannels, X25519PublicKey, X25519SecretKeyHandle,
 };
 
 #[ockam_macros::test]
@@ -317,6 +320,510 @@ async fn test_channel_send_multiple_messages_both_directions(ctx: &mut Context)
     Ok(())
 }
 
+#[ockam_macros::test]
+async fn test_channel_hybrid_key_exchange(ctx: &mut Context) -> Result<()> {
+    let result = create_channel_with_key_exchange_modes(
+        ctx,
+        KeyExchangeMode::HybridRequired,
+        KeyExchangeMode::HybridRequired,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn test_channel_hybrid_key_exchange_fallback(ctx: &mut Context) -> Result<()> {
+    // the listener ignores the hybrid key exchange offer
+    let result = create_channel_with_key_exchange_modes(
+        ctx,
+        KeyExchangeMode::HybridPreferred,
+        KeyExchangeMode::Classic,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    // the initiator doesn't offer a hybrid key exchange
+    let result = create_channel_with_key_exchange_modes(
+        ctx,
+        KeyExchangeMode::Classic,
+        KeyExchangeMode::HybridPreferred,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn test_channel_hybrid_key_exchange_required(ctx: &mut Context) -> Result<()> {
+    let result = create_channel_with_key_exchange_modes(
+        ctx,
+        KeyExchangeMode::HybridRequired,
+        KeyExchangeMode::Classic,
+    )
+    .await;
+    assert!(result.is_err());
+
+    let result = create_channel_with_key_exchange_modes(
+        ctx,
+        KeyExchangeMode::Classic,
+        KeyExchangeMode::HybridRequired,
+    )
+    .await;
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn test_channel_hybrid_key_exchange_unsupported_by_the_vault(
+    ctx: &mut Context,
+) -> Result<()> {
+    let classic = secure_channels_with_vault(Arc::new(ClassicVault(
+        SoftwareVaultForSecureChannels::create().await?,
+    )))
+    .await?;
+    let hybrid = secure_channels().await?;
+
+    // the initiator vault can't generate an ML-KEM-768 key
+    let result = create_channel_between(
+        ctx,
+        &classic,
+        KeyExchangeMode::HybridPreferred,
+        &hybrid,
+        KeyExchangeMode::HybridPreferred,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    // the responder vault can't encapsulate a secret
+    let result = create_channel_between(
+        ctx,
+        &hybrid,
+        KeyExchangeMode::HybridPreferred,
+        &classic,
+        KeyExchangeMode::HybridPreferred,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    let result = create_channel_between(
+        ctx,
+        &hybrid,
+        KeyExchangeMode::HybridRequired,
+        &classic,
+        KeyExchangeMode::HybridRequired,
+    )
+    .await;
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+async fn secure_channels_with_vault(
+    secure_channel_vault: Arc<dyn VaultForSecureChannels>,
+) -> Result<Arc<SecureChannels>> {
+    let vault = Vault::new(
+        SoftwareVaultForSigning::create().await?,
+        secure_channel_vault,
+        SoftwareVaultForSigning::create().await?,
+        SoftwareVaultForVerifyingSignatures::create(),
+    );
+    Ok(SecureChannels::builder().await?.with_vault(vault).build())
+}
+
+/// Vault which doesn't implement the optional ML-KEM-768 functions
+struct ClassicVault(Arc<SoftwareVaultForSecureChannels>);
+
+#[async_trait]
+impl VaultForSecureChannels for ClassicVault {
+    async fn x25519_ecdh(
+        &self,
+        secret_key_handle: &X25519SecretKeyHandle,
+        peer_public_key: &X25519PublicKey,
+    ) -> Result<SecretBufferHandle> {
+        self.0.x25519_ecdh(secret_key_handle, peer_public_key).await
+    }
+
+    async fn hash(&self, data: &[u8]) -> Result<HashOutput> {
+        self.0.hash(data).await
+    }
+
+    async fn hkdf(
+        &self,
+        salt: &SecretBufferHandle,
+        input_key_material: Option<&SecretBufferHandle>,
+        number_of_outputs: HKDFNumberOfOutputs,
+    ) -> Result<HkdfOutput> {
+        self.0
+            .hkdf(salt, input_key_material, number_of_outputs)
+            .await
+    }
+
+    async fn aead_encrypt(
+        &self,
+        secret_key_handle: &AeadSecretKeyHandle,
+        plain_text: &mut [u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<()> {
+        self.0
+            .aead_encrypt(secret_key_handle, plain_text, nonce, aad)
+            .await
+    }
+
+    async fn aead_decrypt<'a>(
+        &self,
+        secret_key_handle: &AeadSecretKeyHandle,
+        cipher_text: &'a mut [u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<&'a mut [u8]> {
+        self.0
+            .aead_decrypt(secret_key_handle, cipher_text, nonce, aad)
+            .await
+    }
+
+    async fn persist_aead_key(&self, secret_key_handle: &AeadSecretKeyHandle) -> Result<()> {
+        self.0.persist_aead_key(secret_key_handle).await
+    }
+
+    async fn load_aead_key(&self, secret_key_handle: &AeadSecretKeyHandle) -> Result<()> {
+        self.0.load_aead_key(secret_key_handle).await
+    }
+
+    async fn delete_persisted_aead_key(
+        &self,
+        secret_key_handle: &AeadSecretKeyHandle,
+    ) -> Result<bool> {
+        self.0.delete_persisted_aead_key(secret_key_handle).await
+    }
+
+    async fn generate_static_x25519_secret_key(&self) -> Result<X25519SecretKeyHandle> {
+        self.0.generate_static_x25519_secret_key().await
+    }
+
+    async fn delete_static_x25519_secret_key(
+        &self,
+        secret_key_handle: X25519SecretKeyHandle,
+    ) -> Result<bool> {
+        self.0
+            .delete_static_x25519_secret_key(secret_key_handle)
+            .await
+    }
+
+    async fn generate_ephemeral_x25519_secret_key(&self) -> Result<X25519SecretKeyHandle> {
+        self.0.generate_ephemeral_x25519_secret_key().await
+    }
+
+    async fn delete_ephemeral_x25519_secret_key(
+        &self,
+        secret_key_handle: X25519SecretKeyHandle,
+    ) -> Result<bool> {
+        self.0
+            .delete_ephemeral_x25519_secret_key(secret_key_handle)
+            .await
+    }
+
+    async fn get_x25519_public_key(
+        &self,
+        secret_key_handle: &X25519SecretKeyHandle,
+    ) -> Result<X25519PublicKey> {
+        self.0.get_x25519_public_key(secret_key_handle).await
+    }
+
+    async fn get_x25519_secret_key_handle(
+        &self,
+        public_key: &X25519PublicKey,
+    ) -> Result<X25519SecretKeyHandle> {
+        self.0.get_x25519_secret_key_handle(public_key).await
+    }
+
+    async fn import_secret_buffer(&self, buffer: Vec<u8>) -> Result<SecretBufferHandle> {
+        self.0.import_secret_buffer(buffer).await
+    }
+
+    async fn delete_secret_buffer(&self, secret_buffer_handle: SecretBufferHandle) -> Result<bool> {
+        self.0.delete_secret_buffer(secret_buffer_handle).await
+    }
+
+    async fn convert_secret_buffer_to_aead_key(
+        &self,
+        secret_buffer_handle: SecretBufferHandle,
+    ) -> Result<AeadSecretKeyHandle> {
+        self.0
+            .convert_secret_buffer_to_aead_key(secret_buffer_handle)
+            .await
+    }
+
+    async fn delete_aead_secret_key(&self, secret_key_handle: AeadSecretKeyHandle) -> Result<bool> {
+        self.0.delete_aead_secret_key(secret_key_handle).await
+    }
+}
+
+/// Create a secure channel between 2 new identities and exchange messages in both directions
+async fn create_channel_with_key_exchange_modes(
+    ctx: &mut Context,
+    initiator_mode: KeyExchangeMode,
+    responder_mode: KeyExchangeMode,
+) -> Result<()> {
+    let secure_channels = secure_channels().await?;
+    create_channel_between(
+        ctx,
+        &secure_channels,
+        initiator_mode,
+        &secure_channels,
+        responder_mode,
+    )
+    .await
+}
+
+/// Create a secure channel between 2 new identities, each one using its own secure channels
+/// vault, and exchange messages in both directions
+async fn create_channel_between(
+    ctx: &mut Context,
+    initiator_secure_channels: &SecureChannels,
+    initiator_mode: KeyExchangeMode,
+    responder_secure_channels: &SecureChannels,
+    responder_mode: KeyExchangeMode,
+) -> Result<()> {
+    let alice = initiator_secure_channels
+        .identities()
+        .identities_creation()
+        .create_identity()
+        .await?;
+    let bob = responder_secure_channels
+        .identities()
+        .identities_creation()
+        .create_identity()
+        .await?;
+
+    let listener_address = Address::random_tagged("bob_listener");
+    let bob_listener = responder_secure_channels.create_secure_channel_listener(
+        ctx,
+        &bob,
+        listener_address.clone(),
+        SecureChannelListenerOptions::new().with_key_exchange_mode(responder_mode),
+    )?;
+
+    let alice_channel = initiator_secure_channels
+        .create_secure_channel(
+            ctx,
+            &alice,
+            route![listener_address],
+            SecureChannelOptions::new()
+                .with_key_exchange_mode(initiator_mode)
+                .with_timeout(Duration::from_millis(500)),
+        )
+        .await?;
+
+    let child_address = Address::random_tagged("child");
+    let mut child_ctx = ctx.new_detached_with_mailboxes(Mailboxes::primary(
+        child_address.clone(),
+        Arc::new(AllowAll),
+        Arc::new(AllowAll),
+    ))?;
+    ctx.flow_controls()
+        .add_consumer(&child_address, bob_listener.flow_control_id());
+    ctx.flow_controls()
+        .add_consumer(&child_address, alice_channel.flow_control_id());
+
+    child_ctx
+        .send(
+            route![alice_channel, child_address.clone()],
+            "Hello, Bob!".to_string(),
+        )
+        .await?;
+    let msg = child_ctx.receive::<String>().await?;
+    let return_route = msg.return_route().clone();
+    assert_eq!("Hello, Bob!", msg.into_body()?);
+
+    child_ctx
+        .send(return_route, "Hello, Alice!".to_string())
+        .await?;
+    let msg = child_ctx.receive::<String>().await?;
+    assert_eq!("Hello, Alice!", msg.into_body()?);
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn test_channel_ik_handshake(ctx: &mut Context) -> Result<()> {
+    create_channel_with_known_responder(ctx, HandshakePattern::IK, KeyExchangeMode::Classic).await
+}
+
+#[ockam_macros::test]
+async fn test_channel_kk_handshake(ctx: &mut Context) -> Result<()> {
+    create_channel_with_known_responder(ctx, HandshakePattern::KK, KeyExchangeMode::Classic).await
+}
+
+#[ockam_macros::test]
+async fn test_channel_ik_handshake_hybrid_key_exchange(ctx: &mut Context) -> Result<()> {
+    create_channel_with_known_responder(ctx, HandshakePattern::IK, KeyExchangeMode::HybridRequired)
+        .await
+}
+
+#[ockam_macros::test]
+async fn test_channel_unknown_responder_falls_back_to_xx(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels().await?;
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let listener_address = Address::random_tagged("bob_listener");
+    secure_channels.create_secure_channel_listener(
+        ctx,
+        &bob,
+        listener_address.clone(),
+        SecureChannelListenerOptions::new(),
+    )?;
+
+    assert!(secure_channels.known_peers().get_peer(&bob).is_none());
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            &alice,
+            route![listener_address],
+            SecureChannelOptions::new()
+                .with_known_responder(bob.clone(), HandshakePattern::IK)
+                .with_timeout(Duration::from_millis(500)),
+        )
+        .await?;
+
+    assert_eq!(alice_channel.their_identifier(), &bob);
+    assert!(secure_channels.known_peers().get_peer(&bob).is_some());
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn test_channel_kk_handshake_unknown_initiator_falls_back_to_xx(
+    ctx: &mut Context,
+) -> Result<()> {
+    let secure_channels = secure_channels().await?;
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let listener_address = Address::random_tagged("bob_listener");
+    secure_channels.create_secure_channel_listener(
+        ctx,
+        &bob,
+        listener_address.clone(),
+        SecureChannelListenerOptions::new(),
+    )?;
+
+    secure_channels
+        .create_secure_channel(
+            ctx,
+            &alice,
+            route![listener_address.clone()],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+    ctx.sleep(Duration::from_millis(100)).await;
+
+    // the responder doesn't know the initiator static key anymore
+    // so the KK handshake fails and the initiator falls back to a XX handshake
+    secure_channels.known_peers().remove_peer(&alice);
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            &alice,
+            route![listener_address],
+            SecureChannelOptions::new()
+                .with_known_responder(bob.clone(), HandshakePattern::KK)
+                .with_timeout(Duration::from_millis(500)),
+        )
+        .await?;
+    assert_eq!(alice_channel.their_identifier(), &bob);
+
+    // the responder and the initiator know each other again after the XX handshake
+    ctx.sleep(Duration::from_millis(100)).await;
+    assert!(secure_channels.known_peers().get_peer(&alice).is_some());
+    assert!(secure_channels.known_peers().get_peer(&bob).is_some());
+
+    Ok(())
+}
+
+async fn create_channel_with_known_responder(
+    ctx: &mut Context,
+    handshake_pattern: HandshakePattern,
+    key_exchange_mode: KeyExchangeMode,
+) -> Result<()> {
+    let secure_channels = secure_channels().await?;
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let listener_address = Address::random_tagged("bob_listener");
+    let bob_listener = secure_channels.create_secure_channel_listener(
+        ctx,
+        &bob,
+        listener_address.clone(),
+        SecureChannelListenerOptions::new().with_key_exchange_mode(key_exchange_mode),
+    )?;
+
+    // a first XX handshake makes the static keys known to both parties
+    secure_channels
+        .create_secure_channel(
+            ctx,
+            &alice,
+            route![listener_address.clone()],
+            SecureChannelOptions::new().with_key_exchange_mode(key_exchange_mode),
+        )
+        .await?;
+    // wait for the responder to process the last message of the handshake
+    ctx.sleep(Duration::from_millis(100)).await;
+
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            &alice,
+            route![listener_address],
+            SecureChannelOptions::new()
+                .with_known_responder(bob.clone(), handshake_pattern)
+                .with_key_exchange_mode(key_exchange_mode)
+                .with_timeout(Duration::from_millis(500)),
+        )
+        .await?;
+    assert_eq!(alice_channel.their_identifier(), &bob);
+
+    let child_address = Address::random_tagged("child");
+    let mut child_ctx = ctx.new_detached_with_mailboxes(Mailboxes::primary(
+        child_address.clone(),
+        Arc::new(AllowAll),
+        Arc::new(AllowAll),
+    ))?;
+    ctx.flow_controls()
+        .add_consumer(&child_address, bob_listener.flow_control_id());
+    ctx.flow_controls()
+        .add_consumer(&child_address, alice_channel.flow_control_id());
+
+    child_ctx
+        .send(
+            route![alice_channel, child_address.clone()],
+            "Hello, Bob!".to_string(),
+        )
+        .await?;
+    let msg = child_ctx.receive::<String>().await?;
+    let return_route = msg.return_route().clone();
+    assert_eq!("Hello, Bob!", msg.into_body()?);
+
+    child_ctx
+        .send(return_route, "Hello, Alice!".to_string())
+        .await?;
+    let msg = child_ctx.receive::<String>().await?;
+    assert_eq!("Hello, Alice!", msg.into_body()?);
+
+    Ok(())
+}
+
 #[ockam_macros::test]
 async fn test_channel_registry(ctx: &mut Context) -> Result<()> {
     let secure_channels = secure_channels().await?;
@@ -1129,3 +1636,199 @@ async fn address_metadata__encryptor__should_be_terminal(ctx: &mut Context) -> R
 
     Ok(())
 }
+
+#[ockam_macros::test]
+async fn test_channel_rekey_policy(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels().await?;
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    // bob renews its key after each message, alice after 5 messages
+    let bob_options = SecureChannelListenerOptions::new()
+        .with_rekey_policy(RekeyPolicy::default().with_max_bytes(1));
+    let sc_listener_flow_control_id = bob_options.spawner_flow_control_id();
+    secure_channels.create_secure_channel_listener(ctx, &bob, "bob_listener", bob_options)?;
+
+    let alice_options =
+        SecureChannelOptions::new().with_rekey_policy(RekeyPolicy::default().with_max_messages(5));
+    let sc_flow_control_id = alice_options.producer_flow_control_id();
+    let alice_channel = secure_channels
+        .create_secure_channel(ctx, &alice, route!["bob_listener"], alice_options)
+        .await?;
+
+    let mut child_ctx = ctx.new_detached_with_mailboxes(Mailboxes::primary(
+        "child",
+        Arc::new(AllowAll),
+        Arc::new(AllowAll),
+    ))?;
+
+    for n in 0..20 {
+        child_ctx
+            .flow_controls()
+            .add_consumer(child_ctx.primary_address(), &sc_listener_flow_control_id);
+        let payload = format!("Hello, Bob! {}", n);
+        child_ctx
+            .send(
+                route![alice_channel.clone(), child_ctx.primary_address().clone()],
+                payload.clone(),
+            )
+            .await?;
+
+        let message = child_ctx.receive::<String>().await?;
+        let return_route = message.return_route().clone();
+        assert_eq!(payload, message.into_body()?);
+
+        child_ctx
+            .flow_controls()
+            .add_consumer(child_ctx.primary_address(), &sc_flow_control_id);
+        let payload = format!("Hello, Alice! {}", n);
+        child_ctx.send(return_route, payload.clone()).await?;
+
+        let message = child_ctx.receive::<String>().await?;
+        assert_eq!(payload, message.into_body()?);
+    }
+
+    let registry = secure_channels.secure_channel_registry();
+    let alice_metrics = registry
+        .get_channel_by_encryptor_address(alice_channel.encryptor_address())
+        .unwrap()
+        .rekey_metrics()
+        .clone();
+    let bob_metrics = registry
+        .get_channel_list()
+        .into_iter()
+        .find(|entry| !entry.is_initiator())
+        .unwrap()
+        .rekey_metrics()
+        .clone();
+
+    assert_eq!(alice_metrics.encryption_rekeys(), 3);
+    assert_eq!(bob_metrics.decryption_rekeys(), 3);
+    assert_eq!(bob_metrics.encryption_rekeys(), 19);
+    assert_eq!(alice_metrics.decryption_rekeys(), 19);
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn test_channel_padding_policy(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels().await?;
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let bob_options =
+        SecureChannelListenerOptions::new().with_padding_policy(PaddingPolicy::FixedCells(512));
+    let sc_listener_flow_control_id = bob_options.spawner_flow_control_id();
+    secure_channels.create_secure_channel_listener(ctx, &bob, "bob_listener", bob_options)?;
+
+    let alice_options =
+        SecureChannelOptions::new().with_padding_policy(PaddingPolicy::FixedCells(512));
+    let sc_flow_control_id = alice_options.producer_flow_control_id();
+    let alice_channel = secure_channels
+        .create_secure_channel(ctx, &alice, route!["bob_listener"], alice_options)
+        .await?;
+
+    let mut child_ctx = ctx.new_detached_with_mailboxes(Mailboxes::primary(
+        "child",
+        Arc::new(AllowAll),
+        Arc::new(AllowAll),
+    ))?;
+
+    child_ctx
+        .flow_controls()
+        .add_consumer(child_ctx.primary_address(), &sc_listener_flow_control_id);
+    child_ctx
+        .send(
+            route![alice_channel.clone(), child_ctx.primary_address().clone()],
+            "Hello, Bob!".to_string(),
+        )
+        .await?;
+
+    let message = child_ctx.receive::<String>().await?;
+    let return_route = message.return_route().clone();
+    assert_eq!("Hello, Bob!", message.into_body()?);
+
+    child_ctx
+        .flow_controls()
+        .add_consumer(child_ctx.primary_address(), &sc_flow_control_id);
+    child_ctx
+        .send(return_route, "Hello, Alice!".to_string())
+        .await?;
+
+    let message = child_ctx.receive::<String>().await?;
+    assert_eq!("Hello, Alice!", message.into_body()?);
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn test_channel_padding_policy_mismatch(ctx: &mut Context) -> Result<()> {
+    // the handshake fails when the policies are different
+    let result =
+        create_channel_with_padding_policies(ctx, PaddingPolicy::PowerOfTwo, PaddingPolicy::None)
+            .await;
+    assert!(result.is_err());
+
+    let result = create_channel_with_padding_policies(
+        ctx,
+        PaddingPolicy::None,
+        PaddingPolicy::FixedCells(512),
+    )
+    .await;
+    assert!(result.is_err());
+
+    let result = create_channel_with_padding_policies(
+        ctx,
+        PaddingPolicy::FixedCells(256),
+        PaddingPolicy::FixedCells(512),
+    )
+    .await;
+    assert!(result.is_err());
+
+    let result = create_channel_with_padding_policies(
+        ctx,
+        PaddingPolicy::FixedCells(512),
+        PaddingPolicy::FixedCells(512),
+    )
+    .await;
+    assert!(result.is_ok());
+
+    Ok(())
+}
+
+async fn create_channel_with_padding_policies(
+    ctx: &mut Context,
+    initiator_policy: PaddingPolicy,
+    responder_policy: PaddingPolicy,
+) -> Result<()> {
+    let secure_channels = secure_channels().await?;
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let listener_address = Address::random_tagged("bob_listener");
+    secure_channels.create_secure_channel_listener(
+        ctx,
+        &bob,
+        listener_address.clone(),
+        SecureChannelListenerOptions::new().with_padding_policy(responder_policy),
+    )?;
+
+    secure_channels
+        .create_secure_channel(
+            ctx,
+            &alice,
+            route![listener_address],
+            SecureChannelOptions::new()
+                .with_padding_policy(initiator_policy)
+                .with_timeout(Duration::from_millis(500)),
+        )
+        .await?;
+
+    Ok(())
+}
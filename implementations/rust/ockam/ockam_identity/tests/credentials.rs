@@ -392,3 +392,96 @@ impl Worker for CountingWorker {
         Ok(())
     }
 }
+
+#[ockam_macros::test]
+async fn revoked_credential(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels().await?;
+    let identities = secure_channels.identities();
+    let identities_creation = identities.identities_creation();
+    let identities_attributes = identities.identities_attributes();
+    let credentials = identities.credentials();
+
+    let authority = identities_creation.create_identity().await?;
+    let server = identities_creation.create_identity().await?;
+    let client = identities_creation.create_identity().await?;
+
+    let credential = credentials
+        .credentials_creation()
+        .issue_credential(
+            &authority,
+            &client,
+            AttributesBuilder::with_schema(CredentialSchemaIdentifier(0))
+                .with_attribute("is_superuser", "true")
+                .build(),
+            Duration::from_secs(60 * 60),
+        )
+        .await?;
+    let credential_hash = credentials
+        .credentials_verification()
+        .compute_credential_hash(&credential.credential)
+        .await?;
+
+    secure_channels.create_secure_channel_listener(
+        ctx,
+        &server,
+        "listener",
+        SecureChannelListenerOptions::new().with_authority(authority.clone()),
+    )?;
+
+    secure_channels
+        .create_secure_channel(
+            ctx,
+            &client,
+            route!["listener"],
+            SecureChannelOptions::new()
+                .with_trust_policy(TrustIdentifierPolicy::new(server.clone()))
+                .with_credential(credential.clone())?,
+        )
+        .await?;
+
+    ctx.sleep(Duration::from_millis(200)).await;
+
+    assert!(identities_attributes
+        .get_attributes(&client, &authority)
+        .await?
+        .is_some());
+
+    // a list signed by an unknown authority is rejected
+    let other_authority = identities_creation.create_identity().await?;
+    let revocation_list = credentials
+        .credentials_creation()
+        .issue_revocation_list(&other_authority, 1, vec![credential_hash.clone()], vec![])
+        .await?;
+    assert!(secure_channels
+        .receive_revocation_list(ctx, &[authority.clone()], &revocation_list)
+        .await
+        .is_err());
+
+    let revocation_list = credentials
+        .credentials_creation()
+        .issue_revocation_list(&authority, 1, vec![credential_hash], vec![])
+        .await?;
+    secure_channels
+        .receive_revocation_list(ctx, &[authority.clone()], &revocation_list)
+        .await?;
+
+    ctx.sleep(Duration::from_millis(200)).await;
+
+    // the attributes are removed, the credential is rejected and the channel is closed
+    assert!(identities_attributes
+        .get_attributes(&client, &authority)
+        .await?
+        .is_none());
+    assert!(credentials
+        .credentials_verification()
+        .verify_credential(Some(&client), &[authority.clone()], &credential)
+        .await
+        .is_err());
+    assert!(!secure_channels
+        .secure_channel_registry()
+        .get_channel_list()
+        .iter()
+        .any(|channel| channel.their_id() == &client));
+
+    Ok(())
+}
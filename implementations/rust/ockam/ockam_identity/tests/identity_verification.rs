@@ -2,8 +2,9 @@ use crate::common::crazy_vault::{CrazySigningVault, CrazyVerifyingVault};
 
 use ockam_core::compat::sync::Arc;
 use ockam_core::Result;
-use ockam_identity::models::ChangeHistory;
-use ockam_identity::{Identifier, Identities, Identity, Vault};
+use ockam_identity::models::{ChangeData, ChangeHistory, VersionedData, MULTI_KEY_CHANGE_VERSION};
+use ockam_identity::{ChangeSigner, Identifier, Identities, Identity, RecoveryKey, Vault};
+use ockam_vault::SigningKeyType;
 use rand::{thread_rng, Rng};
 
 mod common;
@@ -81,6 +82,285 @@ async fn test_eject_signatures() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_threshold_identity() -> Result<()> {
+    let identities = Identities::builder().await?.build();
+    let identities_creation = identities.identities_creation();
+    let identifier = identities_creation
+        .identity_builder()
+        .with_additional_random_key(SigningKeyType::EdDSACurve25519)
+        .with_additional_random_key(SigningKeyType::ECDSASHA256CurveP256)
+        .with_threshold(2)
+        .build()
+        .await?;
+
+    let identity = identities.get_identity(&identifier).await?;
+    let change = identity.get_latest_change()?;
+    assert_eq!(change.public_keys().len(), 3);
+    assert_eq!(change.threshold(), 2);
+    check_identity(&identity).await?;
+
+    // Rotate to a new 2-of-3 set of keys, signed with all the previous keys
+    let options = identities_creation
+        .identity_builder()
+        .with_additional_random_key(SigningKeyType::EdDSACurve25519)
+        .with_additional_random_key(SigningKeyType::EdDSACurve25519)
+        .with_threshold(2)
+        .build_options()
+        .await?;
+    identities_creation
+        .rotate_identity_with_options(&identifier, options)
+        .await?;
+    let identity = identities.get_identity(&identifier).await?;
+    check_identity(&identity).await?;
+
+    // A single signature from the previous keys is not enough
+    let mut change_history = identity.change_history().clone();
+    let change = change_history.0.last_mut().unwrap();
+    let additional_previous_signatures = change.additional_previous_signatures.take().unwrap();
+    assert!(
+        check_change_history(Some(&identifier), change_history.clone())
+            .await
+            .is_err()
+    );
+
+    // Two signatures from the additional previous keys are enough
+    let change = change_history.0.last_mut().unwrap();
+    change.previous_signature = None;
+    change.additional_previous_signatures = Some(additional_previous_signatures.clone());
+    check_change_history(Some(&identifier), change_history.clone()).await?;
+
+    // The same key can't sign twice
+    let change = change_history.0.last_mut().unwrap();
+    change.additional_previous_signatures = Some(vec![
+        additional_previous_signatures[0].clone(),
+        additional_previous_signatures[0].clone(),
+    ]);
+    assert!(check_change_history(Some(&identifier), change_history)
+        .await
+        .is_err());
+
+    // The identity can't be rotated when only one of its keys is available
+    let identity_vault = identities.vault().identity_vault;
+    let secret_keys = identities
+        .identities_keys()
+        .get_secret_keys(&identity)
+        .await?;
+    for (_, secret_key) in secret_keys.into_iter().skip(1) {
+        identity_vault.delete_signing_secret_key(secret_key).await?;
+    }
+    assert!(identities_creation
+        .rotate_identity(&identifier)
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invalid_threshold() -> Result<()> {
+    let identities = Identities::builder().await?.build();
+    let identities_creation = identities.identities_creation();
+
+    let res = identities_creation
+        .identity_builder()
+        .with_additional_random_key(SigningKeyType::EdDSACurve25519)
+        .with_threshold(3)
+        .build()
+        .await;
+    assert!(res.is_err());
+
+    let res = identities_creation
+        .identity_builder()
+        .with_threshold(0)
+        .build()
+        .await;
+    assert!(res.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_threshold_identity_with_several_vaults() -> Result<()> {
+    let identities = Identities::builder().await?.build();
+    let identities_creation = identities.identities_creation();
+
+    // The second key of a 2-of-2 identity is held by another custodian
+    let custodian_vault = Identities::builder().await?.build().vault().identity_vault;
+    let custodian_key = custodian_vault
+        .generate_signing_secret_key(SigningKeyType::EdDSACurve25519)
+        .await?;
+    let custodian = ChangeSigner::new(custodian_vault.clone(), custodian_key);
+    let identifier = identities_creation
+        .identity_builder()
+        .with_additional_signer(custodian.clone())
+        .with_threshold(2)
+        .build()
+        .await?;
+    let identity = identities.get_identity(&identifier).await?;
+    check_identity(&identity).await?;
+
+    // The multi-key change can't be verified by the verifiers supporting only the version 1
+    let versioned_data: VersionedData = minicbor::decode(&identity.change_history().0[0].data)?;
+    assert_eq!(versioned_data.version, MULTI_KEY_CHANGE_VERSION);
+    let mut version_1 = versioned_data.clone();
+    version_1.version = 1;
+    assert!(ChangeData::get_data(&version_1).is_err());
+
+    // The key of the Identity vault is not enough to rotate the identity
+    assert!(identities_creation
+        .rotate_identity(&identifier)
+        .await
+        .is_err());
+
+    // The custodian signs the new change with its own vault
+    let options = identities_creation
+        .identity_builder()
+        .with_previous_signer(custodian)
+        .build_options()
+        .await?;
+    identities_creation
+        .rotate_identity_with_options(&identifier, options)
+        .await?;
+    let identity = identities.get_identity(&identifier).await?;
+    assert_eq!(identity.changes().len(), 2);
+    check_identity(&identity).await?;
+
+    // The next changes keep the multi-key version, even with a single key
+    let versioned_data: VersionedData = minicbor::decode(&identity.change_history().0[1].data)?;
+    assert_eq!(versioned_data.version, MULTI_KEY_CHANGE_VERSION);
+
+    // A key which doesn't belong to the previous change can't sign
+    let other_key = custodian_vault
+        .generate_signing_secret_key(SigningKeyType::EdDSACurve25519)
+        .await?;
+    let options = identities_creation
+        .identity_builder()
+        .with_previous_signer(ChangeSigner::new(custodian_vault, other_key))
+        .build_options()
+        .await?;
+    assert!(identities_creation
+        .rotate_identity_with_options(&identifier, options)
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_duplicate_keys() -> Result<()> {
+    let identities = Identities::builder().await?.build();
+    let identities_creation = identities.identities_creation();
+    let identifier = identities_creation
+        .identity_builder()
+        .with_additional_random_key(SigningKeyType::EdDSACurve25519)
+        .with_threshold(2)
+        .build()
+        .await?;
+    let identity = identities.get_identity(&identifier).await?;
+
+    // The same key listed several times can't reach the threshold on its own
+    let mut change_data = identity.get_latest_change()?.data().clone();
+    change_data.additional_public_keys = Some(vec![
+        change_data.primary_public_key.clone(),
+        change_data.primary_public_key.clone(),
+    ]);
+    change_data.threshold = Some(3);
+    assert!(change_data.threshold().is_err());
+
+    // An identity can't be created with a duplicate key
+    let secret_key = identities_creation
+        .identity_builder()
+        .build_options()
+        .await?
+        .signing_secret_key_handle()
+        .clone();
+    let res = identities_creation
+        .identity_builder()
+        .with_existing_key(secret_key.clone())
+        .with_additional_existing_key(secret_key)
+        .with_threshold(2)
+        .build()
+        .await;
+    assert!(res.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_recovery_key() -> Result<()> {
+    let identities = Identities::builder().await?.build();
+    let (recovery_key, secret) = RecoveryKey::generate().await?;
+    let identifier = identities
+        .identities_creation()
+        .identity_builder()
+        .with_recovery_public_key(recovery_key.public_key().await?)
+        .build()
+        .await?;
+    let change_history = identities.get_change_history(&identifier).await?;
+
+    // The vault is lost, the identity is recovered in a new one
+    let identities = Identities::builder().await?.build();
+    identities
+        .identities_verification()
+        .import_from_change_history(Some(&identifier), change_history.clone())
+        .await?;
+    let identities_creation = identities.identities_creation();
+
+    // A wrong recovery key is rejected
+    let (wrong_recovery_key, _) = RecoveryKey::generate().await?;
+    let options = identities_creation
+        .identity_builder()
+        .with_recovery_key(wrong_recovery_key)
+        .build_options()
+        .await?;
+    assert!(identities_creation
+        .rotate_identity_with_options(&identifier, options)
+        .await
+        .is_err());
+
+    // Without the recovery key the identity can't be rotated
+    assert!(identities_creation
+        .rotate_identity(&identifier)
+        .await
+        .is_err());
+
+    let options = identities_creation
+        .identity_builder()
+        .with_recovery_key(RecoveryKey::import(&secret).await?)
+        .build_options()
+        .await?;
+    identities_creation
+        .rotate_identity_with_options(&identifier, options)
+        .await?;
+    let identity = identities.get_identity(&identifier).await?;
+    assert_eq!(identity.changes().len(), 2);
+    check_identity(&identity).await?;
+
+    // The recovered identity can be rotated with its new key
+    identities_creation.rotate_identity(&identifier).await?;
+
+    // The recovery signature is required
+    let mut change_history = identity.change_history().clone();
+    change_history.0.last_mut().unwrap().recovery_signature = None;
+    assert!(check_change_history(Some(&identifier), change_history)
+        .await
+        .is_err());
+
+    // The recovery key can't be used a second time if it is not committed again
+    let options = identities_creation
+        .identity_builder()
+        .with_recovery_key(RecoveryKey::import(&secret).await?)
+        .build_options()
+        .await?;
+    assert!(identities_creation
+        .rotate_identity_with_options(&identifier, options)
+        .await
+        .is_err());
+
+    Ok(())
+}
+
 // TODO TEST: Test that if previous_hash value doesn't match - verification fails
 // TODO TEST: Test that if previous_hash value is empty - verification fails
 // TODO TEST: Test that if the new key was created earlier that the previous - verification fails
@@ -1,4 +1,5 @@
-use ockam_core::{route, Address};
+use ockam_core::flow_control::FlowControlId;
+use ockam_core::{route, Address, AllowAll, Mailboxes};
 use ockam_identity::{
     secure_channels, DecryptionRequest, DecryptionResponse, EncryptionRequest, EncryptionResponse,
     SecureChannelListenerOptions, SecureChannelOptions, SecureChannelSqlxDatabase, SecureChannels,
@@ -383,3 +384,198 @@ fn test_persistence() -> ockam_core::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_regular_channel_persistence() -> ockam_core::Result<()> {
+    let (_db_file, db_file_path) = NamedTempFile::new().unwrap().keep().unwrap();
+    let db_file_path_clone = db_file_path.clone();
+
+    struct PassBetweenEnv {
+        alice_decryptor_remote: Address,
+        alice_encryptor: Address,
+        bob_decryptor_remote: Address,
+    }
+
+    async fn create_secure_channels(db: SqlxDatabase) -> ockam_core::Result<Arc<SecureChannels>> {
+        Ok(SecureChannels::builder()
+            .await?
+            .with_secure_channel_repository(Arc::new(SecureChannelSqlxDatabase::new(db.clone())))
+            .with_secrets_repository(Arc::new(SecretsSqlxDatabase::new(db)))
+            .build())
+    }
+
+    // Send messages from alice to bob, and from bob to alice using the return route
+    async fn exchange_messages(
+        ctx: &Context,
+        alice_encryptor: &Address,
+        flow_control_ids: &[&FlowControlId],
+        count: usize,
+    ) -> ockam_core::Result<()> {
+        let mut child_ctx = ctx.new_detached_with_mailboxes(Mailboxes::primary(
+            Address::random_local(),
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ))?;
+        for flow_control_id in flow_control_ids {
+            ctx.flow_controls()
+                .add_consumer(child_ctx.primary_address(), flow_control_id);
+        }
+
+        for n in 0..count {
+            child_ctx
+                .send(
+                    route![alice_encryptor.clone(), child_ctx.primary_address().clone()],
+                    format!("Hello, Bob! {n}"),
+                )
+                .await?;
+            let msg = child_ctx.receive::<String>().await?;
+            let return_route = msg.return_route().clone();
+            assert_eq!(format!("Hello, Bob! {n}"), msg.into_body()?);
+
+            child_ctx
+                .send(return_route, format!("Hello, Alice! {n}"))
+                .await?;
+            let msg = child_ctx.receive::<String>().await?;
+            assert_eq!(format!("Hello, Alice! {n}"), msg.into_body()?);
+        }
+
+        Ok(())
+    }
+
+    let (ctx1, mut executor1) = NodeBuilder::new().build();
+    let data = executor1
+        .execute(async move {
+            let data = std::panic::AssertUnwindSafe(async {
+                let db = SqlxDatabase::create_sqlite(db_file_path_clone.as_path()).await?;
+                let secure_channels = create_secure_channels(db).await?;
+                let identities_creation = secure_channels.identities().identities_creation();
+                let alice = identities_creation.create_identity().await?;
+                let bob = identities_creation.create_identity().await?;
+
+                let bob_listener = secure_channels.create_secure_channel_listener(
+                    &ctx1,
+                    &bob,
+                    "bob_listener",
+                    SecureChannelListenerOptions::new().persist()?,
+                )?;
+                let alice_channel = secure_channels
+                    .create_secure_channel(
+                        &ctx1,
+                        &alice,
+                        route!["bob_listener"],
+                        SecureChannelOptions::new().persist()?,
+                    )
+                    .await?;
+
+                // more messages than the rekeying interval
+                exchange_messages(
+                    &ctx1,
+                    alice_channel.encryptor_address(),
+                    &[
+                        bob_listener.flow_control_id(),
+                        alice_channel.flow_control_id(),
+                    ],
+                    50,
+                )
+                .await?;
+
+                let bob_channel = secure_channels
+                    .secure_channel_registry()
+                    .get_channel_list()
+                    .into_iter()
+                    .find(|c| !c.is_initiator())
+                    .unwrap();
+
+                Result::<PassBetweenEnv, ockam_core::Error>::Ok(PassBetweenEnv {
+                    alice_decryptor_remote: alice_channel.decryptor_remote_address().clone(),
+                    alice_encryptor: alice_channel.encryptor_address().clone(),
+                    bob_decryptor_remote: bob_channel.decryptor_messaging_address().clone(),
+                })
+            })
+            .catch_unwind()
+            .await;
+
+            ctx1.shutdown_node().await?;
+
+            data.unwrap()
+        })
+        .unwrap()
+        .unwrap();
+
+    let (ctx2, mut executor2) = NodeBuilder::new().build();
+    executor2
+        .execute(async move {
+            let res = std::panic::AssertUnwindSafe(async {
+                let db = SqlxDatabase::create_sqlite(db_file_path.as_path()).await?;
+                let secure_channels = create_secure_channels(db).await?;
+                assert_eq!(
+                    secure_channels
+                        .secure_channel_repository()
+                        .get_all()
+                        .await?
+                        .len(),
+                    2
+                );
+
+                let bob_options = SecureChannelListenerOptions::new().persist()?;
+                let bob = secure_channels
+                    .secure_channel_repository()
+                    .get(&data.bob_decryptor_remote)
+                    .await?
+                    .unwrap()
+                    .my_identifier()
+                    .clone();
+                let bob_listener = secure_channels.create_secure_channel_listener(
+                    &ctx2,
+                    &bob,
+                    "bob_listener",
+                    bob_options,
+                )?;
+                let bob_channels = secure_channels
+                    .resume_secure_channels_for_listener(
+                        &ctx2,
+                        &bob,
+                        &bob_listener,
+                        SecureChannelListenerOptions::new(),
+                    )
+                    .await?;
+                assert_eq!(bob_channels.len(), 1);
+                assert_eq!(
+                    bob_channels[0].decryptor_remote_address(),
+                    &data.bob_decryptor_remote
+                );
+                let alice_channel = secure_channels
+                    .resume_secure_channel(
+                        &ctx2,
+                        &data.alice_decryptor_remote,
+                        route![],
+                        SecureChannelOptions::new(),
+                    )
+                    .await?;
+                assert_eq!(alice_channel.encryptor_address(), &data.alice_encryptor);
+
+                exchange_messages(
+                    &ctx2,
+                    alice_channel.encryptor_address(),
+                    &[
+                        bob_listener.flow_control_id(),
+                        alice_channel.flow_control_id(),
+                    ],
+                    50,
+                )
+                .await?;
+
+                ockam_core::Result::<()>::Ok(())
+            })
+            .catch_unwind()
+            .await;
+
+            ctx2.shutdown_node().await?;
+
+            res.unwrap()
+        })
+        .unwrap()
+        .unwrap();
+
+    Ok(())
+}
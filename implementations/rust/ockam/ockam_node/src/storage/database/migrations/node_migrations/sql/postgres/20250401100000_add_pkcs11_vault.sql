@@ -0,0 +1,4 @@
+-- A vault can store its keys on a PKCS#11 token.
+-- In that case the path to the PKCS#11 module and the label of the token are stored with the vault metadata
+ALTER TABLE vault ADD COLUMN pkcs11_module TEXT;
+ALTER TABLE vault ADD COLUMN pkcs11_token_label TEXT;
@@ -0,0 +1,8 @@
+-- Add the columns necessary to resume the secure channels of a node when it restarts:
+--  - the number of rekeys of the encryption key, since that key can be saved ahead of a rekey
+--  - the credentials presented by the other party, to restore its attributes
+--  - the listener which spawned a responder secure channel, and the node of that listener
+ALTER TABLE secure_channel ADD encryption_number_of_rekeys BIGINT;
+ALTER TABLE secure_channel ADD their_credentials BYTEA;
+ALTER TABLE secure_channel ADD listener_address TEXT;
+ALTER TABLE secure_channel ADD node_name TEXT;
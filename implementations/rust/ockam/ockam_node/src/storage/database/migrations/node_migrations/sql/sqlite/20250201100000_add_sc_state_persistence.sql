@@ -0,0 +1,9 @@
+-- Add the columns necessary to restart a regular secure channel (not only a key exchange only one)
+-- The encryption and decryption nonces are the first nonces which can be used after a restart
+ALTER TABLE secure_channel ADD encryptor_address TEXT;
+ALTER TABLE secure_channel ADD their_decryptor_address TEXT;
+ALTER TABLE secure_channel ADD authority TEXT;
+ALTER TABLE secure_channel ADD encryption_key_handle BLOB;
+ALTER TABLE secure_channel ADD encryption_nonce INTEGER;
+ALTER TABLE secure_channel ADD decryption_number_of_rekeys INTEGER;
+ALTER TABLE secure_channel ADD decryption_nonce INTEGER;
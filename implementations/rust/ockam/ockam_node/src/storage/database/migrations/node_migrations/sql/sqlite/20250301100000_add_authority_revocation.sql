@@ -0,0 +1,12 @@
+-- This table stores the credentials and identifiers revoked by an authority.
+-- The version of the authority revocation list is the number of revoked items
+CREATE TABLE authority_revocation
+(
+    authority_id TEXT    NOT NULL,
+    kind         TEXT    NOT NULL, -- 'credential' or 'identifier'
+    value        TEXT    NOT NULL, -- hex encoded credential hash or identifier
+    revoked_by   TEXT    NOT NULL,
+    revoked_at   INTEGER NOT NULL
+);
+
+CREATE UNIQUE INDEX authority_revocation_index ON authority_revocation (authority_id, kind, value);
@@ -0,0 +1,12 @@
+-- This table stores the key used to encrypt the secrets of a vault when the vault is encrypted.
+-- That key is itself encrypted with a key derived from a passphrase with Argon2id.
+-- If this table is empty, the secrets are stored in plain text
+CREATE TABLE secrets_encryption
+(
+    id          INTEGER PRIMARY KEY, -- There is only one encryption key per database, with id 1
+    salt        BLOB    NOT NULL,    -- Salt used to derive the key encryption key
+    memory_cost INTEGER NOT NULL,    -- Argon2id memory cost, in KiB
+    time_cost   INTEGER NOT NULL,    -- Argon2id number of iterations
+    parallelism INTEGER NOT NULL,    -- Argon2id degree of parallelism
+    wrapped_key BLOB    NOT NULL     -- Encrypted secrets encryption key
+);
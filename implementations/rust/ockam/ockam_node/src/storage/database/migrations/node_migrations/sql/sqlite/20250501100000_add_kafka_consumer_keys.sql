@@ -0,0 +1,13 @@
+-- This table stores the keys used by Kafka consumers to decrypt the records of a topic.
+-- The keys are kept after their secure channel is closed, until the records encrypted
+-- with them have been deleted by the retention of the topic.
+-- The key itself is stored as an AEAD secret in the vault of the node
+CREATE TABLE kafka_consumer_key
+(
+    node_name           TEXT    NOT NULL, -- Name of the consumer node
+    decryptor_address   TEXT    NOT NULL, -- Decryptor address sent by the producer with each encrypted record
+    producer_identifier TEXT    NOT NULL, -- Identifier of the producer which encrypted the records
+    key_handle          BLOB    NOT NULL, -- Handle of the key in the vault of the node
+    last_used_at        INTEGER NOT NULL  -- Last time the key was used, in seconds since the epoch
+);
+CREATE UNIQUE INDEX kafka_consumer_key_index ON kafka_consumer_key (node_name, decryptor_address);
@@ -28,9 +28,10 @@ pub(crate) use workers::*;
 
 pub use options::{TcpConnectionOptions, TcpListenerOptions};
 pub use portal::{
-    new_certificate_provider_cache, Direction, PortalInletInterceptor, PortalInterceptor,
-    PortalInterceptorFactory, PortalInterceptorWorker, PortalInternalMessage, PortalMessage,
-    PortalOutletInterceptor, TlsCertificate, TlsCertificateProvider,
+    new_certificate_provider_cache, Direction, InterceptedData, PortalInletInterceptor,
+    PortalInterceptor, PortalInterceptorFactory, PortalInterceptorWorker, PortalInternalMessage,
+    PortalMessage, PortalOutletInterceptor, TcpInletConnectionGuard, TcpInletRouteSelector,
+    TcpOutletAllowlist, TcpOutletDestinationAuthorizer, TlsCertificate, TlsCertificateProvider,
 };
 pub use protocol_version::*;
 pub use registry::*;
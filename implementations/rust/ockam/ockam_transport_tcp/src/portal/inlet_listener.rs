@@ -1,7 +1,9 @@
 use crate::portal::addresses::{Addresses, PortalType};
 use crate::portal::tls_certificate::TlsCertificateProvider;
-use crate::portal::{InletSharedState, ReadHalfMaybeTls, WriteHalfMaybeTls};
-use crate::{portal::TcpPortalWorker, TcpInlet, TcpInletOptions, TcpRegistry};
+use crate::portal::{InletSharedState, PortalPeer, ReadHalfMaybeTls, WriteHalfMaybeTls};
+use crate::{
+    portal::TcpPortalWorker, TcpInlet, TcpInletOptions, TcpInletRouteSelector, TcpRegistry,
+};
 use log::warn;
 use ockam_core::compat::net::SocketAddr;
 use ockam_core::compat::sync::{Arc, RwLock as SyncRwLock};
@@ -27,6 +29,8 @@ pub(crate) struct TcpInletListenProcessor {
     registry: TcpRegistry,
     inner: TcpListener,
     inlet_shared_state: Arc<SyncRwLock<InletSharedState>>,
+    // Chooses the route of each connection instead of the route of the shared state
+    route_selector: Option<Arc<dyn TcpInletRouteSelector>>,
     options: TcpInletOptions,
 }
 
@@ -35,12 +39,14 @@ impl TcpInletListenProcessor {
         registry: TcpRegistry,
         inner: TcpListener,
         inlet_shared_state: Arc<SyncRwLock<InletSharedState>>,
+        route_selector: Option<Arc<dyn TcpInletRouteSelector>>,
         options: TcpInletOptions,
     ) -> Self {
         Self {
             registry,
             inner,
             inlet_shared_state,
+            route_selector,
             options,
         }
     }
@@ -51,6 +57,7 @@ impl TcpInletListenProcessor {
         ctx: &Context,
         registry: TcpRegistry,
         outlet_listener_route: Route,
+        route_selector: Option<Arc<dyn TcpInletRouteSelector>>,
         addr: SocketAddr,
         options: TcpInletOptions,
     ) -> Result<TcpInlet> {
@@ -68,7 +75,13 @@ impl TcpInletListenProcessor {
         let inlet_shared_state =
             InletSharedState::create(ctx, outlet_listener_route, options.is_paused)?;
         let inlet_shared_state = Arc::new(SyncRwLock::new(inlet_shared_state));
-        let processor = Self::new(registry, inner, inlet_shared_state.clone(), options);
+        let processor = Self::new(
+            registry,
+            inner,
+            inlet_shared_state.clone(),
+            route_selector,
+            options,
+        );
 
         ctx.start_processor(processor_address.clone(), processor)?;
 
@@ -188,11 +201,26 @@ impl Processor for TcpInletListenProcessor {
             return Ok(true);
         }
 
-        TcpInletOptions::setup_flow_control(
-            ctx.flow_controls(),
-            &addresses,
-            inlet_shared_state.route().next()?,
-        );
+        let (route, their_identifier, connection_guard) = match &self.route_selector {
+            Some(route_selector) => match route_selector.select_route() {
+                Some((route, connection_guard)) => {
+                    let their_identifier =
+                        InletSharedState::create(ctx, route.clone(), false)?.their_identifier();
+                    (route, their_identifier, Some(connection_guard))
+                }
+                None => {
+                    debug!(%socket_addr, "no outlet is available, dropping the connection");
+                    return Ok(true);
+                }
+            },
+            None => (
+                inlet_shared_state.route().clone(),
+                inlet_shared_state.their_identifier(),
+                None,
+            ),
+        };
+
+        TcpInletOptions::setup_flow_control(ctx.flow_controls(), &addresses, route.next()?);
 
         let streams = if let Some(certificate_provider) = &self.options.tls_certificate_provider {
             let (rx, tx) = tokio::io::split(TlsStream::from(
@@ -220,13 +248,16 @@ impl Processor for TcpInletListenProcessor {
             ctx,
             self.registry.clone(),
             streams,
-            HostnamePort::from(socket_addr),
-            inlet_shared_state.route().clone(),
-            inlet_shared_state.their_identifier(),
+            PortalPeer::Tcp(HostnamePort::from(socket_addr)),
+            route,
+            their_identifier,
             addresses,
             self.options.incoming_access_control.clone(),
             self.options.outgoing_access_control.clone(),
             self.options.portal_payload_length,
+            self.options.outlet_destination.clone(),
+            None,
+            connection_guard,
         )?;
 
         Ok(true)
@@ -0,0 +1,20 @@
+use core::any::Any;
+use core::fmt::Debug;
+use ockam_core::compat::boxed::Box;
+use ockam_core::Route;
+
+/// Value kept by the Portal of a connection which was given a route by a
+/// [`TcpInletRouteSelector`], and dropped when that connection is closed
+pub type TcpInletConnectionGuard = Box<dyn Any + Send + Sync>;
+
+/// Choose the route to the Outlet of each connection accepted by an Inlet created with
+/// [`TcpTransport::create_inlet_with_route_selector`](crate::TcpTransport::create_inlet_with_route_selector),
+/// for example to spread the connections across several Outlets exposing the same service
+pub trait TcpInletRouteSelector: Send + Sync + Debug + 'static {
+    /// Return the route to the Outlet for a new connection, or None if no Outlet is
+    /// available, in which case the connection is dropped.
+    ///
+    /// The returned guard is dropped when the connection is closed, which allows
+    /// keeping track of the open connections of each Outlet
+    fn select_route(&self) -> Option<(Route, TcpInletConnectionGuard)>;
+}
@@ -11,7 +11,7 @@ use std::sync::Arc;
 use tracing::{debug, trace};
 
 /// Direction of the data being intercepted
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Direction {
     /// Data is moving from the inlet to the outlet
     FromOutletToInlet,
@@ -32,6 +32,49 @@ pub trait PortalInterceptor: 'static + Send + Sync {
         direction: Direction,
         buffer: &[u8],
     ) -> ockam_core::Result<Option<Vec<u8>>>;
+
+    /// Same as [`PortalInterceptor::intercept`], with the local information of the intercepted
+    /// message, like the identifier of the secure channel it was received from.
+    /// By default, the local information is ignored.
+    async fn intercept_with_local_info(
+        &self,
+        context: &mut Context,
+        direction: Direction,
+        _local_info: &[LocalInfo],
+        buffer: &[u8],
+    ) -> ockam_core::Result<Option<Vec<u8>>> {
+        self.intercept(context, direction, buffer).await
+    }
+
+    /// Same as [`PortalInterceptor::intercept_with_local_info`], with the data to send back
+    /// to the sender of the intercepted data, for example to answer a request without
+    /// forwarding it.
+    /// The data sent back is not intercepted again by this interceptor.
+    /// By default, no data is sent back.
+    async fn intercept_with_reply(
+        &self,
+        context: &mut Context,
+        direction: Direction,
+        local_info: &[LocalInfo],
+        buffer: &[u8],
+    ) -> ockam_core::Result<InterceptedData> {
+        let forward = self
+            .intercept_with_local_info(context, direction, local_info, buffer)
+            .await?;
+        Ok(InterceptedData {
+            forward,
+            reply: None,
+        })
+    }
+}
+
+/// Data returned by a [`PortalInterceptor`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InterceptedData {
+    /// Data sent to the original destination
+    pub forward: Option<Vec<u8>>,
+    /// Data sent back to the sender of the intercepted data
+    pub reply: Option<Vec<u8>>,
 }
 
 /// Portal Interceptor Factory
@@ -267,12 +310,24 @@ impl Worker for PortalInterceptorWorker {
         let portal_message = PortalMessage::decode(routed_message.payload())?;
 
         match portal_message {
+            PortalMessage::Payload(message, _)
+                if routed_message.src_addr() == self.other_worker_address =>
+            {
+                // data sent back by the interceptor of the other direction
+                self.split_and_send(context, onward_route.clone(), route![], message, &[])
+                    .await?;
+            }
             PortalMessage::Payload(message, _) => {
-                let buffer: Option<Vec<u8>> = self
+                let InterceptedData { forward, reply } = self
                     .interceptor
-                    .intercept(context, self.direction, message)
+                    .intercept_with_reply(context, self.direction, local_info, message)
                     .await?;
-                match buffer {
+                if let Some(reply) = reply {
+                    trace!("reply of size {} returned by the interceptor", reply.len());
+                    self.send_reply(context, return_route.clone(), &reply)
+                        .await?;
+                }
+                match forward {
                     Some(buffer) => {
                         trace!(
                             "buffer of size {} returned by the interceptor",
@@ -308,7 +363,9 @@ impl Worker for PortalInterceptorWorker {
                     context.stop_address(context.primary_address())?;
                 }
             }
-            PortalMessage::Ping => self.forward(context, routed_message).await?,
+            PortalMessage::Ping | PortalMessage::PingTo(_) => {
+                self.forward(context, routed_message).await?
+            }
 
             PortalMessage::Pong => {
                 match self.direction {
@@ -394,13 +451,17 @@ impl PortalInterceptorWorker {
             portal_payload_length,
         };
 
+        // allow the other worker to send the replies of the interceptor
         WorkerBuilder::new(from_outlet_worker)
             .with_address(from_outlet_worker_address.clone())
-            .with_incoming_access_control_arc(incoming_access_control)
+            .with_incoming_access_control(AnyIncomingAccessControl::new(vec![
+                Arc::new(AllowSourceAddress(from_inlet_worker_address.clone())),
+                incoming_access_control,
+            ]))
             .start(context)?;
 
         let from_inlet_worker = Self {
-            other_worker_address: from_outlet_worker_address,
+            other_worker_address: from_outlet_worker_address.clone(),
             direction: Direction::FromInletToOutlet,
             disconnect_received: disconnect_received.clone(),
             fixed_onward_route: None,
@@ -408,9 +469,13 @@ impl PortalInterceptorWorker {
             portal_payload_length,
         };
 
+        // allow sending the replies of the interceptor to the other worker
         WorkerBuilder::new(from_inlet_worker)
             .with_address(from_inlet_worker_address.clone())
-            .with_outgoing_access_control_arc(outgoing_access_control)
+            .with_outgoing_access_control(AnyOutgoingAccessControl::new(vec![
+                Arc::new(AllowOnwardAddress::new(from_outlet_worker_address.clone())),
+                outgoing_access_control,
+            ]))
             .start(context)?;
 
         Ok(from_inlet_worker_address)
@@ -478,21 +543,25 @@ impl PortalInterceptorWorker {
             vec![],
         );
 
-        // allow the other worker to forward the `pong` message
+        // allow the other worker to forward the `pong` message,
+        // and to send the replies of the interceptor to the other worker
         WorkerBuilder::new(from_inlet_worker)
             .with_address(from_inlet_worker_address.clone())
             .with_incoming_access_control_arc(Arc::new(AnyIncomingAccessControl::new(vec![
                 Arc::new(AllowSourceAddress(from_outlet_worker_address.clone())),
                 incoming_access_control,
             ])))
-            .with_outgoing_access_control_arc(Arc::new(FlowControlOutgoingAccessControl::new(
-                flow_controls,
-                flow_control_id.clone(),
-                spawner_flow_control_id.clone(),
-            )))
+            .with_outgoing_access_control(AnyOutgoingAccessControl::new(vec![
+                Arc::new(AllowOnwardAddress::new(from_outlet_worker_address.clone())),
+                Arc::new(FlowControlOutgoingAccessControl::new(
+                    flow_controls,
+                    flow_control_id.clone(),
+                    spawner_flow_control_id.clone(),
+                )),
+            ]))
             .start(context)?;
 
-        // allow forwarding the `pong` message to the other worker
+        // allow forwarding the `pong` message and the replies to the other worker
         let response_outgoing_access_control = {
             AnyOutgoingAccessControl::new(vec![
                 Arc::new(AllowOnwardAddress::new(from_inlet_worker_address.clone())),
@@ -549,6 +618,27 @@ impl PortalInterceptorWorker {
         context.forward(local_message).await
     }
 
+    /// Send data back to the sender through the other worker, which sends it without
+    /// intercepting it.
+    /// `return_route` is the route back to the sender, used by the other worker when it
+    /// doesn't have a fixed onward route
+    async fn send_reply(
+        &self,
+        context: &mut Context,
+        return_route: Route,
+        buffer: &[u8],
+    ) -> ockam_core::Result<()> {
+        for chunk in buffer.chunks(self.portal_payload_length) {
+            let message = LocalMessage::new()
+                .with_onward_route(self.other_worker_address.clone() + return_route.clone())
+                .with_return_route(route![context.primary_address().clone()])
+                .with_payload(PortalMessage::Payload(chunk, None).encode()?);
+
+            context.forward(message).await?;
+        }
+        Ok(())
+    }
+
     async fn split_and_send(
         &self,
         context: &mut Context,
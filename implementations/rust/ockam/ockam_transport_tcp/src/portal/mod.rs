@@ -1,22 +1,30 @@
 pub mod addresses;
 mod inlet_listener;
+mod inlet_route_selector;
 mod inlet_shared_state;
 mod interceptor;
 pub mod options;
+mod outlet_destinations;
 mod outlet_listener;
 mod portal_message;
 mod portal_receiver;
 mod portal_worker;
 mod tls_certificate;
+#[cfg(unix)]
+mod unix_inlet_listener;
 
 pub(crate) use inlet_listener::*;
+pub use inlet_route_selector::*;
 pub(crate) use inlet_shared_state::*;
 pub use interceptor::{
-    Direction, PortalInletInterceptor, PortalInterceptor, PortalInterceptorFactory,
-    PortalInterceptorWorker, PortalOutletInterceptor,
+    Direction, InterceptedData, PortalInletInterceptor, PortalInterceptor,
+    PortalInterceptorFactory, PortalInterceptorWorker, PortalOutletInterceptor,
 };
+pub use outlet_destinations::*;
 pub(crate) use outlet_listener::*;
 pub use portal_message::*;
 pub(crate) use portal_receiver::*;
 pub(crate) use portal_worker::*;
 pub use tls_certificate::*;
+#[cfg(unix)]
+pub(crate) use unix_inlet_listener::*;
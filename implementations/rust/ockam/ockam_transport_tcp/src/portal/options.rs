@@ -1,9 +1,10 @@
 use crate::portal::addresses::Addresses;
-use crate::TlsCertificateProvider;
+use crate::{TcpOutletDestinationAuthorizer, TlsCertificateProvider};
 use ockam_core::compat::sync::Arc;
 use ockam_core::env::get_env_with_default_ignore_error;
 use ockam_core::flow_control::{FlowControlId, FlowControls};
 use ockam_core::{Address, AllowAll, IncomingAccessControl, OutgoingAccessControl};
+use ockam_transport_core::HostnamePort;
 
 /// Maximum allowed size for a payload for TCP Portal
 pub fn read_portal_payload_length() -> usize {
@@ -18,6 +19,13 @@ pub struct TcpInletOptions {
     pub(crate) is_paused: bool,
     pub(crate) tls_certificate_provider: Option<Arc<dyn TlsCertificateProvider>>,
     pub(crate) portal_payload_length: usize,
+    pub(crate) outlet_destination: Option<HostnamePort>,
+    #[cfg(unix)]
+    pub(crate) unix_socket_mode: Option<u32>,
+    #[cfg(unix)]
+    pub(crate) unix_socket_owner: Option<u32>,
+    #[cfg(unix)]
+    pub(crate) unix_socket_group: Option<u32>,
 }
 
 impl TcpInletOptions {
@@ -29,6 +37,13 @@ impl TcpInletOptions {
             is_paused: false,
             tls_certificate_provider: None,
             portal_payload_length: read_portal_payload_length(),
+            outlet_destination: None,
+            #[cfg(unix)]
+            unix_socket_mode: None,
+            #[cfg(unix)]
+            unix_socket_owner: None,
+            #[cfg(unix)]
+            unix_socket_group: None,
         }
     }
 
@@ -48,6 +63,30 @@ impl TcpInletOptions {
         self
     }
 
+    /// Set the destination that an Outlet created with
+    /// [`TcpTransport::create_dynamic_outlet`](crate::TcpTransport::create_dynamic_outlet)
+    /// must connect to for the connections of this Inlet
+    pub fn with_outlet_destination(mut self, destination: HostnamePort) -> Self {
+        self.outlet_destination = Some(destination);
+        self
+    }
+
+    /// Set the file mode of the socket of an Inlet listening on a Unix domain socket,
+    /// for example `0o660`
+    #[cfg(unix)]
+    pub fn with_unix_socket_mode(mut self, mode: u32) -> Self {
+        self.unix_socket_mode = Some(mode);
+        self
+    }
+
+    /// Set the user and group owning the socket of an Inlet listening on a Unix domain socket
+    #[cfg(unix)]
+    pub fn with_unix_socket_owner(mut self, uid: Option<u32>, gid: Option<u32>) -> Self {
+        self.unix_socket_owner = uid;
+        self.unix_socket_group = gid;
+        self
+    }
+
     /// Set Incoming Access Control
     pub fn with_incoming_access_control_impl(
         mut self,
@@ -121,6 +160,7 @@ pub struct TcpOutletOptions {
     pub(crate) outgoing_access_control: Arc<dyn OutgoingAccessControl>,
     pub(crate) tls: bool,
     pub(crate) portal_payload_length: usize,
+    pub(crate) destination_authorizer: Option<Arc<dyn TcpOutletDestinationAuthorizer>>,
 }
 
 impl TcpOutletOptions {
@@ -132,6 +172,7 @@ impl TcpOutletOptions {
             outgoing_access_control: Arc::new(AllowAll),
             tls: false,
             portal_payload_length: read_portal_payload_length(),
+            destination_authorizer: None,
         }
     }
 
@@ -153,6 +194,16 @@ impl TcpOutletOptions {
         self
     }
 
+    /// Set the authorizer of the destinations requested to an Outlet created with
+    /// [`TcpTransport::create_dynamic_outlet`](crate::TcpTransport::create_dynamic_outlet)
+    pub fn with_destination_authorizer(
+        mut self,
+        authorizer: Arc<dyn TcpOutletDestinationAuthorizer>,
+    ) -> Self {
+        self.destination_authorizer = Some(authorizer);
+        self
+    }
+
     /// Set TLS
     pub fn with_tls(mut self, tls: bool) -> Self {
         self.tls = tls;
@@ -0,0 +1,312 @@
+use core::fmt::Debug;
+use core::ops::RangeInclusive;
+use core::str::FromStr;
+use ockam_core::compat::net::IpAddr;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, LocalInfoIdentifier, Result};
+use ockam_transport_core::HostnamePort;
+use tokio::net::lookup_host;
+
+/// Authorize the destinations requested to an Outlet created with
+/// [`TcpTransport::create_dynamic_outlet`](crate::TcpTransport::create_dynamic_outlet),
+/// once they are accepted by its [`TcpOutletAllowlist`]
+#[async_trait]
+pub trait TcpOutletDestinationAuthorizer: Send + Sync + Debug + 'static {
+    /// Return true if the Inlet identified by `their_identifier` can connect to `destination`
+    async fn is_authorized(
+        &self,
+        their_identifier: Option<&LocalInfoIdentifier>,
+        destination: &HostnamePort,
+    ) -> Result<bool>;
+}
+
+/// Destinations that an Outlet created with
+/// [`TcpTransport::create_dynamic_outlet`](crate::TcpTransport::create_dynamic_outlet)
+/// can connect to.
+///
+/// Each entry has the format `<host>[:<port>|:<first port>-<last port>]`, where the host is
+/// one of:
+///  - `*` for any host
+///  - a hostname, for example `db.internal`
+///  - `*.domain` for any subdomain of a domain, for example `*.internal`
+///  - an IP address, for example `10.0.0.12` or `[fd00::12]`
+///  - a network in the CIDR notation, for example `10.0.0.0/8` or `[fd00::/8]`
+///
+/// A hostname which is not allowed by name is resolved, and the Outlet connects to its first
+/// IP address belonging to an allowed network, so that the name can't resolve to another
+/// address between the check and the connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TcpOutletAllowlist {
+    entries: Vec<AllowedDestination>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct AllowedDestination {
+    host: AllowedHost,
+    ports: Option<RangeInclusive<u16>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum AllowedHost {
+    Any,
+    Hostname(String),
+    /// Any subdomain of a domain, given with its leading dot
+    Subdomains(String),
+    /// A network given by its address and prefix length. An IP address is a network with
+    /// a full length prefix
+    Network(IpAddr, u8),
+}
+
+impl TcpOutletAllowlist {
+    /// Create an allowlist from its entries
+    pub fn new(entries: &[impl AsRef<str>]) -> Result<Self> {
+        let entries = entries
+            .iter()
+            .map(|entry| AllowedDestination::from_str(entry.as_ref()))
+            .collect::<Result<Vec<_>>>()?;
+        if entries.is_empty() {
+            return Err(ockam_core::Error::new(
+                Origin::Transport,
+                Kind::Invalid,
+                "at least one allowed destination must be provided",
+            ));
+        }
+        Ok(Self { entries })
+    }
+
+    /// Return the address to connect to for a requested destination,
+    /// or None if the destination is not allowed
+    pub async fn resolve(&self, destination: &HostnamePort) -> Result<Option<HostnamePort>> {
+        let port = destination.port();
+        let entries = self
+            .entries
+            .iter()
+            .filter(|entry| entry.ports.as_ref().map_or(true, |p| p.contains(&port)))
+            .map(|entry| &entry.host)
+            .collect::<Vec<_>>();
+
+        let hostname = destination.hostname();
+        let hostname = hostname
+            .strip_prefix('[')
+            .and_then(|h| h.strip_suffix(']'))
+            .unwrap_or(&hostname)
+            .to_lowercase();
+
+        if let Ok(ip) = IpAddr::from_str(&hostname) {
+            let allowed = entries.iter().any(|host| match host {
+                AllowedHost::Any => true,
+                AllowedHost::Network(network, prefix) => contains(network, *prefix, &ip),
+                AllowedHost::Hostname(_) | AllowedHost::Subdomains(_) => false,
+            });
+            return Ok(allowed.then(|| destination.clone()));
+        }
+
+        let allowed_by_name = entries.iter().any(|host| match host {
+            AllowedHost::Any => true,
+            AllowedHost::Hostname(allowed) => allowed == &hostname,
+            AllowedHost::Subdomains(domain) => hostname.ends_with(domain.as_str()),
+            AllowedHost::Network(..) => false,
+        });
+        if allowed_by_name {
+            return Ok(Some(destination.clone()));
+        }
+
+        let networks = entries
+            .iter()
+            .filter_map(|host| match host {
+                AllowedHost::Network(network, prefix) => Some((network, *prefix)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if networks.is_empty() {
+            return Ok(None);
+        }
+        let resolved = match lookup_host(destination.to_string()).await {
+            Ok(resolved) => resolved,
+            Err(_) => return Ok(None),
+        };
+        Ok(resolved
+            .into_iter()
+            .find(|address| {
+                networks
+                    .iter()
+                    .any(|(network, prefix)| contains(network, *prefix, &address.ip()))
+            })
+            .map(HostnamePort::from))
+    }
+}
+
+/// Return true if an IP address belongs to a network
+fn contains(network: &IpAddr, prefix: u8, ip: &IpAddr) -> bool {
+    match (network, ip) {
+        (IpAddr::V4(network), IpAddr::V4(ip)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(*network) & mask == u32::from(*ip) & mask
+        }
+        (IpAddr::V6(network), IpAddr::V6(ip)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(*network) & mask == u128::from(*ip) & mask
+        }
+        _ => false,
+    }
+}
+
+impl FromStr for AllowedDestination {
+    type Err = ockam_core::Error;
+
+    fn from_str(entry: &str) -> Result<Self> {
+        let invalid = || {
+            ockam_core::Error::new(
+                Origin::Transport,
+                Kind::Invalid,
+                format!("Invalid allowed destination '{entry}', expected <host>[:<port>|:<first port>-<last port>]"),
+            )
+        };
+
+        // IPv6 addresses are enclosed in brackets when they are followed by ports
+        let (host, ports) = if let Some(rest) = entry.strip_prefix('[') {
+            let (host, rest) = rest.split_once(']').ok_or_else(invalid)?;
+            match rest {
+                "" => (host, None),
+                _ => (host, Some(rest.strip_prefix(':').ok_or_else(invalid)?)),
+            }
+        } else if entry.matches(':').count() > 1 {
+            (entry, None)
+        } else {
+            match entry.split_once(':') {
+                Some((host, ports)) => (host, Some(ports)),
+                None => (entry, None),
+            }
+        };
+
+        let ports = match ports {
+            Some(ports) => {
+                let (first, last) = ports.split_once('-').unwrap_or((ports, ports));
+                let first = first.parse::<u16>().map_err(|_| invalid())?;
+                let last = last.parse::<u16>().map_err(|_| invalid())?;
+                if first > last {
+                    return Err(invalid());
+                }
+                Some(first..=last)
+            }
+            None => None,
+        };
+
+        let host = host.to_lowercase();
+        let host = if host == "*" {
+            AllowedHost::Any
+        } else if let Some(domain) = host.strip_prefix('*') {
+            if !domain.starts_with('.') || !is_hostname(&domain[1..]) {
+                return Err(invalid());
+            }
+            AllowedHost::Subdomains(domain.to_string())
+        } else if let Some((ip, prefix)) = host.split_once('/') {
+            let ip = IpAddr::from_str(ip).map_err(|_| invalid())?;
+            let prefix = prefix.parse::<u8>().map_err(|_| invalid())?;
+            if prefix > max_prefix(&ip) {
+                return Err(invalid());
+            }
+            AllowedHost::Network(ip, prefix)
+        } else if let Ok(ip) = IpAddr::from_str(&host) {
+            AllowedHost::Network(ip, max_prefix(&ip))
+        } else if is_hostname(&host) {
+            AllowedHost::Hostname(host)
+        } else {
+            return Err(invalid());
+        };
+
+        Ok(Self { host, ports })
+    }
+}
+
+fn max_prefix(ip: &IpAddr) -> u8 {
+    match ip {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+fn is_hostname(host: &str) -> bool {
+    !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn resolve(allowlist: &[&str], destination: &str) -> Option<String> {
+        TcpOutletAllowlist::new(allowlist)
+            .unwrap()
+            .resolve(&HostnamePort::from_str(destination).unwrap())
+            .await
+            .unwrap()
+            .map(|d| d.to_string())
+    }
+
+    #[tokio::test]
+    async fn destinations_are_checked_against_the_allowlist() {
+        let allowlist = [
+            "db.internal:5432",
+            "*.web.internal:8000-8080",
+            "10.0.0.0/8:22",
+            "[fd00::/8]",
+        ];
+        for (destination, expected) in [
+            ("db.internal:5432", Some("db.internal:5432")),
+            ("DB.internal:5432", Some("DB.internal:5432")),
+            ("db.internal:5433", None),
+            ("api.web.internal:8080", Some("api.web.internal:8080")),
+            ("api.web.internal:8081", None),
+            ("web.internal:8000", None),
+            ("10.1.2.3:22", Some("10.1.2.3:22")),
+            ("10.1.2.3:23", None),
+            ("11.1.2.3:22", None),
+            ("[fd00::1]:443", Some("[fd00::1]:443")),
+            ("[fe00::1]:443", None),
+            ("localhost:22", None),
+        ] {
+            assert_eq!(
+                resolve(&allowlist, destination).await.as_deref(),
+                expected,
+                "{destination}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn hostnames_are_resolved_against_allowed_networks() {
+        assert_eq!(
+            resolve(&["127.0.0.0/8:1000-2000"], "localhost:1234")
+                .await
+                .as_deref(),
+            Some("127.0.0.1:1234")
+        );
+        assert_eq!(resolve(&["10.0.0.0/8"], "localhost:1234").await, None);
+        assert_eq!(
+            resolve(&["*"], "localhost:1234").await.as_deref(),
+            Some("localhost:1234")
+        );
+    }
+
+    #[test]
+    fn invalid_entries_are_rejected() {
+        for entry in [
+            "",
+            "*internal",
+            "*.",
+            "db:port",
+            "db:70000",
+            "db:2000-1000",
+            "10.0.0.0/33",
+            "[fd00::/8",
+            "db/internal",
+        ] {
+            assert!(TcpOutletAllowlist::new(&[entry]).is_err(), "{entry}");
+        }
+        assert!(TcpOutletAllowlist::new(&Vec::<String>::new()).is_err());
+        assert!(TcpOutletAllowlist::new(&["fd00::1", "[fd00::1]:22", "*:443"]).is_ok());
+    }
+}
@@ -1,29 +1,50 @@
 use crate::portal::addresses::{Addresses, PortalType};
-use crate::{portal::TcpPortalWorker, PortalMessage, TcpOutletOptions, TcpRegistry};
+use crate::portal::PortalPeer;
+use crate::{
+    portal::TcpPortalWorker, PortalMessage, TcpOutletAllowlist, TcpOutletOptions, TcpRegistry,
+};
+use core::str::FromStr;
+use ockam_core::compat::sync::Arc;
 use ockam_core::{
-    async_trait, Address, DenyAll, NeutralMessage, Result, Routed, SecureChannelLocalInfo, Worker,
+    async_trait, Address, DenyAll, LocalInfoIdentifier, NeutralMessage, Result, Route, Routed,
+    SecureChannelLocalInfo, Worker,
 };
 use ockam_node::{Context, WorkerBuilder};
 use ockam_transport_core::{HostnamePort, TransportError};
-use tracing::{debug, instrument};
+use tracing::{debug, instrument, warn};
+
+/// The destination of the Outlets created by a `TcpOutletListenWorker`
+#[derive(Clone, Debug)]
+pub(crate) enum OutletDestination {
+    /// All the Outlets connect to the same peer, requested with a `Ping`
+    Fixed(PortalPeer),
+    /// Each Outlet connects to the destination requested with a `PingTo`,
+    /// if it is allowed
+    Dynamic(Arc<TcpOutletAllowlist>),
+}
 
 /// A TCP Portal Outlet listen worker
 ///
 /// TCP Portal Outlet listen workers are created by `TcpTransport`
 /// after a call is made to
-/// [`TcpTransport::create_outlet`](crate::TcpTransport::create_outlet).
+/// [`TcpTransport::create_outlet`](crate::TcpTransport::create_outlet)
+/// or [`TcpTransport::create_dynamic_outlet`](crate::TcpTransport::create_dynamic_outlet).
 pub(crate) struct TcpOutletListenWorker {
     registry: TcpRegistry,
-    hostname_port: HostnamePort,
+    destination: OutletDestination,
     options: TcpOutletOptions,
 }
 
 impl TcpOutletListenWorker {
     /// Create a new `TcpOutletListenWorker`
-    fn new(registry: TcpRegistry, hostname_port: HostnamePort, options: TcpOutletOptions) -> Self {
+    fn new(
+        registry: TcpRegistry,
+        destination: OutletDestination,
+        options: TcpOutletOptions,
+    ) -> Self {
         Self {
             registry,
-            hostname_port,
+            destination,
             options,
         }
     }
@@ -33,19 +54,25 @@ impl TcpOutletListenWorker {
         ctx: &Context,
         registry: TcpRegistry,
         address: Address,
-        hostname_port: HostnamePort,
+        destination: OutletDestination,
         options: TcpOutletOptions,
     ) -> Result<()> {
         let access_control = options.incoming_access_control.clone();
 
         options.setup_flow_control_for_outlet_listener(ctx.flow_controls(), &address);
 
-        let worker = Self::new(registry, hostname_port, options);
-        WorkerBuilder::new(worker)
+        let builder = WorkerBuilder::new(Self::new(registry, destination.clone(), options.clone()))
             .with_address(address)
-            .with_incoming_access_control_arc(access_control)
-            .with_outgoing_access_control(DenyAll)
-            .start(ctx)?;
+            .with_incoming_access_control_arc(access_control);
+
+        // A dynamic outlet listener replies to the Inlets requesting a refused destination
+        match destination {
+            OutletDestination::Fixed(_) => builder.with_outgoing_access_control(DenyAll),
+            OutletDestination::Dynamic(_) => {
+                builder.with_outgoing_access_control_arc(options.outgoing_access_control)
+            }
+        }
+        .start(ctx)?;
 
         Ok(())
     }
@@ -87,9 +114,23 @@ impl Worker for TcpOutletListenWorker {
         let body = msg.payload;
         let msg = PortalMessage::decode(&body)?;
 
-        if !matches!(msg, PortalMessage::Ping) {
-            return Err(TransportError::Protocol)?;
-        }
+        let peer = match (&self.destination, msg) {
+            (OutletDestination::Fixed(peer), PortalMessage::Ping) => peer.clone(),
+            (OutletDestination::Dynamic(allowlist), PortalMessage::PingTo(destination)) => {
+                let allowlist = allowlist.clone();
+                match self
+                    .authorize_destination(&allowlist, their_identifier.as_ref(), destination)
+                    .await?
+                {
+                    Some(peer) => PortalPeer::Tcp(peer),
+                    None => {
+                        warn!(%destination, "the requested destination is not allowed");
+                        return self.refuse(ctx, return_route).await;
+                    }
+                }
+            }
+            _ => return Err(TransportError::Protocol)?,
+        };
 
         let addresses = Addresses::generate(PortalType::Outlet);
 
@@ -98,7 +139,7 @@ impl Worker for TcpOutletListenWorker {
         TcpPortalWorker::start_new_outlet(
             ctx,
             self.registry.clone(),
-            self.hostname_port.clone(),
+            peer,
             self.options.tls,
             return_route.clone(),
             their_identifier,
@@ -113,3 +154,42 @@ impl Worker for TcpOutletListenWorker {
         Ok(())
     }
 }
+
+impl TcpOutletListenWorker {
+    /// Return the address to connect to for a destination requested by an Inlet,
+    /// or None if the destination is refused
+    async fn authorize_destination(
+        &self,
+        allowlist: &TcpOutletAllowlist,
+        their_identifier: Option<&LocalInfoIdentifier>,
+        destination: &str,
+    ) -> Result<Option<HostnamePort>> {
+        let destination = match HostnamePort::from_str(destination) {
+            Ok(destination) => destination,
+            Err(_) => return Ok(None),
+        };
+        let resolved = match allowlist.resolve(&destination).await? {
+            Some(resolved) => resolved,
+            None => return Ok(None),
+        };
+        if let Some(authorizer) = &self.options.destination_authorizer {
+            if !authorizer
+                .is_authorized(their_identifier, &destination)
+                .await?
+            {
+                return Ok(None);
+            }
+        }
+        debug!(%destination, %resolved, "the requested destination is allowed");
+        Ok(Some(resolved))
+    }
+
+    /// Let the Inlet know that no Outlet will be created for its connection
+    async fn refuse(&self, ctx: &Context, return_route: Route) -> Result<()> {
+        ctx.send(
+            return_route,
+            PortalMessage::Disconnect.to_neutral_message()?,
+        )
+        .await
+    }
+}
@@ -19,6 +19,9 @@ pub enum PortalMessage<'de> {
     //  require reliable channel anyways. And if PortalMessage is sent over a channel that
     //  guarantees ordering, we don't need route_index
     Payload(&'de [u8], Option<u16>),
+    /// First message that an Inlet sends to an Outlet forwarding to dynamic destinations,
+    /// with the `hostname:port` destination the Outlet must connect to
+    PingTo(&'de str),
 }
 
 impl<'de> PortalMessage<'de> {
@@ -57,6 +60,12 @@ impl<'de> PortalMessage<'de> {
                     None
                 }
             }
+            4 => {
+                let destination = read_slice(slice, &mut index)?;
+                core::str::from_utf8(destination)
+                    .ok()
+                    .map(PortalMessage::PingTo)
+            }
             _ => None,
         }
     }
@@ -97,6 +106,15 @@ impl PortalMessage<'_> {
                 // }
                 Ok(vec)
             }
+            PortalMessage::PingTo(destination) => {
+                let capacity = 1
+                    + destination.len()
+                    + ockam_core::bare::size_of_variable_length(destination.len() as u64);
+                let mut vec = Vec::with_capacity(capacity);
+                vec.push(4);
+                write_slice(&mut vec, destination.as_bytes());
+                Ok(vec)
+            }
         }
     }
 }
@@ -208,4 +226,13 @@ mod test {
             panic!("Decoded message is not a Payload");
         }
     }
+
+    #[test]
+    fn ping_to_can_be_encoded() {
+        let encoded = PortalMessage::encode(PortalMessage::PingTo("db.internal:5432")).unwrap();
+        let decoded = PortalMessage::decode(&encoded).unwrap();
+        assert_eq!(decoded, PortalMessage::PingTo("db.internal:5432"));
+
+        assert!(PortalMessage::decode(&[4, 2, 0xff, 0xfe]).is_err());
+    }
 }
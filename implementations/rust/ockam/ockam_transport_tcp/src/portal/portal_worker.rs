@@ -1,8 +1,15 @@
 use crate::portal::addresses::{Addresses, PortalType};
+#[cfg(unix)]
+use crate::portal::portal_worker::ReadHalfMaybeTls::ReadHalfUnix;
 use crate::portal::portal_worker::ReadHalfMaybeTls::{ReadHalfNoTls, ReadHalfWithTls};
+#[cfg(unix)]
+use crate::portal::portal_worker::WriteHalfMaybeTls::WriteHalfUnix;
 use crate::portal::portal_worker::WriteHalfMaybeTls::{WriteHalfNoTls, WriteHalfWithTls};
 use crate::transport::{connect, connect_tls};
-use crate::{portal::TcpPortalRecvProcessor, PortalInternalMessage, PortalMessage, TcpRegistry};
+use crate::{
+    portal::TcpPortalRecvProcessor, PortalInternalMessage, PortalMessage, TcpInletConnectionGuard,
+    TcpRegistry,
+};
 use ockam_core::compat::{boxed::Box, sync::Arc};
 use ockam_core::{
     async_trait, AllowOnwardAddress, AllowSourceAddress, Decodable, DenyAll, IncomingAccessControl,
@@ -11,10 +18,14 @@ use ockam_core::{
 use ockam_core::{Any, Result, Route, Routed, Worker};
 use ockam_node::{Context, ProcessorBuilder, WorkerBuilder};
 use ockam_transport_core::{HostnamePort, TransportError};
+use std::fmt::{Display, Formatter};
+#[cfg(unix)]
+use std::path::PathBuf;
 use std::time::Duration;
 use tokio::io::{AsyncRead, AsyncWriteExt, ReadHalf, WriteHalf};
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::TcpStream;
+use tokio::sync::oneshot;
 use tokio_rustls::TlsStream;
 use tracing::{debug, info, instrument, trace, warn};
 
@@ -24,10 +35,19 @@ use tracing::{debug, info, instrument, trace, warn};
 ///
 /// `Outlet`: `SendPong` -> `Initialized`
 /// `Inlet`: `SendPing` -> `ReceivePong` -> `Initialized`
+///
+/// An Inlet sends a `PingTo` instead of a `Ping` when it requests a destination to
+/// an Outlet forwarding to dynamic destinations, which replies with a `Disconnect`
+/// when it refuses the destination
 #[derive(Clone)]
 enum State {
-    SendPing { ping_route: Route },
-    SendPong { pong_route: Route },
+    SendPing {
+        ping_route: Route,
+        destination: Option<HostnamePort>,
+    },
+    SendPong {
+        pong_route: Route,
+    },
     ReceivePong,
     Initialized,
 }
@@ -44,7 +64,7 @@ pub(crate) struct TcpPortalWorker {
     their_identifier: Option<LocalInfoIdentifier>,
     write_half: Option<WriteHalfMaybeTls>,
     read_half: Option<ReadHalfMaybeTls>,
-    hostname_port: HostnamePort,
+    peer: PortalPeer,
     addresses: Addresses,
     remote_route: Option<Route>,
     is_disconnecting: bool,
@@ -53,46 +73,90 @@ pub(crate) struct TcpPortalWorker {
     outgoing_access_control: Arc<dyn OutgoingAccessControl>,
     is_tls: bool,
     portal_payload_length: usize,
+    stream_replies: Option<InletStreamReplies>,
+    // Kept until the portal is stopped, when the route was chosen by a `TcpInletRouteSelector`
+    _connection_guard: Option<TcpInletConnectionGuard>,
 }
 
+#[allow(clippy::enum_variant_names)]
 pub(crate) enum ReadHalfMaybeTls {
     ReadHalfNoTls(OwnedReadHalf),
     ReadHalfWithTls(ReadHalf<TlsStream<TcpStream>>),
+    #[cfg(unix)]
+    ReadHalfUnix(tokio::net::unix::OwnedReadHalf),
 }
 
+#[allow(clippy::enum_variant_names)]
 pub(crate) enum WriteHalfMaybeTls {
     WriteHalfNoTls(OwnedWriteHalf),
     WriteHalfWithTls(WriteHalf<TlsStream<TcpStream>>),
+    #[cfg(unix)]
+    WriteHalfUnix(tokio::net::unix::OwnedWriteHalf),
+}
+
+/// Replies written to the client of an Inlet stream which was accepted outside an Inlet
+/// listener, for example by a proxy, depending on the outcome of the handshake with the Outlet
+pub(crate) struct InletStreamReplies {
+    pub(crate) connected: Vec<u8>,
+    pub(crate) failed: Vec<u8>,
+    pub(crate) on_connected: oneshot::Sender<()>,
+}
+
+/// The peer of a portal worker: the client connected to an Inlet,
+/// or the server an Outlet connects to
+#[derive(Clone, Debug)]
+pub(crate) enum PortalPeer {
+    Tcp(HostnamePort),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl Display for PortalPeer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PortalPeer::Tcp(hostname_port) => write!(f, "{}", hostname_port),
+            #[cfg(unix)]
+            PortalPeer::Unix(path) => write!(f, "{}", path.display()),
+        }
+    }
 }
 
 impl TcpPortalWorker {
     /// Start a new `TcpPortalWorker` of type [`TypeName::Inlet`]
     #[instrument(skip_all)]
     #[allow(clippy::too_many_arguments)]
-    pub(super) fn start_new_inlet(
+    pub(crate) fn start_new_inlet(
         ctx: &Context,
         registry: TcpRegistry,
         streams: (ReadHalfMaybeTls, WriteHalfMaybeTls),
-        hostname_port: HostnamePort,
+        peer: PortalPeer,
         ping_route: Route,
         their_identifier: Option<LocalInfoIdentifier>,
         addresses: Addresses,
         incoming_access_control: Arc<dyn IncomingAccessControl>,
         outgoing_access_control: Arc<dyn OutgoingAccessControl>, // To propagate to the receiver
         portal_payload_length: usize,
+        outlet_destination: Option<HostnamePort>,
+        stream_replies: Option<InletStreamReplies>,
+        connection_guard: Option<TcpInletConnectionGuard>,
     ) -> Result<()> {
         Self::start(
             ctx,
             registry,
-            hostname_port,
+            peer,
             false,
-            State::SendPing { ping_route },
+            State::SendPing {
+                ping_route,
+                destination: outlet_destination,
+            },
             their_identifier,
             Some(streams),
             addresses,
             incoming_access_control,
             outgoing_access_control,
             portal_payload_length,
+            stream_replies,
+            connection_guard,
         )
     }
 
@@ -102,7 +166,7 @@ impl TcpPortalWorker {
     pub(super) fn start_new_outlet(
         ctx: &Context,
         registry: TcpRegistry,
-        hostname_port: HostnamePort,
+        peer: PortalPeer,
         tls: bool,
         pong_route: Route,
         their_identifier: Option<LocalInfoIdentifier>,
@@ -114,7 +178,7 @@ impl TcpPortalWorker {
         Self::start(
             ctx,
             registry,
-            hostname_port,
+            peer,
             tls,
             State::SendPong { pong_route },
             their_identifier,
@@ -123,6 +187,8 @@ impl TcpPortalWorker {
             incoming_access_control,
             outgoing_access_control,
             portal_payload_length,
+            None,
+            None,
         )
     }
 
@@ -132,7 +198,7 @@ impl TcpPortalWorker {
     fn start(
         ctx: &Context,
         registry: TcpRegistry,
-        hostname_port: HostnamePort,
+        peer: PortalPeer,
         is_tls: bool,
         state: State,
         their_identifier: Option<LocalInfoIdentifier>,
@@ -141,6 +207,8 @@ impl TcpPortalWorker {
         incoming_access_control: Arc<dyn IncomingAccessControl>,
         outgoing_access_control: Arc<dyn OutgoingAccessControl>,
         portal_payload_length: usize,
+        stream_replies: Option<InletStreamReplies>,
+        connection_guard: Option<TcpInletConnectionGuard>,
     ) -> Result<()> {
         let portal_type = if streams.is_some() {
             PortalType::Inlet
@@ -152,7 +220,7 @@ impl TcpPortalWorker {
         let (rx, tx) = match streams {
             // A TcpStream is provided in case of an inlet
             Some((rx, tx)) => {
-                debug!("Connected to {}", &hostname_port);
+                debug!("Connected to {}", &peer);
                 (Some(rx), Some(tx))
             }
             None => (None, None),
@@ -164,7 +232,7 @@ impl TcpPortalWorker {
             their_identifier,
             write_half: tx,
             read_half: rx,
-            hostname_port,
+            peer,
             addresses: addresses.clone(),
             remote_route: None,
             is_disconnecting: false,
@@ -173,6 +241,8 @@ impl TcpPortalWorker {
             is_tls,
             outgoing_access_control: outgoing_access_control.clone(),
             portal_payload_length,
+            stream_replies,
+            _connection_guard: connection_guard,
         };
 
         let internal_mailbox = Mailbox::new(
@@ -217,6 +287,8 @@ impl TcpPortalWorker {
             match rx {
                 ReadHalfNoTls(rx) => self.start_receive_processor(ctx, onward_route, rx),
                 ReadHalfWithTls(rx) => self.start_receive_processor(ctx, onward_route, rx),
+                #[cfg(unix)]
+                ReadHalfUnix(rx) => self.start_receive_processor(ctx, onward_route, rx),
             }
         } else {
             Err(TransportError::PortalInvalidState)?
@@ -348,11 +420,21 @@ impl TcpPortalWorker {
     }
 
     #[instrument(skip_all)]
-    async fn handle_send_ping(&self, ctx: &Context, ping_route: Route) -> Result<State> {
+    async fn handle_send_ping(
+        &self,
+        ctx: &Context,
+        ping_route: Route,
+        destination: Option<HostnamePort>,
+    ) -> Result<State> {
         // Force creation of Outlet on the other side
+        let destination = destination.map(|d| d.to_string());
+        let ping = match &destination {
+            Some(destination) => PortalMessage::PingTo(destination),
+            None => PortalMessage::Ping,
+        };
         ctx.send_from_address(
             ping_route,
-            PortalMessage::Ping.to_neutral_message()?,
+            ping.to_neutral_message()?,
             self.addresses.sender_remote.clone(),
         )
         .await?;
@@ -368,16 +450,29 @@ impl TcpPortalWorker {
             // Should not happen
             return Err(TransportError::PortalInvalidState)?;
         }
-        if self.is_tls {
-            debug!(portal_type = %self.portal_type, sender_internal = %self.addresses.sender_internal, "connect to {} via TLS", &self.hostname_port);
-            let (rx, tx) = connect_tls(&self.hostname_port).await?;
-            self.write_half = Some(WriteHalfWithTls(tx));
-            self.read_half = Some(ReadHalfWithTls(rx));
-        } else {
-            debug!(portal_type = %self.portal_type, sender_internal = %self.addresses.sender_internal, "connect to {}", self.hostname_port);
-            let (rx, tx) = connect(&self.hostname_port, None).await?;
-            self.write_half = Some(WriteHalfNoTls(tx));
-            self.read_half = Some(ReadHalfNoTls(rx));
+        match &self.peer {
+            PortalPeer::Tcp(hostname_port) if self.is_tls => {
+                debug!(portal_type = %self.portal_type, sender_internal = %self.addresses.sender_internal, "connect to {} via TLS", hostname_port);
+                let (rx, tx) = connect_tls(hostname_port).await?;
+                self.write_half = Some(WriteHalfWithTls(tx));
+                self.read_half = Some(ReadHalfWithTls(rx));
+            }
+            PortalPeer::Tcp(hostname_port) => {
+                debug!(portal_type = %self.portal_type, sender_internal = %self.addresses.sender_internal, "connect to {}", hostname_port);
+                let (rx, tx) = connect(hostname_port, None).await?;
+                self.write_half = Some(WriteHalfNoTls(tx));
+                self.read_half = Some(ReadHalfNoTls(rx));
+            }
+            #[cfg(unix)]
+            PortalPeer::Unix(path) => {
+                debug!(portal_type = %self.portal_type, sender_internal = %self.addresses.sender_internal, "connect to unix socket {}", path.display());
+                let (rx, tx) = tokio::net::UnixStream::connect(path)
+                    .await
+                    .map_err(TransportError::from)?
+                    .into_split();
+                self.write_half = Some(WriteHalfUnix(tx));
+                self.read_half = Some(ReadHalfUnix(rx));
+            }
         }
 
         // Respond to Inlet before starting the processor but
@@ -409,8 +504,17 @@ impl Worker for TcpPortalWorker {
         let state = self.clone_state();
 
         match state {
-            State::SendPing { ping_route } => {
-                self.state = self.handle_send_ping(ctx, ping_route.clone()).await?;
+            State::SendPing {
+                ping_route,
+                destination,
+            } => {
+                self.state = match self.handle_send_ping(ctx, ping_route, destination).await {
+                    Ok(state) => state,
+                    Err(err) => {
+                        self.send_failed_reply().await;
+                        return Err(err);
+                    }
+                };
             }
             State::SendPong { pong_route } => {
                 self.state = self.handle_send_pong(ctx, pong_route.clone()).await?;
@@ -435,6 +539,9 @@ impl Worker for TcpPortalWorker {
         self.registry
             .remove_portal_worker(&self.addresses.sender_remote);
 
+        // The Outlet never answered, let the client know before dropping the stream
+        self.send_failed_reply().await;
+
         Ok(())
     }
 
@@ -481,10 +588,16 @@ impl Worker for TcpPortalWorker {
                 if !remote_packet {
                     return Err(TransportError::PortalInvalidState)?;
                 };
-                if PortalMessage::decode(&payload)? != PortalMessage::Pong {
-                    return Err(TransportError::Protocol)?;
-                };
-                self.handle_receive_pong(ctx, return_route)
+                match PortalMessage::decode(&payload)? {
+                    PortalMessage::Pong => self.handle_receive_pong(ctx, return_route).await,
+                    // The Outlet refused to connect to the requested destination
+                    PortalMessage::Disconnect => {
+                        debug!(portal_type = %self.portal_type, sender_internal = %self.addresses.sender_internal, "the outlet refused the connection");
+                        self.start_disconnection(ctx, DisconnectionReason::Remote)
+                            .await
+                    }
+                    _ => Err(TransportError::Protocol)?,
+                }
             }
             State::Initialized => {
                 trace!(portal_type = %self.portal_type, sender_internal = %self.addresses.sender_internal,
@@ -503,7 +616,9 @@ impl Worker for TcpPortalWorker {
                             self.start_disconnection(ctx, DisconnectionReason::Remote)
                                 .await
                         }
-                        PortalMessage::Ping | PortalMessage::Pong => Err(TransportError::Protocol)?,
+                        PortalMessage::Ping | PortalMessage::PingTo(_) | PortalMessage::Pong => {
+                            Err(TransportError::Protocol)?
+                        }
                     }
                 } else {
                     let msg = PortalInternalMessage::decode(&payload)?;
@@ -522,12 +637,50 @@ impl Worker for TcpPortalWorker {
 
 impl TcpPortalWorker {
     #[instrument(skip_all)]
-    fn handle_receive_pong(&mut self, ctx: &Context, return_route: Route) -> Result<()> {
-        self.start_receiver(ctx, return_route.clone())?;
+    async fn handle_receive_pong(&mut self, ctx: &Context, return_route: Route) -> Result<()> {
         debug!(portal_type = %self.portal_type, sender_internal = %self.addresses.sender_internal, "received pong");
-        self.remote_route = Some(return_route);
+        self.remote_route = Some(return_route.clone());
         self.state = State::Initialized;
-        Ok(())
+
+        // Let the client know that the portal is ready, before any data coming from the Outlet
+        if let Some(replies) = self.stream_replies.take() {
+            if let Err(err) = self.write_to_peer(&replies.connected).await {
+                warn!(portal_type = %self.portal_type, %err,
+                    "failed to send the connection reply to peer {}",
+                    self.peer
+                );
+                return self
+                    .start_disconnection(ctx, DisconnectionReason::FailedTx)
+                    .await;
+            }
+            let _ = replies.on_connected.send(());
+        }
+
+        self.start_receiver(ctx, return_route)
+    }
+
+    async fn send_failed_reply(&mut self) {
+        if let Some(replies) = self.stream_replies.take() {
+            if let Err(err) = self.write_to_peer(&replies.failed).await {
+                debug!(portal_type = %self.portal_type, %err, "failed to send the failure reply");
+            }
+        }
+    }
+
+    async fn write_to_peer(&mut self, data: &[u8]) -> Result<()> {
+        let tx = if let Some(tx) = &mut self.write_half {
+            tx
+        } else {
+            return Err(TransportError::PortalInvalidState)?;
+        };
+
+        let result = match tx {
+            WriteHalfNoTls(tx) => tx.write_all(data).await,
+            WriteHalfWithTls(tx) => tx.write_all(data).await,
+            #[cfg(unix)]
+            WriteHalfUnix(tx) => tx.write_all(data).await,
+        };
+        Ok(result.map_err(TransportError::from)?)
     }
 
     #[instrument(skip_all)]
@@ -547,20 +700,14 @@ impl TcpPortalWorker {
     ) -> Result<()> {
         // detects both missing or out of order packets
         self.check_packet_counter(ctx, packet_counter).await?;
-        let tx = if let Some(tx) = &mut self.write_half {
-            tx
-        } else {
+        if self.write_half.is_none() {
             return Err(TransportError::PortalInvalidState)?;
-        };
+        }
 
-        let result = match tx {
-            WriteHalfNoTls(tx) => tx.write_all(payload).await,
-            WriteHalfWithTls(tx) => tx.write_all(payload).await,
-        };
-        if let Err(err) = result {
+        if let Err(err) = self.write_to_peer(payload).await {
             warn!(portal_type = %self.portal_type, %err,
                 "failed to send message to peer {} with error",
-                self.hostname_port
+                self.peer
             );
             self.start_disconnection(ctx, DisconnectionReason::FailedTx)
                 .await?;
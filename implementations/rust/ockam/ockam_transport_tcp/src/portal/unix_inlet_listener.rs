@@ -0,0 +1,172 @@
+use crate::portal::addresses::{Addresses, PortalType};
+use crate::portal::{InletSharedState, PortalPeer, ReadHalfMaybeTls, WriteHalfMaybeTls};
+use crate::{portal::TcpPortalWorker, TcpInletOptions, TcpRegistry, UnixInlet};
+use ockam_core::compat::sync::{Arc, RwLock as SyncRwLock};
+use ockam_core::{async_trait, compat::boxed::Box, Result};
+use ockam_core::{Address, Processor, Route};
+use ockam_node::Context;
+use ockam_transport_core::TransportError;
+use std::fs::Permissions;
+use std::io::ErrorKind;
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use tokio::net::UnixListener;
+use tracing::{debug, error, instrument, warn};
+
+/// A Unix domain socket Portal Inlet listen processor
+///
+/// Unix domain socket Portal Inlet listen processors are created by `TcpTransport`
+/// after a call is made to
+/// [`TcpTransport::create_unix_inlet`](crate::TcpTransport::create_unix_inlet).
+pub(crate) struct UnixInletListenProcessor {
+    registry: TcpRegistry,
+    inner: UnixListener,
+    path: PathBuf,
+    inlet_shared_state: Arc<SyncRwLock<InletSharedState>>,
+    options: TcpInletOptions,
+}
+
+impl UnixInletListenProcessor {
+    /// Start a new `UnixInletListenProcessor`
+    #[instrument(skip_all, name = "UnixInletListenProcessor::start")]
+    pub(crate) async fn start(
+        ctx: &Context,
+        registry: TcpRegistry,
+        outlet_listener_route: Route,
+        path: PathBuf,
+        options: TcpInletOptions,
+    ) -> Result<UnixInlet> {
+        let processor_address = Address::random_tagged("UnixInletListenProcessor");
+
+        debug!("Binding UnixInletListenProcessor to {}", path.display());
+        let inner = match Self::bind(&path) {
+            Ok(inner) => inner,
+            Err(err) => {
+                error!(path = %path.display(), %err, "could not bind to unix socket");
+                return Err(TransportError::from(err))?;
+            }
+        };
+        if let Err(err) = Self::set_permissions(&path, &options) {
+            error!(path = %path.display(), %err, "could not set the unix socket permissions");
+            let _ = std::fs::remove_file(&path);
+            return Err(TransportError::from(err))?;
+        }
+
+        let inlet_shared_state =
+            InletSharedState::create(ctx, outlet_listener_route, options.is_paused)?;
+        let inlet_shared_state = Arc::new(SyncRwLock::new(inlet_shared_state));
+        let processor = Self {
+            registry,
+            inner,
+            path: path.clone(),
+            inlet_shared_state: inlet_shared_state.clone(),
+            options,
+        };
+
+        ctx.start_processor(processor_address.clone(), processor)?;
+
+        Ok(UnixInlet::new(path, processor_address, inlet_shared_state))
+    }
+
+    /// Bind the socket, replacing a socket file left over by a listener which is not running
+    /// anymore
+    fn bind(path: &Path) -> std::io::Result<UnixListener> {
+        match UnixListener::bind(path) {
+            Err(err) if err.kind() == ErrorKind::AddrInUse && Self::is_stale_socket(path) => {
+                warn!(path = %path.display(), "removing a stale unix socket");
+                std::fs::remove_file(path)?;
+                UnixListener::bind(path)
+            }
+            result => result,
+        }
+    }
+
+    fn is_stale_socket(path: &Path) -> bool {
+        let is_socket = std::fs::metadata(path)
+            .map(|m| m.file_type().is_socket())
+            .unwrap_or(false);
+        is_socket
+            && std::os::unix::net::UnixStream::connect(path)
+                .err()
+                .map(|err| err.kind() == ErrorKind::ConnectionRefused)
+                .unwrap_or(false)
+    }
+
+    fn set_permissions(path: &Path, options: &TcpInletOptions) -> std::io::Result<()> {
+        if options.unix_socket_owner.is_some() || options.unix_socket_group.is_some() {
+            std::os::unix::fs::chown(path, options.unix_socket_owner, options.unix_socket_group)?;
+        }
+        if let Some(mode) = options.unix_socket_mode {
+            std::fs::set_permissions(path, Permissions::from_mode(mode))?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Processor for UnixInletListenProcessor {
+    type Context = Context;
+
+    #[instrument(skip_all, name = "UnixInletListenProcessor::initialize")]
+    async fn initialize(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        self.registry
+            .add_inlet_listener_processor(ctx.primary_address());
+
+        Ok(())
+    }
+
+    #[instrument(skip_all, name = "UnixInletListenProcessor::shutdown")]
+    async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        self.registry
+            .remove_inlet_listener_processor(ctx.primary_address());
+
+        if let Err(err) = std::fs::remove_file(&self.path) {
+            warn!(path = %self.path.display(), %err, "could not remove the unix socket");
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip_all, name = "UnixInletListenProcessor::process")]
+    async fn process(&mut self, ctx: &mut Self::Context) -> Result<bool> {
+        let (stream, _) = self.inner.accept().await.map_err(TransportError::from)?;
+
+        let addresses = Addresses::generate(PortalType::Inlet);
+
+        let inlet_shared_state = self.inlet_shared_state.read().unwrap().clone();
+
+        if inlet_shared_state.is_paused() {
+            // Just drop the stream
+            return Ok(true);
+        }
+
+        TcpInletOptions::setup_flow_control(
+            ctx.flow_controls(),
+            &addresses,
+            inlet_shared_state.route().next()?,
+        );
+
+        let (rx, tx) = stream.into_split();
+
+        TcpPortalWorker::start_new_inlet(
+            ctx,
+            self.registry.clone(),
+            (
+                ReadHalfMaybeTls::ReadHalfUnix(rx),
+                WriteHalfMaybeTls::WriteHalfUnix(tx),
+            ),
+            PortalPeer::Unix(self.path.clone()),
+            inlet_shared_state.route().clone(),
+            inlet_shared_state.their_identifier(),
+            addresses,
+            self.options.incoming_access_control.clone(),
+            self.options.outgoing_access_control.clone(),
+            self.options.portal_payload_length,
+            self.options.outlet_destination.clone(),
+            None,
+            None,
+        )?;
+
+        Ok(true)
+    }
+}
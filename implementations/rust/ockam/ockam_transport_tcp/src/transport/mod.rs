@@ -3,6 +3,9 @@ mod connection;
 mod lifecycle;
 mod listener;
 mod portals;
+mod stream_portals;
+#[cfg(unix)]
+mod unix_portals;
 
 pub(crate) use common::*;
 
@@ -10,6 +13,9 @@ pub use crate::portal::options::*;
 pub use connection::*;
 pub use listener::*;
 pub use portals::*;
+pub use stream_portals::*;
+#[cfg(unix)]
+pub use unix_portals::*;
 
 use crate::TcpRegistry;
 use ockam_core::compat::sync::Arc;
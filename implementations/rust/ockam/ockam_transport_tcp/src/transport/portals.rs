@@ -1,5 +1,8 @@
-use crate::portal::{InletSharedState, TcpInletListenProcessor};
-use crate::{portal::TcpOutletListenWorker, TcpInletOptions, TcpOutletOptions, TcpTransport};
+use crate::portal::{InletSharedState, OutletDestination, PortalPeer, TcpInletListenProcessor};
+use crate::{
+    portal::TcpOutletListenWorker, TcpInletOptions, TcpInletRouteSelector, TcpOutletAllowlist,
+    TcpOutletOptions, TcpTransport,
+};
 use core::fmt;
 use core::fmt::{Debug, Formatter};
 use ockam_core::compat::net::SocketAddr;
@@ -41,6 +44,54 @@ impl TcpTransport {
             &self.ctx,
             self.registry.clone(),
             outlet_route.into(),
+            None,
+            socket_address,
+            options,
+        )
+        .await
+    }
+
+    /// Create Tcp Inlet that listens on bind_addr and forwards each accepted connection to the
+    /// Outlet chosen by `route_selector`, for example to balance the connections across
+    /// several Outlets exposing the same service.
+    ///
+    /// Connections accepted while the selector has no route to return are dropped.
+    ///
+    /// ```rust
+    /// use ockam_transport_tcp::{TcpInletConnectionGuard, TcpInletOptions, TcpInletRouteSelector, TcpTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::{Result, route, Route};
+    /// # use std::sync::Arc;
+    /// #[derive(Debug)]
+    /// struct FirstOutlet;
+    ///
+    /// impl TcpInletRouteSelector for FirstOutlet {
+    ///     fn select_route(&self) -> Option<(Route, TcpInletConnectionGuard)> {
+    ///         Some((route!["outlet1"], Box::new(())))
+    ///     }
+    /// }
+    ///
+    /// # async fn test(ctx: Context) -> Result<()> {
+    /// let tcp = TcpTransport::create(&ctx)?;
+    /// let inlet = tcp
+    ///     .create_inlet_with_route_selector("127.0.0.1:0", Arc::new(FirstOutlet), TcpInletOptions::new())
+    ///     .await?;
+    /// # inlet.stop(&ctx)?;
+    /// # Ok(()) }
+    /// ```
+    #[instrument(skip(self, route_selector, options), fields(address = ? bind_addr.clone().into()))]
+    pub async fn create_inlet_with_route_selector(
+        &self,
+        bind_addr: impl Into<String> + Clone + Debug,
+        route_selector: Arc<dyn TcpInletRouteSelector>,
+        options: TcpInletOptions,
+    ) -> Result<TcpInlet> {
+        let socket_address = parse_socket_addr(&bind_addr.into())?;
+        TcpInletListenProcessor::start(
+            &self.ctx,
+            self.registry.clone(),
+            Route::new().into(),
+            Some(route_selector),
             socket_address,
             options,
         )
@@ -100,7 +151,45 @@ impl TcpTransport {
             &self.ctx,
             self.registry.clone(),
             address.into(),
-            peer,
+            OutletDestination::Fixed(PortalPeer::Tcp(peer)),
+            options,
+        )?;
+
+        Ok(())
+    }
+
+    /// Create Tcp Outlet Listener at address, where each Outlet connects to the destination
+    /// requested by its Inlet with [`TcpInletOptions::with_outlet_destination`], if the
+    /// destination is accepted by the allowlist and by the
+    /// [`TcpOutletOptions::with_destination_authorizer`] authorizer when one is set.
+    /// The Inlets requesting a refused destination are disconnected.
+    ///
+    /// ```rust
+    /// use ockam_transport_tcp::{TcpOutletAllowlist, TcpOutletOptions, TcpTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::{Address, AllowAll, Result};
+    ///
+    /// async fn test(ctx: Context) -> Result<()> {
+    ///
+    /// let tcp = TcpTransport::create(&ctx)?;
+    /// let address: Address = "outlet".into();
+    /// let allowlist = TcpOutletAllowlist::new(&["10.0.0.0/8:22", "*.internal"])?;
+    /// tcp.create_dynamic_outlet(address.clone(), allowlist, TcpOutletOptions::new())?;
+    /// # tcp.stop_outlet(&address)?;
+    /// # Ok(()) }
+    /// ```
+    #[instrument(skip(self), fields(address = ? address.clone().into()))]
+    pub fn create_dynamic_outlet(
+        &self,
+        address: impl Into<Address> + Clone + Debug,
+        allowlist: TcpOutletAllowlist,
+        options: TcpOutletOptions,
+    ) -> Result<()> {
+        TcpOutletListenWorker::start(
+            &self.ctx,
+            self.registry.clone(),
+            address.into(),
+            OutletDestination::Dynamic(Arc::new(allowlist)),
             options,
         )?;
 
@@ -0,0 +1,122 @@
+use crate::portal::addresses::{Addresses, PortalType};
+use crate::portal::{
+    InletSharedState, InletStreamReplies, PortalPeer, ReadHalfMaybeTls, TcpPortalWorker,
+    WriteHalfMaybeTls,
+};
+use crate::{TcpInletOptions, TcpTransport};
+use core::fmt::Debug;
+use core::time::Duration;
+use ockam_core::{Address, Result, Route};
+use ockam_node::Context;
+use ockam_transport_core::{HostnamePort, TransportError};
+use tokio::net::TcpStream;
+use tokio::sync::oneshot;
+use tracing::{debug, instrument};
+
+impl TcpTransport {
+    /// Create a Portal for a single TCP `stream` which was accepted by the caller, for example
+    /// by a proxy which first negotiated the destination with its client, and forward it to the
+    /// Outlet using `outlet_route`.
+    ///
+    /// `connected_reply` is written to the stream as soon as the Outlet answers, before any data
+    /// coming from the Outlet. If the Portal is stopped before the Outlet answers,
+    /// `failed_reply` is written instead.
+    ///
+    /// ```rust
+    /// use ockam_transport_tcp::{TcpInletOptions, TcpTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::{Result, route};
+    /// # use std::time::Duration;
+    /// # async fn test(ctx: Context, stream: tokio::net::TcpStream) -> Result<()> {
+    /// let tcp = TcpTransport::create(&ctx)?;
+    /// let inlet_stream = tcp.create_inlet_stream(
+    ///     stream,
+    ///     route!["outlet"],
+    ///     b"connected\n".to_vec(),
+    ///     b"unavailable\n".to_vec(),
+    ///     TcpInletOptions::new(),
+    /// )?;
+    /// inlet_stream.wait_connected(&ctx, Duration::from_secs(5)).await?;
+    /// # Ok(()) }
+    /// ```
+    #[instrument(skip(self, stream, connected_reply, failed_reply, options))]
+    pub fn create_inlet_stream(
+        &self,
+        stream: TcpStream,
+        outlet_route: impl Into<Route> + Clone + Debug,
+        connected_reply: Vec<u8>,
+        failed_reply: Vec<u8>,
+        options: TcpInletOptions,
+    ) -> Result<TcpInletStream> {
+        let outlet_route = outlet_route.into();
+        let peer = stream.peer_addr().map_err(TransportError::from)?;
+        stream.set_nodelay(true).map_err(TransportError::from)?;
+
+        let addresses = Addresses::generate(PortalType::Inlet);
+        TcpInletOptions::setup_flow_control(
+            self.ctx.flow_controls(),
+            &addresses,
+            outlet_route.next()?,
+        );
+
+        let their_identifier =
+            InletSharedState::create(&self.ctx, outlet_route.clone(), false)?.their_identifier();
+        let (on_connected, connected) = oneshot::channel();
+        let (rx, tx) = stream.into_split();
+
+        TcpPortalWorker::start_new_inlet(
+            &self.ctx,
+            self.registry.clone(),
+            (
+                ReadHalfMaybeTls::ReadHalfNoTls(rx),
+                WriteHalfMaybeTls::WriteHalfNoTls(tx),
+            ),
+            PortalPeer::Tcp(HostnamePort::from(peer)),
+            outlet_route,
+            their_identifier,
+            addresses.clone(),
+            options.incoming_access_control,
+            options.outgoing_access_control,
+            options.portal_payload_length,
+            options.outlet_destination.clone(),
+            Some(InletStreamReplies {
+                connected: connected_reply,
+                failed: failed_reply,
+                on_connected,
+            }),
+            None,
+        )?;
+
+        Ok(TcpInletStream {
+            portal_worker_address: addresses.sender_internal,
+            connected,
+        })
+    }
+}
+
+/// Result of [`TcpTransport::create_inlet_stream`] call.
+#[derive(Debug)]
+pub struct TcpInletStream {
+    portal_worker_address: Address,
+    connected: oneshot::Receiver<()>,
+}
+
+impl TcpInletStream {
+    /// Address of the Portal worker
+    pub fn portal_worker_address(&self) -> &Address {
+        &self.portal_worker_address
+    }
+
+    /// Wait for the Outlet to answer. If it doesn't answer before `timeout`, the Portal is
+    /// stopped, which writes the failure reply to the stream, and an error is returned.
+    pub async fn wait_connected(self, ctx: &Context, timeout: Duration) -> Result<Address> {
+        match tokio::time::timeout(timeout, self.connected).await {
+            Ok(Ok(())) => Ok(self.portal_worker_address),
+            _ => {
+                debug!(address = %self.portal_worker_address, "the outlet did not answer");
+                let _ = ctx.stop_address(&self.portal_worker_address);
+                Err(TransportError::ConnectionTimeout)?
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use crate::portal::{InletSharedState, OutletDestination, PortalPeer, UnixInletListenProcessor};
+use crate::{portal::TcpOutletListenWorker, TcpInletOptions, TcpOutletOptions, TcpTransport};
+use core::fmt;
+use core::fmt::{Debug, Formatter};
+use ockam_core::compat::sync::{Arc, RwLock as SyncRwLock};
+use ockam_core::{Address, Result, Route};
+use ockam_node::Context;
+use std::path::{Path, PathBuf};
+use tracing::{debug, instrument};
+
+impl TcpTransport {
+    /// Create an Inlet that listens on a Unix domain socket at `path`, transforms the streams of
+    /// the accepted connections into Ockam Routable Messages and forward them to the Outlet
+    /// using `outlet_route`. The Outlet can either connect to a TCP server or to a Unix domain
+    /// socket.
+    ///
+    /// The file mode and the owner of the socket can be set with
+    /// [`TcpInletOptions::with_unix_socket_mode`] and [`TcpInletOptions::with_unix_socket_owner`].
+    /// The socket file is removed when the Inlet is stopped.
+    ///
+    /// ```rust
+    /// use ockam_transport_tcp::{TcpInletOptions, TcpTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::{Result, route};
+    /// # async fn test(ctx: Context) -> Result<()> {
+    /// let tcp = TcpTransport::create(&ctx)?;
+    /// let options = TcpInletOptions::new().with_unix_socket_mode(0o660);
+    /// let inlet = tcp.create_unix_inlet("/tmp/inlet.sock", route!["outlet"], options).await?;
+    /// # inlet.stop(&ctx)?;
+    /// # Ok(()) }
+    /// ```
+    #[instrument(skip(self), fields(path = ? path.as_ref(), outlet_route = ? outlet_route.clone()))]
+    pub async fn create_unix_inlet(
+        &self,
+        path: impl AsRef<Path> + Debug,
+        outlet_route: impl Into<Route> + Clone + Debug,
+        options: TcpInletOptions,
+    ) -> Result<UnixInlet> {
+        UnixInletListenProcessor::start(
+            &self.ctx,
+            self.registry.clone(),
+            outlet_route.into(),
+            path.as_ref().to_path_buf(),
+            options,
+        )
+        .await
+    }
+
+    /// Create an Outlet Listener at address, that connects to the Unix domain socket at `path`
+    /// for each Inlet connection, transforms Ockam Messages received from the Inlet into a
+    /// stream and sends it to the socket.
+    ///
+    /// ```rust
+    /// use ockam_transport_tcp::{TcpOutletOptions, TcpTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::{Address, Result};
+    /// # async fn test(ctx: Context) -> Result<()> {
+    /// let tcp = TcpTransport::create(&ctx)?;
+    /// let address: Address = "outlet".into();
+    /// tcp.create_unix_outlet(address.clone(), "/var/run/docker.sock", TcpOutletOptions::new())?;
+    /// # tcp.stop_outlet(&address)?;
+    /// # Ok(()) }
+    /// ```
+    #[instrument(skip(self), fields(address = ? address.clone().into(), path = ? path.as_ref()))]
+    pub fn create_unix_outlet(
+        &self,
+        address: impl Into<Address> + Clone + Debug,
+        path: impl AsRef<Path> + Debug,
+        options: TcpOutletOptions,
+    ) -> Result<()> {
+        TcpOutletListenWorker::start(
+            &self.ctx,
+            self.registry.clone(),
+            address.into(),
+            OutletDestination::Fixed(PortalPeer::Unix(path.as_ref().to_path_buf())),
+            options,
+        )
+    }
+}
+
+/// Result of [`TcpTransport::create_unix_inlet`] call.
+#[derive(Clone, Debug)]
+pub struct UnixInlet {
+    path: PathBuf,
+    processor_address: Address,
+    inlet_shared_state: Arc<SyncRwLock<InletSharedState>>,
+}
+
+impl fmt::Display for UnixInlet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unix socket: {}. Processor address: {}",
+            self.path.display(),
+            self.processor_address
+        )
+    }
+}
+
+impl UnixInlet {
+    pub(crate) fn new(
+        path: PathBuf,
+        processor_address: Address,
+        inlet_shared_state: Arc<SyncRwLock<InletSharedState>>,
+    ) -> Self {
+        Self {
+            path,
+            processor_address,
+            inlet_shared_state,
+        }
+    }
+
+    /// Path of the Unix domain socket
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Processor address
+    pub fn processor_address(&self) -> &Address {
+        &self.processor_address
+    }
+
+    /// Pause the Inlet, all incoming connections will be dropped.
+    pub fn pause(&self) {
+        debug!(path = %self.path.display(), "pausing unix inlet");
+        let mut inlet_shared_state = self.inlet_shared_state.write().unwrap();
+        inlet_shared_state.set_is_paused(true);
+    }
+
+    /// Unpause the Inlet and update the route to the outlet node.
+    ///  NOTE: Existing connections will still use the old route,
+    ///        only newly accepted connections will use the new route.
+    pub fn unpause(&self, ctx: &Context, new_route: Route) -> Result<()> {
+        let mut inlet_shared_state = self.inlet_shared_state.write().unwrap();
+
+        let new_route = new_route + inlet_shared_state.route().recipient()?.clone();
+        inlet_shared_state.update_route(ctx, new_route)?;
+        inlet_shared_state.set_is_paused(false);
+
+        Ok(())
+    }
+
+    /// Stop the Inlet and remove its socket file
+    pub fn stop(&self, ctx: &Context) -> Result<()> {
+        ctx.stop_address(&self.processor_address)
+    }
+}
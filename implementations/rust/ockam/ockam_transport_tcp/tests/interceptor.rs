@@ -1,8 +1,8 @@
-use ockam_core::{async_trait, route, AllowAll};
+use ockam_core::{async_trait, route, AllowAll, LocalInfo};
 use ockam_node::Context;
 use ockam_transport_tcp::{
-    read_portal_payload_length, Direction, PortalInletInterceptor, PortalInterceptor,
-    PortalInterceptorFactory, TcpInletOptions, TcpOutletOptions, TcpTransport,
+    read_portal_payload_length, Direction, InterceptedData, PortalInletInterceptor,
+    PortalInterceptor, PortalInterceptorFactory, TcpInletOptions, TcpOutletOptions, TcpTransport,
 };
 use rand::random;
 use std::sync::{Arc, Mutex};
@@ -16,6 +16,47 @@ struct MockPortalInterceptor {
     from_outlet_to_inlet: Arc<Mutex<Vec<u8>>>,
 }
 
+/// Answer the `ping` requests without forwarding them to the outlet
+struct PingPortalInterceptor;
+
+#[async_trait]
+impl PortalInterceptor for PingPortalInterceptor {
+    async fn intercept(
+        &self,
+        _context: &mut Context,
+        _direction: Direction,
+        buffer: &[u8],
+    ) -> ockam_core::Result<Option<Vec<u8>>> {
+        Ok(Some(buffer.to_vec()))
+    }
+
+    async fn intercept_with_reply(
+        &self,
+        _context: &mut Context,
+        direction: Direction,
+        _local_info: &[LocalInfo],
+        buffer: &[u8],
+    ) -> ockam_core::Result<InterceptedData> {
+        if direction == Direction::FromInletToOutlet && buffer == b"ping" {
+            Ok(InterceptedData {
+                forward: None,
+                reply: Some(b"pong".to_vec()),
+            })
+        } else {
+            Ok(InterceptedData {
+                forward: Some(buffer.to_vec()),
+                reply: None,
+            })
+        }
+    }
+}
+
+impl PortalInterceptorFactory for PingPortalInterceptor {
+    fn create(&self) -> Arc<dyn PortalInterceptor> {
+        Arc::new(PingPortalInterceptor)
+    }
+}
+
 #[async_trait]
 impl PortalInterceptor for MockPortalInterceptor {
     async fn intercept(
@@ -46,6 +87,21 @@ impl PortalInterceptorFactory for MockPortalInterceptorFactory {
 async fn setup(
     context: &mut Context,
 ) -> ockam_core::Result<(String, TcpListener, Arc<MockPortalInterceptor>)> {
+    let mock_portal_interceptor = Arc::new(MockPortalInterceptor::default());
+    let (inlet_addr, listener) = setup_with_factory(
+        context,
+        Arc::new(MockPortalInterceptorFactory {
+            interceptor: mock_portal_interceptor.clone(),
+        }),
+    )
+    .await?;
+    Ok((inlet_addr, listener, mock_portal_interceptor))
+}
+
+async fn setup_with_factory(
+    context: &mut Context,
+    factory: Arc<dyn PortalInterceptorFactory>,
+) -> ockam_core::Result<(String, TcpListener)> {
     let tcp = TcpTransport::create(context)?;
 
     let listener = {
@@ -59,14 +115,10 @@ async fn setup(
         listener
     };
 
-    let mock_portal_interceptor = Arc::new(MockPortalInterceptor::default());
-
     PortalInletInterceptor::create(
         context,
         "interceptor_listener".into(),
-        Arc::new(MockPortalInterceptorFactory {
-            interceptor: mock_portal_interceptor.clone(),
-        }),
+        factory,
         Arc::new(AllowAll),
         Arc::new(AllowAll),
         read_portal_payload_length(),
@@ -81,11 +133,7 @@ async fn setup(
         )
         .await?;
 
-    Ok((
-        inlet.socket_address().to_string(),
-        listener,
-        mock_portal_interceptor,
-    ))
+    Ok((inlet.socket_address().to_string(), listener))
 }
 
 const LENGTH: usize = 32;
@@ -146,3 +194,38 @@ async fn interceptor__simple_payload__received(context: &mut Context) -> ockam_c
 
     Ok(())
 }
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 5_000)]
+async fn interceptor__reply__received_by_sender(context: &mut Context) -> ockam_core::Result<()> {
+    let payload = generate_binary();
+
+    let (inlet_addr, listener) =
+        setup_with_factory(context, Arc::new(PingPortalInterceptor)).await?;
+
+    let handle = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        // the `ping` request is not received by the outlet
+        read_assert_binary(&mut stream, payload).await;
+        write_binary(&mut stream, payload).await;
+
+        stream
+    });
+
+    // Wait till the listener is up
+    tokio::time::sleep(Duration::from_millis(250)).await;
+
+    let mut stream = TcpStream::connect(inlet_addr).await.unwrap();
+    stream.write_all(b"ping").await.unwrap();
+    let mut pong = [0u8; 4];
+    stream.read_exact(&mut pong).await.unwrap();
+    assert_eq!(&pong, b"pong");
+
+    write_binary(&mut stream, payload).await;
+    read_assert_binary(&mut stream, payload).await;
+
+    let res = handle.await;
+    assert!(res.is_ok());
+
+    Ok(())
+}
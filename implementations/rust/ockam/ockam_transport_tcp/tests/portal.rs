@@ -4,11 +4,17 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
 
 use ockam_core::compat::rand::random;
-use ockam_core::{route, Result};
+use ockam_core::compat::sync::Arc;
+use ockam_core::{async_trait, route, LocalInfoIdentifier, Result, Route};
 use ockam_node::Context;
+use ockam_transport_core::HostnamePort;
 use ockam_transport_tcp::{
-    TcpConnectionOptions, TcpInletOptions, TcpListenerOptions, TcpOutletOptions, TcpTransport,
+    TcpConnectionOptions, TcpInletConnectionGuard, TcpInletOptions, TcpInletRouteSelector,
+    TcpListenerOptions, TcpOutletAllowlist, TcpOutletDestinationAuthorizer, TcpOutletOptions,
+    TcpTransport,
 };
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 
 const LENGTH: usize = 32;
 
@@ -307,3 +313,242 @@ async fn portal__update_route__should_succeed(ctx: &mut Context) -> Result<()> {
 
     Ok(())
 }
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 15000)]
+async fn portal__inlet_stream__should_send_replies(ctx: &mut Context) -> Result<()> {
+    let tcp = TcpTransport::create(ctx)?;
+
+    // The server speaks first, its greeting must arrive after the connected reply
+    let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    tcp.create_outlet(
+        "outlet",
+        server.local_addr().unwrap().to_string().try_into().unwrap(),
+        TcpOutletOptions::new(),
+    )?;
+    let handle = tokio::spawn(async move {
+        let (mut stream, _) = server.accept().await.unwrap();
+        stream.write_all(b"greeting").await.unwrap();
+        stream
+    });
+
+    let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mut client = TcpStream::connect(proxy.local_addr().unwrap())
+        .await
+        .unwrap();
+    let (stream, _) = proxy.accept().await.unwrap();
+    tcp.create_inlet_stream(
+        stream,
+        route!["outlet"],
+        b"OK".to_vec(),
+        b"KO".to_vec(),
+        TcpInletOptions::new(),
+    )?
+    .wait_connected(ctx, Duration::from_secs(5))
+    .await?;
+
+    let mut received = [0u8; 10];
+    client.read_exact(&mut received).await.unwrap();
+    assert_eq!(&received, b"OKgreeting");
+    assert!(handle.await.is_ok());
+
+    // The failure reply is sent when the Outlet doesn't answer
+    let mut client = TcpStream::connect(proxy.local_addr().unwrap())
+        .await
+        .unwrap();
+    let (stream, _) = proxy.accept().await.unwrap();
+    let result = tcp
+        .create_inlet_stream(
+            stream,
+            route!["missing_outlet"],
+            b"OK".to_vec(),
+            b"KO".to_vec(),
+            TcpInletOptions::new(),
+        )?
+        .wait_connected(ctx, Duration::from_millis(500))
+        .await;
+    assert!(result.is_err());
+
+    let mut received = vec![];
+    client.read_to_end(&mut received).await.unwrap();
+    assert_eq!(received, b"KO");
+
+    Ok(())
+}
+
+#[derive(Debug)]
+struct DenyPort(u16);
+
+#[async_trait]
+impl TcpOutletDestinationAuthorizer for DenyPort {
+    async fn is_authorized(
+        &self,
+        _their_identifier: Option<&LocalInfoIdentifier>,
+        destination: &HostnamePort,
+    ) -> Result<bool> {
+        Ok(destination.port() != self.0)
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 15000)]
+async fn portal__dynamic_outlet__should_connect_to_allowed_destinations(
+    ctx: &mut Context,
+) -> Result<()> {
+    let tcp = TcpTransport::create(ctx)?;
+
+    let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let server_port = server.local_addr().unwrap().port();
+    let denied_server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let denied_port = denied_server.local_addr().unwrap().port();
+
+    tcp.create_dynamic_outlet(
+        "outlet",
+        TcpOutletAllowlist::new(&["127.0.0.0/8"])?,
+        TcpOutletOptions::new().with_destination_authorizer(Arc::new(DenyPort(denied_port))),
+    )?;
+    let handle = tokio::spawn(async move {
+        let (mut stream, _) = server.accept().await.unwrap();
+        stream.write_all(b"greeting").await.unwrap();
+        stream
+    });
+
+    let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mut client = TcpStream::connect(proxy.local_addr().unwrap())
+        .await
+        .unwrap();
+    let (stream, _) = proxy.accept().await.unwrap();
+    tcp.create_inlet_stream(
+        stream,
+        route!["outlet"],
+        b"OK".to_vec(),
+        b"KO".to_vec(),
+        TcpInletOptions::new().with_outlet_destination(HostnamePort::localhost(server_port)),
+    )?
+    .wait_connected(ctx, Duration::from_secs(5))
+    .await?;
+
+    let mut received = [0u8; 10];
+    client.read_exact(&mut received).await.unwrap();
+    assert_eq!(&received, b"OKgreeting");
+    assert!(handle.await.is_ok());
+
+    // Destinations refused by the allowlist or by the authorizer are disconnected
+    for destination in [
+        HostnamePort::new("10.0.0.1", server_port)?,
+        HostnamePort::localhost(denied_port),
+    ] {
+        let mut client = TcpStream::connect(proxy.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (stream, _) = proxy.accept().await.unwrap();
+        let result = tcp
+            .create_inlet_stream(
+                stream,
+                route!["outlet"],
+                b"OK".to_vec(),
+                b"KO".to_vec(),
+                TcpInletOptions::new().with_outlet_destination(destination),
+            )?
+            .wait_connected(ctx, Duration::from_secs(5))
+            .await;
+        assert!(result.is_err());
+
+        let mut received = vec![];
+        client.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received, b"KO");
+    }
+
+    Ok(())
+}
+
+/// Use the routes in turn, and count the open connections
+#[derive(Debug, Default)]
+struct RoundRobin {
+    routes: Mutex<Vec<Route>>,
+    next: AtomicUsize,
+    open_connections: Arc<AtomicUsize>,
+}
+
+struct OpenConnection(Arc<AtomicUsize>);
+
+impl Drop for OpenConnection {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl TcpInletRouteSelector for RoundRobin {
+    fn select_route(&self) -> Option<(Route, TcpInletConnectionGuard)> {
+        let routes = self.routes.lock().unwrap();
+        if routes.is_empty() {
+            return None;
+        }
+        let route = routes[self.next.fetch_add(1, Ordering::SeqCst) % routes.len()].clone();
+        self.open_connections.fetch_add(1, Ordering::SeqCst);
+        Some((
+            route,
+            Box::new(OpenConnection(self.open_connections.clone())),
+        ))
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 15000)]
+async fn portal__route_selector__should_choose_the_outlet_of_each_connection(
+    ctx: &mut Context,
+) -> Result<()> {
+    let tcp = TcpTransport::create(ctx)?;
+
+    let mut routes = vec![];
+    for name in ["outlet1", "outlet2"] {
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_address = server.local_addr().unwrap();
+        tcp.create_outlet(name, server_address.into(), TcpOutletOptions::new())?;
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = server.accept().await.unwrap();
+                stream.write_all(name.as_bytes()).await.unwrap();
+                tokio::spawn(async move {
+                    let mut buf = vec![];
+                    let _ = stream.read_to_end(&mut buf).await;
+                });
+            }
+        });
+        routes.push(route![name]);
+    }
+
+    let selector = Arc::new(RoundRobin::default());
+    let inlet = tcp
+        .create_inlet_with_route_selector("127.0.0.1:0", selector.clone(), TcpInletOptions::new())
+        .await?;
+
+    // Without any route, the connections are dropped
+    let mut client = TcpStream::connect(inlet.socket_address()).await.unwrap();
+    let mut received = vec![];
+    client.read_to_end(&mut received).await.unwrap();
+    assert!(received.is_empty());
+
+    *selector.routes.lock().unwrap() = routes;
+    let mut clients = vec![];
+    for expected in [b"outlet1", b"outlet2", b"outlet1"] {
+        let mut client = TcpStream::connect(inlet.socket_address()).await.unwrap();
+        let mut received = [0u8; 7];
+        client.read_exact(&mut received).await.unwrap();
+        assert_eq!(&received, expected);
+        clients.push(client);
+    }
+    assert_eq!(selector.open_connections.load(Ordering::SeqCst), 3);
+
+    // The guards are dropped when the connections are closed
+    drop(clients);
+    for _ in 0..50 {
+        if selector.open_connections.load(Ordering::SeqCst) == 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(selector.open_connections.load(Ordering::SeqCst), 0);
+
+    Ok(())
+}
@@ -0,0 +1,103 @@
+#![cfg(unix)]
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, UnixListener, UnixStream};
+
+use ockam_core::compat::rand::random;
+use ockam_core::{route, Result};
+use ockam_node::Context;
+use ockam_transport_tcp::{TcpInletOptions, TcpOutletOptions, TcpTransport};
+
+const LENGTH: usize = 32;
+
+fn socket_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("ockam-{name}-{}.sock", random::<u32>()))
+}
+
+async fn echo<S: AsyncReadExt + AsyncWriteExt + Unpin>(mut stream: S) {
+    let mut payload = [0u8; LENGTH];
+    stream.read_exact(&mut payload).await.unwrap();
+    stream.write_all(&payload).await.unwrap();
+}
+
+async fn assert_echo(stream: &mut UnixStream) {
+    let payload: [u8; LENGTH] = random();
+    stream.write_all(&payload).await.unwrap();
+    let mut received = [0u8; LENGTH];
+    stream.read_exact(&mut received).await.unwrap();
+    assert_eq!(received, payload);
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 5000)]
+async fn unix_portal__unix_outlet__should_succeed(ctx: &mut Context) -> Result<()> {
+    let tcp = TcpTransport::create(ctx)?;
+    let server_path = socket_path("server");
+    let inlet_path = socket_path("inlet");
+
+    let listener = UnixListener::bind(&server_path).unwrap();
+    let handle = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        echo(stream).await;
+    });
+
+    tcp.create_unix_outlet("outlet", &server_path, TcpOutletOptions::new())?;
+    let inlet = tcp
+        .create_unix_inlet(
+            &inlet_path,
+            route!["outlet"],
+            TcpInletOptions::new().with_unix_socket_mode(0o600),
+        )
+        .await?;
+
+    let mode = std::fs::metadata(&inlet_path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let mut stream = UnixStream::connect(&inlet_path).await.unwrap();
+    assert_echo(&mut stream).await;
+    assert!(handle.await.is_ok());
+
+    // The socket file is removed when the inlet is stopped
+    inlet.stop(ctx)?;
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    assert!(!inlet_path.exists());
+
+    let _ = std::fs::remove_file(&server_path);
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 5000)]
+async fn unix_portal__tcp_outlet__should_succeed(ctx: &mut Context) -> Result<()> {
+    let tcp = TcpTransport::create(ctx)?;
+    let inlet_path = socket_path("inlet");
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let bind_address = listener.local_addr().unwrap().to_string();
+    let handle = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        echo(stream).await;
+    });
+
+    tcp.create_outlet(
+        "outlet",
+        bind_address.try_into().unwrap(),
+        TcpOutletOptions::new(),
+    )?;
+    // A socket file left over by a previous inlet is replaced
+    drop(std::os::unix::net::UnixListener::bind(&inlet_path).unwrap());
+    let inlet = tcp
+        .create_unix_inlet(&inlet_path, route!["outlet"], TcpInletOptions::new())
+        .await?;
+
+    let mut stream = UnixStream::connect(&inlet_path).await.unwrap();
+    assert_echo(&mut stream).await;
+    assert!(handle.await.is_ok());
+
+    inlet.stop(ctx)?;
+    Ok(())
+}
@@ -19,6 +19,7 @@ extern crate core;
 mod error;
 mod messages;
 mod options;
+mod portal;
 mod puncture;
 mod size_options;
 mod transport;
@@ -26,6 +27,7 @@ mod workers;
 
 pub use error::*;
 pub use options::UdpBindOptions;
+pub use portal::*;
 pub use puncture::*;
 pub use size_options::*;
 pub use transport::{UdpBind, UdpBindArguments, UdpTransport, UdpTransportExtension};
@@ -0,0 +1,27 @@
+use ockam_core::Address;
+
+/// Addresses used by the worker handling one flow of a UDP Portal
+#[derive(Clone, Debug)]
+pub(crate) struct Addresses {
+    /// Used to receive messages from the other side of the portal
+    pub(crate) remote: Address,
+    /// Used to receive the datagrams read from the local socket
+    pub(crate) internal: Address,
+    /// Used to receive the idle timeout checks
+    pub(crate) heartbeat: Address,
+    /// Used by the processor reading the local socket of an outlet flow
+    pub(crate) receiver: Address,
+}
+
+impl Addresses {
+    pub(crate) fn generate(portal_type: &str) -> Self {
+        Self {
+            remote: Address::random_tagged(&format!("UdpPortalWorker.{portal_type}.remote")),
+            internal: Address::random_tagged(&format!("UdpPortalWorker.{portal_type}.internal")),
+            heartbeat: Address::random_tagged(&format!("UdpPortalWorker.{portal_type}.heartbeat")),
+            receiver: Address::random_tagged(&format!(
+                "UdpPortalRecvProcessor.{portal_type}.receiver"
+            )),
+        }
+    }
+}
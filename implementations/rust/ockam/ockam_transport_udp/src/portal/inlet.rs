@@ -0,0 +1,216 @@
+use crate::portal::addresses::Addresses;
+use crate::portal::portal_worker::{UdpInletFlows, UdpPortalWorker};
+use crate::portal::receiver::MAX_DATAGRAM_SIZE;
+use crate::UdpInletOptions;
+use core::fmt;
+use core::fmt::Formatter;
+use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::{
+    async_trait, route, Address, AllowAll, DenyAll, NeutralMessage, Processor, Result, Route,
+};
+use ockam_node::{Context, ProcessorBuilder};
+use ockam_transport_core::TransportError;
+use std::net::SocketAddr;
+use tokio::net::UdpSocket;
+use tracing::{debug, error, instrument, warn};
+
+/// State shared between a [`UdpInlet`] and the processor reading its socket
+#[derive(Clone, Debug)]
+struct UdpInletSharedState {
+    route: Route,
+    is_paused: bool,
+}
+
+/// A UDP Inlet, created by [`UdpInlet::create`].
+///
+/// Each client address sending datagrams to the inlet socket is handled by a dedicated flow,
+/// relayed to a dedicated flow on the outlet side. Flows are closed after an idle timeout.
+#[derive(Clone, Debug)]
+pub struct UdpInlet {
+    socket_address: SocketAddr,
+    processor_address: Address,
+    shared_state: Arc<RwLock<UdpInletSharedState>>,
+}
+
+impl fmt::Display for UdpInlet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Socket: {}. Processor address: {}",
+            self.socket_address, self.processor_address
+        )
+    }
+}
+
+impl UdpInlet {
+    /// Bind a UDP socket and relay the datagrams it receives to the UDP Outlet at the end of
+    /// `outlet_route`. The datagrams sent back by the outlet are sent to the clients from the
+    /// same socket.
+    #[instrument(skip_all, fields(bind_address = %bind_address, outlet_route = %outlet_route))]
+    pub async fn create(
+        ctx: &Context,
+        bind_address: SocketAddr,
+        outlet_route: Route,
+        options: UdpInletOptions,
+    ) -> Result<Self> {
+        let processor_address = Address::random_tagged("UdpInletListenProcessor");
+
+        debug!("Binding UdpInletListenProcessor to {}", bind_address);
+        let socket = match UdpSocket::bind(bind_address).await {
+            Ok(socket) => socket,
+            Err(err) => {
+                error!(%bind_address, %err, "could not bind to address");
+                return Err(TransportError::from(err))?;
+            }
+        };
+        let socket_address = socket.local_addr().map_err(TransportError::from)?;
+
+        let shared_state = Arc::new(RwLock::new(UdpInletSharedState {
+            route: outlet_route,
+            is_paused: options.is_paused,
+        }));
+
+        let processor = UdpInletListenProcessor {
+            processor_address: processor_address.clone(),
+            socket: Arc::new(socket),
+            flows: Default::default(),
+            shared_state: shared_state.clone(),
+            options,
+            buffer: vec![0; MAX_DATAGRAM_SIZE],
+        };
+
+        ProcessorBuilder::new(processor)
+            .with_address(processor_address.clone())
+            .with_incoming_access_control(DenyAll)
+            .with_outgoing_access_control(AllowAll)
+            .start(ctx)?;
+
+        Ok(Self {
+            socket_address,
+            processor_address,
+            shared_state,
+        })
+    }
+
+    /// Socket Address
+    pub fn socket_address(&self) -> SocketAddr {
+        self.socket_address
+    }
+
+    /// Processor address
+    pub fn processor_address(&self) -> &Address {
+        &self.processor_address
+    }
+
+    /// Pause the UDP Inlet, all incoming datagrams will be dropped.
+    pub fn pause(&self) {
+        debug!(address = %self.socket_address, "pausing udp inlet");
+        self.shared_state.write().unwrap().is_paused = true;
+    }
+
+    /// Unpause the UDP Inlet and update the route to the outlet node.
+    ///  NOTE: existing flows will still use the old route until they are closed,
+    ///        only new flows will use the new route.
+    pub fn unpause(&self, new_route: Route) -> Result<()> {
+        let mut shared_state = self.shared_state.write().unwrap();
+        shared_state.route = new_route + shared_state.route.recipient()?.clone();
+        shared_state.is_paused = false;
+        Ok(())
+    }
+
+    /// Stop the Inlet and all its flows
+    pub fn stop(&self, ctx: &Context) -> Result<()> {
+        ctx.stop_address(&self.processor_address)
+    }
+}
+
+/// A processor reading the datagrams received by a UDP Inlet and dispatching them to the
+/// worker of their flow
+struct UdpInletListenProcessor {
+    processor_address: Address,
+    socket: Arc<UdpSocket>,
+    flows: UdpInletFlows,
+    shared_state: Arc<RwLock<UdpInletSharedState>>,
+    options: UdpInletOptions,
+    buffer: Vec<u8>,
+}
+
+impl UdpInletListenProcessor {
+    /// Return the worker address of the flow of a client, start a new flow if necessary
+    fn flow_address(&self, ctx: &Context, client: SocketAddr) -> Result<Option<Address>> {
+        if let Some(address) = self.flows.lock().unwrap().get(&client) {
+            return Ok(Some(address.clone()));
+        }
+
+        let shared_state = self.shared_state.read().unwrap().clone();
+        if shared_state.is_paused {
+            return Ok(None);
+        }
+        if self.flows.lock().unwrap().len() >= self.options.max_flows {
+            warn!(%client, "dropping a datagram, the maximum number of udp flows is reached");
+            return Ok(None);
+        }
+
+        let addresses = Addresses::generate("inlet");
+        UdpInletOptions::setup_flow_control(
+            ctx.flow_controls(),
+            &addresses,
+            shared_state.route.next()?,
+        );
+        self.flows
+            .lock()
+            .unwrap()
+            .insert(client, addresses.internal.clone());
+
+        let internal = addresses.internal.clone();
+        UdpPortalWorker::start_new_inlet(
+            ctx,
+            self.socket.clone(),
+            client,
+            self.flows.clone(),
+            shared_state.route,
+            addresses,
+            self.options.incoming_access_control.clone(),
+            self.options.outgoing_access_control.clone(),
+            self.options.idle_timeout,
+            self.processor_address.clone(),
+        )?;
+        debug!(%client, "started a new udp flow");
+
+        Ok(Some(internal))
+    }
+}
+
+#[async_trait]
+impl Processor for UdpInletListenProcessor {
+    type Context = Context;
+
+    #[instrument(skip_all, name = "UdpInletListenProcessor::shutdown")]
+    async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        let flows: Vec<Address> = self.flows.lock().unwrap().values().cloned().collect();
+        for flow in flows {
+            _ = ctx.stop_address(&flow);
+        }
+        Ok(())
+    }
+
+    #[instrument(skip_all, name = "UdpInletListenProcessor::process")]
+    async fn process(&mut self, ctx: &mut Self::Context) -> Result<bool> {
+        let (len, client) = match self.socket.recv_from(&mut self.buffer).await {
+            Ok(received) => received,
+            // an ICMP error received for a datagram previously sent to a client
+            Err(err) if err.kind() == std::io::ErrorKind::ConnectionReset => return Ok(true),
+            Err(err) => return Err(TransportError::from(err))?,
+        };
+
+        if let Some(flow_address) = self.flow_address(ctx, client)? {
+            ctx.send(
+                route![flow_address],
+                NeutralMessage::from(self.buffer[..len].to_vec()),
+            )
+            .await?;
+        }
+
+        Ok(true)
+    }
+}
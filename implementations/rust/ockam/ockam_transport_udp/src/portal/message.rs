@@ -0,0 +1,30 @@
+use minicbor::{CborLen, Decode, Encode};
+use ockam_core::{Decodable, Encodable, Message, Result};
+
+/// A command message type for a UDP Portal
+#[derive(Encode, Decode, CborLen, Debug, Clone, PartialEq, Eq)]
+#[rustfmt::skip]
+pub enum UdpPortalMessage {
+    /// First message that the Inlet sends to the Outlet for a new flow
+    #[n(0)] Ping,
+    /// First message that the Outlet sends to the Inlet for a new flow
+    #[n(1)] Pong,
+    /// Message to indicate that the flow was closed, usually after an idle timeout
+    #[n(2)] Disconnect,
+    /// A single datagram
+    #[n(3)] Datagram(#[cbor(with = "minicbor::bytes")] #[n(0)] Vec<u8>),
+}
+
+impl Encodable for UdpPortalMessage {
+    fn encode(self) -> Result<Vec<u8>> {
+        ockam_core::cbor_encode_preallocate(self)
+    }
+}
+
+impl Decodable for UdpPortalMessage {
+    fn decode(data: &[u8]) -> Result<Self> {
+        Ok(minicbor::decode(data)?)
+    }
+}
+
+impl Message for UdpPortalMessage {}
@@ -0,0 +1,12 @@
+mod addresses;
+mod inlet;
+mod message;
+mod options;
+mod outlet;
+mod portal_worker;
+mod receiver;
+
+pub use inlet::*;
+pub use message::*;
+pub use options::*;
+pub use outlet::*;
@@ -0,0 +1,211 @@
+use crate::portal::addresses::Addresses;
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::time::Duration;
+use ockam_core::flow_control::{FlowControlId, FlowControls};
+use ockam_core::{Address, AllowAll, IncomingAccessControl, OutgoingAccessControl};
+
+/// A flow is closed when no datagram was sent in either direction for this duration
+pub const DEFAULT_UDP_PORTAL_IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Maximum number of concurrent flows of a UDP Inlet
+pub const DEFAULT_UDP_INLET_MAX_FLOWS: usize = 1024;
+
+/// Options for a UDP Inlet
+#[derive(Clone, Debug)]
+pub struct UdpInletOptions {
+    pub(crate) incoming_access_control: Arc<dyn IncomingAccessControl>,
+    pub(crate) outgoing_access_control: Arc<dyn OutgoingAccessControl>,
+    pub(crate) is_paused: bool,
+    pub(crate) idle_timeout: Duration,
+    pub(crate) max_flows: usize,
+}
+
+impl UdpInletOptions {
+    /// Default constructor without Incoming Access Control
+    pub fn new() -> Self {
+        Self {
+            incoming_access_control: Arc::new(AllowAll),
+            outgoing_access_control: Arc::new(AllowAll),
+            is_paused: false,
+            idle_timeout: DEFAULT_UDP_PORTAL_IDLE_TIMEOUT,
+            max_flows: DEFAULT_UDP_INLET_MAX_FLOWS,
+        }
+    }
+
+    /// Set the UDP Inlet to paused mode after start. Datagrams are dropped until
+    /// [`UdpInlet::unpause`](crate::UdpInlet::unpause) is called
+    pub fn paused(mut self) -> Self {
+        self.is_paused = true;
+        self
+    }
+
+    /// Set the duration after which an inactive flow is closed
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Set the maximum number of concurrent flows.
+    /// Datagrams from new client addresses are dropped when the limit is reached
+    pub fn with_max_flows(mut self, max_flows: usize) -> Self {
+        self.max_flows = max_flows;
+        self
+    }
+
+    /// Set Incoming Access Control
+    pub fn with_incoming_access_control_impl(
+        mut self,
+        access_control: impl IncomingAccessControl,
+    ) -> Self {
+        self.incoming_access_control = Arc::new(access_control);
+        self
+    }
+
+    /// Set Incoming Access Control
+    pub fn with_incoming_access_control(
+        mut self,
+        access_control: Arc<dyn IncomingAccessControl>,
+    ) -> Self {
+        self.incoming_access_control = access_control;
+        self
+    }
+
+    /// Set Outgoing Access Control
+    pub fn with_outgoing_access_control_impl(
+        mut self,
+        access_control: impl OutgoingAccessControl,
+    ) -> Self {
+        self.outgoing_access_control = Arc::new(access_control);
+        self
+    }
+
+    /// Set Outgoing Access Control
+    pub fn with_outgoing_access_control(
+        mut self,
+        access_control: Arc<dyn OutgoingAccessControl>,
+    ) -> Self {
+        self.outgoing_access_control = access_control;
+        self
+    }
+
+    pub(crate) fn setup_flow_control(
+        flow_controls: &FlowControls,
+        addresses: &Addresses,
+        next: &Address,
+    ) {
+        if let Some(flow_control_id) = flow_controls
+            .find_flow_control_with_producer_address(next)
+            .map(|x| x.flow_control_id().clone())
+        {
+            // Allow a sender with corresponding flow_control_id send messages to this address
+            flow_controls.add_consumer(&addresses.remote, &flow_control_id);
+        }
+    }
+}
+
+impl Default for UdpInletOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Options for a UDP Outlet
+#[derive(Clone, Debug)]
+pub struct UdpOutletOptions {
+    pub(crate) consumer: Vec<FlowControlId>,
+    pub(crate) incoming_access_control: Arc<dyn IncomingAccessControl>,
+    pub(crate) outgoing_access_control: Arc<dyn OutgoingAccessControl>,
+    pub(crate) idle_timeout: Duration,
+}
+
+impl UdpOutletOptions {
+    /// Default constructor without Incoming Access Control
+    pub fn new() -> Self {
+        Self {
+            consumer: vec![],
+            incoming_access_control: Arc::new(AllowAll),
+            outgoing_access_control: Arc::new(AllowAll),
+            idle_timeout: DEFAULT_UDP_PORTAL_IDLE_TIMEOUT,
+        }
+    }
+
+    /// Set the duration after which an inactive flow is closed
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Set Incoming Access Control
+    pub fn with_incoming_access_control_impl(
+        mut self,
+        access_control: impl IncomingAccessControl,
+    ) -> Self {
+        self.incoming_access_control = Arc::new(access_control);
+        self
+    }
+
+    /// Set Incoming Access Control
+    pub fn with_incoming_access_control(
+        mut self,
+        access_control: Arc<dyn IncomingAccessControl>,
+    ) -> Self {
+        self.incoming_access_control = access_control;
+        self
+    }
+
+    /// Set Outgoing Access Control
+    pub fn with_outgoing_access_control_impl(
+        mut self,
+        access_control: impl OutgoingAccessControl,
+    ) -> Self {
+        self.outgoing_access_control = Arc::new(access_control);
+        self
+    }
+
+    /// Set Outgoing Access Control
+    pub fn with_outgoing_access_control(
+        mut self,
+        access_control: Arc<dyn OutgoingAccessControl>,
+    ) -> Self {
+        self.outgoing_access_control = access_control;
+        self
+    }
+
+    /// Mark that this Outlet listener is a Consumer for to the given [`FlowControlId`]
+    /// Also, in this case spawned flow workers will be marked as Consumers with [`FlowControlId`]
+    /// of the message that was used to create them
+    pub fn as_consumer(mut self, id: &FlowControlId) -> Self {
+        self.consumer.push(id.clone());
+
+        self
+    }
+
+    pub(crate) fn setup_flow_control_for_outlet_listener(
+        &self,
+        flow_controls: &FlowControls,
+        address: &Address,
+    ) {
+        for id in &self.consumer {
+            flow_controls.add_consumer(address, id);
+        }
+    }
+
+    pub(crate) fn setup_flow_control_for_outlet(
+        flow_controls: &FlowControls,
+        addresses: &Addresses,
+        src_addr: &Address,
+    ) {
+        // Check if the Worker that send us this message is a Producer
+        // If yes - the flow worker will be added to that flow control to be able to receive
+        // further messages from that Producer
+        if let Some(producer_info) = flow_controls.get_flow_control_with_producer(src_addr) {
+            flow_controls.add_consumer(&addresses.remote, producer_info.flow_control_id());
+        }
+    }
+}
+
+impl Default for UdpOutletOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
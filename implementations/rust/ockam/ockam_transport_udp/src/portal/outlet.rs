@@ -0,0 +1,108 @@
+use crate::portal::addresses::Addresses;
+use crate::portal::portal_worker::UdpPortalWorker;
+use crate::{UdpOutletOptions, UdpPortalMessage};
+use ockam_core::{
+    async_trait, Address, Decodable, DenyAll, Result, Routed, SecureChannelLocalInfo, Worker,
+};
+use ockam_node::{Context, WorkerBuilder};
+use ockam_transport_core::{HostnamePort, TransportError};
+use tracing::{debug, instrument};
+
+/// A UDP Outlet, created by [`UdpOutlet::create`].
+///
+/// The outlet starts a new flow, with its own socket connected to the target,
+/// for each flow started by an inlet.
+#[derive(Clone, Debug)]
+pub struct UdpOutlet {
+    address: Address,
+    peer: HostnamePort,
+}
+
+impl UdpOutlet {
+    /// Start a UDP Outlet listener at `address`, relaying the datagrams of the inlets to `peer`
+    #[instrument(skip_all, fields(address = %address, peer = %peer))]
+    pub fn create(
+        ctx: &Context,
+        address: Address,
+        peer: HostnamePort,
+        options: UdpOutletOptions,
+    ) -> Result<Self> {
+        let access_control = options.incoming_access_control.clone();
+        options.setup_flow_control_for_outlet_listener(ctx.flow_controls(), &address);
+
+        let worker = UdpOutletListenWorker {
+            peer: peer.clone(),
+            options,
+        };
+        WorkerBuilder::new(worker)
+            .with_address(address.clone())
+            .with_incoming_access_control_arc(access_control)
+            .with_outgoing_access_control(DenyAll)
+            .start(ctx)?;
+
+        Ok(Self { address, peer })
+    }
+
+    /// Address of the outlet listener
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Target of the outlet
+    pub fn peer(&self) -> &HostnamePort {
+        &self.peer
+    }
+
+    /// Stop the outlet listener. Existing flows are closed after their idle timeout
+    pub fn stop(&self, ctx: &Context) -> Result<()> {
+        ctx.stop_address(&self.address)
+    }
+}
+
+/// A UDP Portal Outlet listen worker, starting a flow worker for each ping
+struct UdpOutletListenWorker {
+    peer: HostnamePort,
+    options: UdpOutletOptions,
+}
+
+#[async_trait]
+impl Worker for UdpOutletListenWorker {
+    type Context = Context;
+    type Message = UdpPortalMessage;
+
+    #[instrument(skip_all, name = "UdpOutletListenWorker::handle_message")]
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Self::Context,
+        msg: Routed<Self::Message>,
+    ) -> Result<()> {
+        let their_identifier = SecureChannelLocalInfo::find_info(msg.local_message())
+            .map(|l| l.their_identifier())
+            .ok();
+        let src_addr = msg.src_addr();
+        let msg = msg.into_local_message();
+        let return_route = msg.return_route;
+
+        if UdpPortalMessage::decode(&msg.payload)? != UdpPortalMessage::Ping {
+            return Err(TransportError::Protocol)?;
+        }
+
+        let addresses = Addresses::generate("outlet");
+        UdpOutletOptions::setup_flow_control_for_outlet(ctx.flow_controls(), &addresses, &src_addr);
+
+        UdpPortalWorker::start_new_outlet(
+            ctx,
+            self.peer.clone(),
+            return_route,
+            their_identifier,
+            addresses.clone(),
+            self.options.incoming_access_control.clone(),
+            self.options.outgoing_access_control.clone(),
+            self.options.idle_timeout,
+        )?;
+
+        debug!("Created a UDP Outlet flow at {}", addresses.remote);
+
+        Ok(())
+    }
+}
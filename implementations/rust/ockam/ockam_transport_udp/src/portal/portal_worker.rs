@@ -0,0 +1,438 @@
+use crate::portal::addresses::Addresses;
+use crate::portal::receiver::UdpPortalRecvProcessor;
+use crate::portal::UdpPortalMessage;
+use ockam_core::compat::collections::{HashMap, VecDeque};
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::compat::time::Duration;
+use ockam_core::{
+    async_trait, Address, AllowOnwardAddress, AllowSourceAddress, Any, Decodable, DenyAll,
+    IncomingAccessControl, LocalInfoIdentifier, Mailbox, Mailboxes, OutgoingAccessControl, Result,
+    Route, Routed, SecureChannelLocalInfo, Worker,
+};
+use ockam_node::compat::asynchronous::resolve_peer;
+use ockam_node::{Context, DelayedEvent, ProcessorBuilder, WorkerBuilder};
+use ockam_transport_core::{HostnamePort, TransportError};
+use std::net::SocketAddr;
+use std::time::Instant;
+use tokio::net::UdpSocket;
+use tracing::{debug, instrument, trace, warn};
+
+/// Maximum number of datagrams kept while an inlet flow waits for the pong of the outlet
+const MAX_PENDING_DATAGRAMS: usize = 32;
+
+/// Flows of a UDP Inlet, indexed by the address of their client
+pub(crate) type UdpInletFlows = Arc<Mutex<HashMap<SocketAddr, Address>>>;
+
+/// Enumerate all `UdpPortalWorker` states
+///
+/// Possible state transitions are:
+///
+/// `Outlet`: `SendPong` -> `Initialized`
+/// `Inlet`: `SendPing` -> `ReceivePong` -> `Initialized`
+#[derive(Clone)]
+enum State {
+    SendPing { ping_route: Route },
+    SendPong { pong_route: Route },
+    ReceivePong,
+    Initialized,
+}
+
+/// Local end of a flow
+enum LocalSocket {
+    /// The socket of the inlet, shared by all its flows, and the client of this flow
+    Inlet {
+        socket: Arc<UdpSocket>,
+        client: SocketAddr,
+        flows: UdpInletFlows,
+    },
+    /// A socket connected to the target of the outlet, created when the flow starts
+    Outlet {
+        peer: HostnamePort,
+        socket: Option<Arc<UdpSocket>>,
+    },
+}
+
+/// A UDP Portal worker
+///
+/// A UDP Portal worker relays the datagrams of one flow, identified by the address
+/// of a client of the inlet. It is created by the inlet listener when a datagram is received
+/// from a new client, and by the outlet listener when it receives the ping of a new flow.
+/// The flow is closed on both sides when no datagram was relayed for the idle timeout.
+pub(crate) struct UdpPortalWorker {
+    state: State,
+    local: LocalSocket,
+    addresses: Addresses,
+    portal_type: &'static str,
+    their_identifier: Option<LocalInfoIdentifier>,
+    remote_route: Option<Route>,
+    pending_datagrams: VecDeque<Vec<u8>>,
+    heartbeat: DelayedEvent<()>,
+    idle_timeout: Duration,
+    last_activity: Instant,
+}
+
+impl UdpPortalWorker {
+    /// Start a new `UdpPortalWorker` for a new client of an inlet
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn start_new_inlet(
+        ctx: &Context,
+        socket: Arc<UdpSocket>,
+        client: SocketAddr,
+        flows: UdpInletFlows,
+        ping_route: Route,
+        addresses: Addresses,
+        incoming_access_control: Arc<dyn IncomingAccessControl>,
+        outgoing_access_control: Arc<dyn OutgoingAccessControl>,
+        idle_timeout: Duration,
+        listener_address: Address,
+    ) -> Result<()> {
+        Self::start(
+            ctx,
+            State::SendPing { ping_route },
+            LocalSocket::Inlet {
+                socket,
+                client,
+                flows,
+            },
+            None,
+            addresses,
+            incoming_access_control,
+            outgoing_access_control,
+            idle_timeout,
+            listener_address,
+        )
+    }
+
+    /// Start a new `UdpPortalWorker` for a flow created by an inlet
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn start_new_outlet(
+        ctx: &Context,
+        peer: HostnamePort,
+        pong_route: Route,
+        their_identifier: Option<LocalInfoIdentifier>,
+        addresses: Addresses,
+        incoming_access_control: Arc<dyn IncomingAccessControl>,
+        outgoing_access_control: Arc<dyn OutgoingAccessControl>,
+        idle_timeout: Duration,
+    ) -> Result<()> {
+        let receiver = addresses.receiver.clone();
+        Self::start(
+            ctx,
+            State::SendPong { pong_route },
+            LocalSocket::Outlet { peer, socket: None },
+            their_identifier,
+            addresses,
+            incoming_access_control,
+            outgoing_access_control,
+            idle_timeout,
+            receiver,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn start(
+        ctx: &Context,
+        state: State,
+        local: LocalSocket,
+        their_identifier: Option<LocalInfoIdentifier>,
+        addresses: Addresses,
+        incoming_access_control: Arc<dyn IncomingAccessControl>,
+        outgoing_access_control: Arc<dyn OutgoingAccessControl>,
+        idle_timeout: Duration,
+        local_source: Address,
+    ) -> Result<()> {
+        let portal_type = match local {
+            LocalSocket::Inlet { .. } => "inlet",
+            LocalSocket::Outlet { .. } => "outlet",
+        };
+        debug!(%portal_type, remote=%addresses.remote, "creating udp portal worker");
+
+        let heartbeat = DelayedEvent::create(ctx, addresses.heartbeat.clone(), ())?;
+
+        let remote_mailbox = Mailbox::new(
+            addresses.remote.clone(),
+            None,
+            incoming_access_control,
+            outgoing_access_control,
+        );
+
+        let internal_mailbox = Mailbox::new(
+            addresses.internal.clone(),
+            None,
+            Arc::new(AllowSourceAddress(local_source)),
+            Arc::new(DenyAll),
+        );
+
+        let heartbeat_mailbox = Mailbox::new(
+            addresses.heartbeat.clone(),
+            None,
+            Arc::new(AllowSourceAddress(heartbeat.address().clone())),
+            Arc::new(DenyAll),
+        );
+
+        let worker = Self {
+            state,
+            local,
+            addresses,
+            portal_type,
+            their_identifier,
+            remote_route: None,
+            pending_datagrams: VecDeque::new(),
+            heartbeat,
+            idle_timeout,
+            last_activity: Instant::now(),
+        };
+
+        WorkerBuilder::new(worker)
+            .with_mailboxes(Mailboxes::new(
+                remote_mailbox,
+                vec![internal_mailbox, heartbeat_mailbox],
+            ))
+            .start(ctx)?;
+
+        Ok(())
+    }
+}
+
+impl UdpPortalWorker {
+    #[instrument(skip_all)]
+    async fn handle_send_ping(&self, ctx: &Context, ping_route: Route) -> Result<State> {
+        // Force the creation of the flow on the outlet side
+        ctx.send_from_address(
+            ping_route,
+            UdpPortalMessage::Ping,
+            self.addresses.remote.clone(),
+        )
+        .await?;
+
+        debug!(portal_type = %self.portal_type, remote = %self.addresses.remote, "sent ping");
+
+        Ok(State::ReceivePong)
+    }
+
+    #[instrument(skip_all)]
+    async fn handle_send_pong(&mut self, ctx: &Context, pong_route: Route) -> Result<State> {
+        let peer = match &self.local {
+            LocalSocket::Outlet { peer, .. } => peer.clone(),
+            LocalSocket::Inlet { .. } => return Err(TransportError::PortalInvalidState)?,
+        };
+        let peer_address = resolve_peer(&peer).await?;
+        let bind_address = if peer_address.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(bind_address)
+            .await
+            .map_err(TransportError::from)?;
+        socket
+            .connect(peer_address)
+            .await
+            .map_err(TransportError::from)?;
+        let socket = Arc::new(socket);
+        debug!(portal_type = %self.portal_type, remote = %self.addresses.remote, "connected to {}", peer);
+
+        // Respond to the Inlet before starting the processor
+        // to avoid a datagram being sent before the pong
+        ctx.send_from_address(
+            pong_route.clone(),
+            UdpPortalMessage::Pong,
+            self.addresses.remote.clone(),
+        )
+        .await?;
+
+        let receiver = UdpPortalRecvProcessor::new(socket.clone(), self.addresses.internal.clone());
+        let receiver_mailbox = Mailbox::new(
+            self.addresses.receiver.clone(),
+            None,
+            Arc::new(DenyAll),
+            Arc::new(AllowOnwardAddress(self.addresses.internal.clone())),
+        );
+        ProcessorBuilder::new(receiver)
+            .with_mailboxes(Mailboxes::new(receiver_mailbox, vec![]))
+            .start(ctx)?;
+
+        self.local = LocalSocket::Outlet {
+            peer,
+            socket: Some(socket),
+        };
+        self.remote_route = Some(pong_route);
+
+        debug!(portal_type = %self.portal_type, remote = %self.addresses.remote, "sent pong");
+
+        Ok(State::Initialized)
+    }
+
+    /// Relay a datagram read from the local socket to the other side of the portal
+    async fn handle_local_datagram(&mut self, ctx: &Context, datagram: Vec<u8>) -> Result<()> {
+        self.last_activity = Instant::now();
+
+        match (&self.state, &self.remote_route) {
+            (State::Initialized, Some(remote_route)) => {
+                ctx.send_from_address(
+                    remote_route.clone(),
+                    UdpPortalMessage::Datagram(datagram),
+                    self.addresses.remote.clone(),
+                )
+                .await
+            }
+            _ => {
+                if self.pending_datagrams.len() < MAX_PENDING_DATAGRAMS {
+                    self.pending_datagrams.push_back(datagram);
+                } else {
+                    trace!(portal_type = %self.portal_type, "dropping a datagram while waiting for the pong");
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Write a datagram received from the other side of the portal to the local socket.
+    /// Failures are only logged since datagrams can be lost anyway
+    async fn handle_remote_datagram(&mut self, datagram: &[u8]) {
+        self.last_activity = Instant::now();
+
+        let result = match &self.local {
+            LocalSocket::Inlet { socket, client, .. } => socket.send_to(datagram, client).await,
+            LocalSocket::Outlet {
+                socket: Some(socket),
+                ..
+            } => socket.send(datagram).await,
+            LocalSocket::Outlet { socket: None, .. } => return,
+        };
+
+        if let Err(err) = result {
+            warn!(portal_type = %self.portal_type, %err, "failed to send a datagram");
+        }
+    }
+
+    async fn handle_receive_pong(&mut self, ctx: &Context, return_route: Route) -> Result<()> {
+        debug!(portal_type = %self.portal_type, remote = %self.addresses.remote, "received pong");
+        self.state = State::Initialized;
+        self.remote_route = Some(return_route.clone());
+
+        while let Some(datagram) = self.pending_datagrams.pop_front() {
+            ctx.send_from_address(
+                return_route.clone(),
+                UdpPortalMessage::Datagram(datagram),
+                self.addresses.remote.clone(),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Close the flow when it was idle for too long, otherwise check again later
+    async fn handle_heartbeat(&mut self, ctx: &Context) -> Result<()> {
+        let elapsed = self.last_activity.elapsed();
+        if elapsed < self.idle_timeout {
+            return self.heartbeat.schedule(self.idle_timeout - elapsed);
+        }
+
+        debug!(portal_type = %self.portal_type, remote = %self.addresses.remote, "closing the idle flow");
+        if let Some(remote_route) = self.remote_route.take() {
+            ctx.send_from_address(
+                remote_route,
+                UdpPortalMessage::Disconnect,
+                self.addresses.remote.clone(),
+            )
+            .await?;
+        }
+        ctx.stop_address(&self.addresses.remote)
+    }
+
+    async fn handle_remote_message(&mut self, ctx: &Context, msg: Routed<Any>) -> Result<()> {
+        let their_identifier = SecureChannelLocalInfo::find_info(msg.local_message())
+            .map(|l| l.their_identifier())
+            .ok();
+        let state = self.state.clone();
+        if matches!(state, State::ReceivePong) {
+            self.their_identifier = their_identifier;
+        } else if their_identifier != self.their_identifier {
+            debug!(
+                "identifier changed from {:?} to {:?}",
+                self.their_identifier.as_ref().map(|i| i.to_string()),
+                their_identifier.as_ref().map(|i| i.to_string()),
+            );
+            return Err(TransportError::IdentifierChanged)?;
+        }
+
+        let msg = msg.into_local_message();
+        let return_route = msg.return_route;
+        let msg = UdpPortalMessage::decode(&msg.payload)?;
+
+        match (state, msg) {
+            (State::ReceivePong, UdpPortalMessage::Pong) => {
+                self.handle_receive_pong(ctx, return_route).await
+            }
+            (State::Initialized, UdpPortalMessage::Datagram(datagram)) => {
+                self.handle_remote_datagram(&datagram).await;
+                Ok(())
+            }
+            (_, UdpPortalMessage::Disconnect) => {
+                debug!(portal_type = %self.portal_type, remote = %self.addresses.remote, "the other side closed the flow");
+                self.remote_route = None;
+                ctx.stop_address(&self.addresses.remote)
+            }
+            _ => Err(TransportError::Protocol)?,
+        }
+    }
+}
+
+#[async_trait]
+impl Worker for UdpPortalWorker {
+    type Context = Context;
+    type Message = Any;
+
+    #[instrument(skip_all, name = "UdpPortalWorker::initialize")]
+    async fn initialize(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        self.state = match self.state.clone() {
+            State::SendPing { ping_route } => self.handle_send_ping(ctx, ping_route).await?,
+            State::SendPong { pong_route } => self.handle_send_pong(ctx, pong_route).await?,
+            State::ReceivePong | State::Initialized => {
+                return Err(TransportError::PortalInvalidState)?;
+            }
+        };
+        self.last_activity = Instant::now();
+        self.heartbeat.schedule(self.idle_timeout)?;
+
+        debug!(portal_type = %self.portal_type, remote = %self.addresses.remote, "udp portal worker initialized");
+
+        Ok(())
+    }
+
+    #[instrument(skip_all, name = "UdpPortalWorker::shutdown")]
+    async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        self.heartbeat.cancel();
+
+        match &self.local {
+            LocalSocket::Inlet { client, flows, .. } => {
+                let mut flows = flows.lock().unwrap();
+                if flows.get(client) == Some(&self.addresses.internal) {
+                    flows.remove(client);
+                }
+            }
+            LocalSocket::Outlet { socket, .. } => {
+                if socket.is_some() {
+                    _ = ctx.stop_address(&self.addresses.receiver);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip_all, name = "UdpPortalWorker::handle_message")]
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let recipient = msg.msg_addr();
+        if recipient == self.addresses.remote {
+            self.handle_remote_message(ctx, msg).await
+        } else if recipient == self.addresses.internal {
+            self.handle_local_datagram(ctx, msg.into_payload()).await
+        } else if recipient == self.addresses.heartbeat {
+            self.handle_heartbeat(ctx).await
+        } else {
+            Err(TransportError::UnknownRoute)?
+        }
+    }
+}
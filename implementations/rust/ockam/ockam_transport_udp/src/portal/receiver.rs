@@ -0,0 +1,53 @@
+use ockam_core::compat::sync::Arc;
+use ockam_core::{async_trait, route, Address, NeutralMessage, Processor, Result};
+use ockam_node::Context;
+use ockam_transport_core::TransportError;
+use tokio::net::UdpSocket;
+use tracing::{instrument, warn};
+
+/// Maximum size of a UDP datagram
+pub(crate) const MAX_DATAGRAM_SIZE: usize = 65535;
+
+/// A processor reading the datagrams sent by the target of a UDP Outlet flow
+/// and forwarding them to the flow worker
+pub(crate) struct UdpPortalRecvProcessor {
+    socket: Arc<UdpSocket>,
+    worker_address: Address,
+    buffer: Vec<u8>,
+}
+
+impl UdpPortalRecvProcessor {
+    pub(crate) fn new(socket: Arc<UdpSocket>, worker_address: Address) -> Self {
+        Self {
+            socket,
+            worker_address,
+            buffer: vec![0; MAX_DATAGRAM_SIZE],
+        }
+    }
+}
+
+#[async_trait]
+impl Processor for UdpPortalRecvProcessor {
+    type Context = Context;
+
+    #[instrument(skip_all, name = "UdpPortalRecvProcessor::process")]
+    async fn process(&mut self, ctx: &mut Context) -> Result<bool> {
+        let len = match self.socket.recv(&mut self.buffer).await {
+            Ok(len) => len,
+            // an ICMP error received for a previous datagram, the target may come back later
+            Err(err) if err.kind() == std::io::ErrorKind::ConnectionRefused => {
+                warn!(%err, "the target of the udp outlet is unreachable");
+                return Ok(true);
+            }
+            Err(err) => return Err(TransportError::from(err))?,
+        };
+
+        ctx.send(
+            route![self.worker_address.clone()],
+            NeutralMessage::from(self.buffer[..len].to_vec()),
+        )
+        .await?;
+
+        Ok(true)
+    }
+}
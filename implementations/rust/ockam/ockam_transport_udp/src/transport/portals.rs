@@ -0,0 +1,70 @@
+use crate::{UdpInlet, UdpInletOptions, UdpOutlet, UdpOutletOptions, UdpTransport};
+use core::fmt::Debug;
+use ockam_core::{Address, Result, Route};
+use ockam_transport_core::{parse_socket_addr, HostnamePort};
+use tracing::instrument;
+
+impl UdpTransport {
+    /// Create a UDP Inlet that listens on bind_addr, and relays the datagrams of each client
+    /// to the Outlet at the end of outlet_route. Datagrams sent back by the Outlet are sent
+    /// to the client from the same socket.
+    /// Pair of corresponding Inlet and Outlet is called Portal.
+    ///
+    /// ```rust
+    /// use ockam_transport_udp::{UdpInletOptions, UdpTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::{Result, route};
+    /// # async fn test(ctx: Context) -> Result<()> {
+    /// let udp = UdpTransport::create(&ctx)?;
+    /// let inlet = udp.create_inlet("127.0.0.1:5353", route!["outlet"], UdpInletOptions::new()).await?;
+    /// # udp.stop_inlet(inlet.processor_address())?;
+    /// # Ok(()) }
+    /// ```
+    #[instrument(skip(self), fields(address = ? bind_addr.clone().into(), outlet_route = ? outlet_route.clone()))]
+    pub async fn create_inlet(
+        &self,
+        bind_addr: impl Into<String> + Clone + Debug,
+        outlet_route: impl Into<Route> + Clone + Debug,
+        options: UdpInletOptions,
+    ) -> Result<UdpInlet> {
+        let socket_address = parse_socket_addr(&bind_addr.into())?;
+        UdpInlet::create(self.ctx(), socket_address, outlet_route.into(), options).await
+    }
+
+    /// Stop the inlet with the given processor address
+    #[instrument(skip(self), fields(address = ? address))]
+    pub fn stop_inlet(&self, address: &Address) -> Result<()> {
+        self.ctx().stop_address(address)
+    }
+
+    /// Create a UDP Outlet Listener at address, relaying the datagrams received from each
+    /// inlet flow to the peer, with one socket per flow.
+    /// Pair of corresponding Inlet and Outlet is called Portal.
+    ///
+    /// ```rust
+    /// use ockam_transport_udp::{UdpOutletOptions, UdpTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::Result;
+    /// # use ockam_transport_core::HostnamePort;
+    /// # async fn test(ctx: Context) -> Result<()> {
+    /// let udp = UdpTransport::create(&ctx)?;
+    /// udp.create_outlet("outlet", HostnamePort::new("127.0.0.1", 53)?, UdpOutletOptions::new())?;
+    /// # udp.stop_outlet(&"outlet".into())?;
+    /// # Ok(()) }
+    /// ```
+    #[instrument(skip(self), fields(address = ? address.clone().into(), peer = peer.clone().to_string()))]
+    pub fn create_outlet(
+        &self,
+        address: impl Into<Address> + Clone + Debug,
+        peer: HostnamePort,
+        options: UdpOutletOptions,
+    ) -> Result<UdpOutlet> {
+        UdpOutlet::create(self.ctx(), address.into(), peer, options)
+    }
+
+    /// Stop the outlet at the given address
+    #[instrument(skip(self), fields(address = % address))]
+    pub fn stop_outlet(&self, address: &Address) -> Result<()> {
+        self.ctx().stop_address(address)
+    }
+}
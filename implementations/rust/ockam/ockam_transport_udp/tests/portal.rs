@@ -0,0 +1,105 @@
+use ockam_core::{route, Result};
+use ockam_node::Context;
+use ockam_transport_core::{HostnamePort, TransportError};
+use ockam_transport_udp::{UdpInletOptions, UdpOutletOptions, UdpTransport};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Start a UDP server replying to each datagram with its content in uppercase
+async fn start_uppercase_server() -> Result<SocketAddr> {
+    let socket = UdpSocket::bind("127.0.0.1:0")
+        .await
+        .map_err(TransportError::from)?;
+    let address = socket.local_addr().map_err(TransportError::from)?;
+    tokio::spawn(async move {
+        let mut buffer = vec![0; 65535];
+        while let Ok((len, peer)) = socket.recv_from(&mut buffer).await {
+            let reply = buffer[..len].to_ascii_uppercase();
+            let _ = socket.send_to(&reply, peer).await;
+        }
+    });
+    Ok(address)
+}
+
+async fn send_and_receive(client: &UdpSocket, inlet: SocketAddr, datagram: &[u8]) -> Vec<u8> {
+    client.send_to(datagram, inlet).await.unwrap();
+    let mut buffer = vec![0; 65535];
+    let (len, from) = timeout(TIMEOUT, client.recv_from(&mut buffer))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(from, inlet);
+    buffer[..len].to_vec()
+}
+
+#[ockam_macros::test]
+async fn udp_portal_relays_the_datagrams_of_each_client(ctx: &mut Context) -> Result<()> {
+    let server = start_uppercase_server().await?;
+    let udp = UdpTransport::create(ctx)?;
+
+    udp.create_outlet(
+        "outlet",
+        HostnamePort::from(server),
+        UdpOutletOptions::new(),
+    )?;
+    let inlet = udp
+        .create_inlet("127.0.0.1:0", route!["outlet"], UdpInletOptions::new())
+        .await?;
+
+    let client1 = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let client2 = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+    for i in 0..3 {
+        let reply = send_and_receive(&client1, inlet.socket_address(), b"hello").await;
+        assert_eq!(reply, b"HELLO", "client 1, datagram {i}");
+        let reply = send_and_receive(&client2, inlet.socket_address(), b"world").await;
+        assert_eq!(reply, b"WORLD", "client 2, datagram {i}");
+    }
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn udp_portal_closes_idle_flows(ctx: &mut Context) -> Result<()> {
+    let server = start_uppercase_server().await?;
+    let udp = UdpTransport::create(ctx)?;
+    let idle_timeout = Duration::from_millis(300);
+
+    udp.create_outlet(
+        "outlet",
+        HostnamePort::from(server),
+        UdpOutletOptions::new().with_idle_timeout(idle_timeout),
+    )?;
+    let inlet = udp
+        .create_inlet(
+            "127.0.0.1:0",
+            route!["outlet"],
+            UdpInletOptions::new().with_idle_timeout(idle_timeout),
+        )
+        .await?;
+    let initial_workers = ctx.list_workers()?;
+
+    let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let reply = send_and_receive(&client, inlet.socket_address(), b"hello").await;
+    assert_eq!(reply, b"HELLO");
+
+    // the workers of the flow on each side
+    let mut flow_workers = ctx.list_workers()?;
+    flow_workers.retain(|w| !initial_workers.contains(w));
+    assert!(!flow_workers.is_empty());
+
+    ctx.sleep(idle_timeout * 4).await;
+    let mut flow_workers = ctx.list_workers()?;
+    flow_workers.retain(|w| !initial_workers.contains(w));
+    assert!(flow_workers.is_empty());
+
+    // a new flow is started for the same client
+    let reply = send_and_receive(&client, inlet.socket_address(), b"again").await;
+    assert_eq!(reply, b"AGAIN");
+
+    Ok(())
+}
@@ -39,6 +39,28 @@ pub enum VaultError {
     InsufficientEncryptBuffer,
     /// Buffer is too short during decryption
     InsufficientDecryptBuffer,
+    /// ML-KEM key generation failed
+    MlKemKeyGeneration,
+    /// ML-KEM encapsulation failed
+    MlKemEncapsulate,
+    /// ML-KEM decapsulation failed
+    MlKemDecapsulate,
+    /// ML-KEM is not supported by this Vault implementation
+    MlKemNotSupported,
+    /// The secrets are encrypted and no passphrase was provided to unlock them
+    SecretsLocked,
+    /// The passphrase used to unlock the secrets is incorrect
+    InvalidPassphrase,
+    /// The secrets are already encrypted
+    SecretsAlreadyEncrypted,
+    /// The secrets are not encrypted
+    SecretsNotEncrypted,
+    /// A secret could not be encrypted before being stored
+    SecretEncryption,
+    /// A stored secret could not be decrypted
+    SecretDecryption,
+    /// The key derivation parameters are invalid
+    InvalidKeyDerivationParameters,
 }
 
 impl ockam_core::compat::error::Error for VaultError {}
@@ -63,6 +85,22 @@ impl core::fmt::Display for VaultError {
             Self::AeadSecretNotFound => write!(f, "aead secret was not found in the storage"),
             Self::InsufficientEncryptBuffer => write!(f, "insufficient encrypt buffer"),
             Self::InsufficientDecryptBuffer => write!(f, "insufficient decrypt buffer"),
+            Self::MlKemKeyGeneration => write!(f, "ml-kem key generation failed"),
+            Self::MlKemEncapsulate => write!(f, "ml-kem encapsulation failed"),
+            Self::MlKemDecapsulate => write!(f, "ml-kem decapsulation failed"),
+            Self::MlKemNotSupported => write!(f, "ml-kem is not supported"),
+            Self::SecretsLocked => write!(
+                f,
+                "the vault secrets are encrypted, a passphrase is required to unlock them"
+            ),
+            Self::InvalidPassphrase => write!(f, "the vault passphrase is incorrect"),
+            Self::SecretsAlreadyEncrypted => write!(f, "the vault secrets are already encrypted"),
+            Self::SecretsNotEncrypted => write!(f, "the vault secrets are not encrypted"),
+            Self::SecretEncryption => write!(f, "secret encryption failed"),
+            Self::SecretDecryption => write!(f, "secret decryption failed"),
+            Self::InvalidKeyDerivationParameters => {
+                write!(f, "invalid key derivation parameters")
+            }
         }
     }
 }
@@ -74,6 +112,8 @@ impl From<VaultError> for Error {
         let kind = match err {
             InvalidPublicKey | InvalidKeyType | InvalidHkdfOutputType => Kind::Misuse,
             UnknownEcdhKeyType => Kind::NotFound,
+            MlKemNotSupported => Kind::Unsupported,
+            SecretsLocked | SecretsAlreadyEncrypted | SecretsNotEncrypted => Kind::Misuse,
             _ => Kind::Invalid,
         };
 
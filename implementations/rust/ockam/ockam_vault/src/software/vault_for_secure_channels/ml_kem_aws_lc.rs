@@ -0,0 +1,69 @@
+use aws_lc_rs::kem::{Ciphertext, DecapsulationKey, EncapsulationKey};
+use aws_lc_rs::unstable::kem::ML_KEM_768;
+
+use ockam_core::Result;
+
+use crate::{
+    BufferSecret, MLKEM768Ciphertext, MLKEM768EncapsulationKey, VaultError,
+    ML_KEM_768_CIPHERTEXT_LENGTH, ML_KEM_768_ENCAPSULATION_KEY_LENGTH,
+};
+
+/// ML-KEM-768 Decapsulation Key backed by aws-lc
+pub(super) struct MLKEM768DecapsulationKey(DecapsulationKey);
+
+impl MLKEM768DecapsulationKey {
+    /// Generate a fresh decapsulation key
+    pub(super) fn generate() -> Result<Self> {
+        let key =
+            DecapsulationKey::generate(&ML_KEM_768).map_err(|_| VaultError::MlKemKeyGeneration)?;
+        Ok(Self(key))
+    }
+
+    /// Return the encapsulation key which must be sent to the other party
+    pub(super) fn encapsulation_key(&self) -> Result<MLKEM768EncapsulationKey> {
+        let key_bytes = self
+            .0
+            .encapsulation_key()
+            .and_then(|k| k.key_bytes())
+            .map_err(|_| VaultError::MlKemKeyGeneration)?;
+
+        let key: [u8; ML_KEM_768_ENCAPSULATION_KEY_LENGTH] = key_bytes
+            .as_ref()
+            .try_into()
+            .map_err(|_| VaultError::InvalidPublicLength)?;
+
+        Ok(MLKEM768EncapsulationKey(key))
+    }
+
+    /// Decapsulate the shared secret from a ciphertext produced by the other party
+    pub(super) fn decapsulate(&self, ciphertext: &MLKEM768Ciphertext) -> Result<BufferSecret> {
+        let shared_secret = self
+            .0
+            .decapsulate(Ciphertext::from(ciphertext.0.as_slice()))
+            .map_err(|_| VaultError::MlKemDecapsulate)?;
+
+        Ok(BufferSecret::new(shared_secret.as_ref().to_vec()))
+    }
+}
+
+/// Encapsulate a fresh shared secret to the other party encapsulation key
+pub(super) fn ml_kem_768_encapsulate(
+    encapsulation_key: &MLKEM768EncapsulationKey,
+) -> Result<(MLKEM768Ciphertext, BufferSecret)> {
+    let encapsulation_key = EncapsulationKey::new(&ML_KEM_768, encapsulation_key.0.as_slice())
+        .map_err(|_| VaultError::InvalidPublicKey)?;
+
+    let (ciphertext, shared_secret) = encapsulation_key
+        .encapsulate()
+        .map_err(|_| VaultError::MlKemEncapsulate)?;
+
+    let ciphertext: [u8; ML_KEM_768_CIPHERTEXT_LENGTH] = ciphertext
+        .as_ref()
+        .try_into()
+        .map_err(|_| VaultError::MlKemEncapsulate)?;
+
+    Ok((
+        MLKEM768Ciphertext(ciphertext),
+        BufferSecret::new(shared_secret.as_ref().to_vec()),
+    ))
+}
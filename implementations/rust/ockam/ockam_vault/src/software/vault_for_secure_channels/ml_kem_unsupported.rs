@@ -0,0 +1,26 @@
+use ockam_core::Result;
+
+use crate::{BufferSecret, MLKEM768Ciphertext, MLKEM768EncapsulationKey, VaultError};
+
+/// ML-KEM is currently only available with the aws-lc backend
+pub(super) struct MLKEM768DecapsulationKey;
+
+impl MLKEM768DecapsulationKey {
+    pub(super) fn generate() -> Result<Self> {
+        Err(VaultError::MlKemNotSupported)?
+    }
+
+    pub(super) fn encapsulation_key(&self) -> Result<MLKEM768EncapsulationKey> {
+        Err(VaultError::MlKemNotSupported)?
+    }
+
+    pub(super) fn decapsulate(&self, _ciphertext: &MLKEM768Ciphertext) -> Result<BufferSecret> {
+        Err(VaultError::MlKemNotSupported)?
+    }
+}
+
+pub(super) fn ml_kem_768_encapsulate(
+    _encapsulation_key: &MLKEM768EncapsulationKey,
+) -> Result<(MLKEM768Ciphertext, BufferSecret)> {
+    Err(VaultError::MlKemNotSupported)?
+}
@@ -18,6 +18,16 @@ cfg_if! {
     }
 }
 
+cfg_if! {
+    if #[cfg(feature = "aws-lc")] {
+        mod ml_kem_aws_lc;
+        use ml_kem_aws_lc::{ml_kem_768_encapsulate, MLKEM768DecapsulationKey};
+    } else {
+        mod ml_kem_unsupported;
+        use ml_kem_unsupported::{ml_kem_768_encapsulate, MLKEM768DecapsulationKey};
+    }
+}
+
 mod types;
 #[allow(clippy::module_inception)]
 mod vault_for_secure_channels;
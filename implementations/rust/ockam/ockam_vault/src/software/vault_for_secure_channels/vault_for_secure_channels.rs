@@ -15,18 +15,20 @@ use crate::storage::SecretsSqlxDatabase;
 
 use crate::{
     AeadSecret, AeadSecretKeyHandle, BufferSecret, HKDFNumberOfOutputs, HandleToSecret, HashOutput,
-    HkdfOutput, SecretBufferHandle, SoftwareVaultForVerifyingSignatures, VaultError,
-    VaultForSecureChannels, X25519PublicKey, X25519SecretKey, X25519SecretKeyHandle,
-    AEAD_SECRET_LENGTH,
+    HkdfOutput, MLKEM768Ciphertext, MLKEM768DecapsulationKeyHandle, MLKEM768EncapsulationKey,
+    SecretBufferHandle, SoftwareVaultForVerifyingSignatures, VaultError, VaultForSecureChannels,
+    X25519PublicKey, X25519SecretKey, X25519SecretKeyHandle, AEAD_SECRET_LENGTH,
 };
 
-use super::make_aes;
+use super::{make_aes, ml_kem_768_encapsulate, MLKEM768DecapsulationKey};
 
 /// [`SecureChannelVault`] implementation using software
 pub struct SoftwareVaultForSecureChannels {
     ephemeral_buffer_secrets: Arc<RwLock<BTreeMap<SecretBufferHandle, BufferSecret>>>,
     ephemeral_aead_secrets: Arc<RwLock<BTreeMap<AeadSecretKeyHandle, AeadSecret>>>,
     ephemeral_x25519_secrets: Arc<RwLock<BTreeMap<X25519SecretKeyHandle, X25519SecretKey>>>,
+    ephemeral_ml_kem_768_secrets:
+        Arc<RwLock<BTreeMap<MLKEM768DecapsulationKeyHandle, MLKEM768DecapsulationKey>>>,
     secrets_repository: Arc<dyn SecretsRepository>,
 }
 
@@ -37,6 +39,7 @@ impl SoftwareVaultForSecureChannels {
             ephemeral_buffer_secrets: Default::default(),
             ephemeral_aead_secrets: Default::default(),
             ephemeral_x25519_secrets: Default::default(),
+            ephemeral_ml_kem_768_secrets: Default::default(),
             secrets_repository,
         }
     }
@@ -111,6 +114,11 @@ impl SoftwareVaultForSecureChannels {
     pub fn number_of_ephemeral_aead_secrets(&self) -> usize {
         self.ephemeral_aead_secrets.read().unwrap().len()
     }
+
+    /// Return the total number of ephemeral ML-KEM-768 secrets present in the Vault
+    pub fn number_of_ephemeral_ml_kem_768_secrets(&self) -> usize {
+        self.ephemeral_ml_kem_768_secrets.read().unwrap().len()
+    }
 }
 
 impl SoftwareVaultForSecureChannels {
@@ -149,6 +157,10 @@ impl SoftwareVaultForSecureChannels {
         SecretBufferHandle(Self::generate_random_handle())
     }
 
+    fn generate_ml_kem_768_handle() -> MLKEM768DecapsulationKeyHandle {
+        MLKEM768DecapsulationKeyHandle(Self::generate_random_handle())
+    }
+
     fn generate_aead_handle() -> AeadSecretKeyHandle {
         use crate::Aes256GcmSecretKeyHandle;
         let handle = Self::generate_random_handle();
@@ -333,6 +345,16 @@ impl VaultForSecureChannels for SoftwareVaultForSecureChannels {
         Ok(())
     }
 
+    #[instrument(skip_all)]
+    async fn delete_persisted_aead_key(
+        &self,
+        secret_key_handle: &AeadSecretKeyHandle,
+    ) -> Result<bool> {
+        self.secrets_repository
+            .delete_aead_secret(secret_key_handle)
+            .await
+    }
+
     async fn generate_static_x25519_secret_key(&self) -> Result<X25519SecretKeyHandle> {
         let secret = Self::generate_x25519_secret();
 
@@ -437,4 +459,72 @@ impl VaultForSecureChannels for SoftwareVaultForSecureChannels {
             .remove(&secret_key_handle)
             .is_some())
     }
+
+    async fn generate_ephemeral_ml_kem_768_decapsulation_key(
+        &self,
+    ) -> Result<MLKEM768DecapsulationKeyHandle> {
+        let secret = MLKEM768DecapsulationKey::generate()?;
+        let handle = Self::generate_ml_kem_768_handle();
+
+        self.ephemeral_ml_kem_768_secrets
+            .write()
+            .unwrap()
+            .insert(handle.clone(), secret);
+
+        Ok(handle)
+    }
+
+    async fn delete_ephemeral_ml_kem_768_decapsulation_key(
+        &self,
+        decapsulation_key_handle: MLKEM768DecapsulationKeyHandle,
+    ) -> Result<bool> {
+        Ok(self
+            .ephemeral_ml_kem_768_secrets
+            .write()
+            .unwrap()
+            .remove(&decapsulation_key_handle)
+            .is_some())
+    }
+
+    async fn get_ml_kem_768_encapsulation_key(
+        &self,
+        decapsulation_key_handle: &MLKEM768DecapsulationKeyHandle,
+    ) -> Result<MLKEM768EncapsulationKey> {
+        match self
+            .ephemeral_ml_kem_768_secrets
+            .read()
+            .unwrap()
+            .get(decapsulation_key_handle)
+        {
+            Some(secret) => secret.encapsulation_key(),
+            None => Err(VaultError::KeyNotFound)?,
+        }
+    }
+
+    async fn ml_kem_768_encapsulate(
+        &self,
+        peer_encapsulation_key: &MLKEM768EncapsulationKey,
+    ) -> Result<(MLKEM768Ciphertext, SecretBufferHandle)> {
+        let (ciphertext, shared_secret) = ml_kem_768_encapsulate(peer_encapsulation_key)?;
+
+        Ok((ciphertext, self.import_buffer_secret_impl(shared_secret)))
+    }
+
+    async fn ml_kem_768_decapsulate(
+        &self,
+        decapsulation_key_handle: &MLKEM768DecapsulationKeyHandle,
+        ciphertext: &MLKEM768Ciphertext,
+    ) -> Result<SecretBufferHandle> {
+        let shared_secret = match self
+            .ephemeral_ml_kem_768_secrets
+            .read()
+            .unwrap()
+            .get(decapsulation_key_handle)
+        {
+            Some(secret) => secret.decapsulate(ciphertext)?,
+            None => return Err(VaultError::KeyNotFound)?,
+        };
+
+        Ok(self.import_buffer_secret_impl(shared_secret))
+    }
 }
@@ -0,0 +1,199 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use ockam_core::compat::rand::random;
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+
+use crate::VaultError;
+
+const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 24;
+const SALT_LENGTH: usize = 16;
+
+/// Associated data used when encrypting the secrets encryption key itself
+const WRAPPED_KEY_AAD: &[u8] = b"secrets_encryption";
+
+/// Parameters used to derive a key encryption key from a passphrase with Argon2id
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyDerivationParameters {
+    salt: Vec<u8>,
+    memory_cost: u32,
+    time_cost: u32,
+    parallelism: u32,
+}
+
+impl Default for KeyDerivationParameters {
+    /// Generate a random salt and use the recommended Argon2id costs
+    fn default() -> Self {
+        Self::generate(
+            Params::DEFAULT_M_COST,
+            Params::DEFAULT_T_COST,
+            Params::DEFAULT_P_COST,
+        )
+    }
+}
+
+impl KeyDerivationParameters {
+    /// Create parameters from existing values
+    pub fn new(salt: Vec<u8>, memory_cost: u32, time_cost: u32, parallelism: u32) -> Self {
+        Self {
+            salt,
+            memory_cost,
+            time_cost,
+            parallelism,
+        }
+    }
+
+    /// Generate a random salt and use the given Argon2id costs
+    pub fn generate(memory_cost: u32, time_cost: u32, parallelism: u32) -> Self {
+        let salt: [u8; SALT_LENGTH] = random();
+        Self::new(salt.to_vec(), memory_cost, time_cost, parallelism)
+    }
+
+    /// Salt used for the key derivation
+    pub fn salt(&self) -> &[u8] {
+        &self.salt
+    }
+
+    /// Argon2id memory cost, in KiB
+    pub fn memory_cost(&self) -> u32 {
+        self.memory_cost
+    }
+
+    /// Argon2id number of iterations
+    pub fn time_cost(&self) -> u32 {
+        self.time_cost
+    }
+
+    /// Argon2id degree of parallelism
+    pub fn parallelism(&self) -> u32 {
+        self.parallelism
+    }
+
+    /// Derive a key encryption key from a passphrase
+    pub(crate) fn derive_key(&self, passphrase: &[u8]) -> Result<SecretsEncryptionKey> {
+        let params = Params::new(
+            self.memory_cost,
+            self.time_cost,
+            self.parallelism,
+            Some(KEY_LENGTH),
+        )
+        .map_err(|_| VaultError::InvalidKeyDerivationParameters)?;
+        let mut key = [0u8; KEY_LENGTH];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase, &self.salt, &mut key)
+            .map_err(|_| VaultError::InvalidKeyDerivationParameters)?;
+        Ok(SecretsEncryptionKey(key))
+    }
+}
+
+/// Symmetric key used to encrypt secrets at rest with XChaCha20-Poly1305.
+///
+/// The same type is used for the key encrypting each secret and for the key
+/// derived from a passphrase, which encrypts the first one
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct SecretsEncryptionKey([u8; KEY_LENGTH]);
+
+impl SecretsEncryptionKey {
+    /// Generate a random key
+    pub(crate) fn generate() -> Self {
+        Self(random())
+    }
+
+    /// Encrypt some data, bound to the associated data.
+    /// The random nonce is prepended to the cipher text
+    pub(crate) fn encrypt(&self, aad: &[u8], plain_text: &[u8]) -> Result<Vec<u8>> {
+        let nonce: [u8; NONCE_LENGTH] = random();
+        let cipher_text = XChaCha20Poly1305::new((&self.0).into())
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: plain_text,
+                    aad,
+                },
+            )
+            .map_err(|_| VaultError::SecretEncryption)?;
+
+        let mut result = Vec::with_capacity(NONCE_LENGTH + cipher_text.len());
+        result.extend_from_slice(&nonce);
+        result.extend_from_slice(&cipher_text);
+        Ok(result)
+    }
+
+    /// Decrypt some data encrypted with [`SecretsEncryptionKey::encrypt`]
+    pub(crate) fn decrypt(&self, aad: &[u8], data: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+        if data.len() < NONCE_LENGTH {
+            return Err(VaultError::SecretDecryption)?;
+        }
+        let (nonce, cipher_text) = data.split_at(NONCE_LENGTH);
+        let plain_text = XChaCha20Poly1305::new((&self.0).into())
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: cipher_text,
+                    aad,
+                },
+            )
+            .map_err(|_| VaultError::SecretDecryption)?;
+        Ok(Zeroizing::new(plain_text))
+    }
+
+    /// Encrypt this key with a key encryption key
+    pub(crate) fn wrap(&self, key_encryption_key: &SecretsEncryptionKey) -> Result<Vec<u8>> {
+        key_encryption_key.encrypt(WRAPPED_KEY_AAD, &self.0)
+    }
+
+    /// Decrypt a key encrypted with a key encryption key.
+    /// This fails if the key encryption key was derived from an incorrect passphrase
+    pub(crate) fn unwrap(
+        key_encryption_key: &SecretsEncryptionKey,
+        wrapped_key: &[u8],
+    ) -> Result<Self> {
+        let key = key_encryption_key
+            .decrypt(WRAPPED_KEY_AAD, wrapped_key)
+            .map_err(|_| VaultError::InvalidPassphrase)?;
+        let key: [u8; KEY_LENGTH] = key
+            .as_slice()
+            .try_into()
+            .map_err(|_| VaultError::InvalidPassphrase)?;
+        Ok(Self(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt() -> Result<()> {
+        let key = SecretsEncryptionKey::generate();
+        let cipher_text = key.encrypt(b"aad", b"secret")?;
+        assert_eq!(key.decrypt(b"aad", &cipher_text)?.as_slice(), b"secret");
+
+        // the associated data must match
+        assert!(key.decrypt(b"other aad", &cipher_text).is_err());
+
+        // the key must match
+        let other_key = SecretsEncryptionKey::generate();
+        assert!(other_key.decrypt(b"aad", &cipher_text).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_wrap_unwrap() -> Result<()> {
+        let parameters = KeyDerivationParameters::generate(64, 1, 1);
+        let key_encryption_key = parameters.derive_key(b"passphrase")?;
+        let key = SecretsEncryptionKey::generate();
+        let wrapped_key = key.wrap(&key_encryption_key)?;
+
+        let unwrapped = SecretsEncryptionKey::unwrap(&key_encryption_key, &wrapped_key)?;
+        assert_eq!(unwrapped.0, key.0);
+
+        let incorrect_key = parameters.derive_key(b"incorrect")?;
+        assert!(SecretsEncryptionKey::unwrap(&incorrect_key, &wrapped_key).is_err());
+        Ok(())
+    }
+}
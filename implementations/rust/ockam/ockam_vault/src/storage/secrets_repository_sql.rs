@@ -1,11 +1,14 @@
+use sqlx::any::AnyArguments;
 use sqlx::encode::IsNull;
 use sqlx::error::BoxDynError;
+use sqlx::query::Query;
 use sqlx::*;
 use sqlx_core::any::AnyArgumentBuffer;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use tracing::debug;
-use zeroize::{Zeroize, ZeroizeOnDrop};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
+use crate::storage::secrets_encryption::{KeyDerivationParameters, SecretsEncryptionKey};
 use crate::storage::secrets_repository::SecretsRepository;
 use ockam_core::async_trait;
 use ockam_core::compat::vec::Vec;
@@ -16,29 +19,48 @@ use ockam_node::database::{FromSqlxError, SqlxDatabase, ToVoid};
 
 use crate::{
     AeadSecret, AeadSecretKeyHandle, ECDSASHA256CurveP256SecretKey, EdDSACurve25519SecretKey,
-    HandleToSecret, SigningSecret, SigningSecretKeyHandle, X25519SecretKey, X25519SecretKeyHandle,
-    AEAD_TYPE,
+    HandleToSecret, SigningSecret, SigningSecretKeyHandle, VaultError, X25519SecretKey,
+    X25519SecretKeyHandle, AEAD_TYPE,
 };
 
 /// Implementation of a secrets repository using a SQL database
+///
+/// The secrets can be encrypted at rest. In that case each secret is encrypted with a
+/// random key, which is itself encrypted with a key derived from a passphrase, and the repository
+/// must be unlocked with that passphrase before secrets can be read or written.
+///
+/// Whether the secrets are encrypted or not is read once and cached. Secrets stored in plain
+/// text are written in a transaction which checks again that the secrets are not encrypted,
+/// in case they were encrypted by another process in the meantime.
 #[derive(Clone)]
 pub struct SecretsSqlxDatabase {
     database: SqlxDatabase,
+    encryption_key: Option<Arc<SecretsEncryptionKey>>,
+    is_encrypted: Arc<RwLock<Option<bool>>>,
 }
 
 impl SecretsSqlxDatabase {
     /// Create a new database for secrets
     pub fn new(database: SqlxDatabase) -> Self {
         debug!("create a repository for secrets");
-        Self { database }
+        Self {
+            database,
+            encryption_key: None,
+            is_encrypted: Default::default(),
+        }
     }
 
     /// Create a repository
     pub fn make_repository(database: SqlxDatabase) -> Arc<dyn SecretsRepository> {
-        if database.needs_retry() {
-            Arc::new(AutoRetry::new(Self::new(database)))
+        Self::new(database).into_repository()
+    }
+
+    /// Return this database as a repository, with retries if the database needs them
+    pub fn into_repository(self) -> Arc<dyn SecretsRepository> {
+        if self.database.needs_retry() {
+            Arc::new(AutoRetry::new(self))
         } else {
-            Arc::new(Self::new(database))
+            Arc::new(self)
         }
     }
 
@@ -48,9 +70,215 @@ impl SecretsSqlxDatabase {
     }
 }
 
+/// Encryption at rest
+impl SecretsSqlxDatabase {
+    /// Return true if the secrets stored in this database are encrypted
+    pub async fn is_encrypted(&self) -> Result<bool> {
+        if let Some(is_encrypted) = *self.is_encrypted.read().unwrap() {
+            return Ok(is_encrypted);
+        }
+        let is_encrypted = self.get_encryption_row().await?.is_some();
+        self.set_encrypted(is_encrypted);
+        Ok(is_encrypted)
+    }
+
+    fn set_encrypted(&self, is_encrypted: bool) {
+        *self.is_encrypted.write().unwrap() = Some(is_encrypted);
+    }
+
+    /// Unlock encrypted secrets with a passphrase
+    pub async fn unlock(self, passphrase: &[u8]) -> Result<Self> {
+        let row = self
+            .get_encryption_row()
+            .await?
+            .ok_or(VaultError::SecretsNotEncrypted)?;
+        let key_encryption_key = row.parameters()?.derive_key(passphrase)?;
+        let key = SecretsEncryptionKey::unwrap(&key_encryption_key, &row.wrapped_key)?;
+        self.set_encrypted(true);
+        Ok(Self {
+            database: self.database,
+            encryption_key: Some(Arc::new(key)),
+            is_encrypted: self.is_encrypted,
+        })
+    }
+
+    /// Encrypt all the secrets currently stored in plain text with a new random key,
+    /// protected by a passphrase, and return an unlocked repository.
+    ///
+    /// The secrets stored afterwards are encrypted with the same key.
+    pub async fn encrypt(
+        &self,
+        passphrase: &[u8],
+        parameters: KeyDerivationParameters,
+    ) -> Result<Self> {
+        let key = SecretsEncryptionKey::generate();
+        let wrapped_key = key.wrap(&parameters.derive_key(passphrase)?)?;
+
+        // the secrets must not be encrypted by another process between the check and the update
+        let mut transaction = self.database.begin().await.into_core()?;
+        if Self::is_encrypted_in(&mut transaction).await? {
+            self.set_encrypted(true);
+            return Err(VaultError::SecretsAlreadyEncrypted)?;
+        }
+        for table in [SIGNING_SECRET_TABLE, X25519_SECRET_TABLE, AEAD_SECRET_TABLE] {
+            let select = format!("SELECT handle, secret FROM {table}");
+            let rows: Vec<X25519SecretRow> = query_as(&select)
+                .fetch_all(&mut *transaction)
+                .await
+                .into_core()?;
+            let update = format!("UPDATE {table} SET secret = $1 WHERE handle = $2");
+            for row in rows.iter() {
+                let secret = key.encrypt(&secret_aad(table, &row.handle), &row.secret)?;
+                query(&update)
+                    .bind(secret)
+                    .bind(row.handle.clone())
+                    .execute(&mut *transaction)
+                    .await
+                    .void()?;
+            }
+        }
+
+        let query = query(
+            r#"
+            INSERT INTO secrets_encryption (id, salt, memory_cost, time_cost, parallelism, wrapped_key)
+            VALUES ($1, $2, $3, $4, $5, $6)"#,
+        )
+        .bind(ENCRYPTION_KEY_ID)
+        .bind(parameters.salt())
+        .bind(parameters.memory_cost() as i64)
+        .bind(parameters.time_cost() as i64)
+        .bind(parameters.parallelism() as i64)
+        .bind(wrapped_key);
+        query.execute(&mut *transaction).await.void()?;
+        transaction.commit().await.void()?;
+        self.set_encrypted(true);
+
+        Ok(Self {
+            database: self.database.clone(),
+            encryption_key: Some(Arc::new(key)),
+            is_encrypted: self.is_encrypted.clone(),
+        })
+    }
+
+    /// Change the passphrase protecting the secrets.
+    /// The secrets themselves are not re-encrypted
+    pub async fn change_passphrase(
+        &self,
+        passphrase: &[u8],
+        new_passphrase: &[u8],
+        parameters: KeyDerivationParameters,
+    ) -> Result<()> {
+        let row = self
+            .get_encryption_row()
+            .await?
+            .ok_or(VaultError::SecretsNotEncrypted)?;
+        let key_encryption_key = row.parameters()?.derive_key(passphrase)?;
+        let key = SecretsEncryptionKey::unwrap(&key_encryption_key, &row.wrapped_key)?;
+        let wrapped_key = key.wrap(&parameters.derive_key(new_passphrase)?)?;
+
+        let query = query(
+            r#"
+            UPDATE secrets_encryption
+            SET salt = $1, memory_cost = $2, time_cost = $3, parallelism = $4, wrapped_key = $5
+            WHERE id = $6"#,
+        )
+        .bind(parameters.salt())
+        .bind(parameters.memory_cost() as i64)
+        .bind(parameters.time_cost() as i64)
+        .bind(parameters.parallelism() as i64)
+        .bind(wrapped_key)
+        .bind(ENCRYPTION_KEY_ID);
+        query.execute(&*self.database.pool).await.void()
+    }
+
+    async fn get_encryption_row(&self) -> Result<Option<SecretsEncryptionRow>> {
+        let query = query_as(
+            "SELECT salt, memory_cost, time_cost, parallelism, wrapped_key FROM secrets_encryption WHERE id = $1",
+        )
+        .bind(ENCRYPTION_KEY_ID);
+        query.fetch_optional(&*self.database.pool).await.into_core()
+    }
+
+    /// Check if the secrets are encrypted within a transaction
+    async fn is_encrypted_in(transaction: &mut Transaction<'static, Any>) -> Result<bool> {
+        let row: Option<(i64,)> = query_as("SELECT id FROM secrets_encryption WHERE id = $1")
+            .bind(ENCRYPTION_KEY_ID)
+            .fetch_optional(&mut **transaction)
+            .await
+            .into_core()?;
+        Ok(row.is_some())
+    }
+
+    /// Store a secret, encrypted if the secrets are encrypted.
+    /// A plain text secret is only stored if the secrets are still not encrypted
+    async fn store_secret<'q>(
+        &self,
+        table: &str,
+        handle: &[u8],
+        secret: &[u8],
+        make_query: impl FnOnce(Vec<u8>) -> Query<'q, Any, AnyArguments<'q>>,
+    ) -> Result<()> {
+        if let Some(key) = self.get_encryption_key().await? {
+            let secret = key.encrypt(&secret_aad(table, handle), secret)?;
+            return make_query(secret)
+                .execute(&*self.database.pool)
+                .await
+                .void();
+        }
+
+        let mut transaction = self.database.begin().await.into_core()?;
+        if Self::is_encrypted_in(&mut transaction).await? {
+            self.set_encrypted(true);
+            return Err(VaultError::SecretsLocked)?;
+        }
+        make_query(secret.to_vec())
+            .execute(&mut *transaction)
+            .await
+            .void()?;
+        transaction.commit().await.void()
+    }
+
+    /// Return the key used to encrypt secrets if the secrets are encrypted.
+    /// Fail if the secrets are encrypted but the repository has not been unlocked
+    async fn get_encryption_key(&self) -> Result<Option<&SecretsEncryptionKey>> {
+        match &self.encryption_key {
+            Some(key) => Ok(Some(key.as_ref())),
+            None if self.is_encrypted().await? => Err(VaultError::SecretsLocked)?,
+            None => Ok(None),
+        }
+    }
+
+    /// Decrypt a stored secret, if the secrets are encrypted
+    async fn decode_secret(
+        &self,
+        table: &str,
+        handle: &[u8],
+        secret: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>> {
+        match self.get_encryption_key().await? {
+            Some(key) => key.decrypt(&secret_aad(table, handle), secret),
+            None => Ok(Zeroizing::new(secret.to_vec())),
+        }
+    }
+}
+
+/// Each encrypted secret is bound to its table and handle
+fn secret_aad(table: &str, handle: &[u8]) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(table.len() + 1 + handle.len());
+    aad.extend_from_slice(table.as_bytes());
+    aad.push(0);
+    aad.extend_from_slice(handle);
+    aad
+}
+
 const ED_DSA_CURVE_25519: &str = "EdDSACurve25519";
 const EC_DSA_SHA256_CURVE_P256: &str = "ECDSASHA256CurveP256";
 
+const SIGNING_SECRET_TABLE: &str = "signing_secret";
+const X25519_SECRET_TABLE: &str = "x25519_secret";
+const AEAD_SECRET_TABLE: &str = "aead_secret";
+const ENCRYPTION_KEY_ID: i64 = 1;
+
 #[async_trait]
 impl SecretsRepository for SecretsSqlxDatabase {
     async fn store_signing_secret(
@@ -63,17 +291,24 @@ impl SecretsRepository for SecretsSqlxDatabase {
             SigningSecretKeyHandle::ECDSASHA256CurveP256(_) => EC_DSA_SHA256_CURVE_P256.into(),
         };
 
-        let query = query(
-            r#"
+        self.store_secret(
+            SIGNING_SECRET_TABLE,
+            handle.handle().value(),
+            secret.key(),
+            |secret| {
+                query(
+                    r#"
             INSERT INTO signing_secret (handle, secret_type, secret)
             VALUES ($1, $2, $3)
             ON CONFLICT (handle)
             DO UPDATE SET secret_type = $2, secret = $3"#,
+                )
+                .bind(handle)
+                .bind(secret_type)
+                .bind(secret)
+            },
         )
-        .bind(handle)
-        .bind(secret_type)
-        .bind(secret);
-        query.execute(&*self.database.pool).await.void()
+        .await
     }
 
     async fn delete_signing_secret(&self, handle: &SigningSecretKeyHandle) -> Result<bool> {
@@ -94,7 +329,14 @@ impl SecretsRepository for SecretsSqlxDatabase {
             .fetch_optional(&*self.database.pool)
             .await
             .into_core()?;
-        Ok(row.map(|r| r.signing_secret()).transpose()?)
+        let Some(mut row) = row else {
+            return Ok(None);
+        };
+        row.secret = self
+            .decode_secret(SIGNING_SECRET_TABLE, &row.handle, &row.secret)
+            .await?
+            .to_vec();
+        Ok(Some(row.signing_secret()?))
     }
 
     async fn get_signing_secret_handles(&self) -> Result<Vec<SigningSecretKeyHandle>> {
@@ -112,16 +354,23 @@ impl SecretsRepository for SecretsSqlxDatabase {
         handle: &X25519SecretKeyHandle,
         secret: X25519SecretKey,
     ) -> Result<()> {
-        let query = query(
-            r#"
+        self.store_secret(
+            X25519_SECRET_TABLE,
+            handle.0.value(),
+            secret.key(),
+            |secret| {
+                query(
+                    r#"
         INSERT INTO x25519_secret (handle, secret)
         VALUES ($1, $2)
         ON CONFLICT (handle)
         DO UPDATE SET secret = $2"#,
+                )
+                .bind(handle)
+                .bind(secret)
+            },
         )
-        .bind(handle)
-        .bind(secret);
-        query.execute(&*self.database.pool).await.void()
+        .await
     }
 
     async fn delete_x25519_secret(&self, handle: &X25519SecretKeyHandle) -> Result<bool> {
@@ -141,7 +390,14 @@ impl SecretsRepository for SecretsSqlxDatabase {
             .fetch_optional(&*self.database.pool)
             .await
             .into_core()?;
-        Ok(row.map(|r| r.x25519_secret()).transpose()?)
+        let Some(mut row) = row else {
+            return Ok(None);
+        };
+        row.secret = self
+            .decode_secret(X25519_SECRET_TABLE, &row.handle, &row.secret)
+            .await?
+            .to_vec();
+        Ok(Some(row.x25519_secret()?))
     }
 
     async fn get_x25519_secret_handles(&self) -> Result<Vec<X25519SecretKeyHandle>> {
@@ -158,17 +414,24 @@ impl SecretsRepository for SecretsSqlxDatabase {
         handle: &AeadSecretKeyHandle,
         secret: AeadSecret,
     ) -> Result<()> {
-        let query = query(
-            r#"
+        self.store_secret(
+            AEAD_SECRET_TABLE,
+            handle.0 .0.value(),
+            &secret.0,
+            |secret| {
+                query(
+                    r#"
                 INSERT INTO aead_secret (handle, type, secret)
                 VALUES ($1, $2, $3)
                 ON CONFLICT (handle)
                 DO UPDATE SET type = $2, secret = $3"#,
+                )
+                .bind(handle)
+                .bind(AEAD_TYPE)
+                .bind(secret)
+            },
         )
-        .bind(handle)
-        .bind(AEAD_TYPE)
-        .bind(secret);
-        query.execute(&*self.database.pool).await.void()
+        .await
     }
 
     async fn delete_aead_secret(&self, handle: &AeadSecretKeyHandle) -> Result<bool> {
@@ -186,7 +449,14 @@ impl SecretsRepository for SecretsSqlxDatabase {
             .fetch_optional(&*self.database.pool)
             .await
             .into_core()?;
-        Ok(row.map(|r| r.aead_secret()).transpose()?)
+        let Some(mut row) = row else {
+            return Ok(None);
+        };
+        row.secret = self
+            .decode_secret(AEAD_SECRET_TABLE, handle.0 .0.value(), &row.secret)
+            .await?
+            .to_vec();
+        Ok(Some(row.aead_secret()?))
     }
 
     async fn delete_all(&self) -> Result<()> {
@@ -380,6 +650,28 @@ impl AeadSecretRow {
     }
 }
 
+#[derive(FromRow)]
+struct SecretsEncryptionRow {
+    salt: Vec<u8>,
+    memory_cost: i64,
+    time_cost: i64,
+    parallelism: i64,
+    wrapped_key: Vec<u8>,
+}
+
+impl SecretsEncryptionRow {
+    fn parameters(&self) -> Result<KeyDerivationParameters> {
+        let to_u32 =
+            |v: i64| u32::try_from(v).map_err(|_| VaultError::InvalidKeyDerivationParameters);
+        Ok(KeyDerivationParameters::new(
+            self.salt.clone(),
+            to_u32(self.memory_cost)?,
+            to_u32(self.time_cost)?,
+            to_u32(self.parallelism)?,
+        ))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -481,6 +773,112 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_encrypted_secrets_repository() -> Result<()> {
+        let database = SqlxDatabase::in_memory("secrets").await?;
+        let repository = SecretsSqlxDatabase::new(database.clone());
+
+        // store a secret before the encryption
+        let handle1 = X25519SecretKeyHandle(HandleToSecret::new(vec![1, 2, 3]));
+        let secret1 = X25519SecretKey::new([1; 32]);
+        repository
+            .store_x25519_secret(&handle1, secret1.clone())
+            .await?;
+        assert!(!repository.is_encrypted().await?);
+
+        // encrypt the existing secrets and store new ones
+        let unlocked = repository
+            .encrypt(b"passphrase", KeyDerivationParameters::generate(64, 1, 1))
+            .await?;
+        assert!(repository.is_encrypted().await?);
+
+        let handle2 = SigningSecretKeyHandle::EdDSACurve25519(HandleToSecret::new(vec![4, 5, 6]));
+        let secret2 = SigningSecret::EdDSACurve25519(EdDSACurve25519SecretKey::new([2; 32]));
+        unlocked
+            .store_signing_secret(&handle2, secret2.clone())
+            .await?;
+
+        // the secrets are not stored in plain text anymore
+        let stored: Vec<u8> = query_scalar("SELECT secret FROM x25519_secret")
+            .fetch_one(&*database.pool)
+            .await
+            .into_core()?;
+        assert_ne!(stored, secret1.key().to_vec());
+
+        // a locked repository can neither read nor write secrets
+        let locked = SecretsSqlxDatabase::new(database.clone());
+        assert!(locked.get_x25519_secret(&handle1).await.is_err());
+        assert!(locked
+            .store_x25519_secret(&handle1, secret1.clone())
+            .await
+            .is_err());
+
+        // the repository can only be unlocked with the right passphrase
+        assert!(SecretsSqlxDatabase::new(database.clone())
+            .unlock(b"incorrect")
+            .await
+            .is_err());
+        let unlocked = locked.unlock(b"passphrase").await?;
+        assert!(unlocked.get_x25519_secret(&handle1).await? == Some(secret1.clone()));
+        assert!(unlocked.get_signing_secret(&handle2).await? == Some(secret2.clone()));
+
+        // the passphrase can be changed
+        unlocked
+            .change_passphrase(
+                b"passphrase",
+                b"new passphrase",
+                KeyDerivationParameters::generate(64, 1, 1),
+            )
+            .await?;
+        assert!(SecretsSqlxDatabase::new(database.clone())
+            .unlock(b"passphrase")
+            .await
+            .is_err());
+        let unlocked = SecretsSqlxDatabase::new(database)
+            .unlock(b"new passphrase")
+            .await?;
+        assert!(unlocked.get_x25519_secret(&handle1).await? == Some(secret1));
+        assert!(unlocked.get_signing_secret(&handle2).await? == Some(secret2));
+
+        // the secrets cannot be encrypted twice
+        assert!(unlocked
+            .encrypt(b"passphrase", KeyDerivationParameters::generate(64, 1, 1))
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_no_plain_text_secret_is_stored_once_encrypted_elsewhere() -> Result<()> {
+        let database = SqlxDatabase::in_memory("secrets").await?;
+
+        // this repository caches the fact that the secrets are not encrypted
+        let repository = SecretsSqlxDatabase::new(database.clone());
+        assert!(!repository.is_encrypted().await?);
+
+        // the secrets are encrypted by another repository
+        SecretsSqlxDatabase::new(database.clone())
+            .encrypt(b"passphrase", KeyDerivationParameters::generate(64, 1, 1))
+            .await?;
+
+        // a secret can not be stored in plain text anymore
+        let handle = X25519SecretKeyHandle(HandleToSecret::new(vec![1, 2, 3]));
+        assert!(repository
+            .store_x25519_secret(&handle, X25519SecretKey::new([1; 32]))
+            .await
+            .is_err());
+        assert!(repository.is_encrypted().await?);
+
+        // and the secrets can not be encrypted again
+        assert!(SecretsSqlxDatabase::new(database)
+            .encrypt(b"passphrase", KeyDerivationParameters::generate(64, 1, 1))
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
     /// HELPERS
     async fn create_repository() -> Result<Arc<dyn SecretsRepository>> {
         Ok(Arc::new(SecretsSqlxDatabase::create().await?))
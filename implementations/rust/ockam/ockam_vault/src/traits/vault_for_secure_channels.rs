@@ -1,6 +1,7 @@
 use crate::{
-    AeadSecretKeyHandle, HashOutput, HkdfOutput, SecretBufferHandle, X25519PublicKey,
-    X25519SecretKeyHandle,
+    AeadSecretKeyHandle, HashOutput, HkdfOutput, MLKEM768Ciphertext,
+    MLKEM768DecapsulationKeyHandle, MLKEM768EncapsulationKey, SecretBufferHandle, VaultError,
+    X25519PublicKey, X25519SecretKeyHandle,
 };
 
 use ockam_core::compat::vec::Vec;
@@ -64,6 +65,13 @@ pub trait VaultForSecureChannels: Send + Sync + 'static {
     /// Load an AEAD key from the storage.
     async fn load_aead_key(&self, secret_key_handle: &AeadSecretKeyHandle) -> Result<()>;
 
+    /// Delete a persisted AEAD key from the storage.
+    /// The ephemeral copy of that key, if any, is not deleted.
+    async fn delete_persisted_aead_key(
+        &self,
+        secret_key_handle: &AeadSecretKeyHandle,
+    ) -> Result<bool>;
+
     /// Generate a fresh static (persisted) X25519 Key.
     async fn generate_static_x25519_secret_key(&self) -> Result<X25519SecretKeyHandle>;
 
@@ -108,4 +116,50 @@ pub trait VaultForSecureChannels: Send + Sync + 'static {
 
     /// Delete AEAD Key.
     async fn delete_aead_secret_key(&self, secret_key_handle: AeadSecretKeyHandle) -> Result<bool>;
+
+    /// Generate a fresh ephemeral (not persisted) ML-KEM-768 Decapsulation Key.
+    ///
+    /// The ML-KEM-768 functions are optional: a Vault which doesn't support them returns
+    /// [`VaultError::MlKemNotSupported`], in which case a secure channel preferring a hybrid
+    /// key exchange falls back to the classic key exchange.
+    async fn generate_ephemeral_ml_kem_768_decapsulation_key(
+        &self,
+    ) -> Result<MLKEM768DecapsulationKeyHandle> {
+        Err(VaultError::MlKemNotSupported)?
+    }
+
+    /// Delete ephemeral ML-KEM-768 Decapsulation Key.
+    async fn delete_ephemeral_ml_kem_768_decapsulation_key(
+        &self,
+        _decapsulation_key_handle: MLKEM768DecapsulationKeyHandle,
+    ) -> Result<bool> {
+        Err(VaultError::MlKemNotSupported)?
+    }
+
+    /// Get [`MLKEM768EncapsulationKey`] of the corresponding ML-KEM-768 Decapsulation Key given
+    /// its Handle.
+    async fn get_ml_kem_768_encapsulation_key(
+        &self,
+        _decapsulation_key_handle: &MLKEM768DecapsulationKeyHandle,
+    ) -> Result<MLKEM768EncapsulationKey> {
+        Err(VaultError::MlKemNotSupported)?
+    }
+
+    /// Perform ML-KEM-768 encapsulation to the peer [`MLKEM768EncapsulationKey`].
+    /// Return the ciphertext to send to the peer and a handle to the shared secret.
+    async fn ml_kem_768_encapsulate(
+        &self,
+        _peer_encapsulation_key: &MLKEM768EncapsulationKey,
+    ) -> Result<(MLKEM768Ciphertext, SecretBufferHandle)> {
+        Err(VaultError::MlKemNotSupported)?
+    }
+
+    /// Perform ML-KEM-768 decapsulation of a ciphertext sent by the peer.
+    async fn ml_kem_768_decapsulate(
+        &self,
+        _decapsulation_key_handle: &MLKEM768DecapsulationKeyHandle,
+        _ciphertext: &MLKEM768Ciphertext,
+    ) -> Result<SecretBufferHandle> {
+        Err(VaultError::MlKemNotSupported)?
+    }
 }
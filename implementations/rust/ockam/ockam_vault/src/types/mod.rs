@@ -1,9 +1,11 @@
 mod hashes;
+mod kem;
 mod public_keys;
 mod secrets;
 mod signatures;
 
 pub use hashes::*;
+pub use kem::*;
 pub use public_keys::*;
 pub use secrets::*;
 pub use signatures::*;
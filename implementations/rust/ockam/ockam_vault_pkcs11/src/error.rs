@@ -0,0 +1,38 @@
+use core::ffi::c_ulong;
+use ockam_core::errcode::{Kind, Origin};
+use thiserror::Error;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("cannot load the PKCS#11 module {module}: {error}")]
+    LoadModule { module: String, error: String },
+    #[error("PKCS#11 function {function} failed with the error code {code:#x}")]
+    Function {
+        function: &'static str,
+        code: c_ulong,
+    },
+    #[error("PKCS#11 function {0} is not provided by the module")]
+    MissingFunction(&'static str),
+    #[error("no PKCS#11 token was found")]
+    TokenNotFound,
+    #[error("key was not found")]
+    KeyNotFound,
+    #[error("key type is not supported")]
+    UnsupportedKeyType,
+    #[error("public key returned by the token is incorrect")]
+    InvalidPublicKey,
+    #[error("signature returned by the token is incorrect")]
+    InvalidSignature,
+    #[error("shared secret returned by the token is incorrect")]
+    InvalidSharedSecret,
+    #[error("PKCS#11 call could not complete: {0}")]
+    BlockingCall(String),
+}
+
+impl From<Error> for ockam_core::Error {
+    #[track_caller]
+    fn from(e: Error) -> Self {
+        ockam_core::Error::new(Origin::Vault, Kind::Io, e)
+    }
+}
@@ -0,0 +1,317 @@
+//! Minimal subset of the PKCS#11 v2.40 C interface used by this vault.
+//! Structures are packed on Windows, as required by the specification
+#![allow(non_camel_case_types, non_snake_case, dead_code, missing_docs)]
+
+use core::ffi::{c_uchar, c_ulong, c_void};
+
+pub type CK_BYTE = c_uchar;
+pub type CK_BBOOL = CK_BYTE;
+pub type CK_ULONG = c_ulong;
+pub type CK_RV = CK_ULONG;
+pub type CK_FLAGS = CK_ULONG;
+pub type CK_SLOT_ID = CK_ULONG;
+pub type CK_SESSION_HANDLE = CK_ULONG;
+pub type CK_OBJECT_HANDLE = CK_ULONG;
+pub type CK_OBJECT_CLASS = CK_ULONG;
+pub type CK_KEY_TYPE = CK_ULONG;
+pub type CK_ATTRIBUTE_TYPE = CK_ULONG;
+pub type CK_MECHANISM_TYPE = CK_ULONG;
+pub type CK_USER_TYPE = CK_ULONG;
+pub type CK_EC_KDF_TYPE = CK_ULONG;
+
+pub const CK_TRUE: CK_BBOOL = 1;
+pub const CK_FALSE: CK_BBOOL = 0;
+
+// Return values
+pub const CKR_OK: CK_RV = 0x0000_0000;
+pub const CKR_BUFFER_TOO_SMALL: CK_RV = 0x0000_0150;
+pub const CKR_USER_ALREADY_LOGGED_IN: CK_RV = 0x0000_0100;
+pub const CKR_CRYPTOKI_ALREADY_INITIALIZED: CK_RV = 0x0000_0191;
+
+// Flags
+pub const CKF_RW_SESSION: CK_FLAGS = 0x0000_0002;
+pub const CKF_SERIAL_SESSION: CK_FLAGS = 0x0000_0004;
+pub const CKF_OS_LOCKING_OK: CK_FLAGS = 0x0000_0002;
+
+// User types
+pub const CKU_USER: CK_USER_TYPE = 1;
+
+// Object classes
+pub const CKO_PUBLIC_KEY: CK_OBJECT_CLASS = 0x0000_0002;
+pub const CKO_PRIVATE_KEY: CK_OBJECT_CLASS = 0x0000_0003;
+pub const CKO_SECRET_KEY: CK_OBJECT_CLASS = 0x0000_0004;
+
+// Key types
+pub const CKK_EC: CK_KEY_TYPE = 0x0000_0003;
+pub const CKK_GENERIC_SECRET: CK_KEY_TYPE = 0x0000_0010;
+pub const CKK_EC_EDWARDS: CK_KEY_TYPE = 0x0000_0040;
+pub const CKK_EC_MONTGOMERY: CK_KEY_TYPE = 0x0000_0041;
+
+// Attributes
+pub const CKA_CLASS: CK_ATTRIBUTE_TYPE = 0x0000_0000;
+pub const CKA_TOKEN: CK_ATTRIBUTE_TYPE = 0x0000_0001;
+pub const CKA_PRIVATE: CK_ATTRIBUTE_TYPE = 0x0000_0002;
+pub const CKA_LABEL: CK_ATTRIBUTE_TYPE = 0x0000_0003;
+pub const CKA_VALUE: CK_ATTRIBUTE_TYPE = 0x0000_0011;
+pub const CKA_KEY_TYPE: CK_ATTRIBUTE_TYPE = 0x0000_0100;
+pub const CKA_ID: CK_ATTRIBUTE_TYPE = 0x0000_0102;
+pub const CKA_SENSITIVE: CK_ATTRIBUTE_TYPE = 0x0000_0103;
+pub const CKA_SIGN: CK_ATTRIBUTE_TYPE = 0x0000_0108;
+pub const CKA_VERIFY: CK_ATTRIBUTE_TYPE = 0x0000_010A;
+pub const CKA_DERIVE: CK_ATTRIBUTE_TYPE = 0x0000_010C;
+pub const CKA_VALUE_LEN: CK_ATTRIBUTE_TYPE = 0x0000_0161;
+pub const CKA_EXTRACTABLE: CK_ATTRIBUTE_TYPE = 0x0000_0162;
+pub const CKA_EC_PARAMS: CK_ATTRIBUTE_TYPE = 0x0000_0180;
+pub const CKA_EC_POINT: CK_ATTRIBUTE_TYPE = 0x0000_0181;
+
+// Mechanisms
+pub const CKM_EC_KEY_PAIR_GEN: CK_MECHANISM_TYPE = 0x0000_1040;
+pub const CKM_ECDSA: CK_MECHANISM_TYPE = 0x0000_1041;
+pub const CKM_ECDH1_DERIVE: CK_MECHANISM_TYPE = 0x0000_1050;
+pub const CKM_EC_EDWARDS_KEY_PAIR_GEN: CK_MECHANISM_TYPE = 0x0000_1055;
+pub const CKM_EC_MONTGOMERY_KEY_PAIR_GEN: CK_MECHANISM_TYPE = 0x0000_1056;
+pub const CKM_EDDSA: CK_MECHANISM_TYPE = 0x0000_1057;
+
+// Key derivation functions
+pub const CKD_NULL: CK_EC_KDF_TYPE = 0x0000_0001;
+
+/// DER encoding of the secp256r1 curve OID
+pub const EC_PARAMS_P256: &[u8] = &[0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+/// DER encoding of the Ed25519 curve OID
+pub const EC_PARAMS_ED25519: &[u8] = &[0x06, 0x03, 0x2b, 0x65, 0x70];
+/// DER encoding of the X25519 curve OID
+pub const EC_PARAMS_X25519: &[u8] = &[0x06, 0x03, 0x2b, 0x65, 0x6e];
+
+#[cfg_attr(windows, repr(C, packed(1)))]
+#[cfg_attr(not(windows), repr(C))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CK_VERSION {
+    pub major: CK_BYTE,
+    pub minor: CK_BYTE,
+}
+
+#[cfg_attr(windows, repr(C, packed(1)))]
+#[cfg_attr(not(windows), repr(C))]
+pub struct CK_ATTRIBUTE {
+    pub type_: CK_ATTRIBUTE_TYPE,
+    pub pValue: *mut c_void,
+    pub ulValueLen: CK_ULONG,
+}
+
+#[cfg_attr(windows, repr(C, packed(1)))]
+#[cfg_attr(not(windows), repr(C))]
+pub struct CK_MECHANISM {
+    pub mechanism: CK_MECHANISM_TYPE,
+    pub pParameter: *mut c_void,
+    pub ulParameterLen: CK_ULONG,
+}
+
+#[cfg_attr(windows, repr(C, packed(1)))]
+#[cfg_attr(not(windows), repr(C))]
+pub struct CK_ECDH1_DERIVE_PARAMS {
+    pub kdf: CK_EC_KDF_TYPE,
+    pub ulSharedDataLen: CK_ULONG,
+    pub pSharedData: *mut CK_BYTE,
+    pub ulPublicDataLen: CK_ULONG,
+    pub pPublicData: *mut CK_BYTE,
+}
+
+#[cfg_attr(windows, repr(C, packed(1)))]
+#[cfg_attr(not(windows), repr(C))]
+pub struct CK_C_INITIALIZE_ARGS {
+    pub CreateMutex: *mut c_void,
+    pub DestroyMutex: *mut c_void,
+    pub LockMutex: *mut c_void,
+    pub UnlockMutex: *mut c_void,
+    pub flags: CK_FLAGS,
+    pub pReserved: *mut c_void,
+}
+
+#[cfg_attr(windows, repr(C, packed(1)))]
+#[cfg_attr(not(windows), repr(C))]
+pub struct CK_TOKEN_INFO {
+    pub label: [CK_BYTE; 32],
+    pub manufacturerID: [CK_BYTE; 32],
+    pub model: [CK_BYTE; 16],
+    pub serialNumber: [CK_BYTE; 16],
+    pub flags: CK_FLAGS,
+    pub ulMaxSessionCount: CK_ULONG,
+    pub ulSessionCount: CK_ULONG,
+    pub ulMaxRwSessionCount: CK_ULONG,
+    pub ulRwSessionCount: CK_ULONG,
+    pub ulMaxPinLen: CK_ULONG,
+    pub ulMinPinLen: CK_ULONG,
+    pub ulTotalPublicMemory: CK_ULONG,
+    pub ulFreePublicMemory: CK_ULONG,
+    pub ulTotalPrivateMemory: CK_ULONG,
+    pub ulFreePrivateMemory: CK_ULONG,
+    pub hardwareVersion: CK_VERSION,
+    pub firmwareVersion: CK_VERSION,
+    pub utcTime: [CK_BYTE; 16],
+}
+
+/// Placeholder for the functions of the list which are not used by this vault
+type CK_UNUSED = Option<unsafe extern "C" fn()>;
+
+pub type C_GetFunctionList =
+    unsafe extern "C" fn(ppFunctionList: *mut *mut CK_FUNCTION_LIST) -> CK_RV;
+
+/// Table of the PKCS#11 functions, in the order defined by the specification
+#[cfg_attr(windows, repr(C, packed(1)))]
+#[cfg_attr(not(windows), repr(C))]
+pub struct CK_FUNCTION_LIST {
+    pub version: CK_VERSION,
+    pub C_Initialize: Option<unsafe extern "C" fn(pInitArgs: *mut c_void) -> CK_RV>,
+    pub C_Finalize: Option<unsafe extern "C" fn(pReserved: *mut c_void) -> CK_RV>,
+    pub C_GetInfo: CK_UNUSED,
+    pub C_GetFunctionList: CK_UNUSED,
+    pub C_GetSlotList: Option<
+        unsafe extern "C" fn(
+            tokenPresent: CK_BBOOL,
+            pSlotList: *mut CK_SLOT_ID,
+            pulCount: *mut CK_ULONG,
+        ) -> CK_RV,
+    >,
+    pub C_GetSlotInfo: CK_UNUSED,
+    pub C_GetTokenInfo:
+        Option<unsafe extern "C" fn(slotID: CK_SLOT_ID, pInfo: *mut CK_TOKEN_INFO) -> CK_RV>,
+    pub C_GetMechanismList: CK_UNUSED,
+    pub C_GetMechanismInfo: CK_UNUSED,
+    pub C_InitToken: CK_UNUSED,
+    pub C_InitPIN: CK_UNUSED,
+    pub C_SetPIN: CK_UNUSED,
+    pub C_OpenSession: Option<
+        unsafe extern "C" fn(
+            slotID: CK_SLOT_ID,
+            flags: CK_FLAGS,
+            pApplication: *mut c_void,
+            Notify: *mut c_void,
+            phSession: *mut CK_SESSION_HANDLE,
+        ) -> CK_RV,
+    >,
+    pub C_CloseSession: Option<unsafe extern "C" fn(hSession: CK_SESSION_HANDLE) -> CK_RV>,
+    pub C_CloseAllSessions: CK_UNUSED,
+    pub C_GetSessionInfo: CK_UNUSED,
+    pub C_GetOperationState: CK_UNUSED,
+    pub C_SetOperationState: CK_UNUSED,
+    pub C_Login: Option<
+        unsafe extern "C" fn(
+            hSession: CK_SESSION_HANDLE,
+            userType: CK_USER_TYPE,
+            pPin: *mut CK_BYTE,
+            ulPinLen: CK_ULONG,
+        ) -> CK_RV,
+    >,
+    pub C_Logout: CK_UNUSED,
+    pub C_CreateObject: CK_UNUSED,
+    pub C_CopyObject: CK_UNUSED,
+    pub C_DestroyObject: Option<
+        unsafe extern "C" fn(hSession: CK_SESSION_HANDLE, hObject: CK_OBJECT_HANDLE) -> CK_RV,
+    >,
+    pub C_GetObjectSize: CK_UNUSED,
+    pub C_GetAttributeValue: Option<
+        unsafe extern "C" fn(
+            hSession: CK_SESSION_HANDLE,
+            hObject: CK_OBJECT_HANDLE,
+            pTemplate: *mut CK_ATTRIBUTE,
+            ulCount: CK_ULONG,
+        ) -> CK_RV,
+    >,
+    pub C_SetAttributeValue: Option<
+        unsafe extern "C" fn(
+            hSession: CK_SESSION_HANDLE,
+            hObject: CK_OBJECT_HANDLE,
+            pTemplate: *mut CK_ATTRIBUTE,
+            ulCount: CK_ULONG,
+        ) -> CK_RV,
+    >,
+    pub C_FindObjectsInit: Option<
+        unsafe extern "C" fn(
+            hSession: CK_SESSION_HANDLE,
+            pTemplate: *mut CK_ATTRIBUTE,
+            ulCount: CK_ULONG,
+        ) -> CK_RV,
+    >,
+    pub C_FindObjects: Option<
+        unsafe extern "C" fn(
+            hSession: CK_SESSION_HANDLE,
+            phObject: *mut CK_OBJECT_HANDLE,
+            ulMaxObjectCount: CK_ULONG,
+            pulObjectCount: *mut CK_ULONG,
+        ) -> CK_RV,
+    >,
+    pub C_FindObjectsFinal: Option<unsafe extern "C" fn(hSession: CK_SESSION_HANDLE) -> CK_RV>,
+    pub C_EncryptInit: CK_UNUSED,
+    pub C_Encrypt: CK_UNUSED,
+    pub C_EncryptUpdate: CK_UNUSED,
+    pub C_EncryptFinal: CK_UNUSED,
+    pub C_DecryptInit: CK_UNUSED,
+    pub C_Decrypt: CK_UNUSED,
+    pub C_DecryptUpdate: CK_UNUSED,
+    pub C_DecryptFinal: CK_UNUSED,
+    pub C_DigestInit: CK_UNUSED,
+    pub C_Digest: CK_UNUSED,
+    pub C_DigestUpdate: CK_UNUSED,
+    pub C_DigestKey: CK_UNUSED,
+    pub C_DigestFinal: CK_UNUSED,
+    pub C_SignInit: Option<
+        unsafe extern "C" fn(
+            hSession: CK_SESSION_HANDLE,
+            pMechanism: *mut CK_MECHANISM,
+            hKey: CK_OBJECT_HANDLE,
+        ) -> CK_RV,
+    >,
+    pub C_Sign: Option<
+        unsafe extern "C" fn(
+            hSession: CK_SESSION_HANDLE,
+            pData: *mut CK_BYTE,
+            ulDataLen: CK_ULONG,
+            pSignature: *mut CK_BYTE,
+            pulSignatureLen: *mut CK_ULONG,
+        ) -> CK_RV,
+    >,
+    pub C_SignUpdate: CK_UNUSED,
+    pub C_SignFinal: CK_UNUSED,
+    pub C_SignRecoverInit: CK_UNUSED,
+    pub C_SignRecover: CK_UNUSED,
+    pub C_VerifyInit: CK_UNUSED,
+    pub C_Verify: CK_UNUSED,
+    pub C_VerifyUpdate: CK_UNUSED,
+    pub C_VerifyFinal: CK_UNUSED,
+    pub C_VerifyRecoverInit: CK_UNUSED,
+    pub C_VerifyRecover: CK_UNUSED,
+    pub C_DigestEncryptUpdate: CK_UNUSED,
+    pub C_DecryptDigestUpdate: CK_UNUSED,
+    pub C_SignEncryptUpdate: CK_UNUSED,
+    pub C_DecryptVerifyUpdate: CK_UNUSED,
+    pub C_GenerateKey: CK_UNUSED,
+    pub C_GenerateKeyPair: Option<
+        unsafe extern "C" fn(
+            hSession: CK_SESSION_HANDLE,
+            pMechanism: *mut CK_MECHANISM,
+            pPublicKeyTemplate: *mut CK_ATTRIBUTE,
+            ulPublicKeyAttributeCount: CK_ULONG,
+            pPrivateKeyTemplate: *mut CK_ATTRIBUTE,
+            ulPrivateKeyAttributeCount: CK_ULONG,
+            phPublicKey: *mut CK_OBJECT_HANDLE,
+            phPrivateKey: *mut CK_OBJECT_HANDLE,
+        ) -> CK_RV,
+    >,
+    pub C_WrapKey: CK_UNUSED,
+    pub C_UnwrapKey: CK_UNUSED,
+    pub C_DeriveKey: Option<
+        unsafe extern "C" fn(
+            hSession: CK_SESSION_HANDLE,
+            pMechanism: *mut CK_MECHANISM,
+            hBaseKey: CK_OBJECT_HANDLE,
+            pTemplate: *mut CK_ATTRIBUTE,
+            ulAttributeCount: CK_ULONG,
+            phKey: *mut CK_OBJECT_HANDLE,
+        ) -> CK_RV,
+    >,
+    pub C_SeedRandom: CK_UNUSED,
+    pub C_GenerateRandom: CK_UNUSED,
+    pub C_GetFunctionStatus: CK_UNUSED,
+    pub C_CancelFunction: CK_UNUSED,
+    pub C_WaitForSlotEvent: CK_UNUSED,
+}
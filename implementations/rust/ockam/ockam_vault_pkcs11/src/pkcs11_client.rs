@@ -0,0 +1,609 @@
+#![allow(unsafe_code)]
+
+use crate::error::Error;
+use crate::pkcs11::*;
+use core::ffi::c_void;
+use core::mem::size_of;
+use core::ptr;
+use libloading::Library;
+use ockam_core::Result;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing::{debug, warn};
+
+/// Configuration of a PKCS#11 token
+#[derive(Clone)]
+pub struct Pkcs11Config {
+    module: PathBuf,
+    token_label: Option<String>,
+    pin: Option<String>,
+}
+
+impl core::fmt::Debug for Pkcs11Config {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Pkcs11Config")
+            .field("module", &self.module)
+            .field("token_label", &self.token_label)
+            .finish()
+    }
+}
+
+impl Pkcs11Config {
+    /// Create a configuration for the PKCS#11 module at the given path,
+    /// for example `/usr/lib/softhsm/libsofthsm2.so`
+    pub fn new(module: impl Into<PathBuf>) -> Self {
+        Self {
+            module: module.into(),
+            token_label: None,
+            pin: None,
+        }
+    }
+
+    /// Use the token with the given label. The first available token is used otherwise
+    pub fn with_token_label(mut self, token_label: impl Into<String>) -> Self {
+        self.token_label = Some(token_label.into());
+        self
+    }
+
+    /// Log in the token as a user with this PIN
+    pub fn with_pin(mut self, pin: impl Into<String>) -> Self {
+        self.pin = Some(pin.into());
+        self
+    }
+
+    /// Path to the PKCS#11 module
+    pub fn module(&self) -> &Path {
+        self.module.as_path()
+    }
+
+    /// Label of the token
+    pub fn token_label(&self) -> Option<&str> {
+        self.token_label.as_deref()
+    }
+}
+
+/// Types of key pairs which can be generated on a token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pkcs11KeyType {
+    /// Ed25519 key used for EdDSA signatures
+    EdDSACurve25519,
+    /// P-256 key used for ECDSA SHA256 signatures
+    ECDSASHA256CurveP256,
+    /// Curve25519 key used for X25519 key agreements
+    X25519,
+}
+
+impl Pkcs11KeyType {
+    fn key_type(&self) -> CK_KEY_TYPE {
+        match self {
+            Pkcs11KeyType::EdDSACurve25519 => CKK_EC_EDWARDS,
+            Pkcs11KeyType::ECDSASHA256CurveP256 => CKK_EC,
+            Pkcs11KeyType::X25519 => CKK_EC_MONTGOMERY,
+        }
+    }
+
+    fn generation_mechanism(&self) -> CK_MECHANISM_TYPE {
+        match self {
+            Pkcs11KeyType::EdDSACurve25519 => CKM_EC_EDWARDS_KEY_PAIR_GEN,
+            Pkcs11KeyType::ECDSASHA256CurveP256 => CKM_EC_KEY_PAIR_GEN,
+            Pkcs11KeyType::X25519 => CKM_EC_MONTGOMERY_KEY_PAIR_GEN,
+        }
+    }
+
+    fn ec_params(&self) -> &'static [u8] {
+        match self {
+            Pkcs11KeyType::EdDSACurve25519 => EC_PARAMS_ED25519,
+            Pkcs11KeyType::ECDSASHA256CurveP256 => EC_PARAMS_P256,
+            Pkcs11KeyType::X25519 => EC_PARAMS_X25519,
+        }
+    }
+
+    fn public_key_length(&self) -> usize {
+        match self {
+            Pkcs11KeyType::EdDSACurve25519 => 32,
+            Pkcs11KeyType::ECDSASHA256CurveP256 => 65,
+            Pkcs11KeyType::X25519 => 32,
+        }
+    }
+}
+
+/// Client for a PKCS#11 token.
+/// All the operations are executed sequentially on a single logged-in session.
+/// The PKCS#11 functions block until the token answers, so async code must call them
+/// with [`Pkcs11Client::run`]
+pub struct Pkcs11Client {
+    functions: *const CK_FUNCTION_LIST,
+    session: Mutex<CK_SESSION_HANDLE>,
+    // The library must outlive the functions list
+    _library: Library,
+}
+
+// The PKCS#11 module is initialized with CKF_OS_LOCKING_OK and the session is protected by a mutex
+unsafe impl Send for Pkcs11Client {}
+unsafe impl Sync for Pkcs11Client {}
+
+macro_rules! call {
+    ($self:expr, $function:ident ( $($arg:expr),* )) => {{
+        let functions = unsafe { &*$self.functions };
+        match functions.$function {
+            Some(f) => {
+                let rv = unsafe { f($($arg),*) };
+                if rv == CKR_OK {
+                    Ok(())
+                } else {
+                    Err(Error::Function {
+                        function: stringify!($function),
+                        code: rv,
+                    })
+                }
+            }
+            None => Err(Error::MissingFunction(stringify!($function))),
+        }
+    }};
+}
+
+impl Pkcs11Client {
+    /// Load the PKCS#11 module, open a session on the token and log in
+    pub fn open(config: &Pkcs11Config) -> Result<Self> {
+        let load_error = |error: String| Error::LoadModule {
+            module: config.module.display().to_string(),
+            error,
+        };
+
+        let library =
+            unsafe { Library::new(&config.module) }.map_err(|e| load_error(e.to_string()))?;
+        let mut functions: *mut CK_FUNCTION_LIST = ptr::null_mut();
+        unsafe {
+            let get_function_list = library
+                .get::<C_GetFunctionList>(b"C_GetFunctionList\0")
+                .map_err(|e| load_error(e.to_string()))?;
+            let rv = get_function_list(&mut functions);
+            if rv != CKR_OK || functions.is_null() {
+                return Err(load_error(format!(
+                    "C_GetFunctionList failed with the error code {rv:#x}"
+                )))?;
+            }
+        }
+
+        let mut client = Self {
+            functions,
+            session: Mutex::new(0),
+            _library: library,
+        };
+
+        let mut init_args = CK_C_INITIALIZE_ARGS {
+            CreateMutex: ptr::null_mut(),
+            DestroyMutex: ptr::null_mut(),
+            LockMutex: ptr::null_mut(),
+            UnlockMutex: ptr::null_mut(),
+            flags: CKF_OS_LOCKING_OK,
+            pReserved: ptr::null_mut(),
+        };
+        match call!(
+            client,
+            C_Initialize(ptr::addr_of_mut!(init_args).cast::<c_void>())
+        ) {
+            Err(Error::Function { code, .. }) if code == CKR_CRYPTOKI_ALREADY_INITIALIZED => {
+                debug!("the PKCS#11 module is already initialized")
+            }
+            other => other?,
+        }
+
+        let slot = client.find_slot(config.token_label())?;
+        let mut session: CK_SESSION_HANDLE = 0;
+        call!(
+            client,
+            C_OpenSession(
+                slot,
+                CKF_SERIAL_SESSION | CKF_RW_SESSION,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                &mut session
+            )
+        )?;
+        client.session = Mutex::new(session);
+
+        if let Some(pin) = &config.pin {
+            let mut pin = pin.as_bytes().to_vec();
+            match call!(
+                client,
+                C_Login(session, CKU_USER, pin.as_mut_ptr(), pin.len() as CK_ULONG)
+            ) {
+                Err(Error::Function { code, .. }) if code == CKR_USER_ALREADY_LOGGED_IN => {}
+                other => other?,
+            }
+        }
+
+        Ok(client)
+    }
+
+    /// Load the PKCS#11 module and open a session on a thread reserved for blocking operations
+    pub async fn create(config: Pkcs11Config) -> Result<Arc<Self>> {
+        let client = tokio::task::spawn_blocking(move || Self::open(&config))
+            .await
+            .map_err(|e| Error::BlockingCall(e.to_string()))??;
+        Ok(Arc::new(client))
+    }
+
+    /// Run some calls to the token on a thread reserved for blocking operations,
+    /// so that they don't block the threads of the async runtime
+    pub async fn run<T, F>(self: &Arc<Self>, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Pkcs11Client) -> Result<T> + Send + 'static,
+    {
+        let client = self.clone();
+        tokio::task::spawn_blocking(move || f(&client))
+            .await
+            .map_err(|e| Error::BlockingCall(e.to_string()))?
+    }
+
+    /// Lock the session.
+    /// The session handle stays valid if a previous call panicked while holding the lock
+    fn session(&self) -> MutexGuard<'_, CK_SESSION_HANDLE> {
+        self.session.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Generate a key pair on the token, with the given id.
+    /// Return the public key
+    pub fn generate_key_pair(&self, key_type: Pkcs11KeyType, id: &[u8]) -> Result<Vec<u8>> {
+        let session = self.session();
+
+        let mut mechanism = CK_MECHANISM {
+            mechanism: key_type.generation_mechanism(),
+            pParameter: ptr::null_mut(),
+            ulParameterLen: 0,
+        };
+        let mut ec_params = key_type.ec_params().to_vec();
+        let mut id = id.to_vec();
+        let mut key_type_value = key_type.key_type();
+        let mut true_value = CK_TRUE;
+        let mut false_value = CK_FALSE;
+        let is_signing_key = key_type != Pkcs11KeyType::X25519;
+
+        let mut public_template = vec![
+            attribute(CKA_TOKEN, &mut true_value),
+            attribute_bytes(CKA_ID, &mut id),
+            attribute_bytes(CKA_EC_PARAMS, &mut ec_params),
+        ];
+        if is_signing_key {
+            public_template.push(attribute(CKA_VERIFY, &mut true_value));
+        }
+        let mut private_template = vec![
+            attribute(CKA_TOKEN, &mut true_value),
+            attribute(CKA_PRIVATE, &mut true_value),
+            attribute(CKA_SENSITIVE, &mut true_value),
+            attribute(CKA_EXTRACTABLE, &mut false_value),
+            attribute(CKA_KEY_TYPE, &mut key_type_value),
+            attribute_bytes(CKA_ID, &mut id),
+        ];
+        if is_signing_key {
+            private_template.push(attribute(CKA_SIGN, &mut true_value));
+        } else {
+            private_template.push(attribute(CKA_DERIVE, &mut true_value));
+        }
+
+        let mut public_key: CK_OBJECT_HANDLE = 0;
+        let mut private_key: CK_OBJECT_HANDLE = 0;
+        call!(
+            self,
+            C_GenerateKeyPair(
+                *session,
+                &mut mechanism,
+                public_template.as_mut_ptr(),
+                public_template.len() as CK_ULONG,
+                private_template.as_mut_ptr(),
+                private_template.len() as CK_ULONG,
+                &mut public_key,
+                &mut private_key
+            )
+        )?;
+
+        self.read_public_key(*session, public_key, key_type)
+    }
+
+    /// Return the public key of the key pair with the given id, if it exists
+    pub fn get_public_key(&self, key_type: Pkcs11KeyType, id: &[u8]) -> Result<Option<Vec<u8>>> {
+        let session = self.session();
+        match self.find_object(*session, CKO_PUBLIC_KEY, key_type, id)? {
+            Some(public_key) => Ok(Some(self.read_public_key(*session, public_key, key_type)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Return the id of the key pair with the given public key, if it exists
+    pub fn find_id(&self, key_type: Pkcs11KeyType, public_key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let session = self.session();
+
+        // Try both the DER-encoded and the raw forms of the point
+        let mut der = vec![0x04, public_key.len() as u8];
+        der.extend_from_slice(public_key);
+        for mut ec_point in [der, public_key.to_vec()] {
+            let mut class = CKO_PUBLIC_KEY;
+            let mut key_type = key_type.key_type();
+            let mut template = [
+                attribute(CKA_CLASS, &mut class),
+                attribute(CKA_KEY_TYPE, &mut key_type),
+                attribute_bytes(CKA_EC_POINT, &mut ec_point),
+            ];
+            if let Some(object) = self.find_object_with_template(*session, &mut template)? {
+                return Ok(Some(self.get_attribute(*session, object, CKA_ID)?));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Delete the key pair with the given id. Return false if it doesn't exist
+    pub fn delete_key_pair(&self, key_type: Pkcs11KeyType, id: &[u8]) -> Result<bool> {
+        let session = self.session();
+        let mut deleted = false;
+        for class in [CKO_PRIVATE_KEY, CKO_PUBLIC_KEY] {
+            if let Some(object) = self.find_object(*session, class, key_type, id)? {
+                call!(self, C_DestroyObject(*session, object))?;
+                deleted = true;
+            }
+        }
+        Ok(deleted)
+    }
+
+    /// Sign data with the private key with the given id.
+    /// ECDSA keys sign a SHA-256 digest of the data, EdDSA keys sign the data itself
+    pub fn sign(&self, key_type: Pkcs11KeyType, id: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+        let session = self.session();
+        let private_key = self
+            .find_object(*session, CKO_PRIVATE_KEY, key_type, id)?
+            .ok_or(Error::KeyNotFound)?;
+
+        let (mechanism, mut data) = match key_type {
+            Pkcs11KeyType::EdDSACurve25519 => (CKM_EDDSA, data.to_vec()),
+            Pkcs11KeyType::ECDSASHA256CurveP256 => {
+                use sha2::{Digest, Sha256};
+                (CKM_ECDSA, Sha256::digest(data).to_vec())
+            }
+            Pkcs11KeyType::X25519 => return Err(Error::UnsupportedKeyType)?,
+        };
+        let mut mechanism = CK_MECHANISM {
+            mechanism,
+            pParameter: ptr::null_mut(),
+            ulParameterLen: 0,
+        };
+        call!(self, C_SignInit(*session, &mut mechanism, private_key))?;
+
+        let mut signature = vec![0u8; 512];
+        let mut signature_length = signature.len() as CK_ULONG;
+        call!(
+            self,
+            C_Sign(
+                *session,
+                data.as_mut_ptr(),
+                data.len() as CK_ULONG,
+                signature.as_mut_ptr(),
+                &mut signature_length
+            )
+        )?;
+        signature.truncate(signature_length as usize);
+        Ok(signature)
+    }
+
+    /// Compute an X25519 shared secret between the private key with the given id
+    /// and a peer public key. The secret is derived on the token, then extracted.
+    ///
+    /// The extraction is needed because the Noise handshake of the secure channels mixes
+    /// the shared secret into its chaining key with HKDF-SHA256, which PKCS#11 v2.40 tokens
+    /// don't provide. The private key never leaves the token, the shared secret is derived
+    /// into a session object which is destroyed as soon as its value is read
+    pub fn x25519_ecdh(&self, id: &[u8], peer_public_key: &[u8]) -> Result<Vec<u8>> {
+        let session = self.session();
+        let private_key = self
+            .find_object(*session, CKO_PRIVATE_KEY, Pkcs11KeyType::X25519, id)?
+            .ok_or(Error::KeyNotFound)?;
+
+        let mut peer_public_key = peer_public_key.to_vec();
+        let mut params = CK_ECDH1_DERIVE_PARAMS {
+            kdf: CKD_NULL,
+            ulSharedDataLen: 0,
+            pSharedData: ptr::null_mut(),
+            ulPublicDataLen: peer_public_key.len() as CK_ULONG,
+            pPublicData: peer_public_key.as_mut_ptr(),
+        };
+        let mut mechanism = CK_MECHANISM {
+            mechanism: CKM_ECDH1_DERIVE,
+            pParameter: ptr::addr_of_mut!(params).cast::<c_void>(),
+            ulParameterLen: size_of::<CK_ECDH1_DERIVE_PARAMS>() as CK_ULONG,
+        };
+
+        let mut class = CKO_SECRET_KEY;
+        let mut key_type = CKK_GENERIC_SECRET;
+        let mut value_length: CK_ULONG = 32;
+        let mut true_value = CK_TRUE;
+        let mut false_value = CK_FALSE;
+        // the value of the secret can only be read if it is neither sensitive nor unextractable
+        let mut template = [
+            attribute(CKA_CLASS, &mut class),
+            attribute(CKA_KEY_TYPE, &mut key_type),
+            attribute(CKA_VALUE_LEN, &mut value_length),
+            attribute(CKA_TOKEN, &mut false_value),
+            attribute(CKA_SENSITIVE, &mut false_value),
+            attribute(CKA_EXTRACTABLE, &mut true_value),
+        ];
+
+        let mut shared_secret: CK_OBJECT_HANDLE = 0;
+        call!(
+            self,
+            C_DeriveKey(
+                *session,
+                &mut mechanism,
+                private_key,
+                template.as_mut_ptr(),
+                template.len() as CK_ULONG,
+                &mut shared_secret
+            )
+        )?;
+
+        let value = self.get_attribute(*session, shared_secret, CKA_VALUE);
+        if let Err(e) = call!(self, C_DestroyObject(*session, shared_secret)) {
+            warn!("cannot delete the derived secret from the PKCS#11 token: {e}");
+        }
+        let value = value?;
+        if value.len() != 32 {
+            return Err(Error::InvalidSharedSecret)?;
+        }
+        Ok(value)
+    }
+}
+
+impl Pkcs11Client {
+    fn find_slot(&self, token_label: Option<&str>) -> Result<CK_SLOT_ID> {
+        let mut count: CK_ULONG = 0;
+        call!(self, C_GetSlotList(CK_TRUE, ptr::null_mut(), &mut count))?;
+        let mut slots = vec![CK_SLOT_ID::default(); count as usize];
+        call!(self, C_GetSlotList(CK_TRUE, slots.as_mut_ptr(), &mut count))?;
+        slots.truncate(count as usize);
+
+        let token_label = match token_label {
+            Some(token_label) => token_label,
+            None => return slots.first().copied().ok_or(Error::TokenNotFound.into()),
+        };
+
+        for slot in slots {
+            let mut info: CK_TOKEN_INFO = unsafe { core::mem::zeroed() };
+            call!(self, C_GetTokenInfo(slot, &mut info))?;
+            // Token labels are padded with spaces
+            let label = info.label;
+            if String::from_utf8_lossy(&label).trim_end() == token_label {
+                return Ok(slot);
+            }
+        }
+        Err(Error::TokenNotFound)?
+    }
+
+    fn find_object(
+        &self,
+        session: CK_SESSION_HANDLE,
+        class: CK_OBJECT_CLASS,
+        key_type: Pkcs11KeyType,
+        id: &[u8],
+    ) -> Result<Option<CK_OBJECT_HANDLE>> {
+        let mut class = class;
+        let mut key_type = key_type.key_type();
+        let mut id = id.to_vec();
+        let mut template = [
+            attribute(CKA_CLASS, &mut class),
+            attribute(CKA_KEY_TYPE, &mut key_type),
+            attribute_bytes(CKA_ID, &mut id),
+        ];
+        self.find_object_with_template(session, &mut template)
+    }
+
+    fn find_object_with_template(
+        &self,
+        session: CK_SESSION_HANDLE,
+        template: &mut [CK_ATTRIBUTE],
+    ) -> Result<Option<CK_OBJECT_HANDLE>> {
+        call!(
+            self,
+            C_FindObjectsInit(session, template.as_mut_ptr(), template.len() as CK_ULONG)
+        )?;
+
+        let mut object: CK_OBJECT_HANDLE = 0;
+        let mut count: CK_ULONG = 0;
+        let result = call!(self, C_FindObjects(session, &mut object, 1, &mut count));
+        call!(self, C_FindObjectsFinal(session))?;
+        result?;
+
+        Ok(if count == 0 { None } else { Some(object) })
+    }
+
+    fn get_attribute(
+        &self,
+        session: CK_SESSION_HANDLE,
+        object: CK_OBJECT_HANDLE,
+        attribute_type: CK_ATTRIBUTE_TYPE,
+    ) -> Result<Vec<u8>> {
+        let mut template = [CK_ATTRIBUTE {
+            type_: attribute_type,
+            pValue: ptr::null_mut(),
+            ulValueLen: 0,
+        }];
+        call!(
+            self,
+            C_GetAttributeValue(session, object, template.as_mut_ptr(), 1)
+        )?;
+
+        let mut value = vec![0u8; template[0].ulValueLen as usize];
+        template[0].pValue = value.as_mut_ptr() as *mut c_void;
+        call!(
+            self,
+            C_GetAttributeValue(session, object, template.as_mut_ptr(), 1)
+        )?;
+        value.truncate(template[0].ulValueLen as usize);
+        Ok(value)
+    }
+
+    fn read_public_key(
+        &self,
+        session: CK_SESSION_HANDLE,
+        public_key: CK_OBJECT_HANDLE,
+        key_type: Pkcs11KeyType,
+    ) -> Result<Vec<u8>> {
+        let ec_point = self.get_attribute(session, public_key, CKA_EC_POINT)?;
+        Ok(decode_ec_point(&ec_point, key_type.public_key_length())?)
+    }
+}
+
+impl Drop for Pkcs11Client {
+    fn drop(&mut self) {
+        let session = *self.session();
+        if session != 0 {
+            if let Err(e) = call!(self, C_CloseSession(session)) {
+                warn!("cannot close the PKCS#11 session: {e}");
+            }
+        }
+    }
+}
+
+/// CKA_EC_POINT is usually a DER-encoded OCTET STRING, but some tokens return the raw point
+fn decode_ec_point(ec_point: &[u8], length: usize) -> core::result::Result<Vec<u8>, Error> {
+    if ec_point.len() == length {
+        Ok(ec_point.to_vec())
+    } else if ec_point.len() == length + 2 && ec_point[0] == 0x04 && ec_point[1] as usize == length
+    {
+        Ok(ec_point[2..].to_vec())
+    } else {
+        Err(Error::InvalidPublicKey)
+    }
+}
+
+fn attribute<T>(attribute_type: CK_ATTRIBUTE_TYPE, value: &mut T) -> CK_ATTRIBUTE {
+    let value: *mut T = value;
+    CK_ATTRIBUTE {
+        type_: attribute_type,
+        pValue: value.cast::<c_void>(),
+        ulValueLen: size_of::<T>() as CK_ULONG,
+    }
+}
+
+fn attribute_bytes(attribute_type: CK_ATTRIBUTE_TYPE, value: &mut [u8]) -> CK_ATTRIBUTE {
+    CK_ATTRIBUTE {
+        type_: attribute_type,
+        pValue: value.as_mut_ptr() as *mut c_void,
+        ulValueLen: value.len() as CK_ULONG,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_ec_point() {
+        let raw = [1u8; 32];
+        assert_eq!(decode_ec_point(&raw, 32).unwrap(), raw.to_vec());
+
+        let mut der = vec![0x04, 32];
+        der.extend_from_slice(&raw);
+        assert_eq!(decode_ec_point(&der, 32).unwrap(), raw.to_vec());
+
+        assert!(decode_ec_point(&der[..20], 32).is_err());
+    }
+}
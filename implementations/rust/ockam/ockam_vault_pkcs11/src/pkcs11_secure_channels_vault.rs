@@ -0,0 +1,272 @@
+use crate::error::Error;
+use crate::pkcs11_client::{Pkcs11Client, Pkcs11Config, Pkcs11KeyType};
+use ockam_core::compat::rand::random;
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::{async_trait, Result};
+use ockam_vault::{
+    AeadSecretKeyHandle, HKDFNumberOfOutputs, HandleToSecret, HashOutput, HkdfOutput,
+    MLKEM768Ciphertext, MLKEM768DecapsulationKeyHandle, MLKEM768EncapsulationKey,
+    SecretBufferHandle, VaultForSecureChannels, X25519PublicKey, X25519SecretKeyHandle,
+};
+
+/// Secure channel vault implementation using a PKCS#11 token.
+///
+/// Static X25519 keys are generated on the token and the ECDH operation is performed
+/// by the token. The shared secret is then imported in the other vault, see
+/// [`Pkcs11Client::x25519_ecdh`]. Every other operation (ephemeral keys, hashing, HKDF,
+/// AEAD, ML-KEM) is delegated to another vault, since tokens generally don't support them
+pub struct Pkcs11VaultForSecureChannels {
+    client: Arc<Pkcs11Client>,
+    vault: Arc<dyn VaultForSecureChannels>,
+}
+
+impl Pkcs11VaultForSecureChannels {
+    /// Create a vault from an existing client and a vault used for the operations
+    /// which are not supported by the token
+    pub fn new(client: Arc<Pkcs11Client>, vault: Arc<dyn VaultForSecureChannels>) -> Self {
+        Self { client, vault }
+    }
+
+    /// Create a vault by opening a session on the configured token
+    pub async fn create(
+        config: Pkcs11Config,
+        vault: Arc<dyn VaultForSecureChannels>,
+    ) -> Result<Self> {
+        Ok(Self::new(Pkcs11Client::create(config).await?, vault))
+    }
+
+    /// Return the public key of a static key if that key is stored on the token
+    async fn get_token_public_key(
+        &self,
+        secret_key_handle: &X25519SecretKeyHandle,
+    ) -> Result<Option<X25519PublicKey>> {
+        let id = secret_key_handle.0.value().to_vec();
+        match self
+            .client
+            .run(move |client| client.get_public_key(Pkcs11KeyType::X25519, &id))
+            .await?
+        {
+            Some(public_key) => Ok(Some(X25519PublicKey(
+                public_key.try_into().map_err(|_| Error::InvalidPublicKey)?,
+            ))),
+            None => Ok(None),
+        }
+    }
+}
+
+#[async_trait]
+impl VaultForSecureChannels for Pkcs11VaultForSecureChannels {
+    async fn x25519_ecdh(
+        &self,
+        secret_key_handle: &X25519SecretKeyHandle,
+        peer_public_key: &X25519PublicKey,
+    ) -> Result<SecretBufferHandle> {
+        if self
+            .get_token_public_key(secret_key_handle)
+            .await?
+            .is_none()
+        {
+            return self
+                .vault
+                .x25519_ecdh(secret_key_handle, peer_public_key)
+                .await;
+        }
+
+        let id = secret_key_handle.0.value().to_vec();
+        let peer_public_key = peer_public_key.0;
+        let shared_secret = self
+            .client
+            .run(move |client| client.x25519_ecdh(&id, &peer_public_key))
+            .await?;
+        if shared_secret.len() != 32 {
+            return Err(Error::InvalidSharedSecret)?;
+        }
+        self.vault.import_secret_buffer(shared_secret).await
+    }
+
+    async fn hash(&self, data: &[u8]) -> Result<HashOutput> {
+        self.vault.hash(data).await
+    }
+
+    async fn hkdf(
+        &self,
+        salt: &SecretBufferHandle,
+        input_key_material: Option<&SecretBufferHandle>,
+        number_of_outputs: HKDFNumberOfOutputs,
+    ) -> Result<HkdfOutput> {
+        self.vault
+            .hkdf(salt, input_key_material, number_of_outputs)
+            .await
+    }
+
+    async fn aead_encrypt(
+        &self,
+        secret_key_handle: &AeadSecretKeyHandle,
+        plain_text: &mut [u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<()> {
+        self.vault
+            .aead_encrypt(secret_key_handle, plain_text, nonce, aad)
+            .await
+    }
+
+    async fn aead_decrypt<'a>(
+        &self,
+        secret_key_handle: &AeadSecretKeyHandle,
+        cipher_text: &'a mut [u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<&'a mut [u8]> {
+        self.vault
+            .aead_decrypt(secret_key_handle, cipher_text, nonce, aad)
+            .await
+    }
+
+    async fn persist_aead_key(&self, secret_key_handle: &AeadSecretKeyHandle) -> Result<()> {
+        self.vault.persist_aead_key(secret_key_handle).await
+    }
+
+    async fn load_aead_key(&self, secret_key_handle: &AeadSecretKeyHandle) -> Result<()> {
+        self.vault.load_aead_key(secret_key_handle).await
+    }
+
+    async fn delete_persisted_aead_key(
+        &self,
+        secret_key_handle: &AeadSecretKeyHandle,
+    ) -> Result<bool> {
+        self.vault
+            .delete_persisted_aead_key(secret_key_handle)
+            .await
+    }
+
+    async fn generate_static_x25519_secret_key(&self) -> Result<X25519SecretKeyHandle> {
+        let id: [u8; 16] = random();
+        self.client
+            .run(move |client| client.generate_key_pair(Pkcs11KeyType::X25519, &id))
+            .await?;
+        Ok(X25519SecretKeyHandle(HandleToSecret::new(id.to_vec())))
+    }
+
+    async fn delete_static_x25519_secret_key(
+        &self,
+        secret_key_handle: X25519SecretKeyHandle,
+    ) -> Result<bool> {
+        let id = secret_key_handle.0.value().to_vec();
+        if self
+            .client
+            .run(move |client| client.delete_key_pair(Pkcs11KeyType::X25519, &id))
+            .await?
+        {
+            return Ok(true);
+        }
+        self.vault
+            .delete_static_x25519_secret_key(secret_key_handle)
+            .await
+    }
+
+    async fn generate_ephemeral_x25519_secret_key(&self) -> Result<X25519SecretKeyHandle> {
+        self.vault.generate_ephemeral_x25519_secret_key().await
+    }
+
+    async fn delete_ephemeral_x25519_secret_key(
+        &self,
+        secret_key_handle: X25519SecretKeyHandle,
+    ) -> Result<bool> {
+        self.vault
+            .delete_ephemeral_x25519_secret_key(secret_key_handle)
+            .await
+    }
+
+    async fn get_x25519_public_key(
+        &self,
+        secret_key_handle: &X25519SecretKeyHandle,
+    ) -> Result<X25519PublicKey> {
+        match self.get_token_public_key(secret_key_handle).await? {
+            Some(public_key) => Ok(public_key),
+            None => self.vault.get_x25519_public_key(secret_key_handle).await,
+        }
+    }
+
+    async fn get_x25519_secret_key_handle(
+        &self,
+        public_key: &X25519PublicKey,
+    ) -> Result<X25519SecretKeyHandle> {
+        let token_public_key = public_key.0;
+        match self
+            .client
+            .run(move |client| client.find_id(Pkcs11KeyType::X25519, &token_public_key))
+            .await?
+        {
+            Some(id) => Ok(X25519SecretKeyHandle(HandleToSecret::new(id))),
+            None => self.vault.get_x25519_secret_key_handle(public_key).await,
+        }
+    }
+
+    async fn import_secret_buffer(&self, buffer: Vec<u8>) -> Result<SecretBufferHandle> {
+        self.vault.import_secret_buffer(buffer).await
+    }
+
+    async fn delete_secret_buffer(&self, secret_buffer_handle: SecretBufferHandle) -> Result<bool> {
+        self.vault.delete_secret_buffer(secret_buffer_handle).await
+    }
+
+    async fn convert_secret_buffer_to_aead_key(
+        &self,
+        secret_buffer_handle: SecretBufferHandle,
+    ) -> Result<AeadSecretKeyHandle> {
+        self.vault
+            .convert_secret_buffer_to_aead_key(secret_buffer_handle)
+            .await
+    }
+
+    async fn delete_aead_secret_key(&self, secret_key_handle: AeadSecretKeyHandle) -> Result<bool> {
+        self.vault.delete_aead_secret_key(secret_key_handle).await
+    }
+
+    async fn generate_ephemeral_ml_kem_768_decapsulation_key(
+        &self,
+    ) -> Result<MLKEM768DecapsulationKeyHandle> {
+        self.vault
+            .generate_ephemeral_ml_kem_768_decapsulation_key()
+            .await
+    }
+
+    async fn delete_ephemeral_ml_kem_768_decapsulation_key(
+        &self,
+        decapsulation_key_handle: MLKEM768DecapsulationKeyHandle,
+    ) -> Result<bool> {
+        self.vault
+            .delete_ephemeral_ml_kem_768_decapsulation_key(decapsulation_key_handle)
+            .await
+    }
+
+    async fn get_ml_kem_768_encapsulation_key(
+        &self,
+        decapsulation_key_handle: &MLKEM768DecapsulationKeyHandle,
+    ) -> Result<MLKEM768EncapsulationKey> {
+        self.vault
+            .get_ml_kem_768_encapsulation_key(decapsulation_key_handle)
+            .await
+    }
+
+    async fn ml_kem_768_encapsulate(
+        &self,
+        peer_encapsulation_key: &MLKEM768EncapsulationKey,
+    ) -> Result<(MLKEM768Ciphertext, SecretBufferHandle)> {
+        self.vault
+            .ml_kem_768_encapsulate(peer_encapsulation_key)
+            .await
+    }
+
+    async fn ml_kem_768_decapsulate(
+        &self,
+        decapsulation_key_handle: &MLKEM768DecapsulationKeyHandle,
+        ciphertext: &MLKEM768Ciphertext,
+    ) -> Result<SecretBufferHandle> {
+        self.vault
+            .ml_kem_768_decapsulate(decapsulation_key_handle, ciphertext)
+            .await
+    }
+}
@@ -0,0 +1,156 @@
+use crate::error::Error;
+use crate::pkcs11_client::{Pkcs11Client, Pkcs11Config, Pkcs11KeyType};
+use ockam_core::compat::rand::random;
+use ockam_core::compat::sync::Arc;
+use ockam_core::{async_trait, Result};
+use ockam_vault::{
+    ECDSASHA256CurveP256PublicKey, ECDSASHA256CurveP256Signature, EdDSACurve25519PublicKey,
+    EdDSACurve25519Signature, HandleToSecret, Signature, SigningKeyType, SigningSecretKeyHandle,
+    VaultForSigning, VerifyingPublicKey,
+};
+
+/// Signing vault implementation using a PKCS#11 token.
+/// The private keys are generated on the token and never leave it
+pub struct Pkcs11VaultForSigning {
+    client: Arc<Pkcs11Client>,
+}
+
+impl Pkcs11VaultForSigning {
+    /// Create a vault from an existing client
+    pub fn new(client: Arc<Pkcs11Client>) -> Self {
+        Self { client }
+    }
+
+    /// Create a vault by opening a session on the configured token
+    pub async fn create(config: Pkcs11Config) -> Result<Self> {
+        Ok(Self::new(Pkcs11Client::create(config).await?))
+    }
+
+    fn key_type_and_id(handle: &SigningSecretKeyHandle) -> (Pkcs11KeyType, Vec<u8>) {
+        match handle {
+            SigningSecretKeyHandle::EdDSACurve25519(handle) => {
+                (Pkcs11KeyType::EdDSACurve25519, handle.value().to_vec())
+            }
+            SigningSecretKeyHandle::ECDSASHA256CurveP256(handle) => {
+                (Pkcs11KeyType::ECDSASHA256CurveP256, handle.value().to_vec())
+            }
+        }
+    }
+
+    fn make_verifying_public_key(
+        key_type: Pkcs11KeyType,
+        public_key: Vec<u8>,
+    ) -> Result<VerifyingPublicKey> {
+        let public_key = match key_type {
+            Pkcs11KeyType::EdDSACurve25519 => {
+                VerifyingPublicKey::EdDSACurve25519(EdDSACurve25519PublicKey(
+                    public_key.try_into().map_err(|_| Error::InvalidPublicKey)?,
+                ))
+            }
+            Pkcs11KeyType::ECDSASHA256CurveP256 => {
+                VerifyingPublicKey::ECDSASHA256CurveP256(ECDSASHA256CurveP256PublicKey(
+                    public_key.try_into().map_err(|_| Error::InvalidPublicKey)?,
+                ))
+            }
+            Pkcs11KeyType::X25519 => return Err(Error::UnsupportedKeyType)?,
+        };
+        Ok(public_key)
+    }
+}
+
+#[async_trait]
+impl VaultForSigning for Pkcs11VaultForSigning {
+    async fn sign(
+        &self,
+        signing_secret_key_handle: &SigningSecretKeyHandle,
+        data: &[u8],
+    ) -> Result<Signature> {
+        let (key_type, id) = Self::key_type_and_id(signing_secret_key_handle);
+        let data = data.to_vec();
+        let signature: [u8; 64] = self
+            .client
+            .run(move |client| client.sign(key_type, &id, &data))
+            .await?
+            .try_into()
+            .map_err(|_| Error::InvalidSignature)?;
+
+        Ok(match signing_secret_key_handle {
+            SigningSecretKeyHandle::EdDSACurve25519(_) => {
+                Signature::EdDSACurve25519(EdDSACurve25519Signature(signature))
+            }
+            SigningSecretKeyHandle::ECDSASHA256CurveP256(_) => {
+                Signature::ECDSASHA256CurveP256(ECDSASHA256CurveP256Signature(signature))
+            }
+        })
+    }
+
+    async fn generate_signing_secret_key(
+        &self,
+        signing_key_type: SigningKeyType,
+    ) -> Result<SigningSecretKeyHandle> {
+        let id: [u8; 16] = random();
+        let handle = HandleToSecret::new(id.to_vec());
+        let (key_type, handle) = match signing_key_type {
+            SigningKeyType::EdDSACurve25519 => (
+                Pkcs11KeyType::EdDSACurve25519,
+                SigningSecretKeyHandle::EdDSACurve25519(handle),
+            ),
+            SigningKeyType::ECDSASHA256CurveP256 => (
+                Pkcs11KeyType::ECDSASHA256CurveP256,
+                SigningSecretKeyHandle::ECDSASHA256CurveP256(handle),
+            ),
+        };
+        self.client
+            .run(move |client| client.generate_key_pair(key_type, &id))
+            .await?;
+        Ok(handle)
+    }
+
+    async fn get_verifying_public_key(
+        &self,
+        signing_secret_key_handle: &SigningSecretKeyHandle,
+    ) -> Result<VerifyingPublicKey> {
+        let (key_type, id) = Self::key_type_and_id(signing_secret_key_handle);
+        let public_key = self
+            .client
+            .run(move |client| client.get_public_key(key_type, &id))
+            .await?
+            .ok_or(Error::KeyNotFound)?;
+        Self::make_verifying_public_key(key_type, public_key)
+    }
+
+    async fn get_secret_key_handle(
+        &self,
+        verifying_public_key: &VerifyingPublicKey,
+    ) -> Result<SigningSecretKeyHandle> {
+        let (key_type, public_key) = match verifying_public_key {
+            VerifyingPublicKey::EdDSACurve25519(public_key) => {
+                (Pkcs11KeyType::EdDSACurve25519, public_key.0.to_vec())
+            }
+            VerifyingPublicKey::ECDSASHA256CurveP256(public_key) => {
+                (Pkcs11KeyType::ECDSASHA256CurveP256, public_key.0.to_vec())
+            }
+        };
+        let id = self
+            .client
+            .run(move |client| client.find_id(key_type, &public_key))
+            .await?
+            .ok_or(Error::KeyNotFound)?;
+        let handle = HandleToSecret::new(id);
+
+        Ok(match key_type {
+            Pkcs11KeyType::EdDSACurve25519 => SigningSecretKeyHandle::EdDSACurve25519(handle),
+            _ => SigningSecretKeyHandle::ECDSASHA256CurveP256(handle),
+        })
+    }
+
+    async fn delete_signing_secret_key(
+        &self,
+        signing_secret_key_handle: SigningSecretKeyHandle,
+    ) -> Result<bool> {
+        let (key_type, id) = Self::key_type_and_id(&signing_secret_key_handle);
+        self.client
+            .run(move |client| client.delete_key_pair(key_type, &id))
+            .await
+    }
+}
@@ -0,0 +1,116 @@
+use ockam_core::Result;
+use ockam_vault::{
+    HKDFNumberOfOutputs, SigningKeyType, SoftwareVaultForSecureChannels,
+    SoftwareVaultForVerifyingSignatures, VaultForSecureChannels, VaultForSigning,
+    VaultForVerifyingSignatures,
+};
+use ockam_vault_pkcs11::{Pkcs11Config, Pkcs11VaultForSecureChannels, Pkcs11VaultForSigning};
+
+/// These tests need to be executed with the following environment variables
+/// OCKAM_PKCS11_MODULE: path to the PKCS#11 module, for example /usr/lib/softhsm/libsofthsm2.so
+/// OCKAM_PKCS11_TOKEN_LABEL: label of an initialized token
+/// OCKAM_PKCS11_PIN: user PIN of that token
+///
+/// A SoftHSM2 token can be initialized with:
+/// softhsm2-util --init-token --free --label ockam --so-pin 1234 --pin 1234
+fn config() -> Pkcs11Config {
+    let module = std::env::var("OCKAM_PKCS11_MODULE").unwrap();
+    let mut config = Pkcs11Config::new(module);
+    if let Ok(token_label) = std::env::var("OCKAM_PKCS11_TOKEN_LABEL") {
+        config = config.with_token_label(token_label);
+    }
+    if let Ok(pin) = std::env::var("OCKAM_PKCS11_PIN") {
+        config = config.with_pin(pin);
+    }
+    config
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_sign_verify() -> Result<()> {
+    let signing_vault = Pkcs11VaultForSigning::create(config()).await?;
+    let verifier = SoftwareVaultForVerifyingSignatures::new();
+
+    for key_type in [
+        SigningKeyType::EdDSACurve25519,
+        SigningKeyType::ECDSASHA256CurveP256,
+    ] {
+        let handle = signing_vault.generate_signing_secret_key(key_type).await?;
+        let message = b"hello world";
+        let signature = signing_vault.sign(&handle, message.as_slice()).await?;
+        let public_key = signing_vault.get_verifying_public_key(&handle).await?;
+
+        assert!(
+            verifier
+                .verify_signature(&public_key, message, &signature)
+                .await?
+        );
+
+        let handle2 = signing_vault.get_secret_key_handle(&public_key).await?;
+        assert_eq!(handle, handle2);
+
+        assert!(signing_vault.delete_signing_secret_key(handle).await?);
+        assert!(signing_vault
+            .get_verifying_public_key(&handle2)
+            .await
+            .is_err());
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_x25519_ecdh() -> Result<()> {
+    let software_vault = SoftwareVaultForSecureChannels::create().await?;
+    let vault = Pkcs11VaultForSecureChannels::create(config(), software_vault.clone()).await?;
+
+    let static_key = vault.generate_static_x25519_secret_key().await?;
+    let static_public_key = vault.get_x25519_public_key(&static_key).await?;
+    assert_eq!(
+        vault
+            .get_x25519_secret_key_handle(&static_public_key)
+            .await?,
+        static_key
+    );
+
+    let ephemeral_key = vault.generate_ephemeral_x25519_secret_key().await?;
+    let ephemeral_public_key = vault.get_x25519_public_key(&ephemeral_key).await?;
+
+    let shared1 = vault
+        .x25519_ecdh(&static_key, &ephemeral_public_key)
+        .await?;
+    let shared2 = vault
+        .x25519_ecdh(&ephemeral_key, &static_public_key)
+        .await?;
+
+    // both shared secrets must derive the same AEAD key
+    let hkdf1 = vault.hkdf(&shared1, None, HKDFNumberOfOutputs::Two).await?;
+    let hkdf2 = vault.hkdf(&shared2, None, HKDFNumberOfOutputs::Two).await?;
+
+    let aead1 = vault
+        .convert_secret_buffer_to_aead_key(hkdf1.0 .0.into_iter().next().unwrap())
+        .await?;
+    let aead2 = vault
+        .convert_secret_buffer_to_aead_key(hkdf2.0 .0.into_iter().next().unwrap())
+        .await?;
+    let mut message = b"hello world".to_vec();
+    message.extend_from_slice(&[0u8; 16]);
+    let nonce = [0u8; 12];
+    vault
+        .aead_encrypt(&aead1, &mut message, &nonce, &[])
+        .await?;
+    let plain_text = vault
+        .aead_decrypt(&aead2, &mut message, &nonce, &[])
+        .await?;
+    assert_eq!(plain_text, b"hello world");
+
+    assert!(vault.delete_static_x25519_secret_key(static_key).await?);
+    assert!(
+        vault
+            .delete_ephemeral_x25519_secret_key(ephemeral_key)
+            .await?
+    );
+
+    Ok(())
+}